@@ -123,7 +123,7 @@ pub fn maybe_parse_apply_patch(argv: &[String]) -> MaybeApplyPatch {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ApplyPatchFileChange {
     Add {
         content: String,
@@ -179,6 +179,25 @@ impl ApplyPatchAction {
         &self.changes
     }
 
+    /// A copy of this action without the changes to `paths` (absolute, as
+    /// keyed in [`Self::changes`]). The patch text is rebuilt to match, so
+    /// the result can be executed like the original.
+    pub fn without_paths(&self, paths: &[PathBuf]) -> Self {
+        let changes = self
+            .changes
+            .iter()
+            .filter(|(path, _)| !paths.contains(path))
+            .map(|(path, change)| (path.clone(), change.clone()))
+            .collect();
+        let patch =
+            parser::filter_patch_files(&self.patch, |path| !paths.contains(&self.cwd.join(path)));
+        Self {
+            changes,
+            patch,
+            cwd: self.cwd.clone(),
+        }
+    }
+
     /// Should be used exclusively for testing. (Not worth the overhead of
     /// creating a feature flag for this.)
     pub fn new_add_for_test(path: &Path, content: String) -> Self {
@@ -1621,4 +1640,35 @@ g
         let result = apply_patch(&patch, &mut stdout, &mut stderr);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn without_paths_drops_the_excluded_file_sections() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("lib.rs"), "old\n").unwrap();
+        let patch = wrap_patch(
+            "*** Add File: tests/new.rs\n+fn t() {}\n*** Update File: lib.rs\n@@\n-old\n+new\n*** Delete File: tests/gone.rs",
+        );
+        fs::create_dir(dir.path().join("tests")).unwrap();
+        fs::write(dir.path().join("tests/gone.rs"), "bye\n").unwrap();
+        let argv = strs_to_strings(&["apply_patch", &patch]);
+        let MaybeApplyPatchVerified::Body(action) =
+            maybe_parse_apply_patch_verified(&argv, dir.path())
+        else {
+            panic!("expected a verified patch");
+        };
+
+        let filtered = action.without_paths(&[
+            dir.path().join("tests/new.rs"),
+            dir.path().join("tests/gone.rs"),
+        ]);
+
+        assert_eq!(
+            filtered.patch,
+            "*** Begin Patch\n*** Update File: lib.rs\n@@\n-old\n+new\n*** End Patch"
+        );
+        assert_eq!(
+            filtered.changes().keys().collect::<Vec<_>>(),
+            vec![&dir.path().join("lib.rs")]
+        );
+    }
 }
@@ -112,6 +112,32 @@ pub fn parse_patch(patch: &str) -> Result<ApplyPatchArgs, ParseError> {
     parse_patch_text(patch, mode)
 }
 
+/// Rebuild `patch` without the file sections whose header path `keep`
+/// rejects. Everything outside a rejected section, including the begin/end
+/// markers and line endings, is preserved verbatim.
+pub(crate) fn filter_patch_files(patch: &str, keep: impl Fn(&str) -> bool) -> String {
+    let mut filtered = String::with_capacity(patch.len());
+    let mut keeping = true;
+    for line in patch.split_inclusive('\n') {
+        // Diff lines start with '+', '-' or ' ' and are never headers.
+        if !line.starts_with(['+', '-', ' ']) {
+            let trimmed = line.trim();
+            let header_path = [ADD_FILE_MARKER, DELETE_FILE_MARKER, UPDATE_FILE_MARKER]
+                .iter()
+                .find_map(|marker| trimmed.strip_prefix(marker));
+            if let Some(path) = header_path {
+                keeping = keep(path);
+            } else if trimmed == END_PATCH_MARKER {
+                keeping = true;
+            }
+        }
+        if keeping {
+            filtered.push_str(line);
+        }
+    }
+    filtered
+}
+
 enum ParseMode {
     /// Parse the patch text argument as is.
     Strict,
@@ -124,7 +124,8 @@ fn load_config_or_exit(cli_config_overrides: CliConfigOverrides) -> Config {
     }
 }
 
-fn safe_format_key(key: &str) -> String {
+/// Mask an API key for display, keeping only its first and last characters.
+pub fn safe_format_key(key: &str) -> String {
     if key.len() <= 13 {
         return "***".to_string();
     }
@@ -21,8 +21,10 @@ use std::path::PathBuf;
 use supports_color::Stream;
 
 mod mcp_cmd;
+mod omnara_cmd;
 
 use crate::mcp_cmd::McpCli;
+use crate::omnara_cmd::OmnaraCli;
 use crate::proto::ProtoCli;
 
 /// Codex CLI
@@ -65,6 +67,9 @@ enum Subcommand {
     /// [experimental] Run Codex as an MCP server and manage MCP servers.
     Mcp(McpCli),
 
+    /// Manage the Omnara API key stored in the OS keychain.
+    Omnara(OmnaraCli),
+
     /// Run the Protocol stream via stdin/stdout
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
@@ -229,6 +234,13 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut mcp_cli.config_overrides, root_config_overrides.clone());
             mcp_cli.run(codex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Omnara(mut omnara_cli)) => {
+            prepend_config_flags(
+                &mut omnara_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            omnara_cli.run().await?;
+        }
         Some(Subcommand::Resume(ResumeCommand {
             session_id,
             last,
@@ -150,6 +150,7 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
         env: env_map,
         startup_timeout_sec: None,
         tool_timeout_sec: None,
+        require_approval: false,
     };
 
     servers.insert(name.clone(), new_entry);
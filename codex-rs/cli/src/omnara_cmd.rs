@@ -0,0 +1,174 @@
+use std::io::IsTerminal;
+use std::io::Read;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use codex_cli::login::safe_format_key;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::omnara_client::OmnaraClient;
+use codex_core::omnara_keychain;
+use codex_core::omnara_workspace::select_workspace;
+
+/// Manage the Omnara API key Codex uses to mirror sessions.
+///
+/// Subcommands:
+/// - `login`  — store an API key in the OS keychain
+/// - `logout` — remove the stored key
+/// - `status` — show which workspace and key the current directory uses
+/// - `doctor` — check the server, key, sessions, polling, and clock
+#[derive(Debug, clap::Parser)]
+pub struct OmnaraCli {
+    #[clap(flatten)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: OmnaraSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum OmnaraSubcommand {
+    /// Store an Omnara API key in the OS keychain.
+    Login(LoginArgs),
+
+    /// Remove the Omnara API key from the OS keychain.
+    Logout,
+
+    /// Show whether an Omnara API key is configured for the current directory
+    /// and where it comes from.
+    Status,
+
+    /// Check the Omnara server, API key, session creation, polling, and clock.
+    Doctor,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct LoginArgs {
+    /// API key to store. Read from stdin when omitted.
+    #[arg(long = "api-key", value_name = "API_KEY")]
+    pub api_key: Option<String>,
+}
+
+impl OmnaraCli {
+    pub async fn run(self) -> Result<()> {
+        let OmnaraCli {
+            config_overrides,
+            cmd,
+        } = self;
+        // Only `status` and `doctor` read the config; the others just
+        // validate overrides.
+        let cli_overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+        let load_config = || {
+            Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())
+                .context("failed to load the configuration")
+        };
+
+        match cmd {
+            OmnaraSubcommand::Login(args) => run_login(args),
+            OmnaraSubcommand::Logout => run_logout(),
+            OmnaraSubcommand::Status => run_status(&load_config()?),
+            OmnaraSubcommand::Doctor => run_doctor(&load_config()?).await,
+        }
+    }
+}
+
+fn run_login(args: LoginArgs) -> Result<()> {
+    let api_key = match args.api_key {
+        Some(key) => key,
+        None => read_api_key_from_stdin()?,
+    };
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        bail!("no API key provided");
+    }
+
+    omnara_keychain::store_api_key(api_key).context("failed to store the Omnara API key")?;
+    eprintln!(
+        "Stored Omnara API key {} in the OS keychain",
+        safe_format_key(api_key)
+    );
+    if std::env::var_os("OMNARA_API_KEY").is_some() {
+        eprintln!("Note: OMNARA_API_KEY is set and takes precedence over the stored key");
+    }
+    Ok(())
+}
+
+fn read_api_key_from_stdin() -> Result<String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("Paste your Omnara API key and press Enter, then Ctrl-D:");
+    }
+    let mut key = String::new();
+    stdin
+        .read_to_string(&mut key)
+        .context("failed to read the API key from stdin")?;
+    Ok(key)
+}
+
+fn run_logout() -> Result<()> {
+    if omnara_keychain::delete_api_key().context("failed to remove the Omnara API key")? {
+        eprintln!("Removed the Omnara API key from the OS keychain");
+    } else {
+        eprintln!("No Omnara API key stored in the OS keychain");
+    }
+    Ok(())
+}
+
+fn run_status(config: &Config) -> Result<()> {
+    let workspace = select_workspace(&config.omnara.workspaces, &config.cwd)
+        .context("failed to select the Omnara workspace")?;
+    if let Some(workspace) = workspace {
+        let env = &workspace.workspace.api_key_env;
+        match workspace.api_key() {
+            Some(key) => eprintln!(
+                "Omnara enabled for workspace `{}` ({}) with an API key from {env} - {}",
+                workspace.name,
+                workspace.source,
+                safe_format_key(&key)
+            ),
+            None => eprintln!(
+                "Omnara workspace `{}` ({}) selected, but {env} is not set",
+                workspace.name, workspace.source
+            ),
+        }
+        return Ok(());
+    }
+    if let Ok(key) = std::env::var("OMNARA_API_KEY") {
+        eprintln!(
+            "Omnara enabled with an API key from OMNARA_API_KEY - {}",
+            safe_format_key(&key)
+        );
+        return Ok(());
+    }
+    match omnara_keychain::load_api_key() {
+        Ok(Some(key)) => {
+            eprintln!(
+                "Omnara enabled with an API key from the OS keychain - {}",
+                safe_format_key(&key)
+            );
+        }
+        Ok(None) => {
+            eprintln!("Omnara not configured; run `codex omnara login` or set OMNARA_API_KEY");
+        }
+        Err(e) => bail!("failed to read the Omnara API key from the OS keychain: {e}"),
+    }
+    Ok(())
+}
+
+async fn run_doctor(config: &Config) -> Result<()> {
+    let Some(client) = OmnaraClient::from_config(&config.omnara, &config.cwd) else {
+        bail!("Omnara not configured; run `codex omnara login` or set OMNARA_API_KEY");
+    };
+    let client = client
+        .with_http_config(&config.omnara.http)
+        .with_request_compression(config.omnara.compress_requests);
+    let report = client.doctor().await;
+    print!("{}", report.format());
+    if !report.passed() {
+        bail!("some Omnara checks failed");
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use anyhow::Result;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+use tempfile::TempDir;
+
+fn codex_command(home: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", home).env("HOME", home);
+    Ok(cmd)
+}
+
+#[test]
+fn status_reports_unconfigured_without_a_key() -> Result<()> {
+    let home = TempDir::new()?;
+
+    let mut cmd = codex_command(home.path())?;
+    cmd.env_remove("OMNARA_API_KEY")
+        .args(["omnara", "status"])
+        .assert()
+        .success()
+        .stderr(contains("Omnara not configured"));
+
+    Ok(())
+}
+
+#[test]
+fn status_masks_the_environment_key() -> Result<()> {
+    let home = TempDir::new()?;
+
+    let mut cmd = codex_command(home.path())?;
+    cmd.env("OMNARA_API_KEY", "omn-1234567890-secret")
+        .args(["omnara", "status"])
+        .assert()
+        .success()
+        .stderr(contains("from OMNARA_API_KEY - omn-1234***ecret"))
+        .stderr(contains("567890").not());
+
+    Ok(())
+}
@@ -69,23 +69,31 @@ pub(crate) async fn apply_patch(
             let rx_approve = sess
                 .request_patch_approval(sub_id.to_owned(), call_id.to_owned(), &action, None, None)
                 .await;
-            match rx_approve.await.unwrap_or_default() {
-                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+            let decision = rx_approve.await.unwrap_or_default();
+            // A frontend may have approved only some of the files.
+            let excluded = sess.take_excluded_patch_files(sub_id).await;
+            let action = if excluded.is_empty() {
+                action
+            } else {
+                action.without_paths(&excluded)
+            };
+            match decision {
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession
+                    if !action.is_empty() =>
+                {
                     InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
                         action,
                         user_explicitly_approved_this_action: true,
                     })
                 }
-                ReviewDecision::Denied | ReviewDecision::Abort => {
-                    ResponseInputItem::FunctionCallOutput {
-                        call_id: call_id.to_owned(),
-                        output: FunctionCallOutputPayload {
-                            content: "patch rejected by user".to_string(),
-                            success: Some(false),
-                        },
-                    }
-                    .into()
+                _ => ResponseInputItem::FunctionCallOutput {
+                    call_id: call_id.to_owned(),
+                    output: FunctionCallOutputPayload {
+                        content: "patch rejected by user".to_string(),
+                        success: Some(false),
+                    },
                 }
+                .into(),
             }
         }
         SafetyCheck::Reject { reason } => ResponseInputItem::FunctionCallOutput {
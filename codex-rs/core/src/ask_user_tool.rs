@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::codex::Session;
+use crate::config::Config;
+use crate::omnara_approval::resolve_ask_user_reply;
+use crate::omnara_client::OmnaraClient;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ResponseInputItem;
+
+pub(crate) const ASK_USER_TOOL_NAME: &str = "ask_user";
+
+pub(crate) static ASK_USER_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "question".to_string(),
+        JsonSchema::String {
+            description: Some("The question for the user".to_string()),
+        },
+    );
+    properties.insert(
+        "options".to_string(),
+        JsonSchema::Array {
+            description: Some("Suggested answers; the user may also reply freely".to_string()),
+            items: Box::new(JsonSchema::String { description: None }),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: ASK_USER_TOOL_NAME.to_string(),
+        description: r#"Asks the user a question and waits for the answer.
+Use it only when you cannot proceed without a decision or information from the user; the user may be away from the terminal, so prefer reasonable assumptions for minor choices.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["question".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+#[derive(Debug, Deserialize)]
+struct AskUserArgs {
+    question: String,
+    #[serde(default)]
+    options: Vec<String>,
+}
+
+/// How long an `ask_user` call waits for an answer, or `None` when the tool
+/// is unavailable. Answers arrive through the Omnara dashboard, so the tool is
+/// only offered when an Omnara API key is configured, and only in turns where
+/// a frontend that can answer is attached.
+pub(crate) fn ask_user_timeout(config: &Config) -> Option<Duration> {
+    (config.omnara.ask_user_tool && OmnaraClient::configured(&config.omnara, &config.cwd))
+        .then(|| Duration::from_secs(config.omnara.ask_user_timeout_secs))
+}
+
+/// Forward the question to frontends as an `AskUserRequest` and block the
+/// call until `Op::AskUserAnswer` arrives, or report that none did within
+/// `timeout`.
+pub(crate) async fn handle_ask_user(
+    session: &Session,
+    arguments: String,
+    sub_id: String,
+    call_id: String,
+    timeout: Duration,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<AskUserArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!("failed to parse function arguments: {e}"),
+                    success: None,
+                },
+            };
+        }
+    };
+    let answer = session
+        .request_user_answer(sub_id, call_id.clone(), args.question, args.options.clone())
+        .await;
+    let output = match tokio::time::timeout(timeout, answer).await {
+        Ok(Ok(answer)) => FunctionCallOutputPayload {
+            content: resolve_ask_user_reply(&answer, &args.options),
+            success: Some(true),
+        },
+        Ok(Err(_)) | Err(_) => {
+            session.cancel_user_answer(&call_id).await;
+            FunctionCallOutputPayload {
+                content: format!(
+                    "The user did not answer within {} seconds; continue with your best judgment.",
+                    timeout.as_secs()
+                ),
+                success: Some(false),
+            }
+        }
+    };
+    ResponseInputItem::FunctionCallOutput { call_id, output }
+}
@@ -403,6 +403,13 @@ impl ModelClient {
     pub fn get_auth_manager(&self) -> Option<Arc<AuthManager>> {
         self.auth_manager.clone()
     }
+
+    /// This client with a different reasoning effort, for side requests
+    /// that should stay cheap.
+    pub fn with_reasoning_effort(mut self, effort: Option<ReasoningEffortConfig>) -> Self {
+        self.effort = effort;
+        self
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -4,6 +4,8 @@ use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
 
@@ -14,6 +16,7 @@ use crate::review_format::format_review_findings_block;
 use crate::user_notification::UserNotifier;
 use async_channel::Receiver;
 use async_channel::Sender;
+use async_channel::WeakSender;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::MaybeApplyPatchVerified;
 use codex_apply_patch::maybe_parse_apply_patch_verified;
@@ -35,6 +38,7 @@ use serde_json::Value;
 use tokio::sync::Mutex;
 use tokio::sync::oneshot;
 use tokio::task::AbortHandle;
+use tracing::Instrument;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -47,9 +51,13 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::ask_user_tool::ASK_USER_TOOL_NAME;
+use crate::ask_user_tool::ask_user_timeout;
+use crate::ask_user_tool::handle_ask_user;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::command_match::closest_approved;
 use crate::config::Config;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
@@ -70,9 +78,19 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::git_diff_tracker::DiffLimits;
+use crate::git_diff_tracker::SubmoduleDiffs;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
+use crate::omnara_approval::ApprovalParser;
+use crate::omnara_approval::PatchFormat;
+use crate::omnara_circuit::CircuitPolicy;
+use crate::omnara_client::OmnaraClient;
+use crate::omnara_client::PollPolicy;
+use crate::omnara_client::SessionMetadata;
+use crate::omnara_escalation::EscalationChain;
+use crate::omnara_withhold::WithheldPaths;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
@@ -87,6 +105,7 @@ use crate::protocol::AgentReasoningRawContentDeltaEvent;
 use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
+use crate::protocol::AskUserRequestEvent;
 use crate::protocol::BackgroundEventEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
@@ -94,12 +113,17 @@ use crate::protocol::EventMsg;
 use crate::protocol::ExecApprovalRequestEvent;
 use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
+use crate::protocol::ExecSandbox;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::McpInvocation;
+use crate::protocol::McpToolCallApprovalRequestEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
+use crate::protocol::PatchExplanationEvent;
+use crate::protocol::PermissionEscalation;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReviewDecision;
 use crate::protocol::ReviewOutputEvent;
@@ -113,12 +137,15 @@ use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::remote_frontend::LocalControl;
+use crate::remote_frontend::RemoteFrontend;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
 use crate::shell;
+use crate::trace_context::TraceContext;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
@@ -136,6 +163,8 @@ use codex_protocol::models::ShellToolCallParams;
 use codex_protocol::protocol::InitialHistory;
 
 pub mod compact;
+mod context_summary;
+mod explain_patch;
 use self::compact::build_compacted_history;
 use self::compact::collect_user_messages;
 
@@ -157,6 +186,8 @@ pub struct CodexSpawnOk {
 
 pub(crate) const INITIAL_SUBMIT_ID: &str = "";
 pub(crate) const SUBMISSION_CHANNEL_CAPACITY: usize = 64;
+/// How long `Op::Shutdown` waits for a registered remote frontend to flush.
+const REMOTE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 // Model-formatting limits: clients get full streams; oonly content sent to the model is truncated.
 pub(crate) const MODEL_FORMAT_MAX_BYTES: usize = 10 * 1024; // 10 KiB
@@ -208,7 +239,13 @@ impl Codex {
         let conversation_id = session.conversation_id;
 
         // This task will run until Op::Shutdown is received.
-        tokio::spawn(submission_loop(session, turn_context, config, rx_sub));
+        tokio::spawn(submission_loop(
+            session,
+            turn_context,
+            config,
+            rx_sub,
+            tx_sub.downgrade(),
+        ));
         let codex = Codex {
             next_id: AtomicU64::new(0),
             tx_sub,
@@ -256,8 +293,14 @@ impl Codex {
 #[derive(Default)]
 struct State {
     approved_commands: HashSet<Vec<String>>,
+    /// MCP tools approved for the rest of the session, as (server, tool).
+    approved_mcp_tools: HashSet<(String, String)>,
     current_task: Option<AgentTask>,
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    /// Files to leave out of a pending patch, keyed by submission id.
+    excluded_patch_files: HashMap<String, Vec<PathBuf>>,
+    /// `ask_user` calls awaiting an answer, keyed by call id.
+    pending_questions: HashMap<String, oneshot::Sender<String>>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
     token_info: Option<TokenUsageInfo>,
@@ -273,6 +316,8 @@ pub(crate) struct Session {
 
     /// Manager for external MCP servers/tools.
     mcp_connection_manager: McpConnectionManager,
+    /// MCP servers whose tool calls need approval (`require_approval`).
+    mcp_approval_servers: HashSet<String>,
     session_manager: ExecSessionManager,
     unified_exec_manager: UnifiedExecSessionManager,
 
@@ -286,6 +331,16 @@ pub(crate) struct Session {
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
     next_internal_sub_id: AtomicU64,
+
+    /// Omnara connection driven by core once a frontend sends
+    /// `Op::RegisterRemoteFrontend`.
+    remote_frontend: OnceLock<RemoteFrontend>,
+
+    /// Set by `Op::RegisterQuestionFrontend`.
+    question_frontend: AtomicBool,
+
+    /// HTTP control server configured in `[omnara.local_control]`.
+    local_control: OnceLock<LocalControl>,
 }
 
 /// The context needed for a single turn of the conversation.
@@ -463,6 +518,7 @@ impl Session {
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                ask_user_timeout: ask_user_timeout(&config),
             }),
             user_instructions,
             base_instructions,
@@ -477,6 +533,12 @@ impl Session {
             conversation_id,
             tx_event: tx_event.clone(),
             mcp_connection_manager,
+            mcp_approval_servers: config
+                .mcp_servers
+                .iter()
+                .filter(|(_, server)| server.require_approval)
+                .map(|(name, _)| name.clone())
+                .collect(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: notify,
@@ -486,6 +548,9 @@ impl Session {
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             next_internal_sub_id: AtomicU64::new(0),
+            remote_frontend: OnceLock::new(),
+            question_frontend: AtomicBool::new(false),
+            local_control: OnceLock::new(),
         });
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
@@ -573,6 +638,12 @@ impl Session {
         // Persist the event into rollout (recorder filters as needed)
         let rollout_items = vec![RolloutItem::EventMsg(event.msg.clone())];
         self.persist_rollout_items(&rollout_items).await;
+        if let Some(remote) = self.remote_frontend.get() {
+            remote.observe(&event);
+        }
+        if let Some(local) = self.local_control.get() {
+            local.observe(&event);
+        }
         if let Err(e) = self.tx_event.send(event).await {
             error!("failed to send tool call event: {e}");
         }
@@ -585,6 +656,7 @@ impl Session {
         command: Vec<String>,
         cwd: PathBuf,
         reason: Option<String>,
+        escalation: Option<PermissionEscalation>,
     ) -> ReviewDecision {
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
@@ -597,6 +669,10 @@ impl Session {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
         }
 
+        let closest_approved = {
+            let state = self.state.lock().await;
+            closest_approved(&command, &state.approved_commands)
+        };
         let event = Event {
             id: event_id,
             msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
@@ -604,6 +680,8 @@ impl Session {
                 command,
                 cwd,
                 reason,
+                escalation,
+                closest_approved,
             }),
         };
         self.send_event(event).await;
@@ -642,6 +720,34 @@ impl Session {
         rx_approve
     }
 
+    pub async fn request_mcp_tool_approval(
+        &self,
+        sub_id: String,
+        call_id: String,
+        invocation: McpInvocation,
+    ) -> ReviewDecision {
+        // Add the tx_approve callback to the map before sending the request.
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let event_id = sub_id.clone();
+        let prev_entry = {
+            let mut state = self.state.lock().await;
+            state.pending_approvals.insert(sub_id, tx_approve)
+        };
+        if prev_entry.is_some() {
+            warn!("Overwriting existing pending approval for sub_id: {event_id}");
+        }
+
+        let event = Event {
+            id: event_id,
+            msg: EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                call_id,
+                invocation,
+            }),
+        };
+        self.send_event(event).await;
+        rx_approve.await.unwrap_or_default()
+    }
+
     pub async fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut state = self.state.lock().await;
@@ -657,11 +763,101 @@ impl Session {
         }
     }
 
+    pub async fn exclude_patch_files(&self, sub_id: String, paths: Vec<PathBuf>) {
+        let mut state = self.state.lock().await;
+        state.excluded_patch_files.insert(sub_id, paths);
+    }
+
+    /// Files the user left out of the patch pending for `sub_id`, if any.
+    pub(crate) async fn take_excluded_patch_files(&self, sub_id: &str) -> Vec<PathBuf> {
+        let mut state = self.state.lock().await;
+        state
+            .excluded_patch_files
+            .remove(sub_id)
+            .unwrap_or_default()
+    }
+
+    /// Ask the user a question on behalf of the `ask_user` tool. The
+    /// receiver resolves when a frontend sends `Op::AskUserAnswer`.
+    pub(crate) async fn request_user_answer(
+        &self,
+        sub_id: String,
+        call_id: String,
+        question: String,
+        options: Vec<String>,
+    ) -> oneshot::Receiver<String> {
+        let (tx_answer, rx_answer) = oneshot::channel();
+        {
+            let mut state = self.state.lock().await;
+            state.pending_questions.insert(call_id.clone(), tx_answer);
+        }
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::AskUserRequest(AskUserRequestEvent {
+                call_id,
+                question,
+                options,
+            }),
+        };
+        self.send_event(event).await;
+        rx_answer
+    }
+
+    pub async fn notify_user_answer(&self, call_id: &str, answer: String) {
+        let entry = {
+            let mut state = self.state.lock().await;
+            state.pending_questions.remove(call_id)
+        };
+        match entry {
+            Some(tx_answer) => {
+                tx_answer.send(answer).ok();
+            }
+            None => {
+                warn!("No pending question found for call_id: {call_id}");
+            }
+        }
+    }
+
+    /// Whether someone can answer an `ask_user` question: a frontend sent
+    /// `Op::RegisterQuestionFrontend`, core mirrors the session to Omnara, or
+    /// the local control server takes answers. Frontends like `codex exec`
+    /// cannot, so the model is not offered the tool there.
+    fn can_answer_questions(&self) -> bool {
+        self.question_frontend
+            .load(std::sync::atomic::Ordering::SeqCst)
+            || self.remote_frontend.get().is_some()
+            || self.local_control.get().is_some()
+    }
+
+    /// Forget a question whose `ask_user` call gave up waiting.
+    pub(crate) async fn cancel_user_answer(&self, call_id: &str) {
+        let mut state = self.state.lock().await;
+        state.pending_questions.remove(call_id);
+    }
+
     pub async fn add_approved_command(&self, cmd: Vec<String>) {
         let mut state = self.state.lock().await;
         state.approved_commands.insert(cmd);
     }
 
+    /// Whether calling `tool` on `server` must be approved first: the server
+    /// is configured with `require_approval` and the tool has not been
+    /// approved for the session.
+    pub(crate) async fn mcp_tool_needs_approval(&self, server: &str, tool: &str) -> bool {
+        if !self.mcp_approval_servers.contains(server) {
+            return false;
+        }
+        let state = self.state.lock().await;
+        !state
+            .approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
+    pub(crate) async fn add_approved_mcp_tool(&self, server: String, tool: String) {
+        let mut state = self.state.lock().await;
+        state.approved_mcp_tools.insert((server, tool));
+    }
+
     /// Records input items: always append to conversation history and
     /// persist these response items to rollout.
     async fn record_conversation_items(&self, items: &[ResponseItem]) {
@@ -849,6 +1045,7 @@ impl Session {
         call_id: &str,
         output: &ExecToolCallOutput,
         is_apply_patch: bool,
+        sandbox: ExecSandbox,
     ) {
         let ExecToolCallOutput {
             stdout,
@@ -880,6 +1077,7 @@ impl Session {
                 exit_code: *exit_code,
                 duration: *duration,
                 formatted_output,
+                sandbox: Some(sandbox),
             })
         };
 
@@ -916,6 +1114,11 @@ impl Session {
         let is_apply_patch = begin_ctx.apply_patch.is_some();
         let sub_id = begin_ctx.sub_id.clone();
         let call_id = begin_ctx.call_id.clone();
+        let sandbox = match (exec_args.sandbox_type, exec_args.escalated) {
+            (SandboxType::None, true) => ExecSandbox::Escalated,
+            (SandboxType::None, false) => ExecSandbox::Unsandboxed,
+            _ => ExecSandbox::Sandboxed,
+        };
 
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
@@ -952,6 +1155,7 @@ impl Session {
             &call_id,
             borrowed,
             is_apply_patch,
+            sandbox,
         )
         .await;
 
@@ -1028,6 +1232,8 @@ impl Session {
         info!("interrupt received: abort current task, if any");
         let mut state = self.state.lock().await;
         state.pending_approvals.clear();
+        state.excluded_patch_files.clear();
+        state.pending_questions.clear();
         state.pending_input.clear();
         if let Some(task) = state.current_task.take() {
             task.abort(TurnAbortReason::Interrupted);
@@ -1037,6 +1243,8 @@ impl Session {
     fn interrupt_task_sync(&self) {
         if let Ok(mut state) = self.state.try_lock() {
             state.pending_approvals.clear();
+            state.excluded_patch_files.clear();
+            state.pending_questions.clear();
             state.pending_input.clear();
             if let Some(task) = state.current_task.take() {
                 task.abort(TurnAbortReason::Interrupted);
@@ -1096,7 +1304,12 @@ impl AgentTask {
             let sess = sess.clone();
             let sub_id = sub_id.clone();
             let tc = Arc::clone(&turn_context);
-            tokio::spawn(async move { run_task(sess, tc, sub_id, input).await }).abort_handle()
+            let trace = TraceContext::new_root();
+            let span = trace.turn_span(&sub_id);
+            tokio::spawn(
+                async move { run_task(sess, tc, sub_id, input, trace).await }.instrument(span),
+            )
+            .abort_handle()
         };
         Self {
             sess,
@@ -1116,7 +1329,12 @@ impl AgentTask {
             let sess = sess.clone();
             let sub_id = sub_id.clone();
             let tc = Arc::clone(&turn_context);
-            tokio::spawn(async move { run_task(sess, tc, sub_id, input).await }).abort_handle()
+            let trace = TraceContext::new_root();
+            let span = trace.turn_span(&sub_id);
+            tokio::spawn(
+                async move { run_task(sess, tc, sub_id, input, trace).await }.instrument(span),
+            )
+            .abort_handle()
         };
         Self {
             sess,
@@ -1166,14 +1384,59 @@ impl AgentTask {
     }
 }
 
+/// Serve the local control API and tell the frontend where, and with which
+/// token unless the user chose it.
+async fn start_local_control(
+    sess: &Session,
+    config: &Config,
+    listen: &str,
+    tx_sub: WeakSender<Submission>,
+) {
+    let fixed_token = std::env::var(LocalControl::TOKEN_ENV_VAR)
+        .ok()
+        .filter(|token| !token.is_empty());
+    let token = fixed_token
+        .clone()
+        .unwrap_or_else(LocalControl::generate_token);
+    let message = match LocalControl::start(
+        config,
+        listen,
+        token.clone(),
+        tx_sub,
+        sess.tx_event.downgrade(),
+    ) {
+        Ok(local) => {
+            let _ = sess.local_control.set(local);
+            match fixed_token {
+                Some(_) => format!(
+                    "Local control server on http://{listen} (token from {})",
+                    LocalControl::TOKEN_ENV_VAR
+                ),
+                None => format!(
+                    "Local control server on http://{listen}; authenticate with `Authorization: Bearer {token}`"
+                ),
+            }
+        }
+        Err(e) => {
+            warn!(%listen, "cannot start local control server: {e}");
+            format!("Local control server not started: cannot listen on {listen}: {e}")
+        }
+    };
+    sess.notify_background_event("", message).await;
+}
+
 async fn submission_loop(
     sess: Arc<Session>,
     turn_context: TurnContext,
     config: Arc<Config>,
     rx_sub: Receiver<Submission>,
+    tx_sub: WeakSender<Submission>,
 ) {
     // Wrap once to avoid cloning TurnContext for each task.
     let mut turn_context = Arc::new(turn_context);
+    if let Some(listen) = &config.omnara.local_control.listen {
+        start_local_control(&sess, &config, listen, tx_sub.clone()).await;
+    }
     // To break out of this loop, send Op::Shutdown.
     while let Ok(sub) = rx_sub.recv().await {
         debug!(?sub, "Submission");
@@ -1186,12 +1449,22 @@ async fn submission_loop(
                 approval_policy,
                 sandbox_policy,
                 model,
+                model_provider,
                 effort,
                 summary,
             } => {
                 // Recalculate the persistent turn context with provided overrides.
                 let prev = Arc::clone(&turn_context);
-                let provider = prev.client.get_provider();
+                let provider = match model_provider.as_deref() {
+                    Some(id) => match config.model_providers.get(id) {
+                        Some(provider) => provider.clone(),
+                        None => {
+                            warn!("ignoring override to unknown model provider {id:?}");
+                            prev.client.get_provider()
+                        }
+                    },
+                    None => prev.client.get_provider(),
+                };
 
                 // Effective model + family
                 let (effective_model, effective_family) = if let Some(ref m) = model {
@@ -1210,6 +1483,12 @@ async fn submission_loop(
 
                 // Build updated config for the client
                 let mut updated_config = (*config).clone();
+                if let Some(id) =
+                    model_provider.filter(|id| config.model_providers.contains_key(id))
+                {
+                    updated_config.model_provider_id = id;
+                    updated_config.model_provider = provider.clone();
+                }
                 updated_config.model = effective_model.clone();
                 updated_config.model_family = effective_family.clone();
                 if let Some(model_info) = get_model_info(&effective_family) {
@@ -1239,6 +1518,7 @@ async fn submission_loop(
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                    ask_user_timeout: ask_user_timeout(&config),
                 });
 
                 let new_turn_context = TurnContext {
@@ -1329,6 +1609,7 @@ async fn submission_loop(
                             include_view_image_tool: config.include_view_image_tool,
                             experimental_unified_exec_tool: config
                                 .use_experimental_unified_exec_tool,
+                            ask_user_timeout: ask_user_timeout(&config),
                         }),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
@@ -1369,6 +1650,54 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other).await,
             },
+            Op::McpToolCallApproval { id, decision } => match decision {
+                ReviewDecision::Abort => {
+                    sess.interrupt_task().await;
+                }
+                other => sess.notify_approval(&id, other).await,
+            },
+            Op::ExcludePatchFiles { id, paths } => {
+                sess.exclude_patch_files(id, paths).await;
+            }
+            Op::ExplainPatch { id, changes } => {
+                let sess = sess.clone();
+                let client = turn_context.client.clone();
+                let withheld = WithheldPaths::new(&config.omnara.withhold_paths);
+                let sub_id = sub.id.clone();
+                tokio::spawn(async move {
+                    let explanation = explain_patch::explain_patch(&client, &changes, &withheld)
+                        .await
+                        .inspect_err(|e| warn!("failed to explain patch {id}: {e}"))
+                        .ok();
+                    sess.send_event(Event {
+                        id: sub_id,
+                        msg: EventMsg::PatchExplanation(PatchExplanationEvent { id, explanation }),
+                    })
+                    .await;
+                });
+            }
+            Op::GetContextSummary => {
+                let items = {
+                    let state = sess.state.lock().await;
+                    state.history.contents()
+                };
+                let summary = context_summary::summarize_history(
+                    &items,
+                    turn_context.client.get_model_context_window(),
+                );
+                sess.send_event(Event {
+                    id: sub.id,
+                    msg: EventMsg::ContextSummary(summary),
+                })
+                .await;
+            }
+            Op::RegisterQuestionFrontend => {
+                sess.question_frontend
+                    .store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+            Op::AskUserAnswer { id, answer } => {
+                sess.notify_user_answer(&id, answer).await;
+            }
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1461,9 +1790,71 @@ async fn submission_loop(
                     .await;
                 }
             }
+            Op::RegisterRemoteFrontend => {
+                let Some(client) = OmnaraClient::from_config(&config.omnara, &config.cwd) else {
+                    let message =
+                        "Remote frontend not registered: no Omnara API key configured".to_string();
+                    warn!("{message}");
+                    sess.send_event(Event {
+                        id: sub.id,
+                        msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+                    })
+                    .await;
+                    continue;
+                };
+                let remote = RemoteFrontend::new(
+                    client
+                        .with_poll_policy(PollPolicy::from(&config.omnara))
+                        .with_diff_excludes(config.omnara.diff_exclude.clone())
+                        .with_withheld_paths(&config.omnara.withhold_paths)
+                        .with_diff_limits(DiffLimits::from(&config.omnara))
+                        .with_diff_submodules(SubmoduleDiffs::from(&config.omnara))
+                        .with_request_compression(config.omnara.compress_requests)
+                        .with_markdown_dialect(config.omnara.markdown_dialect)
+                        .with_transformers(&config.omnara.transformers)
+                        .with_voice(&config.omnara.voice)
+                        .with_uploads(&config.omnara.uploads, &config.cwd)
+                        .with_http_config(&config.omnara.http)
+                        .with_circuit_breaker(CircuitPolicy::from(&config.omnara.circuit_breaker))
+                        .with_session_metadata(
+                            SessionMetadata::detect(&config.cwd).with_labels(&config.omnara),
+                        ),
+                    tx_sub.clone(),
+                    sess.tx_event.downgrade(),
+                    ApprovalParser::new(&config.omnara.approval_synonyms),
+                )
+                .with_approval_escalation(EscalationChain::from_config(
+                    &config.omnara.approval_escalation,
+                ))
+                .with_approval_push(config.omnara.approval_push)
+                .with_error_notes(config.omnara.error_notes)
+                .with_locale(config.omnara.locale)
+                .with_patch_format(PatchFormat::from(&config.omnara))
+                .with_max_remote_message_bytes(config.omnara.max_remote_message_kb as usize * 1024)
+                .with_patch_preview(
+                    config.cwd.clone(),
+                    config.omnara.preview_command.clone(),
+                    Duration::from_secs(config.omnara.preview_timeout_secs),
+                )
+                .with_session_report(&config.omnara.session_report);
+                if sess.remote_frontend.set(remote).is_ok()
+                    && let Some(remote) = sess.remote_frontend.get()
+                {
+                    remote.start();
+                }
+            }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
 
+                if let Some(remote) = sess.remote_frontend.get() {
+                    remote
+                        .shutdown(std::time::Instant::now() + REMOTE_SHUTDOWN_TIMEOUT)
+                        .await;
+                }
+                if let Some(local) = sess.local_control.get() {
+                    local.shutdown();
+                }
+
                 // Gracefully flush and shutdown rollout recorder on session end so tests
                 // that inspect the rollout file do not race with the background writer.
                 let recorder_opt = {
@@ -1554,6 +1945,7 @@ async fn spawn_review_thread(
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        ask_user_timeout: None,
     });
 
     let base_instructions = REVIEW_PROMPT.to_string();
@@ -1636,6 +2028,7 @@ async fn run_task(
     turn_context: Arc<TurnContext>,
     sub_id: String,
     input: Vec<InputItem>,
+    trace: TraceContext,
 ) {
     if input.is_empty() {
         return;
@@ -1644,6 +2037,7 @@ async fn run_task(
         id: sub_id.clone(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: turn_context.client.get_model_context_window(),
+            traceparent: Some(trace.traceparent()),
         }),
     };
     sess.send_event(event).await;
@@ -1950,8 +2344,16 @@ async fn run_turn(
     sub_id: String,
     input: Vec<ResponseItem>,
 ) -> CodexResult<TurnRunResult> {
+    let tools_config = if sess.can_answer_questions() {
+        turn_context.tools_config.clone()
+    } else {
+        ToolsConfig {
+            ask_user_timeout: None,
+            ..turn_context.tools_config.clone()
+        }
+    };
     let tools = get_openai_tools(
-        &turn_context.tools_config,
+        &tools_config,
         Some(sess.mcp_connection_manager.list_all_tools()),
     );
 
@@ -2530,6 +2932,16 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        ASK_USER_TOOL_NAME
+            if turn_context.tools_config.ask_user_timeout.is_some()
+                && sess.can_answer_questions() =>
+        {
+            let timeout = turn_context
+                .tools_config
+                .ask_user_timeout
+                .unwrap_or_default();
+            handle_ask_user(sess, arguments, sub_id, call_id, timeout).await
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
@@ -2581,7 +2993,16 @@ async fn handle_function_call(
         _ => {
             match sess.mcp_connection_manager.parse_tool_name(&name) {
                 Some((server, tool_name)) => {
-                    handle_mcp_tool_call(sess, &sub_id, call_id, server, tool_name, arguments).await
+                    handle_mcp_tool_call(
+                        sess,
+                        turn_context.approval_policy,
+                        &sub_id,
+                        call_id,
+                        server,
+                        tool_name,
+                        arguments,
+                    )
+                    .await
                 }
                 None => {
                     // Unknown function: reply with structured failure so the model can adapt.
@@ -2686,6 +3107,8 @@ fn parse_container_exec_arguments(
 pub struct ExecInvokeArgs<'a> {
     pub params: ExecParams,
     pub sandbox_type: SandboxType,
+    /// The user approved running this call without the sandbox.
+    pub escalated: bool,
     pub sandbox_policy: &'a SandboxPolicy,
     pub sandbox_cwd: &'a Path,
     pub codex_linux_sandbox_exe: &'a Option<PathBuf>,
@@ -2825,8 +3248,8 @@ async fn handle_container_exec_with_params(
         }
     };
 
-    let sandbox_type = match safety {
-        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+    let (sandbox_type, escalated) = match safety {
+        SafetyCheck::AutoApprove { sandbox_type } => (sandbox_type, false),
         SafetyCheck::AskUser => {
             let decision = sess
                 .request_command_approval(
@@ -2835,6 +3258,10 @@ async fn handle_container_exec_with_params(
                     params.command.clone(),
                     params.cwd.clone(),
                     params.justification.clone(),
+                    params
+                        .with_escalated_permissions
+                        .unwrap_or(false)
+                        .then_some(PermissionEscalation::Requested),
                 )
                 .await;
             match decision {
@@ -2856,7 +3283,7 @@ async fn handle_container_exec_with_params(
             // explicit approval. Often, we end up in this case because
             // the command cannot be run in a sandbox, such as
             // installing a new dependency that requires network access.
-            SandboxType::None
+            (SandboxType::None, true)
         }
         SafetyCheck::Reject { reason } => {
             return ResponseInputItem::FunctionCallOutput {
@@ -2893,6 +3320,7 @@ async fn handle_container_exec_with_params(
             ExecInvokeArgs {
                 params: params.clone(),
                 sandbox_type,
+                escalated,
                 sandbox_policy: &turn_context.sandbox_policy,
                 sandbox_cwd: &turn_context.cwd,
                 codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
@@ -3005,6 +3433,7 @@ async fn handle_sandbox_error(
             params.command.clone(),
             cwd.clone(),
             Some("command failed; retry without sandbox?".to_string()),
+            Some(PermissionEscalation::RetryWithoutSandbox),
         )
         .await;
 
@@ -3028,6 +3457,7 @@ async fn handle_sandbox_error(
                     ExecInvokeArgs {
                         params,
                         sandbox_type: SandboxType::None,
+                        escalated: true,
                         sandbox_policy: &turn_context.sandbox_policy,
                         sandbox_cwd: &turn_context.cwd,
                         codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
@@ -3610,6 +4040,7 @@ mod tests {
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            ask_user_timeout: ask_user_timeout(&config),
         });
         let turn_context = TurnContext {
             client,
@@ -3627,6 +4058,7 @@ mod tests {
             conversation_id,
             tx_event,
             mcp_connection_manager: McpConnectionManager::default(),
+            mcp_approval_servers: HashSet::new(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: UserNotifier::default(),
@@ -3639,6 +4071,9 @@ mod tests {
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             next_internal_sub_id: AtomicU64::new(0),
+            remote_frontend: OnceLock::new(),
+            question_frontend: AtomicBool::new(false),
+            local_control: OnceLock::new(),
         };
         (session, turn_context)
     }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use super::AgentTask;
 use super::Session;
 use super::TurnContext;
+use super::context_summary::history_tokens;
 use super::get_last_assistant_message_from_turn;
 use crate::Prompt;
 use crate::client_common::ResponseEvent;
@@ -10,6 +11,7 @@ use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
 use crate::protocol::AgentMessageEvent;
 use crate::protocol::CompactedItem;
+use crate::protocol::ContextCompactedEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -68,6 +70,7 @@ pub(super) async fn run_compact_task(
         id: sub_id.clone(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: turn_context.client.get_model_context_window(),
+            traceparent: None,
         }),
     };
     sess.send_event(start_event).await;
@@ -101,6 +104,8 @@ async fn run_compact_task_inner(
     let turn_input = sess
         .turn_input_with_history(vec![initial_input_for_turn.clone().into()])
         .await;
+    // Everything but the summarization prompt.
+    let tokens_before = history_tokens(&turn_input[..turn_input.len() - 1]);
 
     let prompt = Prompt {
         input: turn_input,
@@ -170,6 +175,10 @@ async fn run_compact_task_inner(
     let user_messages = collect_user_messages(&history_snapshot);
     let initial_context = sess.build_initial_context(turn_context.as_ref());
     let new_history = build_compacted_history(initial_context, &user_messages, &summary_text);
+    let compacted = ContextCompactedEvent {
+        tokens_before,
+        tokens_after: history_tokens(&new_history),
+    };
     {
         let mut state = sess.state.lock().await;
         state.history.replace(new_history);
@@ -180,6 +189,11 @@ async fn run_compact_task_inner(
     });
     sess.persist_rollout_items(&[rollout_item]).await;
 
+    sess.send_event(Event {
+        id: sub_id.clone(),
+        msg: EventMsg::ContextCompacted(compacted),
+    })
+    .await;
     let event = Event {
         id: sub_id.clone(),
         msg: EventMsg::AgentMessage(AgentMessageEvent {
@@ -0,0 +1,296 @@
+//! `Op::GetContextSummary` and the sizes reported after a compaction: what
+//! the conversation history holds, estimated from its text at about four
+//! bytes a token.
+
+use std::collections::HashMap;
+
+use crate::parse_command::ParsedCommand;
+use crate::parse_command::parse_command;
+use crate::protocol::ContextFile;
+use crate::protocol::ContextSection;
+use crate::protocol::ContextSummaryEvent;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::ENVIRONMENT_CONTEXT_OPEN_TAG;
+use codex_protocol::protocol::USER_INSTRUCTIONS_OPEN_TAG;
+
+/// Files listed in a summary, largest first; smaller ones are left out.
+const MAX_FILES: usize = 20;
+
+/// Opening of a file attached to a message with `@file`.
+const FILE_ATTACHMENT_OPEN: &str = "<file path=\"";
+
+/// Estimated tokens of `items`.
+pub(crate) fn history_tokens(items: &[ResponseItem]) -> u64 {
+    tokens(items.iter().map(item_bytes).sum())
+}
+
+/// What `items` hold, by kind of item and by file.
+pub(crate) fn summarize_history(
+    items: &[ResponseItem],
+    model_context_window: Option<u64>,
+) -> ContextSummaryEvent {
+    let mut sections: Vec<(&str, usize, usize)> = Vec::new();
+    let mut files: HashMap<String, usize> = HashMap::new();
+    // Calls that read a single file, by call id, so their output counts
+    // toward that file.
+    let mut reads: HashMap<&str, String> = HashMap::new();
+    for item in items {
+        let bytes = item_bytes(item);
+        let section = match item {
+            ResponseItem::Message { role, content, .. } if role == "user" => {
+                let text = message_text(content);
+                let trimmed = text.trim_start();
+                if trimmed.starts_with(USER_INSTRUCTIONS_OPEN_TAG)
+                    || trimmed.starts_with(ENVIRONMENT_CONTEXT_OPEN_TAG)
+                {
+                    "Instructions and environment"
+                } else {
+                    for (path, size) in attached_files(&text) {
+                        *files.entry(path).or_default() += size;
+                    }
+                    "User messages"
+                }
+            }
+            ResponseItem::Message { .. } => "Assistant messages",
+            ResponseItem::Reasoning { .. } => "Reasoning",
+            ResponseItem::LocalShellCall {
+                call_id: Some(call_id),
+                action: LocalShellAction::Exec(action),
+                ..
+            } => {
+                if let Some(path) = read_file(&action.command) {
+                    reads.insert(call_id, path);
+                }
+                "Tool calls"
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                if name == "shell"
+                    && let Some(path) = shell_arguments_read_file(arguments)
+                {
+                    reads.insert(call_id, path);
+                }
+                "Tool calls"
+            }
+            ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+            | ResponseItem::WebSearchCall { .. } => "Tool calls",
+            ResponseItem::FunctionCallOutput { call_id, .. }
+            | ResponseItem::CustomToolCallOutput { call_id, .. } => {
+                if let Some(path) = reads.get(call_id.as_str()) {
+                    *files.entry(path.clone()).or_default() += bytes;
+                }
+                "Tool output"
+            }
+            ResponseItem::Other => continue,
+        };
+        match sections.iter_mut().find(|(name, ..)| *name == section) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += bytes;
+            }
+            None => sections.push((section, 1, bytes)),
+        }
+    }
+
+    let mut sections: Vec<ContextSection> = sections
+        .into_iter()
+        .map(|(name, items, bytes)| ContextSection {
+            name: name.to_string(),
+            items,
+            tokens: tokens(bytes),
+        })
+        .collect();
+    sections.sort_by(|a, b| b.tokens.cmp(&a.tokens));
+    let mut files: Vec<ContextFile> = files
+        .into_iter()
+        .map(|(path, bytes)| ContextFile {
+            path,
+            tokens: tokens(bytes),
+        })
+        .collect();
+    files.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(MAX_FILES);
+    ContextSummaryEvent {
+        tokens: history_tokens(items),
+        model_context_window,
+        sections,
+        files,
+    }
+}
+
+fn tokens(bytes: usize) -> u64 {
+    (bytes as u64).div_ceil(4)
+}
+
+/// Bytes of text `item` puts in the model's context.
+fn item_bytes(item: &ResponseItem) -> usize {
+    match item {
+        ResponseItem::Message { content, .. } => content
+            .iter()
+            .map(|c| match c {
+                ContentItem::InputText { text } | ContentItem::OutputText { text } => text.len(),
+                ContentItem::InputImage { .. } => 0,
+            })
+            .sum(),
+        ResponseItem::Reasoning {
+            summary, content, ..
+        } => {
+            let summary: usize = summary
+                .iter()
+                .map(|s| match s {
+                    ReasoningItemReasoningSummary::SummaryText { text } => text.len(),
+                })
+                .sum();
+            let content: usize = content
+                .iter()
+                .flatten()
+                .map(|c| match c {
+                    ReasoningItemContent::ReasoningText { text }
+                    | ReasoningItemContent::Text { text } => text.len(),
+                })
+                .sum();
+            summary + content
+        }
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => action.command.iter().map(String::len).sum(),
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => name.len() + arguments.len(),
+        ResponseItem::FunctionCallOutput { output, .. } => output.content.len(),
+        ResponseItem::CustomToolCall { name, input, .. } => name.len() + input.len(),
+        ResponseItem::CustomToolCallOutput { output, .. } => output.len(),
+        ResponseItem::WebSearchCall { .. } | ResponseItem::Other => 0,
+    }
+}
+
+fn message_text(content: &[ContentItem]) -> String {
+    content
+        .iter()
+        .filter_map(|c| match c {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                Some(text.as_str())
+            }
+            ContentItem::InputImage { .. } => None,
+        })
+        .collect()
+}
+
+/// Paths and sizes of the `<file path="…">` blocks in `text`.
+fn attached_files(text: &str) -> Vec<(String, usize)> {
+    let mut files = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(FILE_ATTACHMENT_OPEN) {
+        let block = &rest[start..];
+        let Some((path, _)) = block[FILE_ATTACHMENT_OPEN.len()..].split_once('"') else {
+            break;
+        };
+        let end = block.find("</file>").map_or(block.len(), |end| end + 7);
+        files.push((path.to_string(), end));
+        rest = &block[end..];
+    }
+    files
+}
+
+/// The file `command` reads, when reading one file is all it does.
+fn read_file(command: &[String]) -> Option<String> {
+    match parse_command(command).as_slice() {
+        [ParsedCommand::Read { name, .. }] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn shell_arguments_read_file(arguments: &str) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct ShellArguments {
+        command: Vec<String>,
+    }
+    let arguments: ShellArguments = serde_json::from_str(arguments).ok()?;
+    read_file(&arguments.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn files_read_by_commands_and_attached_to_messages_are_listed() {
+        let items = vec![
+            message(
+                "user",
+                "<environment_context>\n  <cwd>/repo</cwd>\n</environment_context>",
+            ),
+            message(
+                "user",
+                "Fix it\n<file path=\"src/lib.rs\">\npub fn f() {}\n</file>",
+            ),
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["bash","-lc","cat src/main.rs"]}"#.to_string(),
+                call_id: "call-1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call-1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "fn main() {}\n".repeat(40),
+                    success: Some(true),
+                },
+            },
+            message("assistant", "Done."),
+        ];
+        let summary = summarize_history(&items, Some(272_000));
+
+        assert_eq!(summary.tokens, history_tokens(&items));
+        assert_eq!(
+            summary.files,
+            vec![
+                ContextFile {
+                    path: "main.rs".to_string(),
+                    tokens: 130,
+                },
+                ContextFile {
+                    path: "src/lib.rs".to_string(),
+                    tokens: 12,
+                },
+            ]
+        );
+        let sections: Vec<(&str, usize)> = summary
+            .sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.items))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                ("Tool output", 1),
+                ("Instructions and environment", 1),
+                ("User messages", 1),
+                ("Tool calls", 1),
+                ("Assistant messages", 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! `Op::ExplainPatch`: a plain-language summary of a pending patch for
+//! reviewers who do not read diffs, produced by a single low-effort model
+//! request without tools or conversation history.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::get_last_assistant_message_from_turn;
+use crate::Prompt;
+use crate::client::ModelClient;
+use crate::client_common::ResponseEvent;
+use crate::error::CodexErr;
+use crate::error::Result as CodexResult;
+use crate::omnara_approval::PatchFormat;
+use crate::omnara_approval::format_patch_details;
+use crate::omnara_withhold::WithheldPaths;
+use crate::protocol::FileChange;
+use crate::truncate::truncate_middle;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use futures::prelude::*;
+
+const EXPLAIN_PATCH_INSTRUCTIONS: &str = "You explain proposed code changes to people who \
+     may not be software developers, such as product owners approving a change. Describe \
+     what the change does and what it means for the project in plain language, in at most \
+     five short bullet points. Avoid jargon, do not quote code, and do not judge whether \
+     the change should be approved.";
+
+/// Diff text sent to the model is capped to keep the request cheap.
+const MAX_DIFF_BYTES: usize = 24_000;
+
+/// Ask the model for a plain-language summary of `changes`. The summary is
+/// posted to the dashboard, so `withheld` files are not shown to the model.
+pub(super) async fn explain_patch(
+    client: &ModelClient,
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+) -> CodexResult<String> {
+    let (details, added, removed) = format_patch_details(changes, withheld, PatchFormat::default());
+    let (details, _) = truncate_middle(&details, MAX_DIFF_BYTES);
+    let text = format!(
+        "Explain this patch to {} file(s) (+{added} -{removed}):\n\n{details}",
+        changes.len()
+    );
+    let prompt = Prompt {
+        input: vec![ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText { text }],
+        }],
+        tools: Vec::new(),
+        base_instructions_override: Some(EXPLAIN_PATCH_INSTRUCTIONS.to_string()),
+        output_schema: None,
+    };
+
+    let client = client
+        .clone()
+        .with_reasoning_effort(Some(ReasoningEffortConfig::Low));
+    let mut stream = client.stream(&prompt).await?;
+    let mut output = Vec::new();
+    loop {
+        match stream.next().await {
+            Some(Ok(ResponseEvent::OutputItemDone(item))) => output.push(item),
+            Some(Ok(ResponseEvent::Completed { .. })) => break,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(e),
+            None => {
+                return Err(CodexErr::Stream(
+                    "stream closed before response.completed".into(),
+                    None,
+                ));
+            }
+        }
+    }
+    get_last_assistant_message_from_turn(&output)
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| CodexErr::Stream("the model returned no explanation".into(), None))
+}
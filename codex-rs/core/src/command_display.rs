@@ -0,0 +1,375 @@
+//! Exec commands rendered as command lines a user can copy and paste.
+//!
+//! Commands arrive as argv, often a shell wrapping a script (`bash -lc …`,
+//! `pwsh -Command …`, `cmd /c …`). A wrapped script is shown as is, in the
+//! syntax of its shell; a plain argv is quoted for the shell commands run in
+//! on this platform: PowerShell on Windows, a POSIX shell elsewhere. The
+//! approval prompts posted to Omnara and the TUI's exec cells both render
+//! commands through [`display_command`], so what the user sees pastes back
+//! into the same shell.
+
+/// Quoting rules of the shell a command line is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellSyntax {
+    /// `sh`, `bash`, `zsh`.
+    Posix,
+    /// Windows PowerShell and `pwsh`.
+    PowerShell,
+    /// `cmd.exe`.
+    Cmd,
+}
+
+impl ShellSyntax {
+    /// The syntax of the shell commands run in on this platform.
+    pub fn host() -> Self {
+        if cfg!(windows) {
+            ShellSyntax::PowerShell
+        } else {
+            ShellSyntax::Posix
+        }
+    }
+
+    /// Language tag for a Markdown code block holding a command line.
+    pub fn fence_language(self) -> &'static str {
+        match self {
+            ShellSyntax::Posix => "bash",
+            ShellSyntax::PowerShell => "powershell",
+            ShellSyntax::Cmd => "bat",
+        }
+    }
+}
+
+/// A command line ready to show, and the shell it is written for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayCommand {
+    pub text: String,
+    pub syntax: ShellSyntax,
+}
+
+impl DisplayCommand {
+    /// The command line as a Markdown code span, delimited by more backticks
+    /// than it contains so none of it is read as Markdown.
+    pub fn code_span(&self) -> String {
+        code_span(&self.text)
+    }
+
+    /// The command line as a fenced Markdown code block tagged with its
+    /// shell's language, without a trailing newline.
+    pub fn code_block(&self) -> String {
+        let fence = "`".repeat((longest_backtick_run(&self.text) + 1).max(3));
+        format!(
+            "{fence}{}\n{}\n{fence}",
+            self.syntax.fence_language(),
+            self.text
+        )
+    }
+}
+
+/// `text` as a Markdown code span, delimited by more backticks than it
+/// contains.
+pub fn code_span(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{ticks} {text} {ticks}")
+    } else {
+        format!("{ticks}{text}{ticks}")
+    }
+}
+
+/// `command` as a command line for the shell it runs in.
+pub fn display_command(command: &[String]) -> DisplayCommand {
+    display_command_for(command, ShellSyntax::host())
+}
+
+/// Like [`display_command`], quoting a plain argv for `host`.
+pub fn display_command_for(command: &[String], host: ShellSyntax) -> DisplayCommand {
+    if let Some((syntax, script)) = unwrap_shell(command) {
+        return DisplayCommand {
+            text: script,
+            syntax,
+        };
+    }
+    DisplayCommand {
+        text: quote_command(command, host),
+        syntax: host,
+    }
+}
+
+/// `command` joined into one line, each argument quoted as `syntax` needs.
+pub fn quote_command(command: &[String], syntax: ShellSyntax) -> String {
+    match syntax {
+        ShellSyntax::Posix | ShellSyntax::Cmd => command
+            .iter()
+            .map(|arg| quote_arg(arg, syntax))
+            .collect::<Vec<_>>()
+            .join(" "),
+        ShellSyntax::PowerShell => {
+            let mut line = command
+                .iter()
+                .map(|arg| quote_arg(arg, syntax))
+                .collect::<Vec<_>>()
+                .join(" ");
+            // A quoted program name is a string to PowerShell; the call
+            // operator runs it.
+            if line.starts_with('\'') {
+                line.insert_str(0, "& ");
+            }
+            line
+        }
+    }
+}
+
+/// `arg` as one word of a `syntax` command line: bare when nothing in it is
+/// special to the shell, quoted otherwise, so `echo "hello world"` and
+/// `echo hello world` never render the same.
+pub fn quote_arg(arg: &str, syntax: ShellSyntax) -> String {
+    match syntax {
+        ShellSyntax::Posix => quote_posix(arg),
+        ShellSyntax::PowerShell => quote_powershell(arg),
+        ShellSyntax::Cmd => quote_cmd(arg),
+    }
+}
+
+/// The script and its syntax when `command` only runs a shell on a script.
+fn unwrap_shell(command: &[String]) -> Option<(ShellSyntax, String)> {
+    let (program, args) = command.split_first()?;
+    match program_name(program).as_str() {
+        "bash" | "zsh" | "sh" => match args {
+            [flag, script] if flag == "-lc" || flag == "-c" => {
+                Some((ShellSyntax::Posix, script.clone()))
+            }
+            _ => None,
+        },
+        "powershell" | "pwsh" => {
+            let position = args.iter().position(|arg| {
+                arg.eq_ignore_ascii_case("-Command") || arg.eq_ignore_ascii_case("-c")
+            })?;
+            // Only startup switches such as `-NoProfile` may precede the
+            // script, or dropping them would change what runs.
+            let switches = &args[..position];
+            let script = &args[position + 1..];
+            if script.is_empty()
+                || !switches.iter().all(|arg| {
+                    arg.eq_ignore_ascii_case("-NoProfile") || arg.eq_ignore_ascii_case("-NoLogo")
+                })
+            {
+                return None;
+            }
+            Some((ShellSyntax::PowerShell, script.join(" ")))
+        }
+        "cmd" => match args {
+            [flag, script @ ..] if flag.eq_ignore_ascii_case("/c") && !script.is_empty() => {
+                Some((ShellSyntax::Cmd, script.join(" ")))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// File name of `program` without `.exe`, lowercased, for either path
+/// separator.
+fn program_name(program: &str) -> String {
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    let name = name.to_ascii_lowercase();
+    match name.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+/// The length of the longest run of backticks in `text`, so a fence or
+/// span around it can use more.
+pub fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn quote_posix(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '_' | '-' | '.' | '/' | ':' | ',' | '+' | '=' | '@' | '%')
+        });
+    if plain {
+        arg.to_string()
+    } else {
+        // Nothing is special inside single quotes; a quote ends the string,
+        // is escaped, and starts a new one.
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn quote_powershell(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && !arg.chars().any(|c| {
+            c.is_whitespace()
+                || matches!(
+                    c,
+                    '\'' | '"'
+                        | '`'
+                        | '$'
+                        | '&'
+                        | '|'
+                        | ';'
+                        | '<'
+                        | '>'
+                        | '('
+                        | ')'
+                        | '{'
+                        | '}'
+                        | '@'
+                        | '#'
+                        | ','
+                )
+        });
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "''"))
+    }
+}
+
+fn quote_cmd(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && !arg.chars().any(|c| {
+            c.is_whitespace() || matches!(c, '"' | '&' | '|' | '<' | '>' | '^' | '(' | ')')
+        });
+    if plain {
+        arg.to_string()
+    } else {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn shell_scripts_are_shown_in_their_own_syntax() {
+        assert_eq!(
+            display_command_for(
+                &argv(&["bash", "-lc", "ls | wc -l"]),
+                ShellSyntax::PowerShell
+            ),
+            DisplayCommand {
+                text: "ls | wc -l".to_string(),
+                syntax: ShellSyntax::Posix,
+            }
+        );
+        assert_eq!(
+            display_command_for(
+                &argv(&[
+                    r"C:\Program Files\PowerShell\7\pwsh.exe",
+                    "-NoProfile",
+                    "-Command",
+                    "Get-ChildItem -Force",
+                ]),
+                ShellSyntax::Posix,
+            ),
+            DisplayCommand {
+                text: "Get-ChildItem -Force".to_string(),
+                syntax: ShellSyntax::PowerShell,
+            }
+        );
+        assert_eq!(
+            display_command_for(&argv(&["CMD.EXE", "/C", "dir", "/b"]), ShellSyntax::Posix),
+            DisplayCommand {
+                text: "dir /b".to_string(),
+                syntax: ShellSyntax::Cmd,
+            }
+        );
+        // Options other than startup switches keep the invocation whole.
+        assert_eq!(
+            display_command_for(
+                &argv(&["pwsh", "-ExecutionPolicy", "Bypass", "-c", "ls"]),
+                ShellSyntax::Posix,
+            )
+            .syntax,
+            ShellSyntax::Posix
+        );
+    }
+
+    #[test]
+    fn plain_argv_is_quoted_for_the_host_shell() {
+        let command = argv(&["foo", "bar baz", "weird&stuff"]);
+        assert_eq!(
+            quote_command(&command, ShellSyntax::Posix),
+            "foo 'bar baz' 'weird&stuff'"
+        );
+        assert_eq!(
+            quote_command(&command, ShellSyntax::PowerShell),
+            "foo 'bar baz' 'weird&stuff'"
+        );
+        assert_eq!(
+            quote_command(
+                &argv(&["git", "commit", "-m", "it's done"]),
+                ShellSyntax::PowerShell
+            ),
+            "git commit -m 'it''s done'"
+        );
+        assert_eq!(
+            quote_command(&argv(&["echo", "say \"hi\"", "a&b"]), ShellSyntax::Cmd),
+            r#"echo "say ""hi""" "a&b""#
+        );
+    }
+
+    #[test]
+    fn quoting_keeps_word_boundaries_visible() {
+        assert_eq!(
+            quote_command(&argv(&["echo", "hello world"]), ShellSyntax::Posix),
+            "echo 'hello world'"
+        );
+        assert_eq!(
+            quote_command(&argv(&["echo", "hello", "world"]), ShellSyntax::Posix),
+            "echo hello world"
+        );
+        assert_eq!(
+            quote_command(&argv(&["echo", "it's", "", "$HOME"]), ShellSyntax::Posix),
+            r"echo 'it'\''s' '' '$HOME'"
+        );
+        assert_eq!(
+            quote_arg("--name=a.b/c", ShellSyntax::Posix),
+            "--name=a.b/c"
+        );
+    }
+
+    #[test]
+    fn markdown_never_swallows_backticks() {
+        let command = DisplayCommand {
+            text: "echo `date` ```".to_string(),
+            syntax: ShellSyntax::Posix,
+        };
+        assert_eq!(command.code_span(), "```` echo `date` ``` ````");
+        assert_eq!(command.code_block(), "````bash\necho `date` ```\n````");
+        let command = DisplayCommand {
+            text: "ls".to_string(),
+            syntax: ShellSyntax::Cmd,
+        };
+        assert_eq!(command.code_span(), "`ls`");
+        assert_eq!(command.code_block(), "```bat\nls\n```");
+    }
+
+    #[test]
+    fn windows_paths_survive_powershell_quoting() {
+        let command = argv(&[
+            r"C:\Program Files\Git\bin\git.exe",
+            "-C",
+            r"D:\work\repo",
+            "status",
+        ]);
+        assert_eq!(
+            display_command_for(&command, ShellSyntax::PowerShell),
+            DisplayCommand {
+                text: r"& 'C:\Program Files\Git\bin\git.exe' -C D:\work\repo status".to_string(),
+                syntax: ShellSyntax::PowerShell,
+            }
+        );
+        assert_eq!(ShellSyntax::PowerShell.fence_language(), "powershell");
+    }
+}
@@ -0,0 +1,163 @@
+//! Near matches between a command awaiting approval and the commands already
+//! approved for the session ("always").
+//!
+//! Session approvals only cover an exact repeat, so a command that differs
+//! by one flag is asked for again. When that happens the approval prompt
+//! shows which approved command it is closest to and what differs, so the
+//! approver only has to judge the delta. Commands are compared word by word,
+//! looking inside `bash -lc <script>`.
+
+use std::collections::HashSet;
+
+use crate::command_risk::command_words;
+
+/// Most words added plus removed for a command to count as a near match.
+pub const MAX_NEAR_MATCH_EDITS: usize = 2;
+
+/// The words a command adds to and removes from an approved one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandDelta {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl CommandDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    fn edits(&self) -> usize {
+        self.added.len() + self.removed.len()
+    }
+}
+
+/// The approved command `command` differs least from, when it runs the same
+/// program and differs by at most [`MAX_NEAR_MATCH_EDITS`] words. Ties go to
+/// the command that sorts first, so the answer does not depend on set order.
+pub fn closest_approved(
+    command: &[String],
+    approved: &HashSet<Vec<String>>,
+) -> Option<Vec<String>> {
+    let words = command_words(command);
+    let program = words.first()?;
+    approved
+        .iter()
+        .filter_map(|candidate| {
+            let candidate_words = command_words(candidate);
+            if candidate_words.first() != Some(program) {
+                return None;
+            }
+            let edits = diff_words(&candidate_words, &words).edits();
+            (1..=MAX_NEAR_MATCH_EDITS)
+                .contains(&edits)
+                .then_some((edits, candidate))
+        })
+        .min()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// What `command` adds to and removes from `approved`, word by word.
+pub fn command_delta(approved: &[String], command: &[String]) -> CommandDelta {
+    diff_words(&command_words(approved), &command_words(command))
+}
+
+/// The words outside the longest common subsequence of `old` and `new`.
+fn diff_words(old: &[String], new: &[String]) -> CommandDelta {
+    // lcs[i][j]: length of the LCS of old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut delta = CommandDelta::default();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            delta.removed.push(old[i].clone());
+            i += 1;
+        } else {
+            delta.added.push(new[j].clone());
+            j += 1;
+        }
+    }
+    delta.removed.extend(old[i..].iter().cloned());
+    delta.added.extend(new[j..].iter().cloned());
+    delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn closest_command_differs_by_the_fewest_words() {
+        let approved = HashSet::from([
+            argv("git push origin main"),
+            argv("git push origin feature"),
+            argv("cargo test"),
+        ]);
+        assert_eq!(
+            closest_approved(&argv("git push --force origin main"), &approved),
+            Some(argv("git push origin main"))
+        );
+        assert_eq!(
+            command_delta(
+                &argv("git push origin main"),
+                &argv("git push --force origin main")
+            ),
+            CommandDelta {
+                added: vec!["--force".to_string()],
+                removed: Vec::new(),
+            }
+        );
+        // Ties go to the command that sorts first.
+        assert_eq!(
+            closest_approved(&argv("git push origin release"), &approved),
+            Some(argv("git push origin feature"))
+        );
+        // Another program, or too many differences, is no near match.
+        assert_eq!(
+            closest_approved(&argv("cargo build --release --locked"), &approved),
+            None
+        );
+        assert_eq!(
+            closest_approved(&argv("npm push origin main"), &approved),
+            None
+        );
+    }
+
+    #[test]
+    fn shell_scripts_are_compared_word_by_word() {
+        let approved = HashSet::from([vec![
+            "bash".to_string(),
+            "-lc".to_string(),
+            "cargo test -p codex-core".to_string(),
+        ]]);
+        let command = vec![
+            "bash".to_string(),
+            "-lc".to_string(),
+            "cargo test -p codex-tui".to_string(),
+        ];
+        let closest = closest_approved(&command, &approved).expect("near match");
+        assert_eq!(
+            command_delta(&closest, &command),
+            CommandDelta {
+                added: vec!["codex-tui".to_string()],
+                removed: vec!["codex-core".to_string()],
+            }
+        );
+    }
+}
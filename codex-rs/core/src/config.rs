@@ -2,6 +2,7 @@ use crate::config_profile::ConfigProfile;
 use crate::config_types::History;
 use crate::config_types::McpServerConfig;
 use crate::config_types::Notifications;
+use crate::config_types::Omnara;
 use crate::config_types::ReasoningSummaryFormat;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
@@ -194,6 +195,9 @@ pub struct Config {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: bool,
+
+    /// Settings for the Omnara dashboard integration.
+    pub omnara: Omnara,
 }
 
 impl Config {
@@ -341,6 +345,10 @@ pub fn write_global_mcp_servers(
                 entry["tool_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
             }
 
+            if config.require_approval {
+                entry["require_approval"] = toml_edit::value(true);
+            }
+
             doc["mcp_servers"][name.as_str()] = TomlItem::Table(entry);
         }
     }
@@ -703,6 +711,10 @@ pub struct ConfigToml {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: Option<bool>,
+
+    /// Settings for the Omnara dashboard integration.
+    #[serde(default)]
+    pub omnara: Option<Omnara>,
 }
 
 impl From<ConfigToml> for UserSavedConfig {
@@ -1051,6 +1063,7 @@ impl Config {
                 .as_ref()
                 .map(|t| t.notifications.clone())
                 .unwrap_or_default(),
+            omnara: cfg.omnara.unwrap_or_default(),
         };
         Ok(config)
     }
@@ -1293,6 +1306,7 @@ exclude_slash_tmp = true
                 env: None,
                 startup_timeout_sec: Some(Duration::from_secs(3)),
                 tool_timeout_sec: Some(Duration::from_secs(5)),
+                require_approval: true,
             },
         );
 
@@ -1305,6 +1319,7 @@ exclude_slash_tmp = true
         assert_eq!(docs.args, vec!["hello".to_string()]);
         assert_eq!(docs.startup_timeout_sec, Some(Duration::from_secs(3)));
         assert_eq!(docs.tool_timeout_sec, Some(Duration::from_secs(5)));
+        assert!(docs.require_approval);
 
         let empty = BTreeMap::new();
         write_global_mcp_servers(codex_home.path(), &empty)?;
@@ -1655,6 +1670,7 @@ model_verbosity = "high"
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
                 tui_notifications: Default::default(),
+                omnara: Default::default(),
             },
             o3_profile_config
         );
@@ -1713,6 +1729,7 @@ model_verbosity = "high"
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
+            omnara: Default::default(),
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -1786,6 +1803,7 @@ model_verbosity = "high"
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
+            omnara: Default::default(),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -1845,6 +1863,7 @@ model_verbosity = "high"
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
+            omnara: Default::default(),
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
@@ -1947,6 +1966,52 @@ trust_level = "trusted"
 
         Ok(())
     }
+
+    #[test]
+    fn test_omnara_poll_interval_overrides_default() {
+        let toml = r#"
+            [omnara]
+            poll_interval_ms = 500
+        "#;
+        let parsed: ConfigToml = toml::from_str(toml).expect("deserialize [omnara]");
+        assert_eq!(
+            parsed.omnara,
+            Some(Omnara {
+                poll_interval_ms: 500,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_omnara_digest_mirroring_parses() {
+        let toml = r#"
+            [omnara]
+            mirroring = "digest"
+        "#;
+        let parsed: ConfigToml = toml::from_str(toml).expect("deserialize [omnara]");
+        assert_eq!(
+            parsed.omnara.map(|omnara| omnara.mirroring),
+            Some(crate::config_types::OmnaraMirroring::Digest)
+        );
+    }
+
+    #[test]
+    fn test_omnara_approval_push_keeps_unset_levels() {
+        let toml = r#"
+            [omnara.approval_push]
+            low = false
+        "#;
+        let parsed: ConfigToml = toml::from_str(toml).expect("deserialize [omnara]");
+        assert_eq!(
+            parsed.omnara.map(|omnara| omnara.approval_push),
+            Some(crate::config_types::ApprovalPush {
+                low: false,
+                medium: true,
+                high: true,
+            })
+        );
+    }
 }
 
 #[cfg(test)]
@@ -3,11 +3,14 @@
 // Note this file should generally be restricted to simple struct/enum
 // definitions that do not contain business logic.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 use wildmatch::WildMatchPattern;
 
+use crate::command_risk::RiskLevel;
+
 use serde::Deserialize;
 use serde::Deserializer;
 use serde::Serialize;
@@ -34,6 +37,10 @@ pub struct McpServerConfig {
     /// Default timeout for MCP tool calls initiated via this server.
     #[serde(default, with = "option_duration_secs")]
     pub tool_timeout_sec: Option<Duration>,
+
+    /// Ask the user before each call to one of this server's tools.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_approval: bool,
 }
 
 impl<'de> Deserialize<'de> for McpServerConfig {
@@ -54,6 +61,8 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             startup_timeout_ms: Option<u64>,
             #[serde(default, with = "option_duration_secs")]
             tool_timeout_sec: Option<Duration>,
+            #[serde(default)]
+            require_approval: bool,
         }
 
         let raw = RawMcpServerConfig::deserialize(deserializer)?;
@@ -73,6 +82,7 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             env: raw.env,
             startup_timeout_sec,
             tool_timeout_sec: raw.tool_timeout_sec,
+            require_approval: raw.require_approval,
         })
     }
 }
@@ -155,6 +165,852 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Settings for the Omnara dashboard integration (`[omnara]` in config.toml).
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Omnara {
+    /// Base URL of a self-hosted Omnara server, e.g.
+    /// `"https://omnara.internal.example.com"`. `OMNARA_API_URL` takes
+    /// precedence. Defaults to the hosted service.
+    pub api_url: Option<String>,
+
+    /// Project the dashboard groups this session under. Defaults to the git
+    /// repository's name, or the working directory's.
+    pub project: Option<String>,
+
+    /// Environment label sent with the session, e.g. `"ci"` or `"staging"`.
+    pub environment: Option<String>,
+
+    /// Labels the session starts with, for filtering in the dashboard
+    /// (letters, digits, and `-_.:/`). `/tag add <label>` adds more.
+    pub tags: Vec<String>,
+
+    /// Interval between polls for remote input right after input is requested
+    /// or any other activity. Defaults to 2000ms.
+    pub poll_interval_ms: u64,
+
+    /// Interval between polls once the session has been quiet for longer than
+    /// `fast_poll_window_ms`. Defaults to 30000ms.
+    pub idle_poll_interval_ms: u64,
+
+    /// How long to keep polling at `poll_interval_ms` after the last activity.
+    /// Defaults to 30000ms.
+    pub fast_poll_window_ms: u64,
+
+    /// Hold remote messages that arrive while the agent is mid-turn and submit
+    /// them when the turn finishes, like locally queued input. A remote
+    /// `/now <message>` interrupts the turn instead. Defaults to true.
+    pub queue_remote_input: bool,
+
+    /// Refuse dashboard messages larger than this many KiB, with a note
+    /// saying so, instead of passing them to the agent. Defaults to 64.
+    pub max_remote_message_kb: u64,
+
+    /// Approval prompts posted to the dashboard at once; later ones are
+    /// held until an earlier one is answered. `1` serializes them, `0` (the
+    /// default) posts every prompt right away.
+    pub max_outstanding_approvals: usize,
+
+    /// Exec approvals arriving within this many milliseconds of the first
+    /// one are posted as one numbered prompt, answered all at once or per
+    /// command ("yes to 1, 3; no to 2"). `0` (the default) posts each on
+    /// its own.
+    pub exec_approval_batch_ms: u64,
+
+    /// How far messages from the Omnara workspace are trusted: `trusted`
+    /// forwards them as they are, `shared` holds those that look like prompt
+    /// injection until confirmed in the terminal, and `untrusted` holds
+    /// every one. Defaults to `trusted`.
+    pub workspace_trust: OmnaraWorkspaceTrust,
+
+    /// Stop polling after this many minutes without activity and post a
+    /// "session idle" note; local activity resumes polling. `0` disables.
+    /// Defaults to 60.
+    pub idle_suspend_minutes: u64,
+
+    /// End the Omnara session after this many hours without activity. `0`
+    /// (the default) keeps suspended sessions open indefinitely.
+    pub idle_end_session_hours: u64,
+
+    /// Extra replies accepted for approval prompts, e.g. localized words.
+    pub approval_synonyms: ApprovalSynonyms,
+
+    /// What to do when an approval prompt goes unanswered
+    /// (`[omnara.approval_escalation]`). Off by default.
+    pub approval_escalation: ApprovalEscalation,
+
+    /// Urgent notes when a turn runs long or an approval waits too long
+    /// (`[omnara.alerts]`). Off by default.
+    pub alerts: OmnaraAlerts,
+
+    /// Which approval prompts ask the Omnara app for a push notification,
+    /// by risk level (`[omnara.approval_push]`). All of them by default.
+    pub approval_push: ApprovalPush,
+
+    /// How much of each turn is mirrored to the dashboard. Defaults to `full`.
+    pub mirroring: OmnaraMirroring,
+
+    /// In `full` mirroring, also post a summary note (commands, files
+    /// changed, tool calls, tokens, duration) when each turn ends. `digest`
+    /// mode always posts it, in place of the per-event notes. Defaults to
+    /// false.
+    pub turn_summary: bool,
+
+    /// How web searches and page visits are mirrored: `full` (queries,
+    /// result pages, and URLs), `hide_urls` (URLs reduced to their host), or
+    /// `off`. Defaults to `full`.
+    pub web_activity: OmnaraWebActivity,
+
+    /// Gitignore-style patterns for files whose content is never uploaded
+    /// (e.g. `secrets/**`, `*.pem`). Matching files are listed by name with
+    /// line counts, but diffs, patch details, and the output of commands
+    /// naming them are replaced by a placeholder.
+    pub withhold_paths: Vec<String>,
+
+    /// Language of approval prompts, status words, and session lifecycle
+    /// notes posted to the dashboard. Replies in that language are accepted
+    /// alongside English ones. Defaults to `en`.
+    pub locale: OmnaraLocale,
+
+    /// Markup of the messages posted to the dashboard, for relays to systems
+    /// that render something other than CommonMark. Defaults to
+    /// `commonmark`.
+    pub markdown_dialect: OmnaraMarkdownDialect,
+
+    /// Gitignore-style patterns kept out of the session diff (e.g. lockfiles
+    /// or generated code), in addition to each repository's `.codexignore`.
+    pub diff_exclude: Vec<String>,
+
+    /// A file whose diff exceeds this size is replaced in the session diff by
+    /// a stat-only placeholder. Defaults to 256 KiB.
+    pub diff_max_file_kb: u64,
+
+    /// Cap on the whole session diff; files past it become placeholders too.
+    /// Defaults to 1024 KiB.
+    pub diff_max_total_kb: u64,
+
+    /// Patch notes and approvals show an updated file whose hunks each
+    /// change at most this many lines as `line N: old → new` entries instead
+    /// of a fenced diff; 0 always uses fenced diffs. Defaults to 3.
+    pub compact_patch_lines: usize,
+
+    /// Highlight the words that changed within edited lines instead of
+    /// showing whole removed and added lines. Defaults to `off`.
+    pub word_diff: OmnaraWordDiff,
+
+    /// Diff the working trees of checked-out git submodules too, appending a
+    /// labeled section per dirty submodule. Defaults to true.
+    pub diff_submodules: bool,
+
+    /// Submodule paths (relative to the superproject root) never diffed,
+    /// e.g. a vendored toolchain too large to scan every turn.
+    pub diff_skip_submodules: Vec<String>,
+
+    /// Upload a snapshot of the terminal history whenever a turn ends in an
+    /// error, as `/snapshot` does on demand. Defaults to false.
+    pub snapshot_on_error: bool,
+
+    /// Post a note for stream retries, turn errors, and sandbox failures,
+    /// at most a few of each kind per minute. Defaults to true.
+    pub error_notes: bool,
+
+    /// Send a W3C `traceparent` header with each Omnara request, linking it
+    /// to the running turn's trace. Defaults to false.
+    pub trace_context: bool,
+
+    /// Gzip large request bodies (agent messages carrying diffs, attachment
+    /// uploads). Falls back to plain JSON for the rest of the session if the
+    /// server answers 415. Defaults to true.
+    pub compress_requests: bool,
+
+    /// Offer the model an `ask_user` tool whose questions are posted to the
+    /// dashboard as input requests. Only takes effect when an Omnara API
+    /// key is configured. Defaults to true.
+    pub ask_user_tool: bool,
+
+    /// How long an `ask_user` call waits for an answer before the model is
+    /// told to proceed on its own. Defaults to 900s.
+    pub ask_user_timeout_secs: u64,
+
+    /// Check run when a remote approver replies `/preview` to a patch
+    /// approval, e.g. `["cargo", "check"]`. It runs in a scratch worktree
+    /// with the patch applied. Empty (the default) only checks that the
+    /// patch applies.
+    pub preview_command: Vec<String>,
+
+    /// How long the preview check may run before it is killed. Defaults to
+    /// 300s.
+    pub preview_timeout_secs: u64,
+
+    /// Post a progress note for a command still running after this many
+    /// seconds, refreshed in place at the same interval with the elapsed
+    /// time and latest output. `0` disables. Defaults to 30.
+    pub exec_progress_secs: u64,
+
+    /// Show the colors of command output in exec notes, as HTML spans, when
+    /// the server supports structured messages and `markdown_dialect` is
+    /// `commonmark` or `gfm`. Escape sequences are stripped otherwise.
+    /// Defaults to false.
+    pub exec_output_colors: bool,
+
+    /// Export bridge metrics (`[omnara.metrics]`). Needs a build with the
+    /// `omnara-metrics` feature.
+    pub metrics: OmnaraMetricsConfig,
+
+    /// HTTP client tuning for Omnara requests (`[omnara.http]`).
+    pub http: OmnaraHttpConfig,
+
+    /// When to stop sending to an unreachable server
+    /// (`[omnara.circuit_breaker]`).
+    pub circuit_breaker: OmnaraCircuitBreaker,
+
+    /// Steps applied, in order, to every message posted to the dashboard
+    /// (`[[omnara.transformers]]`): redaction, truncation, emoji stripping,
+    /// and regex rewrites. None by default.
+    pub transformers: Vec<OmnaraTransformer>,
+
+    /// Local HTTP control server (`[omnara.local_control]`), usable without
+    /// an Omnara account.
+    pub local_control: OmnaraLocalControlConfig,
+
+    /// Do-not-disturb schedule (`[omnara.quiet_hours]`). Off unless `ranges`
+    /// is set.
+    pub quiet_hours: OmnaraQuietHours,
+
+    /// Report posted when the session ends (`[omnara.session_report]`).
+    pub session_report: OmnaraSessionReport,
+
+    /// Transcription of voice notes sent from the Omnara app
+    /// (`[omnara.voice]`). Off unless a `transcriber` is set.
+    pub voice: OmnaraVoice,
+
+    /// Files attached to dashboard messages, saved into the workspace
+    /// (`[omnara.uploads]`). Off by default.
+    pub uploads: OmnaraUploads,
+
+    /// Prompts submitted on a schedule, by name
+    /// (`[omnara.schedules.<name>]`), e.g. running the test suite every
+    /// morning. None by default.
+    pub schedules: BTreeMap<String, OmnaraSchedule>,
+
+    /// Named credentials for separate Omnara workspaces
+    /// (`[omnara.workspaces.<name>]`), picked per project by a
+    /// `.codex/omnara.toml` or the working directory. Without a match the
+    /// default `OMNARA_API_KEY` or keychain key is used. None by default.
+    pub workspaces: BTreeMap<String, OmnaraWorkspace>,
+}
+
+/// Credentials for one Omnara workspace (`[omnara.workspaces.<name>]`).
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct OmnaraWorkspace {
+    /// Environment variable holding the workspace's API key, e.g.
+    /// `"OMNARA_WORK_API_KEY"`.
+    pub api_key_env: String,
+
+    /// Server of the workspace; overrides `omnara.api_url`, while
+    /// `OMNARA_API_URL` still takes precedence.
+    #[serde(default)]
+    pub api_url: Option<String>,
+
+    /// Working directories that select this workspace, as globs where `*`
+    /// also matches `/` and a leading `~` is the home directory. A
+    /// directory matches when it or any parent does, so `"~/work/acme"`
+    /// covers everything below it.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+/// A prompt submitted on a schedule (`[omnara.schedules.<name>]`), as if
+/// typed, with the run announced on the dashboard.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct OmnaraSchedule {
+    /// When to run, as a cron expression (`minute hour day-of-month month
+    /// day-of-week`, e.g. `"0 9 * * 1-5"`) or `@hourly`, `@daily`,
+    /// `@weekly`, `@monthly`.
+    pub cron: String,
+
+    /// The prompt submitted.
+    pub prompt: String,
+
+    /// Zone the expression is read in: `"local"` (the default), `"UTC"`, or
+    /// a fixed offset such as `"+02:00"`.
+    #[serde(default = "default_schedule_timezone")]
+    pub timezone: String,
+}
+
+fn default_schedule_timezone() -> String {
+    "local".to_string()
+}
+
+/// Voice notes sent from the Omnara app (`[omnara.voice]`). Each one is
+/// downloaded, transcribed, and forwarded as user input marked
+/// `(from voice)`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OmnaraVoice {
+    /// How voice notes are transcribed. Unset (the default) ignores them.
+    pub transcriber: Option<OmnaraTranscriber>,
+
+    /// Voice notes larger than this many MiB are not downloaded. Defaults
+    /// to 25.
+    pub max_audio_mb: u64,
+
+    /// How long one transcription may take. Defaults to 120s.
+    pub timeout_secs: u64,
+}
+
+impl Default for OmnaraVoice {
+    fn default() -> Self {
+        Self {
+            transcriber: None,
+            max_audio_mb: 25,
+            timeout_secs: 120,
+        }
+    }
+}
+
+/// Files attached to dashboard messages (`[omnara.uploads]`). Each one is
+/// downloaded into `dir`, a confirmation note names its path, and the
+/// message forwarded to the agent mentions it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OmnaraUploads {
+    /// Save attached files. Defaults to false, which ignores them.
+    pub enabled: bool,
+
+    /// Directory the files are saved in, relative to the working
+    /// directory. It must stay inside the workspace. Defaults to
+    /// `uploads`.
+    pub dir: PathBuf,
+
+    /// Files larger than this many MiB are not downloaded. Defaults to 25.
+    pub max_mb: u64,
+}
+
+impl Default for OmnaraUploads {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from("uploads"),
+            max_mb: 25,
+        }
+    }
+}
+
+/// Backend turning a voice note into text (`[omnara.voice.transcriber]`).
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OmnaraTranscriber {
+    /// A local whisper.cpp build, run as `<binary> -m <model> -f <audio>
+    /// -nt`; the transcript is read from its standard output. The audio is
+    /// passed in the format it was recorded in, so the binary must be able
+    /// to read it.
+    WhisperCpp {
+        /// Defaults to `whisper-cli` on the `PATH`.
+        #[serde(default = "default_whisper_binary")]
+        binary: PathBuf,
+        /// A ggml model file, e.g. `ggml-base.en.bin`.
+        model: PathBuf,
+        /// Spoken language, e.g. `"de"`, or `"auto"` to detect it. Defaults
+        /// to whisper's own default.
+        #[serde(default)]
+        language: Option<String>,
+    },
+    /// An OpenAI-compatible transcription endpoint: a multipart `POST` of
+    /// `file` and `model`, answered with `{"text": ...}`.
+    Api {
+        /// Defaults to OpenAI's `/v1/audio/transcriptions`.
+        #[serde(default = "default_transcription_url")]
+        url: String,
+        /// Defaults to `whisper-1`.
+        #[serde(default = "default_transcription_model")]
+        model: String,
+        /// Environment variable holding the bearer token. Defaults to
+        /// `OPENAI_API_KEY`.
+        #[serde(default = "default_transcription_api_key_env")]
+        api_key_env: String,
+        /// Spoken language as an ISO-639-1 code; detected when unset.
+        #[serde(default)]
+        language: Option<String>,
+    },
+}
+
+fn default_whisper_binary() -> PathBuf {
+    PathBuf::from("whisper-cli")
+}
+
+fn default_transcription_url() -> String {
+    "https://api.openai.com/v1/audio/transcriptions".to_string()
+}
+
+fn default_transcription_model() -> String {
+    "whisper-1".to_string()
+}
+
+fn default_transcription_api_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+/// Report posted when an Omnara session ends (`[omnara.session_report]`):
+/// duration, turns, commands, files changed, tokens, and a link to the
+/// uploaded transcript.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct OmnaraSessionReport {
+    /// Post the report at shutdown. Defaults to false.
+    pub enabled: bool,
+
+    /// Price of a million input tokens, in USD. With both prices set the
+    /// report estimates the session's cost.
+    pub input_usd_per_million_tokens: Option<f64>,
+
+    /// Price of a million output tokens, in USD.
+    pub output_usd_per_million_tokens: Option<f64>,
+}
+
+/// HTTP client tuning for Omnara requests (`[omnara.http]`). A `0` duration
+/// disables that limit.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OmnaraHttpConfig {
+    /// Time allowed to establish a connection. Defaults to 10s.
+    pub connect_timeout_secs: u64,
+
+    /// Time allowed for a whole request, including an attachment upload.
+    /// Defaults to 120s.
+    pub request_timeout_secs: u64,
+
+    /// Idle pooled connections are closed after this long. Defaults to 90s.
+    pub pool_idle_timeout_secs: u64,
+
+    /// Idle connections kept open per host. Defaults to 4.
+    pub pool_max_idle_per_host: usize,
+
+    /// Ping interval that keeps an HTTP/2 connection alive between polls.
+    /// Defaults to 30s.
+    pub http2_keep_alive_secs: u64,
+
+    /// Proxy for every Omnara request, e.g. `"http://proxy.corp:3128"`. When
+    /// unset, `HTTPS_PROXY`, `HTTP_PROXY`, and `NO_PROXY` apply.
+    pub proxy: Option<String>,
+
+    /// PEM files of extra root certificates to trust, e.g. the CA of a
+    /// TLS-intercepting corporate proxy.
+    pub ca_certificates: Vec<PathBuf>,
+}
+
+impl Default for OmnaraHttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            request_timeout_secs: 120,
+            pool_idle_timeout_secs: 90,
+            pool_max_idle_per_host: 4,
+            http2_keep_alive_secs: 30,
+            proxy: None,
+            ca_certificates: Vec::new(),
+        }
+    }
+}
+
+/// Pausing Omnara requests while the server is unreachable
+/// (`[omnara.circuit_breaker]`). Agent messages are queued meanwhile and
+/// sent once it answers again.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OmnaraCircuitBreaker {
+    /// Failed requests in a row (network errors or 5xx answers) that pause
+    /// requests. `0` disables the breaker. Defaults to 5.
+    pub failure_threshold: u32,
+
+    /// How long requests stay paused before the server is tried again.
+    /// Defaults to 2.
+    pub open_minutes: u64,
+}
+
+impl Default for OmnaraCircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_minutes: 2,
+        }
+    }
+}
+
+/// One step of the outbound message pipeline (`[[omnara.transformers]]`),
+/// chosen by its `kind`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct OmnaraTransformer {
+    #[serde(flatten)]
+    pub step: OmnaraTransformStep,
+
+    /// Messages the step applies to; every kind when empty (the default).
+    #[serde(default)]
+    pub applies_to: Vec<OmnaraMessageKind>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OmnaraTransformStep {
+    /// Replace likely secrets (API keys, tokens, private keys), and matches
+    /// of the extra `patterns` (regexes), with `[REDACTED]`.
+    Redact {
+        #[serde(default)]
+        patterns: Vec<String>,
+    },
+    /// Cut the message to `max_lines` lines of at most `max_line_chars`
+    /// characters and `max_chars` in total; unset limits do not apply.
+    Truncate {
+        max_lines: Option<usize>,
+        max_line_chars: Option<usize>,
+        max_chars: Option<usize>,
+    },
+    /// Remove emoji.
+    StripEmoji,
+    /// Replace matches of the regex `pattern` with `replacement`, which may
+    /// refer to groups as `$1` or `${name}`.
+    Rewrite {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+    },
+}
+
+/// Kinds of messages posted to the dashboard, for
+/// [`OmnaraTransformer::applies_to`].
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OmnaraMessageKind {
+    /// Agent notes: output, tool calls, diffs, summaries.
+    Note,
+    /// Agent messages that ask for a reply, such as approval prompts.
+    Prompt,
+    /// Edits of a posted note, such as live progress.
+    Update,
+    /// Local user messages mirrored to the dashboard.
+    User,
+}
+
+/// Where to export Omnara bridge metrics (`[omnara.metrics]`). Both
+/// exporters are off unless configured.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OmnaraMetricsConfig {
+    /// Serve Prometheus text metrics on `http://<addr>/metrics`, e.g.
+    /// `"127.0.0.1:9464"`.
+    pub prometheus_listen: Option<String>,
+
+    /// OTLP/HTTP metrics endpoint to push to, e.g.
+    /// `"http://collector:4318/v1/metrics"`.
+    pub otlp_endpoint: Option<String>,
+
+    /// Interval between OTLP pushes. Defaults to 60s.
+    pub otlp_interval_secs: u64,
+}
+
+impl Default for OmnaraMetricsConfig {
+    fn default() -> Self {
+        Self {
+            prometheus_listen: None,
+            otlp_endpoint: None,
+            otlp_interval_secs: 60,
+        }
+    }
+}
+
+/// Local HTTP control server (`[omnara.local_control]`). Off unless `listen`
+/// is set.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct OmnaraLocalControlConfig {
+    /// Address to serve the control API on, e.g. `"127.0.0.1:8765"`. Every
+    /// request must carry `Authorization: Bearer <token>`, where the token
+    /// comes from `CODEX_LOCAL_CONTROL_TOKEN` or is generated and shown at
+    /// session start.
+    pub listen: Option<String>,
+}
+
+/// Do-not-disturb schedule for the Omnara bridge (`[omnara.quiet_hours]`).
+/// While it is on, notes are held for a digest posted when it ends, and
+/// approval prompts follow `approvals`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OmnaraQuietHours {
+    /// Time ranges as `"HH:MM-HH:MM"`, e.g. `["22:00-07:00"]`; a range may
+    /// wrap past midnight, and `24:00` ends a range at midnight.
+    pub ranges: Vec<String>,
+
+    /// Zone the ranges are read in: `"local"` (the default), `"UTC"`, or a
+    /// fixed offset such as `"+02:00"`.
+    pub timezone: String,
+
+    /// What happens to approval prompts. Defaults to `silent`.
+    pub approvals: QuietHoursApprovals,
+
+    /// Decision applied under `approvals = "default"`.
+    pub default_decision: EscalationDecision,
+
+    /// Handle high-risk approvals as outside quiet hours. Defaults to true.
+    pub high_risk_override: bool,
+}
+
+impl Default for OmnaraQuietHours {
+    fn default() -> Self {
+        Self {
+            ranges: Vec::new(),
+            timezone: "local".to_string(),
+            approvals: QuietHoursApprovals::default(),
+            default_decision: EscalationDecision::default(),
+            high_risk_override: true,
+        }
+    }
+}
+
+/// How approval prompts are handled during quiet hours
+/// (`omnara.quiet_hours.approvals`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuietHoursApprovals {
+    /// Post the prompt without a push notification or escalation and keep
+    /// it pending.
+    #[default]
+    Silent,
+    /// Resolve it with `default_decision` without posting it.
+    Default,
+}
+
+/// Mirroring mode for the Omnara dashboard (`omnara.mirroring`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OmnaraMirroring {
+    /// Post every agent message and a note per command, patch, and tool call.
+    #[default]
+    Full,
+    /// Accumulate a turn's intermediate events and post one consolidated note
+    /// (commands run, files changed, message excerpts) when it ends.
+    Digest,
+}
+
+/// How web activity is mirrored to the Omnara dashboard
+/// (`omnara.web_activity`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OmnaraWebActivity {
+    /// Search queries, result pages, and opened URLs.
+    #[default]
+    Full,
+    /// Queries and page titles, with URLs reduced to their host.
+    HideUrls,
+    /// No notes for web activity.
+    Off,
+}
+
+/// Intra-line highlighting of updated files in patch notes and approvals
+/// (`omnara.word_diff`), like `git diff --word-diff`.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OmnaraWordDiff {
+    /// Fenced line diffs.
+    #[default]
+    Off,
+    /// Changed lines merged into one, with `[-removed-]` and `{+added+}`
+    /// around the words that differ.
+    Markers,
+    /// The same in a `<pre>` block with `<del>` / `<ins>` spans, for
+    /// dashboards that render HTML in messages.
+    Html,
+}
+
+/// How far messages typed on the Omnara dashboard are trusted
+/// (`omnara.workspace_trust`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OmnaraWorkspaceTrust {
+    /// Forward every message.
+    #[default]
+    Trusted,
+    /// Hold messages that look like prompt injection for local confirmation.
+    Shared,
+    /// Hold every message for local confirmation.
+    Untrusted,
+}
+
+/// Language of the text posted to the Omnara dashboard (`omnara.locale`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OmnaraLocale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+/// Markup of the messages posted to the Omnara dashboard
+/// (`omnara.markdown_dialect`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OmnaraMarkdownDialect {
+    /// CommonMark; tables become aligned code blocks.
+    #[default]
+    CommonMark,
+    /// GitHub-flavored Markdown, tables included.
+    Gfm,
+    /// Slack mrkdwn.
+    Slack,
+    /// Plain text.
+    Plain,
+}
+
+/// Additional replies mapped to each approval option
+/// (`[omnara.approval_synonyms]`). Matched case-insensitively, ignoring
+/// trailing punctuation.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ApprovalSynonyms {
+    pub yes: Vec<String>,
+    pub always: Vec<String>,
+    pub no: Vec<String>,
+}
+
+/// Escalation chain for unanswered approval prompts
+/// (`[omnara.approval_escalation]`). Each delay counts from when the prompt
+/// was sent; 0 disables that step.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ApprovalEscalation {
+    /// Re-send the prompt with push, email, and SMS notifications.
+    pub remind_after_minutes: u64,
+
+    /// POST a JSON `{"text": ...}` notice to this URL (e.g. a Slack incoming
+    /// webhook) after `webhook_after_minutes`.
+    pub webhook_url: Option<String>,
+    pub webhook_after_minutes: u64,
+
+    /// Resolve the approval with `default_decision`.
+    pub default_after_minutes: u64,
+    pub default_decision: EscalationDecision,
+}
+
+/// Alerts for sessions that need attention (`[omnara.alerts]`): an urgent
+/// note once a turn has run `turn_minutes`, or an approval prompt has gone
+/// unanswered for `approval_minutes`; 0 disables that alert.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct OmnaraAlerts {
+    pub turn_minutes: u64,
+    pub approval_minutes: u64,
+
+    /// Also POST a JSON `{"text": ...}` notice to this URL (e.g. a Slack
+    /// incoming webhook) for each alert.
+    pub webhook_url: Option<String>,
+}
+
+/// Push notifications for exec and patch approval prompts, per risk level
+/// (`[omnara.approval_push]`). Exec prompts take the command's risk
+/// classification, sandbox escalations count as high, and patches as medium
+/// (high when they need write access outside the workspace). A level set to
+/// false sends its prompts without a push.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct ApprovalPush {
+    pub low: bool,
+    pub medium: bool,
+    pub high: bool,
+}
+
+impl Default for ApprovalPush {
+    fn default() -> Self {
+        Self {
+            low: true,
+            medium: true,
+            high: true,
+        }
+    }
+}
+
+impl ApprovalPush {
+    /// Whether a prompt of risk `level` asks for a push notification.
+    pub fn notifies(&self, level: RiskLevel) -> bool {
+        match level {
+            RiskLevel::Low => self.low,
+            RiskLevel::Medium => self.medium,
+            RiskLevel::High => self.high,
+        }
+    }
+}
+
+/// Decision applied when an escalated approval is still unanswered.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EscalationDecision {
+    Approve,
+    /// Deny the request and let the turn continue.
+    #[default]
+    Deny,
+    /// Deny the request and stop the turn.
+    Abort,
+}
+
+impl Default for Omnara {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            project: None,
+            environment: None,
+            tags: Vec::new(),
+            poll_interval_ms: 2_000,
+            idle_poll_interval_ms: 30_000,
+            fast_poll_window_ms: 30_000,
+            queue_remote_input: true,
+            max_remote_message_kb: 64,
+            max_outstanding_approvals: 0,
+            exec_approval_batch_ms: 0,
+            workspace_trust: OmnaraWorkspaceTrust::default(),
+            idle_suspend_minutes: 60,
+            idle_end_session_hours: 0,
+            approval_synonyms: ApprovalSynonyms::default(),
+            approval_escalation: ApprovalEscalation::default(),
+            alerts: OmnaraAlerts::default(),
+            approval_push: ApprovalPush::default(),
+            mirroring: OmnaraMirroring::default(),
+            turn_summary: false,
+            web_activity: OmnaraWebActivity::default(),
+            withhold_paths: Vec::new(),
+            locale: OmnaraLocale::default(),
+            markdown_dialect: OmnaraMarkdownDialect::default(),
+            diff_exclude: Vec::new(),
+            diff_max_file_kb: 256,
+            diff_max_total_kb: 1024,
+            compact_patch_lines: 3,
+            word_diff: OmnaraWordDiff::default(),
+            diff_submodules: true,
+            diff_skip_submodules: Vec::new(),
+            snapshot_on_error: false,
+            error_notes: true,
+            trace_context: false,
+            compress_requests: true,
+            ask_user_tool: true,
+            ask_user_timeout_secs: 900,
+            preview_command: Vec::new(),
+            preview_timeout_secs: 300,
+            exec_progress_secs: 30,
+            exec_output_colors: false,
+            metrics: OmnaraMetricsConfig::default(),
+            http: OmnaraHttpConfig::default(),
+            circuit_breaker: OmnaraCircuitBreaker::default(),
+            transformers: Vec::new(),
+            local_control: OmnaraLocalControlConfig::default(),
+            quiet_hours: OmnaraQuietHours::default(),
+            session_report: OmnaraSessionReport::default(),
+            voice: OmnaraVoice::default(),
+            uploads: OmnaraUploads::default(),
+            schedules: BTreeMap::new(),
+            workspaces: BTreeMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged)]
 pub enum Notifications {
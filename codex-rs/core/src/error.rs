@@ -108,6 +108,11 @@ pub enum CodexErr {
     #[error("unsupported operation: {0}")]
     UnsupportedOperation(String),
 
+    /// Omnara requests are paused after repeated failures
+    /// (see [`crate::omnara_circuit`]).
+    #[error("the Omnara server is unreachable; requests are paused")]
+    OmnaraUnavailable,
+
     // -----------------------------------------------------------------
     // Automatic conversions for common external error types
     // -----------------------------------------------------------------
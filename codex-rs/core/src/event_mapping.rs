@@ -101,16 +101,39 @@ pub(crate) fn map_response_item_to_event_messages(
             events
         }
 
-        ResponseItem::WebSearchCall { id, action, .. } => match action {
-            WebSearchAction::Search { query } => {
-                let call_id = id.clone().unwrap_or_else(|| "".to_string());
-                vec![EventMsg::WebSearchEnd(WebSearchEndEvent {
+        ResponseItem::WebSearchCall { id, action, .. } => {
+            let call_id = id.clone().unwrap_or_else(|| "".to_string());
+            match action {
+                WebSearchAction::Search { query, sources } => {
+                    vec![EventMsg::WebSearchEnd(WebSearchEndEvent {
+                        call_id,
+                        query: query.clone(),
+                        url: None,
+                        results: sources.clone(),
+                    })]
+                }
+                WebSearchAction::OpenPage { url: Some(url) } => {
+                    vec![EventMsg::WebSearchEnd(WebSearchEndEvent {
+                        call_id,
+                        query: String::new(),
+                        url: Some(url.clone()),
+                        results: Vec::new(),
+                    })]
+                }
+                WebSearchAction::Find {
+                    url: Some(url),
+                    pattern,
+                } => vec![EventMsg::WebSearchEnd(WebSearchEndEvent {
                     call_id,
-                    query: query.clone(),
-                })]
+                    query: pattern.clone().unwrap_or_default(),
+                    url: Some(url.clone()),
+                    results: Vec::new(),
+                })],
+                WebSearchAction::OpenPage { url: None }
+                | WebSearchAction::Find { url: None, .. }
+                | WebSearchAction::Other => Vec::new(),
             }
-            WebSearchAction::Other => Vec::new(),
-        },
+        }
 
         // Variants that require side effects are handled by higher layers and do not emit events here.
         ResponseItem::FunctionCall { .. }
@@ -164,4 +187,23 @@ mod tests {
             other => panic!("expected UserMessage, got {other:?}"),
         }
     }
+
+    #[test]
+    fn maps_browsing_actions_to_web_search_end_events() {
+        let item: ResponseItem = serde_json::from_value(serde_json::json!({
+            "type": "web_search_call",
+            "id": "ws_1",
+            "status": "completed",
+            "action": {"type": "find", "url": "https://docs.rs/tokio", "pattern": "spawn"},
+        }))
+        .expect("web search call");
+
+        let events = map_response_item_to_event_messages(&item, false);
+        match events.as_slice() {
+            [EventMsg::WebSearchEnd(ev)] => {
+                assert_eq!(ev.summary(), "Searched https://docs.rs/tokio for: spawn");
+            }
+            other => panic!("expected WebSearchEnd, got {other:?}"),
+        }
+    }
 }
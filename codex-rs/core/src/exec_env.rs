@@ -15,6 +15,31 @@ pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
     populate_env(std::env::vars(), policy)
 }
 
+/// Name patterns of variables that usually hold credentials: the default
+/// excludes, plus passwords, auth headers, cookies, sessions, private keys,
+/// and personal access tokens (`GITHUB_PAT`).
+const SECRET_NAME_PATTERNS: &[&str] = &[
+    "*KEY*",
+    "*SECRET*",
+    "*TOKEN*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*CREDENTIAL*",
+    "*AUTH*",
+    "*COOKIE*",
+    "*SESSION*",
+    "*PRIVATE*",
+    "*_PAT",
+];
+
+/// Whether the variable `name` likely holds a credential, so its value must
+/// not be shown.
+pub fn is_secret_name(name: &str) -> bool {
+    SECRET_NAME_PATTERNS
+        .iter()
+        .any(|pattern| EnvironmentVariablePattern::new_case_insensitive(pattern).matches(name))
+}
+
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
 where
     I: IntoIterator<Item = (String, String)>,
@@ -101,6 +126,22 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn secret_names_match_case_insensitively() {
+        assert!(is_secret_name("OPENAI_API_KEY"));
+        assert!(is_secret_name("github_token"));
+        assert!(is_secret_name("DB_PASSWORD"));
+        assert!(is_secret_name("AWS_SHARED_CREDENTIALS_FILE"));
+        assert!(is_secret_name("GITHUB_AUTH"));
+        assert!(is_secret_name("GITHUB_PAT"));
+        assert!(is_secret_name("SITE_COOKIE"));
+        assert!(is_secret_name("FLASK_SESSION"));
+        assert!(is_secret_name("SSH_PRIVATE"));
+        assert!(!is_secret_name("PATH"));
+        assert!(!is_secret_name("PYTHONPATH"));
+        assert!(!is_secret_name("RUST_LOG"));
+    }
+
     #[test]
     fn test_include_only() {
         let vars = make_vars(&[("PATH", "/usr/bin"), ("FOO", "bar")]);
@@ -1,19 +1,331 @@
+use regex_lite::Regex;
 use sha1::Digest;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
-use std::time::SystemTime;
+
+use crate::config_types::Omnara as OmnaraConfig;
+use crate::patch_ledger::PatchLedger;
+use crate::protocol::FileChange;
+use crate::session_rollback::RollbackFile;
+use crate::session_rollback::RollbackPlan;
+use crate::vcs_diff_tracker::VcsDiffTracker;
+use crate::vcs_diff_tracker::open_tracker;
 
 /// Tracks git changes from an initial state through a session and can produce a
-/// combined unified diff (committed + uncommitted) plus untracked files created
-/// after the tracker was initialized. Mirrors the behavior of the Python
-/// GitDiffTracker used elsewhere.
+/// combined unified diff (committed + uncommitted) plus untracked files that
+/// were not already present when the tracker was initialized. Mirrors the
+/// behavior of the Python GitDiffTracker used elsewhere.
 #[derive(Debug)]
 pub struct GitDiffTracker {
     enabled: bool,
     cwd: Option<PathBuf>,
     initial_git_hash: Option<String>,
-    session_start_time: SystemTime,
+    /// Untracked paths (relative to the repository root) at initialization;
+    /// only untracked files outside this snapshot are reported.
+    initial_untracked: HashSet<String>,
     last_diff_hash: Option<String>,
+    /// Exclude patterns from config; `.codexignore` is re-read on every diff.
+    config_excludes: Vec<String>,
+    limits: DiffLimits,
+    submodules: SubmoduleDiffs,
+    /// Trackers for the checked-out submodules, keyed by their path relative
+    /// to the repository root; each starts from its own `HEAD`.
+    submodule_trackers: Vec<(String, GitDiffTracker)>,
+}
+
+/// Size guards for [`GitDiffTracker::get_diff`]. A file whose diff is larger
+/// than `max_file_bytes`, or that would push the combined diff past
+/// `max_total_bytes`, is replaced by a one-line stat placeholder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffLimits {
+    pub max_file_bytes: usize,
+    pub max_total_bytes: usize,
+}
+
+impl Default for DiffLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 256 * 1024,
+            max_total_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl From<&OmnaraConfig> for DiffLimits {
+    fn from(config: &OmnaraConfig) -> Self {
+        let kib = |kb: u64| usize::try_from(kb.saturating_mul(1024)).unwrap_or(usize::MAX);
+        Self {
+            max_file_bytes: kib(config.diff_max_file_kb),
+            max_total_bytes: kib(config.diff_max_total_kb),
+        }
+    }
+}
+
+/// Which git submodules [`GitDiffTracker`] descends into. A dirty submodule
+/// shows up in the superproject diff only as a `Subproject commit` line; each
+/// included one gets its own `# Submodule: <path>` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmoduleDiffs {
+    pub enabled: bool,
+    /// Submodule paths, relative to the superproject root, left out.
+    pub skip: Vec<String>,
+}
+
+impl Default for SubmoduleDiffs {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            skip: Vec::new(),
+        }
+    }
+}
+
+impl From<&OmnaraConfig> for SubmoduleDiffs {
+    fn from(config: &OmnaraConfig) -> Self {
+        Self {
+            enabled: config.diff_submodules,
+            skip: config.diff_skip_submodules.clone(),
+        }
+    }
+}
+
+impl SubmoduleDiffs {
+    fn includes(&self, path: &str) -> bool {
+        self.enabled
+            && !self
+                .skip
+                .iter()
+                .any(|skip| skip.trim_matches('/') == path.trim_matches('/'))
+    }
+}
+
+/// A session diff plus what it covers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffOutput {
+    pub text: String,
+    pub stats: DiffStats,
+    /// Files whose diff was replaced by a placeholder by [`DiffLimits`].
+    pub truncated_files: Vec<String>,
+}
+
+/// Totals over every changed file, including ones whose diff was truncated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Files kept out of the diff by each exclude pattern.
+    pub excluded: Vec<ExclusionStat>,
+}
+
+impl DiffStats {
+    fn merge(&mut self, other: DiffStats) {
+        self.files_changed += other.files_changed;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+        for stat in other.excluded {
+            match self
+                .excluded
+                .iter_mut()
+                .find(|total| total.pattern == stat.pattern)
+            {
+                Some(total) => total.files += stat.files,
+                None => self.excluded.push(stat),
+            }
+        }
+    }
+}
+
+/// Line counts for one changed file, from [`GitDiffTracker::get_diff_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    pub path: String,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Per-file line counts of the session diff, without its text. Displays as
+/// `+120 −34 across 6 files`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffStatSummary {
+    pub files: Vec<FileStat>,
+}
+
+impl DiffStatSummary {
+    pub fn insertions(&self) -> usize {
+        self.files.iter().map(|file| file.insertions).sum()
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.files.iter().map(|file| file.deletions).sum()
+    }
+}
+
+impl std::fmt::Display for DiffStatSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self.files.len();
+        let plural = if count == 1 { "" } else { "s" };
+        write!(
+            f,
+            "+{} \u{2212}{} across {count} file{plural}",
+            self.insertions(),
+            self.deletions()
+        )
+    }
+}
+
+/// One file's section of the diff. `size` is the rendered size, or the file
+/// size for untracked files too large to render.
+pub(crate) struct FileDiff {
+    pub(crate) path: String,
+    text: String,
+    size: usize,
+    pub(crate) insertions: usize,
+    pub(crate) deletions: usize,
+}
+
+impl FileDiff {
+    /// Split git-format diff output into per-file sections.
+    pub(crate) fn parse_all(diff: &str) -> Vec<FileDiff> {
+        let mut files = Vec::new();
+        let mut rest = diff;
+        while !rest.is_empty() {
+            let end = rest[1..]
+                .find("\ndiff --git ")
+                .map_or(rest.len(), |i| i + 2);
+            files.push(FileDiff::parse(&rest[..end]));
+            rest = &rest[end..];
+        }
+        files
+    }
+
+    pub(crate) fn parse(section: &str) -> FileDiff {
+        let header = section.lines().next().unwrap_or_default();
+        let path = header
+            .rsplit_once(" b/")
+            .map_or(header, |(_, path)| path)
+            .to_string();
+        let (mut insertions, mut deletions) = (0, 0);
+        let mut in_hunk = false;
+        for line in section.lines() {
+            if line.starts_with("@@") {
+                in_hunk = true;
+            } else if in_hunk && line.starts_with('+') {
+                insertions += 1;
+            } else if in_hunk && line.starts_with('-') {
+                deletions += 1;
+            }
+        }
+        FileDiff {
+            path,
+            text: section.to_string(),
+            size: section.len(),
+            insertions,
+            deletions,
+        }
+    }
+
+    fn placeholder(&self, limit: &str) -> String {
+        let header = self.text.lines().next().unwrap_or_default();
+        format!(
+            "{header}\n# Diff omitted ({} KiB, +{} -{}): exceeds the {limit} limit\n",
+            self.size.div_ceil(1024),
+            self.insertions,
+            self.deletions
+        )
+    }
+}
+
+/// File name, at the repository root, listing extra diff exclude patterns.
+pub const CODEXIGNORE_FILE: &str = ".codexignore";
+
+/// How many changed files one exclude pattern kept out of the last diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExclusionStat {
+    pub pattern: String,
+    pub files: usize,
+}
+
+/// A gitignore-style exclude pattern, applied as a git `glob` pathspec
+/// relative to the repository root and matched the same way when counting.
+///
+/// Patterns without a `/` match at any depth, a leading `/` anchors to the
+/// root, and a pattern also excludes everything below a matching directory.
+#[derive(Debug, Clone)]
+pub(crate) struct ExcludePattern {
+    raw: String,
+    glob: String,
+    regex: Option<Regex>,
+}
+
+impl ExcludePattern {
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let raw = line.trim();
+        if raw.is_empty() || raw.starts_with('#') || raw.starts_with('!') {
+            return None;
+        }
+        let trimmed = raw.trim_end_matches('/');
+        let glob = match trimmed.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if trimmed.contains('/') => trimmed.to_string(),
+            None => format!("**/{trimmed}"),
+        };
+        if glob.is_empty() {
+            return None;
+        }
+        let regex = Regex::new(&format!("^{}(?:/.*)?$", glob_to_regex(&glob))).ok();
+        Some(Self {
+            raw: raw.to_string(),
+            glob,
+            regex,
+        })
+    }
+
+    /// Pathspecs excluding matches and everything below matching directories.
+    fn pathspecs(&self) -> [String; 2] {
+        [
+            format!(":(top,exclude,glob){}", self.glob),
+            format!(":(top,exclude,glob){}/**", self.glob),
+        ]
+    }
+
+    pub(crate) fn matches(&self, root_relative: &str) -> bool {
+        self.regex
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(root_relative))
+    }
+}
+
+/// Translate git `glob` pathspec syntax to a regex: `*` and `?` stay within a
+/// path component and `**/` spans any number of directories.
+fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            c if c.is_ascii_alphanumeric() || c == '/' || c == '_' || c == '-' => out.push(c),
+            c => {
+                out.push('\\');
+                out.push(c);
+            }
+        }
+    }
+    out
 }
 
 impl GitDiffTracker {
@@ -22,8 +334,12 @@ impl GitDiffTracker {
             enabled,
             cwd,
             initial_git_hash: None,
-            session_start_time: SystemTime::now(),
+            initial_untracked: HashSet::new(),
             last_diff_hash: None,
+            config_excludes: Vec::new(),
+            limits: DiffLimits::default(),
+            submodules: SubmoduleDiffs::default(),
+            submodule_trackers: Vec::new(),
         };
         if tracker.enabled {
             tracker.capture_initial_state();
@@ -35,6 +351,8 @@ impl GitDiffTracker {
         match self.run_git(&["rev-parse", "HEAD"]) {
             Ok(out) if !out.trim().is_empty() => {
                 self.initial_git_hash = Some(out.trim().to_string());
+                self.initial_untracked = self.untracked_paths(&[]).into_iter().collect();
+                self.submodule_trackers = self.open_submodules();
             }
             _ => {
                 // Not in a git repo or no commits; disable tracking
@@ -43,15 +361,23 @@ impl GitDiffTracker {
         }
     }
 
-    /// Returns Some(diff_text) when tracking is enabled; may be an empty string if
-    /// there are no changes. Returns None when disabled (e.g., not in a git repo).
-    pub fn get_diff(&mut self) -> Option<String> {
+    /// Returns the session diff when tracking is enabled; its text may be empty
+    /// if there are no changes. Returns None when disabled (e.g., not in a git
+    /// repo).
+    pub fn get_diff(&mut self) -> Option<DiffOutput> {
+        self.get_diff_within(self.limits.max_total_bytes)
+    }
+
+    /// [`GitDiffTracker::get_diff`] with the total size capped at `budget`.
+    fn get_diff_within(&mut self, budget: usize) -> Option<DiffOutput> {
         if !self.enabled {
             return None;
         }
 
-        let mut combined = String::new();
-        let exclude_patterns = self.get_worktree_exclusions();
+        let mut exclude_patterns = self.get_worktree_exclusions();
+        let excludes = self.exclude_patterns();
+        let excluded = self.count_exclusions(&excludes);
+        exclude_patterns.extend(excludes.iter().flat_map(ExcludePattern::pathspecs));
 
         // Build git diff command
         let mut args: Vec<&str> = Vec::new();
@@ -68,43 +394,277 @@ impl GitDiffTracker {
             }
         }
 
-        if let Ok(out) = self.run_git(&args) {
-            let s = out.trim();
-            if !s.is_empty() {
-                combined.push_str(s);
+        let mut files = match self.run_git(&args) {
+            Ok(out) => FileDiff::parse_all(out.trim()),
+            Err(_) => Vec::new(),
+        };
+        // Append untracked files content in a diff-like form
+        files.extend(self.get_untracked_files(&exclude_patterns));
+
+        let mut output = assemble_diff(files, excluded, self.limits, budget);
+        for (path, tracker) in &mut self.submodule_trackers {
+            if excludes.iter().any(|pattern| pattern.matches(path)) {
+                continue;
+            }
+            let Some(diff) = tracker.get_diff_within(budget.saturating_sub(output.text.len()))
+            else {
+                continue;
+            };
+            output.stats.merge(diff.stats);
+            output.truncated_files.extend(
+                diff.truncated_files
+                    .iter()
+                    .map(|file| format!("{path}/{file}")),
+            );
+            let text = diff.text.trim();
+            if !text.is_empty() {
+                if !output.text.is_empty() {
+                    output.text.push('\n');
+                }
+                let _ = write!(output.text, "# Submodule: {path}\n{text}");
             }
         }
+        Some(output)
+    }
 
-        // Append untracked files content in a diff-like form
-        let untracked = self.get_untracked_files(&exclude_patterns);
-        if !untracked.is_empty() {
-            if !combined.is_empty() {
-                combined.push('\n');
+    /// Per-file line counts of the session diff from `git diff --numstat`,
+    /// without rendering any diff text; cheap enough to call on every poll.
+    /// Follows the same exclusions and submodule settings as
+    /// [`GitDiffTracker::get_diff`]. Binary files count as 0/0.
+    pub fn get_diff_stats(&self) -> Option<DiffStatSummary> {
+        if !self.enabled {
+            return None;
+        }
+        let excludes = self.exclude_patterns();
+        let mut pathspecs = self.get_worktree_exclusions();
+        pathspecs.extend(excludes.iter().flat_map(ExcludePattern::pathspecs));
+        let base = self.initial_git_hash.as_deref().unwrap_or("HEAD");
+        let mut args = vec!["diff", "--numstat", "-z", "--no-renames", base];
+        if !pathspecs.is_empty() {
+            args.push("--");
+            args.extend(pathspecs.iter().map(String::as_str));
+        }
+        let numstat = self.run_git(&args).unwrap_or_default();
+        let mut files: Vec<FileStat> = numstat.split('\0').filter_map(parse_numstat).collect();
+        if let Some(root) = self.repo_root() {
+            files.extend(
+                self.new_untracked_paths(&pathspecs)
+                    .into_iter()
+                    .map(|rel| FileStat {
+                        insertions: count_lines(&root.join(&rel)),
+                        deletions: 0,
+                        path: rel,
+                    }),
+            );
+        }
+        for (path, tracker) in &self.submodule_trackers {
+            if excludes.iter().any(|pattern| pattern.matches(path)) {
+                continue;
+            }
+            if let Some(stats) = tracker.get_diff_stats() {
+                files.extend(stats.files.into_iter().map(|file| FileStat {
+                    path: format!("{path}/{}", file.path),
+                    ..file
+                }));
             }
-            combined.push_str(&untracked);
         }
+        Some(DiffStatSummary { files })
+    }
+
+    /// Trackers for the initialized submodules the settings include.
+    fn open_submodules(&self) -> Vec<(String, GitDiffTracker)> {
+        let Some(root) = self.repo_root() else {
+            return Vec::new();
+        };
+        let Ok(index) = self.run_git(&["ls-files", "--stage", "--full-name", "--", ":(top)"])
+        else {
+            return Vec::new();
+        };
+        index
+            .lines()
+            .filter_map(|line| {
+                let (mode, path) = line.split_once('\t')?;
+                mode.starts_with("160000 ").then_some(path)
+            })
+            .filter(|path| self.submodules.includes(path) && root.join(path).join(".git").exists())
+            .filter_map(|path| {
+                let mut tracker = GitDiffTracker::new(true, Some(root.join(path)));
+                tracker.set_exclude_patterns(self.config_excludes.clone());
+                tracker.set_limits(self.limits);
+                tracker.enabled.then(|| (path.to_string(), tracker))
+            })
+            .collect()
+    }
+
+    /// Set exclude patterns from config, applied alongside `.codexignore`.
+    pub fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        for (_, tracker) in &mut self.submodule_trackers {
+            tracker.set_exclude_patterns(patterns.clone());
+        }
+        self.config_excludes = patterns;
+    }
+
+    pub fn set_limits(&mut self, limits: DiffLimits) {
+        for (_, tracker) in &mut self.submodule_trackers {
+            tracker.set_limits(limits);
+        }
+        self.limits = limits;
+    }
+
+    /// Choose which submodules are diffed; nested submodules are matched by
+    /// their full path. Submodules are opened when tracking starts, so this
+    /// only drops ones no longer included.
+    pub fn set_submodules(&mut self, submodules: SubmoduleDiffs) {
+        self.submodule_trackers
+            .retain(|(path, _)| submodules.includes(path));
+        for (path, tracker) in &mut self.submodule_trackers {
+            let prefix = format!("{path}/");
+            tracker.set_submodules(SubmoduleDiffs {
+                enabled: submodules.enabled,
+                skip: submodules
+                    .skip
+                    .iter()
+                    .filter_map(|skip| skip.trim_matches('/').strip_prefix(&prefix))
+                    .map(str::to_string)
+                    .collect(),
+            });
+        }
+        self.submodules = submodules;
+    }
 
-        Some(combined)
+    fn exclude_patterns(&self) -> Vec<ExcludePattern> {
+        load_exclude_patterns(self.repo_root().as_deref(), &self.config_excludes)
+    }
+
+    /// Count, per pattern, the changed and new untracked files it excludes.
+    fn count_exclusions(&self, excludes: &[ExcludePattern]) -> Vec<ExclusionStat> {
+        if excludes.is_empty() {
+            return Vec::new();
+        }
+        let base = self.initial_git_hash.as_deref().unwrap_or("HEAD");
+        let changed = self
+            .run_git(&["diff", "--name-only", base])
+            .unwrap_or_default();
+        let untracked = self.new_untracked_paths(&[]);
+        let files: Vec<&str> = changed
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .chain(untracked.iter().map(String::as_str))
+            .collect();
+        count_exclusions(excludes, &files)
     }
 
     /// Return a diff only if it is non-empty and different from the last one
     /// returned by this method during the session. Uses SHA-1 of the trimmed
     /// diff text to detect changes.
-    pub fn get_diff_if_changed(&mut self) -> Option<String> {
+    pub fn get_diff_if_changed(&mut self) -> Option<DiffOutput> {
         let diff = self.get_diff()?;
-        let trimmed = diff.trim().to_string();
-        let mut hasher = sha1::Sha1::new();
-        hasher.update(trimmed.as_bytes());
-        let hash = format!("{:x}", hasher.finalize());
-        match &self.last_diff_hash {
-            Some(prev) if prev == &hash => None,
-            _ => {
-                self.last_diff_hash = Some(hash);
-                Some(trimmed)
-            }
+        changed_since(&mut self.last_diff_hash, diff)
+    }
+
+    /// Move the baseline to the current working tree so later diffs only show
+    /// changes made from now on. Uncommitted changes are captured with
+    /// `git stash create`, which writes a commit without touching the
+    /// worktree or the stash list; untracked files present now are treated
+    /// as pre-existing.
+    pub fn checkpoint(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let base = self
+            .run_git_trimmed(&["stash", "create"])
+            .or_else(|| self.run_git_trimmed(&["rev-parse", "HEAD"]));
+        if let Some(base) = base {
+            self.initial_git_hash = Some(base);
+            self.initial_untracked = self.untracked_paths(&[]).into_iter().collect();
+            self.last_diff_hash = None;
+        }
+        for (_, tracker) in &mut self.submodule_trackers {
+            tracker.checkpoint();
         }
     }
 
+    /// Files to restore to undo every change since the baseline, skipping
+    /// the same paths the diff excludes. Restores go through the file system,
+    /// so the index and `HEAD` are left alone.
+    fn rollback_files(&self) -> Option<Vec<RollbackFile>> {
+        if !self.enabled {
+            return None;
+        }
+        let root = self.repo_root()?;
+        let base = self.initial_git_hash.as_deref().unwrap_or("HEAD");
+        let mut pathspecs = self.get_worktree_exclusions();
+        pathspecs.extend(
+            self.exclude_patterns()
+                .iter()
+                .flat_map(ExcludePattern::pathspecs),
+        );
+        let mut args = vec!["diff", "--name-status", "-z", "--no-renames", base];
+        if !pathspecs.is_empty() {
+            args.push("--");
+            args.extend(pathspecs.iter().map(String::as_str));
+        }
+        let changed = self.run_git(&args).ok()?;
+        let read_current = |path: &Path| {
+            String::from_utf8_lossy(&std::fs::read(path).unwrap_or_default()).to_string()
+        };
+
+        let mut files = Vec::new();
+        let mut entries = changed.split('\0');
+        while let (Some(status), Some(rel)) = (entries.next(), entries.next()) {
+            let path = root.join(rel);
+            let file = match status {
+                "A" => RollbackFile {
+                    change: FileChange::Delete {
+                        content: read_current(&path),
+                    },
+                    original: None,
+                    path,
+                },
+                _ => {
+                    let Some(original) = self.run_git_bytes(&["show", &format!("{base}:{rel}")])
+                    else {
+                        continue;
+                    };
+                    let change = if status == "D" {
+                        FileChange::Add {
+                            content: String::from_utf8_lossy(&original).to_string(),
+                        }
+                    } else {
+                        let top = format!(":(top){rel}");
+                        let diff = self
+                            .run_git(&["diff", "-R", base, "--", &top])
+                            .unwrap_or_default();
+                        FileChange::Update {
+                            unified_diff: strip_diff_header(&diff),
+                            move_path: None,
+                        }
+                    };
+                    RollbackFile {
+                        path,
+                        original: Some(original),
+                        change,
+                    }
+                }
+            };
+            files.push(file);
+        }
+        for rel in self.new_untracked_paths(&pathspecs) {
+            let path = root.join(&rel);
+            files.push(RollbackFile {
+                change: FileChange::Delete {
+                    content: read_current(&path),
+                },
+                original: None,
+                path,
+            });
+        }
+        for (_, tracker) in &self.submodule_trackers {
+            files.extend(tracker.rollback_files().unwrap_or_default());
+        }
+        Some(files)
+    }
+
     fn get_worktree_exclusions(&self) -> Vec<String> {
         let mut out = Vec::new();
         if let Ok(raw) = self.run_git(&["worktree", "list", "--porcelain"]) {
@@ -130,85 +690,1020 @@ impl GitDiffTracker {
         out
     }
 
-    fn get_untracked_files(&self, exclude_patterns: &[String]) -> String {
-        // Build git ls-files to find untracked files
-        let mut args: Vec<&str> = vec!["ls-files", "--others", "--exclude-standard"];
-        if !exclude_patterns.is_empty() {
-            args.push("--");
-            for p in exclude_patterns {
-                args.push(p);
-            }
+    fn get_untracked_files(&self, exclude_patterns: &[String]) -> Vec<FileDiff> {
+        let untracked = self.new_untracked_paths(exclude_patterns);
+        if untracked.is_empty() {
+            return Vec::new();
         }
+        let Some(root) = self.repo_root() else {
+            return Vec::new();
+        };
+        untracked
+            .iter()
+            .map(|rel| untracked_file_diff(rel, &root.join(rel), self.limits.max_file_bytes))
+            .collect()
+    }
+
+    /// Untracked paths that were not in the snapshot taken at initialization.
+    fn new_untracked_paths(&self, pathspecs: &[String]) -> Vec<String> {
+        self.untracked_paths(pathspecs)
+            .into_iter()
+            .filter(|rel| !self.initial_untracked.contains(rel))
+            .collect()
+    }
 
+    /// Untracked, non-ignored files per `git status`, relative to the
+    /// repository root.
+    fn untracked_paths(&self, pathspecs: &[String]) -> Vec<String> {
+        let mut args: Vec<&str> = vec!["status", "--porcelain", "-z", "--untracked-files=all"];
+        if !pathspecs.is_empty() {
+            args.push("--");
+            args.extend(pathspecs.iter().map(String::as_str));
+        }
         let Ok(out) = self.run_git(&args) else {
-            return String::new();
+            return Vec::new();
         };
-        let files: Vec<&str> = out.lines().filter(|s| !s.trim().is_empty()).collect();
-        if files.is_empty() {
-            return String::new();
-        }
-
-        let base = self
-            .cwd
-            .clone()
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
-
-        let mut buf = String::new();
-        for rel in files {
-            let abs = base.join(rel);
-            // Skip files that existed before the session started.
-            match std::fs::metadata(&abs).and_then(|m| m.created().or_else(|_| m.modified())) {
-                Ok(created) => {
-                    if created < self.session_start_time {
-                        continue;
-                    }
-                }
-                Err(_) => continue,
-            }
-
-            use std::fmt::Write as _;
-            let _ = writeln!(buf, "diff --git a/{rel} b/{rel}");
-            buf.push_str("new file mode 100644\n");
-            buf.push_str("index 0000000..0000000\n");
-            buf.push_str("--- /dev/null\n");
-            let _ = writeln!(buf, "+++ b/{rel}");
-
-            match std::fs::read_to_string(&abs) {
-                Ok(contents) => {
-                    let lines: Vec<&str> = contents.lines().collect();
-                    let count = lines.len();
-                    let _ = writeln!(buf, "@@ -0,0 +1,{count} @@");
-                    for line in lines {
-                        let _ = writeln!(buf, "+{line}");
-                    }
-                    if !contents.ends_with('\n') {
-                        buf.push_str("\\ No newline at end of file\n");
-                    }
-                }
-                Err(_) => {
-                    buf.push_str("@@ -0,0 +1,1 @@\n");
-                    buf.push_str("+[Binary or unreadable file]\n");
-                }
+        let mut paths = Vec::new();
+        let mut entries = out.split('\0');
+        while let Some(entry) = entries.next() {
+            if let Some(path) = entry.strip_prefix("?? ") {
+                paths.push(path.to_string());
+            } else if entry.starts_with('R') || entry.starts_with('C') {
+                // Renames and copies are followed by their source path.
+                entries.next();
             }
-            buf.push('\n');
         }
+        paths
+    }
+
+    /// URL of the `origin` remote, if the working directory is a git repo with one.
+    pub fn remote_url(&self) -> Option<String> {
+        self.run_git_trimmed(&["remote", "get-url", "origin"])
+    }
+
+    /// Name of the checked-out branch; None when detached or outside a repo.
+    pub fn current_branch(&self) -> Option<String> {
+        self.run_git_trimmed(&["rev-parse", "--abbrev-ref", "HEAD"])
+            .filter(|branch| branch != "HEAD")
+    }
+
+    /// Top-level directory of the repository containing the working directory.
+    fn repo_root(&self) -> Option<PathBuf> {
+        self.run_git_trimmed(&["rev-parse", "--show-toplevel"])
+            .map(PathBuf::from)
+    }
 
-        buf
+    fn run_git_trimmed(&self, args: &[&str]) -> Option<String> {
+        let out = self.run_git(args).ok()?;
+        let out = out.trim();
+        (!out.is_empty()).then(|| out.to_string())
     }
 
     fn run_git(&self, args: &[&str]) -> std::io::Result<String> {
-        let mut cmd = Command::new("git");
-        cmd.args(args);
-        if let Some(cwd) = &self.cwd {
-            cmd.current_dir(cwd);
-        }
         // Note: std::process::Command doesn't support a timeout natively.
         // We rely on the fact that these commands are quick in practice.
-        let out = cmd.output()?;
+        let out = self.git_command(args).output()?;
         if out.status.success() {
             Ok(String::from_utf8_lossy(&out.stdout).to_string())
         } else {
             Ok(String::new())
         }
     }
+
+    /// Raw stdout of a successful git command (e.g. file contents).
+    fn run_git_bytes(&self, args: &[&str]) -> Option<Vec<u8>> {
+        let out = self.git_command(args).output().ok()?;
+        out.status.success().then_some(out.stdout)
+    }
+
+    fn git_command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.args(args);
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        cmd
+    }
+}
+
+/// The hunks of a single-file `git diff`, without its file headers.
+fn strip_diff_header(diff: &str) -> String {
+    match diff.find("\n@@") {
+        Some(start) => diff[start + 1..].to_string(),
+        None => String::new(),
+    }
+}
+
+/// Session diff across every repository the agent touches (git, jj, or hg;
+/// see [`open_tracker`]). Starts with
+/// the repository containing the initial cwd and adds repositories as paths
+/// from patches and exec working directories are reported via
+/// [`MultiRepoDiffTracker::track_path`]. Patched files outside every
+/// repository go to a [`PatchLedger`] rooted at the cwd, reported via
+/// [`MultiRepoDiffTracker::record_patch`]. With more than one source, each
+/// diff is prefixed with a `# Repository: <root>` label.
+#[derive(Debug)]
+pub struct MultiRepoDiffTracker {
+    repos: Vec<(Option<PathBuf>, Box<dyn VcsDiffTracker>)>,
+    cwd: Option<PathBuf>,
+    ledger: Option<PatchLedger>,
+    seen_dirs: HashSet<PathBuf>,
+    last_diff_hash: Option<String>,
+    exclude_patterns: Vec<String>,
+    limits: DiffLimits,
+    submodules: SubmoduleDiffs,
+}
+
+impl MultiRepoDiffTracker {
+    /// Track the repository containing `cwd` (or the process cwd when None).
+    pub fn new(cwd: Option<PathBuf>) -> Self {
+        let initial = open_tracker(cwd.clone());
+        let root = initial.repo_root();
+        Self {
+            repos: vec![(root, initial)],
+            cwd,
+            ledger: None,
+            seen_dirs: HashSet::new(),
+            last_diff_hash: None,
+            exclude_patterns: Vec::new(),
+            limits: DiffLimits::default(),
+            submodules: SubmoduleDiffs::default(),
+        }
+    }
+
+    /// Apply config exclude patterns to every tracked repository, including
+    /// ones discovered later.
+    pub fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        for (_, tracker) in &mut self.repos {
+            tracker.set_exclude_patterns(patterns.clone());
+        }
+        if let Some(ledger) = &mut self.ledger {
+            ledger.set_exclude_patterns(patterns.clone());
+        }
+        self.exclude_patterns = patterns;
+    }
+
+    /// Apply size limits to every tracked repository; the total limit caps
+    /// the combined diff.
+    pub fn set_limits(&mut self, limits: DiffLimits) {
+        for (_, tracker) in &mut self.repos {
+            tracker.set_limits(limits);
+        }
+        if let Some(ledger) = &mut self.ledger {
+            ledger.set_limits(limits);
+        }
+        self.limits = limits;
+    }
+
+    /// Apply submodule settings to every tracked git repository.
+    pub fn set_submodules(&mut self, submodules: SubmoduleDiffs) {
+        for (_, tracker) in &mut self.repos {
+            tracker.set_submodules(submodules.clone());
+        }
+        self.submodules = submodules;
+    }
+
+    /// Start tracking the repository containing `path` (a file or directory,
+    /// which need not exist yet). Returns true if a new repository was added.
+    /// Untracked files already in that repository are treated as pre-existing.
+    pub fn track_path(&mut self, path: &Path) -> bool {
+        let Some(dir) = path
+            .ancestors()
+            .find(|ancestor| ancestor.is_dir())
+            .map(Path::to_path_buf)
+        else {
+            return false;
+        };
+        if !self.seen_dirs.insert(dir.clone()) {
+            return false;
+        }
+        let mut tracker = open_tracker(Some(dir));
+        let Some(root) = tracker.repo_root() else {
+            return false;
+        };
+        if !tracker.enabled()
+            || self
+                .repos
+                .iter()
+                .any(|(known, _)| known.as_ref() == Some(&root))
+        {
+            return false;
+        }
+        tracker.set_exclude_patterns(self.exclude_patterns.clone());
+        tracker.set_limits(self.limits);
+        tracker.set_submodules(self.submodules.clone());
+        self.repos.push((Some(root), tracker));
+        true
+    }
+
+    /// Track the files an applied patch changes. Files in a repository are
+    /// left to it (discovering the repository if needed); the rest are
+    /// snapshotted in the patch ledger, so projects without version control
+    /// still get a session diff.
+    pub fn record_patch(&mut self, changes: &HashMap<PathBuf, FileChange>) {
+        for path in changes.keys() {
+            self.track_path(path);
+        }
+        let untracked: HashMap<PathBuf, FileChange> = changes
+            .iter()
+            .filter(|(path, _)| !self.in_repo(path))
+            .map(|(path, change)| (path.clone(), change.clone()))
+            .collect();
+        if untracked.is_empty() {
+            return;
+        }
+        let ledger = self.ledger.get_or_insert_with(|| {
+            let root = self
+                .cwd
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default();
+            let mut ledger = PatchLedger::new(root);
+            ledger.set_exclude_patterns(self.exclude_patterns.clone());
+            ledger.set_limits(self.limits);
+            ledger
+        });
+        ledger.record(&untracked);
+    }
+
+    /// Whether `path` lies in an enabled repository.
+    fn in_repo(&self, path: &Path) -> bool {
+        let Some(dir) = path.ancestors().find(|ancestor| ancestor.is_dir()) else {
+            return false;
+        };
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        self.repos.iter().any(|(root, tracker)| {
+            tracker.enabled() && root.as_ref().is_some_and(|root| dir.starts_with(root))
+        })
+    }
+
+    /// Enabled repositories plus the patch ledger, once it exists.
+    fn source_count(&self) -> usize {
+        let repos = self.repos.iter().filter(|(_, t)| t.enabled()).count();
+        repos + usize::from(self.ledger.is_some())
+    }
+
+    /// Roots of the repositories tracked so far, initial repository first.
+    pub fn repo_roots(&self) -> Vec<&Path> {
+        self.repos
+            .iter()
+            .filter_map(|(root, _)| root.as_deref())
+            .collect()
+    }
+
+    /// Combined diff across tracked repositories and the patch ledger; None
+    /// when there is neither an enabled repository nor a ledger.
+    pub fn get_diff(&mut self) -> Option<DiffOutput> {
+        let labeled = self.source_count() > 1;
+        let mut output = DiffOutput::default();
+        let mut sections = Vec::new();
+        let mut budget = self.limits.max_total_bytes;
+        let ledger = self.ledger.as_mut().map(|ledger| {
+            let root = Some(ledger.root().to_path_buf());
+            (root, ledger as &mut dyn VcsDiffTracker)
+        });
+        let sources = self
+            .repos
+            .iter_mut()
+            .map(|(root, tracker)| (root.clone(), tracker.as_mut() as &mut dyn VcsDiffTracker))
+            .chain(ledger);
+        for (root, tracker) in sources {
+            let Some(diff) = tracker.get_diff_within(budget) else {
+                continue;
+            };
+            output.stats.merge(diff.stats);
+            output.truncated_files.extend(diff.truncated_files);
+            let text = diff.text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            budget = budget.saturating_sub(text.len());
+            match root {
+                Some(root) if labeled => {
+                    sections.push(format!("# Repository: {}\n{text}", root.display()));
+                }
+                _ => sections.push(text.to_string()),
+            }
+        }
+        if sections.is_empty() && self.source_count() == 0 {
+            return None;
+        }
+        output.text = sections.join("\n");
+        Some(output)
+    }
+
+    /// Per-file line counts across tracked repositories and the patch
+    /// ledger, without diff text; None when no source offers them. With more
+    /// than one source, paths are prefixed with its root.
+    pub fn get_diff_stats(&self) -> Option<DiffStatSummary> {
+        let labeled = self.source_count() > 1;
+        let mut summary: Option<DiffStatSummary> = None;
+        let ledger = self.ledger.as_ref().map(|ledger| {
+            let root = Some(ledger.root().to_path_buf());
+            (root, ledger as &dyn VcsDiffTracker)
+        });
+        let sources = self
+            .repos
+            .iter()
+            .map(|(root, tracker)| (root.clone(), tracker.as_ref() as &dyn VcsDiffTracker))
+            .chain(ledger);
+        for (root, tracker) in sources {
+            let Some(stats) = tracker.get_diff_stats() else {
+                continue;
+            };
+            let files = &mut summary.get_or_insert_with(DiffStatSummary::default).files;
+            match root {
+                Some(root) if labeled => {
+                    files.extend(stats.files.into_iter().map(|file| FileStat {
+                        path: root.join(&file.path).display().to_string(),
+                        ..file
+                    }));
+                }
+                _ => files.extend(stats.files),
+            }
+        }
+        summary
+    }
+
+    /// Same contract as [`GitDiffTracker::get_diff_if_changed`], over the
+    /// combined diff.
+    pub fn get_diff_if_changed(&mut self) -> Option<DiffOutput> {
+        let diff = self.get_diff()?;
+        changed_since(&mut self.last_diff_hash, diff)
+    }
+
+    /// Undo the changes in every tracked repository that supports it and in
+    /// the patch ledger; None when none does.
+    pub fn rollback_plan(&self) -> Option<RollbackPlan> {
+        let mut supported = false;
+        let mut files = Vec::new();
+        let ledger = self
+            .ledger
+            .as_ref()
+            .map(|ledger| ledger as &dyn VcsDiffTracker);
+        let sources = self
+            .repos
+            .iter()
+            .map(|(_, tracker)| tracker.as_ref() as &dyn VcsDiffTracker)
+            .chain(ledger);
+        for tracker in sources {
+            if let Some(repo_files) = tracker.rollback_files() {
+                supported = true;
+                files.extend(repo_files);
+            }
+        }
+        supported.then(|| RollbackPlan::new(files))
+    }
+
+    /// Start a new diff baseline in every tracked repository, e.g. when a new
+    /// task begins, so diffs are reported per task.
+    pub fn checkpoint(&mut self) {
+        for (_, tracker) in &mut self.repos {
+            tracker.checkpoint();
+        }
+        if let Some(ledger) = &mut self.ledger {
+            ledger.checkpoint();
+        }
+        self.last_diff_hash = None;
+    }
+}
+
+impl VcsDiffTracker for GitDiffTracker {
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn repo_root(&self) -> Option<PathBuf> {
+        GitDiffTracker::repo_root(self)
+    }
+
+    fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        GitDiffTracker::set_exclude_patterns(self, patterns);
+    }
+
+    fn set_limits(&mut self, limits: DiffLimits) {
+        GitDiffTracker::set_limits(self, limits);
+    }
+
+    fn set_submodules(&mut self, submodules: SubmoduleDiffs) {
+        GitDiffTracker::set_submodules(self, submodules);
+    }
+
+    fn limits(&self) -> DiffLimits {
+        self.limits
+    }
+
+    fn get_diff_within(&mut self, budget: usize) -> Option<DiffOutput> {
+        GitDiffTracker::get_diff_within(self, budget)
+    }
+
+    fn get_diff_if_changed(&mut self) -> Option<DiffOutput> {
+        GitDiffTracker::get_diff_if_changed(self)
+    }
+
+    fn get_diff_stats(&self) -> Option<DiffStatSummary> {
+        GitDiffTracker::get_diff_stats(self)
+    }
+
+    fn checkpoint(&mut self) {
+        GitDiffTracker::checkpoint(self);
+    }
+
+    fn rollback_files(&self) -> Option<Vec<RollbackFile>> {
+        GitDiffTracker::rollback_files(self)
+    }
+}
+
+/// One `git diff --numstat -z --no-renames` record: `<ins>\t<del>\t<path>`,
+/// with `-` counts for binary files.
+fn parse_numstat(record: &str) -> Option<FileStat> {
+    let mut fields = record.splitn(3, '\t');
+    let insertions = fields.next()?;
+    let deletions = fields.next()?;
+    let path = fields.next().filter(|path| !path.is_empty())?;
+    Some(FileStat {
+        path: path.to_string(),
+        insertions: insertions.parse().unwrap_or(0),
+        deletions: deletions.parse().unwrap_or(0),
+    })
+}
+
+/// Lines in a new text file, counted like its rendered diff; 0 for binary
+/// or unreadable files, as `--numstat` reports them.
+fn count_lines(path: &Path) -> usize {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .map_or(0, |contents| contents.lines().count())
+}
+
+/// Config patterns followed by the `.codexignore` at `root`, if any.
+pub(crate) fn load_exclude_patterns(root: Option<&Path>, config: &[String]) -> Vec<ExcludePattern> {
+    let codexignore = root
+        .and_then(|root| std::fs::read_to_string(root.join(CODEXIGNORE_FILE)).ok())
+        .unwrap_or_default();
+    config
+        .iter()
+        .map(String::as_str)
+        .chain(codexignore.lines())
+        .filter_map(ExcludePattern::parse)
+        .collect()
+}
+
+/// Count, per pattern, how many of the root-relative `files` it matches.
+pub(crate) fn count_exclusions(excludes: &[ExcludePattern], files: &[&str]) -> Vec<ExclusionStat> {
+    excludes
+        .iter()
+        .map(|pattern| ExclusionStat {
+            pattern: pattern.raw.clone(),
+            files: files.iter().filter(|file| pattern.matches(file)).count(),
+        })
+        .collect()
+}
+
+/// Join per-file sections into a [`DiffOutput`], replacing sections that
+/// exceed `limits` (with the total capped at `budget`) by placeholders and
+/// appending an `# Excluded:` summary.
+pub(crate) fn assemble_diff(
+    files: Vec<FileDiff>,
+    excluded: Vec<ExclusionStat>,
+    limits: DiffLimits,
+    budget: usize,
+) -> DiffOutput {
+    let mut output = DiffOutput::default();
+    let mut sections = Vec::new();
+    let mut total = 0;
+    for file in files {
+        output.stats.files_changed += 1;
+        output.stats.insertions += file.insertions;
+        output.stats.deletions += file.deletions;
+        let limit = if file.size > limits.max_file_bytes {
+            Some("per-file")
+        } else if total + file.size > budget {
+            Some("total diff")
+        } else {
+            None
+        };
+        let section = match limit {
+            Some(limit) => {
+                output.truncated_files.push(file.path.clone());
+                file.placeholder(limit)
+            }
+            None => file.text,
+        };
+        total += section.len();
+        sections.push(section.trim_end().to_string());
+    }
+
+    let summary: Vec<String> = excluded
+        .iter()
+        .filter(|stat| stat.files > 0)
+        .map(|stat| {
+            let plural = if stat.files == 1 { "" } else { "s" };
+            format!("{} ({} file{plural})", stat.pattern, stat.files)
+        })
+        .collect();
+    if !summary.is_empty() {
+        sections.push(format!("# Excluded: {}", summary.join(", ")));
+    }
+    output.stats.excluded = excluded;
+    output.text = sections.join("\n");
+    output
+}
+
+/// Render an untracked file as a new-file diff. Files over the per-file
+/// limit are only measured, so huge files never get formatted.
+pub(crate) fn untracked_file_diff(rel: &str, abs: &Path, max_file_bytes: usize) -> FileDiff {
+    let mut buf = String::new();
+    let _ = writeln!(buf, "diff --git a/{rel} b/{rel}");
+    buf.push_str("new file mode 100644\n");
+    buf.push_str("index 0000000..0000000\n");
+    buf.push_str("--- /dev/null\n");
+    let _ = writeln!(buf, "+++ b/{rel}");
+
+    let contents = std::fs::read(abs)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+    let Some(contents) = contents else {
+        buf.push_str("@@ -0,0 +1,1 @@\n");
+        buf.push_str("+[Binary or unreadable file]\n");
+        return FileDiff {
+            path: rel.to_string(),
+            size: buf.len(),
+            text: buf,
+            insertions: 1,
+            deletions: 0,
+        };
+    };
+    let count = contents.lines().count();
+    if contents.len() > max_file_bytes {
+        return FileDiff {
+            path: rel.to_string(),
+            text: buf,
+            size: contents.len(),
+            insertions: count,
+            deletions: 0,
+        };
+    }
+    let _ = writeln!(buf, "@@ -0,0 +1,{count} @@");
+    for line in contents.lines() {
+        let _ = writeln!(buf, "+{line}");
+    }
+    if !contents.ends_with('\n') {
+        buf.push_str("\\ No newline at end of file\n");
+    }
+    FileDiff {
+        path: rel.to_string(),
+        size: buf.len(),
+        text: buf,
+        insertions: count,
+        deletions: 0,
+    }
+}
+
+/// Return `diff` (trimmed) if it is non-empty and its hash differs from
+/// `last_hash`, recording the new hash.
+pub(crate) fn changed_since(
+    last_hash: &mut Option<String>,
+    mut diff: DiffOutput,
+) -> Option<DiffOutput> {
+    diff.text = diff.text.trim().to_string();
+    if diff.text.is_empty() {
+        return None;
+    }
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(diff.text.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    if last_hash.as_ref() == Some(&hash) {
+        return None;
+    }
+    *last_hash = Some(hash);
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .expect("run git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn init_repo(dir: &Path) {
+        std::fs::create_dir_all(dir).expect("mkdir");
+        git(dir, &["init", "-q"]);
+        git(dir, &["config", "user.email", "test@example.com"]);
+        git(dir, &["config", "user.name", "Test"]);
+        std::fs::write(dir.join("README.md"), "hello\n").expect("write");
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "init"]);
+    }
+
+    #[test]
+    fn discovered_repos_are_labeled_in_combined_diff() {
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp.path().join("first"));
+        init_repo(&tmp.path().join("second"));
+        let first = tmp
+            .path()
+            .join("first")
+            .canonicalize()
+            .expect("canonicalize");
+        let second = tmp
+            .path()
+            .join("second")
+            .canonicalize()
+            .expect("canonicalize");
+
+        let mut tracker = MultiRepoDiffTracker::new(Some(first.clone()));
+        std::fs::write(first.join("README.md"), "hello first\n").expect("write");
+        assert_eq!(
+            tracker
+                .get_diff_if_changed()
+                .map(|d| d.text.contains("# Repository")),
+            Some(false)
+        );
+
+        std::fs::write(second.join("README.md"), "hello second\n").expect("write");
+        assert!(tracker.track_path(&second.join("src/new_file.rs")));
+        assert!(!tracker.track_path(&second));
+        assert_eq!(tracker.repo_roots().len(), 2);
+
+        let diff = tracker.get_diff_if_changed().expect("changed diff").text;
+        let first_label = format!("# Repository: {}", tracker.repo_roots()[0].display());
+        let second_label = format!("# Repository: {}", second.display());
+        assert!(diff.starts_with(&first_label), "{diff}");
+        assert!(diff.contains(&second_label), "{diff}");
+        assert!(diff.contains("+hello second"), "{diff}");
+        assert_eq!(tracker.get_diff_if_changed(), None);
+    }
+
+    #[test]
+    fn rollback_restores_the_baseline() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        std::fs::write(repo.join("gone.txt"), "keep me\n").expect("write");
+        std::fs::write(repo.join("notes.txt"), "already here\n").expect("write");
+        git(&repo, &["add", "gone.txt"]);
+        git(&repo, &["commit", "-q", "-m", "more"]);
+
+        let tracker = MultiRepoDiffTracker::new(Some(repo.clone()));
+        std::fs::write(repo.join("README.md"), "hello world\n").expect("write");
+        std::fs::remove_file(repo.join("gone.txt")).expect("remove");
+        std::fs::create_dir(repo.join("src")).expect("mkdir");
+        std::fs::write(repo.join("src/new.rs"), "fn main() {}\n").expect("write");
+
+        let plan = tracker.rollback_plan().expect("git supports rollback");
+        let changes = plan.changes();
+        let root = tracker.repo_roots()[0].to_path_buf();
+        assert_eq!(changes.len(), 3);
+        match &changes[&root.join("README.md")] {
+            FileChange::Update { unified_diff, .. } => {
+                assert!(unified_diff.starts_with("@@"), "{unified_diff}");
+                assert!(
+                    unified_diff.contains("-hello world\n+hello\n"),
+                    "{unified_diff}"
+                );
+            }
+            other => panic!("unexpected change {other:?}"),
+        }
+        assert_eq!(
+            changes[&root.join("gone.txt")],
+            FileChange::Add {
+                content: "keep me\n".to_string()
+            }
+        );
+        assert!(matches!(
+            changes[&root.join("src/new.rs")],
+            FileChange::Delete { .. }
+        ));
+
+        assert_eq!(plan.apply().expect("apply"), 3);
+        assert_eq!(
+            std::fs::read_to_string(repo.join("README.md")).expect("read"),
+            "hello\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(repo.join("gone.txt")).expect("read"),
+            "keep me\n"
+        );
+        assert!(!repo.join("src/new.rs").exists());
+        assert!(repo.join("notes.txt").exists());
+        assert!(tracker.rollback_plan().expect("plan").is_empty());
+    }
+
+    #[test]
+    fn exclude_patterns_follow_gitignore_rules() {
+        let pattern = |p: &str| ExcludePattern::parse(p).expect("pattern");
+        assert!(pattern("Cargo.lock").matches("Cargo.lock"));
+        assert!(pattern("Cargo.lock").matches("crates/a/Cargo.lock"));
+        assert!(pattern("*.min.js").matches("web/app.min.js"));
+        assert!(!pattern("/dist").matches("web/dist/app.js"));
+        assert!(pattern("/dist").matches("dist/app.js"));
+        assert!(pattern("build/").matches("sub/build/out.o"));
+        assert!(pattern("gen/*.rs").matches("gen/a.rs"));
+        assert!(!pattern("gen/*.rs").matches("gen/nested/a.rs"));
+        assert!(ExcludePattern::parse("# comment").is_none());
+        assert!(ExcludePattern::parse("   ").is_none());
+    }
+
+    #[test]
+    fn codexignore_and_config_patterns_are_excluded_and_counted() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        std::fs::write(repo.join("Cargo.lock"), "v1\n").expect("write");
+        std::fs::create_dir(repo.join("generated")).expect("mkdir");
+        std::fs::write(repo.join("generated/a.rs"), "a\n").expect("write");
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "more"]);
+
+        let mut tracker = GitDiffTracker::new(true, Some(repo.clone()));
+        tracker.set_exclude_patterns(vec!["Cargo.lock".to_string()]);
+        std::fs::write(
+            repo.join(CODEXIGNORE_FILE),
+            "# generated code\ngenerated/\n",
+        )
+        .expect("write");
+        std::fs::write(repo.join("Cargo.lock"), "v2\n").expect("write");
+        std::fs::write(repo.join("generated/a.rs"), "b\n").expect("write");
+        std::fs::write(repo.join("generated/b.rs"), "new\n").expect("write");
+        std::fs::write(repo.join("README.md"), "changed\n").expect("write");
+
+        let DiffOutput {
+            text: diff, stats, ..
+        } = tracker.get_diff().expect("diff");
+        assert!(diff.contains("+changed"), "{diff}");
+        assert!(!diff.contains("b/Cargo.lock"), "{diff}");
+        assert!(!diff.contains("b/generated/a.rs"), "{diff}");
+        assert!(!diff.contains("b/generated/b.rs"), "{diff}");
+        assert!(diff.contains(CODEXIGNORE_FILE), "{diff}");
+        assert_eq!(
+            stats.excluded,
+            vec![
+                ExclusionStat {
+                    pattern: "Cargo.lock".to_string(),
+                    files: 1,
+                },
+                ExclusionStat {
+                    pattern: "generated/".to_string(),
+                    files: 2,
+                },
+            ]
+        );
+        assert!(diff.ends_with("# Excluded: Cargo.lock (1 file), generated/ (2 files)"));
+    }
+
+    #[test]
+    fn oversized_file_diffs_are_replaced_by_stat_placeholders() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        let mut tracker = GitDiffTracker::new(true, Some(repo.clone()));
+        tracker.set_limits(DiffLimits {
+            max_file_bytes: 1024,
+            max_total_bytes: 4096,
+        });
+        std::fs::write(repo.join("README.md"), "changed\n").expect("write");
+        std::fs::write(repo.join("vendored.txt"), "line\n".repeat(1000)).expect("write");
+
+        let output = tracker.get_diff().expect("diff");
+        assert_eq!(output.truncated_files, vec!["vendored.txt".to_string()]);
+        assert_eq!(
+            (
+                output.stats.files_changed,
+                output.stats.insertions,
+                output.stats.deletions
+            ),
+            (2, 1001, 1)
+        );
+        assert!(output.text.contains("+changed"), "{}", output.text);
+        assert!(
+            output.text.contains(
+                "diff --git a/vendored.txt b/vendored.txt\n\
+                 # Diff omitted (5 KiB, +1000 -0): exceeds the per-file limit"
+            ),
+            "{}",
+            output.text
+        );
+        assert!(!output.text.contains("+line"), "{}", output.text);
+    }
+
+    #[test]
+    fn total_limit_truncates_files_past_the_budget() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        let mut tracker = GitDiffTracker::new(true, Some(repo.clone()));
+        tracker.set_limits(DiffLimits {
+            max_file_bytes: 1024,
+            max_total_bytes: 1500,
+        });
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            std::fs::write(repo.join(name), "0123456789\n".repeat(40)).expect("write");
+        }
+
+        let output = tracker.get_diff().expect("diff");
+        assert_eq!(output.truncated_files, vec!["c.txt".to_string()]);
+        assert!(
+            output.text.contains("exceeds the total diff limit"),
+            "{}",
+            output.text
+        );
+        assert_eq!(output.stats.files_changed, 3);
+    }
+
+    #[test]
+    fn untracked_files_present_at_start_are_not_reported() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        std::fs::create_dir(repo.join("cache")).expect("mkdir");
+        std::fs::write(repo.join("cache/restored.bin"), "old\n").expect("write");
+
+        let mut tracker = GitDiffTracker::new(true, Some(repo.clone()));
+        // Touching a pre-existing file (e.g. a cache restore) does not make it new.
+        std::fs::write(repo.join("cache/restored.bin"), "rewritten\n").expect("write");
+        std::fs::write(repo.join("cache/created.txt"), "new\n").expect("write");
+
+        let output = tracker.get_diff().expect("diff");
+        assert!(
+            output.text.contains("diff --git a/cache/created.txt"),
+            "{}",
+            output.text
+        );
+        assert!(!output.text.contains("restored.bin"), "{}", output.text);
+        assert_eq!(output.stats.files_changed, 1);
+    }
+
+    #[test]
+    fn checkpoint_limits_diffs_to_changes_made_afterwards() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        let mut tracker = MultiRepoDiffTracker::new(Some(repo.clone()));
+        std::fs::write(repo.join("README.md"), "hello\nfirst task\n").expect("write");
+        std::fs::write(repo.join("first.txt"), "one\n").expect("write");
+        assert!(tracker.get_diff_if_changed().is_some());
+
+        tracker.checkpoint();
+        assert_eq!(
+            tracker.get_diff().map(|d| d.text),
+            Some(String::new()),
+            "nothing changed since the checkpoint"
+        );
+        std::fs::write(repo.join("README.md"), "hello\nfirst task\nsecond task\n").expect("write");
+        std::fs::write(repo.join("second.txt"), "two\n").expect("write");
+
+        let diff = tracker.get_diff_if_changed().expect("diff").text;
+        assert!(diff.contains("+second task"), "{diff}");
+        assert!(!diff.contains("+first task"), "{diff}");
+        assert!(diff.contains("b/second.txt"), "{diff}");
+        assert!(!diff.contains("first.txt"), "{diff}");
+        // The stash list is left alone.
+        let stashes = Command::new("git")
+            .args(["stash", "list"])
+            .current_dir(&repo)
+            .output()
+            .expect("run git");
+        assert!(stashes.stdout.is_empty());
+    }
+
+    #[test]
+    fn diff_stats_count_lines_without_rendering_the_diff() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        init_repo(&repo);
+        std::fs::write(repo.join("old.txt"), "a\nb\n").expect("write");
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "more"]);
+
+        let mut tracker = GitDiffTracker::new(true, Some(repo.clone()));
+        tracker.set_exclude_patterns(vec!["*.lock".to_string()]);
+        std::fs::write(repo.join("README.md"), "hello\nworld\n").expect("write");
+        std::fs::remove_file(repo.join("old.txt")).expect("remove");
+        std::fs::write(repo.join("new.rs"), "fn a() {}\nfn b() {}\nfn c() {}").expect("write");
+        std::fs::write(repo.join("Cargo.lock"), "ignored\n").expect("write");
+
+        let stats = tracker.get_diff_stats().expect("stats");
+        assert_eq!(
+            stats.files,
+            vec![
+                FileStat {
+                    path: "README.md".to_string(),
+                    insertions: 1,
+                    deletions: 0,
+                },
+                FileStat {
+                    path: "old.txt".to_string(),
+                    insertions: 0,
+                    deletions: 2,
+                },
+                FileStat {
+                    path: "new.rs".to_string(),
+                    insertions: 3,
+                    deletions: 0,
+                },
+            ]
+        );
+        assert_eq!(stats.to_string(), "+4 \u{2212}2 across 3 files");
+        let full = tracker.get_diff().expect("diff").stats;
+        assert_eq!(
+            (full.files_changed, full.insertions, full.deletions),
+            (stats.files.len(), stats.insertions(), stats.deletions())
+        );
+    }
+
+    #[test]
+    fn dirty_submodules_get_labeled_sections_unless_skipped() {
+        let tmp = TempDir::new().expect("tempdir");
+        let lib = tmp.path().join("lib");
+        let app = tmp.path().join("app");
+        init_repo(&lib);
+        init_repo(&app);
+        git(
+            &app,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                lib.to_str().expect("utf-8 path"),
+                "vendor/lib",
+            ],
+        );
+        git(&app, &["commit", "-q", "-m", "add lib"]);
+        let submodule = app.join("vendor/lib");
+        git(&submodule, &["config", "user.email", "test@example.com"]);
+        git(&submodule, &["config", "user.name", "Test"]);
+
+        let mut tracker = GitDiffTracker::new(true, Some(app.clone()));
+        std::fs::write(app.join("README.md"), "hello app\n").expect("write");
+        std::fs::write(submodule.join("README.md"), "hello lib\n").expect("write");
+        std::fs::write(submodule.join("new.txt"), "new\n").expect("write");
+
+        let output = tracker.get_diff().expect("diff");
+        let (app_part, lib_part) = output
+            .text
+            .split_once("# Submodule: vendor/lib\n")
+            .expect("submodule section");
+        assert!(app_part.contains("+hello app"), "{}", output.text);
+        assert!(lib_part.contains("diff --git a/README.md b/README.md"));
+        assert!(lib_part.contains("+hello lib"), "{}", output.text);
+        assert!(lib_part.contains("diff --git a/new.txt b/new.txt"));
+        // README.md and the `Subproject commit` line, then the two lib files.
+        assert_eq!(output.stats.files_changed, 4);
+
+        tracker.set_submodules(SubmoduleDiffs {
+            enabled: true,
+            skip: vec!["vendor/lib/".to_string()],
+        });
+        let output = tracker.get_diff().expect("diff");
+        assert!(!output.text.contains("# Submodule"), "{}", output.text);
+        assert!(output.text.contains("+hello app"), "{}", output.text);
+    }
+
+    #[test]
+    fn paths_outside_git_are_ignored() {
+        let tmp = TempDir::new().expect("tempdir");
+        let mut tracker = MultiRepoDiffTracker::new(Some(tmp.path().to_path_buf()));
+        assert!(!tracker.track_path(tmp.path()));
+        assert_eq!(tracker.get_diff(), None);
+    }
+
+    #[test]
+    fn patched_files_outside_git_are_diffed_from_the_ledger() {
+        let tmp = TempDir::new().expect("tempdir");
+        let path = tmp.path().join("notes.txt");
+        let mut tracker = MultiRepoDiffTracker::new(Some(tmp.path().to_path_buf()));
+        tracker.record_patch(&HashMap::from([(
+            path.clone(),
+            FileChange::Add {
+                content: "hello\n".to_string(),
+            },
+        )]));
+        std::fs::write(&path, "hello\n").expect("write");
+
+        let diff = tracker.get_diff().expect("ledger diff");
+        assert!(
+            diff.text
+                .starts_with("diff --git a/notes.txt b/notes.txt\nnew file mode")
+        );
+        assert_eq!(diff.stats.insertions, 1);
+        let plan = tracker.rollback_plan().expect("rollback");
+        assert_eq!(plan.len(), 1);
+        plan.apply().expect("rollback applies");
+        assert!(!path.exists());
+        tracker.checkpoint();
+        assert_eq!(tracker.get_diff().expect("ledger diff").text, "");
+    }
 }
@@ -6,6 +6,7 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 mod apply_patch;
+mod ask_user_tool;
 pub mod auth;
 pub mod bash;
 mod chat_completions;
@@ -13,6 +14,9 @@ mod client;
 mod client_common;
 pub mod codex;
 mod codex_conversation;
+pub mod command_display;
+pub mod command_match;
+pub mod command_risk;
 pub mod token_data;
 pub use codex_conversation::CodexConversation;
 pub mod config;
@@ -57,15 +61,20 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+pub mod patch_ledger;
+pub mod patch_preview;
 pub mod plan_tool;
 pub mod project_doc;
+pub mod remote_patch;
 mod rollout;
 pub(crate) mod safety;
 pub mod seatbelt;
+pub mod session_rollback;
 pub mod shell;
 pub mod spawn;
 pub mod terminal;
 mod tool_apply_patch;
+pub mod trace_context;
 pub mod turn_diff_tracker;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
@@ -76,9 +85,37 @@ pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
 pub mod git_diff_tracker;
+mod omnara_ack;
+pub mod omnara_alerts;
+pub mod omnara_approval;
+pub mod omnara_capabilities;
+pub mod omnara_circuit;
 pub mod omnara_client;
+pub mod omnara_clock;
+pub mod omnara_diagnostics;
+pub mod omnara_doctor;
+pub mod omnara_escalation;
+pub mod omnara_http;
+pub mod omnara_i18n;
+pub mod omnara_journal;
+pub mod omnara_keychain;
+pub mod omnara_markdown;
+pub mod omnara_metrics;
+pub mod omnara_question;
+pub mod omnara_quiet_hours;
+pub mod omnara_sanitize;
+pub mod omnara_schedule;
+pub mod omnara_session_report;
+pub mod omnara_tags;
+pub mod omnara_transform;
+pub mod omnara_uploads;
+pub mod omnara_voice;
+pub mod omnara_withhold;
+pub mod omnara_workspace;
+mod remote_frontend;
 mod user_notification;
 pub mod util;
+pub mod vcs_diff_tracker;
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
 pub use safety::get_platform_sandbox;
 // Re-export the protocol types from the standalone `codex-protocol` crate so existing
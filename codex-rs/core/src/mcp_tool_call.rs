@@ -3,18 +3,23 @@ use std::time::Instant;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 
 /// Handles the specified tool call dispatches the appropriate
-/// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
+/// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`. Calls to
+/// servers configured with `require_approval` are approved first, or
+/// rejected outright when `approval_policy` is `never`.
 pub(crate) async fn handle_mcp_tool_call(
     sess: &Session,
+    approval_policy: AskForApproval,
     sub_id: &str,
     call_id: String,
     server: String,
@@ -47,6 +52,38 @@ pub(crate) async fn handle_mcp_tool_call(
         arguments: arguments_value.clone(),
     };
 
+    if sess.mcp_tool_needs_approval(&server, &tool_name).await {
+        if approval_policy == AskForApproval::Never {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: "tool call requires approval; rejected by user approval settings"
+                        .to_string(),
+                    success: Some(false),
+                },
+            };
+        }
+        let decision = sess
+            .request_mcp_tool_approval(sub_id.to_string(), call_id.clone(), invocation.clone())
+            .await;
+        match decision {
+            ReviewDecision::Approved => (),
+            ReviewDecision::ApprovedForSession => {
+                sess.add_approved_mcp_tool(server.clone(), tool_name.clone())
+                    .await;
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: FunctionCallOutputPayload {
+                        content: "tool call rejected by user".to_string(),
+                        success: Some(false),
+                    },
+                };
+            }
+        }
+    }
+
     let tool_call_begin_event = EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
         call_id: call_id.clone(),
         invocation: invocation.clone(),
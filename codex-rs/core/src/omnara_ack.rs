@@ -0,0 +1,155 @@
+//! Durable delivery acknowledgements for remote Omnara input.
+//!
+//! The poller hands each remote message to the frontend exactly once, even
+//! when Codex restarts into the same Omnara session (`OMNARA_SESSION_ID`):
+//! the last-read message id and the ids of messages already handed off are
+//! persisted next to the session log. A message is recorded only after it is
+//! handed off, so a crash in between redelivers it rather than losing it, and
+//! a redelivery of a recorded id is skipped.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+/// Processed message ids remembered; older ones are forgotten first.
+const MAX_PROCESSED_IDS: usize = 512;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AckState {
+    last_read_message_id: Option<String>,
+    processed: VecDeque<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct AckStore {
+    path: PathBuf,
+    state: AckState,
+}
+
+impl AckStore {
+    /// Load the state persisted at `path`, starting empty when there is none
+    /// (or it cannot be read).
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let state = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                warn!(path = %path.display(), "Omnara ack state unreadable; starting fresh: {e}");
+                AckState::default()
+            }),
+            Err(_) => AckState::default(),
+        };
+        Self { path, state }
+    }
+
+    pub(crate) fn last_read_message_id(&self) -> Option<&str> {
+        self.state.last_read_message_id.as_deref()
+    }
+
+    pub(crate) fn set_last_read_message_id(&mut self, id: &str) {
+        if self.last_read_message_id() != Some(id) {
+            self.state.last_read_message_id = Some(id.to_string());
+            self.persist();
+        }
+    }
+
+    /// Whether `id` was recorded as handed off, i.e. a delivery of it now is
+    /// a redelivery and must be dropped.
+    pub(crate) fn is_processed(&self, id: &str) -> bool {
+        self.state.processed.iter().any(|seen| seen == id)
+    }
+
+    /// Record `id` as handed off. Returns false when it already was.
+    pub(crate) fn mark_processed(&mut self, id: &str) -> bool {
+        if self.is_processed(id) {
+            return false;
+        }
+        self.state.processed.push_back(id.to_string());
+        while self.state.processed.len() > MAX_PROCESSED_IDS {
+            self.state.processed.pop_front();
+        }
+        self.persist();
+        true
+    }
+
+    /// Write to a temporary file and rename it over the old state, so a crash
+    /// mid-write never leaves a torn file behind.
+    fn persist(&self) {
+        if let Err(e) = write_atomically(&self.path, &self.state) {
+            warn!(path = %self.path.display(), "Failed to persist Omnara ack state: {e}");
+        }
+    }
+}
+
+/// Write `state` as JSON to a temporary file and rename it over `path`.
+pub(crate) fn write_atomically<T: Serialize>(path: &Path, state: &T) -> std::io::Result<()> {
+    use std::io::Write as _;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec(state)?)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn state_survives_a_reload_and_forgets_the_oldest_ids() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("session.ack.json");
+
+        let mut store = AckStore::load(path.clone());
+        assert_eq!(store.last_read_message_id(), None);
+        store.set_last_read_message_id("msg-3");
+        assert!(store.mark_processed("msg-4"));
+        assert!(!store.mark_processed("msg-4"));
+
+        let mut reloaded = AckStore::load(path.clone());
+        assert_eq!(reloaded.last_read_message_id(), Some("msg-3"));
+        assert!(!reloaded.mark_processed("msg-4"));
+
+        for i in 0..MAX_PROCESSED_IDS {
+            assert!(reloaded.mark_processed(&format!("other-{i}")));
+        }
+        assert!(reloaded.mark_processed("msg-4"));
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn an_id_counts_as_processed_only_once_recorded() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("session.ack.json");
+
+        // Crash after the hand-off but before recording: redelivered.
+        let store = AckStore::load(path.clone());
+        assert!(!store.is_processed("msg-1"));
+        drop(store);
+        let mut store = AckStore::load(path.clone());
+        assert!(!store.is_processed("msg-1"));
+
+        // Crash after recording: skipped.
+        assert!(store.mark_processed("msg-1"));
+        drop(store);
+        assert!(AckStore::load(path).is_processed("msg-1"));
+    }
+
+    #[test]
+    fn corrupt_state_starts_fresh() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("session.ack.json");
+        std::fs::write(&path, "{not json").expect("write");
+
+        let mut store = AckStore::load(path);
+        assert_eq!(store.last_read_message_id(), None);
+        assert!(store.mark_processed("msg-1"));
+    }
+}
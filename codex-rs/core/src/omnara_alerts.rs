@@ -0,0 +1,135 @@
+//! Alerts for sessions that need attention (`[omnara.alerts]`).
+//!
+//! A turn that keeps running, or an approval prompt nobody answers, is easy
+//! to miss on the dashboard. Frontends arm an [`Alerts`] timer when a turn
+//! starts or a prompt is posted and abort it when the turn ends or the
+//! approval resolves. When it fires they post an urgent message (push,
+//! email, and SMS notifications requested) and, with `webhook_url`, notify
+//! the webhook the same way the escalation chain does.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::config_types::OmnaraAlerts;
+use crate::config_types::OmnaraLocale;
+use crate::omnara_clock::Clock;
+use crate::omnara_i18n::fill;
+use crate::omnara_i18n::strings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    /// A turn has run for the configured time.
+    Turn,
+    /// An approval prompt has gone unanswered for the configured time.
+    Approval,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alerts {
+    turn: Option<Duration>,
+    approval: Option<Duration>,
+    webhook_url: Option<String>,
+}
+
+impl Alerts {
+    /// The configured alerts, or `None` when both are disabled.
+    pub fn from_config(config: &OmnaraAlerts) -> Option<Self> {
+        let minutes = |m: u64| (m > 0).then(|| Duration::from_secs(m * 60));
+        let alerts = Self {
+            turn: minutes(config.turn_minutes),
+            approval: minutes(config.approval_minutes),
+            webhook_url: config.webhook_url.clone(),
+        };
+        (alerts.turn.is_some() || alerts.approval.is_some()).then_some(alerts)
+    }
+
+    /// How long before a `kind` alert fires; `None` when it is disabled.
+    pub fn threshold(&self, kind: AlertKind) -> Option<Duration> {
+        match kind {
+            AlertKind::Turn => self.turn,
+            AlertKind::Approval => self.approval,
+        }
+    }
+
+    pub fn webhook_url(&self) -> Option<&str> {
+        self.webhook_url.as_deref()
+    }
+
+    /// Call `on_alert` with the threshold once a `kind` alert comes due on
+    /// `clock`; `None` when that alert is disabled. Abort the handle when
+    /// the turn ends or the approval resolves.
+    pub fn arm(
+        &self,
+        kind: AlertKind,
+        clock: Arc<dyn Clock>,
+        on_alert: impl FnOnce(Duration) + Send + 'static,
+    ) -> Option<JoinHandle<()>> {
+        let threshold = self.threshold(kind)?;
+        Some(tokio::spawn(async move {
+            clock.sleep(threshold).await;
+            on_alert(threshold);
+        }))
+    }
+}
+
+/// Dashboard note for a turn that has been running for `elapsed`.
+pub fn format_turn_alert(elapsed: Duration, locale: OmnaraLocale) -> String {
+    let headline = fill(
+        strings(locale).turn_alert,
+        &[("minutes", &(elapsed.as_secs() / 60))],
+    );
+    format!("**🚨 {headline}**")
+}
+
+/// The pending approval `prompt`, re-sent under an alert headline.
+pub fn format_approval_alert(prompt: &str, waited: Duration, locale: OmnaraLocale) -> String {
+    let headline = fill(
+        strings(locale).approval_alert,
+        &[("minutes", &(waited.as_secs() / 60))],
+    );
+    format!("**🚨 {headline}**\n\n{prompt}")
+}
+
+/// Plain-text webhook notice for a turn that has been running for `elapsed`.
+pub fn format_turn_alert_webhook_text(session_id: &str, elapsed: Duration) -> String {
+    format!(
+        "Codex session {session_id} has been running the same turn for {} min",
+        elapsed.as_secs() / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn disabled_alerts_are_none() {
+        assert_eq!(Alerts::from_config(&OmnaraAlerts::default()), None);
+        let alerts = Alerts::from_config(&OmnaraAlerts {
+            turn_minutes: 0,
+            approval_minutes: 15,
+            webhook_url: None,
+        })
+        .expect("alerts");
+        assert_eq!(alerts.threshold(AlertKind::Turn), None);
+        assert_eq!(
+            alerts.threshold(AlertKind::Approval),
+            Some(Duration::from_secs(900))
+        );
+        assert_eq!(
+            format_approval_alert(
+                "**Execute command?**",
+                Duration::from_secs(900),
+                OmnaraLocale::En
+            ),
+            "**🚨 No reply to this approval for 15 min**\n\n**Execute command?**"
+        );
+        assert_eq!(
+            format_turn_alert(Duration::from_secs(1800), OmnaraLocale::En),
+            "**🚨 Turn still running after 30 min**"
+        );
+    }
+}
@@ -0,0 +1,2262 @@
+//! Frontend-agnostic Omnara approval prompts.
+//!
+//! Approval requests are sent to the dashboard as Markdown with an `[OPTIONS]`
+//! block; the reply is the chosen option's text. Both the TUI bridge and the
+//! core-side remote frontend format prompts and parse replies here so every
+//! frontend offers the same choices.
+
+use crate::command_display::ShellSyntax;
+use crate::command_display::code_span;
+use crate::command_display::display_command;
+use crate::command_display::quote_arg;
+use crate::command_match::command_delta;
+use crate::command_risk::CommandRisk;
+use crate::command_risk::RiskFlag;
+use crate::command_risk::RiskLevel;
+use crate::command_risk::classify_command;
+use crate::config_types::ApprovalSynonyms;
+use crate::config_types::Omnara as OmnaraConfig;
+use crate::config_types::OmnaraLocale;
+use crate::config_types::OmnaraWordDiff;
+use crate::omnara_i18n::fill;
+use crate::omnara_i18n::strings;
+use crate::omnara_withhold::WITHHELD_PLACEHOLDER;
+use crate::omnara_withhold::WithheldPaths;
+use crate::patch_preview::CheckResult;
+use crate::patch_preview::PreviewError;
+use crate::protocol::FileChange;
+use crate::protocol::McpInvocation;
+use crate::protocol::PermissionEscalation;
+use crate::protocol::ReviewDecision;
+use crate::remote_patch::RemotePatchError;
+use crate::session_rollback::RollbackError;
+use similar::ChangeTag;
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use wildmatch::WildMatch;
+
+/// Maximum diff lines shown inline per file before the preview is truncated.
+pub const MAX_DIFF_LINES: usize = 100;
+
+/// The files a patch touches, in the order [`format_patch_details`] lists
+/// (and numbers) them.
+pub fn patch_files(changes: &HashMap<PathBuf, FileChange>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = changes.keys().cloned().collect();
+    files.sort();
+    files
+}
+
+/// One hunk of a patch, as patch summaries and `show N` replies see it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchHunk {
+    /// Index of the file in [`patch_files`].
+    pub file: usize,
+    /// The hunk's lines, `@@` header first.
+    pub text: String,
+    /// Added plus removed lines.
+    pub changed: usize,
+}
+
+/// The hunks of one file change, numbered from `file`; new files are one
+/// hunk, deleted files have none.
+pub fn patch_hunks(file: usize, change: &FileChange) -> Vec<PatchHunk> {
+    match change {
+        FileChange::Add { content } => {
+            let count = content.lines().count();
+            let mut text = format!("@@ -0,0 +1,{count} @@\n");
+            for line in content.lines() {
+                text.push('+');
+                text.push_str(line);
+                text.push('\n');
+            }
+            vec![PatchHunk {
+                file,
+                text,
+                changed: count,
+            }]
+        }
+        FileChange::Update { unified_diff, .. } => {
+            let mut hunks: Vec<PatchHunk> = Vec::new();
+            for line in unified_diff.lines() {
+                if line.starts_with("@@") {
+                    hunks.push(PatchHunk {
+                        file,
+                        text: String::new(),
+                        changed: 0,
+                    });
+                }
+                let Some(hunk) = hunks.last_mut() else {
+                    continue;
+                };
+                if !line.starts_with("@@") && (line.starts_with('+') || line.starts_with('-')) {
+                    hunk.changed += 1;
+                }
+                hunk.text.push_str(line);
+                hunk.text.push('\n');
+            }
+            hunks
+        }
+        FileChange::Delete { .. } => Vec::new(),
+    }
+}
+
+/// How [`format_patch_details`] renders updated files. The default always
+/// uses fenced diffs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PatchFormat {
+    /// Show an updated file whose hunks each change at most this many lines
+    /// as ``line 42: `old` → `new` `` entries instead of a fenced diff;
+    /// 0 turns the compact rendering off.
+    pub compact_max_lines: usize,
+    /// Intra-line highlighting for files not shown compactly.
+    pub word_diff: OmnaraWordDiff,
+}
+
+impl From<&OmnaraConfig> for PatchFormat {
+    fn from(config: &OmnaraConfig) -> Self {
+        Self {
+            compact_max_lines: config.compact_patch_lines,
+            word_diff: config.word_diff,
+        }
+    }
+}
+
+/// Format patch changes for display in Omnara dashboard, one section per
+/// file. Multi-file patches number the files so replies can refer to them.
+/// The content of `withheld` files is replaced by a placeholder; their lines
+/// are still counted. Returns (details_markdown, added_lines, removed_lines).
+pub fn format_patch_details(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+    format: PatchFormat,
+) -> (String, usize, usize) {
+    let mut patch_details = String::new();
+    let mut added_lines = 0usize;
+    let mut removed_lines = 0usize;
+
+    let files = patch_files(changes);
+    for (index, path) in files.iter().enumerate() {
+        let Some(change) = changes.get(path) else {
+            continue;
+        };
+        let number = if files.len() > 1 {
+            format!("{}. ", index + 1)
+        } else {
+            String::new()
+        };
+        let path_str = path.display().to_string();
+
+        if !patch_details.is_empty() {
+            patch_details.push('\n');
+        }
+
+        if withheld.matches(path) {
+            let (added, removed) = change_line_counts(change);
+            added_lines += added;
+            removed_lines += removed;
+            let label = match change {
+                FileChange::Add { .. } => "New file: ",
+                FileChange::Delete { .. } => "Delete file: ",
+                FileChange::Update { .. } => "",
+            };
+            patch_details.push_str(&format!(
+                "**{number}{label}{path_str}** (+{added} -{removed})\n{WITHHELD_PLACEHOLDER}\n"
+            ));
+            continue;
+        }
+
+        match change {
+            FileChange::Add { content } => {
+                added_lines += content.lines().count();
+                patch_details.push_str(&format!("**{number}New file: {path_str}**\n"));
+                patch_details.push_str("```diff\n");
+                let total = content.lines().count();
+                for line in content.lines().take(MAX_DIFF_LINES) {
+                    patch_details.push_str(&format!("+{line}\n"));
+                }
+                if total > MAX_DIFF_LINES {
+                    let more = total - MAX_DIFF_LINES;
+                    patch_details.push_str(&format!("... ({more} more lines)\n"));
+                }
+                patch_details.push_str("```\n");
+            }
+            FileChange::Update { unified_diff, .. } => {
+                patch_details.push_str(&format!("**{number}{path_str}**\n"));
+                if let Some(compact) = compact_diff(unified_diff, format.compact_max_lines) {
+                    patch_details.push_str(&compact);
+                } else if let Some(words) = word_diff(unified_diff, format.word_diff) {
+                    patch_details.push_str(&words);
+                } else {
+                    patch_details.push_str("```diff\n");
+                    let total = unified_diff.lines().count();
+                    for line in unified_diff.lines().take(MAX_DIFF_LINES) {
+                        patch_details.push_str(line);
+                        patch_details.push('\n');
+                    }
+                    if total > MAX_DIFF_LINES {
+                        let more = total - MAX_DIFF_LINES;
+                        patch_details.push_str(&format!("... ({more} more lines)\n"));
+                    }
+                    patch_details.push_str("```\n");
+                }
+
+                for line in unified_diff.lines() {
+                    if line.starts_with('+') && !line.starts_with("+++") {
+                        added_lines += 1;
+                    } else if line.starts_with('-') && !line.starts_with("---") {
+                        removed_lines += 1;
+                    }
+                }
+            }
+            FileChange::Delete { content } => {
+                removed_lines += content.lines().count();
+                patch_details.push_str(&format!("**{number}Delete file: {path_str}**\n"));
+            }
+        }
+    }
+
+    (patch_details, added_lines, removed_lines)
+}
+
+/// `unified_diff` as one `- line N: ...` entry per changed line: `old` →
+/// `new` for a replaced line (numbered in the old file), `+` for an added
+/// line (numbered in the new file), `−` for a removed one. None, for a fenced
+/// diff instead, when `max_lines` is 0, a hunk changes more than `max_lines`
+/// lines, or a run of changes replaces some lines with a different number of
+/// lines.
+fn compact_diff(unified_diff: &str, max_lines: usize) -> Option<String> {
+    if max_lines == 0 {
+        return None;
+    }
+    let mut out = String::new();
+    let (mut old_line, mut new_line) = (0, 0);
+    let mut removed: Vec<(usize, &str)> = Vec::new();
+    let mut added: Vec<(usize, &str)> = Vec::new();
+    let mut hunk_lines = 0;
+    let mut in_hunk = false;
+    for line in unified_diff.lines() {
+        if line.starts_with("@@") {
+            hunk_lines = 0;
+            (old_line, new_line) = hunk_starts(line)?;
+            in_hunk = true;
+            continue;
+        }
+        if !in_hunk {
+            continue;
+        }
+        if let Some(text) = line.strip_prefix('-') {
+            if !added.is_empty() {
+                hunk_lines += push_compact_run(&mut out, &mut removed, &mut added)?;
+            }
+            removed.push((old_line, text));
+            old_line += 1;
+        } else if let Some(text) = line.strip_prefix('+') {
+            added.push((new_line, text));
+            new_line += 1;
+        } else if !line.starts_with('\\') {
+            hunk_lines += push_compact_run(&mut out, &mut removed, &mut added)?;
+            old_line += 1;
+            new_line += 1;
+        }
+        if hunk_lines + removed.len().max(added.len()) > max_lines {
+            return None;
+        }
+    }
+    push_compact_run(&mut out, &mut removed, &mut added)?;
+    (!out.is_empty() && out.lines().count() <= MAX_DIFF_LINES).then_some(out)
+}
+
+/// Old and new start lines from a `@@ -a,b +c,d @@` hunk header.
+fn hunk_starts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.split_whitespace().skip(1);
+    let start = |range: &str| range.split(',').next()?.parse().ok();
+    let old = start(ranges.next()?.strip_prefix('-')?)?;
+    let new = start(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// Render one run of removed-then-added lines, clearing both; returns how
+/// many changed lines it covered.
+fn push_compact_run(
+    out: &mut String,
+    removed: &mut Vec<(usize, &str)>,
+    added: &mut Vec<(usize, &str)>,
+) -> Option<usize> {
+    let count = removed.len().max(added.len());
+    if removed.is_empty() {
+        for (line, text) in added.iter() {
+            out.push_str(&format!("- line {line}: + {}\n", inline_code(text)));
+        }
+    } else if added.is_empty() {
+        for (line, text) in removed.iter() {
+            out.push_str(&format!("- line {line}: \u{2212} {}\n", inline_code(text)));
+        }
+    } else if removed.len() == added.len() {
+        for ((line, old), (_, new)) in removed.iter().zip(added.iter()) {
+            out.push_str(&format!(
+                "- line {line}: {} \u{2192} {}\n",
+                inline_code(old),
+                inline_code(new)
+            ));
+        }
+    } else {
+        return None;
+    }
+    removed.clear();
+    added.clear();
+    Some(count)
+}
+
+/// A source line as Markdown inline code, without its indentation.
+fn inline_code(text: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        "(blank line)".to_string()
+    } else if text.contains('`') {
+        format!("`` {text} ``")
+    } else {
+        format!("`{text}`")
+    }
+}
+
+/// `unified_diff` with each run of removed lines merged into the added lines
+/// that replace it, pairwise, marking only the words that differ; surplus
+/// lines of a run are marked whole. Context lines lose their ` ` prefix.
+/// None for [`OmnaraWordDiff::Off`].
+fn word_diff(unified_diff: &str, style: OmnaraWordDiff) -> Option<String> {
+    let (open, close) = match style {
+        OmnaraWordDiff::Off => return None,
+        OmnaraWordDiff::Markers => ("```\n", "```\n"),
+        OmnaraWordDiff::Html => ("<pre>\n", "</pre>\n"),
+    };
+    let mut lines = Vec::new();
+    let mut removed: Vec<&str> = Vec::new();
+    let mut added: Vec<&str> = Vec::new();
+    let mut in_hunk = false;
+    for line in unified_diff.lines() {
+        if let Some(text) = line.strip_prefix('-').filter(|_| in_hunk) {
+            if !added.is_empty() {
+                push_word_run(&mut lines, &mut removed, &mut added, style);
+            }
+            removed.push(text);
+        } else if let Some(text) = line.strip_prefix('+').filter(|_| in_hunk) {
+            added.push(text);
+        } else {
+            push_word_run(&mut lines, &mut removed, &mut added, style);
+            if line.starts_with("@@") {
+                in_hunk = true;
+                lines.push(escape_words(line, style));
+            } else if in_hunk && !line.starts_with('\\') {
+                lines.push(escape_words(line.strip_prefix(' ').unwrap_or(line), style));
+            }
+        }
+    }
+    push_word_run(&mut lines, &mut removed, &mut added, style);
+
+    let mut out = open.to_string();
+    for line in lines.iter().take(MAX_DIFF_LINES) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if lines.len() > MAX_DIFF_LINES {
+        let more = lines.len() - MAX_DIFF_LINES;
+        out.push_str(&format!("... ({more} more lines)\n"));
+    }
+    out.push_str(close);
+    Some(out)
+}
+
+/// Render one run of removed-then-added lines for [`word_diff`], clearing
+/// both.
+fn push_word_run(
+    lines: &mut Vec<String>,
+    removed: &mut Vec<&str>,
+    added: &mut Vec<&str>,
+    style: OmnaraWordDiff,
+) {
+    let paired = removed.len().min(added.len());
+    for (old, new) in removed.iter().zip(added.iter()) {
+        let diff = similar::TextDiff::from_words(*old, *new);
+        let mut line = String::new();
+        let mut span: Option<(ChangeTag, String)> = None;
+        for change in diff.iter_all_changes() {
+            match &mut span {
+                Some((tag, text)) if *tag == change.tag() => text.push_str(change.value()),
+                _ => {
+                    if let Some((tag, text)) = span.take() {
+                        push_word_span(&mut line, tag, &text, style);
+                    }
+                    span = Some((change.tag(), change.value().to_string()));
+                }
+            }
+        }
+        if let Some((tag, text)) = span {
+            push_word_span(&mut line, tag, &text, style);
+        }
+        lines.push(line);
+    }
+    for old in &removed[paired..] {
+        let mut line = String::new();
+        push_word_span(&mut line, ChangeTag::Delete, old, style);
+        lines.push(line);
+    }
+    for new in &added[paired..] {
+        let mut line = String::new();
+        push_word_span(&mut line, ChangeTag::Insert, new, style);
+        lines.push(line);
+    }
+    removed.clear();
+    added.clear();
+}
+
+fn push_word_span(line: &mut String, tag: ChangeTag, text: &str, style: OmnaraWordDiff) {
+    let text = escape_words(text, style);
+    match (tag, style) {
+        (ChangeTag::Equal, _) => line.push_str(&text),
+        (ChangeTag::Delete, OmnaraWordDiff::Html) => line.push_str(&format!("<del>{text}</del>")),
+        (ChangeTag::Insert, OmnaraWordDiff::Html) => line.push_str(&format!("<ins>{text}</ins>")),
+        (ChangeTag::Delete, _) => line.push_str(&format!("[-{text}-]")),
+        (ChangeTag::Insert, _) => line.push_str(&format!("{{+{text}+}}")),
+    }
+}
+
+/// `text` escaped for a `<pre>` block in HTML mode; unchanged otherwise.
+fn escape_words(text: &str, style: OmnaraWordDiff) -> String {
+    match style {
+        OmnaraWordDiff::Html => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        _ => text.to_string(),
+    }
+}
+
+/// Lines added and removed by one file change.
+pub fn change_line_counts(change: &FileChange) -> (usize, usize) {
+    match change {
+        FileChange::Add { content } => (content.lines().count(), 0),
+        FileChange::Delete { content } => (0, content.lines().count()),
+        FileChange::Update { unified_diff, .. } => {
+            unified_diff.lines().fold((0, 0), |(added, removed), line| {
+                if line.starts_with('+') && !line.starts_with("+++") {
+                    (added + 1, removed)
+                } else if line.starts_with('-') && !line.starts_with("---") {
+                    (added, removed + 1)
+                } else {
+                    (added, removed)
+                }
+            })
+        }
+    }
+}
+
+/// Risk badge for a command, e.g. "⚠️ **High risk:** uses sudo, writes
+/// outside workspace". `None` for low-risk commands.
+pub fn format_risk_badge(risk: &CommandRisk, locale: OmnaraLocale) -> Option<String> {
+    let text = strings(locale);
+    let (emoji, headline) = match risk.level {
+        RiskLevel::Low => return None,
+        RiskLevel::Medium => ("🔶", text.risk_medium),
+        RiskLevel::High => ("⚠️", text.risk_high),
+    };
+    let highlights: Vec<&str> = risk
+        .flags
+        .iter()
+        .map(|flag| match flag {
+            RiskFlag::Sudo => text.risk_sudo,
+            RiskFlag::Credentials => text.risk_credentials,
+            RiskFlag::WritesOutsideWorkspace => text.risk_writes_outside,
+            RiskFlag::PackageInstall => text.risk_package_install,
+            RiskFlag::Network => text.risk_network,
+        })
+        .collect();
+    Some(format!("{emoji} **{headline}:** {}", highlights.join(", ")))
+}
+
+/// Risk level of an exec approval, for `[omnara.approval_push]`: the
+/// command's classification, or high for a sandbox escalation.
+pub fn exec_approval_risk(
+    command: &[String],
+    cwd: &Path,
+    escalation: Option<PermissionEscalation>,
+) -> RiskLevel {
+    match escalation {
+        Some(_) => RiskLevel::High,
+        None => classify_command(command, cwd).level,
+    }
+}
+
+/// Risk level of a patch approval, for `[omnara.approval_push]`: medium, or
+/// high when the patch needs write access outside the workspace.
+pub fn patch_approval_risk(grant_root: Option<&Path>) -> RiskLevel {
+    match grant_root {
+        Some(_) => RiskLevel::High,
+        None => RiskLevel::Medium,
+    }
+}
+
+/// What `command` changes from `approved`, the closest command approved for
+/// the session, e.g. "≈ **Close to a command approved for this session:**
+/// `git push origin main` — adds `--force`". `None` when they do not differ.
+pub fn format_near_approved(
+    approved: &[String],
+    command: &[String],
+    locale: OmnaraLocale,
+) -> Option<String> {
+    let delta = command_delta(approved, command);
+    if delta.is_empty() {
+        return None;
+    }
+    let text = strings(locale);
+    let words = |words: &[String]| {
+        words
+            .iter()
+            .map(|word| code_span(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut changes = Vec::new();
+    if !delta.added.is_empty() {
+        changes.push(format!("{} {}", text.words_added, words(&delta.added)));
+    }
+    if !delta.removed.is_empty() {
+        changes.push(format!("{} {}", text.words_removed, words(&delta.removed)));
+    }
+    Some(format!(
+        "≈ **{}:** {} — {}",
+        text.near_approved,
+        display_command(approved).code_span(),
+        changes.join("; ")
+    ))
+}
+
+/// Format an exec approval request message with command, risk badge, and
+/// options. `closest_approved` is the session-approved command this one
+/// nearly matches, if any; what differs from it is shown under the badge.
+pub fn format_exec_approval_request(
+    command: &[String],
+    cwd: &Path,
+    reason: Option<&str>,
+    closest_approved: Option<&[String]>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let command_line = display_command(command);
+    let reason_str = reason.unwrap_or(text.exec_default_reason);
+    let badge = format_risk_badge(&classify_command(command, cwd), locale)
+        .into_iter()
+        .chain(
+            closest_approved.and_then(|approved| format_near_approved(approved, command, locale)),
+        )
+        .map(|line| format!("{line}\n\n"))
+        .collect::<String>();
+    format!(
+        "**{}**\n\n{badge}{reason_str}\n\n{}\n\n{}",
+        text.execute_command,
+        command_line.code_block(),
+        options_block(EXEC_APPROVAL_OPTIONS, locale)
+    )
+}
+
+/// An exec approval request, as gathered into a batch prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecRequest {
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub reason: Option<String>,
+    /// The session-approved command this one nearly matches.
+    pub closest_approved: Option<Vec<String>>,
+}
+
+/// Format one approval prompt for several exec requests, numbered so a
+/// reply can decide each of them (see [`ApprovalParser::parse_batch`]).
+pub fn format_exec_batch_approval_request(
+    requests: &[ExecRequest],
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let mut msg = format!(
+        "**{}**",
+        fill(text.execute_commands, &[("count", &requests.len())])
+    );
+    for (i, request) in requests.iter().enumerate() {
+        msg.push_str(&format!("\n\n**{}.**", i + 1));
+        if let Some(reason) = &request.reason {
+            msg.push_str(&format!(" {reason}"));
+        }
+        if let Some(badge) =
+            format_risk_badge(&classify_command(&request.command, &request.cwd), locale)
+        {
+            msg.push_str(&format!("\n{badge}"));
+        }
+        if let Some(near) = request
+            .closest_approved
+            .as_deref()
+            .and_then(|approved| format_near_approved(approved, &request.command, locale))
+        {
+            msg.push_str(&format!("\n{near}"));
+        }
+        msg.push_str(&format!(
+            "\n{}",
+            display_command(&request.command).code_block()
+        ));
+    }
+    msg.push_str(&format!(
+        "\n\n{}\n\n{}",
+        text.batch_hint,
+        options_block(EXEC_APPROVAL_OPTIONS, locale)
+    ));
+    msg
+}
+
+/// What a reaction on an approval prompt answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalReaction {
+    /// 👍: approve, like a "yes" reply.
+    Approve,
+    /// 👎: deny and stop, like a "no" reply.
+    Deny,
+    /// 🔁: post the prompt again with more detail. (As a text reply 🔁
+    /// means "always".)
+    AskAgain,
+}
+
+impl ApprovalReaction {
+    /// The answer `emoji` gives, ignoring skin tones and variation
+    /// selectors; `None` for other reactions.
+    pub fn from_emoji(emoji: &str) -> Option<Self> {
+        let base: String = emoji
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, '\u{fe0f}' | '\u{1f3fb}'..='\u{1f3ff}'))
+            .collect();
+        match base.as_str() {
+            "👍" => Some(ApprovalReaction::Approve),
+            "👎" => Some(ApprovalReaction::Deny),
+            "🔁" => Some(ApprovalReaction::AskAgain),
+            _ => None,
+        }
+    }
+
+    /// The text reply with the same answer, for reactions that have one.
+    pub fn reply(self) -> Option<&'static str> {
+        match self {
+            ApprovalReaction::Approve => Some("👍"),
+            ApprovalReaction::Deny => Some("👎"),
+            ApprovalReaction::AskAgain => None,
+        }
+    }
+}
+
+/// `prompt` posted again after a 🔁 reaction, with more detail above its
+/// options: for an exec approval the working directory and every argument
+/// as passed, for a patch each file as a full diff. Other prompts are
+/// returned as they are.
+pub fn format_prompt_with_details(
+    prompt: &str,
+    exec: Option<&ExecRequest>,
+    changes: Option<&HashMap<PathBuf, FileChange>>,
+    withheld: &WithheldPaths,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let body = if let Some(request) = exec {
+        let mut body = format!(
+            "**{}:** `{}`\n\n**{}:**",
+            text.working_directory,
+            request.cwd.display(),
+            text.arguments
+        );
+        for arg in &request.command {
+            // An empty argument would be an empty code span.
+            let shown = if arg.is_empty() {
+                quote_arg(arg, ShellSyntax::host())
+            } else {
+                arg.clone()
+            };
+            body.push_str(&format!("\n- {}", code_span(&shown)));
+        }
+        body
+    } else if let Some(changes) = changes {
+        let (details, _, _) = format_patch_details(changes, withheld, PatchFormat::default());
+        details.trim_end().to_string()
+    } else {
+        return prompt.to_string();
+    };
+    let details = format!("**🔍 {}**\n\n{body}\n\n", text.details_title);
+    match prompt.find("[OPTIONS]") {
+        Some(options) => format!("{}{details}{}", &prompt[..options], &prompt[options..]),
+        None => format!("{}\n\n{}", prompt.trim_end(), details.trim_end()),
+    }
+}
+
+/// Format an approval request for running a command outside the sandbox.
+/// Spells out what is being lifted, since an approval here is broader than a
+/// regular exec approval.
+pub fn format_escalation_approval_request(
+    command: &[String],
+    cwd: &Path,
+    reason: Option<&str>,
+    escalation: PermissionEscalation,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let command_line = display_command(command);
+    let (title, context) = match escalation {
+        PermissionEscalation::Requested => {
+            (text.outside_sandbox_title, text.outside_sandbox_context)
+        }
+        PermissionEscalation::RetryWithoutSandbox => {
+            (text.retry_sandbox_title, text.retry_sandbox_context)
+        }
+    };
+    let mut msg = format!("**⚠️ {title}**\n\n{context}");
+    if let Some(badge) = format_risk_badge(&classify_command(command, cwd), locale) {
+        msg.push_str(&format!("\n\n{badge}"));
+    }
+    // The retry prompt's reason is the generic "retry without sandbox?".
+    if escalation == PermissionEscalation::Requested
+        && let Some(reason) = reason
+    {
+        msg.push_str(&format!("\n\n**{}:** {reason}", text.justification));
+    }
+    msg.push_str(&format!(
+        "\n\n**{}:** `{}`\n\n{}\n\n{}\n\n{}",
+        text.working_directory,
+        cwd.display(),
+        command_line.code_block(),
+        text.unsandboxed_warning,
+        options_block(ESCALATION_APPROVAL_OPTIONS, locale)
+    ));
+    msg
+}
+
+/// Maximum lines of pretty-printed MCP tool arguments shown in an approval
+/// prompt.
+pub const MAX_MCP_ARGUMENT_LINES: usize = 40;
+
+/// Maximum characters per argument line; long string values are clipped.
+const MAX_MCP_ARGUMENT_LINE_CHARS: usize = 200;
+
+/// Format an approval request for an MCP tool call, with the arguments
+/// pretty-printed and cut to [`MAX_MCP_ARGUMENT_LINES`].
+pub fn format_mcp_approval_request(invocation: &McpInvocation, locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    let mut msg = format!(
+        "**{}**\n\n**{}:** `{}`\n**{}:** `{}`",
+        text.call_mcp_tool, text.mcp_server, invocation.server, text.mcp_tool, invocation.tool
+    );
+    if let Some(arguments) = &invocation.arguments {
+        let pretty =
+            serde_json::to_string_pretty(arguments).unwrap_or_else(|_| arguments.to_string());
+        msg.push_str("\n\n```json\n");
+        let total = pretty.lines().count();
+        for line in pretty.lines().take(MAX_MCP_ARGUMENT_LINES) {
+            if line.chars().count() > MAX_MCP_ARGUMENT_LINE_CHARS {
+                let clipped: String = line.chars().take(MAX_MCP_ARGUMENT_LINE_CHARS).collect();
+                msg.push_str(&clipped);
+                msg.push('…');
+            } else {
+                msg.push_str(line);
+            }
+            msg.push('\n');
+        }
+        if total > MAX_MCP_ARGUMENT_LINES {
+            let more = total - MAX_MCP_ARGUMENT_LINES;
+            msg.push_str(&format!("... ({more} more lines)\n"));
+        }
+        msg.push_str("```");
+    }
+    msg.push_str("\n\n");
+    msg.push_str(&options_block(EXEC_APPROVAL_OPTIONS, locale));
+    msg
+}
+
+/// Format a patch approval request message with optional reason, grant root, and details.
+pub fn format_patch_approval_request(
+    file_count: usize,
+    added_lines: usize,
+    removed_lines: usize,
+    reason: Option<&str>,
+    grant_root: Option<&Path>,
+    patch_details: Option<&str>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let headline = if file_count == 1 {
+        text.patch_headline_one
+    } else {
+        text.patch_headline_many
+    };
+    let mut approval_msg = format!(
+        "**{}**",
+        fill(
+            headline,
+            &[
+                ("count", &file_count),
+                ("added", &added_lines),
+                ("removed", &removed_lines)
+            ]
+        )
+    );
+    if let Some(root) = grant_root {
+        approval_msg.push_str("\n\n");
+        approval_msg.push_str(&fill(text.grant_root, &[("root", &root.display())]));
+    }
+    if let Some(r) = reason {
+        approval_msg.push_str(&format!("\n\n{r}"));
+    }
+    if let Some(details) = patch_details
+        && !details.is_empty()
+    {
+        approval_msg.push_str("\n\n");
+        approval_msg.push_str(details);
+    }
+    approval_msg.push_str("\n\n");
+    approval_msg.push_str(&fill(text.preview_hint, &[("command", &PREVIEW_COMMAND)]));
+    approval_msg.push(' ');
+    approval_msg.push_str(&fill(text.explain_hint, &[("command", &EXPLAIN_COMMAND)]));
+    if file_count > 1 {
+        approval_msg.push(' ');
+        approval_msg.push_str(text.partial_hint);
+    }
+    approval_msg.push_str(&apply_changes_footer(locale));
+    approval_msg
+}
+
+/// The question and options closing a patch prompt (or preview result).
+fn apply_changes_footer(locale: OmnaraLocale) -> String {
+    format!(
+        "\n\n**{}**\n\n{}",
+        strings(locale).apply_changes,
+        options_block(PATCH_APPROVAL_OPTIONS, locale)
+    )
+}
+
+/// Reply to a patch approval asking for a dry run before deciding.
+pub const PREVIEW_COMMAND: &str = "/preview";
+
+/// Acknowledge a `/preview` reply while the dry run is in progress.
+pub fn format_patch_preview_started(command: &[String], locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    if command.is_empty() {
+        format!("🔬 {}", text.preview_started)
+    } else {
+        format!(
+            "🔬 {}",
+            fill(
+                text.preview_started_check,
+                &[("command", &command.join(" "))]
+            )
+        )
+    }
+}
+
+/// Format the outcome of a `/preview` dry run, asking for the decision again.
+pub fn format_patch_preview_result(
+    result: &Result<Option<CheckResult>, PreviewError>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let mut msg = match result {
+        Ok(None) => format!("**🔬 {}**", text.preview_applies),
+        Ok(Some(check)) => {
+            let command = check.command.join(" ");
+            let secs = check.duration.as_secs();
+            let args: [(&str, &dyn std::fmt::Display); 2] =
+                [("command", &command), ("secs", &secs)];
+            let headline = if check.timed_out {
+                fill(text.preview_timed_out, &args)
+            } else if check.passed() {
+                fill(text.preview_passed, &args)
+            } else {
+                match check.exit_code {
+                    Some(code) => fill(
+                        text.preview_failed,
+                        &[("command", &command), ("code", &code), ("secs", &secs)],
+                    ),
+                    None => fill(text.preview_killed, &args),
+                }
+            };
+            if check.output.is_empty() {
+                format!("**🔬 {headline}**")
+            } else {
+                format!("**🔬 {headline}**\n\n```text\n{}\n```", check.output)
+            }
+        }
+        Err(err) => format!("**🔬 {}**\n\n{err}", text.preview_error),
+    };
+    msg.push_str(&apply_changes_footer(locale));
+    msg
+}
+
+/// Reply to a patch approval asking for a plain-language summary of it.
+pub const EXPLAIN_COMMAND: &str = "/explain";
+
+/// Format the answer to an `/explain` reply, asking for the decision again.
+/// `None` means the summary could not be produced.
+pub fn format_patch_explanation(explanation: Option<&str>, locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    let mut msg = match explanation {
+        Some(explanation) => format!("**💡 {}**\n\n{explanation}", text.explanation_title),
+        None => format!("**💡 {}**", text.explanation_failed),
+    };
+    msg.push_str(&apply_changes_footer(locale));
+    msg
+}
+
+/// Reply to a patch approval asking to see one hunk in full (`show 3`).
+pub const SHOW_HUNK_COMMAND: &str = "show";
+
+/// Hunks listed in a patch prompt's index; the rest are counted.
+const MAX_INDEXED_HUNKS: usize = 50;
+
+/// Lines of an expanded hunk shown before it is cut.
+const MAX_EXPANDED_HUNK_LINES: usize = 400;
+
+/// The hunk number a `show N` reply asks for; `/show N` and `show #N` work
+/// too.
+pub fn parse_show_hunk(text: &str) -> Option<usize> {
+    let mut words = text.split_whitespace();
+    let command = words.next()?.trim_start_matches('/');
+    if !command.eq_ignore_ascii_case(SHOW_HUNK_COMMAND) {
+        return None;
+    }
+    let index = words.next()?.trim_start_matches('#').parse().ok()?;
+    words.next().is_none().then_some(index)
+}
+
+/// The hunks `show N` can expand, numbered from 1 in this order: files as
+/// [`patch_files`] lists them, withheld files skipped.
+pub fn approval_hunks(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+) -> Vec<PatchHunk> {
+    patch_files(changes)
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| !withheld.matches(path))
+        .filter_map(|(index, path)| Some(patch_hunks(index, changes.get(path)?)))
+        .flatten()
+        .collect()
+}
+
+/// [`format_patch_details`] for an approval prompt: with more than one
+/// hunk, followed by a numbered hunk index that `show N` replies refer to.
+pub fn format_patch_approval_details(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+    format: PatchFormat,
+    locale: OmnaraLocale,
+) -> (String, usize, usize) {
+    let (mut details, added, removed) = format_patch_details(changes, withheld, format);
+    let hunks = approval_hunks(changes, withheld);
+    if hunks.len() > 1 {
+        let text = strings(locale);
+        let files = patch_files(changes);
+        details.push_str(&format!("\n**{}**\n", text.hunks_title));
+        for (index, hunk) in hunks.iter().enumerate().take(MAX_INDEXED_HUNKS) {
+            let header = hunk.text.lines().next().unwrap_or_default();
+            details.push_str(&format!(
+                "- `#{}` {} `{header}` ±{}\n",
+                index + 1,
+                files[hunk.file].display(),
+                hunk.changed
+            ));
+        }
+        if hunks.len() > MAX_INDEXED_HUNKS {
+            let more = hunks.len() - MAX_INDEXED_HUNKS;
+            details.push_str(&format!("- … ({more} more)\n"));
+        }
+        details.push('\n');
+        details.push_str(&fill(
+            text.show_hunk_hint,
+            &[("command", &SHOW_HUNK_COMMAND)],
+        ));
+        details.push('\n');
+    }
+    (details, added, removed)
+}
+
+/// Format the answer to a `show N` reply: hunk `index` (from 1) in full, or
+/// why there is no such hunk, asking for the decision again.
+pub fn format_hunk_expansion(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+    index: usize,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let hunks = approval_hunks(changes, withheld);
+    let count = hunks.len();
+    let mut msg = match index.checked_sub(1).and_then(|i| hunks.get(i)) {
+        Some(hunk) => {
+            let path = patch_files(changes)[hunk.file].display().to_string();
+            let title = fill(
+                text.hunk_title,
+                &[("index", &index), ("count", &count), ("path", &path)],
+            );
+            let mut msg = format!("**🔎 {title}**\n\n```diff\n");
+            let total = hunk.text.lines().count();
+            for line in hunk.text.lines().take(MAX_EXPANDED_HUNK_LINES) {
+                msg.push_str(line);
+                msg.push('\n');
+            }
+            if total > MAX_EXPANDED_HUNK_LINES {
+                let more = total - MAX_EXPANDED_HUNK_LINES;
+                msg.push_str(&format!("... ({more} more lines)\n"));
+            }
+            msg.push_str("```");
+            msg
+        }
+        None => format!(
+            "⚠️ {}",
+            fill(text.no_such_hunk, &[("index", &index), ("count", &count)])
+        ),
+    };
+    msg.push_str(&apply_changes_footer(locale));
+    msg
+}
+
+/// Dashboard command that offers to undo the session's changes.
+pub const ROLLBACK_COMMAND: &str = "/rollback";
+
+/// Format the approval prompt for a `/rollback`, shown as the patch that
+/// undoes the session's changes.
+pub fn format_rollback_request(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+    format: PatchFormat,
+    locale: OmnaraLocale,
+) -> String {
+    let (details, added, removed) =
+        format_patch_approval_details(changes, withheld, format, locale);
+    let reason = format!("↩️ {}", strings(locale).rollback_reason);
+    format_patch_approval_request(
+        changes.len(),
+        added,
+        removed,
+        Some(&reason),
+        None,
+        Some(&details),
+        locale,
+    )
+}
+
+/// Format the outcome of an approved `/rollback`.
+pub fn format_rollback_result(
+    result: &Result<usize, RollbackError>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    match result {
+        Ok(count) => format!("**↩️ {}**", fill(text.rollback_done, &[("count", count)])),
+        Err(err) => format!("**↩️ {}**\n\n{err}", text.rollback_failed),
+    }
+}
+
+/// Dashboard command that hands the agent a patch, as a unified diff, to
+/// apply after local approval.
+pub const APPLY_PATCH_COMMAND: &str = "/apply-patch";
+
+/// Format the note acknowledging a valid `/apply-patch`, sent while it waits
+/// for approval in the terminal.
+pub fn format_remote_patch_received(count: usize, locale: OmnaraLocale) -> String {
+    format!(
+        "📥 {}",
+        fill(strings(locale).apply_patch_waiting, &[("count", &count)])
+    )
+}
+
+/// Format the reply to a `/apply-patch` whose diff was refused.
+pub fn format_remote_patch_refused(err: &RemotePatchError, locale: OmnaraLocale) -> String {
+    format!("**📥 {}**\n\n{err}", strings(locale).apply_patch_invalid)
+}
+
+/// Format the outcome of a `/apply-patch` approved in the terminal.
+pub fn format_remote_patch_result(
+    result: &Result<usize, RemotePatchError>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    match result {
+        Ok(count) => format!(
+            "**📥 {}**",
+            fill(text.apply_patch_done, &[("count", count)])
+        ),
+        Err(err) => format!("**📥 {}**\n\n{err}", text.apply_patch_failed),
+    }
+}
+
+/// One choice in an approval prompt's `[OPTIONS]` block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApprovalOption {
+    Yes,
+    Always,
+    No,
+}
+
+impl ApprovalOption {
+    fn label(self, locale: OmnaraLocale) -> &'static str {
+        let text = strings(locale);
+        match self {
+            ApprovalOption::Yes => text.option_yes,
+            ApprovalOption::Always => text.option_always,
+            ApprovalOption::No => text.option_no,
+        }
+    }
+}
+
+/// Options offered for exec approvals, in `[OPTIONS]` order.
+pub const EXEC_APPROVAL_OPTIONS: &[ApprovalOption] = &[
+    ApprovalOption::Yes,
+    ApprovalOption::Always,
+    ApprovalOption::No,
+];
+
+/// Options offered for patch approvals, in `[OPTIONS]` order.
+pub const PATCH_APPROVAL_OPTIONS: &[ApprovalOption] = &[ApprovalOption::Yes, ApprovalOption::No];
+
+/// Options offered for sandbox escalations. No "Always": each escalation is
+/// approved on its own.
+pub const ESCALATION_APPROVAL_OPTIONS: &[ApprovalOption] =
+    &[ApprovalOption::Yes, ApprovalOption::No];
+
+fn options_block(options: &[ApprovalOption], locale: OmnaraLocale) -> String {
+    labels_block(options.iter().map(|option| option.label(locale)))
+}
+
+pub(crate) fn labels_block<'a>(labels: impl Iterator<Item = &'a str>) -> String {
+    let mut block = String::from("[OPTIONS]\n");
+    for (i, label) in labels.enumerate() {
+        block.push_str(&format!("{}. {label}\n", i + 1));
+    }
+    block.push_str("[/OPTIONS]");
+    block
+}
+
+/// Format a question from the model's `ask_user` tool. Suggested answers
+/// become an `[OPTIONS]` block; the user may still reply freely.
+pub fn format_ask_user_request(question: &str, options: &[String], locale: OmnaraLocale) -> String {
+    let mut msg = format!("**{}**\n\n{question}", strings(locale).question_from_agent);
+    if !options.is_empty() {
+        msg.push_str("\n\n");
+        msg.push_str(&labels_block(options.iter().map(String::as_str)));
+    }
+    msg
+}
+
+/// Format the note closing a prompt whose turn was interrupted before it was
+/// answered; `kind` is the approval kind's label.
+pub fn format_withdrawn_approval_note(kind: &str, locale: OmnaraLocale) -> String {
+    format!(
+        "⚠️ {}",
+        fill(strings(locale).approval_withdrawn, &[("kind", &kind)])
+    )
+}
+
+/// The answer handed back to the model for an `ask_user` reply: an option
+/// number ("2", "2.") selects that option's text, anything else is passed
+/// through verbatim.
+pub fn resolve_ask_user_reply(reply: &str, options: &[String]) -> String {
+    normalize_reply(reply)
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| options.get(i))
+        .cloned()
+        .unwrap_or_else(|| reply.trim().to_string())
+}
+
+/// Replies understood without configuration, already normalized.
+const BUILTIN_SYNONYMS: &[(&str, ApprovalOption)] = &[
+    ("yes", ApprovalOption::Yes),
+    ("y", ApprovalOption::Yes),
+    ("ok", ApprovalOption::Yes),
+    ("okay", ApprovalOption::Yes),
+    ("approve", ApprovalOption::Yes),
+    ("approved", ApprovalOption::Yes),
+    ("✅", ApprovalOption::Yes),
+    ("👍", ApprovalOption::Yes),
+    ("✔", ApprovalOption::Yes),
+    ("always", ApprovalOption::Always),
+    ("yes always", ApprovalOption::Always),
+    ("always approve", ApprovalOption::Always),
+    ("🔁", ApprovalOption::Always),
+    ("no", ApprovalOption::No),
+    ("n", ApprovalOption::No),
+    ("deny", ApprovalOption::No),
+    ("denied", ApprovalOption::No),
+    ("reject", ApprovalOption::No),
+    ("no, provide feedback", ApprovalOption::No),
+    ("❌", ApprovalOption::No),
+    ("👎", ApprovalOption::No),
+];
+
+/// Lowercase, drop emoji variation selectors, and strip surrounding
+/// whitespace, quotes, and trailing punctuation ("Yes!" → "yes", "1." → "1").
+pub fn normalize_reply(reply: &str) -> String {
+    reply
+        .chars()
+        .filter(|c| *c != '\u{fe0f}')
+        .collect::<String>()
+        .to_lowercase()
+        .trim()
+        .trim_matches(['"', '\'', '`'])
+        .trim_end_matches(['.', '!', '?', ')'])
+        .trim()
+        .to_string()
+}
+
+/// Longest auto-approval window a "yes for <duration>" reply can open.
+pub const MAX_AUTO_APPROVE_WINDOW: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Note posted when a "yes for <duration>" reply opens an auto-approval
+/// window for `kind` requests.
+pub fn format_auto_approve_started(kind: &str, window: Duration, locale: OmnaraLocale) -> String {
+    let minutes = window.as_secs().div_ceil(60);
+    format!(
+        "⏱️ {}",
+        fill(
+            strings(locale).auto_approve_started,
+            &[("kind", &kind), ("minutes", &minutes)]
+        )
+    )
+}
+
+/// Note posted when an auto-approval window closes.
+pub fn format_auto_approve_ended(locale: OmnaraLocale) -> String {
+    format!("⏱️ {}", strings(locale).auto_approve_ended)
+}
+
+/// A parsed reply to a batch of exec approvals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchReply {
+    /// One decision per command, in prompt order.
+    pub decisions: Vec<ReviewDecision>,
+    /// Feedback for the model from a "No: <feedback>" covering the batch.
+    pub feedback: Option<String>,
+}
+
+/// A parsed reply to an approval prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalReply {
+    pub decision: ReviewDecision,
+    /// Why the request was rejected, from a `No: <feedback>` reply. The
+    /// frontend forwards it to the model as user input right after the denial.
+    pub feedback: Option<String>,
+}
+
+/// Maps dashboard replies to approval decisions: option numbers, the built-in
+/// phrases and reactions, the options and words of the configured locale,
+/// and any synonyms configured under `[omnara.approval_synonyms]`.
+#[derive(Debug, Clone)]
+pub struct ApprovalParser {
+    synonyms: HashMap<String, ApprovalOption>,
+    /// Words introducing the files left out of a partial patch approval.
+    except_words: Vec<String>,
+    /// Words between a decision and the commands it covers in a batch reply.
+    batch_to_words: Vec<String>,
+    /// Words covering the commands a batch reply has not decided yet.
+    batch_rest_words: Vec<String>,
+    /// Words between "yes" and an auto-approval window's length.
+    window_words: Vec<String>,
+}
+
+impl Default for ApprovalParser {
+    fn default() -> Self {
+        Self::new(&ApprovalSynonyms::default())
+    }
+}
+
+impl ApprovalParser {
+    pub fn new(configured: &ApprovalSynonyms) -> Self {
+        let mut synonyms: HashMap<String, ApprovalOption> = BUILTIN_SYNONYMS
+            .iter()
+            .map(|(reply, option)| ((*reply).to_string(), *option))
+            .collect();
+        for (replies, option) in [
+            (&configured.yes, ApprovalOption::Yes),
+            (&configured.always, ApprovalOption::Always),
+            (&configured.no, ApprovalOption::No),
+        ] {
+            for reply in replies {
+                synonyms.insert(normalize_reply(reply), option);
+            }
+        }
+        let text = strings(OmnaraLocale::default());
+        Self {
+            synonyms,
+            except_words: words(text.except_words),
+            batch_to_words: words(text.batch_to_words),
+            batch_rest_words: words(text.batch_rest_words),
+            window_words: words(text.window_words),
+        }
+    }
+
+    /// Also accept `locale`'s option labels and words.
+    pub fn with_locale(mut self, locale: OmnaraLocale) -> Self {
+        let text = strings(locale);
+        for (option, words) in [
+            (ApprovalOption::Yes, text.yes_words),
+            (ApprovalOption::Always, text.always_words),
+            (ApprovalOption::No, text.no_words),
+        ] {
+            let label = normalize_reply(option.label(locale));
+            for reply in words
+                .iter()
+                .map(|word| normalize_reply(word))
+                .chain([label])
+            {
+                // Built-in and configured replies keep their meaning.
+                self.synonyms.entry(reply).or_insert(option);
+            }
+        }
+        for (known, added) in [
+            (&mut self.except_words, text.except_words),
+            (&mut self.batch_to_words, text.batch_to_words),
+            (&mut self.batch_rest_words, text.batch_rest_words),
+            (&mut self.window_words, text.window_words),
+        ] {
+            for word in words(added) {
+                if !known.contains(&word) {
+                    known.push(word);
+                }
+            }
+        }
+        self
+    }
+
+    /// Map a reply to a prompt offering `options` onto a decision. Returns
+    /// None for replies that do not match any option.
+    ///
+    /// A bare "No" (or the "No, provide feedback" option) aborts the turn and
+    /// waits for the next message. "No: <feedback>" (or "No, <feedback>")
+    /// denies the request and lets the model continue with the feedback.
+    pub fn parse(&self, message: &str, options: &[ApprovalOption]) -> Option<ApprovalReply> {
+        if let Some(option) = self.option(&normalize_reply(message), options) {
+            return Some(ApprovalReply {
+                decision: match option {
+                    ApprovalOption::Yes => ReviewDecision::Approved,
+                    ApprovalOption::Always => ReviewDecision::ApprovedForSession,
+                    ApprovalOption::No => ReviewDecision::Abort,
+                },
+                feedback: None,
+            });
+        }
+        let (head, feedback) = message.trim().split_once([':', ','])?;
+        let feedback = feedback.trim();
+        (self.option(&normalize_reply(head), options) == Some(ApprovalOption::No)
+            && !feedback.is_empty())
+        .then(|| ApprovalReply {
+            decision: ReviewDecision::Denied,
+            feedback: Some(feedback.to_string()),
+        })
+    }
+
+    /// Parse a partial approval of a patch touching `files` (as listed by
+    /// [`patch_files`]), e.g. "yes except 2" or "yes except tests/*, *.md".
+    /// Returns the files to leave out, or None when the reply is not a
+    /// partial approval or names something that matches none of the files.
+    ///
+    /// Files are referred to by their number in the prompt or by a glob
+    /// matched against the end of the path, so `tests/*` matches
+    /// `/repo/tests/a.rs` and `lib.rs` matches `/repo/src/lib.rs`.
+    pub fn parse_partial_patch(&self, message: &str, files: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        let tokens: Vec<&str> = message.split_whitespace().collect();
+        let lowered: Vec<String> = tokens.iter().map(|t| t.to_lowercase()).collect();
+        let (at, len) = self.except_words.iter().find_map(|word| {
+            let word: Vec<&str> = word.split_whitespace().collect();
+            (1..=lowered.len().saturating_sub(word.len()))
+                .find(|&at| lowered[at..at + word.len()] == word[..])
+                .map(|at| (at, word.len()))
+        })?;
+        let head = tokens[..at].join(" ");
+        let head = normalize_reply(head.trim_end_matches(','));
+        if self.option(&head, PATCH_APPROVAL_OPTIONS) != Some(ApprovalOption::Yes) {
+            return None;
+        }
+
+        let items = tokens[at + len..].join(" ");
+        let items: Vec<&str> = items
+            .split([',', ' '])
+            .map(|item| item.trim_end_matches(['.', '!']))
+            .filter(|item| !item.is_empty())
+            .collect();
+        if items.is_empty() {
+            return None;
+        }
+        let mut excluded = Vec::new();
+        for item in items {
+            let matched: Vec<&PathBuf> = match item.parse::<usize>() {
+                Ok(n) => files.get(n.checked_sub(1)?).into_iter().collect(),
+                Err(_) => files
+                    .iter()
+                    .filter(|path| path_matches(item, path))
+                    .collect(),
+            };
+            if matched.is_empty() {
+                return None;
+            }
+            excluded.extend(matched.into_iter().cloned());
+        }
+        excluded.sort();
+        excluded.dedup();
+        Some(excluded)
+    }
+
+    /// Parse a reply to a prompt batching `count` exec approvals. A reply
+    /// [`Self::parse`] understands decides every command. Otherwise each
+    /// `;`-separated clause gives a decision and the commands it covers, by
+    /// number or range ("yes to 1, 3; no to 2", "always 1-2; no to the
+    /// rest"). "No" in a clause denies those commands without aborting the
+    /// turn, and commands the reply leaves out are denied as well.
+    ///
+    /// Returns None when a clause is not understood, or names a command
+    /// twice or one the batch does not have.
+    pub fn parse_batch(&self, message: &str, count: usize) -> Option<BatchReply> {
+        if let Some(ApprovalReply { decision, feedback }) =
+            self.parse(message, EXEC_APPROVAL_OPTIONS)
+        {
+            return Some(BatchReply {
+                decisions: vec![decision; count],
+                feedback,
+            });
+        }
+        let mut decisions: Vec<Option<ReviewDecision>> = vec![None; count];
+        for clause in message
+            .split([';', '\n'])
+            .filter(|clause| !clause.trim().is_empty())
+        {
+            let (head, items) = self.split_batch_clause(clause)?;
+            let decision = match self.option(&head, EXEC_APPROVAL_OPTIONS)? {
+                ApprovalOption::Yes => ReviewDecision::Approved,
+                ApprovalOption::Always => ReviewDecision::ApprovedForSession,
+                ApprovalOption::No => ReviewDecision::Denied,
+            };
+            let indices: Vec<usize> = if self.batch_rest_words.contains(&normalize_reply(&items)) {
+                (0..count).filter(|&i| decisions[i].is_none()).collect()
+            } else {
+                batch_indices(&items, count)?
+            };
+            for i in indices {
+                if decisions[i].replace(decision).is_some() {
+                    return None;
+                }
+            }
+        }
+        Some(BatchReply {
+            decisions: decisions
+                .into_iter()
+                .map(|decision| decision.unwrap_or(ReviewDecision::Denied))
+                .collect(),
+            feedback: None,
+        })
+    }
+
+    /// Parse a time-boxed approval ("yes for 30m", "yes for 2 hours",
+    /// "ja für 1h"): the request is approved, and so are requests like it
+    /// for the returned window, capped at [`MAX_AUTO_APPROVE_WINDOW`].
+    pub fn parse_window(&self, message: &str) -> Option<Duration> {
+        let normalized = normalize_reply(message);
+        let tokens: Vec<&str> = normalized.split_whitespace().collect();
+        let at = tokens
+            .iter()
+            .skip(1)
+            .position(|token| self.window_words.iter().any(|word| word == token))?
+            + 1;
+        if self.option(&tokens[..at].join(" "), EXEC_APPROVAL_OPTIONS) != Some(ApprovalOption::Yes)
+        {
+            return None;
+        }
+        let length = tokens[at + 1..].concat();
+        let (number, unit) = length.split_at(length.find(|c: char| !c.is_ascii_digit())?);
+        let count = number.parse::<u64>().ok().filter(|count| *count > 0)?;
+        // Minutes and hours in any of the locales: `m`, `min`, `minuten`,
+        // `h`, `hours`, `heures`, `horas`, `std`, `stunden`.
+        let unit_secs = if unit.starts_with('m') {
+            60
+        } else if unit.starts_with('h') || unit.starts_with("st") {
+            3600
+        } else {
+            return None;
+        };
+        Some(Duration::from_secs(count.saturating_mul(unit_secs)).min(MAX_AUTO_APPROVE_WINDOW))
+    }
+
+    /// A batch reply clause split into its normalized decision and the
+    /// commands it names: around a "to" word, or before the first number.
+    fn split_batch_clause(&self, clause: &str) -> Option<(String, String)> {
+        let tokens: Vec<&str> = clause.split_whitespace().collect();
+        let (at, skip) = match tokens
+            .iter()
+            .skip(1)
+            .position(|token| self.batch_to_words.contains(&token.to_lowercase()))
+        {
+            Some(i) => (i + 1, 1),
+            None => (
+                tokens
+                    .iter()
+                    .position(|token| token.starts_with(|c: char| c.is_ascii_digit()))?,
+                0,
+            ),
+        };
+        if at == 0 {
+            return None;
+        }
+        let head = tokens[..at].join(" ");
+        Some((
+            normalize_reply(head.trim_end_matches([',', ':'])),
+            tokens[at + skip..].join(" "),
+        ))
+    }
+
+    fn option(&self, normalized: &str, options: &[ApprovalOption]) -> Option<ApprovalOption> {
+        match normalized.parse::<usize>() {
+            Ok(n) => options.get(n.checked_sub(1)?).copied(),
+            Err(_) => self
+                .synonyms
+                .get(normalized)
+                .copied()
+                .filter(|option| options.contains(option)),
+        }
+    }
+}
+
+/// Zero-based indices of the commands `items` names by number ("1, 3")
+/// or range ("2-4"), out of `count`.
+fn batch_indices(items: &str, count: usize) -> Option<Vec<usize>> {
+    let mut indices = Vec::new();
+    for item in items
+        .split([',', ' '])
+        .map(|item| item.trim_end_matches(['.', '!']))
+        .filter(|item| !item.is_empty())
+    {
+        let (first, last) = match item.split_once('-') {
+            Some((first, last)) => (first.parse::<usize>().ok()?, last.parse::<usize>().ok()?),
+            None => {
+                let n = item.parse::<usize>().ok()?;
+                (n, n)
+            }
+        };
+        if first == 0 || first > last || last > count {
+            return None;
+        }
+        indices.extend(first - 1..last);
+    }
+    (!indices.is_empty()).then_some(indices)
+}
+
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_lowercase()).collect()
+}
+
+/// Whether glob `pattern` matches a trailing run of `path`'s components.
+/// A trailing '/' matches everything below a directory.
+fn path_matches(pattern: &str, path: &Path) -> bool {
+    let pattern = match pattern.strip_suffix('/') {
+        Some(dir) => format!("{dir}/*"),
+        None => pattern.to_string(),
+    };
+    let pattern = WildMatch::new(&pattern);
+    let components: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    (0..components.len()).any(|start| pattern.matches(&components[start..].join("/")))
+}
+
+/// User input telling the model which files of an approved patch were
+/// left out at the user's request.
+pub fn format_partial_approval_feedback(excluded: &[PathBuf]) -> String {
+    let files = excluded
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("I approved that patch except the changes to {files}, which were not applied.")
+}
+
+/// User input that carries approval feedback to the model.
+pub fn format_denial_feedback(feedback: &str) -> String {
+    format!("I rejected that request. Feedback: {feedback}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_display::ShellSyntax;
+    use pretty_assertions::assert_eq;
+
+    fn reply(decision: ReviewDecision, feedback: Option<&str>) -> Option<ApprovalReply> {
+        Some(ApprovalReply {
+            decision,
+            feedback: feedback.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn small_hunks_render_as_line_edits() {
+        let compact = PatchFormat {
+            compact_max_lines: 3,
+            ..PatchFormat::default()
+        };
+        let changes = HashMap::from([(
+            PathBuf::from("src/lib.rs"),
+            FileChange::Update {
+                unified_diff: "@@ -41,3 +41,3 @@\n fn main() {\n-    let retries = 3;\n+    let retries = 5;\n }\n\
+                               @@ -90,2 +90,3 @@\n mod tests;\n+\n+// `TODO` docs\n"
+                    .to_string(),
+                move_path: None,
+            },
+        )]);
+        let (details, added, removed) =
+            format_patch_details(&changes, &WithheldPaths::default(), compact);
+        assert_eq!(
+            details,
+            "**src/lib.rs**\n\
+             - line 42: `let retries = 3;` \u{2192} `let retries = 5;`\n\
+             - line 91: + (blank line)\n\
+             - line 92: + `` // `TODO` docs ``\n"
+        );
+        assert_eq!((added, removed), (3, 1));
+
+        // A hunk past the limit, or an uneven replacement, keeps the fenced diff.
+        for unified_diff in [
+            "@@ -1,4 +1,4 @@\n-a\n-b\n+c\n+d\n-e\n-f\n+g\n+h\n",
+            "@@ -1,1 +1,2 @@\n-a\n+b\n+c\n",
+        ] {
+            let changes = HashMap::from([(
+                PathBuf::from("src/lib.rs"),
+                FileChange::Update {
+                    unified_diff: unified_diff.to_string(),
+                    move_path: None,
+                },
+            )]);
+            let (details, _, _) =
+                format_patch_details(&changes, &WithheldPaths::default(), compact);
+            assert!(details.contains("```diff\n"), "{details}");
+        }
+        let (details, _, _) =
+            format_patch_details(&changes, &WithheldPaths::default(), PatchFormat::default());
+        assert!(details.contains("```diff\n"), "{details}");
+    }
+
+    #[test]
+    fn word_diffs_mark_only_the_changed_words() {
+        let changes = HashMap::from([(
+            PathBuf::from("src/lib.rs"),
+            FileChange::Update {
+                unified_diff: "@@ -1,3 +1,3 @@\n fn main() {\n-    let count = old_total(a < b);\n\
+                               +    let total = new_total(a < b);\n+    log(total);\n }\n"
+                    .to_string(),
+                move_path: None,
+            },
+        )]);
+        let details = |word_diff| {
+            let format = PatchFormat {
+                compact_max_lines: 0,
+                word_diff,
+            };
+            format_patch_details(&changes, &WithheldPaths::default(), format).0
+        };
+        assert_eq!(
+            details(OmnaraWordDiff::Markers),
+            "**src/lib.rs**\n```\n@@ -1,3 +1,3 @@\nfn main() {\n\
+             \x20   let [-count-]{+total+} = [-old_total(a-]{+new_total(a+} < b);\n\
+             {+    log(total);+}\n}\n```\n"
+        );
+        assert_eq!(
+            details(OmnaraWordDiff::Html),
+            "**src/lib.rs**\n<pre>\n@@ -1,3 +1,3 @@\nfn main() {\n\
+             \x20   let <del>count</del><ins>total</ins> = \
+             <del>old_total(a</del><ins>new_total(a</ins> &lt; b);\n\
+             <ins>    log(total);</ins>\n}\n</pre>\n"
+        );
+    }
+
+    #[test]
+    fn normalization_table() {
+        for (raw, normalized) in [
+            (" Yes ", "yes"),
+            ("YES!", "yes"),
+            ("\"always\"", "always"),
+            ("1.", "1"),
+            ("2)", "2"),
+            ("✔\u{fe0f}", "✔"),
+            ("No, provide feedback.", "no, provide feedback"),
+        ] {
+            assert_eq!(normalize_reply(raw), normalized, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn options_map_to_decisions() {
+        let parser = ApprovalParser::default();
+        for (raw, decision) in [
+            ("Yes", ReviewDecision::Approved),
+            ("ok!", ReviewDecision::Approved),
+            ("✅", ReviewDecision::Approved),
+            ("👍", ReviewDecision::Approved),
+            ("Always.", ReviewDecision::ApprovedForSession),
+            ("No, provide feedback", ReviewDecision::Abort),
+            ("❌", ReviewDecision::Abort),
+            ("n", ReviewDecision::Abort),
+        ] {
+            assert_eq!(
+                parser.parse(raw, EXEC_APPROVAL_OPTIONS),
+                reply(decision, None),
+                "{raw:?}"
+            );
+        }
+        assert_eq!(parser.parse("nope", EXEC_APPROVAL_OPTIONS), None);
+    }
+
+    #[test]
+    fn option_numbers_follow_the_prompt() {
+        let parser = ApprovalParser::default();
+        assert_eq!(
+            parser.parse("2", EXEC_APPROVAL_OPTIONS),
+            reply(ReviewDecision::ApprovedForSession, None)
+        );
+        assert_eq!(
+            parser.parse("2.", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Abort, None)
+        );
+        assert_eq!(parser.parse("3", PATCH_APPROVAL_OPTIONS), None);
+        assert_eq!(parser.parse("0", PATCH_APPROVAL_OPTIONS), None);
+        assert!(
+            format_exec_approval_request(
+                &["ls".to_string()],
+                Path::new("/work"),
+                None,
+                None,
+                OmnaraLocale::En
+            )
+            .ends_with("[OPTIONS]\n1. Yes\n2. Always\n3. No, provide feedback\n[/OPTIONS]")
+        );
+    }
+
+    #[test]
+    fn exec_prompts_fence_commands_in_their_shell_language() {
+        let prompt = format_exec_approval_request(
+            &[
+                "pwsh.exe".to_string(),
+                "-NoProfile".to_string(),
+                "-Command".to_string(),
+                "Get-ChildItem C:\\Users".to_string(),
+            ],
+            Path::new("C:\\work"),
+            None,
+            None,
+            OmnaraLocale::En,
+        );
+        assert!(prompt.contains("```powershell\nGet-ChildItem C:\\Users\n```"));
+
+        let prompt = format_exec_approval_request(
+            &["bash".to_string(), "-lc".to_string(), "ls -la".to_string()],
+            Path::new("/work"),
+            None,
+            None,
+            OmnaraLocale::En,
+        );
+        assert!(prompt.contains("```bash\nls -la\n```"));
+
+        // Quoted arguments stay quoted, so the approver sees what runs.
+        let prompt = format_exec_approval_request(
+            &["echo".to_string(), "hello world".to_string()],
+            Path::new("/work"),
+            None,
+            None,
+            OmnaraLocale::En,
+        );
+        let fence = ShellSyntax::host().fence_language();
+        assert!(prompt.contains(&format!("```{fence}\necho 'hello world'\n```")));
+    }
+
+    #[test]
+    fn risky_commands_get_a_badge_below_the_headline() {
+        let command =
+            |script: &str| vec!["bash".to_string(), "-lc".to_string(), script.to_string()];
+        let prompt = format_exec_approval_request(
+            &command("sudo cp build/app /usr/local/bin/app"),
+            Path::new("/work"),
+            None,
+            None,
+            OmnaraLocale::En,
+        );
+        assert!(prompt.starts_with(
+            "**Execute command?**\n\n⚠️ **High risk:** uses sudo, writes outside workspace\n\n"
+        ));
+
+        let prompt = format_exec_approval_request(
+            &command("curl -O https://example.com/data.json"),
+            Path::new("/work"),
+            None,
+            None,
+            OmnaraLocale::De,
+        );
+        assert!(prompt.contains("🔶 **Mittleres Risiko:** Netzwerkzugriff"));
+
+        let prompt = format_exec_approval_request(
+            &command("cargo test"),
+            Path::new("/work"),
+            None,
+            None,
+            OmnaraLocale::En,
+        );
+        assert!(!prompt.contains("risk"));
+    }
+
+    #[test]
+    fn near_matches_show_what_differs_from_the_approved_command() {
+        let argv =
+            |line: &str| -> Vec<String> { line.split_whitespace().map(str::to_string).collect() };
+        let approved = argv("git push origin main");
+        let prompt = format_exec_approval_request(
+            &argv("git push --force origin main"),
+            Path::new("/work"),
+            None,
+            Some(&approved),
+            OmnaraLocale::En,
+        );
+        assert!(prompt.contains(
+            "\n\n≈ **Close to a command approved for this session:** \
+             `git push origin main` — adds `--force`\n\n"
+        ));
+        assert_eq!(
+            format_near_approved(&approved, &argv("git push upstream main"), OmnaraLocale::En),
+            Some(
+                "≈ **Close to a command approved for this session:** \
+                 `git push origin main` — adds `upstream`; drops `origin`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            format_near_approved(&approved, &approved, OmnaraLocale::En),
+            None
+        );
+    }
+
+    #[test]
+    fn escalation_prompts_offer_no_session_wide_approval() {
+        let parser = ApprovalParser::default();
+        assert_eq!(parser.parse("always", ESCALATION_APPROVAL_OPTIONS), None);
+        assert_eq!(
+            parser.parse("1", ESCALATION_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Approved, None)
+        );
+
+        let prompt = format_escalation_approval_request(
+            &["curl".to_string(), "https://example.com".to_string()],
+            Path::new("/work/repo"),
+            Some("Download the release manifest"),
+            PermissionEscalation::Requested,
+            OmnaraLocale::En,
+        );
+        assert!(prompt.starts_with("**⚠️ Run command outside the sandbox?**"));
+        assert!(prompt.contains("**Justification:** Download the release manifest"));
+        assert!(prompt.contains("**Working directory:** `/work/repo`"));
+        assert!(prompt.contains(&format!(
+            "```{}\ncurl https://example.com\n```",
+            ShellSyntax::host().fence_language()
+        )));
+        assert!(prompt.ends_with("[OPTIONS]\n1. Yes\n2. No, provide feedback\n[/OPTIONS]"));
+    }
+
+    #[test]
+    fn mcp_prompts_pretty_print_and_cut_long_arguments() {
+        let invocation = McpInvocation {
+            server: "github".to_string(),
+            tool: "create_issue".to_string(),
+            arguments: Some(serde_json::json!({"repo": "omnara/codex", "title": "Flaky test"})),
+        };
+        assert_eq!(
+            format_mcp_approval_request(&invocation, OmnaraLocale::En),
+            "**Call MCP tool?**\n\n**Server:** `github`\n**Tool:** `create_issue`\n\n\
+             ```json\n{\n  \"repo\": \"omnara/codex\",\n  \"title\": \"Flaky test\"\n}\n```\n\n\
+             [OPTIONS]\n1. Yes\n2. Always\n3. No, provide feedback\n[/OPTIONS]"
+        );
+
+        let long = McpInvocation {
+            arguments: Some(serde_json::json!({
+                "body": "x".repeat(500),
+                "labels": (0..100).collect::<Vec<_>>(),
+            })),
+            ..invocation
+        };
+        let prompt = format_mcp_approval_request(&long, OmnaraLocale::En);
+        assert!(prompt.contains(&format!("  \"body\": \"{}…\n", "x".repeat(189))));
+        assert!(prompt.contains("... (65 more lines)\n```"));
+    }
+
+    #[test]
+    fn ask_user_replies_resolve_option_numbers() {
+        let options = vec!["Postgres".to_string(), "SQLite".to_string()];
+        assert_eq!(
+            format_ask_user_request("Which database?", &options, OmnaraLocale::En),
+            "**Question from the agent**\n\nWhich database?\n\n\
+             [OPTIONS]\n1. Postgres\n2. SQLite\n[/OPTIONS]"
+        );
+        assert_eq!(resolve_ask_user_reply("2.", &options), "SQLite");
+        assert_eq!(resolve_ask_user_reply("3", &options), "3");
+        assert_eq!(
+            resolve_ask_user_reply(" Use MySQL instead ", &options),
+            "Use MySQL instead"
+        );
+    }
+
+    #[test]
+    fn preview_results_ask_for_the_decision_again() {
+        let check = CheckResult {
+            command: vec!["cargo".to_string(), "check".to_string()],
+            exit_code: Some(101),
+            timed_out: false,
+            duration: std::time::Duration::from_secs(12),
+            output: "error[E0425]: cannot find value `x`".to_string(),
+        };
+        assert_eq!(
+            format_patch_preview_result(&Ok(Some(check)), OmnaraLocale::En),
+            "**🔬 Preview: `cargo check` failed with exit code 101 (12s)**\n\n\
+             ```text\nerror[E0425]: cannot find value `x`\n```\n\n\
+             **Apply changes?**\n\n[OPTIONS]\n1. Yes\n2. No, provide feedback\n[/OPTIONS]"
+        );
+        assert!(
+            format_patch_preview_result(&Ok(None), OmnaraLocale::En)
+                .starts_with("**🔬 Preview: the patch applies cleanly**\n\n**Apply changes?**")
+        );
+        assert!(
+            format_patch_approval_request(1, 1, 0, None, None, None, OmnaraLocale::En)
+                .contains("Reply `/preview`")
+        );
+    }
+
+    #[test]
+    fn localized_prompts_offer_and_accept_localized_options() {
+        let prompt = format_patch_approval_request(
+            2,
+            5,
+            1,
+            None,
+            Some(Path::new("/work")),
+            None,
+            OmnaraLocale::De,
+        );
+        assert!(prompt.starts_with("**Vorgeschlagener Patch für 2 Dateien (+5 -1)**"));
+        assert!(prompt.contains("Schreibzugriff auf /work"));
+        assert!(prompt.ends_with(
+            "**Änderungen anwenden?**\n\n[OPTIONS]\n1. Ja\n2. Nein, mit Feedback\n[/OPTIONS]"
+        ));
+
+        let parser = ApprovalParser::default().with_locale(OmnaraLocale::De);
+        assert_eq!(
+            parser.parse("Ja!", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Approved, None)
+        );
+        assert_eq!(
+            parser.parse("Immer", EXEC_APPROVAL_OPTIONS),
+            reply(ReviewDecision::ApprovedForSession, None)
+        );
+        assert_eq!(
+            parser.parse("Nein, mit Feedback", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Abort, None)
+        );
+        assert_eq!(
+            parser.parse("Nein: erst die Tests", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Denied, Some("erst die Tests"))
+        );
+        // English replies keep working.
+        assert_eq!(
+            parser.parse("yes", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Approved, None)
+        );
+        // Spanish "no" stays a plain denial.
+        let parser = ApprovalParser::default().with_locale(OmnaraLocale::Es);
+        assert_eq!(
+            parser.parse("Sí", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Approved, None)
+        );
+        assert_eq!(
+            parser.parse("no, con comentarios", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Abort, None)
+        );
+    }
+
+    #[test]
+    fn configured_synonyms_extend_the_table() {
+        let parser = ApprovalParser::new(&ApprovalSynonyms {
+            yes: vec!["Ja".to_string(), "sí".to_string()],
+            always: vec!["Immer".to_string()],
+            no: vec!["Nein".to_string()],
+        });
+        assert_eq!(
+            parser.parse("ja!", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Approved, None)
+        );
+        assert_eq!(
+            parser.parse("Sí", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Approved, None)
+        );
+        assert_eq!(
+            parser.parse("immer", EXEC_APPROVAL_OPTIONS),
+            reply(ReviewDecision::ApprovedForSession, None)
+        );
+        assert_eq!(
+            parser.parse("Nein: bitte nicht", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Denied, Some("bitte nicht"))
+        );
+    }
+
+    #[test]
+    fn inline_feedback_denies_and_keeps_original_casing() {
+        let parser = ApprovalParser::default();
+        assert_eq!(
+            parser.parse("No: use `cargo nextest` instead", EXEC_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Denied, Some("use `cargo nextest` instead"))
+        );
+        assert_eq!(
+            parser.parse("no, Don't touch Cargo.lock", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Denied, Some("Don't touch Cargo.lock"))
+        );
+        assert_eq!(
+            parser.parse("❌: too risky", PATCH_APPROVAL_OPTIONS),
+            reply(ReviewDecision::Denied, Some("too risky"))
+        );
+        assert_eq!(parser.parse("no:   ", EXEC_APPROVAL_OPTIONS), None);
+        assert_eq!(parser.parse("yes: but", EXEC_APPROVAL_OPTIONS), None);
+    }
+
+    #[test]
+    fn partial_patch_replies_name_the_files_to_leave_out() {
+        let files = vec![
+            PathBuf::from("/repo/src/lib.rs"),
+            PathBuf::from("/repo/tests/a.rs"),
+            PathBuf::from("/repo/tests/b.rs"),
+        ];
+        let parser = ApprovalParser::default().with_locale(OmnaraLocale::De);
+        assert_eq!(
+            parser.parse_partial_patch("Yes except tests/*", &files),
+            Some(files[1..].to_vec())
+        );
+        assert_eq!(
+            parser.parse_partial_patch("1, but not 3, lib.rs", &files),
+            Some(vec![files[0].clone(), files[2].clone()])
+        );
+        assert_eq!(
+            parser.parse_partial_patch("ja außer tests/", &files),
+            Some(files[1..].to_vec())
+        );
+        // Every item must match a file, and only "yes" can be narrowed.
+        assert_eq!(
+            parser.parse_partial_patch("yes except docs/*", &files),
+            None
+        );
+        assert_eq!(parser.parse_partial_patch("yes except 4", &files), None);
+        assert_eq!(parser.parse_partial_patch("no except 2", &files), None);
+        assert_eq!(parser.parse_partial_patch("yes", &files), None);
+    }
+
+    #[test]
+    fn batch_replies_decide_each_command() {
+        use ReviewDecision::*;
+        let parser = ApprovalParser::default().with_locale(OmnaraLocale::De);
+        let decisions = |reply: &str| parser.parse_batch(reply, 4).map(|reply| reply.decisions);
+        assert_eq!(
+            decisions("yes to 1,3; no to 2"),
+            Some(vec![Approved, Denied, Approved, Denied])
+        );
+        assert_eq!(
+            decisions("always 1-2\nno to the rest"),
+            Some(vec![ApprovedForSession, ApprovedForSession, Denied, Denied])
+        );
+        assert_eq!(
+            decisions("ja für 4; nein für alle"),
+            Some(vec![Denied, Denied, Denied, Approved])
+        );
+        assert_eq!(decisions("Yes"), Some(vec![Approved; 4]));
+        assert_eq!(
+            parser.parse_batch("no: wrong directory", 2),
+            Some(BatchReply {
+                decisions: vec![Denied; 2],
+                feedback: Some("wrong directory".to_string()),
+            })
+        );
+        // Unknown commands, repeats, and unclear decisions are not guessed.
+        assert_eq!(decisions("yes to 5"), None);
+        assert_eq!(decisions("yes to 1; no to 1"), None);
+        assert_eq!(decisions("maybe to 2"), None);
+        assert_eq!(decisions("run the first one"), None);
+    }
+
+    #[test]
+    fn timed_replies_open_an_auto_approval_window() {
+        let parser = ApprovalParser::default().with_locale(OmnaraLocale::De);
+        let minutes = |m: u64| Some(Duration::from_secs(m * 60));
+        assert_eq!(parser.parse_window("yes for 30m"), minutes(30));
+        assert_eq!(parser.parse_window("Yes for 2 hours."), minutes(120));
+        assert_eq!(parser.parse_window("ja für 45 Minuten"), minutes(45));
+        assert_eq!(parser.parse_window("ja für 2 Stunden"), minutes(120));
+        assert_eq!(
+            parser.parse_window("yes for 3 days"),
+            None,
+            "unknown units are not guessed"
+        );
+        assert_eq!(
+            parser.parse_window("yes for 12h"),
+            Some(MAX_AUTO_APPROVE_WINDOW)
+        );
+        assert_eq!(parser.parse_window("no for 30m"), None);
+        assert_eq!(parser.parse_window("always for 30m"), None);
+        assert_eq!(parser.parse_window("yes for 0m"), None);
+        assert_eq!(parser.parse_window("yes for 1, 3"), None);
+        assert_eq!(parser.parse_window("yes"), None);
+        assert_eq!(
+            format_auto_approve_started("exec", Duration::from_secs(90), OmnaraLocale::En),
+            "⏱️ Auto-approving exec requests of this risk level or lower for the next 2 min."
+        );
+    }
+
+    #[test]
+    fn batch_prompts_number_each_command() {
+        let request = |script: &str, reason: Option<&str>| ExecRequest {
+            command: vec!["bash".to_string(), "-lc".to_string(), script.to_string()],
+            cwd: PathBuf::from("/work"),
+            reason: reason.map(str::to_string),
+            closest_approved: None,
+        };
+        let prompt = format_exec_batch_approval_request(
+            &[
+                request("cargo test", Some("Run the tests")),
+                request("npm install", None),
+            ],
+            OmnaraLocale::En,
+        );
+        assert_eq!(
+            prompt,
+            "**Execute 2 commands?**\n\n\
+             **1.** Run the tests\n```bash\ncargo test\n```\n\n\
+             **2.**\n🔶 **Medium risk:** installs packages\n```bash\nnpm install\n```\n\n\
+             Reply for all of them, or per command, e.g. `yes to 1, 3; no to 2`.\n\n\
+             [OPTIONS]\n1. Yes\n2. Always\n3. No, provide feedback\n[/OPTIONS]"
+        );
+    }
+
+    #[test]
+    fn reactions_answer_like_replies_except_ask_again() {
+        assert_eq!(
+            ApprovalReaction::from_emoji("👍🏽"),
+            Some(ApprovalReaction::Approve)
+        );
+        assert_eq!(
+            ApprovalReaction::from_emoji("👎"),
+            Some(ApprovalReaction::Deny)
+        );
+        assert_eq!(
+            ApprovalReaction::from_emoji("🔁\u{fe0f}"),
+            Some(ApprovalReaction::AskAgain)
+        );
+        assert_eq!(ApprovalReaction::from_emoji("🎉"), None);
+        let parser = ApprovalParser::default();
+        assert_eq!(
+            ApprovalReaction::Deny
+                .reply()
+                .and_then(|reply| parser.parse(reply, EXEC_APPROVAL_OPTIONS)),
+            reply(ReviewDecision::Abort, None)
+        );
+        assert_eq!(ApprovalReaction::AskAgain.reply(), None);
+
+        let request = ExecRequest {
+            command: vec![
+                "git".to_string(),
+                "commit".to_string(),
+                "-m".to_string(),
+                "fix `a`".to_string(),
+            ],
+            cwd: PathBuf::from("/work"),
+            reason: None,
+            closest_approved: None,
+        };
+        let prompt = "**Execute command?**\n\n[OPTIONS]\n1. Yes\n[/OPTIONS]";
+        assert_eq!(
+            format_prompt_with_details(
+                prompt,
+                Some(&request),
+                None,
+                &WithheldPaths::default(),
+                OmnaraLocale::En
+            ),
+            "**Execute command?**\n\n**🔍 More detail**\n\n**Working directory:** `/work`\n\n\
+             **Arguments:**\n- `git`\n- `commit`\n- `-m`\n- `` fix `a` ``\n\n\
+             [OPTIONS]\n1. Yes\n[/OPTIONS]"
+        );
+        assert_eq!(
+            format_prompt_with_details(
+                prompt,
+                None,
+                None,
+                &WithheldPaths::default(),
+                OmnaraLocale::En
+            ),
+            prompt
+        );
+    }
+
+    #[test]
+    fn hunks_are_numbered_across_files_without_withheld_ones() {
+        let changes = HashMap::from([
+            (
+                PathBuf::from("/repo/a.rs"),
+                FileChange::Update {
+                    unified_diff: "@@ -1 +1 @@\n-a\n+b\n@@ -7 +7 @@\n-c\n+d\n".to_string(),
+                    move_path: None,
+                },
+            ),
+            (
+                PathBuf::from("/repo/b.pem"),
+                FileChange::Add {
+                    content: "secret\n".to_string(),
+                },
+            ),
+            (
+                PathBuf::from("/repo/c.rs"),
+                FileChange::Add {
+                    content: "new\n".to_string(),
+                },
+            ),
+        ]);
+        let withheld = WithheldPaths::new(&["*.pem".to_string()]);
+        let (details, _, _) = format_patch_approval_details(
+            &changes,
+            &withheld,
+            PatchFormat::default(),
+            OmnaraLocale::En,
+        );
+        assert!(details.ends_with(
+            "**Hunks**\n\
+             - `#1` /repo/a.rs `@@ -1 +1 @@` ±2\n\
+             - `#2` /repo/a.rs `@@ -7 +7 @@` ±2\n\
+             - `#3` /repo/c.rs `@@ -0,0 +1,1 @@` ±1\n\n\
+             Reply `show N` to see hunk N in full before deciding.\n"
+        ));
+        assert_eq!(
+            format_hunk_expansion(&changes, &withheld, 3, OmnaraLocale::En),
+            format!(
+                "**🔎 Hunk 3 of 3: /repo/c.rs**\n\n```diff\n@@ -0,0 +1,1 @@\n+new\n```{}",
+                apply_changes_footer(OmnaraLocale::En)
+            )
+        );
+        assert!(
+            format_hunk_expansion(&changes, &withheld, 4, OmnaraLocale::En)
+                .starts_with("⚠️ There is no hunk 4; this patch has 3.")
+        );
+        assert_eq!(parse_show_hunk("/show #2"), Some(2));
+        assert_eq!(parse_show_hunk("show me"), None);
+        assert_eq!(parse_show_hunk("show 2 please"), None);
+    }
+}
@@ -0,0 +1,172 @@
+//! API-version handshake with the Omnara server.
+//!
+//! At session start the client asks the server which API version it speaks
+//! (`GET /api/v1/version`). Servers that predate the handshake answer 404 and
+//! are treated as the legacy API: plain-text messages only, so session
+//! metadata, title updates, attachment uploads, gzip bodies, and in-place
+//! message updates are skipped instead of failing against an endpoint or
+//! field the server does not know.
+//! Until the handshake completes, or when it fails for another reason, every
+//! feature is assumed to be available.
+
+use serde::Deserialize;
+
+/// Optional server features the client adapts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Structured fields beyond message text: session metadata with the
+    /// first agent message and session title updates.
+    StructuredMessages,
+    /// Artifact uploads (`POST /api/v1/attachments`).
+    Attachments,
+    /// Gzip-encoded request bodies.
+    GzipRequests,
+    /// Replacing a posted agent message's content
+    /// (`PATCH /api/v1/messages/{id}`), used for live progress notes.
+    MessageUpdates,
+    /// Session share links for viewers (`POST /api/v1/sessions/{id}/share`).
+    ShareLinks,
+    /// A session's message history (`GET /api/v1/sessions/{id}/messages`),
+    /// replayed into a resumed terminal.
+    MessageHistory,
+    /// Changing a session's `tags` after it started
+    /// (`PATCH /api/v1/sessions/{id}`), for `/tag`.
+    SessionTags,
+    /// Read receipts both ways: confirming delivered remote messages
+    /// (`POST /api/v1/messages/{id}/read`) and `read_message_ids` on polls.
+    ReadReceipts,
+}
+
+impl Capability {
+    fn wire_name(self) -> &'static str {
+        match self {
+            Capability::StructuredMessages => "structured_messages",
+            Capability::Attachments => "attachments",
+            Capability::GzipRequests => "gzip_requests",
+            Capability::MessageUpdates => "message_updates",
+            Capability::ShareLinks => "share_links",
+            Capability::MessageHistory => "message_history",
+            Capability::SessionTags => "session_tags",
+            Capability::ReadReceipts => "read_receipts",
+        }
+    }
+}
+
+/// What the server supports, as negotiated by
+/// [`OmnaraClient::negotiate`](crate::omnara_client::OmnaraClient::negotiate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    /// Version reported by the server; `None` for the legacy API.
+    pub api_version: Option<String>,
+    pub structured_messages: bool,
+    pub attachments: bool,
+    pub gzip_requests: bool,
+    pub message_updates: bool,
+    pub share_links: bool,
+    pub message_history: bool,
+    pub session_tags: bool,
+    pub read_receipts: bool,
+}
+
+impl ServerCapabilities {
+    /// Everything this client can use; assumed until negotiated.
+    pub fn full() -> Self {
+        Self {
+            api_version: None,
+            structured_messages: true,
+            attachments: true,
+            gzip_requests: true,
+            message_updates: true,
+            share_links: true,
+            message_history: true,
+            session_tags: true,
+            read_receipts: true,
+        }
+    }
+
+    /// A server without the version endpoint.
+    pub fn legacy() -> Self {
+        Self {
+            api_version: None,
+            structured_messages: false,
+            attachments: false,
+            gzip_requests: false,
+            message_updates: false,
+            share_links: false,
+            message_history: false,
+            session_tags: false,
+            read_receipts: false,
+        }
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::StructuredMessages => self.structured_messages,
+            Capability::Attachments => self.attachments,
+            Capability::GzipRequests => self.gzip_requests,
+            Capability::MessageUpdates => self.message_updates,
+            Capability::ShareLinks => self.share_links,
+            Capability::MessageHistory => self.message_history,
+            Capability::SessionTags => self.session_tags,
+            Capability::ReadReceipts => self.read_receipts,
+        }
+    }
+
+    /// Capabilities advertised by a version response. Names this client does
+    /// not know are ignored.
+    pub(crate) fn from_response(response: VersionResponse) -> Self {
+        let has = |capability: Capability| {
+            response
+                .capabilities
+                .iter()
+                .any(|name| name == capability.wire_name())
+        };
+        Self {
+            structured_messages: has(Capability::StructuredMessages),
+            attachments: has(Capability::Attachments),
+            gzip_requests: has(Capability::GzipRequests),
+            message_updates: has(Capability::MessageUpdates),
+            share_links: has(Capability::ShareLinks),
+            message_history: has(Capability::MessageHistory),
+            session_tags: has(Capability::SessionTags),
+            read_receipts: has(Capability::ReadReceipts),
+            api_version: Some(response.api_version),
+        }
+    }
+}
+
+/// Body of `GET /api/v1/version`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct VersionResponse {
+    pub api_version: String,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unknown_capabilities_are_ignored() {
+        let response: VersionResponse = serde_json::from_str(
+            r#"{"api_version": "1.2", "capabilities": ["attachments", "voice_notes"]}"#,
+        )
+        .expect("parse");
+        assert_eq!(
+            ServerCapabilities::from_response(response),
+            ServerCapabilities {
+                api_version: Some("1.2".to_string()),
+                structured_messages: false,
+                attachments: true,
+                gzip_requests: false,
+                message_updates: false,
+                share_links: false,
+                message_history: false,
+                session_tags: false,
+                read_receipts: false,
+            }
+        );
+    }
+}
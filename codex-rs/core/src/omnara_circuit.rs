@@ -0,0 +1,144 @@
+//! Circuit breaker for the Omnara API.
+//!
+//! Every request the client sends counts toward a [`CircuitBreaker`]: a
+//! network error or a 5xx answer is a failure, any other answer a success.
+//! After [`CircuitPolicy::failure_threshold`] failures in a row the circuit
+//! opens, and for [`CircuitPolicy::open_for`] requests fail right away with
+//! [`CodexErr::OmnaraUnavailable`](crate::error::CodexErr::OmnaraUnavailable)
+//! instead of reaching the server; agent messages are queued instead (see
+//! [`OmnaraClient::send_queued_messages`](crate::omnara_client::OmnaraClient::send_queued_messages)).
+//! Once that time has passed requests go out again, and the first answer
+//! closes the circuit or keeps it open for another period.
+//! [`OmnaraClient::probe_circuit`](crate::omnara_client::OmnaraClient::probe_circuit)
+//! tests the server without waiting for other traffic.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::config_types::OmnaraCircuitBreaker;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitPolicy {
+    /// Failures in a row that open the circuit; `0` never opens it.
+    pub failure_threshold: u32,
+    pub open_for: Duration,
+}
+
+impl Default for CircuitPolicy {
+    fn default() -> Self {
+        Self::from(&OmnaraCircuitBreaker::default())
+    }
+}
+
+impl From<&OmnaraCircuitBreaker> for CircuitPolicy {
+    fn from(config: &OmnaraCircuitBreaker) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold,
+            open_for: Duration::from_secs(config.open_minutes * 60),
+        }
+    }
+}
+
+/// A change [`CircuitBreaker::record`] made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitChange {
+    Opened,
+    Closed,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    policy: CircuitPolicy,
+    failures: u32,
+    /// Set while the circuit is open: when requests may go out again.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(policy: CircuitPolicy) -> Self {
+        Self {
+            policy,
+            failures: 0,
+            open_until: None,
+        }
+    }
+
+    pub fn policy(&self) -> CircuitPolicy {
+        self.policy
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open_until.is_some()
+    }
+
+    /// Whether a request may be sent at `now`: always while the circuit is
+    /// closed, and again once its open period has passed.
+    pub fn allows(&self, now: Instant) -> bool {
+        self.open_until.is_none_or(|until| now >= until)
+    }
+
+    /// Count the outcome of a request answered at `now`. A failure while
+    /// open starts the open period over.
+    pub fn record(&mut self, ok: bool, now: Instant) -> Option<CircuitChange> {
+        if ok {
+            self.failures = 0;
+            return self.open_until.take().map(|_| CircuitChange::Closed);
+        }
+        if self.policy.failure_threshold == 0 {
+            return None;
+        }
+        self.failures = self.failures.saturating_add(1);
+        let opened = self.open_until.is_none() && self.failures >= self.policy.failure_threshold;
+        if opened || self.open_until.is_some() {
+            self.open_until = Some(now + self.policy.open_for);
+        }
+        opened.then_some(CircuitChange::Opened)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn opens_after_repeated_failures_and_closes_on_success() {
+        let policy = CircuitPolicy {
+            failure_threshold: 3,
+            open_for: Duration::from_secs(120),
+        };
+        let mut breaker = CircuitBreaker::new(policy);
+        let start = Instant::now();
+
+        assert_eq!(breaker.record(false, start), None);
+        assert_eq!(breaker.record(true, start), None);
+        assert_eq!(breaker.record(false, start), None);
+        assert_eq!(breaker.record(false, start), None);
+        assert!(breaker.allows(start));
+        assert_eq!(breaker.record(false, start), Some(CircuitChange::Opened));
+        assert!(breaker.is_open());
+        assert!(!breaker.allows(start + Duration::from_secs(119)));
+
+        // A failed attempt after the open period keeps it open for another.
+        let retry = start + Duration::from_secs(120);
+        assert!(breaker.allows(retry));
+        assert_eq!(breaker.record(false, retry), None);
+        assert!(!breaker.allows(retry + Duration::from_secs(60)));
+
+        assert_eq!(
+            breaker.record(true, retry + Duration::from_secs(240)),
+            Some(CircuitChange::Closed)
+        );
+        assert!(!breaker.is_open());
+
+        let mut disabled = CircuitBreaker::new(CircuitPolicy {
+            failure_threshold: 0,
+            ..policy
+        });
+        for _ in 0..10 {
+            assert_eq!(disabled.record(false, start), None);
+        }
+        assert!(disabled.allows(start));
+    }
+}
@@ -1,14 +1,95 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::io::Write as _;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use crate::config_types::Omnara as OmnaraConfig;
+use crate::config_types::OmnaraHttpConfig;
+use crate::config_types::OmnaraMarkdownDialect;
+use crate::config_types::OmnaraMessageKind;
+use crate::config_types::OmnaraTransformer;
+use crate::config_types::OmnaraUploads;
+use crate::config_types::OmnaraVoice;
+use crate::git_diff_tracker::DiffLimits;
+use crate::git_diff_tracker::DiffStatSummary;
 use crate::git_diff_tracker::GitDiffTracker;
+use crate::git_diff_tracker::MultiRepoDiffTracker;
+use crate::git_diff_tracker::SubmoduleDiffs;
+use crate::omnara_ack::AckStore;
+use crate::omnara_capabilities::Capability;
+use crate::omnara_capabilities::ServerCapabilities;
+use crate::omnara_capabilities::VersionResponse;
+use crate::omnara_circuit::CircuitBreaker;
+use crate::omnara_circuit::CircuitChange;
+use crate::omnara_circuit::CircuitPolicy;
+use crate::omnara_clock::Clock;
+use crate::omnara_clock::TokioClock;
+use crate::omnara_doctor::DoctorReport;
+use crate::omnara_doctor::LATENCY_SAMPLES;
+use crate::omnara_doctor::ServerProbe;
+use crate::omnara_doctor::SessionProbe;
+use crate::omnara_journal::Journal;
+use crate::omnara_journal::Recovery;
+use crate::omnara_keychain;
+use crate::omnara_markdown::convert_markdown;
+use crate::omnara_metrics::MessageKind;
+use crate::omnara_metrics::OmnaraMetrics;
+use crate::omnara_tags::TagCommand;
+use crate::omnara_tags::apply_tag_command;
+use crate::omnara_tags::normalize_tags;
+use crate::omnara_transform::TransformPipeline;
+use crate::omnara_uploads::UploadError;
+use crate::omnara_uploads::Uploads;
+use crate::omnara_uploads::format_saved_note;
+use crate::omnara_uploads::safe_filename;
+use crate::omnara_uploads::upload_input;
+use crate::omnara_voice::Transcriber;
+use crate::omnara_voice::TranscriptionError;
+use crate::omnara_voice::is_audio;
+use crate::omnara_voice::voice_input;
+use crate::omnara_withhold::WithheldPaths;
+use crate::omnara_workspace::SelectedWorkspace;
+use crate::omnara_workspace::select_workspace;
+use crate::protocol::FileChange;
+use crate::session_rollback::RollbackPlan;
+use crate::trace_context::TraceContext;
+use chrono::DateTime;
+use chrono::Utc;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use tracing::{debug, error, info, trace, warn};
 
+/// Request bodies smaller than this are sent as plain JSON; gzip would save
+/// little and cost a round of CPU per message.
+const MIN_COMPRESSED_BODY_BYTES: usize = 1024;
+
+/// The hosted Omnara API, used unless `OMNARA_API_URL` or `[omnara] api_url`
+/// points elsewhere.
+const DEFAULT_API_URL: &str = "https://agent-dashboard-mcp.onrender.com";
+
+/// Tries at requesting input on a message, which the server may not have
+/// persisted yet right after the send returned.
+const REQUEST_INPUT_ATTEMPTS: u32 = 5;
+/// Wait before the first retry of a request-input call; doubled each time.
+const REQUEST_INPUT_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Agent messages queued while the circuit is open; the oldest are dropped
+/// past this.
+const MAX_QUEUED_MESSAGES: usize = 200;
+
+/// Origin ids of local user messages remembered for echo filtering; the
+/// oldest are forgotten first.
+const MAX_ORIGIN_IDS: usize = 256;
+
 /// Omnara API client with minimal surface for Codex integration.
 ///
 /// Responsibilities:
@@ -22,9 +103,65 @@ pub struct OmnaraClient {
     api_key: String,
     session_id: uuid::Uuid,
     last_agent_message_id: Arc<Mutex<Option<String>>>,
+    /// Durable last-read id and delivered message ids, so a restart into the
+    /// same session neither re-reads old replies nor injects one twice.
+    acks: Arc<Mutex<AckStore>>,
+    /// Pending approvals and unfinished notes, for recovery after a crash.
+    journal: Arc<Mutex<Journal>>,
     poller: Arc<Mutex<PollerState>>, // single active poller
     wrapper_log: PathBuf,
-    git: Option<Arc<Mutex<GitDiffTracker>>>,
+    git: Option<Arc<Mutex<MultiRepoDiffTracker>>>,
+    /// Files whose content stays out of uploads, including the session diff.
+    withheld: WithheldPaths,
+    poll_policy: PollPolicy,
+    activity: Arc<Activity>,
+    in_flight: Arc<InFlight>,
+    metrics: Arc<OmnaraMetrics>,
+    session_metadata: Arc<Mutex<Option<SessionMetadata>>>,
+    /// Sequence number of the next message sent, shared with clones.
+    next_sequence: Arc<AtomicU64>,
+    titled: Arc<AtomicBool>,
+    /// The session's tags as last sent, starting with the metadata's.
+    tags: Arc<Mutex<Vec<String>>>,
+    /// Send a `traceparent` header with each request (`omnara.trace_context`).
+    trace_propagation: bool,
+    /// Trace of the running turn; requests become child spans of it.
+    turn_trace: Arc<Mutex<Option<TraceContext>>>,
+    /// Gzip large request bodies (`omnara.compress_requests`); cleared for
+    /// every clone once the server rejects a compressed body.
+    compress_requests: Arc<AtomicBool>,
+    /// Dialect agent messages are converted to before they are sent.
+    markdown_dialect: OmnaraMarkdownDialect,
+    /// `[[omnara.transformers]]`, run on every outbound message before the
+    /// dialect conversion.
+    transformers: Arc<TransformPipeline>,
+    /// Result of the version handshake, shared with clones and child
+    /// sessions; `None` (every feature assumed) until [`Self::negotiate`].
+    capabilities: Arc<Mutex<Option<ServerCapabilities>>>,
+    /// Pauses requests while the server keeps failing, shared with clones.
+    circuit: Arc<Mutex<CircuitBreaker>>,
+    /// Whether the circuit is open, for frontends to watch.
+    circuit_open: Arc<watch::Sender<bool>>,
+    /// Agent messages held while the circuit is open, oldest first.
+    outbox: Arc<Mutex<VecDeque<(String, Notifications)>>>,
+    /// `client_message_id`s of the local user messages this client sent,
+    /// so the poller can drop them when the server hands them back.
+    sent_origins: Arc<Mutex<VecDeque<String>>>,
+    /// Whether the dashboard has read the last agent message (the one
+    /// replies answer), for frontends to watch.
+    last_message_seen: Arc<watch::Sender<bool>>,
+    /// The latest reaction the dashboard left on the last agent message,
+    /// for frontends to watch.
+    last_reaction: Arc<watch::Sender<Option<MessageReaction>>>,
+    /// Turns voice notes into user input (`[omnara.voice]`); voice notes are
+    /// ignored without one.
+    transcriber: Option<Arc<Transcriber>>,
+    /// Saves files attached to dashboard messages (`[omnara.uploads]`);
+    /// they are ignored without one.
+    uploads: Option<Arc<Uploads>>,
+    /// Named workspace whose credentials the client uses
+    /// (`[omnara.workspaces]`); None for the default key.
+    workspace: Option<SelectedWorkspace>,
 }
 
 #[derive(Default)]
@@ -33,6 +170,127 @@ struct PollerState {
     handle: Option<JoinHandle<()>>,
 }
 
+/// Adaptive polling schedule: poll at `fast_interval` for `fast_window` after
+/// the last activity, then back off to `idle_interval`. After `suspend_after`
+/// without activity polling pauses until the next activity, and after
+/// `end_session_after` the Omnara session is ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollPolicy {
+    pub fast_interval: Duration,
+    pub idle_interval: Duration,
+    pub fast_window: Duration,
+    pub suspend_after: Option<Duration>,
+    pub end_session_after: Option<Duration>,
+}
+
+impl PollPolicy {
+    /// Whether a poller should suspend given the time since the last activity.
+    pub fn should_suspend(&self, since_activity: Duration) -> bool {
+        self.suspend_after
+            .is_some_and(|suspend_after| since_activity >= suspend_after)
+    }
+
+    /// Interval to wait before the next poll given the time since the last activity.
+    pub fn interval_after(&self, since_activity: Duration) -> Duration {
+        if since_activity < self.fast_window {
+            self.fast_interval
+        } else {
+            self.idle_interval.max(self.fast_interval)
+        }
+    }
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        Self::from(&OmnaraConfig::default())
+    }
+}
+
+impl From<&OmnaraConfig> for PollPolicy {
+    fn from(config: &OmnaraConfig) -> Self {
+        Self {
+            fast_interval: Duration::from_millis(config.poll_interval_ms),
+            idle_interval: Duration::from_millis(config.idle_poll_interval_ms),
+            fast_window: Duration::from_millis(config.fast_poll_window_ms),
+            suspend_after: (config.idle_suspend_minutes > 0)
+                .then(|| Duration::from_secs(config.idle_suspend_minutes * 60)),
+            end_session_after: (config.idle_end_session_hours > 0)
+                .then(|| Duration::from_secs(config.idle_end_session_hours * 60 * 60)),
+        }
+    }
+}
+
+/// Time of the last send/receive; wakes a backed-off poller on new activity.
+struct Activity {
+    clock: Arc<dyn Clock>,
+    last: Mutex<tokio::time::Instant>,
+    notify: Notify,
+}
+
+impl Activity {
+    fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            last: Mutex::new(clock.now()),
+            clock,
+            notify: Notify::new(),
+        }
+    }
+
+    fn touch(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = self.clock.now();
+        }
+        self.notify.notify_waiters();
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.last
+            .lock()
+            .map(|last| self.clock.elapsed(*last))
+            .unwrap_or_default()
+    }
+}
+
+/// Counts outbound requests that have started but not finished, so shutdown
+/// can wait for them to settle before ending the session.
+#[derive(Default)]
+struct InFlight {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+struct InFlightGuard(Arc<InFlight>);
+
+impl InFlight {
+    fn enter(self: &Arc<Self>) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+
+    fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    async fn wait_idle(&self) {
+        loop {
+            // Register before checking so a concurrent drop cannot be missed.
+            let notified = self.idle.notified();
+            if self.count() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct AgentMessageRequest<'a> {
     agent_instance_id: &'a str,
@@ -44,6 +302,10 @@ struct AgentMessageRequest<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     git_diff: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    session_metadata: Option<&'a SessionMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_metadata: Option<MessageMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     send_push: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     send_email: Option<bool>,
@@ -51,169 +313,1759 @@ struct AgentMessageRequest<'a> {
     send_sms: Option<bool>,
 }
 
+/// Where a message falls in the session: `sequence` counts the client's
+/// sends from zero, so the dashboard can order notes that arrive out of
+/// order, and `timestamp_ms` is when it was sent (Unix milliseconds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct MessageMetadata {
+    sequence: u64,
+    timestamp_ms: i64,
+}
+
+/// Notifications an agent message asks the server for.
+#[derive(Debug, Clone, Copy)]
+enum Notifications {
+    /// Whatever the server does by default.
+    Default,
+    /// Push on or off, e.g. per `[omnara.approval_push]`.
+    Push(bool),
+    /// Push, email, and SMS.
+    Urgent,
+}
+
+impl Notifications {
+    fn push(self) -> Option<bool> {
+        match self {
+            Notifications::Default => None,
+            Notifications::Push(push) => Some(push),
+            Notifications::Urgent => Some(true),
+        }
+    }
+
+    fn urgent(self) -> Option<bool> {
+        matches!(self, Notifications::Urgent).then_some(true)
+    }
+}
+
+/// Descriptive session details shown in the dashboard session list. Sent once,
+/// with the first agent message of the session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SessionMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_remote: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codex_version: Option<String>,
+    /// Session this one was opened from (e.g. a fork or another agent of the
+    /// same process); the dashboard links the two.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<String>,
+    /// Project the dashboard groups the session under.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// `owner/name` of the git remote.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl SessionMetadata {
+    /// Fill in the working directory name and git remote/branch for `cwd`,
+    /// and name the project after the repository (or the directory).
+    pub fn detect(cwd: &Path) -> Self {
+        let git = GitDiffTracker::new(false, Some(cwd.to_path_buf()));
+        let working_directory = cwd
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        let git_remote = git.remote_url();
+        let repo = git_remote.as_deref().and_then(repo_slug);
+        let project = repo
+            .as_deref()
+            .and_then(|repo| repo.rsplit('/').next())
+            .map(str::to_string)
+            .or_else(|| working_directory.clone());
+        Self {
+            working_directory,
+            git_remote,
+            git_branch: git.current_branch(),
+            project,
+            repo,
+            ..Default::default()
+        }
+    }
+
+    /// Apply `[omnara] project`, `environment`, and `tags`.
+    pub fn with_labels(self, config: &OmnaraConfig) -> Self {
+        Self {
+            project: config.project.clone().or(self.project),
+            environment: config.environment.clone().or(self.environment),
+            tags: normalize_tags(&config.tags),
+            ..self
+        }
+    }
+}
+
+/// `owner/name` from a git remote URL, e.g. `git@github.com:owner/name.git`
+/// or `https://github.com/owner/name`.
+fn repo_slug(remote: &str) -> Option<String> {
+    let path = remote.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let mut parts = path.rsplit(['/', ':']).filter(|part| !part.is_empty());
+    let name = parts.next()?;
+    Some(match parts.next() {
+        Some(owner) if !owner.contains('@') => format!("{owner}/{name}"),
+        _ => name.to_string(),
+    })
+}
+
 #[derive(Debug, Deserialize)]
 struct AgentMessageResponse {
     message_id: String,
 }
 
+/// Reference to an artifact uploaded via [`OmnaraClient::upload_attachment`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AttachmentRef {
+    pub attachment_id: String,
+    pub url: String,
+}
+
+/// What a viewer opening a share link may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharePermissions {
+    /// Follow the session without replying.
+    ReadOnly,
+    /// Follow the session and answer approval prompts, but send no other
+    /// messages.
+    ApproveOnly,
+}
+
+/// A dashboard link created by [`OmnaraClient::create_share_link`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ShareLink {
+    pub url: String,
+    /// RFC 3339 timestamp after which the link stops working, if the server
+    /// sets one.
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// Who sent a message in the session history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MessageSender {
+    User,
+    Agent,
+}
+
+/// A message of the session history, from [`OmnaraClient::message_history`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HistoryMessage {
+    pub id: String,
+    #[serde(rename = "sender_type")]
+    pub sender: MessageSender,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageHistoryResponse {
+    messages: Vec<HistoryMessage>,
+}
+
 #[derive(Debug, Deserialize)]
 struct PendingMessagesResponse {
     messages: Vec<PendingMessage>,
     status: String, // "ok" | "stale"
+    /// Agent messages the dashboard has read; sent by servers with
+    /// `read_receipts`.
+    #[serde(default)]
+    read_message_ids: Vec<String>,
+    /// Reactions left on agent messages since the last poll; sent by
+    /// servers that support reactions.
+    #[serde(default)]
+    reactions: Vec<PendingReaction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PendingReaction {
+    /// Absent on older servers; such reactions cannot be deduplicated.
+    #[serde(default)]
+    id: Option<String>,
+    message_id: String,
+    emoji: String,
+}
+
+/// A reaction the dashboard user left on an agent message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageReaction {
+    pub message_id: String,
+    pub emoji: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 struct PendingMessage {
+    /// Absent on older servers; such messages cannot be deduplicated.
+    #[serde(default)]
+    pub id: Option<String>,
     pub content: String,
+    /// The `client_message_id` the message was posted with, echoed by the
+    /// server; set on messages a client mirrored.
+    #[serde(default)]
+    pub client_message_id: Option<String>,
     #[allow(dead_code)]
     pub sender_type: Option<String>,
     #[allow(dead_code)]
     pub created_at: Option<String>,
     #[allow(dead_code)]
     pub requires_user_input: Option<bool>,
+    /// Files sent with the message, e.g. voice notes from the mobile app.
+    #[serde(default)]
+    pub attachments: Vec<PendingAttachment>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct PendingAttachment {
+    /// Absolute, or relative to the server.
+    url: String,
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    filename: Option<String>,
 }
 
 impl OmnaraClient {
+    /// Whether `from_env` would enable a client.
+    pub fn env_configured() -> bool {
+        std::env::var_os("OMNARA_API_KEY").is_some() || omnara_keychain::has_stored_api_key()
+    }
+
+    /// Whether `from_config` would find a key for a session in `cwd`: the
+    /// selected workspace's, or the default one.
+    pub fn configured(config: &OmnaraConfig, cwd: &Path) -> bool {
+        match select_workspace(&config.workspaces, cwd) {
+            Ok(Some(workspace)) => workspace.api_key().is_some(),
+            Ok(None) => Self::env_configured(),
+            Err(_) => false,
+        }
+    }
+
     /// Construct a client from env vars. Returns None when not configured.
+    /// Like [`Self::from_config`] with the default `[omnara]` section.
+    pub fn from_env() -> Option<Self> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        Self::from_config(&OmnaraConfig::default(), &cwd)
+    }
+
+    /// Construct a client for a session in `cwd` from env vars, falling back
+    /// to `config.api_url` for the server. Returns None when not configured.
+    /// When `config.workspaces` selects a workspace for `cwd` (see
+    /// [`select_workspace`]), its key and server are used instead of the
+    /// defaults; a missing key then disables Omnara rather than falling back.
     /// Env vars:
-    /// - OMNARA_API_KEY (required to enable)
-    /// - OMNARA_API_URL (optional; defaults to hosted URL)
+    /// - OMNARA_API_KEY (required to enable unless `codex omnara login`
+    ///   stored a key in the OS keychain)
+    /// - OMNARA_API_URL (optional; defaults to the workspace's or
+    ///   `config.api_url`, then the hosted URL)
     /// - OMNARA_SESSION_ID (optional; autogenerated if missing)
-    pub fn from_env() -> Option<Self> {
-        let api_key = match std::env::var("OMNARA_API_KEY") {
-            Ok(v) => v,
-            Err(_) => {
-                debug!("Omnara disabled: OMNARA_API_KEY not set");
+    pub fn from_config(config: &OmnaraConfig, cwd: &Path) -> Option<Self> {
+        let workspace = match select_workspace(&config.workspaces, cwd) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                warn!("Omnara disabled: {e}");
                 return None;
             }
         };
+        let api_key = match &workspace {
+            Some(workspace) => match workspace.api_key() {
+                Some(key) => key,
+                None => {
+                    warn!(
+                        "Omnara disabled: {} (API key of workspace `{}`) is not set",
+                        workspace.workspace.api_key_env, workspace.name
+                    );
+                    return None;
+                }
+            },
+            None => match std::env::var("OMNARA_API_KEY") {
+                Ok(v) => v,
+                Err(_) => match omnara_keychain::load_api_key() {
+                    Ok(Some(key)) => key,
+                    Ok(None) => {
+                        debug!("Omnara disabled: OMNARA_API_KEY not set");
+                        return None;
+                    }
+                    Err(e) => {
+                        warn!("Omnara disabled: cannot read the API key from the OS keychain: {e}");
+                        return None;
+                    }
+                },
+            },
+        };
         let base_url = std::env::var("OMNARA_API_URL")
             .ok()
-            .unwrap_or_else(|| "https://agent-dashboard-mcp.onrender.com".to_string());
+            .or_else(|| {
+                workspace
+                    .as_ref()
+                    .and_then(|workspace| workspace.workspace.api_url.clone())
+            })
+            .or_else(|| config.api_url.clone())
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
         let session_id = std::env::var("OMNARA_SESSION_ID")
             .ok()
             .and_then(|s| uuid::Uuid::parse_str(&s).ok())
             .unwrap_or_else(uuid::Uuid::new_v4);
 
-        info!(base_url = %base_url, session_id = %session_id, "Initializing OmnaraClient from env");
-        Some(Self::new(api_key, base_url, session_id))
+        info!(base_url = %base_url, session_id = %session_id, "Initializing OmnaraClient from env");
+        let mut client = Self::new(api_key, base_url, session_id);
+        if let Some(workspace) = workspace {
+            info!(workspace = %workspace.name, source = %workspace.source, "Omnara workspace selected");
+            client.append_log(&format!(
+                "Workspace: {} ({})\n\n",
+                workspace.name, workspace.source
+            ));
+            client.workspace = Some(workspace);
+        }
+        Some(client)
+    }
+
+    /// End the Omnara session (POST /api/v1/sessions/end).
+    pub async fn end_session(&self) -> crate::error::Result<()> {
+        #[derive(Serialize)]
+        struct EndReq<'a> {
+            agent_instance_id: &'a str,
+        }
+        info!(session_id = %self.session_id, "Omnara end_session: begin");
+        let req = EndReq {
+            agent_instance_id: &self.session_id.to_string(),
+        };
+        let url = self.url("/api/v1/sessions/end");
+        info!(url = %url, "Omnara end_session: POST");
+        let resp = self
+            .send_traced(self.auth(self.http.post(url)).json(&req), "end_session")
+            .await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara end_session: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara end_session: error");
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        info!("Omnara end_session: success");
+        Ok(())
+    }
+
+    /// Stop polling, wait for in-flight sends to finish, then end the session.
+    /// Every step is bounded by `deadline`; sends still running when it passes
+    /// are abandoned and the session end is attempted anyway.
+    pub async fn shutdown(&self, deadline: Instant) -> crate::error::Result<()> {
+        info!(session_id = %self.session_id, "Omnara shutdown: begin");
+        self.cancel_polling();
+        let deadline = tokio::time::Instant::from_std(deadline);
+        if tokio::time::timeout_at(deadline, self.in_flight.wait_idle())
+            .await
+            .is_err()
+        {
+            let pending = self.in_flight.count();
+            warn!(
+                pending,
+                "Omnara shutdown: deadline reached with sends in flight"
+            );
+            self.append_log(&format!(
+                "[Shutdown] deadline reached with {pending} send(s) in flight\n"
+            ));
+        }
+        if let Ok(mut journal) = self.journal.lock() {
+            journal.clear();
+        }
+        match tokio::time::timeout_at(deadline, self.end_session()).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("Omnara shutdown: deadline reached before session end");
+                self.append_log("[Shutdown] deadline reached before session end\n");
+                Err(crate::error::CodexErr::Timeout)
+            }
+        }
+    }
+    /// Rename the session in the dashboard (PATCH /api/v1/sessions/{id}).
+    pub async fn set_title(&self, title: &str) -> crate::error::Result<()> {
+        #[derive(Serialize)]
+        struct TitleReq<'a> {
+            title: &'a str,
+        }
+        debug!(title, "Omnara set_title: begin");
+        self.titled.store(true, Ordering::SeqCst);
+        if !self.supports(Capability::StructuredMessages) {
+            debug!("Omnara set_title: server does not support session titles; skipped");
+            return Ok(());
+        }
+        let _in_flight = self.in_flight.enter();
+        let url = self.url(&format!("/api/v1/sessions/{}", self.session_id));
+        info!(url = %url, "Omnara set_title: PATCH");
+        self.append_log(&format!(
+            "--- SETTING SESSION TITLE ---\nTitle: {title}\n\n"
+        ));
+        let resp = self
+            .send_traced(
+                self.auth(self.http.patch(url)).json(&TitleReq { title }),
+                "set_title",
+            )
+            .await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara set_title: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara set_title: error");
+            self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        info!("Omnara set_title: success");
+        Ok(())
+    }
+
+    /// The session's tags.
+    pub fn tags(&self) -> Vec<String> {
+        self.tags
+            .lock()
+            .map(|tags| tags.clone())
+            .unwrap_or_default()
+    }
+
+    /// Add or remove session tags (PATCH /api/v1/sessions/{id} with the
+    /// whole new `tags` list), or list them; returns the tags after the
+    /// command. Fails with `UnsupportedOperation` when the server cannot
+    /// change tags.
+    pub async fn apply_tag_command(
+        &self,
+        command: &TagCommand,
+    ) -> crate::error::Result<Vec<String>> {
+        #[derive(Serialize)]
+        struct TagsReq<'a> {
+            tags: &'a [String],
+        }
+        let current = self.tags();
+        if *command == TagCommand::List {
+            return Ok(current);
+        }
+        if !self.supports(Capability::SessionTags) {
+            return Err(crate::error::CodexErr::UnsupportedOperation(
+                "Omnara server does not support session tags".to_string(),
+            ));
+        }
+        let tags = apply_tag_command(&current, command);
+        let _in_flight = self.in_flight.enter();
+        let url = self.url(&format!("/api/v1/sessions/{}", self.session_id));
+        info!(url = %url, ?tags, "Omnara set_tags: PATCH");
+        self.append_log(&format!(
+            "--- SETTING SESSION TAGS ---\nTags: {}\n\n",
+            tags.join(", ")
+        ));
+        let resp = self
+            .send_traced(
+                self.auth(self.http.patch(url))
+                    .json(&TagsReq { tags: &tags }),
+                "set_tags",
+            )
+            .await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara set_tags: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara set_tags: error");
+            self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        if let Ok(mut guard) = self.tags.lock() {
+            guard.clone_from(&tags);
+        }
+        // Metadata not sent yet would put the old tags back.
+        if let Ok(mut guard) = self.session_metadata.lock()
+            && let Some(metadata) = guard.as_mut()
+        {
+            metadata.tags.clone_from(&tags);
+        }
+        info!("Omnara set_tags: success");
+        Ok(tags)
+    }
+
+    /// Set the title unless one was already set, either explicitly or via
+    /// session metadata. Used to derive a title from the first prompt.
+    pub async fn set_title_if_unset(&self, title: &str) -> crate::error::Result<()> {
+        if self.titled.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.set_title(title).await
+    }
+
+    /// Send a local user message to Omnara for this session.
+    pub async fn send_user_message(
+        &self,
+        content: &str,
+        mark_as_read: bool,
+    ) -> crate::error::Result<String> {
+        debug!(
+            content_len = content.len(),
+            mark_as_read, "Omnara send_user_message: begin"
+        );
+        self.note_activity();
+        let result = self.post_user_message(content, mark_as_read).await;
+        self.metrics.record_send(MessageKind::User, result.is_ok());
+        result
+    }
+
+    async fn post_user_message(
+        &self,
+        content: &str,
+        mark_as_read: bool,
+    ) -> crate::error::Result<String> {
+        let _in_flight = self.in_flight.enter();
+        #[derive(Serialize)]
+        struct UserMessageReq<'a> {
+            agent_instance_id: &'a str,
+            content: &'a str,
+            mark_as_read: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            client_message_id: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            message_metadata: Option<MessageMetadata>,
+        }
+        #[derive(Deserialize)]
+        struct UserMessageResp {
+            message_id: String,
+        }
+
+        // Remembered before the send: a poll can return the message before
+        // its POST has been answered.
+        let client_message_id = self
+            .supports(Capability::StructuredMessages)
+            .then(|| self.remember_origin());
+        let content = &self.transformers.apply(OmnaraMessageKind::User, content);
+        let req = UserMessageReq {
+            agent_instance_id: &self.session_id.to_string(),
+            content,
+            mark_as_read,
+            client_message_id,
+            message_metadata: self.next_message_metadata(),
+        };
+        let url = self.url("/api/v1/messages/user");
+        info!(url = %url, "Omnara send_user_message: POST");
+        let resp = self
+            .send_json(self.auth(self.http.post(url)), &req, "send_user_message")
+            .await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara send_user_message: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara send_user_message: error");
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        let parsed: UserMessageResp = resp.json().await?;
+        info!(message_id = %parsed.message_id, "Omnara send_user_message: success");
+        Ok(parsed.message_id)
+    }
+
+    /// Upload a full artifact (diff, command output) so notes can link to it
+    /// instead of inlining it (POST /api/v1/attachments). Fails with
+    /// `UnsupportedOperation` when the server does not accept attachments;
+    /// check [`Self::supports`] first to inline the artifact instead.
+    pub async fn upload_attachment(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        mime: &str,
+    ) -> crate::error::Result<AttachmentRef> {
+        use base64::Engine;
+        if !self.supports(Capability::Attachments) {
+            return Err(crate::error::CodexErr::UnsupportedOperation(
+                "Omnara server does not accept attachments".to_string(),
+            ));
+        }
+        debug!(
+            name,
+            size = bytes.len(),
+            mime,
+            "Omnara upload_attachment: begin"
+        );
+        let _in_flight = self.in_flight.enter();
+        #[derive(Serialize)]
+        struct AttachmentReq<'a> {
+            agent_instance_id: &'a str,
+            filename: &'a str,
+            mime_type: &'a str,
+            content_base64: String,
+        }
+
+        let req = AttachmentReq {
+            agent_instance_id: &self.session_id.to_string(),
+            filename: name,
+            mime_type: mime,
+            content_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+        };
+        let url = self.url("/api/v1/attachments");
+        info!(url = %url, "Omnara upload_attachment: POST");
+        self.append_log(&format!(
+            "--- UPLOADING ATTACHMENT ---\nTime: {}\nName: {}\nSize: {} bytes\n\n",
+            chrono::Utc::now().to_rfc3339(),
+            name,
+            bytes.len()
+        ));
+        let resp = self
+            .send_json(self.auth(self.http.post(url)), &req, "upload_attachment")
+            .await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara upload_attachment: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara upload_attachment: error");
+            self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        let parsed: AttachmentRef = resp.json().await?;
+        info!(attachment_id = %parsed.attachment_id, "Omnara upload_attachment: success");
+        Ok(parsed)
+    }
+
+    /// Create a dashboard link that lets someone else follow this session
+    /// with `permissions` (POST /api/v1/sessions/{id}/share). Fails with
+    /// `UnsupportedOperation` when the server does not issue share links.
+    pub async fn create_share_link(
+        &self,
+        permissions: SharePermissions,
+    ) -> crate::error::Result<ShareLink> {
+        #[derive(Serialize)]
+        struct ShareReq {
+            permissions: SharePermissions,
+        }
+        if !self.supports(Capability::ShareLinks) {
+            return Err(crate::error::CodexErr::UnsupportedOperation(
+                "Omnara server does not issue share links".to_string(),
+            ));
+        }
+        let _in_flight = self.in_flight.enter();
+        let url = self.url(&format!("/api/v1/sessions/{}/share", self.session_id));
+        info!(url = %url, ?permissions, "Omnara create_share_link: POST");
+        self.append_log(&format!(
+            "--- CREATING SHARE LINK ---\nTime: {}\nPermissions: {permissions:?}\n\n",
+            chrono::Utc::now().to_rfc3339()
+        ));
+        let resp = self
+            .send_traced(
+                self.auth(self.http.post(url))
+                    .json(&ShareReq { permissions }),
+                "create_share_link",
+            )
+            .await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara create_share_link: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara create_share_link: error");
+            self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        let parsed: ShareLink = resp.json().await?;
+        info!("Omnara create_share_link: success");
+        Ok(parsed)
+    }
+
+    /// Messages of this session created after `since` (all of them for
+    /// `None`), oldest first (GET /api/v1/sessions/{id}/messages). Fails
+    /// with `UnsupportedOperation` when the server keeps no history.
+    pub async fn message_history(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> crate::error::Result<Vec<HistoryMessage>> {
+        if !self.supports(Capability::MessageHistory) {
+            return Err(crate::error::CodexErr::UnsupportedOperation(
+                "Omnara server does not keep message history".to_string(),
+            ));
+        }
+        let _in_flight = self.in_flight.enter();
+        let url = self.url(&format!("/api/v1/sessions/{}/messages", self.session_id));
+        info!(url = %url, ?since, "Omnara message_history: GET");
+        let mut req = self.auth(self.http.get(url));
+        if let Some(since) = since {
+            req = req.query(&[("since", since.to_rfc3339())]);
+        }
+        let resp = self.send_traced(req, "message_history").await?;
+        let status = resp.status();
+        debug!(status = %status, "Omnara message_history: response status");
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            error!(status = %status, body = %text, "Omnara message_history: error");
+            self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        let mut parsed: MessageHistoryResponse = resp.json().await?;
+        parsed.messages.sort_by_key(|message| message.created_at);
+        info!(
+            count = parsed.messages.len(),
+            "Omnara message_history: success"
+        );
+        Ok(parsed.messages)
+    }
+
+    pub fn new(api_key: String, base_url: String, session_id: uuid::Uuid) -> Self {
+        info!(base_url = %base_url, session_id = %session_id, "Creating OmnaraClient");
+        let wrapper_log = Self::init_wrapper_log_path(session_id);
+        let acks = AckStore::load(wrapper_log.with_extension("ack.json"));
+        let journal = Journal::load(wrapper_log.with_extension("journal.json"));
+        let last_read = acks
+            .last_read_message_id()
+            .or(journal.last_read_message_id())
+            .map(str::to_string);
+        let this = Self {
+            http: crate::omnara_http::default_http_client(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            session_id,
+            last_agent_message_id: Arc::new(Mutex::new(last_read)),
+            acks: Arc::new(Mutex::new(acks)),
+            journal: Arc::new(Mutex::new(journal)),
+            poller: Arc::new(Mutex::new(PollerState::default())),
+            wrapper_log,
+            git: Some(Arc::new(Mutex::new(MultiRepoDiffTracker::new(None)))),
+            withheld: WithheldPaths::default(),
+            poll_policy: PollPolicy::default(),
+            activity: Arc::new(Activity::new(Arc::new(TokioClock))),
+            in_flight: Arc::new(InFlight::default()),
+            metrics: Arc::new(OmnaraMetrics::default()),
+            session_metadata: Arc::new(Mutex::new(None)),
+            next_sequence: Arc::new(AtomicU64::new(0)),
+            titled: Arc::new(AtomicBool::new(false)),
+            tags: Arc::new(Mutex::new(Vec::new())),
+            trace_propagation: false,
+            turn_trace: Arc::new(Mutex::new(None)),
+            compress_requests: Arc::new(AtomicBool::new(true)),
+            markdown_dialect: OmnaraMarkdownDialect::default(),
+            transformers: Arc::new(TransformPipeline::default()),
+            capabilities: Arc::new(Mutex::new(None)),
+            circuit: Arc::new(Mutex::new(CircuitBreaker::new(CircuitPolicy::default()))),
+            circuit_open: Arc::new(watch::Sender::new(false)),
+            outbox: Arc::new(Mutex::new(VecDeque::new())),
+            sent_origins: Arc::new(Mutex::new(VecDeque::new())),
+            last_message_seen: Arc::new(watch::Sender::new(false)),
+            last_reaction: Arc::new(watch::Sender::new(None)),
+            transcriber: None,
+            uploads: None,
+            workspace: None,
+        };
+        this.append_log(&format!(
+            "=== OMNARA CLIENT INITIALIZED ===\nTime: {}\nSession ID: {}\nAPI URL: {}\n\n",
+            chrono::Utc::now().to_rfc3339(),
+            this.session_id,
+            this.base_url
+        ));
+        this
+    }
+
+    /// A client for a new session on the same server, with this client's
+    /// connection pool, polling schedule, clock, trace propagation, metrics,
+    /// negotiated capabilities, withheld paths, markdown dialect, voice
+    /// transcriber, uploads directory, and workspace. Its metadata should
+    /// name this session as `parent_session_id`.
+    pub fn child(&self) -> Self {
+        let mut child = Self::new(
+            self.api_key.clone(),
+            self.base_url.clone(),
+            uuid::Uuid::new_v4(),
+        )
+        .with_poll_policy(self.poll_policy)
+        .with_clock(self.clock())
+        .with_trace_propagation(self.trace_propagation)
+        .with_request_compression(self.compress_requests.load(Ordering::SeqCst));
+        child.http = self.http.clone();
+        child.metrics = self.metrics.clone();
+        child.capabilities = self.capabilities.clone();
+        child.withheld = self.withheld.clone();
+        child.markdown_dialect = self.markdown_dialect;
+        child.transformers = self.transformers.clone();
+        child.transcriber = self.transcriber.clone();
+        child.uploads = self.uploads.clone();
+        child.workspace = self.workspace.clone();
+        child
+    }
+
+    /// Keep delivery acknowledgements at `path` instead of next to the session
+    /// log, restoring the last-read id persisted there.
+    pub fn with_ack_path(self, path: PathBuf) -> Self {
+        let acks = AckStore::load(path);
+        if let Ok(mut guard) = self.last_agent_message_id.lock() {
+            *guard = acks.last_read_message_id().map(str::to_string);
+        }
+        if let Ok(mut guard) = self.acks.lock() {
+            *guard = acks;
+        }
+        self
+    }
+
+    /// Keep the crash recovery journal at `path` instead of next to the
+    /// session log, offering what it holds as the recovery.
+    pub fn with_journal_path(self, path: PathBuf) -> Self {
+        if let Ok(mut guard) = self.journal.lock() {
+            *guard = Journal::load(path);
+        }
+        self
+    }
+
+    /// Replace the polling schedule (e.g., from the `[omnara]` config section).
+    pub fn with_poll_policy(mut self, poll_policy: PollPolicy) -> Self {
+        self.poll_policy = poll_policy;
+        self
+    }
+
+    /// Read the time and sleep through `clock` for polling, idle suspension,
+    /// and the timers of frontends built on this client (see
+    /// [`crate::omnara_clock`]).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.activity = Arc::new(Activity::new(clock));
+        self
+    }
+
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.activity.clock.clone()
+    }
+
+    /// Attach metadata to be sent with the first agent message of the session.
+    pub fn with_session_metadata(self, metadata: SessionMetadata) -> Self {
+        if metadata.title.is_some() {
+            self.titled.store(true, Ordering::SeqCst);
+        }
+        if let Ok(mut tags) = self.tags.lock() {
+            tags.clone_from(&metadata.tags);
+        }
+        if let Ok(mut guard) = self.session_metadata.lock() {
+            *guard = Some(metadata);
+        }
+        self
+    }
+
+    /// Keep files matching these gitignore-style patterns out of the session
+    /// diff, in addition to each repository's `.codexignore`.
+    pub fn with_diff_excludes(self, patterns: Vec<String>) -> Self {
+        if let Some(git) = &self.git
+            && let Ok(mut tracker) = git.lock()
+        {
+            tracker.set_exclude_patterns(patterns);
+        }
+        self
+    }
+
+    /// Withhold the content of files matching these patterns
+    /// (`omnara.withhold_paths`) from the session diff and from the
+    /// formatters that consult [`Self::withheld_paths`].
+    pub fn with_withheld_paths(mut self, patterns: &[String]) -> Self {
+        self.withheld = WithheldPaths::new(patterns);
+        self
+    }
+
+    pub fn withheld_paths(&self) -> &WithheldPaths {
+        &self.withheld
+    }
+
+    /// Replace the session diff size limits.
+    pub fn with_diff_limits(self, limits: DiffLimits) -> Self {
+        if let Some(git) = &self.git
+            && let Ok(mut tracker) = git.lock()
+        {
+            tracker.set_limits(limits);
+        }
+        self
+    }
+
+    /// Per-file line counts of the session diff without its text, e.g. for
+    /// a `+120 −34 across 6 files` status; None outside a repository.
+    pub fn diff_stats(&self) -> Option<DiffStatSummary> {
+        self.git.as_ref()?.lock().ok()?.get_diff_stats()
+    }
+
+    /// Choose which git submodules the session diff descends into.
+    pub fn with_diff_submodules(self, submodules: SubmoduleDiffs) -> Self {
+        if let Some(git) = &self.git
+            && let Ok(mut tracker) = git.lock()
+        {
+            tracker.set_submodules(submodules);
+        }
+        self
+    }
+
+    pub fn session_id(&self) -> uuid::Uuid {
+        self.session_id
+    }
+
+    /// The named workspace whose key the client uses; None for the default
+    /// key.
+    pub fn workspace(&self) -> Option<&SelectedWorkspace> {
+        self.workspace.as_ref()
+    }
+
+    /// Propagate the turn's trace context to Omnara in `traceparent` headers.
+    pub fn with_trace_propagation(mut self, enabled: bool) -> Self {
+        self.trace_propagation = enabled;
+        self
+    }
+
+    /// Replace the shared default HTTP client with one tuned by `config`
+    /// (`[omnara.http]`). Keeps the current client if `config` is invalid.
+    pub fn with_http_config(mut self, config: &OmnaraHttpConfig) -> Self {
+        match crate::omnara_http::build_http_client(config) {
+            Ok(http) => self.http = http,
+            Err(e) => {
+                warn!("Omnara: ignoring [omnara.http]: {e}");
+                self.append_log(&format!("[Http] ignoring [omnara.http]: {e}\n"));
+            }
+        }
+        self
+    }
+
+    /// Transcribe voice notes sent from the Omnara app as `[omnara.voice]`
+    /// says, forwarding each transcript marked `(from voice)`.
+    pub fn with_voice(mut self, config: &OmnaraVoice) -> Self {
+        self.transcriber = Transcriber::from_config(config).map(Arc::new);
+        self
+    }
+
+    /// Save files attached to dashboard messages into the workspace at `cwd`
+    /// as `[omnara.uploads]` says, naming each in the forwarded message.
+    pub fn with_uploads(mut self, config: &OmnaraUploads, cwd: &Path) -> Self {
+        self.uploads = Uploads::from_config(config, cwd).map(Arc::new);
+        self
+    }
+
+    /// Pause requests after repeated failures as `policy` says
+    /// (`[omnara.circuit_breaker]`).
+    pub fn with_circuit_breaker(self, policy: CircuitPolicy) -> Self {
+        if let Ok(mut circuit) = self.circuit.lock() {
+            *circuit = CircuitBreaker::new(policy);
+        }
+        self
+    }
+
+    /// Ask the server for its API version and capabilities
+    /// (GET /api/v1/version) and stop using features it lacks. A 404 means a
+    /// server older than the handshake, which gets only plain messages; any
+    /// other failure keeps every feature on. Runs once per server: later
+    /// calls, from clones and child sessions too, return the first result.
+    pub async fn negotiate(&self) -> ServerCapabilities {
+        if let Some(negotiated) = self
+            .capabilities
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+        {
+            return negotiated;
+        }
+        let url = self.url("/api/v1/version");
+        info!(url = %url, "Omnara negotiate: GET");
+        let negotiated = match self
+            .send_traced(self.auth(self.http.get(url)), "negotiate")
+            .await
+        {
+            Ok(resp) if resp.status() == reqwest::StatusCode::NOT_FOUND => {
+                Some(ServerCapabilities::legacy())
+            }
+            Ok(resp) if resp.status().is_success() => match resp.json::<VersionResponse>().await {
+                Ok(version) => Some(ServerCapabilities::from_response(version)),
+                Err(e) => {
+                    warn!("Omnara negotiate: unreadable version response: {e}");
+                    None
+                }
+            },
+            Ok(resp) => {
+                warn!(status = %resp.status(), "Omnara negotiate: unexpected status");
+                None
+            }
+            Err(e) => {
+                warn!("Omnara negotiate: request failed: {e}");
+                None
+            }
+        };
+        let Some(negotiated) = negotiated else {
+            return self.capabilities();
+        };
+        info!(capabilities = ?negotiated, "Omnara negotiate: success");
+        self.append_log(&format!(
+            "[Handshake] server capabilities: {negotiated:?}\n"
+        ));
+        if !negotiated.gzip_requests {
+            self.compress_requests.store(false, Ordering::SeqCst);
+        }
+        if let Ok(mut guard) = self.capabilities.lock() {
+            *guard = Some(negotiated.clone());
+        }
+        negotiated
+    }
+
+    /// The negotiated server capabilities, or every feature before
+    /// [`Self::negotiate`] has completed.
+    pub fn capabilities(&self) -> ServerCapabilities {
+        self.capabilities
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+            .unwrap_or_else(ServerCapabilities::full)
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().map(|c| c.supports(capability)))
+            .unwrap_or(true)
+    }
+
+    /// The pooled HTTP client behind this session's requests.
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Time one authenticated round trip to the version endpoint and read
+    /// the server's clock from its `Date` header. Any answer counts, so a
+    /// rejected key shows in [`ServerProbe::status`].
+    pub async fn probe_server(&self) -> crate::error::Result<ServerProbe> {
+        let url = self.url("/api/v1/version");
+        let sent_at = Utc::now();
+        let start = Instant::now();
+        let resp = self
+            .send_counted(self.auth(self.http.get(url)), "probe_server")
+            .await?;
+        let latency = start.elapsed();
+        let midpoint = sent_at + chrono::Duration::from_std(latency / 2).unwrap_or_default();
+        let clock_skew = resp
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| date.with_timezone(&Utc) - midpoint);
+        Ok(ServerProbe {
+            latency,
+            status: resp.status(),
+            clock_skew,
+        })
+    }
+
+    /// Create a throwaway session on this server with one agent message,
+    /// poll its pending messages once, and end it. The session is ended even
+    /// when polling fails.
+    pub async fn probe_session(&self) -> SessionProbe {
+        let mut probe = self.child();
+        probe.git = None;
+        let start = Instant::now();
+        if let Err(e) = probe
+            .post_agent_message(
+                "Codex health check (`codex omnara doctor`); this session ends right away.",
+                false,
+                Notifications::Push(false),
+            )
+            .await
+        {
+            return SessionProbe {
+                create: Err(e),
+                poll: None,
+                end: None,
+            };
+        }
+        let create = start.elapsed();
+        let start = Instant::now();
+        let poll = probe.poll_pending_once().await.map(|()| start.elapsed());
+        let end = probe.end_session().await;
+        SessionProbe {
+            create: Ok(create),
+            poll: Some(poll),
+            end: Some(end),
+        }
+    }
+
+    /// Run every health check against this client's server and key. The
+    /// session probe is left out when the server is unreachable or rejects
+    /// the key.
+    pub async fn doctor(&self) -> DoctorReport {
+        let mut server = Vec::with_capacity(LATENCY_SAMPLES);
+        for _ in 0..LATENCY_SAMPLES {
+            server.push(self.probe_server().await);
+        }
+        let usable = server.iter().flatten().next().is_some()
+            && server.iter().flatten().all(|probe| {
+                !matches!(
+                    probe.status,
+                    reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+                )
+            });
+        let session = if usable {
+            Some(self.probe_session().await)
+        } else {
+            None
+        };
+        DoctorReport::from_probes(self.base_url.clone(), &server, session.as_ref())
+    }
+
+    /// Follow the circuit: `true` while requests are paused.
+    pub fn subscribe_circuit(&self) -> watch::Receiver<bool> {
+        self.circuit_open.subscribe()
+    }
+
+    pub fn circuit_policy(&self) -> CircuitPolicy {
+        self.circuit
+            .lock()
+            .map(|circuit| circuit.policy())
+            .unwrap_or_default()
+    }
+
+    pub fn circuit_is_open(&self) -> bool {
+        self.circuit
+            .lock()
+            .map(|circuit| circuit.is_open())
+            .unwrap_or(false)
+    }
+
+    /// Try the server while the circuit is open; an answer closes it.
+    /// Returns whether the circuit is closed afterwards.
+    pub async fn probe_circuit(&self) -> bool {
+        if self.circuit_is_open() {
+            let _ = self.probe_server().await;
+        }
+        !self.circuit_is_open()
+    }
+
+    /// Watch the circuit on a task: `on_change` gets `true` when requests
+    /// pause and `false` when they resume, and while they are paused the
+    /// server is probed every [`CircuitPolicy::open_for`]. Abort the
+    /// returned task when done with the session.
+    pub fn start_circuit_monitor<F>(&self, mut on_change: F) -> JoinHandle<()>
+    where
+        F: FnMut(bool) + Send + 'static,
+    {
+        let client = self.clone();
+        let mut open = self.subscribe_circuit();
+        tokio::spawn(async move {
+            let mut paused = *open.borrow_and_update();
+            if paused {
+                on_change(true);
+            }
+            loop {
+                if paused {
+                    let probe_after = client.circuit_policy().open_for;
+                    tokio::select! {
+                        changed = open.changed() => {
+                            if changed.is_err() {
+                                return;
+                            }
+                        }
+                        _ = client.clock().sleep(probe_after) => {
+                            debug!("Omnara: probing the server");
+                            client.probe_circuit().await;
+                        }
+                    }
+                } else if open.changed().await.is_err() {
+                    return;
+                }
+                let now = *open.borrow_and_update();
+                if now != paused {
+                    paused = now;
+                    on_change(now);
+                }
+            }
+        })
+    }
+
+    /// Agent messages queued while the circuit is open.
+    pub fn queued_message_count(&self) -> usize {
+        self.outbox.lock().map(|outbox| outbox.len()).unwrap_or(0)
+    }
+
+    /// Send the agent messages queued while the circuit was open, oldest
+    /// first, stopping at the first failure; the rest stay queued. Returns
+    /// how many were sent.
+    pub async fn send_queued_messages(&self) -> usize {
+        let mut sent = 0;
+        while self.circuit_allows() {
+            let Some((content, notify)) = self
+                .outbox
+                .lock()
+                .ok()
+                .and_then(|mut outbox| outbox.pop_front())
+            else {
+                break;
+            };
+            let result = self.try_post_agent_message(&content, false, notify).await;
+            self.metrics.record_send(MessageKind::Agent, result.is_ok());
+            if result.is_err() {
+                if let Ok(mut outbox) = self.outbox.lock() {
+                    outbox.push_front((content, notify));
+                }
+                break;
+            }
+            sent += 1;
+        }
+        sent
+    }
+
+    fn queue_message(&self, content: &str, notify: Notifications) {
+        let Ok(mut outbox) = self.outbox.lock() else {
+            return;
+        };
+        if outbox.len() == MAX_QUEUED_MESSAGES {
+            outbox.pop_front();
+            warn!("Omnara: outbox full; dropped the oldest queued message");
+        }
+        outbox.push_back((content.to_string(), notify));
+        debug!(
+            queued = outbox.len(),
+            "Omnara: circuit open; message queued"
+        );
+    }
+
+    /// One GET of the pending messages, as the poller sends it.
+    async fn poll_pending_once(&self) -> crate::error::Result<()> {
+        let mut req = self
+            .auth(self.http.get(self.url("/api/v1/messages/pending")))
+            .query(&[("agent_instance_id", self.session_id.to_string())]);
+        let last_read = self
+            .last_agent_message_id
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone());
+        if let Some(id) = last_read {
+            req = req.query(&[("last_read_message_id", id)]);
+        }
+        let resp = self.send_traced(req, "poll").await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+        }
+        resp.json::<PendingMessagesResponse>().await?;
+        Ok(())
+    }
+
+    /// Convert agent messages, and their edits, to `dialect` before they
+    /// are sent (`omnara.markdown_dialect`).
+    pub fn with_markdown_dialect(mut self, dialect: OmnaraMarkdownDialect) -> Self {
+        self.markdown_dialect = dialect;
+        self
+    }
+
+    /// Run `transformers` (`[[omnara.transformers]]`) on every message
+    /// before it is sent or edited.
+    pub fn with_transformers(mut self, transformers: &[OmnaraTransformer]) -> Self {
+        self.transformers = Arc::new(TransformPipeline::from_config(transformers));
+        self
+    }
+
+    /// Gzip request bodies of at least 1 KiB.
+    pub fn with_request_compression(self, enabled: bool) -> Self {
+        self.compress_requests.store(enabled, Ordering::SeqCst);
+        self
+    }
+
+    /// Make later requests part of the turn's trace (from `TaskStarted`).
+    pub fn set_turn_trace(&self, trace: Option<TraceContext>) {
+        if let Ok(mut guard) = self.turn_trace.lock() {
+            *guard = trace;
+        }
+    }
+
+    /// A span for `operation`. With trace propagation on and a traced turn,
+    /// it is a new child span of the turn, whose context is returned for the
+    /// `traceparent` header.
+    pub fn span(&self, operation: &'static str) -> (tracing::Span, Option<TraceContext>) {
+        let span = tracing::info_span!(
+            "omnara",
+            operation,
+            trace_id = tracing::field::Empty,
+            span_id = tracing::field::Empty,
+            parent_span_id = tracing::field::Empty,
+        );
+        let parent = self
+            .trace_propagation
+            .then(|| self.turn_trace.lock().ok().and_then(|guard| *guard))
+            .flatten();
+        let context = parent.map(|parent| {
+            let context = parent.child();
+            span.record("trace_id", context.trace_id_hex());
+            span.record("span_id", context.span_id_hex());
+            span.record("parent_span_id", parent.span_id_hex());
+            context
+        });
+        (span, context)
+    }
+
+    /// Send `req` inside a span for `operation`, with its `traceparent`.
+    /// Fails with [`CodexErr::OmnaraUnavailable`] without sending while the
+    /// circuit is open.
+    ///
+    /// [`CodexErr::OmnaraUnavailable`]: crate::error::CodexErr::OmnaraUnavailable
+    async fn send_traced(
+        &self,
+        req: reqwest::RequestBuilder,
+        operation: &'static str,
+    ) -> crate::error::Result<reqwest::Response> {
+        if !self.circuit_allows() {
+            trace!(operation, "Omnara: circuit open; request not sent");
+            return Err(crate::error::CodexErr::OmnaraUnavailable);
+        }
+        self.send_counted(req, operation).await
+    }
+
+    /// [`Self::send_traced`] even while the circuit is open; the outcome
+    /// still counts toward it.
+    async fn send_counted(
+        &self,
+        req: reqwest::RequestBuilder,
+        operation: &'static str,
+    ) -> crate::error::Result<reqwest::Response> {
+        let (span, context) = self.span(operation);
+        let req = match context {
+            Some(context) => req.header("traceparent", context.traceparent()),
+            None => req,
+        };
+        let result = req.send().instrument(span).await;
+        self.record_outcome(
+            operation,
+            matches!(&result, Ok(resp) if !resp.status().is_server_error()),
+        );
+        Ok(result?)
+    }
+
+    fn circuit_allows(&self) -> bool {
+        let now = self.clock().now();
+        self.circuit
+            .lock()
+            .map(|circuit| circuit.allows(now))
+            .unwrap_or(true)
+    }
+
+    fn record_outcome(&self, operation: &'static str, ok: bool) {
+        let now = self.clock().now();
+        let Some(change) = self
+            .circuit
+            .lock()
+            .ok()
+            .and_then(|mut circuit| circuit.record(ok, now))
+        else {
+            return;
+        };
+        match change {
+            CircuitChange::Opened => {
+                warn!(operation, "Omnara: repeated failures; pausing requests");
+                self.append_log("[Circuit] opened after repeated failures\n");
+            }
+            CircuitChange::Closed => {
+                info!(operation, "Omnara: server answered; resuming requests");
+                self.append_log("[Circuit] closed\n");
+            }
+        }
+        self.circuit_open
+            .send_replace(change == CircuitChange::Opened);
+    }
+
+    /// Send `body` as JSON, gzip-compressed when it is large enough. A server
+    /// that does not accept `Content-Encoding: gzip` answers 415 (RFC 7694);
+    /// the request is then repeated uncompressed and compression stays off.
+    async fn send_json<T: Serialize>(
+        &self,
+        req: reqwest::RequestBuilder,
+        body: &T,
+        operation: &'static str,
+    ) -> crate::error::Result<reqwest::Response> {
+        let Some(compressed) = self.compress_body(body) else {
+            return self.send_traced(req.json(body), operation).await;
+        };
+        let retry = req.try_clone();
+        let resp = self
+            .send_traced(
+                req.header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(reqwest::header::CONTENT_ENCODING, "gzip")
+                    .body(compressed),
+                operation,
+            )
+            .await?;
+        match retry {
+            Some(retry) if resp.status() == reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                warn!(
+                    operation,
+                    "Omnara: server rejected a gzip body; compression disabled"
+                );
+                self.append_log("[Compression] server rejected gzip bodies; disabled\n");
+                self.compress_requests.store(false, Ordering::SeqCst);
+                self.send_traced(retry.json(body), operation).await
+            }
+            _ => Ok(resp),
+        }
+    }
+
+    /// The gzip-compressed JSON of `body`, if compression is on and pays off.
+    fn compress_body<T: Serialize>(&self, body: &T) -> Option<Vec<u8>> {
+        if !self.compress_requests.load(Ordering::SeqCst) {
+            return None;
+        }
+        let json = serde_json::to_vec(body).ok()?;
+        if json.len() < MIN_COMPRESSED_BODY_BYTES {
+            return None;
+        }
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&json).ok()?;
+        let compressed = encoder.finish().ok()?;
+        trace!(
+            plain = json.len(),
+            compressed = compressed.len(),
+            "Omnara: compressed request body"
+        );
+        (compressed.len() < json.len()).then_some(compressed)
+    }
+
+    /// Bridge health metrics, shared by every clone of this client.
+    pub fn metrics(&self) -> Arc<OmnaraMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Include the git repository containing `path` (a patched file or an
+    /// exec working directory) in the session diff. Runs git synchronously.
+    pub fn track_repo_path(&self, path: &Path) {
+        if let Some(git) = &self.git
+            && let Ok(mut tracker) = git.lock()
+            && tracker.track_path(path)
+        {
+            info!(path = %path.display(), "Omnara: tracking additional git repository");
+            self.append_log(&format!(
+                "[Git] tracking additional repository for {}\n",
+                path.display()
+            ));
+        }
+    }
+
+    /// Record the files an applied patch changes, so those outside version
+    /// control still show up in the session diff. Runs the VCS
+    /// synchronously.
+    pub fn record_patch(&self, changes: &HashMap<PathBuf, FileChange>) {
+        if let Some(git) = &self.git
+            && let Ok(mut tracker) = git.lock()
+        {
+            tracker.record_patch(changes);
+        }
+    }
+
+    /// Start a fresh session diff baseline (e.g. for a new task), so later
+    /// diffs only cover changes made from now on. Runs the VCS synchronously.
+    pub fn checkpoint_diff(&self) {
+        if let Some(git) = &self.git
+            && let Ok(mut tracker) = git.lock()
+        {
+            tracker.checkpoint();
+            self.append_log("[Git] diff baseline checkpointed\n");
+        }
+    }
+
+    /// What it takes to undo the session's changes back to the diff
+    /// baseline; None when no tracked repository supports it. Runs the VCS
+    /// synchronously.
+    pub fn rollback_plan(&self) -> Option<RollbackPlan> {
+        let git = self.git.as_ref()?;
+        let tracker = git.lock().ok()?;
+        tracker.rollback_plan()
+    }
+
+    /// Record session activity so polling snaps back to the fast interval.
+    pub fn note_activity(&self) {
+        self.activity.touch();
+    }
+
+    #[allow(clippy::expect_used)]
+    fn url(&self, path: &str) -> reqwest::Url {
+        let base = Url::parse(&self.base_url).expect("valid OMNARA_API_URL");
+        base.join(path).expect("valid API path")
+    }
+
+    fn auth(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        req.bearer_auth(&self.api_key)
+    }
+
+    /// Set the last-read message id used when polling for pending messages.
+    pub fn set_last_read_message_id(&self, id: String) {
+        self.persist_last_read(&id);
+        self.track_last_message(id);
+    }
+
+    /// Make `id` the message replies answer; a new one is not seen yet.
+    fn track_last_message(&self, id: String) {
+        let Ok(mut guard) = self.last_agent_message_id.lock() else {
+            return;
+        };
+        if guard.as_deref() != Some(id.as_str()) {
+            self.last_message_seen
+                .send_if_modified(|seen| std::mem::replace(seen, false));
+        }
+        *guard = Some(id);
+    }
+
+    /// Follow read receipts: `true` once the dashboard has read the last
+    /// agent message, `false` again when a newer one is sent.
+    pub fn subscribe_last_message_seen(&self) -> watch::Receiver<bool> {
+        self.last_message_seen.subscribe()
+    }
+
+    /// Note the agent messages a poll reported as read on the dashboard.
+    fn note_read_receipts(&self, read_message_ids: &[String]) {
+        let last = self
+            .last_agent_message_id
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone());
+        if let Some(last) = last
+            && read_message_ids.contains(&last)
+            && self
+                .last_message_seen
+                .send_if_modified(|seen| !std::mem::replace(seen, true))
+        {
+            debug!(message_id = %last, "Omnara: last agent message seen on the dashboard");
+        }
+    }
+
+    /// Follow reactions on the last agent message (the one replies answer):
+    /// the latest one, `None` until there is one.
+    pub fn subscribe_reactions(&self) -> watch::Receiver<Option<MessageReaction>> {
+        self.last_reaction.subscribe()
     }
 
-    /// End the Omnara session (POST /api/v1/sessions/end).
-    pub async fn end_session(&self) -> crate::error::Result<()> {
-        #[derive(Serialize)]
-        struct EndReq<'a> {
-            agent_instance_id: &'a str,
-        }
-        info!(session_id = %self.session_id, "Omnara end_session: begin");
-        let req = EndReq {
-            agent_instance_id: &self.session_id.to_string(),
+    /// Publish the newest reaction a poll reported on the last agent
+    /// message, skipping ones already seen. Reactions on older messages are
+    /// dropped. Returns whether one was published.
+    fn note_reactions(&self, reactions: Vec<PendingReaction>) -> bool {
+        let last = self
+            .last_agent_message_id
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone());
+        let Some(last) = last else {
+            return false;
         };
-        let url = self.url("/api/v1/sessions/end");
-        info!(url = %url, "Omnara end_session: POST");
-        let resp = self.auth(self.http.post(url)).json(&req).send().await?;
+        let mut fresh = reactions
+            .into_iter()
+            .filter(|reaction| reaction.message_id == last)
+            .filter(|reaction| {
+                reaction
+                    .id
+                    .as_deref()
+                    .is_none_or(|id| !self.was_acknowledged(&format!("reaction:{id}")))
+            })
+            .collect::<Vec<_>>();
+        let Some(reaction) = fresh.pop() else {
+            return false;
+        };
+        debug!(message_id = %last, emoji = %reaction.emoji, "Omnara: reaction on the last agent message");
+        let published_id = reaction.id.clone();
+        self.last_reaction.send_replace(Some(MessageReaction {
+            message_id: reaction.message_id,
+            emoji: reaction.emoji,
+        }));
+        // Acknowledge every new reaction once published, not only the latest,
+        // so none is published on a later poll.
+        for id in fresh
+            .iter()
+            .filter_map(|reaction| reaction.id.as_deref())
+            .chain(published_id.as_deref())
+        {
+            self.acknowledge(&format!("reaction:{id}"));
+        }
+        true
+    }
+
+    /// Confirm that remote message `message_id` was handed to the agent
+    /// (POST /api/v1/messages/{id}/read). A no-op on servers without read
+    /// receipts.
+    pub async fn send_read_receipt(&self, message_id: &str) -> crate::error::Result<()> {
+        if !self.supports(Capability::ReadReceipts) {
+            return Ok(());
+        }
+        let url = self.url(&format!("/api/v1/messages/{message_id}/read"));
+        debug!(url = %url, "Omnara send_read_receipt: POST");
+        let resp = self
+            .send_traced(self.auth(self.http.post(url)), "read_receipt")
+            .await?;
         let status = resp.status();
-        debug!(status = %status, "Omnara end_session: response status");
         if !status.is_success() {
             let text = resp.text().await.unwrap_or_default();
-            error!(status = %status, body = %text, "Omnara end_session: error");
             return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
         }
-        info!("Omnara end_session: success");
         Ok(())
     }
-    /// Send a local user message to Omnara for this session.
-    pub async fn send_user_message(
-        &self,
-        content: &str,
-        mark_as_read: bool,
-    ) -> crate::error::Result<String> {
-        debug!(
-            content_len = content.len(),
-            mark_as_read, "Omnara send_user_message: begin"
-        );
-        #[derive(Serialize)]
-        struct UserMessageReq<'a> {
-            agent_instance_id: &'a str,
-            content: &'a str,
-            mark_as_read: bool,
+
+    fn persist_last_read(&self, id: &str) {
+        if let Ok(mut acks) = self.acks.lock() {
+            acks.set_last_read_message_id(id);
         }
-        #[derive(Deserialize)]
-        struct UserMessageResp {
-            message_id: String,
+        if let Ok(mut journal) = self.journal.lock() {
+            journal.set_last_read_message_id(id);
         }
+    }
 
-        let req = UserMessageReq {
-            agent_instance_id: &self.session_id.to_string(),
-            content,
-            mark_as_read,
-        };
-        let url = self.url("/api/v1/messages/user");
-        info!(url = %url, "Omnara send_user_message: POST");
-        let resp = self.auth(self.http.post(url)).json(&req).send().await?;
-        let status = resp.status();
-        debug!(status = %status, "Omnara send_user_message: response status");
-        if !status.is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            error!(status = %status, body = %text, "Omnara send_user_message: error");
-            return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+    /// Journal approval `id` as posted, so it can be closed on the dashboard
+    /// if Codex crashes before it is answered.
+    pub fn journal_approval(&self, id: &str, kind: &str) {
+        if let Ok(mut journal) = self.journal.lock() {
+            journal.record_approval(id, kind);
         }
-        let parsed: UserMessageResp = resp.json().await?;
-        info!(message_id = %parsed.message_id, "Omnara send_user_message: success");
-        Ok(parsed.message_id)
     }
 
-    pub fn new(api_key: String, base_url: String, session_id: uuid::Uuid) -> Self {
-        info!(base_url = %base_url, session_id = %session_id, "Creating OmnaraClient");
-        let wrapper_log = Self::init_wrapper_log_path(session_id);
-        let this = Self {
-            http: reqwest::Client::new(),
-            base_url: base_url.trim_end_matches('/').to_string(),
-            api_key,
-            session_id,
-            last_agent_message_id: Arc::new(Mutex::new(None)),
-            poller: Arc::new(Mutex::new(PollerState::default())),
-            wrapper_log,
-            git: Some(Arc::new(Mutex::new(GitDiffTracker::new(true, None)))),
-        };
-        this.append_log(&format!(
-            "=== OMNARA CLIENT INITIALIZED ===\nTime: {}\nSession ID: {}\nAPI URL: {}\n\n",
-            chrono::Utc::now().to_rfc3339(),
-            this.session_id,
-            this.base_url
-        ));
-        this
+    /// Remove approval `id` from the journal once it is answered or cancelled.
+    pub fn settle_journaled_approval(&self, id: &str) {
+        if let Ok(mut journal) = self.journal.lock() {
+            journal.settle_approval(id);
+        }
     }
 
-    pub fn session_id(&self) -> uuid::Uuid {
-        self.session_id
+    /// What a crashed run of this session left pending, if anything.
+    pub fn recovery(&self) -> Option<Recovery> {
+        self.journal
+            .lock()
+            .ok()
+            .and_then(|journal| journal.recovery().cloned())
     }
 
-    #[allow(clippy::expect_used)]
-    fn url(&self, path: &str) -> reqwest::Url {
-        let base = Url::parse(&self.base_url).expect("valid OMNARA_API_URL");
-        base.join(path).expect("valid API path")
+    /// Take the recovery once it is recovered or discarded, so it is not
+    /// offered again.
+    pub fn take_recovery(&self) -> Option<Recovery> {
+        self.journal
+            .lock()
+            .ok()
+            .and_then(|mut journal| journal.take_recovery())
     }
 
-    fn auth(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
-        req.bearer_auth(&self.api_key)
+    /// Whether a remote message was already handed off, possibly before a
+    /// restart.
+    fn was_acknowledged(&self, message_id: &str) -> bool {
+        self.acks
+            .lock()
+            .map(|acks| acks.is_processed(message_id))
+            .unwrap_or(false)
     }
 
-    /// Set the last-read message id used when polling for pending messages.
-    pub fn set_last_read_message_id(&self, id: String) {
-        if let Ok(mut guard) = self.last_agent_message_id.lock() {
-            *guard = Some(id);
+    /// Durably record a remote message as handed off (or deliberately
+    /// dropped), once that has happened.
+    fn acknowledge(&self, message_id: &str) {
+        if let Ok(mut acks) = self.acks.lock() {
+            acks.mark_processed(message_id);
+        }
+    }
+
+    /// Tag for a local user message: a fresh id, remembered so its echo can
+    /// be recognized.
+    fn remember_origin(&self) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        if let Ok(mut origins) = self.sent_origins.lock() {
+            if origins.len() == MAX_ORIGIN_IDS {
+                origins.pop_front();
+            }
+            origins.push_back(id.clone());
         }
+        id
+    }
+
+    /// Whether a polled message is one this client posted itself.
+    fn is_own_message(&self, client_message_id: &str) -> bool {
+        self.sent_origins
+            .lock()
+            .map(|origins| origins.iter().any(|id| id == client_message_id))
+            .unwrap_or(false)
     }
 
     /// Send an agent message. When `requires_user_input` is false, the returned
-    /// message id is recorded as the last agent message id.
+    /// message id is recorded as the last agent message id, and the note is
+    /// journaled until the send succeeds.
     pub async fn send_agent_message(
         &self,
         content: &str,
         requires_user_input: bool,
+    ) -> crate::error::Result<String> {
+        self.note_activity();
+        let seq = (!requires_user_input)
+            .then(|| self.journal.lock().ok().map(|mut j| j.begin_send(content)))
+            .flatten();
+        let result = self
+            .post_agent_message(content, requires_user_input, Notifications::Default)
+            .await;
+        if let Some(seq) = seq
+            && result.is_ok()
+            && let Ok(mut journal) = self.journal.lock()
+        {
+            journal.finish_send(seq);
+        }
+        result
+    }
+
+    /// Send an approval prompt (a message requiring input), asking the server
+    /// to send a push notification or explicitly not to.
+    pub async fn send_approval_request(
+        &self,
+        content: &str,
+        push: bool,
+    ) -> crate::error::Result<String> {
+        self.note_activity();
+        self.post_agent_message(content, true, Notifications::Push(push))
+            .await
+    }
+
+    /// Send a message requiring input with push, email, and SMS
+    /// notifications requested, for prompts that have gone unanswered.
+    pub async fn send_urgent_agent_message(&self, content: &str) -> crate::error::Result<String> {
+        self.note_activity();
+        self.post_agent_message(content, true, Notifications::Urgent)
+            .await
+    }
+
+    /// Send a note with push, email, and SMS notifications requested, for
+    /// sessions that need attention while the agent keeps working.
+    pub async fn send_urgent_note(&self, content: &str) -> crate::error::Result<String> {
+        self.note_activity();
+        self.post_agent_message(content, false, Notifications::Urgent)
+            .await
+    }
+
+    /// Send an agent message without counting it as session activity.
+    async fn post_agent_message(
+        &self,
+        content: &str,
+        requires_user_input: bool,
+        notify: Notifications,
+    ) -> crate::error::Result<String> {
+        if !requires_user_input && !self.circuit_allows() {
+            self.queue_message(content, notify);
+            return Err(crate::error::CodexErr::OmnaraUnavailable);
+        }
+        let result = self
+            .try_post_agent_message(content, requires_user_input, notify)
+            .await;
+        self.metrics.record_send(MessageKind::Agent, result.is_ok());
+        result
+    }
+
+    async fn try_post_agent_message(
+        &self,
+        content: &str,
+        requires_user_input: bool,
+        notify: Notifications,
     ) -> crate::error::Result<String> {
         debug!(content_len = content.len(), requires_user_input, session_id = %self.session_id, "Omnara send_agent_message: begin");
+        let kind = if requires_user_input {
+            OmnaraMessageKind::Prompt
+        } else {
+            OmnaraMessageKind::Note
+        };
+        let content = &self.outbound(kind, content);
+        let _in_flight = self.in_flight.enter();
         // Compute git diff if changed; include when present.
         let git_diff = if let Some(g) = &self.git {
             if let Ok(mut guard) = g.lock() {
@@ -224,6 +2076,24 @@ impl OmnaraClient {
         } else {
             None
         };
+        if let Some(diff) = &git_diff
+            && !diff.truncated_files.is_empty()
+        {
+            self.append_log(&format!(
+                "[Git] diff omitted for oversized files: {}\n",
+                diff.truncated_files.join(", ")
+            ));
+        }
+        let git_diff = git_diff.map(|diff| self.withheld.redact_diff(&diff.text));
+
+        // Metadata rides along until a send succeeds; legacy servers only
+        // take the message text.
+        let session_metadata = self
+            .session_metadata
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+            .filter(|_| self.supports(Capability::StructuredMessages));
 
         let body = AgentMessageRequest {
             agent_instance_id: &self.session_id.to_string(),
@@ -231,9 +2101,11 @@ impl OmnaraClient {
             requires_user_input,
             agent_type: Some("codex"),
             git_diff: git_diff.as_deref(),
-            send_push: None,
-            send_email: None,
-            send_sms: None,
+            session_metadata: session_metadata.as_ref(),
+            message_metadata: self.next_message_metadata(),
+            send_push: notify.push(),
+            send_email: notify.urgent(),
+            send_sms: notify.urgent(),
         };
 
         let url = self.url("/api/v1/messages/agent");
@@ -245,7 +2117,9 @@ impl OmnaraClient {
             requires_user_input,
             content
         ));
-        let resp = self.auth(self.http.post(url)).json(&body).send().await?;
+        let resp = self
+            .send_json(self.auth(self.http.post(url)), &body, "send_agent_message")
+            .await?;
 
         let status = resp.status();
         debug!(status = %status, "Omnara send_agent_message: response status");
@@ -263,10 +2137,17 @@ impl OmnaraClient {
         ));
         info!(message_id = %parsed.message_id, requires_user_input, "Omnara send_agent_message: success");
 
-        if !requires_user_input && let Ok(mut guard) = self.last_agent_message_id.lock() {
-            *guard = Some(parsed.message_id.clone());
+        if session_metadata.is_some()
+            && let Ok(mut guard) = self.session_metadata.lock()
+        {
+            *guard = None;
+        }
+
+        if !requires_user_input {
+            self.persist_last_read(&parsed.message_id);
+            self.track_last_message(parsed.message_id.clone());
             debug!(
-                last_agent_message_id = guard.as_deref().unwrap_or("<none>"),
+                last_agent_message_id = %parsed.message_id,
                 "Updated last_agent_message_id"
             );
         }
@@ -274,7 +2155,30 @@ impl OmnaraClient {
         Ok(parsed.message_id)
     }
 
-    /// Request user input for the last recorded agent message id.
+    /// Agent `content` of `kind` as posted: transformed, then converted to
+    /// the markdown dialect.
+    fn outbound(&self, kind: OmnaraMessageKind, content: &str) -> String {
+        convert_markdown(
+            &self.transformers.apply(kind, content),
+            self.markdown_dialect,
+        )
+    }
+
+    /// Number and timestamp the next message, unless the server only takes
+    /// the message text.
+    fn next_message_metadata(&self) -> Option<MessageMetadata> {
+        if !self.supports(Capability::StructuredMessages) {
+            return None;
+        }
+        Some(MessageMetadata {
+            sequence: self.next_sequence.fetch_add(1, Ordering::SeqCst),
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+        })
+    }
+
+    /// Request user input for the last recorded agent message id. Prefer
+    /// [`Self::request_user_input`] with the id the send returned; this
+    /// one can only see messages whose send has already finished.
     pub async fn request_user_input_for_last_message(&self) -> crate::error::Result<()> {
         let last_id = self
             .last_agent_message_id
@@ -287,26 +2191,106 @@ impl OmnaraClient {
             debug!("request_user_input: no last_agent_message_id set (noop)");
             return Ok(());
         };
+        self.request_user_input(&message_id).await
+    }
 
+    /// Request user input on `message_id`, as returned by
+    /// [`Self::send_agent_message`]. A message the server has not persisted
+    /// yet answers 404, so that, 409, server errors, and network errors are
+    /// retried with backoff a few times before giving up.
+    pub async fn request_user_input(&self, message_id: &str) -> crate::error::Result<()> {
+        let _in_flight = self.in_flight.enter();
         info!(message_id = %message_id, "request_user_input: begin");
         let url = self.url(&format!("/api/v1/messages/{message_id}/request-input"));
-        info!(url = %url, "request_user_input: PATCH");
-        self.append_log(&format!(
-            "--- REQUEST USER INPUT ---\nTime: {}\nURL: {}\nMessage: last_agent_message\n\n",
-            chrono::Utc::now().to_rfc3339(),
-            url
-        ));
-        let resp = self.auth(self.http.patch(url)).send().await?;
+        let mut delay = REQUEST_INPUT_RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            info!(url = %url, attempt, "request_user_input: PATCH");
+            self.append_log(&format!(
+                "--- REQUEST USER INPUT ---\nTime: {}\nURL: {}\nMessage: {}\nAttempt: {}\n\n",
+                chrono::Utc::now().to_rfc3339(),
+                url,
+                message_id,
+                attempt
+            ));
+            let last = attempt == REQUEST_INPUT_ATTEMPTS;
+            match self
+                .send_traced(
+                    self.auth(self.http.patch(url.clone())),
+                    "request_user_input",
+                )
+                .await
+            {
+                Ok(resp) if resp.status().is_success() => {
+                    info!("request_user_input: success");
+                    self.append_log("\u{2713} Request user input updated successfully\n\n");
+                    return Ok(());
+                }
+                Ok(resp) => {
+                    let status = resp.status();
+                    let text = resp.text().await.unwrap_or_default();
+                    self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
+                    let retryable = status == reqwest::StatusCode::NOT_FOUND
+                        || status == reqwest::StatusCode::CONFLICT
+                        || status.is_server_error();
+                    if last || !retryable {
+                        error!(status = %status, body = %text, "request_user_input: error");
+                        return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
+                    }
+                    warn!(status = %status, attempt, "request_user_input: retrying");
+                }
+                Err(crate::error::CodexErr::OmnaraUnavailable) => {
+                    return Err(crate::error::CodexErr::OmnaraUnavailable);
+                }
+                Err(err) => {
+                    self.append_log(&format!("Request failed: {err}\n\n"));
+                    if last {
+                        error!(error = %err, "request_user_input: error");
+                        return Err(err);
+                    }
+                    warn!(error = %err, attempt, "request_user_input: network error; retrying");
+                }
+            }
+            self.clock().sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    /// Replace the content of a posted agent message
+    /// (PATCH /api/v1/messages/{id}), e.g. to refresh a progress note in
+    /// place. A no-op on servers without [`Capability::MessageUpdates`].
+    pub async fn update_agent_message(
+        &self,
+        message_id: &str,
+        content: &str,
+    ) -> crate::error::Result<()> {
+        #[derive(Serialize)]
+        struct UpdateReq<'a> {
+            content: &'a str,
+        }
+        if !self.supports(Capability::MessageUpdates) {
+            debug!("Omnara update_agent_message: server does not support updates; skipped");
+            return Ok(());
+        }
+        let _in_flight = self.in_flight.enter();
+        let content = &self.outbound(OmnaraMessageKind::Update, content);
+        let url = self.url(&format!("/api/v1/messages/{message_id}"));
+        debug!(url = %url, content_len = content.len(), "Omnara update_agent_message: PATCH");
+        let resp = self
+            .send_json(
+                self.auth(self.http.patch(url)),
+                &UpdateReq { content },
+                "update_agent_message",
+            )
+            .await?;
         let status = resp.status();
-        debug!(status = %status, "request_user_input: response status");
         if !status.is_success() {
             let text = resp.text().await.unwrap_or_default();
-            error!(status = %status, body = %text, "request_user_input: error");
+            error!(status = %status, body = %text, "Omnara update_agent_message: error");
             self.append_log(&format!("Response Status: {status}\nBody: {text}\n\n"));
             return Err(crate::error::CodexErr::UnexpectedStatus(status, text));
         }
-        info!("request_user_input: success");
-        self.append_log("\u{2713} Request user input updated successfully\n\n");
         Ok(())
     }
 
@@ -318,11 +2302,12 @@ impl OmnaraClient {
     where
         F: FnMut(String) + Send + 'static,
     {
-        // Cancel any existing poller.
+        // Cancel any existing poller; starting to wait for input counts as activity.
         self.cancel_polling();
+        self.note_activity();
         info!("Omnara polling: start");
         self.append_log(&format!(
-            "--- STARTING POLLING FOR USER RESPONSE ---\nTime: {}\nURL: {}\nTimeout: 24 hours (5-second intervals)\n\n",
+            "--- STARTING POLLING FOR USER RESPONSE ---\nTime: {}\nURL: {}\nTimeout: 24 hours ({}ms intervals, {}ms when idle)\n\n",
             chrono::Utc::now().to_rfc3339(),
             {
                 let last_read = self
@@ -335,7 +2320,9 @@ impl OmnaraClient {
                     "{}/api/v1/messages/pending?agent_instance_id={}&last_read_message_id={}",
                     self.base_url, self.session_id, last_read
                 )
-            }
+            },
+            self.poll_policy.fast_interval.as_millis(),
+            self.poll_policy.idle_interval.as_millis(),
         ));
 
         let cancel = CancellationToken::new();
@@ -352,11 +2339,14 @@ impl OmnaraClient {
             .and_then(|g| g.clone());
 
         let http = self.http.clone();
+        let poll_policy = self.poll_policy;
+        let activity = self.activity.clone();
+        let clock = self.clock();
+        let client = self.clone();
 
         let handle = tokio::spawn(async move {
-            let poll_interval_secs = 5u64;
             let timeout_secs = 24 * 60 * 60u64; // 24h
-            let start = std::time::Instant::now();
+            let start = clock.now();
             let last_id = last_read;
 
             loop {
@@ -365,11 +2355,18 @@ impl OmnaraClient {
                     break;
                 }
 
-                if start.elapsed().as_secs() > timeout_secs {
+                if clock.elapsed(start).as_secs() > timeout_secs {
                     info!("Omnara polling: timeout reached (24h) ending");
                     break;
                 }
 
+                if poll_policy.should_suspend(activity.elapsed()) {
+                    if client.suspend_until_activity(&cancel_child).await {
+                        continue;
+                    }
+                    break;
+                }
+
                 // Build request
                 #[allow(clippy::expect_used)]
                 let url = Url::parse(&base_url)
@@ -383,18 +2380,23 @@ impl OmnaraClient {
                     req = req.query(&[("last_read_message_id", id.clone())]);
                 }
                 trace!(url = %url, last_read = ?last_id, "Omnara polling: GET pending");
-                match req.send().await {
+                match client.send_traced(req, "poll").await {
                     Ok(resp) if resp.status().is_success() => {
                         let status = resp.status();
                         debug!(status = %status, "Omnara polling: response status");
-                        let Ok(pending) = resp.json::<PendingMessagesResponse>().await else {
+                        let pending = resp.json::<PendingMessagesResponse>().await;
+                        client.metrics.record_poll(pending.is_ok());
+                        let Ok(pending) = pending else {
                             // Malformed JSON, wait and retry
                             warn!("Omnara polling: invalid JSON response; retrying");
-                            tokio::time::sleep(std::time::Duration::from_secs(poll_interval_secs))
+                            clock
+                                .sleep(poll_policy.interval_after(activity.elapsed()))
                                 .await;
                             continue;
                         };
 
+                        client.note_read_receipts(&pending.read_message_ids);
+                        let reacted = client.note_reactions(pending.reactions);
                         if pending.status == "stale" {
                             // Another reader consumed messages; stop.
                             info!("Omnara polling: stale status; exiting");
@@ -422,25 +2424,92 @@ impl OmnaraClient {
                                 count = pending.messages.len(),
                                 "Omnara polling: messages received"
                             );
+                            let mut delivered = 0;
                             for m in pending.messages {
-                                on_message(m.content);
+                                // Dedupe on the persisted ids, and record a
+                                // message only after the hand-off: a crash in
+                                // between redelivers it instead of losing it.
+                                if let Some(id) = m.id.as_deref()
+                                    && client.was_acknowledged(id)
+                                {
+                                    info!(
+                                        message_id = id,
+                                        "Omnara polling: skipping redelivered message"
+                                    );
+                                    continue;
+                                }
+                                let content = if let Some(origin) = m.client_message_id.as_deref()
+                                    && client.is_own_message(origin)
+                                {
+                                    debug!(
+                                        client_message_id = origin,
+                                        "Omnara polling: skipping echo of a local message"
+                                    );
+                                    None
+                                } else {
+                                    match client
+                                        .with_voice_transcripts(m.content, &m.attachments)
+                                        .await
+                                    {
+                                        Some(content) => {
+                                            client
+                                                .with_uploaded_files(content, &m.attachments)
+                                                .await
+                                        }
+                                        None => None,
+                                    }
+                                };
+                                let handed_off = content.is_some();
+                                if let Some(content) = content {
+                                    on_message(content);
+                                    delivered += 1;
+                                }
+                                let Some(id) = m.id.as_deref() else {
+                                    continue;
+                                };
+                                client.acknowledge(id);
+                                if handed_off && let Err(e) = client.send_read_receipt(id).await {
+                                    debug!(
+                                        message_id = id,
+                                        "Omnara polling: read receipt failed: {e}"
+                                    );
+                                }
+                            }
+                            if delivered > 0 {
+                                info!("Omnara polling: delivered messages; exiting");
+                                break;
                             }
-                            info!("Omnara polling: delivered messages; exiting");
+                        }
+                        if reacted {
+                            // A reaction answers like a message does.
+                            info!("Omnara polling: reaction received; exiting");
                             break;
                         }
                     }
                     Ok(resp) => {
+                        client.metrics.record_poll(false);
                         let status = resp.status();
                         warn!(status = %status, "Omnara polling: non-success status; retrying");
                     }
+                    Err(crate::error::CodexErr::OmnaraUnavailable) => {
+                        trace!("Omnara polling: paused while the server is unreachable");
+                    }
                     Err(_) => {
+                        client.metrics.record_poll(false);
                         warn!("Omnara polling: network error; retrying");
                     }
                 }
 
+                // Back off while idle, but wake early when new activity arrives.
+                let interval = poll_policy.interval_after(activity.elapsed());
+                trace!(
+                    interval_ms = interval.as_millis() as u64,
+                    "Omnara polling: sleeping"
+                );
                 tokio::select! {
                     _ = cancel_child.cancelled() => break,
-                    _ = tokio::time::sleep(std::time::Duration::from_secs(poll_interval_secs)) => {}
+                    _ = activity.notify.notified() => {}
+                    _ = clock.sleep(interval) => {}
                 }
             }
         });
@@ -451,6 +2520,254 @@ impl OmnaraClient {
         }
     }
 
+    /// `content` followed by the transcript of each voice note among
+    /// `attachments`. A voice note that cannot be transcribed is left out
+    /// and a note tells the dashboard why; `None` when that leaves nothing
+    /// to deliver. Without a transcriber `content` is returned as is.
+    async fn with_voice_transcripts(
+        &self,
+        content: String,
+        attachments: &[PendingAttachment],
+    ) -> Option<String> {
+        let Some(transcriber) = self.transcriber.as_deref() else {
+            return Some(content);
+        };
+        let voice_notes: Vec<&PendingAttachment> = attachments
+            .iter()
+            .filter(|attachment| is_audio(&attachment.mime_type))
+            .collect();
+        if voice_notes.is_empty() {
+            return Some(content);
+        }
+        let mut transcripts = Vec::new();
+        for voice_note in voice_notes {
+            let filename = voice_note.filename.as_deref().unwrap_or("voice-note");
+            let transcript = match self.download_voice_note(transcriber, voice_note).await {
+                Ok(audio) => {
+                    transcriber
+                        .transcribe(&self.http, audio, filename, &voice_note.mime_type)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match transcript {
+                Ok(transcript) => {
+                    info!(
+                        chars = transcript.len(),
+                        "Omnara polling: voice note transcribed"
+                    );
+                    self.append_log(&format!("[Voice] transcribed {filename}\n"));
+                    transcripts.push(transcript);
+                }
+                Err(e) => {
+                    warn!("Omnara polling: voice note not transcribed: {e}");
+                    self.append_log(&format!("[Voice] {filename} not transcribed: {e}\n"));
+                    let _ = self
+                        .post_agent_message(
+                            &format!("🎙️ Could not transcribe the voice note: {e}"),
+                            false,
+                            Notifications::Default,
+                        )
+                        .await;
+                }
+            }
+        }
+        let input = voice_input(&content, &transcripts);
+        (!input.is_empty()).then_some(input)
+    }
+
+    /// Download `voice_note`, refusing one larger than `transcriber`
+    /// accepts. Server-relative URLs, and URLs on the server, are fetched
+    /// with the API key.
+    async fn download_voice_note(
+        &self,
+        transcriber: &Transcriber,
+        voice_note: &PendingAttachment,
+    ) -> Result<Vec<u8>, TranscriptionError> {
+        let base = self.url("/");
+        let url = base
+            .join(&voice_note.url)
+            .map_err(|_| TranscriptionError::InvalidUrl(voice_note.url.clone()))?;
+        let mut req = self.http.get(url.clone());
+        if url.origin() == base.origin() {
+            req = self.auth(req);
+        }
+        let mut resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(TranscriptionError::Download(resp.status()));
+        }
+        let limit = transcriber.max_audio_bytes();
+        if resp
+            .content_length()
+            .is_some_and(|length| length as usize > limit)
+        {
+            return Err(transcriber.too_large());
+        }
+        let mut audio = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if audio.len() + chunk.len() > limit {
+                return Err(transcriber.too_large());
+            }
+            audio.extend_from_slice(&chunk);
+        }
+        Ok(audio)
+    }
+
+    /// `content` followed by a line naming each file among `attachments`
+    /// saved into the uploads directory, with a note on the dashboard for
+    /// every file saved or refused. Voice notes are left to the
+    /// transcriber when there is one. `None` when nothing is left to
+    /// deliver; without uploads `content` is returned as is.
+    async fn with_uploaded_files(
+        &self,
+        content: String,
+        attachments: &[PendingAttachment],
+    ) -> Option<String> {
+        let Some(uploads) = self.uploads.clone() else {
+            return Some(content);
+        };
+        let files: Vec<&PendingAttachment> = attachments
+            .iter()
+            .filter(|attachment| !(self.transcriber.is_some() && is_audio(&attachment.mime_type)))
+            .collect();
+        if files.is_empty() {
+            return Some(content);
+        }
+        let mut saved = Vec::new();
+        for file in files {
+            let filename = safe_filename(file.filename.as_deref());
+            let result = match self.download_upload(&uploads, file).await {
+                Ok(contents) => {
+                    let uploads = uploads.clone();
+                    let name = file.filename.clone();
+                    tokio::task::spawn_blocking(move || uploads.save(name.as_deref(), &contents))
+                        .await
+                        .unwrap_or_else(|e| Err(std::io::Error::other(e).into()))
+                }
+                Err(e) => Err(e),
+            };
+            let note = match result {
+                Ok(file) => {
+                    info!(bytes = file.bytes, "Omnara polling: attachment saved");
+                    self.append_log(&format!("[Upload] saved {}\n", file.display_path));
+                    let note = format_saved_note(&file);
+                    saved.push(file);
+                    note
+                }
+                Err(e) => {
+                    warn!("Omnara polling: attachment not saved: {e}");
+                    self.append_log(&format!("[Upload] {filename} not saved: {e}\n"));
+                    format!("📎 Could not save `{filename}`: {e}")
+                }
+            };
+            let _ = self
+                .post_agent_message(&note, false, Notifications::Default)
+                .await;
+        }
+        let input = if saved.is_empty() {
+            content
+        } else {
+            upload_input(&content, &saved)
+        };
+        (!input.trim().is_empty()).then_some(input)
+    }
+
+    /// Download `file`, refusing one larger than `uploads` accepts.
+    /// Server-relative URLs, and URLs on the server, are fetched with the
+    /// API key.
+    async fn download_upload(
+        &self,
+        uploads: &Uploads,
+        file: &PendingAttachment,
+    ) -> Result<Vec<u8>, UploadError> {
+        let base = self.url("/");
+        let url = base
+            .join(&file.url)
+            .map_err(|_| UploadError::InvalidUrl(file.url.clone()))?;
+        let mut req = self.http.get(url.clone());
+        if url.origin() == base.origin() {
+            req = self.auth(req);
+        }
+        let mut resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(UploadError::Download(resp.status()));
+        }
+        let limit = uploads.max_bytes();
+        if resp
+            .content_length()
+            .is_some_and(|length| length as usize > limit)
+        {
+            return Err(uploads.too_large());
+        }
+        let mut contents = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if contents.len() + chunk.len() > limit {
+                return Err(uploads.too_large());
+            }
+            contents.extend_from_slice(&chunk);
+        }
+        Ok(contents)
+    }
+
+    /// Idle suspension for the poller: post a "session idle" note and wait for
+    /// the next activity (a local key press, a send, or an external push via
+    /// [`Self::note_activity`]). Returns true when polling should resume, false
+    /// when cancelled or when the session was ended for inactivity.
+    async fn suspend_until_activity(&self, cancel: &CancellationToken) -> bool {
+        // Register before sending the note so activity during the send still wakes us.
+        let resumed = self.activity.notify.notified();
+        tokio::pin!(resumed);
+        resumed.as_mut().enable();
+
+        let idle_for = self.activity.elapsed();
+        info!(
+            idle_secs = idle_for.as_secs(),
+            "Omnara polling: idle; suspending"
+        );
+        self.append_log(&format!(
+            "[Idle] no activity for {}s; polling suspended\n",
+            idle_for.as_secs()
+        ));
+        let mut note = format!(
+            "💤 Session idle for {} min — remote polling paused. It resumes as soon as there is activity in the terminal.",
+            idle_for.as_secs() / 60
+        );
+        if let Some(end_after) = self.poll_policy.end_session_after {
+            note.push_str(&format!(
+                " The session ends after {}h without activity.",
+                end_after.as_secs() / 3600
+            ));
+        }
+        let _ = self
+            .post_agent_message(&note, false, Notifications::Default)
+            .await;
+
+        let end_in = self
+            .poll_policy
+            .end_session_after
+            .map(|end_after| end_after.saturating_sub(self.activity.elapsed()));
+        let end_timer = async {
+            match end_in {
+                Some(end_in) => self.activity.clock.sleep(end_in).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = cancel.cancelled() => false,
+            _ = &mut resumed => {
+                info!("Omnara polling: activity; resuming");
+                self.append_log("[Idle] activity; polling resumed\n");
+                true
+            }
+            _ = end_timer => {
+                info!("Omnara polling: idle limit reached; ending session");
+                self.append_log("[Idle] idle limit reached; ending session\n");
+                let _ = self.end_session().await;
+                false
+            }
+        }
+    }
+
     /// Cancel any active polling task.
     pub fn cancel_polling(&self) {
         if let Ok(mut state) = self.poller.lock() {
@@ -476,6 +2793,16 @@ impl OmnaraClient {
         path
     }
 
+    /// Record an approval lifecycle step (sent, escalated, answered, default
+    /// applied) in the session log under an `[Audit]` marker.
+    pub fn audit_approval(&self, approval_id: &str, step: &str) {
+        info!(approval_id, step, "Omnara approval audit");
+        self.append_log(&format!(
+            "[Audit] {} approval {approval_id}: {step}\n",
+            chrono::Utc::now().to_rfc3339()
+        ));
+    }
+
     /// Append a line to the per-session wrapper log file.
     pub fn append_log(&self, text: &str) {
         if let Ok(mut f) = std::fs::OpenOptions::new()
@@ -488,3 +2815,80 @@ impl OmnaraClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn repo_slugs_come_from_ssh_and_https_remotes() {
+        assert_eq!(
+            repo_slug("git@github.com:omnara-ai/codex.git").as_deref(),
+            Some("omnara-ai/codex")
+        );
+        assert_eq!(
+            repo_slug("https://github.com/omnara-ai/codex/").as_deref(),
+            Some("omnara-ai/codex")
+        );
+        assert_eq!(repo_slug("git@host:codex").as_deref(), Some("codex"));
+    }
+
+    #[test]
+    fn poll_policy_backs_off_after_fast_window() {
+        let policy = PollPolicy::from(&OmnaraConfig {
+            poll_interval_ms: 1_000,
+            idle_poll_interval_ms: 20_000,
+            fast_poll_window_ms: 30_000,
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.interval_after(Duration::from_secs(0)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            policy.interval_after(Duration::from_secs(29)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            policy.interval_after(Duration::from_secs(30)),
+            Duration::from_secs(20)
+        );
+    }
+
+    #[test]
+    fn idle_interval_never_polls_faster_than_fast_interval() {
+        let policy = PollPolicy::from(&OmnaraConfig {
+            poll_interval_ms: 5_000,
+            idle_poll_interval_ms: 1_000,
+            fast_poll_window_ms: 0,
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.interval_after(Duration::from_secs(60)),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn idle_suspend_is_configured_in_minutes_and_zero_disables_it() {
+        let policy = PollPolicy::from(&OmnaraConfig {
+            idle_suspend_minutes: 10,
+            idle_end_session_hours: 8,
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.end_session_after,
+            Some(Duration::from_secs(8 * 3600))
+        );
+        assert!(!policy.should_suspend(Duration::from_secs(599)));
+        assert!(policy.should_suspend(Duration::from_secs(600)));
+
+        let never = PollPolicy::from(&OmnaraConfig {
+            idle_suspend_minutes: 0,
+            ..Default::default()
+        });
+        assert_eq!(never.end_session_after, None);
+        assert!(!never.should_suspend(Duration::from_secs(24 * 3600)));
+    }
+}
@@ -0,0 +1,166 @@
+//! Time source for the Omnara client and bridge.
+//!
+//! Polling intervals and idle backoff, idle suspension and the end-of-session
+//! limit, the poller's 24h timeout, approval escalation, and the bridge's
+//! progress and turn timings all read the time and sleep through a [`Clock`]
+//! (see [`OmnaraClient::with_clock`](crate::omnara_client::OmnaraClient::with_clock)).
+//!
+//! The default [`TokioClock`] follows tokio's clock, so tests without network
+//! traffic can use `tokio::time::pause`. Tests against a mock server inject a
+//! [`ManualClock`] instead: HTTP requests keep running in real time while the
+//! client's timers only fire when the test advances the clock.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Complete once `duration` has passed on this clock.
+    fn sleep(&self, duration: Duration) -> Sleep;
+
+    /// Complete once this clock reaches `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> Sleep {
+        self.sleep(deadline.saturating_duration_since(self.now()))
+    }
+
+    /// Time since `earlier` on this clock.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// tokio's clock; `tokio::time::pause` and `advance` control it in tests.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// A clock that only moves when [`ManualClock::advance`] is called.
+#[derive(Debug)]
+pub struct ManualClock {
+    state: Mutex<ManualState>,
+}
+
+#[derive(Debug)]
+struct ManualState {
+    now: Instant,
+    sleepers: Vec<(Instant, oneshot::Sender<()>)>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(ManualState {
+                now: Instant::now(),
+                sleepers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Move the clock forward, waking every sleep that is now due.
+    pub fn advance(&self, duration: Duration) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.now += duration;
+        let now = state.now;
+        let (due, waiting) = std::mem::take(&mut state.sleepers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(deadline, _)| *deadline <= now);
+        state.sleepers = waiting;
+        for (_, wake) in due {
+            let _ = wake.send(());
+        }
+    }
+
+    /// Sleeps still waiting to come due; lets a test wait until a timer is
+    /// armed before advancing past it.
+    pub fn pending_sleeps(&self) -> usize {
+        self.state
+            .lock()
+            .map(|state| {
+                state
+                    .sleepers
+                    .iter()
+                    .filter(|(_, wake)| !wake.is_closed())
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.state
+            .lock()
+            .map(|state| state.now)
+            .unwrap_or_else(|_| Instant::now())
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        if duration.is_zero() {
+            return Box::pin(std::future::ready(()));
+        }
+        let (wake, woken) = oneshot::channel();
+        if let Ok(mut state) = self.state.lock() {
+            let deadline = state.now + duration;
+            state.sleepers.push((deadline, wake));
+        }
+        Box::pin(async move {
+            let _ = woken.await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn manual_sleeps_wake_in_deadline_order() {
+        let clock = ManualClock::new();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for secs in [30, 10, 20] {
+            let sleep = clock.sleep(Duration::from_secs(secs));
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                sleep.await;
+                let _ = tx.send(secs);
+            });
+        }
+        assert_eq!(clock.pending_sleeps(), 3);
+
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(rx.recv().await, Some(10));
+        assert_eq!(clock.pending_sleeps(), 2);
+
+        clock.advance(Duration::from_secs(15));
+        let mut woken = vec![rx.recv().await, rx.recv().await];
+        woken.sort();
+        assert_eq!(woken, vec![Some(20), Some(30)]);
+        assert_eq!(clock.pending_sleeps(), 0);
+    }
+}
@@ -0,0 +1,213 @@
+//! Error and diagnostics notes: stream retries, turn errors, and sandbox
+//! failures that would otherwise only show in the terminal.
+//!
+//! Frontends turn core events into a [`Diagnostic`] with
+//! [`diagnostic_for_event`] and post [`format_diagnostic_note`] when the
+//! [`DiagnosticLimiter`] admits it, so a flapping connection does not flood
+//! the dashboard.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::protocol::EventMsg;
+
+/// Diagnostics of one kind posted per [`DIAGNOSTIC_WINDOW`]; the rest are
+/// counted and reported with the next one admitted.
+pub const DIAGNOSTIC_BURST: usize = 3;
+pub const DIAGNOSTIC_WINDOW: Duration = Duration::from_secs(60);
+
+/// Longest error detail quoted in a note, in characters.
+const MAX_DETAIL_CHARS: usize = 500;
+
+/// Prefix of the background event core emits when a sandboxed command fails.
+const SANDBOX_FAILURE_PREFIX: &str = "Execution failed: ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    Stream,
+    Error,
+    Sandbox,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The model stream dropped and core is retrying; `attempt` is
+    /// `(attempt, max_attempts)` when core reported it.
+    StreamRetry {
+        attempt: Option<(u64, u64)>,
+        detail: String,
+    },
+    /// The turn ended in an error.
+    Error(String),
+    /// A command failed in the sandbox; core asks to retry it without one.
+    SandboxFailure(String),
+}
+
+impl Diagnostic {
+    pub fn kind(&self) -> DiagnosticKind {
+        match self {
+            Diagnostic::StreamRetry { .. } => DiagnosticKind::Stream,
+            Diagnostic::Error(_) => DiagnosticKind::Error,
+            Diagnostic::SandboxFailure(_) => DiagnosticKind::Sandbox,
+        }
+    }
+}
+
+/// The diagnostic `msg` reports, if any.
+pub fn diagnostic_for_event(msg: &EventMsg) -> Option<Diagnostic> {
+    match msg {
+        EventMsg::StreamError(ev) => Some(parse_stream_error(&ev.message)),
+        EventMsg::Error(ev) => Some(Diagnostic::Error(ev.message.clone())),
+        EventMsg::BackgroundEvent(ev) => ev
+            .message
+            .strip_prefix(SANDBOX_FAILURE_PREFIX)
+            .map(|detail| Diagnostic::SandboxFailure(detail.to_string())),
+        _ => None,
+    }
+}
+
+/// Split core's `stream error: <e>; retrying N/M in <delay>…` message.
+fn parse_stream_error(message: &str) -> Diagnostic {
+    let message = message.strip_prefix("stream error: ").unwrap_or(message);
+    let Some((detail, retry)) = message.rsplit_once("; retrying ") else {
+        return Diagnostic::StreamRetry {
+            attempt: None,
+            detail: message.to_string(),
+        };
+    };
+    let attempt = retry
+        .split_whitespace()
+        .next()
+        .and_then(|count| count.split_once('/'))
+        .and_then(|(n, m)| Some((n.parse().ok()?, m.parse().ok()?)));
+    Diagnostic::StreamRetry {
+        attempt,
+        detail: detail.to_string(),
+    }
+}
+
+/// Dashboard note for `diagnostic`; `suppressed` counts the ones of its kind
+/// the limiter held back since the last note.
+pub fn format_diagnostic_note(diagnostic: &Diagnostic, suppressed: usize) -> String {
+    let (headline, detail) = match diagnostic {
+        Diagnostic::StreamRetry {
+            attempt: Some((n, m)),
+            detail,
+        } => (
+            format!("❗ Stream error, retrying (attempt {n}/{m})"),
+            detail,
+        ),
+        Diagnostic::StreamRetry {
+            attempt: None,
+            detail,
+        } => ("❗ Stream error, retrying".to_string(), detail),
+        Diagnostic::Error(detail) => ("❗ Turn failed".to_string(), detail),
+        Diagnostic::SandboxFailure(detail) => {
+            ("❗ Command failed in the sandbox".to_string(), detail)
+        }
+    };
+    let mut note = headline;
+    let detail = truncate_detail(detail.trim());
+    if !detail.is_empty() {
+        note.push_str("\n\n");
+        let quoted = detail
+            .lines()
+            .map(|line| format!("> {line}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        note.push_str(&quoted);
+    }
+    match suppressed {
+        0 => {}
+        1 => note.push_str("\n\n_1 similar diagnostic was not posted_"),
+        n => note.push_str(&format!("\n\n_{n} similar diagnostics were not posted_")),
+    }
+    note
+}
+
+fn truncate_detail(detail: &str) -> String {
+    match detail.char_indices().nth(MAX_DETAIL_CHARS) {
+        Some((end, _)) => format!("{}…", &detail[..end]),
+        None => detail.to_string(),
+    }
+}
+
+/// Admits at most [`DIAGNOSTIC_BURST`] diagnostics of each kind per
+/// [`DIAGNOSTIC_WINDOW`].
+#[derive(Debug, Default)]
+pub struct DiagnosticLimiter {
+    /// When the admitted diagnostics of each kind were posted, oldest first.
+    posted: HashMap<DiagnosticKind, Vec<Instant>>,
+    suppressed: HashMap<DiagnosticKind, usize>,
+}
+
+impl DiagnosticLimiter {
+    /// Whether a diagnostic of `kind` may be posted at `now`; when it may,
+    /// how many of its kind were held back since the last one.
+    pub fn admit(&mut self, kind: DiagnosticKind, now: Instant) -> Option<usize> {
+        let posted = self.posted.entry(kind).or_default();
+        posted.retain(|at| now.duration_since(*at) < DIAGNOSTIC_WINDOW);
+        if posted.len() >= DIAGNOSTIC_BURST {
+            *self.suppressed.entry(kind).or_default() += 1;
+            return None;
+        }
+        posted.push(now);
+        Some(self.suppressed.remove(&kind).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::BackgroundEventEvent;
+    use crate::protocol::StreamErrorEvent;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn stream_errors_report_the_retry_attempt() {
+        let msg = EventMsg::StreamError(StreamErrorEvent {
+            message: "stream error: stream disconnected before completion; retrying 2/5 in 400ms…"
+                .to_string(),
+        });
+        let diagnostic = diagnostic_for_event(&msg).expect("diagnostic");
+        assert_eq!(
+            format_diagnostic_note(&diagnostic, 0),
+            "❗ Stream error, retrying (attempt 2/5)\n\n> stream disconnected before completion"
+        );
+        let sandbox = EventMsg::BackgroundEvent(BackgroundEventEvent {
+            message: "Execution failed: sandbox denied exec error".to_string(),
+        });
+        assert_eq!(
+            diagnostic_for_event(&sandbox),
+            Some(Diagnostic::SandboxFailure(
+                "sandbox denied exec error".to_string()
+            ))
+        );
+        let other = EventMsg::BackgroundEvent(BackgroundEventEvent {
+            message: "Connecting to MCP servers".to_string(),
+        });
+        assert_eq!(diagnostic_for_event(&other), None);
+    }
+
+    #[test]
+    fn limiter_holds_back_bursts_and_reports_them_later() {
+        let mut limiter = DiagnosticLimiter::default();
+        let start = Instant::now();
+        for _ in 0..DIAGNOSTIC_BURST {
+            assert_eq!(limiter.admit(DiagnosticKind::Stream, start), Some(0));
+        }
+        assert_eq!(limiter.admit(DiagnosticKind::Stream, start), None);
+        assert_eq!(limiter.admit(DiagnosticKind::Stream, start), None);
+        assert_eq!(limiter.admit(DiagnosticKind::Error, start), Some(0));
+        assert_eq!(
+            limiter.admit(DiagnosticKind::Stream, start + DIAGNOSTIC_WINDOW),
+            Some(2)
+        );
+        assert_eq!(
+            format_diagnostic_note(&Diagnostic::Error("boom".to_string()), 2),
+            "❗ Turn failed\n\n> boom\n\n_2 similar diagnostics were not posted_"
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Health checks behind `codex omnara doctor`.
+//!
+//! The probes are methods on the client
+//! ([`OmnaraClient::probe_server`](crate::omnara_client::OmnaraClient::probe_server),
+//! [`OmnaraClient::probe_session`](crate::omnara_client::OmnaraClient::probe_session),
+//! and [`OmnaraClient::doctor`](crate::omnara_client::OmnaraClient::doctor),
+//! which runs them all), so a frontend can show the same figures. This module
+//! turns their results into [`DoctorCheck`]s, each with a hint on what to do
+//! when it does not pass.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::error::CodexErr;
+
+/// Round trips timed for the latency figure; the median is reported.
+pub const LATENCY_SAMPLES: usize = 3;
+
+/// Median round trip above which the connection is reported as slow.
+const SLOW_LATENCY: Duration = Duration::from_millis(1500);
+
+/// Clock difference from the server above which the clock is reported as off.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+/// One authenticated round trip to the server's version endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerProbe {
+    pub latency: Duration,
+    pub status: StatusCode,
+    /// Server clock minus the local clock, from the response's `Date`
+    /// header; `None` without one.
+    pub clock_skew: Option<chrono::Duration>,
+}
+
+/// A throwaway session created with one agent message, polled once, and
+/// ended. Later steps are `None` when creating it failed.
+#[derive(Debug)]
+pub struct SessionProbe {
+    pub create: Result<Duration, CodexErr>,
+    pub poll: Option<Result<Duration, CodexErr>>,
+    pub end: Option<Result<(), CodexErr>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not run because an earlier check failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+
+    fn skipped(name: &'static str) -> Self {
+        Self::new(name, CheckStatus::Skipped, "skipped")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub server_url: String,
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// The checks for the probes' results. `session` is `None` when the
+    /// session probe was not run.
+    pub fn from_probes(
+        server_url: String,
+        server: &[Result<ServerProbe, CodexErr>],
+        session: Option<&SessionProbe>,
+    ) -> Self {
+        let probes: Vec<&ServerProbe> = server.iter().filter_map(|p| p.as_ref().ok()).collect();
+        let mut checks = vec![server_check(server, &probes)];
+        let key = api_key_check(&probes, session);
+        let key_failed = key.status == CheckStatus::Fail;
+        checks.push(key);
+        match session {
+            Some(session) if !key_failed => checks.extend(session_checks(session)),
+            _ => {
+                checks.push(DoctorCheck::skipped("Session"));
+                checks.push(DoctorCheck::skipped("Polling"));
+            }
+        }
+        checks.push(clock_check(&probes));
+        Self { server_url, checks }
+    }
+
+    /// Whether no check failed; warnings pass.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status != CheckStatus::Fail)
+    }
+
+    /// One line per check, followed by its hint.
+    pub fn format(&self) -> String {
+        let mut out = format!("Omnara doctor: {}\n", self.server_url);
+        for check in &self.checks {
+            let mark = match check.status {
+                CheckStatus::Pass => "✓",
+                CheckStatus::Warn => "!",
+                CheckStatus::Fail => "✗",
+                CheckStatus::Skipped => "-",
+            };
+            let _ = writeln!(out, "{mark} {:<8} {}", check.name, check.detail);
+            if let Some(hint) = &check.hint {
+                let _ = writeln!(out, "  → {hint}");
+            }
+        }
+        out
+    }
+}
+
+fn server_check(server: &[Result<ServerProbe, CodexErr>], probes: &[&ServerProbe]) -> DoctorCheck {
+    let mut latencies: Vec<Duration> = probes.iter().map(|probe| probe.latency).collect();
+    latencies.sort();
+    let Some(median) = latencies.get(latencies.len() / 2).copied() else {
+        let error = server
+            .iter()
+            .find_map(|probe| probe.as_ref().err())
+            .map_or_else(|| "not probed".to_string(), ToString::to_string);
+        return DoctorCheck::new("Server", CheckStatus::Fail, format!("unreachable: {error}"))
+            .with_hint(
+                "Check OMNARA_API_URL or `omnara.api_url`, your network, and the proxy in `[omnara.http]`",
+            );
+    };
+    let detail = format!(
+        "reachable, {} ms round trip (median of {})",
+        median.as_millis(),
+        latencies.len()
+    );
+    if let Some(probe) = probes.iter().find(|probe| probe.status.is_server_error()) {
+        return DoctorCheck::new(
+            "Server",
+            CheckStatus::Warn,
+            format!("{detail}; answered {}", probe.status),
+        )
+        .with_hint("The server reported an error; try again later");
+    }
+    if median > SLOW_LATENCY {
+        return DoctorCheck::new("Server", CheckStatus::Warn, format!("{detail}; slow")).with_hint(
+            "Slow round trips delay approvals and remote input; check your network or proxy",
+        );
+    }
+    DoctorCheck::new("Server", CheckStatus::Pass, detail)
+}
+
+fn api_key_check(probes: &[&ServerProbe], session: Option<&SessionProbe>) -> DoctorCheck {
+    let rejected_by = probes
+        .iter()
+        .map(|probe| probe.status)
+        .chain(session.and_then(|session| match &session.create {
+            Err(CodexErr::UnexpectedStatus(status, _)) => Some(*status),
+            _ => None,
+        }))
+        .find(|status| matches!(*status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN));
+    if let Some(status) = rejected_by {
+        return DoctorCheck::new("API key", CheckStatus::Fail, format!("rejected ({status})"))
+            .with_hint("Run `codex omnara login` with a current key, or fix OMNARA_API_KEY");
+    }
+    match session {
+        Some(SessionProbe { create: Ok(_), .. }) => {
+            DoctorCheck::new("API key", CheckStatus::Pass, "accepted")
+        }
+        _ => DoctorCheck::skipped("API key"),
+    }
+}
+
+fn session_checks(session: &SessionProbe) -> [DoctorCheck; 2] {
+    let created = match &session.create {
+        Ok(created) => created,
+        Err(e) => {
+            return [
+                DoctorCheck::new(
+                    "Session",
+                    CheckStatus::Fail,
+                    format!("could not create a session: {e}"),
+                )
+                .with_hint("Sessions cannot be mirrored; the error above names the cause"),
+                DoctorCheck::skipped("Polling"),
+            ];
+        }
+    };
+    let session_check = match &session.end {
+        Some(Err(e)) => DoctorCheck::new(
+            "Session",
+            CheckStatus::Warn,
+            format!(
+                "created in {} ms, but ending it failed: {e}",
+                created.as_millis()
+            ),
+        )
+        .with_hint("The throwaway session may stay listed on the dashboard; end it there"),
+        _ => DoctorCheck::new(
+            "Session",
+            CheckStatus::Pass,
+            format!(
+                "throwaway session created in {} ms and ended",
+                created.as_millis()
+            ),
+        ),
+    };
+    let polling_check = match &session.poll {
+        Some(Ok(polled)) => DoctorCheck::new(
+            "Polling",
+            CheckStatus::Pass,
+            format!("pending messages answered in {} ms", polled.as_millis()),
+        ),
+        Some(Err(e)) => DoctorCheck::new("Polling", CheckStatus::Fail, format!("failed: {e}"))
+            .with_hint("Dashboard replies will not reach Codex; check that your proxy allows long-running GET requests"),
+        None => DoctorCheck::skipped("Polling"),
+    };
+    [session_check, polling_check]
+}
+
+fn clock_check(probes: &[&ServerProbe]) -> DoctorCheck {
+    let Some(skew) = probes.iter().find_map(|probe| probe.clock_skew) else {
+        return DoctorCheck::new(
+            "Clock",
+            CheckStatus::Skipped,
+            "the server sent no Date header",
+        );
+    };
+    let offset = skew.abs().to_std().unwrap_or_default();
+    if offset <= MAX_CLOCK_SKEW {
+        return DoctorCheck::new(
+            "Clock",
+            CheckStatus::Pass,
+            format!("in sync with the server ({}s apart)", offset.as_secs()),
+        );
+    }
+    let direction = if skew > chrono::Duration::zero() {
+        "behind"
+    } else {
+        "ahead of"
+    };
+    DoctorCheck::new(
+        "Clock",
+        CheckStatus::Warn,
+        format!(
+            "the local clock is {}s {direction} the server",
+            offset.as_secs()
+        ),
+    )
+    .with_hint(
+        "Sync the system clock (e.g. enable NTP); message timestamps and history replay rely on it",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn probe(millis: u64, skew_secs: i64) -> Result<ServerProbe, CodexErr> {
+        Ok(ServerProbe {
+            latency: Duration::from_millis(millis),
+            status: StatusCode::OK,
+            clock_skew: Some(chrono::Duration::seconds(skew_secs)),
+        })
+    }
+
+    #[test]
+    fn report_flags_what_to_fix() {
+        let session = SessionProbe {
+            create: Ok(Duration::from_millis(210)),
+            poll: Some(Ok(Duration::from_millis(95))),
+            end: Some(Ok(())),
+        };
+        let report = DoctorReport::from_probes(
+            "https://omnara.example.com".to_string(),
+            &[probe(120, 95), probe(80, 95), probe(300, 95)],
+            Some(&session),
+        );
+        assert!(report.passed());
+        assert_eq!(
+            report.format(),
+            "Omnara doctor: https://omnara.example.com\n\
+             ✓ Server   reachable, 120 ms round trip (median of 3)\n\
+             ✓ API key  accepted\n\
+             ✓ Session  throwaway session created in 210 ms and ended\n\
+             ✓ Polling  pending messages answered in 95 ms\n\
+             ! Clock    the local clock is 95s behind the server\n\
+             \x20 → Sync the system clock (e.g. enable NTP); message timestamps and history replay rely on it\n"
+        );
+
+        let rejected = SessionProbe {
+            create: Err(CodexErr::UnexpectedStatus(
+                StatusCode::UNAUTHORIZED,
+                String::new(),
+            )),
+            poll: None,
+            end: None,
+        };
+        let report = DoctorReport::from_probes(
+            "https://omnara.example.com".to_string(),
+            &[probe(80, 0)],
+            Some(&rejected),
+        );
+        assert!(!report.passed());
+        let statuses: Vec<(&str, CheckStatus)> = report
+            .checks
+            .iter()
+            .map(|check| (check.name, check.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("Server", CheckStatus::Pass),
+                ("API key", CheckStatus::Fail),
+                ("Session", CheckStatus::Skipped),
+                ("Polling", CheckStatus::Skipped),
+                ("Clock", CheckStatus::Pass),
+            ]
+        );
+    }
+}
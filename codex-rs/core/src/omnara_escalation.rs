@@ -0,0 +1,278 @@
+//! Escalation chain for approval prompts nobody answers.
+//!
+//! `[omnara.approval_escalation]` turns into an [`EscalationChain`]: a list of
+//! steps, each with a delay measured from when the prompt was sent. Frontends
+//! start the chain's timer when they post an approval prompt, drop it when
+//! the approval resolves, and carry out each step as it comes due, recording
+//! it with [`crate::omnara_client::OmnaraClient::audit_approval`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::config_types::ApprovalEscalation;
+use crate::config_types::EscalationDecision;
+use crate::config_types::OmnaraLocale;
+use crate::omnara_clock::Clock;
+use crate::omnara_i18n::fill;
+use crate::omnara_i18n::strings;
+use crate::protocol::ReviewDecision;
+
+/// How long a webhook POST may take before it is given up on.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscalationStep {
+    /// Re-send the prompt as an urgent message.
+    Remind,
+    /// Notify a secondary channel.
+    Webhook { url: String },
+    /// Resolve the approval without an answer.
+    ApplyDefault(ReviewDecision),
+}
+
+impl EscalationStep {
+    /// How the step reads in the audit log.
+    pub fn describe(&self, waited: Duration) -> String {
+        let minutes = waited.as_secs() / 60;
+        match self {
+            EscalationStep::Remind => format!("no reply after {minutes}m; reminder sent"),
+            EscalationStep::Webhook { url } => {
+                format!("no reply after {minutes}m; notified {}", redact_url(url))
+            }
+            EscalationStep::ApplyDefault(decision) => {
+                format!("no reply after {minutes}m; applied default decision {decision:?}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscalationChain {
+    /// Steps ordered by delay.
+    steps: Vec<(Duration, EscalationStep)>,
+}
+
+impl EscalationChain {
+    /// The configured chain, or `None` when every step is disabled.
+    pub fn from_config(config: &ApprovalEscalation) -> Option<Self> {
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        let mut steps = Vec::new();
+        if config.remind_after_minutes > 0 {
+            steps.push((minutes(config.remind_after_minutes), EscalationStep::Remind));
+        }
+        if let Some(url) = &config.webhook_url
+            && config.webhook_after_minutes > 0
+        {
+            steps.push((
+                minutes(config.webhook_after_minutes),
+                EscalationStep::Webhook { url: url.clone() },
+            ));
+        }
+        if config.default_after_minutes > 0 {
+            steps.push((
+                minutes(config.default_after_minutes),
+                EscalationStep::ApplyDefault(default_review_decision(config.default_decision)),
+            ));
+        }
+        (!steps.is_empty()).then(|| Self::new(steps))
+    }
+
+    pub fn new(mut steps: Vec<(Duration, EscalationStep)>) -> Self {
+        // Stable, so a step due together with the default decision still
+        // runs before it; steps due after it never run.
+        steps.sort_by_key(|(delay, _)| *delay);
+        Self { steps }
+    }
+
+    pub fn steps(&self) -> &[(Duration, EscalationStep)] {
+        &self.steps
+    }
+
+    /// Run the chain's timer on `clock`, calling `on_step` with each step and
+    /// the delay it was due at. Abort the handle once the approval resolves.
+    pub fn start(
+        &self,
+        clock: Arc<dyn Clock>,
+        mut on_step: impl FnMut(EscalationStep, Duration) + Send + 'static,
+    ) -> JoinHandle<()> {
+        let steps = self.steps.clone();
+        tokio::spawn(async move {
+            let started = clock.now();
+            for (delay, step) in steps {
+                clock.sleep_until(started + delay).await;
+                let is_last = matches!(step, EscalationStep::ApplyDefault(_));
+                on_step(step, delay);
+                if is_last {
+                    break;
+                }
+            }
+        })
+    }
+}
+
+pub(crate) fn default_review_decision(decision: EscalationDecision) -> ReviewDecision {
+    match decision {
+        EscalationDecision::Approve => ReviewDecision::Approved,
+        EscalationDecision::Deny => ReviewDecision::Denied,
+        EscalationDecision::Abort => ReviewDecision::Abort,
+    }
+}
+
+/// The approval prompt re-sent as a reminder.
+pub fn format_approval_reminder(prompt: &str, waited: Duration, locale: OmnaraLocale) -> String {
+    let headline = fill(
+        strings(locale).approval_reminder,
+        &[("minutes", &(waited.as_secs() / 60))],
+    );
+    format!("**⏰ {headline}**\n\n{prompt}")
+}
+
+/// Dashboard note for an approval resolved by the escalation chain.
+pub fn format_default_decision_note(
+    decision: ReviewDecision,
+    waited: Duration,
+    locale: OmnaraLocale,
+) -> String {
+    let note = fill(
+        strings(locale).default_decision,
+        &[
+            ("minutes", &(waited.as_secs() / 60)),
+            ("decision", &decision_word(decision, locale)),
+        ],
+    );
+    format!("⏱️ {note}")
+}
+
+/// How a decision applied without an answer reads in a dashboard note.
+pub(crate) fn decision_word(decision: ReviewDecision, locale: OmnaraLocale) -> &'static str {
+    let text = strings(locale);
+    match decision {
+        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => text.decision_approved,
+        ReviewDecision::Denied => text.decision_denied,
+        ReviewDecision::Abort => text.decision_aborted,
+    }
+}
+
+/// Plain-text notice for the webhook: the prompt's headline and how long it
+/// has been waiting.
+pub fn format_webhook_text(session_id: &str, prompt: &str, waited: Duration) -> String {
+    let headline = prompt
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default()
+        .replace("**", "");
+    format!(
+        "Codex session {session_id} has been waiting {} min for approval: {}",
+        waited.as_secs() / 60,
+        headline.trim()
+    )
+}
+
+/// POST `{"text": text}` to a webhook, the payload Slack incoming webhooks
+/// accept, over the Omnara client's `http` connection pool.
+pub async fn send_webhook(http: &reqwest::Client, url: &str, text: &str) -> Result<(), String> {
+    let resp = http
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&serde_json::json!({ "text": text }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("webhook returned {}", resp.status()))
+    }
+}
+
+/// Webhook URLs often embed a secret token; only the host is logged.
+fn redact_url(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|host| format!("webhook at {host}")))
+        .unwrap_or_else(|| "webhook".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn chain_orders_enabled_steps_by_delay() {
+        assert_eq!(
+            EscalationChain::from_config(&ApprovalEscalation::default()),
+            None
+        );
+        let chain = EscalationChain::from_config(&ApprovalEscalation {
+            remind_after_minutes: 5,
+            webhook_url: Some("https://hooks.slack.com/services/T0/B0/secret".to_string()),
+            webhook_after_minutes: 10,
+            default_after_minutes: 5,
+            default_decision: EscalationDecision::Abort,
+        })
+        .expect("chain");
+        let webhook = EscalationStep::Webhook {
+            url: "https://hooks.slack.com/services/T0/B0/secret".to_string(),
+        };
+        assert_eq!(
+            chain.steps(),
+            &[
+                (Duration::from_secs(300), EscalationStep::Remind),
+                (
+                    Duration::from_secs(300),
+                    EscalationStep::ApplyDefault(ReviewDecision::Abort)
+                ),
+                (Duration::from_secs(600), webhook.clone()),
+            ]
+        );
+        assert_eq!(
+            webhook.describe(Duration::from_secs(600)),
+            "no reply after 10m; notified webhook at hooks.slack.com"
+        );
+        assert_eq!(
+            format_webhook_text(
+                "s-1",
+                "**Execute command?**\n\nrun tests",
+                Duration::from_secs(600)
+            ),
+            "Codex session s-1 has been waiting 10 min for approval: Execute command?"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timer_stops_after_the_default_decision() {
+        let chain = EscalationChain::new(vec![
+            (Duration::from_secs(600), EscalationStep::Remind),
+            (
+                Duration::from_secs(1200),
+                EscalationStep::ApplyDefault(ReviewDecision::Denied),
+            ),
+            (Duration::from_secs(1800), EscalationStep::Remind),
+        ]);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        chain
+            .start(
+                Arc::new(crate::omnara_clock::TokioClock),
+                move |step, delay| {
+                    let _ = tx.send((step, delay));
+                },
+            )
+            .await
+            .expect("timer task");
+        assert_eq!(
+            rx.recv().await,
+            Some((EscalationStep::Remind, Duration::from_secs(600)))
+        );
+        assert_eq!(
+            rx.recv().await,
+            Some((
+                EscalationStep::ApplyDefault(ReviewDecision::Denied),
+                Duration::from_secs(1200)
+            ))
+        );
+        assert_eq!(rx.recv().await, None);
+    }
+}
@@ -0,0 +1,125 @@
+//! The HTTP client behind every Omnara request.
+//!
+//! One pooled [`reqwest::Client`] is shared by an [`OmnaraClient`] and all of
+//! its clones and child sessions, so sends, polls, and uploads reuse the same
+//! connections (HTTP/2 where the server negotiates it). Timeouts, pooling, a
+//! proxy, and extra root certificates come from `[omnara.http]`; without an
+//! explicit proxy, `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` apply.
+//!
+//! [`OmnaraClient`]: crate::omnara_client::OmnaraClient
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::config_types::OmnaraHttpConfig;
+use crate::default_client::get_codex_user_agent;
+
+#[derive(Debug, Error)]
+pub enum HttpClientError {
+    #[error("cannot read CA certificate {path}: {source}")]
+    ReadCertificate {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid CA certificate {path}: {source}")]
+    ParseCertificate {
+        path: PathBuf,
+        source: reqwest::Error,
+    },
+    #[error("invalid proxy {url}: {source}")]
+    Proxy { url: String, source: reqwest::Error },
+    #[error(transparent)]
+    Build(#[from] reqwest::Error),
+}
+
+/// Build a client tuned by `config`.
+pub fn build_http_client(config: &OmnaraHttpConfig) -> Result<reqwest::Client, HttpClientError> {
+    let secs = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+
+    let mut builder = reqwest::Client::builder()
+        .user_agent(get_codex_user_agent())
+        .pool_idle_timeout(secs(config.pool_idle_timeout_secs))
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .http2_keep_alive_interval(secs(config.http2_keep_alive_secs))
+        .http2_keep_alive_while_idle(true);
+    if let Some(timeout) = secs(config.connect_timeout_secs) {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = secs(config.request_timeout_secs) {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(url) = &config.proxy {
+        let proxy = reqwest::Proxy::all(url).map_err(|source| HttpClientError::Proxy {
+            url: url.clone(),
+            source,
+        })?;
+        builder = builder.proxy(proxy);
+    }
+    for path in &config.ca_certificates {
+        let pem = std::fs::read(path).map_err(|source| HttpClientError::ReadCertificate {
+            path: path.clone(),
+            source,
+        })?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|source| {
+            HttpClientError::ParseCertificate {
+                path: path.clone(),
+                source,
+            }
+        })?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// The process-wide client with default tuning, used until a client is
+/// configured with [`OmnaraClient::with_http_config`].
+///
+/// [`OmnaraClient::with_http_config`]: crate::omnara_client::OmnaraClient::with_http_config
+pub fn default_http_client() -> reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            build_http_client(&OmnaraHttpConfig::default()).unwrap_or_else(|e| {
+                tracing::warn!("Omnara: default HTTP client unavailable ({e}); using reqwest's");
+                reqwest::Client::new()
+            })
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_certificates_and_proxies_are_reported() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let missing = dir.path().join("missing.pem");
+        let err = build_http_client(&OmnaraHttpConfig {
+            ca_certificates: vec![missing.clone()],
+            ..OmnaraHttpConfig::default()
+        })
+        .expect_err("missing certificate");
+        assert!(
+            matches!(&err, HttpClientError::ReadCertificate { path, .. } if *path == missing),
+            "{err}"
+        );
+
+        let err = build_http_client(&OmnaraHttpConfig {
+            proxy: Some("not a url".to_string()),
+            ..OmnaraHttpConfig::default()
+        })
+        .expect_err("invalid proxy");
+        assert!(
+            err.to_string().starts_with("invalid proxy not a url"),
+            "{err}"
+        );
+
+        assert!(build_http_client(&OmnaraHttpConfig::default()).is_ok());
+    }
+}
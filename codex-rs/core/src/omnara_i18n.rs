@@ -0,0 +1,926 @@
+//! Locale tables for text posted to the Omnara dashboard.
+//!
+//! `omnara.locale` selects one [`Strings`] table, covering approval options,
+//! prompt headlines and status words, and session lifecycle notes. Markdown
+//! and emoji stay in the formatters; the tables hold only words. Templates
+//! use `{name}` placeholders, filled with [`fill`]. Text meant for the model
+//! (e.g. denial feedback) is not localized.
+
+use crate::config_types::OmnaraLocale;
+
+pub struct Strings {
+    // Approval options, as listed in `[OPTIONS]` blocks.
+    pub option_yes: &'static str,
+    pub option_always: &'static str,
+    pub option_no: &'static str,
+    /// Replies accepted for each option besides the option labels, already
+    /// normalized (lowercase, no trailing punctuation).
+    pub yes_words: &'static [&'static str],
+    pub always_words: &'static [&'static str],
+    pub no_words: &'static [&'static str],
+    /// Words separating "yes" from the files left out of a partial patch
+    /// approval ("yes except tests/*").
+    pub except_words: &'static [&'static str],
+    /// Words between a decision and the commands it covers in a reply to a
+    /// batch of exec approvals ("yes to 1, 3").
+    pub batch_to_words: &'static [&'static str],
+    /// Words standing for every command a batch reply has not decided yet
+    /// ("no to the rest").
+    pub batch_rest_words: &'static [&'static str],
+    /// Words between "yes" and the length of an auto-approval window
+    /// ("yes for 30m").
+    pub window_words: &'static [&'static str],
+
+    // Approval prompts.
+    pub execute_command: &'static str,
+    pub exec_default_reason: &'static str,
+    /// `{count}`.
+    pub execute_commands: &'static str,
+    /// How to answer the commands of a batch one by one.
+    pub batch_hint: &'static str,
+    pub outside_sandbox_title: &'static str,
+    pub outside_sandbox_context: &'static str,
+    pub retry_sandbox_title: &'static str,
+    pub retry_sandbox_context: &'static str,
+    pub justification: &'static str,
+    pub working_directory: &'static str,
+    pub unsandboxed_warning: &'static str,
+    /// Risk badge headlines and highlights (`crate::command_risk`).
+    pub risk_high: &'static str,
+    pub risk_medium: &'static str,
+    pub risk_sudo: &'static str,
+    pub risk_credentials: &'static str,
+    pub risk_writes_outside: &'static str,
+    pub risk_package_install: &'static str,
+    pub risk_network: &'static str,
+    // Near match with a command approved for the session.
+    pub near_approved: &'static str,
+    pub words_added: &'static str,
+    pub words_removed: &'static str,
+    /// `{count}`, `{added}`, `{removed}`.
+    pub patch_headline_one: &'static str,
+    pub patch_headline_many: &'static str,
+    /// `{root}`.
+    pub grant_root: &'static str,
+    /// `{command}`.
+    pub preview_hint: &'static str,
+    /// `{command}`.
+    pub explain_hint: &'static str,
+    /// How to approve only some files of a multi-file patch.
+    pub partial_hint: &'static str,
+    pub apply_changes: &'static str,
+    pub question_from_agent: &'static str,
+    /// Hint under a question the agent ended its turn with.
+    pub reply_below: &'static str,
+    /// Suggested "no" to a yes/no question (`option_yes` is the "yes").
+    pub answer_no: &'static str,
+    pub call_mcp_tool: &'static str,
+    pub mcp_server: &'static str,
+    pub mcp_tool: &'static str,
+
+    // Patch previews.
+    pub preview_started: &'static str,
+    /// `{command}`.
+    pub preview_started_check: &'static str,
+    pub preview_applies: &'static str,
+    /// `{command}`, `{secs}`.
+    pub preview_passed: &'static str,
+    /// `{command}`, `{code}`, `{secs}`.
+    pub preview_failed: &'static str,
+    /// `{command}`, `{secs}`.
+    pub preview_killed: &'static str,
+    /// `{command}`, `{secs}`.
+    pub preview_timed_out: &'static str,
+    pub preview_error: &'static str,
+
+    // Patch explanations (`/explain`).
+    pub explanation_title: &'static str,
+    pub explanation_failed: &'static str,
+
+    // Hunk index and expansion (`show N`).
+    pub hunks_title: &'static str,
+    /// `{command}`.
+    pub show_hunk_hint: &'static str,
+    /// `{index}`, `{count}`, `{path}`.
+    pub hunk_title: &'static str,
+    /// `{index}`, `{count}`.
+    pub no_such_hunk: &'static str,
+
+    // More detail on a prompt, after a 🔁 reaction.
+    pub details_title: &'static str,
+    pub arguments: &'static str,
+
+    // Session rollback (`/rollback`).
+    pub rollback_reason: &'static str,
+    pub rollback_unavailable: &'static str,
+    pub rollback_nothing: &'static str,
+    pub rollback_busy: &'static str,
+    /// `{count}`.
+    pub rollback_done: &'static str,
+    pub rollback_failed: &'static str,
+    pub rollback_cancelled: &'static str,
+
+    // Patches sent from the dashboard (`/apply-patch`).
+    pub apply_patch_busy: &'static str,
+    pub apply_patch_invalid: &'static str,
+    /// `{count}`.
+    pub apply_patch_waiting: &'static str,
+    /// `{count}`.
+    pub apply_patch_done: &'static str,
+    pub apply_patch_failed: &'static str,
+    pub apply_patch_declined: &'static str,
+
+    // Escalation.
+    /// `{minutes}`.
+    pub approval_reminder: &'static str,
+    /// `{minutes}`, `{decision}`.
+    pub default_decision: &'static str,
+    pub decision_approved: &'static str,
+    pub decision_denied: &'static str,
+    pub decision_aborted: &'static str,
+
+    // Alerts (`[omnara.alerts]`).
+    /// `{minutes}`.
+    pub turn_alert: &'static str,
+    /// `{minutes}`.
+    pub approval_alert: &'static str,
+
+    // Quiet hours.
+    /// `{count}`.
+    pub quiet_hours_digest: &'static str,
+    /// `{decision}`.
+    pub quiet_hours_decision: &'static str,
+
+    // Scheduled prompts (`[omnara.schedules]`).
+    /// `{name}`.
+    pub schedule_started: &'static str,
+    /// `{name}`.
+    pub schedule_finished: &'static str,
+    /// `{time}`.
+    pub schedule_next_run: &'static str,
+
+    // Approvals held by `omnara.max_outstanding_approvals`.
+    pub approvals_pending_one: &'static str,
+    /// `{count}`.
+    pub approvals_pending_many: &'static str,
+
+    // Auto-approval windows ("yes for 30m").
+    /// `{kind}`, `{minutes}`.
+    pub auto_approve_started: &'static str,
+    pub auto_approve_ended: &'static str,
+
+    // Crash recovery.
+    /// An approval a crashed run left unanswered; `{kind}`.
+    pub approval_interrupted: &'static str,
+    /// A prompt whose turn was interrupted before it was answered; `{kind}`.
+    pub approval_withdrawn: &'static str,
+
+    // Session lifecycle.
+    pub session_started: &'static str,
+    pub interrupt_prompt: &'static str,
+    pub dropped_messages: &'static str,
+    /// `{waiting}`.
+    pub queued_input: &'static str,
+    pub new_task: &'static str,
+    pub new_task_usage: &'static str,
+    /// A dashboard message over `omnara.max_remote_message_kb`; `{size}`,
+    /// `{limit}` (KiB).
+    pub message_too_large: &'static str,
+    /// A message held by `omnara.workspace_trust`.
+    pub input_held: &'static str,
+    pub input_discarded: &'static str,
+    /// Posted in the parent session; `{label}`, `{session}`.
+    pub continued_in_session: &'static str,
+    /// Posted first in a child session; `{session}`.
+    pub opened_from_session: &'static str,
+    pub new_session_label: &'static str,
+    pub fork_label: &'static str,
+    pub fork_usage: &'static str,
+    pub fork_busy: &'static str,
+    /// `{prompt}`, `{count}`.
+    pub fork_out_of_range: &'static str,
+    /// `{error}`.
+    pub fork_failed: &'static str,
+    /// Posted first in a session forked from the dashboard; `{prompt}`.
+    pub forked_before: &'static str,
+
+    // Code review.
+    pub review_started: &'static str,
+    /// Followed by a dash and `review_no_findings`.
+    pub review_interrupted: &'static str,
+    pub review_no_findings: &'static str,
+    pub review_finished: &'static str,
+    /// `{count}`.
+    pub review_finished_one: &'static str,
+    pub review_finished_many: &'static str,
+    pub verdict: &'static str,
+    pub confidence: &'static str,
+    pub severity_critical: &'static str,
+    pub severity_high: &'static str,
+    pub severity_medium: &'static str,
+    pub severity_low: &'static str,
+    pub accept_findings: &'static str,
+    pub request_changes: &'static str,
+    pub what_changes: &'static str,
+}
+
+pub fn strings(locale: OmnaraLocale) -> &'static Strings {
+    match locale {
+        OmnaraLocale::En => &EN,
+        OmnaraLocale::De => &DE,
+        OmnaraLocale::Fr => &FR,
+        OmnaraLocale::Es => &ES,
+    }
+}
+
+/// Replace each `{name}` in `template` with its value.
+pub fn fill(template: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+static EN: Strings = Strings {
+    option_yes: "Yes",
+    option_always: "Always",
+    option_no: "No, provide feedback",
+    yes_words: &[],
+    always_words: &[],
+    no_words: &[],
+    except_words: &["except", "but not", "without"],
+    batch_to_words: &["to", "for"],
+    batch_rest_words: &["all", "rest", "the rest", "others", "the others"],
+    window_words: &["for"],
+
+    execute_command: "Execute command?",
+    exec_default_reason: "Agent wants to execute a command",
+    execute_commands: "Execute {count} commands?",
+    batch_hint: "Reply for all of them, or per command, e.g. `yes to 1, 3; no to 2`.",
+    outside_sandbox_title: "Run command outside the sandbox?",
+    outside_sandbox_context: "The agent is asking to run this command without sandbox restrictions.",
+    retry_sandbox_title: "Retry command without the sandbox?",
+    retry_sandbox_context: "This command failed inside the sandbox. Approving reruns it without \
+                            sandbox restrictions and skips the sandbox for it for the rest of the \
+                            session.",
+    justification: "Justification",
+    working_directory: "Working directory",
+    unsandboxed_warning: "Unsandboxed commands can write anywhere your user can and can access \
+                          the network.",
+    risk_high: "High risk",
+    risk_medium: "Medium risk",
+    risk_sudo: "uses sudo",
+    risk_credentials: "accesses credentials",
+    risk_writes_outside: "writes outside workspace",
+    risk_package_install: "installs packages",
+    risk_network: "network access",
+    near_approved: "Close to a command approved for this session",
+    words_added: "adds",
+    words_removed: "drops",
+    patch_headline_one: "Proposed patch to {count} file (+{added} -{removed})",
+    patch_headline_many: "Proposed patch to {count} files (+{added} -{removed})",
+    grant_root: "This will grant write access to {root} for the remainder of this session.",
+    preview_hint: "Reply `{command}` to try the patch in a scratch worktree first.",
+    explain_hint: "Reply `{command}` for a plain-language summary of the change.",
+    partial_hint: "To apply only some files, reply `yes except 2` or `yes except tests/*`.",
+    apply_changes: "Apply changes?",
+    question_from_agent: "Question from the agent",
+    reply_below: "Reply below to answer.",
+    answer_no: "No",
+    call_mcp_tool: "Call MCP tool?",
+    mcp_server: "Server",
+    mcp_tool: "Tool",
+
+    preview_started: "Applying the patch in a scratch worktree...",
+    preview_started_check: "Applying the patch in a scratch worktree and running `{command}`...",
+    preview_applies: "Preview: the patch applies cleanly",
+    preview_passed: "Preview: `{command}` passed ({secs}s)",
+    preview_failed: "Preview: `{command}` failed with exit code {code} ({secs}s)",
+    preview_killed: "Preview: `{command}` was killed ({secs}s)",
+    preview_timed_out: "Preview: `{command}` timed out after {secs}s",
+    preview_error: "Preview failed",
+
+    explanation_title: "What this change does",
+    explanation_failed: "Could not summarize this patch right now.",
+
+    hunks_title: "Hunks",
+    show_hunk_hint: "Reply `{command} N` to see hunk N in full before deciding.",
+    hunk_title: "Hunk {index} of {count}: {path}",
+    no_such_hunk: "There is no hunk {index}; this patch has {count}.",
+
+    details_title: "More detail",
+    arguments: "Arguments",
+
+    rollback_reason: "Roll back this session's changes: edited and deleted files are restored \
+                      and files the session created are removed.",
+    rollback_unavailable: "Rollback is only available in git repositories.",
+    rollback_nothing: "Nothing to roll back: the session has not changed any files.",
+    rollback_busy: "The agent is still working. Send `/now` to stop it, then `/rollback`.",
+    rollback_done: "Rolled back {count} file(s)",
+    rollback_failed: "Rollback failed",
+    rollback_cancelled: "Rollback cancelled; no files were changed.",
+
+    apply_patch_busy: "The agent is still working. Send `/now` to stop it, then `/apply-patch`.",
+    apply_patch_invalid: "Patch not applied",
+    apply_patch_waiting: "Patch for {count} file(s) received; waiting for approval in the terminal.",
+    apply_patch_done: "Applied the patch to {count} file(s)",
+    apply_patch_failed: "Patch failed",
+    apply_patch_declined: "Patch declined in the terminal; no files were changed.",
+
+    approval_reminder: "Still waiting for your approval ({minutes} min)",
+    default_decision: "No reply after {minutes} min: the request was {decision} by default.",
+    decision_approved: "approved",
+    decision_denied: "denied",
+    decision_aborted: "denied, and the turn was stopped",
+
+    turn_alert: "Turn still running after {minutes} min",
+    approval_alert: "No reply to this approval for {minutes} min",
+
+    quiet_hours_digest: "Held during quiet hours ({count})",
+    quiet_hours_decision: "Answered during quiet hours: the request was {decision} by default.",
+    schedule_started: "Running scheduled prompt {name}",
+    schedule_finished: "Scheduled prompt {name} finished.",
+    schedule_next_run: "Next run: {time}",
+    approvals_pending_one: "1 more approval pending",
+    approvals_pending_many: "{count} more approvals pending",
+
+    auto_approve_started: "Auto-approving {kind} requests of this risk level or lower for the next {minutes} min.",
+    auto_approve_ended: "The auto-approval window has ended; approvals are asked for again.",
+    approval_interrupted: "Codex restarted before this {kind} approval was answered, so it was cancelled. Ask again to retry.",
+    approval_withdrawn: "Request withdrawn: the turn was interrupted before this {kind} request was answered.",
+
+    session_started: "Codex session started - waiting for your input...",
+    interrupt_prompt: "Tell the model what to do differently",
+    dropped_messages: "These queued messages were not sent:",
+    queued_input: "Queued until Codex finishes the current turn ({waiting} waiting). Reply \
+                   `/now <message>` to interrupt and send immediately.",
+    new_task: "New task",
+    new_task_usage: "Usage: `/new-task <prompt>`",
+    message_too_large: "Message not delivered: it is {size} KiB, over the {limit} KiB limit.",
+    input_held: "Message held until it is confirmed in the terminal.",
+    input_discarded: "Message discarded in the terminal; it was not sent to Codex.",
+    continued_in_session: "**{label}** continues in a linked session: `{session}`",
+    opened_from_session: "Opened from session `{session}`",
+    new_session_label: "New session",
+    fork_label: "Fork",
+    fork_usage: "Usage: `/fork [N]` continues in a new conversation forked before prompt N (the latest by default).",
+    fork_busy: "The agent is still working. Send `/now` to stop it, then `/fork`.",
+    fork_out_of_range: "There is no prompt {prompt} to fork before; this conversation has {count}.",
+    fork_failed: "Could not fork the conversation: {error}",
+    forked_before: "Forked before prompt {prompt}, which this conversation leaves out:",
+
+    review_started: "Code review started:",
+    review_interrupted: "Code review interrupted",
+    review_no_findings: "no findings were produced.",
+    review_finished: "Code review finished:",
+    review_finished_one: "{count} finding",
+    review_finished_many: "{count} findings",
+    verdict: "Verdict",
+    confidence: "confidence",
+    severity_critical: "critical",
+    severity_high: "high",
+    severity_medium: "medium",
+    severity_low: "low",
+    accept_findings: "Accept findings",
+    request_changes: "Request changes",
+    what_changes: "What changes would you like?",
+};
+
+static DE: Strings = Strings {
+    option_yes: "Ja",
+    option_always: "Immer",
+    option_no: "Nein, mit Feedback",
+    yes_words: &["ja", "j", "genehmigen", "genehmigt"],
+    always_words: &["immer", "ja immer", "immer genehmigen"],
+    no_words: &["nein", "ablehnen", "abgelehnt"],
+    except_words: &["außer", "ohne"],
+    batch_to_words: &["für", "zu"],
+    batch_rest_words: &[
+        "alle",
+        "rest",
+        "den rest",
+        "der rest",
+        "andere",
+        "die anderen",
+    ],
+    window_words: &["für"],
+
+    execute_command: "Befehl ausführen?",
+    exec_default_reason: "Der Agent möchte einen Befehl ausführen",
+    execute_commands: "{count} Befehle ausführen?",
+    batch_hint: "Antworte für alle oder pro Befehl, z. B. `ja für 1, 3; nein für 2`.",
+    outside_sandbox_title: "Befehl außerhalb der Sandbox ausführen?",
+    outside_sandbox_context: "Der Agent möchte diesen Befehl ohne Sandbox-Beschränkungen \
+                              ausführen.",
+    retry_sandbox_title: "Befehl ohne Sandbox wiederholen?",
+    retry_sandbox_context: "Dieser Befehl ist in der Sandbox fehlgeschlagen. Eine Genehmigung \
+                            führt ihn ohne Sandbox-Beschränkungen erneut aus und umgeht die \
+                            Sandbox für ihn für den Rest der Sitzung.",
+    justification: "Begründung",
+    working_directory: "Arbeitsverzeichnis",
+    unsandboxed_warning: "Befehle ohne Sandbox können überall schreiben, wo dein Benutzer es \
+                          kann, und auf das Netzwerk zugreifen.",
+    risk_high: "Hohes Risiko",
+    risk_medium: "Mittleres Risiko",
+    risk_sudo: "nutzt sudo",
+    risk_credentials: "greift auf Zugangsdaten zu",
+    risk_writes_outside: "schreibt außerhalb des Arbeitsbereichs",
+    risk_package_install: "installiert Pakete",
+    risk_network: "Netzwerkzugriff",
+    near_approved: "Ähnlich einem für diese Sitzung freigegebenen Befehl",
+    words_added: "fügt hinzu",
+    words_removed: "lässt weg",
+    patch_headline_one: "Vorgeschlagener Patch für {count} Datei (+{added} -{removed})",
+    patch_headline_many: "Vorgeschlagener Patch für {count} Dateien (+{added} -{removed})",
+    grant_root: "Damit erhält der Agent für den Rest der Sitzung Schreibzugriff auf {root}.",
+    preview_hint: "Antworte `{command}`, um den Patch zuerst in einem temporären Worktree \
+                   auszuprobieren.",
+    explain_hint: "Antworte `{command}` für eine verständliche Zusammenfassung der Änderung.",
+    partial_hint: "Um nur einige Dateien anzuwenden, antworte `ja außer 2` oder `ja außer tests/*`.",
+    apply_changes: "Änderungen anwenden?",
+    question_from_agent: "Frage des Agenten",
+    reply_below: "Antworte unten.",
+    answer_no: "Nein",
+    call_mcp_tool: "MCP-Tool aufrufen?",
+    mcp_server: "Server",
+    mcp_tool: "Tool",
+
+    preview_started: "Der Patch wird in einem temporären Worktree angewendet...",
+    preview_started_check: "Der Patch wird in einem temporären Worktree angewendet und \
+                            `{command}` ausgeführt...",
+    preview_applies: "Vorschau: Der Patch lässt sich sauber anwenden",
+    preview_passed: "Vorschau: `{command}` erfolgreich ({secs}s)",
+    preview_failed: "Vorschau: `{command}` mit Exit-Code {code} fehlgeschlagen ({secs}s)",
+    preview_killed: "Vorschau: `{command}` wurde abgebrochen ({secs}s)",
+    preview_timed_out: "Vorschau: `{command}` nach {secs}s abgebrochen (Zeitlimit)",
+    preview_error: "Vorschau fehlgeschlagen",
+
+    explanation_title: "Was diese Änderung bewirkt",
+    explanation_failed: "Der Patch konnte gerade nicht zusammengefasst werden.",
+
+    hunks_title: "Abschnitte",
+    show_hunk_hint: "Antworte `{command} N`, um Abschnitt N vor der Entscheidung vollständig zu sehen.",
+    hunk_title: "Abschnitt {index} von {count}: {path}",
+    no_such_hunk: "Es gibt keinen Abschnitt {index}; dieser Patch hat {count}.",
+
+    details_title: "Weitere Details",
+    arguments: "Argumente",
+
+    rollback_reason: "Änderungen dieser Sitzung zurücknehmen: Bearbeitete und gelöschte \
+                      Dateien werden wiederhergestellt, neu angelegte Dateien entfernt.",
+    rollback_unavailable: "Zurücknehmen ist nur in Git-Repositories möglich.",
+    rollback_nothing: "Nichts zurückzunehmen: Die Sitzung hat keine Dateien geändert.",
+    rollback_busy: "Der Agent arbeitet noch. Sende `/now`, um ihn zu stoppen, dann `/rollback`.",
+    rollback_done: "{count} Datei(en) zurückgesetzt",
+    rollback_failed: "Zurücknehmen fehlgeschlagen",
+    rollback_cancelled: "Zurücknehmen abgebrochen; keine Dateien wurden geändert.",
+
+    apply_patch_busy: "Der Agent arbeitet noch. Sende `/now`, um ihn zu stoppen, dann `/apply-patch`.",
+    apply_patch_invalid: "Patch nicht angewendet",
+    apply_patch_waiting: "Patch für {count} Datei(en) erhalten; wartet auf Freigabe im Terminal.",
+    apply_patch_done: "Patch auf {count} Datei(en) angewendet",
+    apply_patch_failed: "Patch fehlgeschlagen",
+    apply_patch_declined: "Patch im Terminal abgelehnt; keine Dateien wurden geändert.",
+
+    approval_reminder: "Wartet weiterhin auf deine Genehmigung ({minutes} Min.)",
+    default_decision: "Keine Antwort nach {minutes} Min.: Die Anfrage wurde standardmäßig \
+                       {decision}.",
+    decision_approved: "genehmigt",
+    decision_denied: "abgelehnt",
+    decision_aborted: "abgelehnt und der Durchlauf gestoppt",
+
+    turn_alert: "Durchlauf läuft seit {minutes} Min.",
+    approval_alert: "Diese Genehmigung ist seit {minutes} Min. unbeantwortet",
+
+    quiet_hours_digest: "Während der Ruhezeit zurückgehalten ({count})",
+    quiet_hours_decision: "Während der Ruhezeit beantwortet: Die Anfrage wurde standardmäßig \
+                           {decision}.",
+    schedule_started: "Geplanter Prompt {name} wird ausgeführt",
+    schedule_finished: "Geplanter Prompt {name} ist fertig.",
+    schedule_next_run: "Nächster Lauf: {time}",
+    approvals_pending_one: "1 weitere Freigabe ausstehend",
+    approvals_pending_many: "{count} weitere Freigaben ausstehend",
+
+    auto_approve_started: "{kind}-Anfragen mit dieser oder geringerer Risikostufe werden für die nächsten {minutes} Min. automatisch freigegeben.",
+    auto_approve_ended: "Die automatische Freigabe ist abgelaufen; Freigaben werden wieder angefragt.",
+    approval_interrupted: "Codex wurde neu gestartet, bevor diese {kind}-Freigabe beantwortet wurde; sie wurde abgebrochen. Bitte erneut anfragen.",
+    approval_withdrawn: "Anfrage zurückgezogen: Der Durchlauf wurde unterbrochen, bevor diese {kind}-Anfrage beantwortet wurde.",
+
+    session_started: "Codex-Sitzung gestartet - wartet auf deine Eingabe...",
+    interrupt_prompt: "Sag dem Modell, was es anders machen soll",
+    dropped_messages: "Diese Nachrichten aus der Warteschlange wurden nicht gesendet:",
+    queued_input: "In der Warteschlange, bis Codex den aktuellen Durchlauf beendet ({waiting} \
+                   wartend). Antworte `/now <Nachricht>`, um zu unterbrechen und sofort zu \
+                   senden.",
+    new_task: "Neue Aufgabe",
+    new_task_usage: "Verwendung: `/new-task <Prompt>`",
+    message_too_large: "Nachricht nicht zugestellt: Sie ist {size} KiB groß, das Limit liegt bei \
+                        {limit} KiB.",
+    input_held: "Nachricht zurückgehalten, bis sie im Terminal bestätigt wird.",
+    input_discarded: "Nachricht im Terminal verworfen; sie wurde nicht an Codex gesendet.",
+    continued_in_session: "**{label}** geht in einer verknüpften Sitzung weiter: `{session}`",
+    opened_from_session: "Geöffnet aus Sitzung `{session}`",
+    new_session_label: "Neue Sitzung",
+    fork_label: "Abzweigung",
+    fork_usage: "Verwendung: `/fork [N]` geht in einer neuen Unterhaltung weiter, die vor Prompt N \
+                 abzweigt (standardmäßig vor dem letzten).",
+    fork_busy: "Der Agent arbeitet noch. Sende `/now`, um ihn zu stoppen, dann `/fork`.",
+    fork_out_of_range: "Es gibt keinen Prompt {prompt}, vor dem abgezweigt werden kann; diese \
+                        Unterhaltung hat {count}.",
+    fork_failed: "Die Unterhaltung konnte nicht abgezweigt werden: {error}",
+    forked_before: "Vor Prompt {prompt} abgezweigt, den diese Unterhaltung auslässt:",
+
+    review_started: "Code-Review gestartet:",
+    review_interrupted: "Code-Review unterbrochen",
+    review_no_findings: "es wurden keine Befunde erstellt.",
+    review_finished: "Code-Review abgeschlossen:",
+    review_finished_one: "{count} Befund",
+    review_finished_many: "{count} Befunde",
+    verdict: "Urteil",
+    confidence: "Konfidenz",
+    severity_critical: "kritisch",
+    severity_high: "hoch",
+    severity_medium: "mittel",
+    severity_low: "niedrig",
+    accept_findings: "Befunde akzeptieren",
+    request_changes: "Änderungen anfordern",
+    what_changes: "Welche Änderungen möchtest du?",
+};
+
+static FR: Strings = Strings {
+    option_yes: "Oui",
+    option_always: "Toujours",
+    option_no: "Non, avec un commentaire",
+    yes_words: &["oui", "o", "approuver", "approuvé", "d'accord"],
+    always_words: &["toujours", "oui toujours", "toujours approuver"],
+    no_words: &["non", "refuser", "refusé"],
+    except_words: &["sauf", "excepté", "sans"],
+    batch_to_words: &["pour", "à"],
+    batch_rest_words: &["tout", "tous", "toutes", "reste", "le reste", "les autres"],
+    window_words: &["pour", "pendant"],
+
+    execute_command: "Exécuter la commande ?",
+    exec_default_reason: "L'agent veut exécuter une commande",
+    execute_commands: "Exécuter {count} commandes ?",
+    batch_hint: "Répondez pour toutes, ou par commande, p. ex. `oui pour 1, 3; non pour 2`.",
+    outside_sandbox_title: "Exécuter la commande hors du bac à sable ?",
+    outside_sandbox_context: "L'agent demande à exécuter cette commande sans les restrictions \
+                              du bac à sable.",
+    retry_sandbox_title: "Relancer la commande sans le bac à sable ?",
+    retry_sandbox_context: "Cette commande a échoué dans le bac à sable. L'approuver la relance \
+                            sans restrictions et la dispense du bac à sable pour le reste de la \
+                            session.",
+    justification: "Justification",
+    working_directory: "Répertoire de travail",
+    unsandboxed_warning: "Hors du bac à sable, une commande peut écrire partout où votre \
+                          utilisateur le peut et accéder au réseau.",
+    risk_high: "Risque élevé",
+    risk_medium: "Risque moyen",
+    risk_sudo: "utilise sudo",
+    risk_credentials: "accède à des identifiants",
+    risk_writes_outside: "écrit hors de l'espace de travail",
+    risk_package_install: "installe des paquets",
+    risk_network: "accès réseau",
+    near_approved: "Proche d'une commande approuvée pour cette session",
+    words_added: "ajoute",
+    words_removed: "retire",
+    patch_headline_one: "Patch proposé pour {count} fichier (+{added} -{removed})",
+    patch_headline_many: "Patch proposé pour {count} fichiers (+{added} -{removed})",
+    grant_root: "Cela accordera un accès en écriture à {root} pour le reste de la session.",
+    preview_hint: "Répondez `{command}` pour d'abord essayer le patch dans un worktree \
+                   temporaire.",
+    explain_hint: "Répondez `{command}` pour un résumé de la modification en langage clair.",
+    partial_hint: "Pour n'appliquer qu'une partie des fichiers, répondez `oui sauf 2` ou `oui sauf tests/*`.",
+    apply_changes: "Appliquer les modifications ?",
+    question_from_agent: "Question de l'agent",
+    reply_below: "Répondez ci-dessous.",
+    answer_no: "Non",
+    call_mcp_tool: "Appeler l'outil MCP ?",
+    mcp_server: "Serveur",
+    mcp_tool: "Outil",
+
+    preview_started: "Application du patch dans un worktree temporaire...",
+    preview_started_check: "Application du patch dans un worktree temporaire et exécution de \
+                            `{command}`...",
+    preview_applies: "Aperçu : le patch s'applique proprement",
+    preview_passed: "Aperçu : `{command}` a réussi ({secs} s)",
+    preview_failed: "Aperçu : `{command}` a échoué avec le code {code} ({secs} s)",
+    preview_killed: "Aperçu : `{command}` a été interrompu ({secs} s)",
+    preview_timed_out: "Aperçu : `{command}` a dépassé le délai de {secs} s",
+    preview_error: "Échec de l'aperçu",
+
+    explanation_title: "Ce que fait cette modification",
+    explanation_failed: "Impossible de résumer ce patch pour le moment.",
+
+    hunks_title: "Sections",
+    show_hunk_hint: "Répondez `{command} N` pour voir la section N en entier avant de décider.",
+    hunk_title: "Section {index} sur {count} : {path}",
+    no_such_hunk: "Il n'y a pas de section {index} ; ce patch en compte {count}.",
+
+    details_title: "Plus de détails",
+    arguments: "Arguments",
+
+    rollback_reason: "Annuler les modifications de cette session : les fichiers modifiés et \
+                      supprimés sont restaurés et les fichiers créés sont supprimés.",
+    rollback_unavailable: "L'annulation n'est disponible que dans les dépôts git.",
+    rollback_nothing: "Rien à annuler : la session n'a modifié aucun fichier.",
+    rollback_busy: "L'agent travaille encore. Envoyez `/now` pour l'arrêter, puis `/rollback`.",
+    rollback_done: "{count} fichier(s) restauré(s)",
+    rollback_failed: "Échec de l'annulation",
+    rollback_cancelled: "Annulation abandonnée ; aucun fichier n'a été modifié.",
+
+    apply_patch_busy: "L'agent travaille encore. Envoyez `/now` pour l'arrêter, puis `/apply-patch`.",
+    apply_patch_invalid: "Patch non appliqué",
+    apply_patch_waiting: "Patch pour {count} fichier(s) reçu ; en attente d'approbation dans le terminal.",
+    apply_patch_done: "Patch appliqué à {count} fichier(s)",
+    apply_patch_failed: "Échec du patch",
+    apply_patch_declined: "Patch refusé dans le terminal ; aucun fichier n'a été modifié.",
+
+    approval_reminder: "Votre approbation est toujours attendue ({minutes} min)",
+    default_decision: "Sans réponse après {minutes} min : la demande a été {decision} par \
+                       défaut.",
+    decision_approved: "approuvée",
+    decision_denied: "refusée",
+    decision_aborted: "refusée, et le tour a été arrêté",
+
+    turn_alert: "Le tour est toujours en cours après {minutes} min",
+    approval_alert: "Aucune réponse à cette approbation depuis {minutes} min",
+
+    quiet_hours_digest: "Retenu pendant les heures calmes ({count})",
+    quiet_hours_decision: "Répondu pendant les heures calmes : la demande a été {decision} par \
+                           défaut.",
+    schedule_started: "Exécution du prompt planifié {name}",
+    schedule_finished: "Le prompt planifié {name} est terminé.",
+    schedule_next_run: "Prochaine exécution : {time}",
+    approvals_pending_one: "1 autre approbation en attente",
+    approvals_pending_many: "{count} autres approbations en attente",
+
+    auto_approve_started: "Approbation automatique des demandes {kind} de ce niveau de risque ou inférieur pendant les {minutes} prochaines minutes.",
+    auto_approve_ended: "La période d'approbation automatique est terminée ; les approbations sont de nouveau demandées.",
+    approval_interrupted: "Codex a redémarré avant la réponse à cette approbation {kind} ; elle a été annulée. Redemandez pour réessayer.",
+    approval_withdrawn: "Demande retirée : le tour a été interrompu avant la réponse à cette demande {kind}.",
+
+    session_started: "Session Codex démarrée - en attente de votre saisie...",
+    interrupt_prompt: "Dites au modèle ce qu'il doit faire autrement",
+    dropped_messages: "Ces messages en file d'attente n'ont pas été envoyés :",
+    queued_input: "En attente jusqu'à ce que Codex termine le tour en cours ({waiting} en \
+                   attente). Répondez `/now <message>` pour interrompre et envoyer \
+                   immédiatement.",
+    new_task: "Nouvelle tâche",
+    new_task_usage: "Utilisation : `/new-task <prompt>`",
+    message_too_large: "Message non transmis : il fait {size} Kio, au-delà de la limite de \
+                        {limit} Kio.",
+    input_held: "Message retenu jusqu'à sa confirmation dans le terminal.",
+    input_discarded: "Message rejeté dans le terminal ; il n'a pas été envoyé à Codex.",
+    continued_in_session: "**{label}** continue dans une session liée : `{session}`",
+    opened_from_session: "Ouverte depuis la session `{session}`",
+    new_session_label: "Nouvelle session",
+    fork_label: "Embranchement",
+    fork_usage: "Utilisation : `/fork [N]` continue dans une nouvelle conversation embranchée \
+                 avant le prompt N (le dernier par défaut).",
+    fork_busy: "L'agent travaille encore. Envoyez `/now` pour l'arrêter, puis `/fork`.",
+    fork_out_of_range: "Il n'y a pas de prompt {prompt} avant lequel embrancher ; cette conversation \
+                        en compte {count}.",
+    fork_failed: "Impossible d'embrancher la conversation : {error}",
+    forked_before: "Embranchée avant le prompt {prompt}, que cette conversation laisse de côté :",
+
+    review_started: "Revue de code démarrée :",
+    review_interrupted: "Revue de code interrompue",
+    review_no_findings: "aucun constat n'a été produit.",
+    review_finished: "Revue de code terminée :",
+    review_finished_one: "{count} constat",
+    review_finished_many: "{count} constats",
+    verdict: "Verdict",
+    confidence: "confiance",
+    severity_critical: "critique",
+    severity_high: "élevée",
+    severity_medium: "moyenne",
+    severity_low: "faible",
+    accept_findings: "Accepter les constats",
+    request_changes: "Demander des modifications",
+    what_changes: "Quelles modifications souhaitez-vous ?",
+};
+
+static ES: Strings = Strings {
+    option_yes: "Sí",
+    option_always: "Siempre",
+    option_no: "No, con comentarios",
+    yes_words: &["sí", "si", "s", "aprobar", "aprobado", "vale"],
+    always_words: &["siempre", "sí siempre", "si siempre", "aprobar siempre"],
+    no_words: &["rechazar", "rechazado", "denegar"],
+    except_words: &["excepto", "salvo", "menos", "sin"],
+    batch_to_words: &["para", "a"],
+    batch_rest_words: &[
+        "todo",
+        "todos",
+        "resto",
+        "el resto",
+        "los demás",
+        "los otros",
+    ],
+    window_words: &["por", "durante"],
+
+    execute_command: "¿Ejecutar el comando?",
+    exec_default_reason: "El agente quiere ejecutar un comando",
+    execute_commands: "¿Ejecutar {count} comandos?",
+    batch_hint: "Responde para todos o por comando, p. ej. `sí para 1, 3; no para 2`.",
+    outside_sandbox_title: "¿Ejecutar el comando fuera del sandbox?",
+    outside_sandbox_context: "El agente pide ejecutar este comando sin las restricciones del \
+                              sandbox.",
+    retry_sandbox_title: "¿Reintentar el comando sin el sandbox?",
+    retry_sandbox_context: "Este comando falló dentro del sandbox. Aprobarlo lo vuelve a \
+                            ejecutar sin restricciones y omite el sandbox para él durante el \
+                            resto de la sesión.",
+    justification: "Justificación",
+    working_directory: "Directorio de trabajo",
+    unsandboxed_warning: "Fuera del sandbox, un comando puede escribir en cualquier lugar al \
+                          que tenga acceso tu usuario y acceder a la red.",
+    risk_high: "Riesgo alto",
+    risk_medium: "Riesgo medio",
+    risk_sudo: "usa sudo",
+    risk_credentials: "accede a credenciales",
+    risk_writes_outside: "escribe fuera del espacio de trabajo",
+    risk_package_install: "instala paquetes",
+    risk_network: "acceso a la red",
+    near_approved: "Parecido a un comando aprobado para esta sesión",
+    words_added: "añade",
+    words_removed: "quita",
+    patch_headline_one: "Parche propuesto para {count} archivo (+{added} -{removed})",
+    patch_headline_many: "Parche propuesto para {count} archivos (+{added} -{removed})",
+    grant_root: "Esto concederá acceso de escritura a {root} durante el resto de la sesión.",
+    preview_hint: "Responde `{command}` para probar antes el parche en un worktree temporal.",
+    explain_hint: "Responde `{command}` para un resumen del cambio en lenguaje sencillo.",
+    partial_hint: "Para aplicar solo algunos archivos, responde `sí excepto 2` o `sí excepto tests/*`.",
+    apply_changes: "¿Aplicar los cambios?",
+    question_from_agent: "Pregunta del agente",
+    reply_below: "Responde abajo.",
+    answer_no: "No",
+    call_mcp_tool: "¿Llamar a la herramienta MCP?",
+    mcp_server: "Servidor",
+    mcp_tool: "Herramienta",
+
+    preview_started: "Aplicando el parche en un worktree temporal...",
+    preview_started_check: "Aplicando el parche en un worktree temporal y ejecutando \
+                            `{command}`...",
+    preview_applies: "Vista previa: el parche se aplica sin conflictos",
+    preview_passed: "Vista previa: `{command}` se completó correctamente ({secs} s)",
+    preview_failed: "Vista previa: `{command}` falló con el código {code} ({secs} s)",
+    preview_killed: "Vista previa: `{command}` fue interrumpido ({secs} s)",
+    preview_timed_out: "Vista previa: `{command}` superó el límite de {secs} s",
+    preview_error: "La vista previa falló",
+
+    explanation_title: "Qué hace este cambio",
+    explanation_failed: "No se pudo resumir este parche en este momento.",
+
+    hunks_title: "Fragmentos",
+    show_hunk_hint: "Responde `{command} N` para ver el fragmento N completo antes de decidir.",
+    hunk_title: "Fragmento {index} de {count}: {path}",
+    no_such_hunk: "No hay fragmento {index}; este parche tiene {count}.",
+
+    details_title: "Más detalles",
+    arguments: "Argumentos",
+
+    rollback_reason: "Revertir los cambios de esta sesión: los archivos editados y eliminados \
+                      se restauran y los archivos creados se eliminan.",
+    rollback_unavailable: "Revertir solo está disponible en repositorios git.",
+    rollback_nothing: "Nada que revertir: la sesión no ha modificado ningún archivo.",
+    rollback_busy: "El agente sigue trabajando. Envía `/now` para detenerlo y luego `/rollback`.",
+    rollback_done: "{count} archivo(s) revertido(s)",
+    rollback_failed: "No se pudo revertir",
+    rollback_cancelled: "Reversión cancelada; no se modificó ningún archivo.",
+
+    apply_patch_busy: "El agente sigue trabajando. Envía `/now` para detenerlo y luego `/apply-patch`.",
+    apply_patch_invalid: "Parche no aplicado",
+    apply_patch_waiting: "Parche para {count} archivo(s) recibido; esperando aprobación en la terminal.",
+    apply_patch_done: "Parche aplicado a {count} archivo(s)",
+    apply_patch_failed: "No se pudo aplicar el parche",
+    apply_patch_declined: "Parche rechazado en la terminal; no se modificó ningún archivo.",
+
+    approval_reminder: "Sigue esperando tu aprobación ({minutes} min)",
+    default_decision: "Sin respuesta tras {minutes} min: la solicitud fue {decision} por \
+                       defecto.",
+    decision_approved: "aprobada",
+    decision_denied: "rechazada",
+    decision_aborted: "rechazada, y el turno se detuvo",
+
+    turn_alert: "El turno sigue en curso tras {minutes} min",
+    approval_alert: "Sin respuesta a esta aprobación desde hace {minutes} min",
+
+    quiet_hours_digest: "Retenido durante las horas de silencio ({count})",
+    quiet_hours_decision: "Respondido durante las horas de silencio: la solicitud fue {decision} \
+                           por defecto.",
+    schedule_started: "Ejecutando el prompt programado {name}",
+    schedule_finished: "El prompt programado {name} ha terminado.",
+    schedule_next_run: "Próxima ejecución: {time}",
+    approvals_pending_one: "1 aprobación más pendiente",
+    approvals_pending_many: "{count} aprobaciones más pendientes",
+
+    auto_approve_started: "Aprobando automáticamente las solicitudes {kind} de este nivel de riesgo o inferior durante los próximos {minutes} min.",
+    auto_approve_ended: "El periodo de aprobación automática terminó; las aprobaciones se vuelven a solicitar.",
+    approval_interrupted: "Codex se reinició antes de que se respondiera esta aprobación {kind}; se canceló. Vuelve a pedirla para reintentar.",
+    approval_withdrawn: "Solicitud retirada: el turno se interrumpió antes de que se respondiera esta solicitud {kind}.",
+
+    session_started: "Sesión de Codex iniciada - esperando tu mensaje...",
+    interrupt_prompt: "Dile al modelo qué debe hacer de otra forma",
+    dropped_messages: "Estos mensajes en cola no se enviaron:",
+    queued_input: "En cola hasta que Codex termine el turno actual ({waiting} en espera). \
+                   Responde `/now <mensaje>` para interrumpir y enviarlo de inmediato.",
+    new_task: "Nueva tarea",
+    new_task_usage: "Uso: `/new-task <prompt>`",
+    message_too_large: "Mensaje no entregado: ocupa {size} KiB, por encima del límite de \
+                        {limit} KiB.",
+    input_held: "Mensaje retenido hasta que se confirme en la terminal.",
+    input_discarded: "Mensaje descartado en la terminal; no se envió a Codex.",
+    continued_in_session: "**{label}** continúa en una sesión vinculada: `{session}`",
+    opened_from_session: "Abierta desde la sesión `{session}`",
+    new_session_label: "Nueva sesión",
+    fork_label: "Bifurcación",
+    fork_usage: "Uso: `/fork [N]` continúa en una nueva conversación bifurcada antes del \
+                 prompt N (el último por defecto).",
+    fork_busy: "El agente sigue trabajando. Envía `/now` para detenerlo y luego `/fork`.",
+    fork_out_of_range: "No hay un prompt {prompt} antes del que bifurcar; esta conversación tiene \
+                        {count}.",
+    fork_failed: "No se pudo bifurcar la conversación: {error}",
+    forked_before: "Bifurcada antes del prompt {prompt}, que esta conversación deja fuera:",
+
+    review_started: "Revisión de código iniciada:",
+    review_interrupted: "Revisión de código interrumpida",
+    review_no_findings: "no se generaron hallazgos.",
+    review_finished: "Revisión de código terminada:",
+    review_finished_one: "{count} hallazgo",
+    review_finished_many: "{count} hallazgos",
+    verdict: "Veredicto",
+    confidence: "confianza",
+    severity_critical: "crítico",
+    severity_high: "alto",
+    severity_medium: "medio",
+    severity_low: "bajo",
+    accept_findings: "Aceptar hallazgos",
+    request_changes: "Solicitar cambios",
+    what_changes: "¿Qué cambios quieres?",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn templates_keep_their_placeholders_in_every_locale() {
+        let en = strings(OmnaraLocale::En);
+        for locale in [OmnaraLocale::De, OmnaraLocale::Fr, OmnaraLocale::Es] {
+            let table = strings(locale);
+            for (english, localized) in [
+                (en.execute_commands, table.execute_commands),
+                (en.patch_headline_many, table.patch_headline_many),
+                (en.grant_root, table.grant_root),
+                (en.preview_hint, table.preview_hint),
+                (en.preview_failed, table.preview_failed),
+                (en.default_decision, table.default_decision),
+                (en.turn_alert, table.turn_alert),
+                (en.approval_alert, table.approval_alert),
+                (en.quiet_hours_digest, table.quiet_hours_digest),
+                (en.quiet_hours_decision, table.quiet_hours_decision),
+                (en.schedule_started, table.schedule_started),
+                (en.schedule_finished, table.schedule_finished),
+                (en.schedule_next_run, table.schedule_next_run),
+                (en.approvals_pending_many, table.approvals_pending_many),
+                (en.auto_approve_started, table.auto_approve_started),
+                (en.approval_interrupted, table.approval_interrupted),
+                (en.approval_withdrawn, table.approval_withdrawn),
+                (en.apply_patch_waiting, table.apply_patch_waiting),
+                (en.apply_patch_done, table.apply_patch_done),
+                (en.queued_input, table.queued_input),
+                (en.message_too_large, table.message_too_large),
+                (en.continued_in_session, table.continued_in_session),
+                (en.opened_from_session, table.opened_from_session),
+                (en.fork_out_of_range, table.fork_out_of_range),
+                (en.fork_failed, table.fork_failed),
+                (en.forked_before, table.forked_before),
+                (en.review_finished_many, table.review_finished_many),
+                (en.show_hunk_hint, table.show_hunk_hint),
+                (en.hunk_title, table.hunk_title),
+                (en.no_such_hunk, table.no_such_hunk),
+            ] {
+                let placeholders = |text: &str| {
+                    let mut names: Vec<String> = text
+                        .split('{')
+                        .skip(1)
+                        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name.to_string()))
+                        .collect();
+                    names.sort();
+                    names
+                };
+                assert_eq!(placeholders(localized), placeholders(english), "{locale:?}");
+            }
+        }
+        assert_eq!(
+            fill(
+                strings(OmnaraLocale::De).patch_headline_one,
+                &[("count", &1), ("added", &3), ("removed", &0)]
+            ),
+            "Vorgeschlagener Patch für 1 Datei (+3 -0)"
+        );
+    }
+}
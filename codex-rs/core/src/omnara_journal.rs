@@ -0,0 +1,213 @@
+//! Crash recovery journal for an Omnara bridge.
+//!
+//! The approvals a bridge has posted and not seen answered, the last-read
+//! message id, and agent notes whose send has not finished are written next
+//! to the session log on every change. When Codex restarts into the same
+//! session (`OMNARA_SESSION_ID`) after a crash, what the previous run left
+//! behind is offered as a [`Recovery`]: the notes can be sent again and the
+//! approvals, which nothing can answer any more, closed on the dashboard.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::omnara_ack::write_atomically;
+
+/// Unsent notes kept; older ones are dropped first.
+const MAX_UNSENT: usize = 50;
+
+/// An approval prompt posted to the dashboard and not yet answered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournaledApproval {
+    pub id: String,
+    /// The approval kind's label (`exec`, `patch`, …).
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct UnsentMessage {
+    seq: u64,
+    content: String,
+}
+
+/// What an interrupted run left behind.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recovery {
+    pub approvals: Vec<JournaledApproval>,
+    /// Agent notes, oldest first.
+    pub unsent: Vec<String>,
+}
+
+impl Recovery {
+    pub fn is_empty(&self) -> bool {
+        self.approvals.is_empty() && self.unsent.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct JournalState {
+    last_read_message_id: Option<String>,
+    approvals: Vec<JournaledApproval>,
+    unsent: Vec<UnsentMessage>,
+    next_seq: u64,
+    /// Left behind by earlier runs and not yet recovered or discarded.
+    #[serde(default)]
+    recovered: Recovery,
+}
+
+#[derive(Debug)]
+pub(crate) struct Journal {
+    path: PathBuf,
+    state: JournalState,
+}
+
+impl Journal {
+    /// Load the journal at `path`. Approvals and notes the previous run left
+    /// pending become its recovery.
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let mut state: JournalState = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                warn!(path = %path.display(), "Omnara journal unreadable; starting fresh: {e}");
+                JournalState::default()
+            }),
+            Err(_) => JournalState::default(),
+        };
+        let approvals = std::mem::take(&mut state.approvals);
+        state.recovered.approvals.extend(approvals);
+        let unsent = std::mem::take(&mut state.unsent);
+        state
+            .recovered
+            .unsent
+            .extend(unsent.into_iter().map(|message| message.content));
+        Self { path, state }
+    }
+
+    pub(crate) fn last_read_message_id(&self) -> Option<&str> {
+        self.state.last_read_message_id.as_deref()
+    }
+
+    pub(crate) fn set_last_read_message_id(&mut self, id: &str) {
+        if self.last_read_message_id() != Some(id) {
+            self.state.last_read_message_id = Some(id.to_string());
+            self.persist();
+        }
+    }
+
+    pub(crate) fn record_approval(&mut self, id: &str, kind: &str) {
+        if self
+            .state
+            .approvals
+            .iter()
+            .any(|approval| approval.id == id)
+        {
+            return;
+        }
+        self.state.approvals.push(JournaledApproval {
+            id: id.to_string(),
+            kind: kind.to_string(),
+        });
+        self.persist();
+    }
+
+    pub(crate) fn settle_approval(&mut self, id: &str) {
+        let before = self.state.approvals.len();
+        self.state.approvals.retain(|approval| approval.id != id);
+        if self.state.approvals.len() != before {
+            self.persist();
+        }
+    }
+
+    /// Record a note about to be sent; pass the returned number to
+    /// [`Self::finish_send`] once the server has it.
+    pub(crate) fn begin_send(&mut self, content: &str) -> u64 {
+        let seq = self.state.next_seq;
+        self.state.next_seq += 1;
+        self.state.unsent.push(UnsentMessage {
+            seq,
+            content: content.to_string(),
+        });
+        if self.state.unsent.len() > MAX_UNSENT {
+            self.state.unsent.remove(0);
+        }
+        self.persist();
+        seq
+    }
+
+    pub(crate) fn finish_send(&mut self, seq: u64) {
+        self.state.unsent.retain(|message| message.seq != seq);
+        self.persist();
+    }
+
+    /// What earlier runs left behind, unless there is nothing.
+    pub(crate) fn recovery(&self) -> Option<&Recovery> {
+        (!self.state.recovered.is_empty()).then_some(&self.state.recovered)
+    }
+
+    /// Take the recovery, recovered or discarded by the caller.
+    pub(crate) fn take_recovery(&mut self) -> Option<Recovery> {
+        let recovery = std::mem::take(&mut self.state.recovered);
+        if recovery.is_empty() {
+            return None;
+        }
+        self.persist();
+        Some(recovery)
+    }
+
+    /// Forget pending approvals and notes at a clean shutdown; the session
+    /// end closes them.
+    pub(crate) fn clear(&mut self) {
+        if self.state.approvals.is_empty() && self.state.unsent.is_empty() {
+            return;
+        }
+        self.state.approvals.clear();
+        self.state.unsent.clear();
+        self.persist();
+    }
+
+    fn persist(&self) {
+        if let Err(e) = write_atomically(&self.path, &self.state) {
+            warn!(path = %self.path.display(), "Failed to persist Omnara journal: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pending_state_becomes_the_next_runs_recovery() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("session.journal.json");
+
+        let mut journal = Journal::load(path.clone());
+        assert_eq!(journal.recovery(), None);
+        journal.set_last_read_message_id("msg-1");
+        journal.record_approval("call-1", "exec");
+        journal.record_approval("call-2", "patch");
+        journal.settle_approval("call-1");
+        let sent = journal.begin_send("✅ done");
+        journal.begin_send("📝 not sent");
+        journal.finish_send(sent);
+
+        let mut reloaded = Journal::load(path.clone());
+        assert_eq!(reloaded.last_read_message_id(), Some("msg-1"));
+        let expected = Recovery {
+            approvals: vec![JournaledApproval {
+                id: "call-2".to_string(),
+                kind: "patch".to_string(),
+            }],
+            unsent: vec!["📝 not sent".to_string()],
+        };
+        assert_eq!(reloaded.recovery(), Some(&expected));
+
+        // Offered again until it is taken.
+        assert_eq!(Journal::load(path.clone()).recovery(), Some(&expected));
+        assert_eq!(reloaded.take_recovery(), Some(expected));
+        reloaded.clear();
+        assert_eq!(Journal::load(path).recovery(), None);
+    }
+}
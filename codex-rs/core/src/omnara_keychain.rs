@@ -0,0 +1,128 @@
+//! Omnara API key storage in the OS keychain (`codex omnara login`).
+//!
+//! The key is kept through the `keyring` crate: the login keychain on
+//! macOS, the Secret Service on Linux, and the Credential Manager on
+//! Windows. Other platforms have no keychain support and keep using
+//! `OMNARA_API_KEY`.
+//!
+//! Storing a key also writes an empty `~/.omnara/codex_keychain` marker, so
+//! Codex only asks the keychain for a key once one was stored and never
+//! triggers a keychain prompt for users without one.
+
+use std::path::PathBuf;
+
+use keyring::Entry;
+use thiserror::Error;
+
+/// Keychain service name the key is stored under.
+pub const SERVICE: &str = "codex-omnara";
+const ACCOUNT: &str = "api-key";
+
+#[derive(Debug, Error)]
+pub enum KeychainError {
+    #[error("no supported OS keychain on this platform; set OMNARA_API_KEY instead")]
+    Unsupported,
+    #[error(transparent)]
+    Keyring(#[from] keyring::Error),
+}
+
+/// The keychain entry holding the key.
+fn entry() -> Result<Entry, KeychainError> {
+    // Without a platform store `keyring` falls back to an in-memory mock,
+    // which would silently lose the key.
+    if !cfg!(any(
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "windows"
+    )) {
+        return Err(KeychainError::Unsupported);
+    }
+    Ok(Entry::new(SERVICE, ACCOUNT)?)
+}
+
+/// The key in `entry`; `Ok(None)` when the item does not exist.
+fn read(entry: &Entry) -> Result<Option<String>, KeychainError> {
+    match entry.get_password() {
+        Ok(key) => Ok(Some(key.trim().to_string()).filter(|key| !key.is_empty())),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Remove the key from `entry`. Returns whether there was one.
+fn remove(entry: &Entry) -> Result<bool, KeychainError> {
+    match entry.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn marker_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".omnara").join("codex_keychain"))
+}
+
+/// Whether a key was stored with [`store_api_key`] (and not deleted since).
+/// Cheap: only checks the marker file.
+pub fn has_stored_api_key() -> bool {
+    marker_path().is_some_and(|path| path.exists())
+}
+
+/// Store `key` in the OS keychain, replacing any stored key.
+pub fn store_api_key(key: &str) -> Result<(), KeychainError> {
+    entry()?.set_password(key)?;
+    if let Some(path) = marker_path() {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(path, "");
+    }
+    Ok(())
+}
+
+/// The stored key, if any. Does not consult the keychain unless a key was
+/// stored with [`store_api_key`].
+pub fn load_api_key() -> Result<Option<String>, KeychainError> {
+    if !has_stored_api_key() {
+        return Ok(None);
+    }
+    read(&entry()?)
+}
+
+/// Remove the stored key. Returns whether there was one.
+pub fn delete_api_key() -> Result<bool, KeychainError> {
+    let existed = remove(&entry()?)?;
+    if let Some(path) = marker_path() {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(existed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn mock_entry() -> Entry {
+        let credential = keyring::mock::default_credential_builder()
+            .build(None, SERVICE, ACCOUNT)
+            .unwrap();
+        Entry::new_with_credential(credential)
+    }
+
+    #[test]
+    fn missing_key_reads_as_none_and_deletes_as_absent() {
+        let entry = mock_entry();
+        assert_eq!(read(&entry).unwrap(), None);
+        assert!(!remove(&entry).unwrap());
+    }
+
+    #[test]
+    fn stored_key_round_trips_until_removed() {
+        let entry = mock_entry();
+        entry.set_password("omn-secret\n").unwrap();
+        assert_eq!(read(&entry).unwrap(), Some("omn-secret".to_string()));
+        assert!(remove(&entry).unwrap());
+        assert_eq!(read(&entry).unwrap(), None);
+    }
+}
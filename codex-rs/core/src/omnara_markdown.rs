@@ -0,0 +1,336 @@
+//! Conversion of dashboard messages to the configured markdown dialect
+//! (`omnara.markdown_dialect`).
+//!
+//! The formatters write CommonMark. [`OmnaraClient`](crate::omnara_client::OmnaraClient)
+//! converts each message once, as it is sent or edited, so every note and
+//! prompt from either frontend comes out in the same dialect. `[OPTIONS]`
+//! blocks are left alone: the dashboard reads them whatever the dialect.
+
+use crate::config_types::OmnaraMarkdownDialect;
+
+const OPTIONS_OPEN: &str = "[OPTIONS]";
+const OPTIONS_CLOSE: &str = "[/OPTIONS]";
+
+/// `markdown` (CommonMark, with GitHub tables) in `dialect`.
+pub fn convert_markdown(markdown: &str, dialect: OmnaraMarkdownDialect) -> String {
+    if dialect == OmnaraMarkdownDialect::Gfm {
+        return markdown.to_string();
+    }
+    let lines: Vec<&str> = markdown.split('\n').collect();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    // The opening marker of the code block being copied, if any.
+    let mut fence: Option<&str> = None;
+    let mut in_options = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if let Some(marker) = fence {
+            if closes_fence(trimmed, marker) {
+                fence = None;
+                match dialect {
+                    OmnaraMarkdownDialect::Slack => out.push("```".to_string()),
+                    OmnaraMarkdownDialect::Plain => {}
+                    _ => out.push(line.to_string()),
+                }
+            } else if dialect == OmnaraMarkdownDialect::Slack {
+                out.push(escape_slack(line));
+            } else {
+                out.push(line.to_string());
+            }
+            i += 1;
+            continue;
+        }
+        if let Some(marker) = fence_marker(trimmed) {
+            fence = Some(marker);
+            match dialect {
+                OmnaraMarkdownDialect::Slack => out.push("```".to_string()),
+                OmnaraMarkdownDialect::Plain => {}
+                _ => out.push(line.to_string()),
+            }
+            i += 1;
+            continue;
+        }
+        if trimmed == OPTIONS_OPEN {
+            in_options = true;
+        }
+        if in_options {
+            in_options = trimmed != OPTIONS_CLOSE;
+            out.push(line.to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(rows) = table_len(&lines[i..]) {
+            out.extend(render_table(&lines[i..i + rows], dialect));
+            i += rows;
+            continue;
+        }
+        out.push(match dialect {
+            OmnaraMarkdownDialect::Slack | OmnaraMarkdownDialect::Plain => {
+                convert_line(line, dialect)
+            }
+            _ => line.to_string(),
+        });
+        i += 1;
+    }
+    out.join("\n")
+}
+
+/// The marker opening a fenced code block on `line`: three or more
+/// backticks or tildes.
+fn fence_marker(line: &str) -> Option<&str> {
+    let ch = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.len() - line.trim_start_matches(ch).len();
+    (len >= 3).then(|| &line[..len])
+}
+
+fn closes_fence(line: &str, marker: &str) -> bool {
+    fence_marker(line).is_some_and(|close| close.len() == line.len() && close.starts_with(marker))
+}
+
+/// Lines in the GitHub table starting `lines`, if one does: a header row, a
+/// delimiter row with as many cells, and the body rows after them.
+fn table_len(lines: &[&str]) -> Option<usize> {
+    let [header, delimiter, ..] = lines else {
+        return None;
+    };
+    if !header.contains('|') {
+        return None;
+    }
+    let delimiters = table_cells(delimiter);
+    let is_delimiter = |cell: &String| {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    };
+    if !delimiter.contains('-')
+        || delimiters.len() != table_cells(header).len()
+        || !delimiters.iter().all(is_delimiter)
+    {
+        return None;
+    }
+    let body = lines[2..]
+        .iter()
+        .take_while(|line| line.contains('|') && !line.trim().is_empty())
+        .count();
+    Some(2 + body)
+}
+
+fn table_cells(row: &str) -> Vec<String> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = row.strip_suffix('|').unwrap_or(row);
+    row.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+/// A table as aligned columns: in a code block, except in plain text.
+fn render_table(lines: &[&str], dialect: OmnaraMarkdownDialect) -> Vec<String> {
+    let rows: Vec<Vec<String>> = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 1)
+        .map(|(_, line)| {
+            table_cells(line)
+                .iter()
+                .map(|cell| convert_inline(cell, OmnaraMarkdownDialect::Plain))
+                .collect()
+        })
+        .collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let render_row = |cells: Vec<String>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        line.trim_end().to_string()
+    };
+    let mut out = Vec::with_capacity(rows.len() + 3);
+    let fenced = dialect != OmnaraMarkdownDialect::Plain;
+    if fenced {
+        out.push("```".to_string());
+    }
+    for (i, row) in rows.into_iter().enumerate() {
+        out.push(render_row(row));
+        if i == 0 {
+            out.push(render_row(widths.iter().map(|w| "-".repeat(*w)).collect()));
+        }
+    }
+    if fenced {
+        out.push("```".to_string());
+    }
+    if dialect == OmnaraMarkdownDialect::Slack {
+        for line in &mut out {
+            *line = escape_slack(line);
+        }
+    }
+    out
+}
+
+/// One line outside code blocks and tables, in Slack mrkdwn or plain text.
+fn convert_line(line: &str, dialect: OmnaraMarkdownDialect) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let body = line.trim_start();
+    if let Some(quoted) = body.strip_prefix('>') {
+        let quoted = quoted.strip_prefix(' ').unwrap_or(quoted);
+        return format!("{indent}> {}", convert_line(quoted, dialect));
+    }
+    if let Some(heading) = heading_text(body) {
+        let heading = convert_inline(heading, OmnaraMarkdownDialect::Plain);
+        return match dialect {
+            OmnaraMarkdownDialect::Slack => format!("{indent}*{}*", escape_slack(&heading)),
+            _ => format!("{indent}{heading}"),
+        };
+    }
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = body.strip_prefix(bullet) {
+            let bullet = match dialect {
+                OmnaraMarkdownDialect::Slack => "• ",
+                _ => "- ",
+            };
+            return format!("{indent}{bullet}{}", convert_inline(item, dialect));
+        }
+    }
+    format!("{indent}{}", convert_inline(body, dialect))
+}
+
+/// The text of an ATX heading (`## Title`).
+fn heading_text(line: &str) -> Option<&str> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let text = line[hashes..].strip_prefix(' ')?;
+    Some(text.trim_end_matches('#').trim_end())
+}
+
+/// Inline markup of `text`: code spans, bold, strikethrough, and links.
+fn convert_inline(text: &str, dialect: OmnaraMarkdownDialect) -> String {
+    let slack = dialect == OmnaraMarkdownDialect::Slack;
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        if ch == '`' {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let delimiter = &rest[..ticks];
+            if let Some(end) = rest[ticks..].find(delimiter) {
+                let code = &rest[ticks..ticks + end];
+                if slack {
+                    out.push('`');
+                    out.push_str(&escape_slack(code));
+                    out.push('`');
+                } else {
+                    out.push_str(code);
+                }
+                rest = &rest[2 * ticks + end..];
+            } else {
+                out.push_str(delimiter);
+                rest = &rest[ticks..];
+            }
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            out.push_str(if slack { "*" } else { "" });
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("~~") {
+            out.push_str(if slack { "~" } else { "" });
+            rest = after;
+            continue;
+        }
+        if ch == '['
+            && let Some((label, url, len)) = link_at(rest)
+        {
+            let label = convert_inline(label, OmnaraMarkdownDialect::Plain);
+            if slack {
+                out.push_str(&format!("<{url}|{}>", escape_slack(&label)));
+            } else if label == url {
+                out.push_str(url);
+            } else {
+                out.push_str(&format!("{label} ({url})"));
+            }
+            rest = &rest[len..];
+            continue;
+        }
+        match ch {
+            '&' if slack => out.push_str("&amp;"),
+            '<' if slack => out.push_str("&lt;"),
+            '>' if slack => out.push_str("&gt;"),
+            _ => out.push(ch),
+        }
+        rest = &rest[ch.len_utf8()..];
+    }
+    out
+}
+
+/// The label, URL, and length of the `[label](url)` link `text` starts with.
+fn link_at(text: &str) -> Option<(&str, &str, usize)> {
+    let close = text.find("](")?;
+    let label = &text[1..close];
+    if label.contains('[') {
+        return None;
+    }
+    let target = &text[close + 2..];
+    let end = target.find(')')?;
+    let url = target[..end].trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label, url, close + 2 + end + 1))
+}
+
+fn escape_slack(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const NOTE: &str = "## Result\n\n**Exit:** 0 · see [the log](https://example.com/log?a=1&b=2)\n- `cargo test` ~~failed~~ passed\n\n| Crate | Tests |\n|:------|------:|\n| `core` | 12 |\n| tui | 3 |\n\n```rust\nlet x = a < b;\n```\n[OPTIONS]\n1. **Yes**\n[/OPTIONS]";
+
+    #[test]
+    fn notes_are_converted_to_each_dialect() {
+        assert_eq!(convert_markdown(NOTE, OmnaraMarkdownDialect::Gfm), NOTE);
+        assert_eq!(
+            convert_markdown(NOTE, OmnaraMarkdownDialect::CommonMark),
+            "## Result\n\n**Exit:** 0 · see [the log](https://example.com/log?a=1&b=2)\n- `cargo test` ~~failed~~ passed\n\n```\nCrate  Tests\n-----  -----\ncore   12\ntui    3\n```\n\n```rust\nlet x = a < b;\n```\n[OPTIONS]\n1. **Yes**\n[/OPTIONS]"
+        );
+        assert_eq!(
+            convert_markdown(NOTE, OmnaraMarkdownDialect::Slack),
+            "*Result*\n\n*Exit:* 0 · see <https://example.com/log?a=1&b=2|the log>\n• `cargo test` ~failed~ passed\n\n```\nCrate  Tests\n-----  -----\ncore   12\ntui    3\n```\n\n```\nlet x = a &lt; b;\n```\n[OPTIONS]\n1. **Yes**\n[/OPTIONS]"
+        );
+        assert_eq!(
+            convert_markdown(NOTE, OmnaraMarkdownDialect::Plain),
+            "Result\n\nExit: 0 · see the log (https://example.com/log?a=1&b=2)\n- cargo test failed passed\n\nCrate  Tests\n-----  -----\ncore   12\ntui    3\n\nlet x = a < b;\n[OPTIONS]\n1. **Yes**\n[/OPTIONS]"
+        );
+    }
+
+    #[test]
+    fn quotes_and_unclosed_markup_survive() {
+        assert_eq!(
+            convert_markdown(
+                "> **Note:** 2 | 3 ` tick\n> - x",
+                OmnaraMarkdownDialect::Slack
+            ),
+            "> *Note:* 2 | 3 ` tick\n> • x"
+        );
+        assert_eq!(
+            convert_markdown("[not a link] (x)", OmnaraMarkdownDialect::Plain),
+            "[not a link] (x)"
+        );
+    }
+}
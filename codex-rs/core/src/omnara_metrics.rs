@@ -0,0 +1,540 @@
+//! Health metrics for the Omnara bridge, with optional exporters.
+//!
+//! Recording is always on and costs a few atomic adds per request. With the
+//! `omnara-metrics` feature, [`spawn_exporters`] serves the metrics as
+//! Prometheus text and/or pushes them to an OTLP/HTTP collector, so operators
+//! running many agents can watch bridge health centrally.
+
+use std::fmt::Write as _;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde_json::Value;
+use serde_json::json;
+
+use crate::config_types::OmnaraMetricsConfig;
+
+/// Upper bounds (seconds) of the approval latency buckets. Remote approvals
+/// take anywhere from seconds to hours, so the buckets are coarse.
+const APPROVAL_LATENCY_BOUNDS: [f64; 10] = [
+    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 900.0, 1800.0, 3600.0,
+];
+
+/// Which side of the conversation a message was posted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Agent,
+    User,
+}
+
+/// Who decided an approval prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalResolver {
+    /// A reply on the dashboard.
+    Remote,
+    /// The local user, in the TUI.
+    Local,
+}
+
+/// A point-in-time copy of the counters, for display.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OmnaraStats {
+    pub agent_messages_sent: u64,
+    pub user_messages_sent: u64,
+    pub remote_inputs_received: u64,
+    pub approvals_resolved_remotely: u64,
+    pub approvals_resolved_locally: u64,
+    /// Mean time to a dashboard reply; `None` before the first one.
+    pub average_approval_latency: Option<Duration>,
+}
+
+/// Counters, gauges, and histograms shared by an `OmnaraClient` and its
+/// clones.
+pub struct OmnaraMetrics {
+    start: SystemTime,
+    agent_messages_sent: AtomicU64,
+    user_messages_sent: AtomicU64,
+    send_failures: AtomicU64,
+    polls: AtomicU64,
+    poll_failures: AtomicU64,
+    remote_queue_depth: AtomicU64,
+    remote_inputs_received: AtomicU64,
+    approvals_resolved_remotely: AtomicU64,
+    approvals_resolved_locally: AtomicU64,
+    approval_latency: Histogram,
+}
+
+struct Histogram {
+    /// Per-bucket (not cumulative) counts; the last bucket is `+Inf`.
+    buckets: [AtomicU64; APPROVAL_LATENCY_BOUNDS.len() + 1],
+    sum_millis: AtomicU64,
+}
+
+impl Default for OmnaraMetrics {
+    fn default() -> Self {
+        Self {
+            start: SystemTime::now(),
+            agent_messages_sent: AtomicU64::new(0),
+            user_messages_sent: AtomicU64::new(0),
+            send_failures: AtomicU64::new(0),
+            polls: AtomicU64::new(0),
+            poll_failures: AtomicU64::new(0),
+            remote_queue_depth: AtomicU64::new(0),
+            remote_inputs_received: AtomicU64::new(0),
+            approvals_resolved_remotely: AtomicU64::new(0),
+            approvals_resolved_locally: AtomicU64::new(0),
+            approval_latency: Histogram {
+                buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+                sum_millis: AtomicU64::new(0),
+            },
+        }
+    }
+}
+
+impl OmnaraMetrics {
+    /// Record the outcome of posting a message.
+    pub fn record_send(&self, kind: MessageKind, ok: bool) {
+        let counter = match (ok, kind) {
+            (false, _) => &self.send_failures,
+            (true, MessageKind::Agent) => &self.agent_messages_sent,
+            (true, MessageKind::User) => &self.user_messages_sent,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record one request for pending remote messages.
+    pub fn record_poll(&self, ok: bool) {
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.poll_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of remote messages held until the running turn ends.
+    pub fn set_remote_queue_depth(&self, depth: usize) {
+        self.remote_queue_depth
+            .store(depth as u64, Ordering::Relaxed);
+    }
+
+    /// Record one message received from the dashboard.
+    pub fn record_remote_input(&self) {
+        self.remote_inputs_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an approval prompt answered by `resolver`.
+    pub fn record_approval_resolved(&self, resolver: ApprovalResolver) {
+        let counter = match resolver {
+            ApprovalResolver::Remote => &self.approvals_resolved_remotely,
+            ApprovalResolver::Local => &self.approvals_resolved_locally,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time from posting an approval prompt to the dashboard's reply.
+    pub fn observe_approval_latency(&self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let bucket = APPROVAL_LATENCY_BOUNDS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(APPROVAL_LATENCY_BOUNDS.len());
+        let histogram = &self.approval_latency;
+        histogram.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        histogram
+            .sum_millis
+            .fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// The counters as they are now.
+    pub fn stats(&self) -> OmnaraStats {
+        let answered: u64 = self.approval_latency_counts().iter().sum();
+        OmnaraStats {
+            agent_messages_sent: load(&self.agent_messages_sent),
+            user_messages_sent: load(&self.user_messages_sent),
+            remote_inputs_received: load(&self.remote_inputs_received),
+            approvals_resolved_remotely: load(&self.approvals_resolved_remotely),
+            approvals_resolved_locally: load(&self.approvals_resolved_locally),
+            average_approval_latency: (answered > 0)
+                .then(|| Duration::from_millis(load(&self.approval_latency.sum_millis) / answered)),
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "omnara_messages_sent_total",
+                "Messages posted to Omnara.",
+                "counter",
+                vec![
+                    ("{kind=\"agent\"}", load(&self.agent_messages_sent)),
+                    ("{kind=\"user\"}", load(&self.user_messages_sent)),
+                ],
+            ),
+            (
+                "omnara_send_failures_total",
+                "Messages that could not be posted to Omnara.",
+                "counter",
+                vec![("", load(&self.send_failures))],
+            ),
+            (
+                "omnara_polls_total",
+                "Requests for pending remote messages.",
+                "counter",
+                vec![("", load(&self.polls))],
+            ),
+            (
+                "omnara_poll_failures_total",
+                "Requests for pending remote messages that failed.",
+                "counter",
+                vec![("", load(&self.poll_failures))],
+            ),
+            (
+                "omnara_remote_queue_depth",
+                "Remote messages held until the running turn ends.",
+                "gauge",
+                vec![("", load(&self.remote_queue_depth))],
+            ),
+            (
+                "omnara_remote_inputs_total",
+                "Messages received from the dashboard.",
+                "counter",
+                vec![("", load(&self.remote_inputs_received))],
+            ),
+            (
+                "omnara_approvals_resolved_total",
+                "Approval prompts answered, by where the answer came from.",
+                "counter",
+                vec![
+                    ("{kind=\"remote\"}", load(&self.approvals_resolved_remotely)),
+                    ("{kind=\"local\"}", load(&self.approvals_resolved_locally)),
+                ],
+            ),
+        ];
+        for (name, help, kind, samples) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        }
+
+        let name = "omnara_approval_latency_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Time from sending an approval prompt to the dashboard reply."
+        );
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let counts = self.approval_latency_counts();
+        let mut cumulative = 0;
+        for (bound, count) in APPROVAL_LATENCY_BOUNDS.iter().zip(&counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let total: u64 = counts.iter().sum();
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {total}");
+        let _ = writeln!(out, "{name}_sum {}", self.approval_latency_sum());
+        let _ = writeln!(out, "{name}_count {total}");
+        out
+    }
+
+    /// The metrics as an OTLP/HTTP JSON `ExportMetricsServiceRequest`.
+    pub fn to_otlp_json(&self, session_id: &str) -> Value {
+        let start = unix_nanos(self.start);
+        let now = unix_nanos(SystemTime::now());
+        let sum = |name: &str, description: &str, points: Vec<(Option<&str>, u64)>| {
+            let points: Vec<Value> = points
+                .into_iter()
+                .map(|(kind, value)| {
+                    let attributes: Vec<Value> = kind
+                        .map(|kind| json!({"key": "kind", "value": {"stringValue": kind}}))
+                        .into_iter()
+                        .collect();
+                    json!({
+                        "attributes": attributes,
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "asInt": value.to_string(),
+                    })
+                })
+                .collect();
+            json!({
+                "name": name,
+                "description": description,
+                "sum": {
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                    "dataPoints": points,
+                },
+            })
+        };
+        let counts = self.approval_latency_counts();
+        let metrics = vec![
+            sum(
+                "omnara.messages.sent",
+                "Messages posted to Omnara.",
+                vec![
+                    (Some("agent"), load(&self.agent_messages_sent)),
+                    (Some("user"), load(&self.user_messages_sent)),
+                ],
+            ),
+            sum(
+                "omnara.send.failures",
+                "Messages that could not be posted to Omnara.",
+                vec![(None, load(&self.send_failures))],
+            ),
+            sum(
+                "omnara.polls",
+                "Requests for pending remote messages.",
+                vec![(None, load(&self.polls))],
+            ),
+            sum(
+                "omnara.poll.failures",
+                "Requests for pending remote messages that failed.",
+                vec![(None, load(&self.poll_failures))],
+            ),
+            sum(
+                "omnara.remote_inputs",
+                "Messages received from the dashboard.",
+                vec![(None, load(&self.remote_inputs_received))],
+            ),
+            sum(
+                "omnara.approvals.resolved",
+                "Approval prompts answered, by where the answer came from.",
+                vec![
+                    (Some("remote"), load(&self.approvals_resolved_remotely)),
+                    (Some("local"), load(&self.approvals_resolved_locally)),
+                ],
+            ),
+            json!({
+                "name": "omnara.remote_queue.depth",
+                "description": "Remote messages held until the running turn ends.",
+                "gauge": {
+                    "dataPoints": [{
+                        "timeUnixNano": now,
+                        "asInt": load(&self.remote_queue_depth).to_string(),
+                    }],
+                },
+            }),
+            json!({
+                "name": "omnara.approval.latency",
+                "description": "Time from sending an approval prompt to the dashboard reply.",
+                "unit": "s",
+                "histogram": {
+                    "aggregationTemporality": 2,
+                    "dataPoints": [{
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "count": counts.iter().sum::<u64>().to_string(),
+                        "sum": self.approval_latency_sum(),
+                        "bucketCounts": counts.iter().map(u64::to_string).collect::<Vec<_>>(),
+                        "explicitBounds": APPROVAL_LATENCY_BOUNDS,
+                    }],
+                },
+            }),
+        ];
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [
+                        {"key": "service.name", "value": {"stringValue": "codex"}},
+                        {"key": "omnara.session_id", "value": {"stringValue": session_id}},
+                    ],
+                },
+                "scopeMetrics": [{
+                    "scope": {"name": "codex.omnara"},
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+
+    fn approval_latency_counts(&self) -> Vec<u64> {
+        self.approval_latency.buckets.iter().map(load).collect()
+    }
+
+    fn approval_latency_sum(&self) -> f64 {
+        load(&self.approval_latency.sum_millis) as f64 / 1000.0
+    }
+}
+
+fn load(value: &AtomicU64) -> u64 {
+    value.load(Ordering::Relaxed)
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Start the exporters configured in `[omnara.metrics]`: a Prometheus
+/// endpoint on a background thread and/or a periodic OTLP push task. Both run
+/// for the rest of the process.
+#[cfg(feature = "omnara-metrics")]
+pub fn spawn_exporters(
+    metrics: std::sync::Arc<OmnaraMetrics>,
+    config: &OmnaraMetricsConfig,
+    session_id: uuid::Uuid,
+) {
+    if let Some(addr) = &config.prometheus_listen {
+        match tiny_http::Server::http(addr.as_str()) {
+            Ok(server) => {
+                tracing::info!(%addr, "Omnara metrics: serving Prometheus endpoint");
+                let metrics = metrics.clone();
+                std::thread::spawn(move || serve_prometheus(&server, &metrics));
+            }
+            Err(err) => {
+                tracing::warn!(%addr, %err, "Omnara metrics: cannot bind Prometheus endpoint");
+            }
+        }
+    }
+    if let Some(endpoint) = config.otlp_endpoint.clone() {
+        let interval = Duration::from_secs(config.otlp_interval_secs.max(1));
+        tracing::info!(%endpoint, "Omnara metrics: pushing OTLP");
+        tokio::spawn(async move {
+            let http = reqwest::Client::new();
+            let session_id = session_id.to_string();
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let body = metrics.to_otlp_json(&session_id);
+                match http.post(&endpoint).json(&body).send().await {
+                    Ok(resp) if resp.status().is_success() => {}
+                    Ok(resp) => {
+                        tracing::debug!(status = %resp.status(), "Omnara metrics: OTLP push rejected");
+                    }
+                    Err(err) => tracing::debug!(%err, "Omnara metrics: OTLP push failed"),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(feature = "omnara-metrics")]
+fn serve_prometheus(server: &tiny_http::Server, metrics: &OmnaraMetrics) {
+    #[allow(clippy::expect_used)]
+    let content_type =
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4"[..])
+            .expect("valid header");
+    for request in server.incoming_requests() {
+        let response = if request.url() == "/metrics" {
+            tiny_http::Response::from_string(metrics.render_prometheus())
+                .with_header(content_type.clone())
+        } else {
+            tiny_http::Response::from_string("not found").with_status_code(404)
+        };
+        let _ = request.respond(response);
+    }
+}
+
+/// Without the `omnara-metrics` feature there is nothing to export; warn if
+/// exporters were configured anyway.
+#[cfg(not(feature = "omnara-metrics"))]
+pub fn spawn_exporters(
+    _metrics: std::sync::Arc<OmnaraMetrics>,
+    config: &OmnaraMetricsConfig,
+    _session_id: uuid::Uuid,
+) {
+    if config.prometheus_listen.is_some() || config.otlp_endpoint.is_some() {
+        tracing::warn!(
+            "[omnara.metrics] is configured, but this build lacks the `omnara-metrics` feature"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn sample() -> OmnaraMetrics {
+        let metrics = OmnaraMetrics::default();
+        metrics.record_send(MessageKind::Agent, true);
+        metrics.record_send(MessageKind::Agent, true);
+        metrics.record_send(MessageKind::User, true);
+        metrics.record_send(MessageKind::Agent, false);
+        metrics.record_poll(true);
+        metrics.record_poll(false);
+        metrics.set_remote_queue_depth(2);
+        metrics.record_remote_input();
+        metrics.record_approval_resolved(ApprovalResolver::Remote);
+        metrics.record_approval_resolved(ApprovalResolver::Remote);
+        metrics.record_approval_resolved(ApprovalResolver::Local);
+        metrics.observe_approval_latency(Duration::from_millis(500));
+        metrics.observe_approval_latency(Duration::from_secs(42));
+        metrics.observe_approval_latency(Duration::from_secs(7200));
+        metrics
+    }
+
+    #[test]
+    fn prometheus_text_has_counters_and_cumulative_buckets() {
+        let text = sample().render_prometheus();
+        for line in [
+            "omnara_messages_sent_total{kind=\"agent\"} 2",
+            "omnara_messages_sent_total{kind=\"user\"} 1",
+            "omnara_send_failures_total 1",
+            "omnara_polls_total 2",
+            "omnara_poll_failures_total 1",
+            "omnara_remote_queue_depth 2",
+            "omnara_remote_inputs_total 1",
+            "omnara_approvals_resolved_total{kind=\"remote\"} 2",
+            "omnara_approvals_resolved_total{kind=\"local\"} 1",
+            "# TYPE omnara_approval_latency_seconds histogram",
+            "omnara_approval_latency_seconds_bucket{le=\"1\"} 1",
+            "omnara_approval_latency_seconds_bucket{le=\"30\"} 1",
+            "omnara_approval_latency_seconds_bucket{le=\"60\"} 2",
+            "omnara_approval_latency_seconds_bucket{le=\"3600\"} 2",
+            "omnara_approval_latency_seconds_bucket{le=\"+Inf\"} 3",
+            "omnara_approval_latency_seconds_sum 7242.5",
+            "omnara_approval_latency_seconds_count 3",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {line:?} in\n{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn otlp_histogram_uses_per_bucket_counts() {
+        let body = sample().to_otlp_json("session-1");
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let latency = metrics
+            .as_array()
+            .and_then(|m| m.iter().find(|m| m["name"] == "omnara.approval.latency"))
+            .map(|m| m["histogram"]["dataPoints"][0].clone())
+            .unwrap_or_default();
+        assert_eq!(latency["count"], "3");
+        assert_eq!(
+            latency["bucketCounts"],
+            json!(["1", "0", "0", "0", "1", "0", "0", "0", "0", "0", "1"])
+        );
+        assert_eq!(metrics[0]["sum"]["dataPoints"][0]["asInt"], "2");
+        assert_eq!(
+            body["resourceMetrics"][0]["resource"]["attributes"][1]["value"]["stringValue"],
+            "session-1"
+        );
+    }
+
+    #[test]
+    fn stats_average_the_answered_approvals() {
+        assert_eq!(
+            sample().stats(),
+            OmnaraStats {
+                agent_messages_sent: 2,
+                user_messages_sent: 1,
+                remote_inputs_received: 1,
+                approvals_resolved_remotely: 2,
+                approvals_resolved_locally: 1,
+                average_approval_latency: Some(Duration::from_millis(2414166)),
+            }
+        );
+        assert_eq!(OmnaraMetrics::default().stats(), OmnaraStats::default());
+    }
+}
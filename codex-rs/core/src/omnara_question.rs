@@ -0,0 +1,210 @@
+//! Turns that end with a clarifying question.
+//!
+//! A model asking something without the `ask_user` tool just ends its turn
+//! with a question, which the dashboard showed like any other message. When
+//! the turn's last message reads as a question, it is posted with a "reply
+//! below" hint and suggested answers in an `[OPTIONS]` block, so a mobile
+//! user can answer with a tap: the choices of a short trailing list, or
+//! yes/no for a question such as "Should I ...?".
+
+use crate::config_types::OmnaraLocale;
+use crate::omnara_approval::labels_block;
+use crate::omnara_i18n::strings;
+
+/// Most list items offered as answers; longer lists stay in the text.
+const MAX_OPTIONS: usize = 6;
+
+/// Longest list item offered as an answer, in characters.
+const MAX_OPTION_CHARS: usize = 80;
+
+/// Openings of a question answered by yes or no, lowercase.
+const YES_NO_OPENINGS: &[&str] = &[
+    "should i",
+    "shall i",
+    "do you want",
+    "would you like",
+    "want me to",
+    "can i",
+    "may i",
+    "is it ok",
+    "is that ok",
+    "is this ok",
+    "ok to",
+    "okay to",
+    "does that work",
+    "does this work",
+];
+
+/// A message ending with a question, ready to be posted as one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClarifyingQuestion {
+    /// The message, without a trailing list moved into `options`.
+    pub body: String,
+    /// Suggested answers; the user may still reply freely.
+    pub options: Vec<String>,
+}
+
+/// `message` as a question when its last paragraph asks one: the last line
+/// ends with `?`, or it is followed by a list of choices or of questions.
+/// A list of two to six short choices after the question becomes the
+/// options; a yes/no question (English openings only) gets those two.
+pub fn detect_question(message: &str, locale: OmnaraLocale) -> Option<ClarifyingQuestion> {
+    let message = message.trim_end();
+    let lines: Vec<&str> = message.lines().collect();
+    let mut end = lines.len();
+    let mut items = Vec::new();
+    while let Some(item) = end.checked_sub(1).and_then(|last| list_item(lines[last])) {
+        items.push(item);
+        end -= 1;
+    }
+    items.reverse();
+    let question = lines[..end]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())?;
+    let asked_in_list = !items.is_empty() && items.iter().all(|item| is_question(item));
+    if !is_question(question) && !asked_in_list {
+        return None;
+    }
+    let choices = !asked_in_list
+        && (2..=MAX_OPTIONS).contains(&items.len())
+        && items
+            .iter()
+            .all(|item| item.chars().count() <= MAX_OPTION_CHARS);
+    if choices {
+        return Some(ClarifyingQuestion {
+            body: lines[..end].join("\n").trim_end().to_string(),
+            options: items.iter().map(|item| item.replace("**", "")).collect(),
+        });
+    }
+    let options = if items.is_empty() && is_yes_no(question) {
+        let text = strings(locale);
+        vec![text.option_yes.to_string(), text.answer_no.to_string()]
+    } else {
+        Vec::new()
+    };
+    Some(ClarifyingQuestion {
+        body: message.to_string(),
+        options,
+    })
+}
+
+/// The message posted for `question`: its text, a reply hint, and any
+/// suggested answers.
+pub fn format_clarifying_question(question: &ClarifyingQuestion, locale: OmnaraLocale) -> String {
+    let mut msg = format!("{}\n\n💬 _{}_", question.body, strings(locale).reply_below);
+    if !question.options.is_empty() {
+        msg.push_str("\n\n");
+        msg.push_str(&labels_block(question.options.iter().map(String::as_str)));
+    }
+    msg
+}
+
+/// The text of a numbered (`1.`, `1)`) or bulleted (`-`, `*`, `•`) list
+/// item.
+fn list_item(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let rest = ["- ", "* ", "• "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .or_else(|| {
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let marker = line[digits..].strip_prefix(['.', ')'])?;
+            (digits > 0).then_some(marker.strip_prefix(' ')?)
+        })?;
+    let item = rest.trim();
+    (!item.is_empty()).then_some(item)
+}
+
+/// Whether `line` ends with a question mark, ignoring closing markup.
+fn is_question(line: &str) -> bool {
+    line.trim_end_matches(['*', '_', '`', ')', '"', '\'', ' '])
+        .ends_with(['?', '？'])
+}
+
+/// Whether the last sentence of `line` asks for a yes or no.
+fn is_yes_no(line: &str) -> bool {
+    let line = line.trim_end_matches(['*', '_', ' ']);
+    let starts = line
+        .char_indices()
+        .zip(line.chars().skip(1))
+        .filter(|((_, c), next)| matches!(c, '.' | '!' | '?' | ':') && next.is_whitespace())
+        .map(|((i, _), _)| i + 1);
+    let sentence = line[starts.last().unwrap_or(0)..]
+        .trim_start_matches(['*', '_', ' '])
+        .to_lowercase();
+    !sentence.contains(" or ")
+        && YES_NO_OPENINGS
+            .iter()
+            .any(|opening| sentence.starts_with(opening))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn question(message: &str) -> Option<ClarifyingQuestion> {
+        detect_question(message, OmnaraLocale::En)
+    }
+
+    #[test]
+    fn trailing_choices_become_options() {
+        let detected = question(
+            "I found two ways to fix the flaky test.\n\nWhich do you prefer?\n1. **Retry** the request\n2) Mock the clock\n",
+        )
+        .expect("question");
+        assert_eq!(
+            detected,
+            ClarifyingQuestion {
+                body: "I found two ways to fix the flaky test.\n\nWhich do you prefer?".to_string(),
+                options: vec![
+                    "Retry the request".to_string(),
+                    "Mock the clock".to_string()
+                ],
+            }
+        );
+        assert_eq!(
+            format_clarifying_question(&detected, OmnaraLocale::En),
+            "I found two ways to fix the flaky test.\n\nWhich do you prefer?\n\n💬 _Reply below to answer._\n\n[OPTIONS]\n1. Retry the request\n2. Mock the clock\n[/OPTIONS]"
+        );
+    }
+
+    #[test]
+    fn yes_no_questions_offer_yes_and_no() {
+        assert_eq!(
+            question("The build passes. Should I also update the changelog?")
+                .expect("question")
+                .options,
+            vec!["Yes".to_string(), "No".to_string()]
+        );
+        assert_eq!(
+            detect_question("Soll ich fortfahren? Should I push?", OmnaraLocale::De)
+                .expect("question")
+                .options,
+            vec!["Ja".to_string(), "Nein".to_string()]
+        );
+        // A choice between alternatives has no yes/no answer.
+        assert_eq!(
+            question("Should I use Postgres or SQLite?")
+                .expect("question")
+                .options,
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn statements_and_question_lists_are_told_apart() {
+        assert_eq!(question("Done. All tests pass."), None);
+        assert_eq!(question("What changed?\n```diff\n+x\n```"), None);
+        assert_eq!(question("Next steps:\n- run the tests\n- push"), None);
+        let detected =
+            question("Before I continue:\n- Which branch?\n- Keep the old API?").expect("question");
+        assert_eq!(
+            detected.body,
+            "Before I continue:\n- Which branch?\n- Keep the old API?"
+        );
+        assert_eq!(detected.options, Vec::<String>::new());
+    }
+}
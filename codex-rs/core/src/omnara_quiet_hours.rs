@@ -0,0 +1,319 @@
+//! Do-not-disturb schedule for the Omnara bridge.
+//!
+//! `[omnara.quiet_hours]` turns into [`QuietHours`]: daily time ranges read
+//! in a configured zone. While a range is on, the bridge holds its notes and
+//! posts them as one digest ([`format_quiet_hours_digest`]) when quiet hours
+//! end, and approval prompts are posted without a push notification or
+//! resolved with a default decision ([`QuietApproval`]). High-risk approvals
+//! can be exempted.
+
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::FixedOffset;
+use chrono::Local;
+use chrono::NaiveTime;
+use chrono::Timelike;
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::command_risk::RiskLevel;
+use crate::config_types::OmnaraLocale;
+use crate::config_types::OmnaraQuietHours;
+use crate::config_types::QuietHoursApprovals;
+use crate::omnara_escalation::decision_word;
+use crate::omnara_escalation::default_review_decision;
+use crate::omnara_i18n::fill;
+use crate::omnara_i18n::strings;
+use crate::protocol::ReviewDecision;
+
+const SECS_PER_DAY: u32 = 24 * 60 * 60;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QuietHoursError {
+    #[error("invalid quiet hours range {0:?} (expected \"HH:MM-HH:MM\")")]
+    Range(String),
+    #[error("invalid quiet hours timezone {0:?} (expected \"local\", \"UTC\", or \"+HH:MM\")")]
+    Timezone(String),
+}
+
+/// Zone the ranges are read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Zone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+/// What to do with an approval prompt right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuietApproval {
+    /// Post it as usual.
+    Notify,
+    /// Post it without a push notification and without escalation.
+    Silent,
+    /// Resolve it with this decision without posting it.
+    Decide(ReviewDecision),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Start and end of each range, in seconds since midnight; a range whose
+    /// end is not after its start wraps past midnight.
+    ranges: Vec<(u32, u32)>,
+    zone: Zone,
+    approvals: QuietHoursApprovals,
+    default_decision: ReviewDecision,
+    high_risk_override: bool,
+}
+
+impl QuietHours {
+    /// The configured schedule, or `None` when no ranges are set.
+    pub fn from_config(config: &OmnaraQuietHours) -> Result<Option<Self>, QuietHoursError> {
+        if config.ranges.is_empty() {
+            return Ok(None);
+        }
+        let ranges = config
+            .ranges
+            .iter()
+            .map(|range| parse_range(range).ok_or_else(|| QuietHoursError::Range(range.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let zone = parse_zone(&config.timezone)
+            .ok_or_else(|| QuietHoursError::Timezone(config.timezone.clone()))?;
+        Ok(Some(Self {
+            ranges,
+            zone,
+            approvals: config.approvals,
+            default_decision: default_review_decision(config.default_decision),
+            high_risk_override: config.high_risk_override,
+        }))
+    }
+
+    /// Whether quiet hours are on at `now`.
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        self.remaining(now).is_some()
+    }
+
+    /// How long until the range covering `now` ends, or `None` outside quiet
+    /// hours. Ranges that follow on from each other are not merged; check
+    /// again when this runs out.
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let time = self.time_of_day(now);
+        let secs = time.num_seconds_from_midnight();
+        self.ranges
+            .iter()
+            .filter_map(|&(start, end)| {
+                let until_end = if start < end {
+                    (start..end).contains(&secs).then(|| end - secs)
+                } else {
+                    (secs >= start || secs < end)
+                        .then(|| (end + SECS_PER_DAY - secs) % SECS_PER_DAY)
+                }?;
+                Some(Duration::from_secs(u64::from(until_end)) - sub_second(time))
+            })
+            .max()
+    }
+
+    /// How an approval prompt of `risk` is handled at `now`. `None` is an
+    /// unclassified prompt (e.g. an MCP tool call), never exempted.
+    pub fn approval(&self, now: DateTime<Utc>, risk: Option<RiskLevel>) -> QuietApproval {
+        if !self.is_quiet(now) || (self.high_risk_override && risk == Some(RiskLevel::High)) {
+            return QuietApproval::Notify;
+        }
+        match self.approvals {
+            QuietHoursApprovals::Silent => QuietApproval::Silent,
+            QuietHoursApprovals::Default => QuietApproval::Decide(self.default_decision),
+        }
+    }
+
+    fn time_of_day(&self, now: DateTime<Utc>) -> NaiveTime {
+        match self.zone {
+            Zone::Local => now.with_timezone(&Local).time(),
+            Zone::Fixed(offset) => now.with_timezone(&offset).time(),
+        }
+    }
+}
+
+/// Sub-second part of `time`, so `remaining` ends exactly on the minute.
+fn sub_second(time: NaiveTime) -> Duration {
+    Duration::from_nanos(u64::from(time.nanosecond().min(999_999_999)))
+}
+
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    let (start, end) = range.split_once('-')?;
+    let start = parse_time(start.trim())?;
+    let end = match end.trim() {
+        "24:00" => SECS_PER_DAY,
+        end => parse_time(end)?,
+    };
+    (start != end).then_some((start, end))
+}
+
+fn parse_time(time: &str) -> Option<u32> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .ok()
+        .map(|time| time.num_seconds_from_midnight())
+}
+
+/// `"local"`, `"UTC"`, or a fixed offset such as `"+02:00"`.
+pub(crate) fn parse_zone(zone: &str) -> Option<Zone> {
+    match zone.trim() {
+        zone if zone.eq_ignore_ascii_case("local") => Some(Zone::Local),
+        zone if zone.eq_ignore_ascii_case("utc") => FixedOffset::east_opt(0).map(Zone::Fixed),
+        zone => zone.parse().ok().map(Zone::Fixed),
+    }
+}
+
+/// The notes held during quiet hours as one message, oldest first.
+pub fn format_quiet_hours_digest(notes: &[String], locale: OmnaraLocale) -> String {
+    let headline = fill(
+        strings(locale).quiet_hours_digest,
+        &[("count", &notes.len())],
+    );
+    format!("🌙 **{headline}**\n\n{}", notes.join("\n\n---\n\n"))
+}
+
+/// Digest entry for an approval prompt resolved by quiet hours: the decision
+/// and the prompt without its options.
+pub fn format_quiet_hours_decision_note(
+    prompt: &str,
+    decision: ReviewDecision,
+    locale: OmnaraLocale,
+) -> String {
+    let note = fill(
+        strings(locale).quiet_hours_decision,
+        &[("decision", &decision_word(decision, locale))],
+    );
+    let prompt = prompt
+        .split("[OPTIONS]")
+        .next()
+        .unwrap_or_default()
+        .trim_end();
+    format!("🌙 {note}\n\n{prompt}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_types::EscalationDecision;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    fn quiet_hours(ranges: &[&str], timezone: &str) -> QuietHours {
+        QuietHours::from_config(&OmnaraQuietHours {
+            ranges: ranges.iter().map(ToString::to_string).collect(),
+            timezone: timezone.to_string(),
+            ..OmnaraQuietHours::default()
+        })
+        .expect("valid config")
+        .expect("ranges set")
+    }
+
+    fn utc(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 14, hour, minute, 0)
+            .single()
+            .expect("valid time")
+    }
+
+    #[test]
+    fn ranges_wrap_past_midnight() {
+        let quiet = quiet_hours(&["22:00-07:00"], "UTC");
+        assert_eq!(
+            quiet.remaining(utc(23, 30)),
+            Some(Duration::from_secs(7 * 3600 + 30 * 60))
+        );
+        assert_eq!(quiet.remaining(utc(6, 59)), Some(Duration::from_secs(60)));
+        assert!(!quiet.is_quiet(utc(7, 0)));
+        assert!(!quiet.is_quiet(utc(12, 0)));
+        assert!(quiet.is_quiet(utc(22, 0)));
+    }
+
+    #[test]
+    fn fixed_offsets_shift_the_ranges() {
+        let quiet = quiet_hours(&["12:30-13:00", "00:00-24:00"], "+02:00");
+        // 10:45 UTC is 12:45 at +02:00; the all-day range ends at midnight.
+        assert_eq!(
+            quiet.remaining(utc(10, 45)),
+            Some(Duration::from_secs(11 * 3600 + 15 * 60))
+        );
+    }
+
+    #[test]
+    fn invalid_ranges_and_zones_are_rejected() {
+        for (ranges, timezone, error) in [
+            (
+                vec!["22:00"],
+                "local",
+                QuietHoursError::Range("22:00".to_string()),
+            ),
+            (
+                vec!["07:00-07:00"],
+                "local",
+                QuietHoursError::Range("07:00-07:00".to_string()),
+            ),
+            (
+                vec!["22:00-07:00"],
+                "Europe/Berlin",
+                QuietHoursError::Timezone("Europe/Berlin".to_string()),
+            ),
+        ] {
+            let config = OmnaraQuietHours {
+                ranges: ranges.into_iter().map(str::to_string).collect(),
+                timezone: timezone.to_string(),
+                ..OmnaraQuietHours::default()
+            };
+            assert_eq!(QuietHours::from_config(&config), Err(error));
+        }
+        assert_eq!(
+            QuietHours::from_config(&OmnaraQuietHours::default()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn approvals_follow_the_policy_unless_high_risk() {
+        let config = OmnaraQuietHours {
+            ranges: vec!["22:00-07:00".to_string()],
+            timezone: "UTC".to_string(),
+            approvals: QuietHoursApprovals::Default,
+            default_decision: EscalationDecision::Deny,
+            high_risk_override: true,
+        };
+        let quiet = QuietHours::from_config(&config)
+            .expect("valid config")
+            .expect("ranges set");
+        assert_eq!(
+            quiet.approval(utc(23, 0), Some(RiskLevel::Low)),
+            QuietApproval::Decide(ReviewDecision::Denied)
+        );
+        assert_eq!(
+            quiet.approval(utc(23, 0), None),
+            QuietApproval::Decide(ReviewDecision::Denied)
+        );
+        assert_eq!(
+            quiet.approval(utc(23, 0), Some(RiskLevel::High)),
+            QuietApproval::Notify
+        );
+        assert_eq!(
+            quiet.approval(utc(12, 0), Some(RiskLevel::Low)),
+            QuietApproval::Notify
+        );
+        assert_eq!(
+            quiet_hours(&["22:00-07:00"], "UTC").approval(utc(23, 0), Some(RiskLevel::Medium)),
+            QuietApproval::Silent
+        );
+    }
+
+    #[test]
+    fn decision_notes_drop_the_options() {
+        let note = format_quiet_hours_decision_note(
+            "**Execute command**\n\n```bash\nls\n```\n\n[OPTIONS]\n1. Yes\n[/OPTIONS]",
+            ReviewDecision::Denied,
+            OmnaraLocale::En,
+        );
+        assert_eq!(
+            note,
+            "🌙 Answered during quiet hours: the request was denied by default.\n\n\
+             **Execute command**\n\n```bash\nls\n```"
+        );
+    }
+}
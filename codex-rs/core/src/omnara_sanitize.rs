@@ -0,0 +1,195 @@
+//! Sanitizing of messages from the Omnara dashboard before they reach the
+//! terminal or the model.
+//!
+//! Remote text ends up in TUI history cells and in `Op::UserInput`, so a
+//! message carrying terminal escape sequences could repaint the screen,
+//! retitle the window, or hide text from the person reading it locally.
+//! [`sanitize_remote_message`] removes escape sequences, control characters
+//! other than newlines and tabs, and invisible characters that change how
+//! text renders (bidirectional overrides, zero-width spaces, byte-order
+//! marks). Line endings become `\n`, and the text is normalized to NFC, so
+//! a decomposed `é` (`e` plus a combining accent) reaches the model and
+//! command matching as the one character it looks like. Messages over
+//! `omnara.max_remote_message_kb` are refused rather than cut, since half
+//! an instruction can mean something else entirely.
+
+use icu_normalizer::ComposingNormalizerBorrowed;
+use thiserror::Error;
+
+use crate::config_types::OmnaraLocale;
+use crate::omnara_i18n::fill;
+use crate::omnara_i18n::strings;
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+/// 8-bit forms of CSI and OSC.
+const C1_CSI: char = '\u{9b}';
+const C1_OSC: char = '\u{9d}';
+/// 8-bit string terminator.
+const C1_ST: char = '\u{9c}';
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RejectedMessage {
+    #[error("message is {bytes} bytes, over the {limit}-byte limit")]
+    TooLarge { bytes: usize, limit: usize },
+}
+
+/// `text` with escape sequences and unsafe characters removed, in NFC, or
+/// the reason it is refused.
+pub fn sanitize_remote_message(text: &str, max_bytes: usize) -> Result<String, RejectedMessage> {
+    if text.len() > max_bytes {
+        return Err(RejectedMessage::TooLarge {
+            bytes: text.len(),
+            limit: max_bytes,
+        });
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ESC => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']' | 'P' | 'X' | '^' | '_') => skip_string(&mut chars),
+                // Intermediate bytes, then the final byte.
+                Some(' '..='/') => {
+                    while chars.next_if(|c| matches!(c, ' '..='/')).is_some() {}
+                    chars.next();
+                }
+                _ => {}
+            },
+            C1_CSI => skip_csi(&mut chars),
+            C1_OSC => skip_string(&mut chars),
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                out.push('\n');
+            }
+            '\n' | '\t' => out.push(c),
+            c if c.is_control() || is_invisible_format(c) => {}
+            c => out.push(c),
+        }
+    }
+    let nfc = ComposingNormalizerBorrowed::new_nfc();
+    if nfc.is_normalized(&out) {
+        Ok(out)
+    } else {
+        Ok(nfc.normalize(&out).into_owned())
+    }
+}
+
+/// Skip the parameters and final byte of a control sequence.
+fn skip_csi(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    for c in chars.by_ref() {
+        if matches!(c, '@'..='~') {
+            return;
+        }
+        if !matches!(c, ' '..='?') {
+            // Malformed; drop what was consumed and stop.
+            return;
+        }
+    }
+}
+
+/// Skip an OSC, DCS, SOS, PM, or APC string up to its terminator (BEL, ST,
+/// or the end of the message).
+fn skip_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            BEL | C1_ST => return,
+            ESC if chars.next_if_eq(&'\\').is_some() => return,
+            _ => {}
+        }
+    }
+}
+
+/// Characters that render as nothing but reorder or hide the text around
+/// them. Joiners stay, since emoji and several scripts need them.
+fn is_invisible_format(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061c}'
+            | '\u{200b}'
+            | '\u{200e}'
+            | '\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{feff}'
+    )
+}
+
+/// Format the note posted when a dashboard message is refused.
+pub fn format_rejected_message_note(rejected: &RejectedMessage, locale: OmnaraLocale) -> String {
+    match rejected {
+        RejectedMessage::TooLarge { bytes, limit } => format!(
+            "🚫 {}",
+            fill(
+                strings(locale).message_too_large,
+                &[("size", &bytes.div_ceil(1024)), ("limit", &(limit / 1024))],
+            )
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn sanitize(text: &str) -> String {
+        sanitize_remote_message(text, 1024).unwrap()
+    }
+
+    #[test]
+    fn plain_text_is_unchanged() {
+        let text = "run the tests\n\tthen commit 👩‍💻 ünïcödé";
+        assert_eq!(sanitize(text), text);
+    }
+
+    #[test]
+    fn escape_sequences_are_removed() {
+        assert_eq!(sanitize("\u{1b}[2J\u{1b}[31mred\u{1b}[0m"), "red");
+        assert_eq!(sanitize("\u{1b}]0;pwned\u{7}title"), "title");
+        assert_eq!(sanitize("\u{1b}]8;;https://evil\u{1b}\\link"), "link");
+        assert_eq!(sanitize("\u{1b}Pq#0;2;0;0;0\u{1b}\\after"), "after");
+        assert_eq!(sanitize("\u{1b}(Bascii\u{1b}7saved"), "asciisaved");
+        assert_eq!(sanitize("\u{9b}1Aup\u{9d}0;x\u{9c}!"), "up!");
+        assert_eq!(sanitize("unterminated\u{1b}]0;title"), "unterminated");
+    }
+
+    #[test]
+    fn control_and_invisible_characters_are_removed() {
+        assert_eq!(sanitize("a\u{0}b\u{8}c\u{7f}d\u{85}e"), "abcde");
+        assert_eq!(sanitize("one\r\ntwo\rthree"), "one\ntwo\nthree");
+        assert_eq!(
+            sanitize("\u{feff}rm \u{202e}txt.exe\u{202c} \u{200b}-rf"),
+            "rm txt.exe -rf"
+        );
+    }
+
+    #[test]
+    fn decomposed_text_is_normalized_to_nfc() {
+        assert_eq!(sanitize("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(sanitize("A\u{30a}ngstr\u{f6}m"), "\u{c5}ngstr\u{f6}m");
+    }
+
+    #[test]
+    fn oversized_messages_are_refused() {
+        assert_eq!(
+            sanitize_remote_message(&"x".repeat(2049), 2048),
+            Err(RejectedMessage::TooLarge {
+                bytes: 2049,
+                limit: 2048
+            })
+        );
+        assert_eq!(
+            format_rejected_message_note(
+                &RejectedMessage::TooLarge {
+                    bytes: 2049,
+                    limit: 2048
+                },
+                OmnaraLocale::En
+            ),
+            "🚫 Message not delivered: it is 3 KiB, over the 2 KiB limit."
+        );
+    }
+}
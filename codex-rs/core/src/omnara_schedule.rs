@@ -0,0 +1,404 @@
+//! Prompts submitted on a schedule (`[omnara.schedules]`).
+//!
+//! Each `[omnara.schedules.<name>]` entry becomes a [`Schedule`]: a cron
+//! expression read in a configured zone, and the prompt to submit. The TUI
+//! bridge waits for [`Schedule::next_after`], submits the prompt as if it
+//! had been typed, and announces the run on the dashboard
+//! ([`format_schedule_started`]) and its end ([`format_schedule_finished`]),
+//! so an otherwise idle session can take on a recurring task unattended.
+
+use std::collections::BTreeMap;
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::NaiveTime;
+use chrono::TimeZone;
+use chrono::Timelike;
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::command_display::code_span;
+use crate::config_types::OmnaraLocale;
+use crate::config_types::OmnaraSchedule;
+use crate::omnara_i18n::fill;
+use crate::omnara_i18n::strings;
+use crate::omnara_quiet_hours::Zone;
+use crate::omnara_quiet_hours::parse_zone;
+
+/// How far ahead a run is looked for: long enough for a schedule on
+/// February 29.
+const SEARCH_DAYS: usize = 4 * 366 + 1;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("invalid cron expression {cron:?} for schedule {name:?}")]
+    Cron { name: String, cron: String },
+    #[error(
+        "invalid timezone {timezone:?} for schedule {name:?} (expected \"local\", \"UTC\", or \"+HH:MM\")"
+    )]
+    Timezone { name: String, timezone: String },
+    #[error("schedule {0:?} has an empty prompt")]
+    EmptyPrompt(String),
+}
+
+/// A named prompt and when to submit it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub name: String,
+    pub prompt: String,
+    cron: Cron,
+    zone: Zone,
+}
+
+impl Schedule {
+    pub fn from_config(name: &str, config: &OmnaraSchedule) -> Result<Self, ScheduleError> {
+        let cron = Cron::parse(&config.cron).ok_or_else(|| ScheduleError::Cron {
+            name: name.to_string(),
+            cron: config.cron.clone(),
+        })?;
+        let zone = parse_zone(&config.timezone).ok_or_else(|| ScheduleError::Timezone {
+            name: name.to_string(),
+            timezone: config.timezone.clone(),
+        })?;
+        let prompt = config.prompt.trim();
+        if prompt.is_empty() {
+            return Err(ScheduleError::EmptyPrompt(name.to_string()));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            prompt: prompt.to_string(),
+            cron,
+            zone,
+        })
+    }
+
+    /// The first run after `now`, or `None` when the expression names no
+    /// real date (e.g. `0 0 31 2 *`). A local time skipped by a daylight
+    /// saving change is skipped; one repeated by it runs once.
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = match self.zone {
+            Zone::Local => now.with_timezone(&Local).naive_local(),
+            Zone::Fixed(offset) => now.with_timezone(&offset).naive_local(),
+        };
+        let start = local.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let mut date = start.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.cron.matches_day(date) {
+                let from = if date == start.date() {
+                    start.time()
+                } else {
+                    NaiveTime::MIN
+                };
+                for hour in (from.hour()..24).filter(|hour| bit(self.cron.hours, *hour)) {
+                    let first_minute = if hour == from.hour() {
+                        from.minute()
+                    } else {
+                        0
+                    };
+                    for minute in
+                        (first_minute..60).filter(|minute| bit(self.cron.minutes, *minute))
+                    {
+                        if let Some(at) = self.to_utc(date.and_hms_opt(hour, minute, 0)?)
+                            && at > now
+                        {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    /// `at` as shown on the dashboard, in the schedule's zone.
+    pub fn display_time(&self, at: DateTime<Utc>) -> String {
+        match self.zone {
+            Zone::Local => at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            Zone::Fixed(offset) => at
+                .with_timezone(&offset)
+                .format("%Y-%m-%d %H:%M %:z")
+                .to_string(),
+        }
+    }
+
+    fn to_utc(&self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.zone {
+            Zone::Local => Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|at| at.with_timezone(&Utc)),
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(&time)
+                .single()
+                .map(|at| at.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// The configured schedules, in name order.
+pub fn schedules_from_config(
+    config: &BTreeMap<String, OmnaraSchedule>,
+) -> Result<Vec<Schedule>, ScheduleError> {
+    config
+        .iter()
+        .map(|(name, schedule)| Schedule::from_config(name, schedule))
+        .collect()
+}
+
+/// Note posted when a scheduled prompt is submitted: the schedule's name,
+/// the prompt quoted, and when it runs next.
+pub fn format_schedule_started(
+    schedule: &Schedule,
+    next: Option<DateTime<Utc>>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let headline = fill(
+        text.schedule_started,
+        &[("name", &code_span(&schedule.name))],
+    );
+    let prompt = schedule
+        .prompt
+        .lines()
+        .map(|line| format!("> {line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut msg = format!("⏰ **{headline}**\n\n{prompt}");
+    if let Some(next) = next {
+        msg.push_str("\n\n");
+        msg.push_str(&fill(
+            text.schedule_next_run,
+            &[("time", &schedule.display_time(next))],
+        ));
+    }
+    msg
+}
+
+/// Note posted when the turn a scheduled prompt started ends.
+pub fn format_schedule_finished(
+    schedule: &Schedule,
+    next: Option<DateTime<Utc>>,
+    locale: OmnaraLocale,
+) -> String {
+    let text = strings(locale);
+    let mut msg = format!(
+        "⏰ {}",
+        fill(
+            text.schedule_finished,
+            &[("name", &code_span(&schedule.name))]
+        )
+    );
+    if let Some(next) = next {
+        msg.push(' ');
+        msg.push_str(&fill(
+            text.schedule_next_run,
+            &[("time", &schedule.display_time(next))],
+        ));
+    }
+    msg
+}
+
+/// A parsed cron expression: the values each field allows, as bit masks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Bit 0 is Sunday.
+    weekdays: u64,
+    /// When both day fields are restricted, a day matching either runs, as
+    /// in cron.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Cron {
+    fn parse(expr: &str) -> Option<Self> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expr => expr,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return None;
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 is Sunday too.
+        if bit(weekdays, 7) {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Some(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// The values a cron field allows as a bit mask: a comma-separated list of
+/// `*`, `n`, or `a-b`, each optionally with a `/step`.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|step| *step > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+            // `n/step` runs from n to the end of the range.
+            None if part.contains('/') => (range.parse().ok()?, max),
+            None => {
+                let value = range.parse().ok()?;
+                (value, value)
+            }
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step) {
+            mask |= 1 << value;
+        }
+    }
+    Some(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn schedule(cron: &str, timezone: &str) -> Schedule {
+        Schedule::from_config(
+            "morning-tests",
+            &OmnaraSchedule {
+                cron: cron.to_string(),
+                prompt: "Run the test suite and report failures.".to_string(),
+                timezone: timezone.to_string(),
+            },
+        )
+        .expect("valid schedule")
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn next_run_follows_the_cron_fields_in_the_configured_zone() {
+        // Weekdays at 09:00 in UTC+02:00; 2026-10-16 is a Friday.
+        let weekdays = schedule("0 9 * * 1-5", "+02:00");
+        assert_eq!(
+            weekdays.next_after(utc("2026-10-16T06:59:30Z")),
+            Some(utc("2026-10-16T07:00:00Z"))
+        );
+        // A run is never at `now` itself, and the weekend is skipped.
+        assert_eq!(
+            weekdays.next_after(utc("2026-10-16T07:00:00Z")),
+            Some(utc("2026-10-19T07:00:00Z"))
+        );
+        assert_eq!(
+            weekdays.display_time(utc("2026-10-19T07:00:00Z")),
+            "2026-10-19 09:00 +02:00"
+        );
+
+        let every_quarter = schedule("*/15 * * * *", "UTC");
+        assert_eq!(
+            every_quarter.next_after(utc("2026-10-16T23:50:00Z")),
+            Some(utc("2026-10-17T00:00:00Z"))
+        );
+        // Both day fields restricted: either one matching runs.
+        let first_or_sunday = schedule("30 6 1 * 7", "UTC");
+        assert_eq!(
+            first_or_sunday.next_after(utc("2026-10-16T00:00:00Z")),
+            Some(utc("2026-10-18T06:30:00Z"))
+        );
+        assert_eq!(
+            schedule("@monthly", "UTC").next_after(utc("2026-10-16T00:00:00Z")),
+            Some(utc("2026-11-01T00:00:00Z"))
+        );
+        assert_eq!(
+            schedule("0 0 31 2 *", "UTC").next_after(utc("2026-10-16T00:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        let config = |cron: &str, prompt: &str| OmnaraSchedule {
+            cron: cron.to_string(),
+            prompt: prompt.to_string(),
+            timezone: "local".to_string(),
+        };
+        for cron in [
+            "0 9 * *",
+            "60 * * * *",
+            "0 9 * * mon",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert_eq!(
+                Schedule::from_config("bad", &config(cron, "hi")),
+                Err(ScheduleError::Cron {
+                    name: "bad".to_string(),
+                    cron: cron.to_string(),
+                })
+            );
+        }
+        assert_eq!(
+            Schedule::from_config("bad", &config("@daily", " ")),
+            Err(ScheduleError::EmptyPrompt("bad".to_string()))
+        );
+    }
+
+    #[test]
+    fn notes_announce_the_run_and_the_next_one() {
+        let schedule = schedule("0 9 * * *", "UTC");
+        let next = Some(utc("2026-10-18T09:00:00Z"));
+        assert_eq!(
+            format_schedule_started(&schedule, next, OmnaraLocale::En),
+            "⏰ **Running scheduled prompt `morning-tests`**\n\n\
+             > Run the test suite and report failures.\n\n\
+             Next run: 2026-10-18 09:00 +00:00"
+        );
+        assert_eq!(
+            format_schedule_finished(&schedule, next, OmnaraLocale::De),
+            "⏰ Geplanter Prompt `morning-tests` ist fertig. Nächster Lauf: 2026-10-18 09:00 +00:00"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! The report posted when an Omnara session ends: how long the session ran,
+//! its turns and commands, the files it changed, the tokens it used, and a
+//! link to its transcript.
+//!
+//! A frontend feeds every session event to a [`SessionReportBuilder`]. At
+//! shutdown it builds the [`SessionReport`] with the session diff's line
+//! counts ([`OmnaraClient::diff_stats`](crate::omnara_client::OmnaraClient::diff_stats)),
+//! uploads the transcript, and posts [`SessionReport::format_note`].
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use codex_protocol::num_format::format_with_separators;
+use tokio::time::Instant;
+
+use crate::config_types::OmnaraSessionReport;
+use crate::git_diff_tracker::DiffStatSummary;
+use crate::omnara_client::AttachmentRef;
+use crate::protocol::EventMsg;
+use crate::protocol::InputMessageKind;
+use crate::protocol::TokenUsage;
+
+/// File name of the uploaded transcript.
+pub const TRANSCRIPT_NAME: &str = "transcript.md";
+
+/// Transcript kept for upload; later messages are left out.
+const MAX_TRANSCRIPT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Collects what a session did, event by event.
+#[derive(Debug, Clone)]
+pub struct SessionReportBuilder {
+    started_at: Instant,
+    turns: usize,
+    commands: usize,
+    failed_commands: usize,
+    tokens: Option<TokenUsage>,
+    /// User and agent messages as Markdown, oldest first.
+    transcript: String,
+    transcript_truncated: bool,
+}
+
+impl SessionReportBuilder {
+    pub fn new(started_at: Instant) -> Self {
+        Self {
+            started_at,
+            turns: 0,
+            commands: 0,
+            failed_commands: 0,
+            tokens: None,
+            transcript: String::new(),
+            transcript_truncated: false,
+        }
+    }
+
+    pub fn observe(&mut self, msg: &EventMsg) {
+        match msg {
+            EventMsg::TaskStarted(_) => self.turns += 1,
+            EventMsg::ExecCommandEnd(ev) => {
+                self.commands += 1;
+                if ev.exit_code != 0 {
+                    self.failed_commands += 1;
+                }
+            }
+            EventMsg::TokenCount(ev) => {
+                if let Some(info) = &ev.info {
+                    self.tokens = Some(info.total_token_usage.clone());
+                }
+            }
+            EventMsg::UserMessage(ev)
+                if matches!(ev.kind, None | Some(InputMessageKind::Plain)) =>
+            {
+                self.push_transcript("You", &ev.message);
+            }
+            EventMsg::AgentMessage(ev) => self.push_transcript("Codex", &ev.message),
+            _ => {}
+        }
+    }
+
+    fn push_transcript(&mut self, speaker: &str, message: &str) {
+        if self.transcript_truncated {
+            return;
+        }
+        let entry = format!("## {speaker}\n\n{}\n\n", message.trim());
+        if self.transcript.len() + entry.len() > MAX_TRANSCRIPT_BYTES {
+            self.transcript
+                .push_str("_The rest of the transcript was left out._\n");
+            self.transcript_truncated = true;
+            return;
+        }
+        self.transcript.push_str(&entry);
+    }
+
+    /// The report at `now`; `diff` is the session diff's line counts, when
+    /// the session runs in a repository.
+    pub fn build(&self, now: Instant, diff: Option<&DiffStatSummary>) -> SessionReport {
+        SessionReport {
+            duration: now.saturating_duration_since(self.started_at),
+            turns: self.turns,
+            commands: self.commands,
+            failed_commands: self.failed_commands,
+            diff: diff.map(|diff| DiffTotals {
+                files: diff.files.len(),
+                insertions: diff.insertions(),
+                deletions: diff.deletions(),
+            }),
+            tokens: self.tokens.clone(),
+            transcript: self.transcript.clone(),
+        }
+    }
+}
+
+/// Files in the session diff and their changed lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffTotals {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// What a session did, as of its end.
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub duration: Duration,
+    pub turns: usize,
+    pub commands: usize,
+    pub failed_commands: usize,
+    pub diff: Option<DiffTotals>,
+    pub tokens: Option<TokenUsage>,
+    /// User and agent messages as Markdown, for upload as [`TRANSCRIPT_NAME`].
+    pub transcript: String,
+}
+
+impl SessionReport {
+    /// The report note; the cost is estimated when `settings` has both
+    /// prices, and `transcript` links the uploaded transcript.
+    pub fn format_note(
+        &self,
+        settings: &OmnaraSessionReport,
+        transcript: Option<&AttachmentRef>,
+    ) -> String {
+        let mut note = String::from("🏁 **Session report**");
+        let turns = match self.turns {
+            1 => "1 turn".to_string(),
+            n => format!("{n} turns"),
+        };
+        let _ = write!(
+            note,
+            "\n- ⏱ {} · {turns}",
+            format_session_duration(self.duration)
+        );
+        let commands = match self.commands {
+            1 => "1 command".to_string(),
+            n => format!("{n} commands"),
+        };
+        match self.failed_commands {
+            0 => {
+                let _ = write!(note, "\n- 💻 {commands}");
+            }
+            failed => {
+                let _ = write!(note, "\n- 💻 {commands} ({failed} failed)");
+            }
+        }
+        match self.diff {
+            Some(DiffTotals { files: 0, .. }) => note.push_str("\n- 📝 No files changed"),
+            Some(DiffTotals {
+                files,
+                insertions,
+                deletions,
+            }) => {
+                let files = match files {
+                    1 => "1 file".to_string(),
+                    n => format!("{n} files"),
+                };
+                let _ = write!(note, "\n- 📝 {files} changed (+{insertions} -{deletions})");
+            }
+            None => {}
+        }
+        if let Some(tokens) = self.tokens.as_ref().filter(|tokens| !tokens.is_zero()) {
+            let _ = write!(
+                note,
+                "\n- 🔢 {} tokens ({} in · {} out)",
+                format_with_separators(tokens.total_tokens),
+                format_with_separators(tokens.input_tokens),
+                format_with_separators(tokens.output_tokens)
+            );
+            if let (Some(input), Some(output)) = (
+                settings.input_usd_per_million_tokens,
+                settings.output_usd_per_million_tokens,
+            ) {
+                let cost = (tokens.input_tokens as f64 * input
+                    + tokens.output_tokens as f64 * output)
+                    / 1_000_000.0;
+                let _ = write!(note, " · ~${cost:.2}");
+            }
+        }
+        if let Some(transcript) = transcript {
+            let _ = write!(note, "\n\n📎 [Transcript]({})", transcript.url);
+        }
+        note
+    }
+}
+
+/// `1h 05m`, `4m 09s`, or `12s`.
+fn format_session_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_diff_tracker::FileStat;
+    use crate::protocol::AgentMessageEvent;
+    use crate::protocol::ExecCommandEndEvent;
+    use crate::protocol::TaskStartedEvent;
+    use crate::protocol::TokenCountEvent;
+    use crate::protocol::TokenUsageInfo;
+    use crate::protocol::UserMessageEvent;
+    use pretty_assertions::assert_eq;
+
+    fn exec_end(exit_code: i32) -> EventMsg {
+        EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+            call_id: "call".to_string(),
+            stdout: String::new(),
+            stderr: String::new(),
+            aggregated_output: String::new(),
+            exit_code,
+            duration: Duration::from_millis(10),
+            formatted_output: String::new(),
+            sandbox: None,
+        })
+    }
+
+    #[test]
+    fn report_counts_turns_commands_changes_and_tokens() {
+        let start = Instant::now();
+        let mut builder = SessionReportBuilder::new(start);
+        let usage = TokenUsage {
+            input_tokens: 800_000,
+            cached_input_tokens: 0,
+            output_tokens: 40_000,
+            reasoning_output_tokens: 0,
+            total_tokens: 840_000,
+        };
+        for msg in [
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+                traceparent: None,
+            }),
+            EventMsg::UserMessage(UserMessageEvent {
+                message: "fix the build".to_string(),
+                kind: Some(InputMessageKind::Plain),
+                images: None,
+            }),
+            EventMsg::UserMessage(UserMessageEvent {
+                message: "<environment_context />".to_string(),
+                kind: Some(InputMessageKind::EnvironmentContext),
+                images: None,
+            }),
+            exec_end(0),
+            exec_end(101),
+            exec_end(0),
+            EventMsg::AgentMessage(AgentMessageEvent {
+                message: "Fixed.".to_string(),
+            }),
+            EventMsg::TokenCount(TokenCountEvent {
+                info: Some(TokenUsageInfo {
+                    total_token_usage: usage.clone(),
+                    last_token_usage: usage,
+                    model_context_window: None,
+                }),
+                rate_limits: None,
+            }),
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+                traceparent: None,
+            }),
+        ] {
+            builder.observe(&msg);
+        }
+        let diff = DiffStatSummary {
+            files: vec![
+                FileStat {
+                    path: "src/lib.rs".to_string(),
+                    insertions: 12,
+                    deletions: 3,
+                },
+                FileStat {
+                    path: "README.md".to_string(),
+                    insertions: 1,
+                    deletions: 0,
+                },
+            ],
+        };
+        let report = builder.build(start + Duration::from_secs(3_900), Some(&diff));
+        assert_eq!(
+            report.transcript,
+            "## You\n\nfix the build\n\n## Codex\n\nFixed.\n\n"
+        );
+
+        let settings = OmnaraSessionReport {
+            enabled: true,
+            input_usd_per_million_tokens: Some(1.25),
+            output_usd_per_million_tokens: Some(10.0),
+        };
+        let transcript = AttachmentRef {
+            attachment_id: "att-1".to_string(),
+            url: "https://example.com/att-1".to_string(),
+        };
+        assert_eq!(
+            report.format_note(&settings, Some(&transcript)),
+            "🏁 **Session report**\n- ⏱ 1h 05m · 2 turns\n- 💻 3 commands (1 failed)\n- 📝 2 files changed (+13 -3)\n- 🔢 840,000 tokens (800,000 in · 40,000 out) · ~$1.40\n\n📎 [Transcript](https://example.com/att-1)"
+        );
+    }
+}
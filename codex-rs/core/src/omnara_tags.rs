@@ -0,0 +1,154 @@
+//! Session tags: labels the Omnara dashboard filters and groups sessions by.
+//!
+//! `[omnara] tags` are sent with the session metadata, next to `project` and
+//! `environment`. `/tag add <label>…`, `/tag remove <label>…`, and `/tag`
+//! change or list them later, typed in the terminal or sent from the
+//! dashboard; [`OmnaraClient::apply_tag_command`](crate::omnara_client::OmnaraClient::apply_tag_command)
+//! sends the new set.
+
+use thiserror::Error;
+
+/// Prefix of a message that changes or lists the session's tags.
+pub const TAG_COMMAND: &str = "/tag";
+
+/// Longest tag accepted, in characters.
+const MAX_TAG_CHARS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagCommand {
+    /// `/tag add <label>…`
+    Add(Vec<String>),
+    /// `/tag remove <label>…`
+    Remove(Vec<String>),
+    /// `/tag` or `/tag list`
+    List,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TagError {
+    #[error("usage: /tag [list | add <label>… | remove <label>…]")]
+    Usage,
+    #[error(
+        "invalid tag {0:?} (up to 64 letters, digits, or `-_.:/`; labels are separated by spaces or commas)"
+    )]
+    Invalid(String),
+}
+
+/// Parse the text after `/tag`.
+pub fn parse_tag_command(args: &str) -> Result<TagCommand, TagError> {
+    let mut words = args.split_whitespace();
+    let action = words.next();
+    let labels = words
+        .flat_map(|word| word.split(','))
+        .filter(|label| !label.is_empty())
+        .map(|label| {
+            if is_valid_tag(label) {
+                Ok(label.to_string())
+            } else {
+                Err(TagError::Invalid(label.to_string()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match (action, labels.is_empty()) {
+        (None, _) | (Some("list"), true) => Ok(TagCommand::List),
+        (Some("add"), false) => Ok(TagCommand::Add(labels)),
+        (Some("remove" | "rm"), false) => Ok(TagCommand::Remove(labels)),
+        _ => Err(TagError::Usage),
+    }
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    tag.chars().count() <= MAX_TAG_CHARS
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '/'))
+}
+
+/// `tags` without blanks, invalid labels, or repeats, in their first order.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && is_valid_tag(tag) && !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// The tags after `command`; listing leaves them as they are.
+pub fn apply_tag_command(tags: &[String], command: &TagCommand) -> Vec<String> {
+    match command {
+        TagCommand::Add(added) => {
+            normalize_tags(&tags.iter().chain(added).cloned().collect::<Vec<_>>())
+        }
+        TagCommand::Remove(removed) => tags
+            .iter()
+            .filter(|tag| !removed.contains(tag))
+            .cloned()
+            .collect(),
+        TagCommand::List => tags.to_vec(),
+    }
+}
+
+/// Dashboard note answering a `/tag` command.
+pub fn format_tags_note(tags: &[String]) -> String {
+    if tags.is_empty() {
+        return "🏷️ This session has no tags".to_string();
+    }
+    let tags = tags
+        .iter()
+        .map(|tag| format!("`{tag}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("🏷️ Tags: {tags}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn labels(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn commands_parse_space_and_comma_separated_labels() {
+        assert_eq!(parse_tag_command(""), Ok(TagCommand::List));
+        assert_eq!(parse_tag_command(" list "), Ok(TagCommand::List));
+        assert_eq!(
+            parse_tag_command("add backend, env:staging team/infra"),
+            Ok(TagCommand::Add(labels(&[
+                "backend",
+                "env:staging",
+                "team/infra"
+            ])))
+        );
+        assert_eq!(
+            parse_tag_command("rm backend"),
+            Ok(TagCommand::Remove(labels(&["backend"])))
+        );
+        assert_eq!(parse_tag_command("add"), Err(TagError::Usage));
+        assert_eq!(parse_tag_command("rename a b"), Err(TagError::Usage));
+        assert_eq!(
+            parse_tag_command("add `rm -rf`"),
+            Err(TagError::Invalid("`rm".to_string()))
+        );
+    }
+
+    #[test]
+    fn adding_skips_repeats_and_removing_keeps_the_order() {
+        let tags = labels(&["backend", "urgent"]);
+        let tags = apply_tag_command(
+            &tags,
+            &TagCommand::Add(labels(&["urgent", "ci", "backend"])),
+        );
+        assert_eq!(tags, labels(&["backend", "urgent", "ci"]));
+        let tags = apply_tag_command(&tags, &TagCommand::Remove(labels(&["urgent", "missing"])));
+        assert_eq!(tags, labels(&["backend", "ci"]));
+        assert_eq!(
+            normalize_tags(&labels(&[" ci ", "", "bad tag", "ci"])),
+            labels(&["ci"])
+        );
+    }
+}
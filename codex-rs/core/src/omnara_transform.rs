@@ -0,0 +1,321 @@
+//! Transformations applied to every message posted to the dashboard
+//! (`[[omnara.transformers]]`).
+//!
+//! [`OmnaraClient`](crate::omnara_client::OmnaraClient) runs the configured
+//! [`TransformPipeline`] on each note, prompt, note edit, and mirrored user
+//! message before converting it to the markdown dialect, so the steps see
+//! the CommonMark the formatters wrote. Steps run in the order configured,
+//! each only on the message kinds it lists. An `[OPTIONS]` block is left
+//! alone: the dashboard needs it intact to offer the choices.
+//!
+//! [`Truncation`] is also what the formatters use to preview command output.
+
+use regex_lite::Regex;
+use tracing::warn;
+
+use crate::config_types::OmnaraMessageKind;
+use crate::config_types::OmnaraTransformStep;
+use crate::config_types::OmnaraTransformer;
+
+/// What redacted text is replaced with.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Likely secrets matched by every `redact` step.
+const SECRET_PATTERNS: &[&str] = &[
+    // Private key blocks.
+    r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----",
+    // OpenAI/Anthropic-style API keys.
+    r"\bsk-[A-Za-z0-9_-]{20,}",
+    // GitHub tokens.
+    r"\bgh[pousr]_[A-Za-z0-9]{30,}",
+    r"\bgithub_pat_[A-Za-z0-9_]{30,}",
+    // AWS access key ids.
+    r"\bAKIA[0-9A-Z]{16}\b",
+    // Slack tokens.
+    r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    // Bearer credentials in headers or commands.
+    r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]{16,}=*",
+];
+
+const OPTIONS_OPEN: &str = "[OPTIONS]";
+
+/// Limits for cutting text to a preview: lines, characters per line, and
+/// characters in total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub max_lines: usize,
+    pub max_line_chars: usize,
+    pub max_chars: usize,
+}
+
+impl Truncation {
+    /// `line` cut to [`Self::max_line_chars`], marked with ` …` when cut.
+    pub fn clip_line(&self, line: &str) -> String {
+        if line.chars().count() <= self.max_line_chars {
+            return line.to_string();
+        }
+        let mut clipped: String = line.chars().take(self.max_line_chars).collect();
+        clipped.push_str(" …");
+        clipped
+    }
+
+    /// The leading lines of `text` within the limits, each clipped and
+    /// ending in a newline, and whether anything was cut.
+    pub fn preview(&self, text: &str) -> (String, bool) {
+        let mut preview = String::new();
+        let mut shown_lines = 0usize;
+        let mut total_chars = 0usize;
+        let mut truncated_by_chars = false;
+        for raw_line in text.lines() {
+            if shown_lines >= self.max_lines {
+                break;
+            }
+            let line = self.clip_line(raw_line);
+            let line_len = line.len() + 1; // include newline
+            if total_chars.saturating_add(line_len) > self.max_chars {
+                truncated_by_chars = true;
+                break;
+            }
+            preview.push_str(&line);
+            preview.push('\n');
+            total_chars += line_len;
+            shown_lines += 1;
+        }
+        let truncated = truncated_by_chars || shown_lines < text.lines().count();
+        (preview, truncated)
+    }
+
+    /// `text` cut to the limits, noting the cut and closing a code block it
+    /// left open.
+    fn apply(&self, text: &str) -> String {
+        let (mut preview, truncated) = self.preview(text);
+        if !truncated && preview.lines().eq(text.lines()) {
+            return text.to_string();
+        }
+        let fences = preview
+            .lines()
+            .filter(|line| line.trim_start().starts_with("```"))
+            .count();
+        if fences % 2 == 1 {
+            preview.push_str("```\n");
+        }
+        if truncated {
+            preview.push_str("… (truncated)");
+        }
+        preview.trim_end_matches('\n').to_string()
+    }
+}
+
+#[derive(Debug)]
+enum Step {
+    Redact(Vec<Regex>),
+    Truncate(Truncation),
+    StripEmoji,
+    Rewrite { pattern: Regex, replacement: String },
+}
+
+impl Step {
+    fn apply(&self, text: &str) -> String {
+        match self {
+            Step::Redact(patterns) => patterns.iter().fold(text.to_string(), |text, pattern| {
+                pattern.replace_all(&text, REDACTED).into_owned()
+            }),
+            Step::Truncate(truncation) => truncation.apply(text),
+            Step::StripEmoji => strip_emoji(text),
+            Step::Rewrite {
+                pattern,
+                replacement,
+            } => pattern.replace_all(text, replacement.as_str()).into_owned(),
+        }
+    }
+}
+
+/// The configured steps, ready to run.
+#[derive(Debug, Default)]
+pub struct TransformPipeline {
+    /// Each step with the kinds it applies to; every kind when empty.
+    steps: Vec<(Vec<OmnaraMessageKind>, Step)>,
+}
+
+impl TransformPipeline {
+    /// Compile `[[omnara.transformers]]`. A step with an invalid regex is
+    /// logged and left out; the others still run.
+    pub fn from_config(transformers: &[OmnaraTransformer]) -> Self {
+        let steps = transformers
+            .iter()
+            .filter_map(|transformer| {
+                let step = match compile(&transformer.step) {
+                    Ok(step) => step,
+                    Err(e) => {
+                        warn!("Omnara transformer {:?} skipped: {e}", transformer.step);
+                        return None;
+                    }
+                };
+                Some((transformer.applies_to.clone(), step))
+            })
+            .collect();
+        Self { steps }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// `text`, a message of `kind`, after every step that applies to it.
+    pub fn apply(&self, kind: OmnaraMessageKind, text: &str) -> String {
+        let (body, options) = match text.find(OPTIONS_OPEN) {
+            Some(start) => text.split_at(start),
+            None => (text, ""),
+        };
+        let transformed = self
+            .steps
+            .iter()
+            .filter(|(kinds, _)| kinds.is_empty() || kinds.contains(&kind))
+            .fold(body.to_string(), |body, (_, step)| step.apply(&body));
+        if options.is_empty() || transformed == body {
+            return format!("{transformed}{options}");
+        }
+        format!("{}\n\n{options}", transformed.trim_end())
+    }
+}
+
+fn compile(step: &OmnaraTransformStep) -> Result<Step, regex_lite::Error> {
+    Ok(match step {
+        OmnaraTransformStep::Redact { patterns } => Step::Redact(
+            SECRET_PATTERNS
+                .iter()
+                .copied()
+                .chain(patterns.iter().map(String::as_str))
+                .map(Regex::new)
+                .collect::<Result<_, _>>()?,
+        ),
+        OmnaraTransformStep::Truncate {
+            max_lines,
+            max_line_chars,
+            max_chars,
+        } => Step::Truncate(Truncation {
+            max_lines: max_lines.unwrap_or(usize::MAX),
+            max_line_chars: max_line_chars.unwrap_or(usize::MAX),
+            max_chars: max_chars.unwrap_or(usize::MAX),
+        }),
+        OmnaraTransformStep::StripEmoji => Step::StripEmoji,
+        OmnaraTransformStep::Rewrite {
+            pattern,
+            replacement,
+        } => Step::Rewrite {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.clone(),
+        },
+    })
+}
+
+/// `text` without emoji, and without the space that followed one.
+fn strip_emoji(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut after_emoji = false;
+    for c in text.chars() {
+        if is_emoji(c) {
+            after_emoji = true;
+            continue;
+        }
+        if !(after_emoji && c == ' ') {
+            out.push(c);
+        }
+        after_emoji = false;
+    }
+    out
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, flags, symbols
+            | 0x2600..=0x27BF // miscellaneous symbols and dingbats
+            | 0x2300..=0x23FF // technical symbols such as ⏳ and ⌛
+            | 0x2B00..=0x2BFF // arrows and stars such as ⭐
+            | 0x200D // zero-width joiner
+            | 0xFE0F // emoji presentation selector
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn pipeline(toml: &str) -> TransformPipeline {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            transformers: Vec<OmnaraTransformer>,
+        }
+        let config: Config = toml::from_str(toml).expect("valid transformers");
+        TransformPipeline::from_config(&config.transformers)
+    }
+
+    #[test]
+    fn steps_run_in_order_on_the_kinds_they_list() {
+        let pipeline = pipeline(
+            r#"
+            [[transformers]]
+            kind = "rewrite"
+            pattern = "acme-(\\w+)"
+            replacement = "<customer $1>"
+
+            [[transformers]]
+            kind = "redact"
+            patterns = ["internal\\.example\\.com"]
+
+            [[transformers]]
+            kind = "strip_emoji"
+            applies_to = ["note"]
+            "#,
+        );
+        let note = "✅ **Done** for acme-berlin on internal.example.com\n\
+                    token: sk-abcdefghijklmnopqrstuvwxyz";
+        assert_eq!(
+            pipeline.apply(OmnaraMessageKind::Note, note),
+            "**Done** for <customer berlin> on [REDACTED]\ntoken: [REDACTED]"
+        );
+        assert_eq!(
+            pipeline.apply(OmnaraMessageKind::User, "✅ acme-berlin"),
+            "✅ <customer berlin>"
+        );
+    }
+
+    #[test]
+    fn truncation_closes_code_blocks_and_keeps_options() {
+        let pipeline = pipeline(
+            r#"
+            [[transformers]]
+            kind = "truncate"
+            max_lines = 3
+            applies_to = ["prompt"]
+            "#,
+        );
+        let prompt =
+            "**Exec:** `ls`\n```text\na\nb\nc\n```\n\n[OPTIONS]\n1. Yes\n2. No\n[/OPTIONS]";
+        assert_eq!(
+            pipeline.apply(OmnaraMessageKind::Prompt, prompt),
+            "**Exec:** `ls`\n```text\na\n```\n… (truncated)\n\n[OPTIONS]\n1. Yes\n2. No\n[/OPTIONS]"
+        );
+        assert_eq!(pipeline.apply(OmnaraMessageKind::Note, prompt), prompt);
+    }
+
+    #[test]
+    fn invalid_patterns_skip_only_their_step() {
+        let pipeline = pipeline(
+            r#"
+            [[transformers]]
+            kind = "rewrite"
+            pattern = "(unclosed"
+
+            [[transformers]]
+            kind = "strip_emoji"
+            "#,
+        );
+        assert_eq!(
+            pipeline.apply(OmnaraMessageKind::Note, "🚀 (unclosed"),
+            "(unclosed"
+        );
+    }
+}
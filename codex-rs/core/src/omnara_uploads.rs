@@ -0,0 +1,310 @@
+//! Files attached to messages sent from the Omnara dashboard.
+//!
+//! With `[omnara.uploads] enabled = true`, the poller downloads each
+//! attachment that is not a voice note into the uploads directory, posts a
+//! note with the file's path and size, and forwards the message with a line
+//! naming the file so the agent can open it. The directory must resolve,
+//! symlinks included, to a place the agent may write: inside the working
+//! directory and outside its `.git`. A taken name gets a numbered suffix
+//! rather than overwriting the file.
+
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::io::Write as _;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::config_types::OmnaraUploads;
+
+/// Name of a file sent without one, or with one that is unusable.
+const FALLBACK_NAME: &str = "upload";
+
+/// Most characters kept of a file name.
+const MAX_NAME_CHARS: usize = 120;
+
+/// Numbered names tried before giving up on a taken one.
+const MAX_SUFFIX: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("the file is larger than {limit_mb} MiB")]
+    TooLarge { limit_mb: u64 },
+    #[error("invalid file URL `{0}`")]
+    InvalidUrl(String),
+    #[error("downloading the file failed with {0}")]
+    Download(StatusCode),
+    #[error("{} is outside the workspace", path.display())]
+    OutsideWorkspace { path: PathBuf },
+    #[error("{} is read-only to the agent", path.display())]
+    ReadOnly { path: PathBuf },
+    #[error("too many files named `{0}` already")]
+    NameTaken(String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// The configured uploads directory and size limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uploads {
+    workspace: PathBuf,
+    dir: PathBuf,
+    max_bytes: usize,
+}
+
+/// A file saved by [`Uploads::save`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedUpload {
+    pub path: PathBuf,
+    /// `path` relative to the workspace, with `/` separators.
+    pub display_path: String,
+    pub bytes: u64,
+}
+
+impl Uploads {
+    /// The uploads `[omnara.uploads]` configures for a session in `cwd`;
+    /// `None` unless `enabled`.
+    pub fn from_config(config: &OmnaraUploads, cwd: &Path) -> Option<Self> {
+        config.enabled.then(|| Self {
+            workspace: cwd.to_path_buf(),
+            dir: normalize(&cwd.join(&config.dir)),
+            max_bytes: (config.max_mb as usize).saturating_mul(1024 * 1024),
+        })
+    }
+
+    /// Largest file worth downloading.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Error for a file over [`Self::max_bytes`].
+    pub fn too_large(&self) -> UploadError {
+        UploadError::TooLarge {
+            limit_mb: (self.max_bytes / (1024 * 1024)) as u64,
+        }
+    }
+
+    /// Write `contents` to a new file in the uploads directory, named after
+    /// `filename` as far as it is safe to.
+    pub fn save(
+        &self,
+        filename: Option<&str>,
+        contents: &[u8],
+    ) -> Result<SavedUpload, UploadError> {
+        let workspace = self.workspace.canonicalize()?;
+        // Check the closest existing ancestor first, so a symlink out of the
+        // workspace cannot make us create directories elsewhere.
+        if let Some(existing) = self.dir.ancestors().find(|dir| dir.exists()) {
+            self.check_writable(&existing.canonicalize()?, &workspace)?;
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        let dir = self.dir.canonicalize()?;
+        self.check_writable(&dir, &workspace)?;
+
+        let name = safe_filename(filename);
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+            _ => (name.as_str(), None),
+        };
+        for n in 0..MAX_SUFFIX {
+            let candidate = match (n, extension) {
+                (0, _) => name.clone(),
+                (n, Some(extension)) => format!("{stem}-{n}.{extension}"),
+                (n, None) => format!("{stem}-{n}"),
+            };
+            let path = dir.join(&candidate);
+            // `create_new` also refuses a symlink planted under the name.
+            let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            };
+            file.write_all(contents)?;
+            let display_path = path
+                .strip_prefix(&workspace)
+                .unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            return Ok(SavedUpload {
+                path,
+                display_path,
+                bytes: contents.len() as u64,
+            });
+        }
+        Err(UploadError::NameTaken(name))
+    }
+
+    /// Refuse `dir` (resolved) unless the agent may write there: inside
+    /// `workspace` (resolved) and outside its `.git`.
+    fn check_writable(&self, dir: &Path, workspace: &Path) -> Result<(), UploadError> {
+        if !dir.starts_with(workspace) {
+            return Err(UploadError::OutsideWorkspace {
+                path: self.dir.clone(),
+            });
+        }
+        if dir.starts_with(workspace.join(".git")) {
+            return Err(UploadError::ReadOnly {
+                path: self.dir.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The last path segment of `filename` without control characters, so a
+/// name like `../../.bashrc` cannot leave the uploads directory.
+pub fn safe_filename(filename: Option<&str>) -> String {
+    let name = filename
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_CHARS)
+        .collect::<String>();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        FALLBACK_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// `12 B`, `3.4 KiB`, `1.2 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KIB {
+        format!("{bytes} B")
+    } else if size < KIB * KIB {
+        format!("{:.1} KiB", size / KIB)
+    } else {
+        format!("{:.1} MiB", size / (KIB * KIB))
+    }
+}
+
+/// Dashboard note confirming `saved`.
+pub fn format_saved_note(saved: &SavedUpload) -> String {
+    format!(
+        "📎 Saved `{}` ({})",
+        saved.display_path,
+        format_size(saved.bytes)
+    )
+}
+
+/// The user input for a message with text `content` and files saved as
+/// `saved`: the text, then a line naming each file.
+pub fn upload_input(content: &str, saved: &[SavedUpload]) -> String {
+    let content = content.trim();
+    let files = saved
+        .iter()
+        .map(|saved| {
+            format!(
+                "(attached file: {}, {})",
+                saved.display_path,
+                format_size(saved.bytes)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    match (content.is_empty(), files.is_empty()) {
+        (_, true) => content.to_string(),
+        (true, false) => files,
+        (false, false) => format!("{content}\n\n{files}"),
+    }
+}
+
+/// `path` with `.` and `..` resolved without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn uploads(cwd: &Path, dir: &str) -> Uploads {
+        Uploads::from_config(
+            &OmnaraUploads {
+                enabled: true,
+                dir: PathBuf::from(dir),
+                ..OmnaraUploads::default()
+            },
+            cwd,
+        )
+        .expect("enabled")
+    }
+
+    #[test]
+    fn files_land_in_the_uploads_dir_under_unique_names() {
+        let workspace = tempfile::tempdir().unwrap();
+        let uploads = uploads(workspace.path(), "uploads");
+
+        let first = uploads.save(Some("data.csv"), b"a,b\n1,2\n").unwrap();
+        let second = uploads.save(Some("../../data.csv"), b"a,b\n").unwrap();
+        let unnamed = uploads.save(None, b"?").unwrap();
+        assert_eq!(
+            [&first, &second, &unnamed].map(|saved| saved.display_path.as_str()),
+            ["uploads/data.csv", "uploads/data-1.csv", "uploads/upload"]
+        );
+        assert_eq!(std::fs::read(&first.path).unwrap(), b"a,b\n1,2\n");
+        assert_eq!(
+            format_saved_note(&first),
+            "📎 Saved `uploads/data.csv` (8 B)"
+        );
+        assert_eq!(
+            upload_input("Use this file", &[first]),
+            "Use this file\n\n(attached file: uploads/data.csv, 8 B)"
+        );
+        assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MiB");
+        assert_eq!(safe_filename(Some("..\\.bashrc\n")), "bashrc");
+    }
+
+    #[test]
+    fn directories_outside_the_workspace_are_refused() {
+        let workspace = tempfile::tempdir().unwrap();
+
+        let escaping = uploads(workspace.path(), "../elsewhere");
+        assert!(matches!(
+            escaping.save(Some("a.txt"), b""),
+            Err(UploadError::OutsideWorkspace { .. })
+        ));
+        let git = uploads(workspace.path(), ".git/uploads");
+        std::fs::create_dir(workspace.path().join(".git")).unwrap();
+        assert!(matches!(
+            git.save(Some("a.txt"), b""),
+            Err(UploadError::ReadOnly { .. })
+        ));
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), workspace.path().join("link")).unwrap();
+            let linked = uploads(workspace.path(), "link/uploads");
+            assert!(matches!(
+                linked.save(Some("a.txt"), b""),
+                Err(UploadError::OutsideWorkspace { .. })
+            ));
+            assert!(!outside.path().join("uploads").exists());
+        }
+    }
+}
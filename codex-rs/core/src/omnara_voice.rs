@@ -0,0 +1,246 @@
+//! Transcription of voice notes sent from the Omnara app.
+//!
+//! A dashboard message can carry audio attachments. With
+//! `[omnara.voice.transcriber]` set, the poller downloads each one, turns it
+//! into text with a [`Transcriber`] (a local whisper.cpp binary or an
+//! OpenAI-compatible API), and forwards the transcript as user input marked
+//! `(from voice)`, so the agent knows the wording may be off.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::multipart::Form;
+use reqwest::multipart::Part;
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::process::Command;
+
+use crate::config_types::OmnaraTranscriber;
+use crate::config_types::OmnaraVoice;
+
+/// Prefix of a transcript forwarded as user input.
+pub const VOICE_MARKER: &str = "(from voice)";
+
+/// Most characters of a failed command's stderr kept in the error.
+const MAX_STDERR_CHARS: usize = 500;
+
+#[derive(Debug, Error)]
+pub enum TranscriptionError {
+    #[error("the voice note is larger than {limit_mb} MiB")]
+    TooLarge { limit_mb: u64 },
+    #[error("invalid voice note URL `{0}`")]
+    InvalidUrl(String),
+    #[error("downloading the voice note failed with {0}")]
+    Download(StatusCode),
+    #[error("`{program}` failed: {stderr}")]
+    Command { program: String, stderr: String },
+    #[error("transcription took longer than {0}s")]
+    TimedOut(u64),
+    #[error("the transcription API answered {status}: {body}")]
+    Api { status: StatusCode, body: String },
+    #[error("{0} is not set")]
+    MissingApiKey(String),
+    #[error("the transcript is empty")]
+    Empty,
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A configured transcription backend with its limits.
+#[derive(Debug, Clone)]
+pub struct Transcriber {
+    backend: OmnaraTranscriber,
+    max_audio_bytes: usize,
+    timeout: Duration,
+}
+
+impl Transcriber {
+    /// The transcriber `[omnara.voice]` configures; `None` without a
+    /// `transcriber`.
+    pub fn from_config(config: &OmnaraVoice) -> Option<Self> {
+        Some(Self {
+            backend: config.transcriber.clone()?,
+            max_audio_bytes: (config.max_audio_mb as usize).saturating_mul(1024 * 1024),
+            timeout: Duration::from_secs(config.timeout_secs),
+        })
+    }
+
+    /// Largest voice note worth downloading.
+    pub fn max_audio_bytes(&self) -> usize {
+        self.max_audio_bytes
+    }
+
+    /// Error for a voice note over [`Self::max_audio_bytes`].
+    pub fn too_large(&self) -> TranscriptionError {
+        TranscriptionError::TooLarge {
+            limit_mb: (self.max_audio_bytes / (1024 * 1024)) as u64,
+        }
+    }
+
+    /// The text spoken in `audio`, a file named `filename` of type
+    /// `mime_type`. API requests go through `http`.
+    pub async fn transcribe(
+        &self,
+        http: &reqwest::Client,
+        audio: Vec<u8>,
+        filename: &str,
+        mime_type: &str,
+    ) -> Result<String, TranscriptionError> {
+        let transcribe = async move {
+            match &self.backend {
+                OmnaraTranscriber::WhisperCpp {
+                    binary,
+                    model,
+                    language,
+                } => run_whisper_cpp(binary, model, language.as_deref(), &audio, filename).await,
+                OmnaraTranscriber::Api {
+                    url,
+                    model,
+                    api_key_env,
+                    language,
+                } => {
+                    let api_key = std::env::var(api_key_env)
+                        .map_err(|_| TranscriptionError::MissingApiKey(api_key_env.clone()))?;
+                    let mut form = Form::new().text("model", model.clone()).part(
+                        "file",
+                        Part::bytes(audio)
+                            .file_name(filename.to_string())
+                            .mime_str(mime_type)?,
+                    );
+                    if let Some(language) = language {
+                        form = form.text("language", language.clone());
+                    }
+                    let resp = http
+                        .post(url)
+                        .bearer_auth(api_key)
+                        .multipart(form)
+                        .send()
+                        .await?;
+                    let status = resp.status();
+                    if !status.is_success() {
+                        let body = resp.text().await.unwrap_or_default();
+                        return Err(TranscriptionError::Api { status, body });
+                    }
+                    #[derive(Deserialize)]
+                    struct TranscriptionResponse {
+                        text: String,
+                    }
+                    Ok(resp.json::<TranscriptionResponse>().await?.text)
+                }
+            }
+        };
+        let text = tokio::time::timeout(self.timeout, transcribe)
+            .await
+            .map_err(|_| TranscriptionError::TimedOut(self.timeout.as_secs()))??;
+        let transcript = clean_transcript(&text);
+        if transcript.is_empty() {
+            return Err(TranscriptionError::Empty);
+        }
+        Ok(transcript)
+    }
+}
+
+/// Whether an attachment of type `mime_type` is a voice note.
+pub fn is_audio(mime_type: &str) -> bool {
+    mime_type.trim().to_ascii_lowercase().starts_with("audio/")
+}
+
+/// The user input for a message with text `content` and voice notes
+/// transcribed as `transcripts`: the text, then each transcript marked
+/// [`VOICE_MARKER`], separated by blank lines.
+pub fn voice_input(content: &str, transcripts: &[String]) -> String {
+    let content = content.trim();
+    let mut parts: Vec<String> = Vec::new();
+    if !content.is_empty() {
+        parts.push(content.to_string());
+    }
+    parts.extend(
+        transcripts
+            .iter()
+            .map(|transcript| format!("{VOICE_MARKER} {transcript}")),
+    );
+    parts.join("\n\n")
+}
+
+/// One line of text from a transcriber's output: whisper.cpp prints a
+/// segment per line, indented, with markers such as `[BLANK_AUDIO]` for
+/// silence.
+fn clean_transcript(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && (!line.starts_with('[') || !line.ends_with(']')))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+async fn run_whisper_cpp(
+    binary: &Path,
+    model: &Path,
+    language: Option<&str>,
+    audio: &[u8],
+    filename: &str,
+) -> Result<String, TranscriptionError> {
+    // whisper.cpp picks the decoder by extension, so keep the original one.
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| format!(".{extension}"))
+        .unwrap_or_default();
+    let file = tempfile::Builder::new()
+        .prefix("omnara-voice-")
+        .suffix(&extension)
+        .tempfile()?;
+    tokio::fs::write(file.path(), audio).await?;
+    let mut command = Command::new(binary);
+    command.arg("-m").arg(model).arg("-f").arg(file.path());
+    command.arg("-nt");
+    if let Some(language) = language {
+        command.arg("-l").arg(language);
+    }
+    let output = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(TranscriptionError::Command {
+            program: binary.display().to_string(),
+            stderr: stderr.trim().chars().take(MAX_STDERR_CHARS).collect(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn transcripts_follow_the_text_with_a_marker() {
+        assert_eq!(
+            clean_transcript("\n [BLANK_AUDIO]\n Run the tests\n and push.\n"),
+            "Run the tests and push."
+        );
+        assert_eq!(
+            voice_input("", &["Run the tests.".to_string()]),
+            "(from voice) Run the tests."
+        );
+        assert_eq!(
+            voice_input(
+                "see below ",
+                &["First this.".to_string(), "Then that.".to_string()]
+            ),
+            "see below\n\n(from voice) First this.\n\n(from voice) Then that."
+        );
+        assert!(is_audio("audio/ogg; codecs=opus"));
+        assert!(!is_audio("image/png"));
+    }
+}
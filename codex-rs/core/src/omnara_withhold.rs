@@ -0,0 +1,190 @@
+//! Paths whose content is never uploaded to Omnara (`omnara.withhold_paths`).
+//!
+//! Matching files still appear by name, with their line counts, in patch
+//! prompts, patch notes, and the session diff, but their content is replaced
+//! by [`WITHHELD_PLACEHOLDER`]. The output of a command that names a matching
+//! path (e.g. `cat secrets/prod.env`) is withheld the same way.
+//!
+//! Patterns use the gitignore syntax of `diff_exclude`, but are matched
+//! against every trailing part of a path, since patch paths are absolute and
+//! the repository root is not always known: `secrets/**` covers any
+//! `secrets` directory and `*.pem` any PEM file. When in doubt, content is
+//! withheld.
+
+use std::path::Component;
+use std::path::Path;
+
+use crate::git_diff_tracker::ExcludePattern;
+
+/// Shown in place of withheld content.
+pub const WITHHELD_PLACEHOLDER: &str = "[content withheld by policy]";
+
+#[derive(Debug, Clone, Default)]
+pub struct WithheldPaths {
+    patterns: Vec<ExcludePattern>,
+}
+
+impl WithheldPaths {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .filter_map(|pattern| ExcludePattern::parse(pattern))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether the content of `path` (absolute or relative) is withheld.
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let parts: Vec<String> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        (0..parts.len()).any(|start| {
+            let suffix = parts[start..].join("/");
+            self.patterns.iter().any(|pattern| pattern.matches(&suffix))
+        })
+    }
+
+    /// Whether any argument of `command` (including words of a shell
+    /// script passed as one argument) names a withheld path.
+    pub fn touches_command(&self, command: &[String]) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        command
+            .iter()
+            .flat_map(|arg| {
+                shlex::split(arg)
+                    .unwrap_or_else(|| arg.split_whitespace().map(str::to_string).collect())
+            })
+            .any(|word| self.matches(Path::new(&word)))
+    }
+
+    /// `diff` (git format, possibly with `# Repository:` labels) with the
+    /// sections of withheld files reduced to their `diff --git` header and a
+    /// placeholder carrying the line counts.
+    pub fn redact_diff(&self, diff: &str) -> String {
+        if self.patterns.is_empty() {
+            return diff.to_string();
+        }
+        let mut out = String::new();
+        // Lines added and removed in the withheld section being skipped.
+        let mut withheld: Option<(usize, usize)> = None;
+        let mut in_hunk = false;
+        let flush = |out: &mut String, withheld: &mut Option<(usize, usize)>| {
+            if let Some((added, removed)) = withheld.take() {
+                out.push_str(&format!("{WITHHELD_PLACEHOLDER} (+{added} -{removed})\n"));
+            }
+        };
+        for line in diff.lines() {
+            let header = line.strip_prefix("diff --git ");
+            if header.is_some()
+                || line.starts_with("# Repository: ")
+                || line.starts_with("# Excluded: ")
+            {
+                flush(&mut out, &mut withheld);
+                in_hunk = false;
+                if let Some((_, path)) = header.and_then(|header| header.rsplit_once(" b/"))
+                    && self.matches(Path::new(path))
+                {
+                    withheld = Some((0, 0));
+                }
+                out.push_str(line);
+                out.push('\n');
+                continue;
+            }
+            match &mut withheld {
+                Some((added, removed)) => {
+                    if line.starts_with("@@") {
+                        in_hunk = true;
+                    } else if in_hunk && line.starts_with('+') {
+                        *added += 1;
+                    } else if in_hunk && line.starts_with('-') {
+                        *removed += 1;
+                    }
+                }
+                None => {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+        flush(&mut out, &mut withheld);
+        if !diff.ends_with('\n') {
+            out.pop();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn policy() -> WithheldPaths {
+        WithheldPaths::new(&["secrets/**".to_string(), "*.pem".to_string()])
+    }
+
+    #[test]
+    fn patterns_match_any_trailing_part_of_a_path() {
+        let policy = policy();
+        assert!(policy.matches(Path::new("/home/me/repo/secrets/prod.env")));
+        assert!(policy.matches(Path::new("config/secrets/nested/key")));
+        assert!(policy.matches(Path::new("/etc/ssl/server.pem")));
+        assert!(!policy.matches(Path::new("/home/me/repo/src/secrets.rs")));
+        assert!(!WithheldPaths::default().matches(Path::new("secrets/prod.env")));
+
+        let cat = |script: &str| vec!["bash".to_string(), "-lc".to_string(), script.to_string()];
+        assert!(policy.touches_command(&cat("cat secrets/prod.env | head")));
+        assert!(policy.touches_command(&[
+            "openssl".to_string(),
+            "x509".to_string(),
+            "-in".to_string(),
+            "cert.pem".to_string()
+        ]));
+        assert!(!policy.touches_command(&cat("cargo test")));
+    }
+
+    #[test]
+    fn withheld_diff_sections_keep_header_and_line_counts() {
+        let diff = "# Repository: /repo\n\
+                    diff --git a/secrets/prod.env b/secrets/prod.env\n\
+                    index 1111111..2222222 100644\n\
+                    --- a/secrets/prod.env\n\
+                    +++ b/secrets/prod.env\n\
+                    @@ -1 +1,2 @@\n\
+                    -TOKEN=old\n\
+                    +TOKEN=new\n\
+                    +EXTRA=1\n\
+                    diff --git a/src/lib.rs b/src/lib.rs\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1 +1 @@\n\
+                    -a\n\
+                    +b";
+        assert_eq!(
+            policy().redact_diff(diff),
+            "# Repository: /repo\n\
+             diff --git a/secrets/prod.env b/secrets/prod.env\n\
+             [content withheld by policy] (+2 -1)\n\
+             diff --git a/src/lib.rs b/src/lib.rs\n\
+             --- a/src/lib.rs\n\
+             +++ b/src/lib.rs\n\
+             @@ -1 +1 @@\n\
+             -a\n\
+             +b"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Per-project selection among named Omnara credentials
+//! (`[omnara.workspaces.<name>]`).
+//!
+//! People with both personal and company workspaces keep one API key per
+//! workspace, each in its own environment variable. The workspace a session
+//! reports to follows its working directory: a `.codex/omnara.toml` in the
+//! directory or a parent (`workspace = "<name>"`) wins, then the first
+//! workspace, by name, with a `paths` glob matching the directory. Without a
+//! match the default `OMNARA_API_KEY` / keychain key is used.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use thiserror::Error;
+use wildmatch::WildMatch;
+
+use crate::config_types::OmnaraWorkspace;
+
+/// Per-project selection file, relative to the project directory.
+pub const PROJECT_FILE: &str = ".codex/omnara.toml";
+
+#[derive(Debug, Error)]
+pub enum WorkspaceError {
+    #[error("cannot read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("{} selects workspace `{name}`, which is not in [omnara.workspaces]", path.display())]
+    Unknown { path: PathBuf, name: String },
+}
+
+/// Why a workspace was selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceSource {
+    /// Named by this project file.
+    ProjectFile(PathBuf),
+    /// The working directory matched this `paths` glob.
+    Path(String),
+}
+
+impl std::fmt::Display for WorkspaceSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkspaceSource::ProjectFile(path) => write!(f, "from {}", path.display()),
+            WorkspaceSource::Path(glob) => write!(f, "matched `{glob}`"),
+        }
+    }
+}
+
+/// The workspace a session reports to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectedWorkspace {
+    pub name: String,
+    pub workspace: OmnaraWorkspace,
+    pub source: WorkspaceSource,
+}
+
+impl SelectedWorkspace {
+    /// The workspace's API key; None when its variable is not set.
+    pub fn api_key(&self) -> Option<String> {
+        std::env::var(&self.workspace.api_key_env).ok()
+    }
+}
+
+#[derive(Deserialize)]
+struct ProjectFile {
+    workspace: String,
+}
+
+/// The workspace for a session in `cwd`; None when no workspaces are
+/// configured or none applies. A project file naming an unknown workspace
+/// is an error rather than a fallback, so a session never reports to the
+/// wrong account.
+pub fn select_workspace(
+    workspaces: &BTreeMap<String, OmnaraWorkspace>,
+    cwd: &Path,
+) -> Result<Option<SelectedWorkspace>, WorkspaceError> {
+    if workspaces.is_empty() {
+        return Ok(None);
+    }
+    if let Some((path, name)) = project_selection(cwd)? {
+        let Some(workspace) = workspaces.get(&name) else {
+            return Err(WorkspaceError::Unknown { path, name });
+        };
+        return Ok(Some(SelectedWorkspace {
+            name,
+            workspace: workspace.clone(),
+            source: WorkspaceSource::ProjectFile(path),
+        }));
+    }
+    let home = dirs::home_dir();
+    Ok(workspaces.iter().find_map(|(name, workspace)| {
+        let glob = workspace.paths.iter().find(|glob| {
+            let pattern = WildMatch::new(&expand_home(glob, home.as_deref()));
+            cwd.ancestors()
+                .any(|dir| pattern.matches(&dir.to_string_lossy()))
+        })?;
+        Some(SelectedWorkspace {
+            name: name.clone(),
+            workspace: workspace.clone(),
+            source: WorkspaceSource::Path(glob.clone()),
+        })
+    }))
+}
+
+/// The closest project file at or above `cwd` and the workspace it names.
+fn project_selection(cwd: &Path) -> Result<Option<(PathBuf, String)>, WorkspaceError> {
+    let Some(path) = cwd
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(source) => return Err(WorkspaceError::Read { path, source }),
+    };
+    match toml::from_str::<ProjectFile>(&text) {
+        Ok(file) => Ok(Some((path, file.workspace))),
+        Err(source) => Err(WorkspaceError::Parse { path, source }),
+    }
+}
+
+/// `glob` with a leading `~` replaced by `home`.
+fn expand_home(glob: &str, home: Option<&Path>) -> String {
+    match (glob.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{rest}", home.display())
+        }
+        _ => glob.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn workspace(env: &str, paths: &[&str]) -> OmnaraWorkspace {
+        OmnaraWorkspace {
+            api_key_env: env.to_string(),
+            api_url: None,
+            paths: paths.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn project_file_wins_over_path_globs() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        let project = root.join("acme/api");
+        std::fs::create_dir_all(project.join("src")).expect("mkdir");
+        let workspaces = BTreeMap::from([
+            (
+                "company".to_string(),
+                workspace("OMNARA_WORK_KEY", &[&format!("{}/acme", root.display())]),
+            ),
+            ("personal".to_string(), workspace("OMNARA_HOME_KEY", &[])),
+        ]);
+
+        let selected = select_workspace(&workspaces, &project.join("src"))
+            .expect("selection")
+            .expect("workspace");
+        assert_eq!(selected.name, "company");
+        assert_eq!(
+            selected.source,
+            WorkspaceSource::Path(format!("{}/acme", root.display()))
+        );
+        assert_eq!(
+            select_workspace(&workspaces, root).expect("selection"),
+            None
+        );
+
+        std::fs::create_dir_all(project.join(".codex")).expect("mkdir");
+        std::fs::write(project.join(PROJECT_FILE), "workspace = \"personal\"\n").expect("write");
+        let selected = select_workspace(&workspaces, &project.join("src"))
+            .expect("selection")
+            .expect("workspace");
+        assert_eq!(selected.name, "personal");
+        assert_eq!(
+            selected.source,
+            WorkspaceSource::ProjectFile(project.join(PROJECT_FILE))
+        );
+
+        std::fs::write(project.join(PROJECT_FILE), "workspace = \"other\"\n").expect("write");
+        assert!(matches!(
+            select_workspace(&workspaces, &project),
+            Err(WorkspaceError::Unknown { name, .. }) if name == "other"
+        ));
+        // Without configured workspaces the project file is not consulted.
+        assert_eq!(
+            select_workspace(&BTreeMap::new(), &project).expect("selection"),
+            None
+        );
+    }
+
+    #[test]
+    fn leading_tilde_is_the_home_directory() {
+        let home = Path::new("/home/me");
+        assert_eq!(expand_home("~/work/*", Some(home)), "/home/me/work/*");
+        assert_eq!(expand_home("~", Some(home)), "/home/me");
+        assert_eq!(expand_home("~other/x", Some(home)), "~other/x");
+        assert_eq!(expand_home("~/x", None), "~/x");
+    }
+}
@@ -4,7 +4,9 @@ use serde_json::Value as JsonValue;
 use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::time::Duration;
 
+use crate::ask_user_tool::ASK_USER_TOOL;
 use crate::model_family::ModelFamily;
 use crate::plan_tool::PLAN_TOOL;
 use crate::tool_apply_patch::ApplyPatchToolType;
@@ -68,6 +70,8 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    /// Set when the `ask_user` tool is offered; how long a call waits.
+    pub ask_user_timeout: Option<Duration>,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -78,6 +82,7 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) ask_user_timeout: Option<Duration>,
 }
 
 impl ToolsConfig {
@@ -90,6 +95,7 @@ impl ToolsConfig {
             use_streamable_shell_tool,
             include_view_image_tool,
             experimental_unified_exec_tool,
+            ask_user_timeout,
         } = params;
         let shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::Streamable
@@ -118,6 +124,7 @@ impl ToolsConfig {
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            ask_user_timeout: *ask_user_timeout,
         }
     }
 }
@@ -507,6 +514,10 @@ pub(crate) fn get_openai_tools(
         tools.push(PLAN_TOOL.clone());
     }
 
+    if config.ask_user_timeout.is_some() {
+        tools.push(ASK_USER_TOOL.clone());
+    }
+
     if let Some(apply_patch_tool_type) = &config.apply_patch_tool_type {
         match apply_patch_tool_type {
             ApplyPatchToolType::Freeform => {
@@ -588,6 +599,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -608,6 +620,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -628,6 +641,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
         let tools = get_openai_tools(
             &config,
@@ -732,6 +746,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -808,6 +823,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
 
         let tools = get_openai_tools(
@@ -869,6 +885,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
 
         let tools = get_openai_tools(
@@ -925,6 +942,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
 
         let tools = get_openai_tools(
@@ -984,6 +1002,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            ask_user_timeout: None,
         });
 
         let tools = get_openai_tools(
@@ -0,0 +1,434 @@
+//! Session diff for files outside version control.
+//!
+//! The other [`VcsDiffTracker`] backends ask a repository what changed, so a
+//! project without one had no change tracking at all. A [`PatchLedger`]
+//! instead records every file an applied patch touches, with its content from
+//! before the session first changed it, and diffs those snapshots against
+//! the files on disk. Files changed by commands rather than patches go
+//! unseen.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use similar::TextDiff;
+
+use crate::git_diff_tracker::DiffLimits;
+use crate::git_diff_tracker::DiffOutput;
+use crate::git_diff_tracker::DiffStatSummary;
+use crate::git_diff_tracker::FileDiff;
+use crate::git_diff_tracker::FileStat;
+use crate::git_diff_tracker::changed_since;
+use crate::git_diff_tracker::load_exclude_patterns;
+use crate::protocol::FileChange;
+use crate::session_rollback::RollbackFile;
+use crate::vcs_diff_tracker::VcsDiffTracker;
+use crate::vcs_diff_tracker::exclude_and_assemble;
+
+/// Before-snapshots of the files patches changed this session.
+#[derive(Debug)]
+pub struct PatchLedger {
+    root: PathBuf,
+    /// Content of each recorded file before the session first changed it;
+    /// `None` for a file the session created.
+    before: BTreeMap<PathBuf, Option<Vec<u8>>>,
+    last_diff_hash: Option<String>,
+    config_excludes: Vec<String>,
+    limits: DiffLimits,
+}
+
+impl PatchLedger {
+    /// An empty ledger; diff paths are shown relative to `root`.
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            before: BTreeMap::new(),
+            last_diff_hash: None,
+            config_excludes: Vec::new(),
+            limits: DiffLimits::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Snapshot the files `changes` touches that are not recorded yet.
+    ///
+    /// Frontends see a patch once core has started applying it, so a file
+    /// may already hold the patch: an update found applied on disk (and not
+    /// still pending) is reverted to recover the original.
+    pub fn record(&mut self, changes: &HashMap<PathBuf, FileChange>) {
+        for (path, change) in changes {
+            if !self.before.contains_key(path) {
+                self.before
+                    .insert(path.clone(), original_content(path, change));
+            }
+            if let FileChange::Update {
+                move_path: Some(dest),
+                ..
+            } = change
+                && !self.before.contains_key(dest)
+            {
+                // Once the source is gone the destination is the moved file.
+                let original = if path.exists() {
+                    std::fs::read(dest).ok()
+                } else {
+                    None
+                };
+                self.before.insert(dest.clone(), original);
+            }
+        }
+    }
+
+    /// Diff of each recorded file that changed, in path order.
+    fn file_diffs(&self) -> Vec<FileDiff> {
+        self.before
+            .iter()
+            .filter_map(|(path, original)| {
+                let current = std::fs::read(path).ok();
+                let text = file_diff(
+                    &self.display_path(path),
+                    original.as_deref(),
+                    current.as_deref(),
+                )?;
+                Some(FileDiff::parse(&text))
+            })
+            .collect()
+    }
+
+    /// `path` relative to the root; absolute when outside it.
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+impl VcsDiffTracker for PatchLedger {
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn repo_root(&self) -> Option<PathBuf> {
+        Some(self.root.clone())
+    }
+
+    fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        self.config_excludes = patterns;
+    }
+
+    fn set_limits(&mut self, limits: DiffLimits) {
+        self.limits = limits;
+    }
+
+    fn limits(&self) -> DiffLimits {
+        self.limits
+    }
+
+    fn get_diff_within(&mut self, budget: usize) -> Option<DiffOutput> {
+        Some(exclude_and_assemble(
+            self.file_diffs(),
+            &self.root,
+            &self.config_excludes,
+            self.limits,
+            budget,
+        ))
+    }
+
+    fn get_diff_if_changed(&mut self) -> Option<DiffOutput> {
+        let diff = self.get_diff()?;
+        changed_since(&mut self.last_diff_hash, diff)
+    }
+
+    fn get_diff_stats(&self) -> Option<DiffStatSummary> {
+        let excludes = load_exclude_patterns(Some(&self.root), &self.config_excludes);
+        let files = self
+            .file_diffs()
+            .into_iter()
+            .filter(|file| !excludes.iter().any(|pattern| pattern.matches(&file.path)))
+            .map(|file| FileStat {
+                path: file.path,
+                insertions: file.insertions,
+                deletions: file.deletions,
+            })
+            .collect();
+        Some(DiffStatSummary { files })
+    }
+
+    /// Forget every snapshot; later patches record their files afresh.
+    fn checkpoint(&mut self) {
+        self.before.clear();
+        self.last_diff_hash = None;
+    }
+
+    fn rollback_files(&self) -> Option<Vec<RollbackFile>> {
+        let excludes = load_exclude_patterns(Some(&self.root), &self.config_excludes);
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        let files = self
+            .before
+            .iter()
+            .filter(|(path, _)| {
+                let rel = self.display_path(path);
+                !excludes.iter().any(|pattern| pattern.matches(&rel))
+            })
+            .filter_map(|(path, original)| {
+                let current = std::fs::read(path).ok();
+                let change = match (original, current) {
+                    (original, current) if *original == current => return None,
+                    (None, Some(current)) => FileChange::Delete {
+                        content: lossy(&current),
+                    },
+                    (Some(original), None) => FileChange::Add {
+                        content: lossy(original),
+                    },
+                    (Some(original), Some(current)) => FileChange::Update {
+                        unified_diff: TextDiff::from_lines(&lossy(&current), &lossy(original))
+                            .unified_diff()
+                            .context_radius(3)
+                            .to_string(),
+                        move_path: None,
+                    },
+                    (None, None) => return None,
+                };
+                Some(RollbackFile {
+                    path: path.clone(),
+                    original: original.clone(),
+                    change,
+                })
+            })
+            .collect();
+        Some(files)
+    }
+}
+
+/// Content of `path` before `change` was applied; `None` when the file did
+/// not exist.
+fn original_content(path: &Path, change: &FileChange) -> Option<Vec<u8>> {
+    match change {
+        FileChange::Add { content } => std::fs::read(path)
+            .ok()
+            .filter(|current| current != content.as_bytes()),
+        FileChange::Delete { content } => Some(content.clone().into_bytes()),
+        FileChange::Update {
+            unified_diff,
+            move_path,
+        } => {
+            let current = std::fs::read(path).ok().or_else(|| {
+                move_path
+                    .as_deref()
+                    .and_then(|dest| std::fs::read(dest).ok())
+            })?;
+            let reverted = std::str::from_utf8(&current)
+                .ok()
+                .and_then(|text| revert_applied(unified_diff, text));
+            Some(reverted.map_or(current, String::into_bytes))
+        }
+    }
+}
+
+/// One hunk of a unified diff, with 0-based start lines.
+struct Hunk<'a> {
+    old_start: usize,
+    new_start: usize,
+    old: Vec<&'a str>,
+    new: Vec<&'a str>,
+}
+
+/// `text` with `unified_diff` reverted, when every hunk's new side sits at
+/// its position in `text` and the old sides do not; None otherwise.
+fn revert_applied(unified_diff: &str, text: &str) -> Option<String> {
+    let hunks = parse_hunks(unified_diff)?;
+    let lines: Vec<&str> = text.lines().collect();
+    let holds = |start: usize, expected: &[&str]| {
+        lines.get(start..start + expected.len()) == Some(expected)
+    };
+    let applied = hunks.iter().all(|hunk| holds(hunk.new_start, &hunk.new));
+    let pending = hunks.iter().all(|hunk| holds(hunk.old_start, &hunk.old));
+    if hunks.is_empty() || !applied || pending {
+        return None;
+    }
+    let mut original: Vec<&str> = Vec::new();
+    let mut cursor = 0;
+    for hunk in &hunks {
+        original.extend(lines.get(cursor..hunk.new_start)?);
+        original.extend(&hunk.old);
+        cursor = hunk.new_start + hunk.new.len();
+    }
+    original.extend(lines.get(cursor..)?);
+    let mut reverted = original.join("\n");
+    if !original.is_empty() {
+        reverted.push('\n');
+    }
+    Some(reverted)
+}
+
+/// The hunks of a single-file unified diff; None when a header is malformed.
+fn parse_hunks(unified_diff: &str) -> Option<Vec<Hunk<'_>>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for line in unified_diff.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            let mut ranges = header.split_whitespace();
+            let old_start = hunk_start(ranges.next()?.strip_prefix('-')?)?;
+            let new_start = hunk_start(ranges.next()?.strip_prefix('+')?)?;
+            hunks.push(Hunk {
+                old_start,
+                new_start,
+                old: Vec::new(),
+                new: Vec::new(),
+            });
+            continue;
+        }
+        // File headers come before the first hunk.
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        if let Some(added) = line.strip_prefix('+') {
+            hunk.new.push(added);
+        } else if let Some(removed) = line.strip_prefix('-') {
+            hunk.old.push(removed);
+        } else if !line.starts_with('\\') {
+            let context = line.strip_prefix(' ').unwrap_or(line);
+            hunk.old.push(context);
+            hunk.new.push(context);
+        }
+    }
+    Some(hunks)
+}
+
+/// 0-based first line of a `start,count` hunk range. An empty range names
+/// the line it follows.
+fn hunk_start(range: &str) -> Option<usize> {
+    let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+    let start: usize = start.parse().ok()?;
+    let count: usize = count.parse().ok()?;
+    Some(if count == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    })
+}
+
+/// Git-style diff of one file from `original` to `current`, where `None`
+/// means the file does not exist; None when nothing changed.
+fn file_diff(rel: &str, original: Option<&[u8]>, current: Option<&[u8]>) -> Option<String> {
+    if original == current {
+        return None;
+    }
+    let mut text = format!("diff --git a/{rel} b/{rel}\n");
+    match (original, current) {
+        (None, _) => text.push_str("new file mode 100644\n"),
+        (_, None) => text.push_str("deleted file mode 100644\n"),
+        _ => {}
+    }
+    let old_header = match original {
+        Some(_) => format!("a/{rel}"),
+        None => "/dev/null".to_string(),
+    };
+    let new_header = match current {
+        Some(_) => format!("b/{rel}"),
+        None => "/dev/null".to_string(),
+    };
+    fn decode(bytes: Option<&[u8]>) -> Option<&str> {
+        bytes.map_or(Some(""), |bytes| std::str::from_utf8(bytes).ok())
+    }
+    match (decode(original), decode(current)) {
+        (Some(old), Some(new)) => {
+            let _ = write!(
+                text,
+                "{}",
+                TextDiff::from_lines(old, new)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&old_header, &new_header)
+            );
+        }
+        _ => {
+            let _ = write!(
+                text,
+                "--- {old_header}\n+++ {new_header}\nBinary files differ\n"
+            );
+        }
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn update(old: &str, new: &str) -> FileChange {
+        FileChange::Update {
+            unified_diff: TextDiff::from_lines(old, new)
+                .unified_diff()
+                .context_radius(3)
+                .to_string(),
+            move_path: None,
+        }
+    }
+
+    #[test]
+    fn originals_are_recovered_before_or_after_the_patch_lands() {
+        let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\n";
+        let new = "one\nTWO\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+        let tmp = TempDir::new().expect("tempdir");
+        let path = tmp.path().join("notes.txt");
+
+        std::fs::write(&path, old).expect("write");
+        let mut pending = PatchLedger::new(tmp.path().to_path_buf());
+        pending.record(&HashMap::from([(path.clone(), update(old, new))]));
+        std::fs::write(&path, new).expect("write");
+        let mut applied = PatchLedger::new(tmp.path().to_path_buf());
+        applied.record(&HashMap::from([(path, update(old, new))]));
+
+        let diff = pending.get_diff().expect("diff").text;
+        assert_eq!(applied.get_diff().expect("diff").text, diff);
+        assert!(diff.starts_with("diff --git a/notes.txt b/notes.txt\n--- a/notes.txt\n"));
+        assert!(diff.contains("-two\n+TWO\n"));
+        assert!(diff.contains("+ten"));
+        let stats = applied.get_diff_stats().expect("stats");
+        assert_eq!((stats.insertions(), stats.deletions()), (2, 1));
+    }
+
+    #[test]
+    fn added_and_deleted_files_diff_and_roll_back() {
+        let tmp = TempDir::new().expect("tempdir");
+        let added = tmp.path().join("new.txt");
+        let deleted = tmp.path().join("old.txt");
+        std::fs::write(&deleted, "bye\n").expect("write");
+        let mut ledger = PatchLedger::new(tmp.path().to_path_buf());
+        ledger.record(&HashMap::from([
+            (
+                added.clone(),
+                FileChange::Add {
+                    content: "hello\n".to_string(),
+                },
+            ),
+            (
+                deleted.clone(),
+                FileChange::Delete {
+                    content: "bye\n".to_string(),
+                },
+            ),
+        ]));
+        std::fs::write(&added, "hello\n").expect("write");
+        std::fs::remove_file(&deleted).expect("remove");
+
+        let diff = ledger.get_diff().expect("diff").text;
+        assert!(diff.contains("diff --git a/new.txt b/new.txt\nnew file mode 100644\n"));
+        assert!(diff.contains("diff --git a/old.txt b/old.txt\ndeleted file mode 100644\n"));
+
+        let plan = ledger.rollback_files().expect("rollback");
+        assert_eq!(plan.len(), 2);
+        assert!(matches!(plan[0].change, FileChange::Delete { .. }));
+        assert_eq!(plan[1].original.as_deref(), Some(b"bye\n".as_slice()));
+
+        ledger.checkpoint();
+        assert_eq!(ledger.get_diff().expect("diff").text, "");
+    }
+}
@@ -0,0 +1,368 @@
+//! Try a proposed patch before it is approved.
+//!
+//! A [`PatchPreview`] is a detached git worktree holding the current working
+//! tree's tracked state (via `git stash create`, so uncommitted edits are
+//! included; untracked files are not). The patch is applied there and an
+//! optional check command (e.g. `cargo check`) runs in it, leaving the real
+//! checkout untouched. The worktree is removed when the preview is dropped.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use tempfile::TempDir;
+use thiserror::Error;
+use tokio::process::Command;
+
+use crate::protocol::FileChange;
+
+/// Lines of check output kept for the report.
+const MAX_OUTPUT_LINES: usize = 40;
+
+#[derive(Debug, Error)]
+pub enum PreviewError {
+    #[error("not inside a git repository")]
+    NotAGitRepository,
+    #[error("`git {args}` failed: {stderr}")]
+    Git { args: String, stderr: String },
+    #[error("{path} is outside the repository")]
+    OutsideRepository { path: PathBuf },
+    #[error("patch does not apply to {path}: {message}")]
+    Apply { path: PathBuf, message: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Result of the check command run against the patched worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub command: Vec<String>,
+    /// `None` when the command was killed (timeout or signal).
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration: Duration,
+    /// Combined stdout and stderr, trimmed to the last lines.
+    pub output: String,
+}
+
+impl CheckResult {
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+pub struct PatchPreview {
+    repo_root: PathBuf,
+    worktree: PathBuf,
+    /// The session cwd inside the worktree; the check command runs here.
+    cwd: PathBuf,
+    dir: TempDir,
+}
+
+impl PatchPreview {
+    /// Create a worktree mirroring the tracked state of the repository
+    /// containing `cwd`.
+    pub async fn create(cwd: &Path) -> Result<Self, PreviewError> {
+        let repo_root = git(cwd, &["rev-parse", "--show-toplevel"])
+            .await
+            .map_err(|_| PreviewError::NotAGitRepository)?;
+        let repo_root = PathBuf::from(repo_root.trim());
+        let base = git(&repo_root, &["stash", "create"]).await?;
+        let base = match base.trim() {
+            "" => "HEAD".to_string(),
+            commit => commit.to_string(),
+        };
+        let dir = tempfile::Builder::new()
+            .prefix("codex-preview-")
+            .tempdir()?;
+        let worktree = dir.path().join("worktree");
+        let worktree_arg = worktree.to_string_lossy().to_string();
+        git(
+            &repo_root,
+            &["worktree", "add", "--detach", &worktree_arg, &base],
+        )
+        .await?;
+        let cwd = relative_to(&repo_root, cwd)
+            .map(|rel| worktree.join(rel))
+            .unwrap_or_else(|| worktree.clone());
+        Ok(Self {
+            repo_root,
+            worktree,
+            cwd,
+            dir,
+        })
+    }
+
+    /// Apply `changes` (paths as reported in the approval request) to the
+    /// worktree.
+    pub async fn apply(&self, changes: &HashMap<PathBuf, FileChange>) -> Result<(), PreviewError> {
+        let mut paths: Vec<&PathBuf> = changes.keys().collect();
+        paths.sort();
+        for path in paths {
+            let rel = relative_to(&self.repo_root, path)
+                .ok_or_else(|| PreviewError::OutsideRepository { path: path.clone() })?;
+            let target = self.worktree.join(&rel);
+            match &changes[path] {
+                FileChange::Add { content } => {
+                    if let Some(parent) = target.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::write(&target, content).await?;
+                }
+                FileChange::Delete { .. } => tokio::fs::remove_file(&target).await?,
+                FileChange::Update {
+                    unified_diff,
+                    move_path,
+                } => {
+                    self.apply_diff(&rel, unified_diff).await?;
+                    if let Some(move_path) = move_path {
+                        let dest = relative_to(&self.repo_root, move_path).ok_or_else(|| {
+                            PreviewError::OutsideRepository {
+                                path: move_path.clone(),
+                            }
+                        })?;
+                        let dest = self.worktree.join(dest);
+                        if let Some(parent) = dest.parent() {
+                            tokio::fs::create_dir_all(parent).await?;
+                        }
+                        tokio::fs::rename(&target, dest).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `FileChange::Update` carries bare hunks; give them file headers and
+    /// let `git apply` do the rest.
+    async fn apply_diff(&self, rel: &Path, unified_diff: &str) -> Result<(), PreviewError> {
+        let rel_str = rel.to_string_lossy().replace('\\', "/");
+        let patch = format!("--- a/{rel_str}\n+++ b/{rel_str}\n{unified_diff}");
+        let patch_file = self
+            .dir
+            .path()
+            .join(format!("change-{}.diff", rand::random::<u64>()));
+        tokio::fs::write(&patch_file, patch).await?;
+        let patch_arg = patch_file.to_string_lossy().to_string();
+        let result = git(&self.worktree, &["apply", "--recount", &patch_arg]).await;
+        let _ = tokio::fs::remove_file(&patch_file).await;
+        result.map(|_| ()).map_err(|e| PreviewError::Apply {
+            path: rel.to_path_buf(),
+            message: match e {
+                PreviewError::Git { stderr, .. } => stderr,
+                other => other.to_string(),
+            },
+        })
+    }
+
+    /// Run `command` in the worktree, killing it after `timeout`.
+    pub async fn run_check(
+        &self,
+        command: &[String],
+        timeout: Duration,
+    ) -> Result<CheckResult, PreviewError> {
+        let Some((program, args)) = command.split_first() else {
+            return Err(std::io::Error::other("empty check command").into());
+        };
+        let started = Instant::now();
+        let child = Command::new(program)
+            .args(args)
+            .current_dir(&self.cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let (exit_code, timed_out, output) =
+            match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                Ok(output) => {
+                    let output = output?;
+                    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+                    text.push_str(&String::from_utf8_lossy(&output.stderr));
+                    (output.status.code(), false, text)
+                }
+                Err(_) => (None, true, String::new()),
+            };
+        Ok(CheckResult {
+            command: command.to_vec(),
+            exit_code,
+            timed_out,
+            duration: started.elapsed(),
+            output: tail_lines(&output, MAX_OUTPUT_LINES),
+        })
+    }
+}
+
+impl Drop for PatchPreview {
+    fn drop(&mut self) {
+        // The temp dir removes the files; this drops git's bookkeeping.
+        let _ = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_root)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.worktree)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Apply `changes` in a fresh worktree for `cwd` and run `command` there
+/// (skipped when empty).
+pub async fn preview_patch(
+    cwd: &Path,
+    changes: &HashMap<PathBuf, FileChange>,
+    command: &[String],
+    timeout: Duration,
+) -> Result<Option<CheckResult>, PreviewError> {
+    let preview = PatchPreview::create(cwd).await?;
+    preview.apply(changes).await?;
+    if command.is_empty() {
+        return Ok(None);
+    }
+    preview.run_check(command, timeout).await.map(Some)
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<String, PreviewError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(PreviewError::Git {
+            args: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        })
+    }
+}
+
+/// `path` relative to `root`, resolving symlinks (e.g. `/tmp` on macOS) when
+/// the plain prefix does not match.
+fn relative_to(root: &Path, path: &Path) -> Option<PathBuf> {
+    if let Ok(rel) = path.strip_prefix(root) {
+        return Some(rel.to_path_buf());
+    }
+    let root = root.canonicalize().ok()?;
+    // The file itself may not exist yet (an added file); resolve the closest
+    // existing ancestor instead.
+    let mut existing = path;
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        rest.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+    let mut resolved = existing.canonicalize().ok()?;
+    resolved.extend(rest.into_iter().rev());
+    resolved.strip_prefix(&root).ok().map(Path::to_path_buf)
+}
+
+fn tail_lines(text: &str, max: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(max)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .expect("git");
+        assert!(status.success(), "git {args:?}");
+    }
+
+    fn repo() -> TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        run(dir.path(), &["init", "-q"]);
+        run(dir.path(), &["config", "user.email", "preview@example.com"]);
+        run(dir.path(), &["config", "user.name", "Preview"]);
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").expect("write");
+        run(dir.path(), &["add", "."]);
+        run(dir.path(), &["commit", "-q", "-m", "init"]);
+        dir
+    }
+
+    #[tokio::test]
+    async fn patch_is_checked_in_a_worktree_and_the_checkout_is_untouched() {
+        let repo = repo();
+        // Uncommitted edits are part of the preview's base.
+        std::fs::write(repo.path().join("a.txt"), "one\ntwo\nTHREE\n").expect("write");
+        let changes = HashMap::from([
+            (
+                repo.path().join("a.txt"),
+                FileChange::Update {
+                    unified_diff: "@@ -1,3 +1,3 @@\n one\n-two\n+2\n THREE\n".to_string(),
+                    move_path: None,
+                },
+            ),
+            (
+                repo.path().join("src/new.txt"),
+                FileChange::Add {
+                    content: "new\n".to_string(),
+                },
+            ),
+        ]);
+        let command = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "cat a.txt src/new.txt; exit 3".to_string(),
+        ];
+
+        let check = preview_patch(repo.path(), &changes, &command, Duration::from_secs(30))
+            .await
+            .expect("preview")
+            .expect("check ran");
+
+        assert_eq!(check.exit_code, Some(3));
+        assert_eq!(check.output, "one\n2\nTHREE\nnew");
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("a.txt")).expect("read"),
+            "one\ntwo\nTHREE\n"
+        );
+        assert!(!repo.path().join("src/new.txt").exists());
+        // The worktree was removed again.
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["worktree", "list"])
+            .output()
+            .expect("git");
+        assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn stale_patch_reports_the_file() {
+        let repo = repo();
+        let changes = HashMap::from([(
+            repo.path().join("a.txt"),
+            FileChange::Update {
+                unified_diff: "@@ -1,2 +1,2 @@\n zero\n-one\n+1\n".to_string(),
+                move_path: None,
+            },
+        )]);
+
+        let err = preview_patch(repo.path(), &changes, &[], Duration::from_secs(30))
+            .await
+            .expect_err("stale patch");
+
+        assert!(
+            matches!(&err, PreviewError::Apply { path, .. } if path == Path::new("a.txt")),
+            "{err}"
+        );
+    }
+}
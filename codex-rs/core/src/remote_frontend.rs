@@ -0,0 +1,1377 @@
+//! Core-side Omnara connection for frontends that send
+//! [`Op::RegisterRemoteFrontend`].
+//!
+//! The TUI drives Omnara through its own bridge, but other frontends (the MCP
+//! server, GUIs) only see protocol events. Registering hands the decisions to
+//! core: agent messages and approval requests are mirrored to the dashboard,
+//! and dashboard replies are submitted back into the session as ops, with
+//! [`EventMsg::RemoteInputReceived`] / [`EventMsg::RemoteApprovalResolved`]
+//! telling the frontend what happened.
+//!
+//! [`LocalControl`] routes the same way for a token-protected HTTP server
+//! on the local machine, for setups without the Omnara service.
+
+mod local_control;
+
+pub(crate) use local_control::LocalControl;
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_channel::WeakSender;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::info;
+use tracing::warn;
+
+use crate::config_types::ApprovalPush;
+use crate::config_types::OmnaraLocale;
+use crate::config_types::OmnaraSessionReport;
+use crate::omnara_approval::ApprovalParser;
+use crate::omnara_approval::ApprovalReply;
+use crate::omnara_approval::ESCALATION_APPROVAL_OPTIONS;
+use crate::omnara_approval::EXEC_APPROVAL_OPTIONS;
+use crate::omnara_approval::EXPLAIN_COMMAND;
+use crate::omnara_approval::PATCH_APPROVAL_OPTIONS;
+use crate::omnara_approval::PREVIEW_COMMAND;
+use crate::omnara_approval::PatchFormat;
+use crate::omnara_approval::exec_approval_risk;
+use crate::omnara_approval::format_ask_user_request;
+use crate::omnara_approval::format_denial_feedback;
+use crate::omnara_approval::format_escalation_approval_request;
+use crate::omnara_approval::format_exec_approval_request;
+use crate::omnara_approval::format_hunk_expansion;
+use crate::omnara_approval::format_mcp_approval_request;
+use crate::omnara_approval::format_partial_approval_feedback;
+use crate::omnara_approval::format_patch_approval_details;
+use crate::omnara_approval::format_patch_approval_request;
+use crate::omnara_approval::format_patch_explanation;
+use crate::omnara_approval::format_patch_preview_result;
+use crate::omnara_approval::format_patch_preview_started;
+use crate::omnara_approval::format_withdrawn_approval_note;
+use crate::omnara_approval::parse_show_hunk;
+use crate::omnara_approval::patch_approval_risk;
+use crate::omnara_approval::patch_files;
+use crate::omnara_client::OmnaraClient;
+use crate::omnara_diagnostics::Diagnostic;
+use crate::omnara_diagnostics::DiagnosticLimiter;
+use crate::omnara_diagnostics::diagnostic_for_event;
+use crate::omnara_diagnostics::format_diagnostic_note;
+use crate::omnara_escalation::EscalationChain;
+use crate::omnara_escalation::EscalationStep;
+use crate::omnara_escalation::format_approval_reminder;
+use crate::omnara_escalation::format_default_decision_note;
+use crate::omnara_escalation::format_webhook_text;
+use crate::omnara_escalation::send_webhook;
+use crate::omnara_i18n::strings;
+use crate::omnara_sanitize::RejectedMessage;
+use crate::omnara_sanitize::format_rejected_message_note;
+use crate::omnara_sanitize::sanitize_remote_message;
+use crate::omnara_session_report::SessionReportBuilder;
+use crate::omnara_session_report::TRANSCRIPT_NAME;
+use crate::omnara_tags::TAG_COMMAND;
+use crate::omnara_tags::TagCommand;
+use crate::omnara_tags::TagError;
+use crate::omnara_tags::format_tags_note;
+use crate::omnara_tags::parse_tag_command;
+use crate::omnara_withhold::WithheldPaths;
+use crate::patch_preview::preview_patch;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::FileChange;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::RemoteApprovalResolvedEvent;
+use crate::protocol::RemoteInputReceivedEvent;
+use crate::protocol::ReviewDecision;
+use crate::protocol::Submission;
+use crate::protocol::TurnAbortReason;
+
+#[derive(Debug, Clone, Copy)]
+enum ApprovalKind {
+    Exec,
+    /// An exec approval that would run the command outside the sandbox.
+    Escalation,
+    Patch,
+    Mcp,
+}
+
+/// A prompt on the dashboard whose reply belongs to the session rather than
+/// being new user input.
+#[derive(Debug, Clone, Copy)]
+enum PendingReply {
+    Approval(ApprovalKind),
+    /// A question from the `ask_user` tool; the reply is its answer.
+    Question,
+}
+
+impl PendingReply {
+    fn label(self) -> &'static str {
+        match self {
+            PendingReply::Approval(ApprovalKind::Exec) => "exec",
+            PendingReply::Approval(ApprovalKind::Escalation) => "escalation",
+            PendingReply::Approval(ApprovalKind::Patch) => "patch",
+            PendingReply::Approval(ApprovalKind::Mcp) => "mcp",
+            PendingReply::Question => "question",
+        }
+    }
+}
+
+pub(crate) struct RemoteFrontend {
+    client: OmnaraClient,
+    router: Arc<Router>,
+    escalation: Option<EscalationChain>,
+    /// Which exec and patch approval prompts ask for a push notification.
+    approval_push: ApprovalPush,
+    /// Rate limit of error and diagnostics notes; `None` disables them.
+    diagnostics: Option<Mutex<DiagnosticLimiter>>,
+    /// What the session did, for the report posted at shutdown; `None`
+    /// unless `omnara.session_report.enabled`.
+    session_report: Option<Mutex<SessionReportBuilder>>,
+    session_report_settings: OmnaraSessionReport,
+    /// Sends the messages queued while requests were paused once they
+    /// resume; started with the session.
+    circuit_monitor: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Routes dashboard replies back into the session. Holds weak channel ends so
+/// a lingering poller never keeps the session alive.
+struct Router {
+    tx_sub: WeakSender<Submission>,
+    tx_event: WeakSender<Event>,
+    /// Approval requests and questions awaiting a dashboard reply, oldest
+    /// first.
+    pending: Mutex<VecDeque<(String, PendingReply)>>,
+    /// Changes of the pending patch approvals, for `/preview`.
+    patches: Mutex<HashMap<String, HashMap<PathBuf, FileChange>>>,
+    /// Where and how `/preview` dry-runs a patch; `None` disables it.
+    preview: Option<PreviewSettings>,
+    approval_parser: ApprovalParser,
+    /// Language of the prompts and notes posted to the dashboard.
+    locale: OmnaraLocale,
+    /// How patch approvals render their changes.
+    patch_format: PatchFormat,
+    /// Larger dashboard messages are refused.
+    max_message_bytes: usize,
+    /// Every dashboard send, so notes arrive in the order of their events.
+    outbox: Outbox,
+    /// Id of the turn's latest agent message, so the end of the turn
+    /// requests input on that message.
+    last_agent_message: Mutex<Option<String>>,
+    next_sub_id: AtomicU64,
+}
+
+type QueuedSend = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Runs dashboard sends one at a time, in the order they were queued, on a
+/// task started with the first one.
+struct Outbox {
+    tx: mpsc::UnboundedSender<QueuedSend>,
+    rx: Mutex<Option<mpsc::UnboundedReceiver<QueuedSend>>>,
+}
+
+impl Outbox {
+    fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    }
+
+    /// Queue `send` behind the sends already queued.
+    fn push(&self, send: impl Future<Output = ()> + Send + 'static) {
+        if let Some(mut rx) = self.rx.lock().ok().and_then(|mut rx| rx.take()) {
+            tokio::spawn(async move {
+                while let Some(send) = rx.recv().await {
+                    send.await;
+                }
+            });
+        }
+        let _ = self.tx.send(Box::pin(send));
+    }
+
+    /// Wait until the sends queued so far have run, or until `deadline`.
+    async fn flush(&self, deadline: Instant) {
+        let (done, flushed) = oneshot::channel();
+        self.push(async move {
+            let _ = done.send(());
+        });
+        let _ = tokio::time::timeout_at(deadline.into(), flushed).await;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PreviewSettings {
+    cwd: PathBuf,
+    command: Vec<String>,
+    timeout: Duration,
+}
+
+impl RemoteFrontend {
+    pub(crate) fn new(
+        client: OmnaraClient,
+        tx_sub: WeakSender<Submission>,
+        tx_event: WeakSender<Event>,
+        approval_parser: ApprovalParser,
+    ) -> Self {
+        info!(session_id = %client.session_id(), "RemoteFrontend: registered");
+        Self {
+            client,
+            router: Arc::new(Router::new(tx_sub, tx_event, approval_parser)),
+            escalation: None,
+            approval_push: ApprovalPush::default(),
+            diagnostics: Some(Mutex::new(DiagnosticLimiter::default())),
+            session_report: None,
+            session_report_settings: OmnaraSessionReport::default(),
+            circuit_monitor: Mutex::new(None),
+        }
+    }
+
+    /// Post a report of the session at shutdown, if `settings` enables it.
+    pub(crate) fn with_session_report(mut self, settings: &OmnaraSessionReport) -> Self {
+        self.session_report = settings
+            .enabled
+            .then(|| Mutex::new(SessionReportBuilder::new(self.client.clock().now())));
+        self.session_report_settings = settings.clone();
+        self
+    }
+
+    /// Escalate approval prompts that go unanswered.
+    pub(crate) fn with_approval_escalation(mut self, chain: Option<EscalationChain>) -> Self {
+        self.escalation = chain;
+        self
+    }
+
+    /// Ask for push notifications on approval prompts per risk level.
+    pub(crate) fn with_approval_push(mut self, approval_push: ApprovalPush) -> Self {
+        self.approval_push = approval_push;
+        self
+    }
+
+    /// Post notes for stream retries, turn errors, and sandbox failures.
+    pub(crate) fn with_error_notes(mut self, enabled: bool) -> Self {
+        self.diagnostics = enabled.then(|| Mutex::new(DiagnosticLimiter::default()));
+        self
+    }
+
+    /// Post prompts and notes in `locale` and accept its approval replies.
+    pub(crate) fn with_locale(mut self, locale: OmnaraLocale) -> Self {
+        if let Some(router) = Arc::get_mut(&mut self.router) {
+            router.locale = locale;
+            router.approval_parser = router.approval_parser.clone().with_locale(locale);
+        }
+        self
+    }
+
+    /// Render patch approvals with `format`.
+    pub(crate) fn with_patch_format(mut self, format: PatchFormat) -> Self {
+        if let Some(router) = Arc::get_mut(&mut self.router) {
+            router.patch_format = format;
+        }
+        self
+    }
+
+    /// Refuse dashboard messages larger than `bytes`.
+    pub(crate) fn with_max_remote_message_bytes(mut self, bytes: usize) -> Self {
+        if let Some(router) = Arc::get_mut(&mut self.router) {
+            router.max_message_bytes = bytes;
+        }
+        self
+    }
+
+    /// Let `/preview` dry-run pending patches in a worktree of `cwd`,
+    /// running `command` there (empty: only check that the patch applies).
+    pub(crate) fn with_patch_preview(
+        mut self,
+        cwd: PathBuf,
+        command: Vec<String>,
+        timeout: Duration,
+    ) -> Self {
+        if let Some(router) = Arc::get_mut(&mut self.router) {
+            router.preview = Some(PreviewSettings {
+                cwd,
+                command,
+                timeout,
+            });
+        }
+        self
+    }
+
+    /// Negotiate the server's capabilities, announce the session on the
+    /// dashboard, and start listening for input.
+    pub(crate) fn start(&self) {
+        let client = self.client.clone();
+        let router = self.router.clone();
+        self.router.outbox.push(async move {
+            client.negotiate().await;
+            if let Ok(id) = client
+                .send_agent_message(strings(router.locale).session_started, true)
+                .await
+            {
+                client.set_last_read_message_id(id);
+            }
+            Router::start_polling(&client, router);
+        });
+        let client = self.client.clone();
+        let router = self.router.clone();
+        let monitor = self.client.start_circuit_monitor(move |paused| {
+            if !paused {
+                let client = client.clone();
+                router.outbox.push(async move {
+                    client.send_queued_messages().await;
+                });
+            }
+        });
+        if let Ok(mut slot) = self.circuit_monitor.lock() {
+            *slot = Some(monitor);
+        }
+    }
+
+    /// Mirror an outgoing session event to the dashboard.
+    pub(crate) fn observe(&self, event: &Event) {
+        if let Some(diagnostic) = diagnostic_for_event(&event.msg) {
+            self.post_diagnostic(diagnostic);
+        }
+        if let EventMsg::TurnAborted(_) = &event.msg {
+            self.withdraw_pending();
+        }
+        if let Some(report) = &self.session_report
+            && let Ok(mut report) = report.lock()
+        {
+            report.observe(&event.msg);
+        }
+        match &event.msg {
+            EventMsg::AgentMessage(ev) => {
+                let client = self.client.clone();
+                let router = self.router.clone();
+                let message = ev.message.clone();
+                self.router.outbox.push(async move {
+                    let id = client.send_agent_message(&message, false).await.ok();
+                    if let Ok(mut last) = router.last_agent_message.lock() {
+                        *last = id;
+                    }
+                });
+            }
+            EventMsg::TaskComplete(_) => {
+                let client = self.client.clone();
+                let router = self.router.clone();
+                self.router.outbox.push(async move {
+                    // Queued behind the turn's last message, so its send has
+                    // returned by now.
+                    let message_id = router
+                        .last_agent_message
+                        .lock()
+                        .ok()
+                        .and_then(|mut last| last.take());
+                    let _ = match message_id {
+                        Some(id) => client.request_user_input(&id).await,
+                        None => client.request_user_input_for_last_message().await,
+                    };
+                    Router::start_polling(&client, router);
+                });
+            }
+            EventMsg::PatchExplanation(ev) => {
+                let client = self.client.clone();
+                let router = self.router.clone();
+                let id = ev.id.clone();
+                let note = format_patch_explanation(ev.explanation.as_deref(), router.locale);
+                self.router.outbox.push(async move {
+                    router.post_follow_up(&client, &id, &note).await;
+                });
+            }
+            _ => {
+                if let Some((prompt, expects)) =
+                    self.router.prompt(event, self.client.withheld_paths())
+                {
+                    self.ask(prompt, expects, self.push(&event.msg));
+                }
+            }
+        }
+    }
+
+    /// Drop the prompts of an aborted turn. Nothing can answer them any
+    /// more, so each gets a note withdrawing it and a later reply is read as
+    /// new input.
+    fn withdraw_pending(&self) {
+        for (id, reply) in self.router.take_all_pending() {
+            self.client
+                .audit_approval(&id, "withdrawn; the turn was aborted");
+            let client = self.client.clone();
+            let note = format_withdrawn_approval_note(reply.label(), self.router.locale);
+            self.router.outbox.push(async move {
+                let _ = client.send_agent_message(&note, false).await;
+            });
+        }
+    }
+
+    /// Post a note for `diagnostic` unless too many of its kind were posted
+    /// recently.
+    fn post_diagnostic(&self, diagnostic: Diagnostic) {
+        let Some(limiter) = self.diagnostics.as_ref() else {
+            return;
+        };
+        let now = self.client.clock().now();
+        let admitted = limiter
+            .lock()
+            .ok()
+            .and_then(|mut limiter| limiter.admit(diagnostic.kind(), now));
+        if let Some(suppressed) = admitted {
+            let client = self.client.clone();
+            let note = format_diagnostic_note(&diagnostic, suppressed);
+            self.router.outbox.push(async move {
+                let _ = client.send_agent_message(&note, false).await;
+            });
+        }
+    }
+
+    /// Stop polling, flush queued and in-flight sends, and end the Omnara
+    /// session.
+    pub(crate) async fn shutdown(&self, deadline: Instant) {
+        if let Some(monitor) = self
+            .circuit_monitor
+            .lock()
+            .ok()
+            .and_then(|mut slot| slot.take())
+        {
+            monitor.abort();
+        }
+        self.post_session_report();
+        self.router.outbox.flush(deadline).await;
+        let _ = self.client.shutdown(deadline).await;
+    }
+
+    /// Upload the session's transcript and post the report linking it.
+    fn post_session_report(&self) {
+        let Some(builder) = self
+            .session_report
+            .as_ref()
+            .and_then(|report| report.lock().ok().map(|report| report.clone()))
+        else {
+            return;
+        };
+        let client = self.client.clone();
+        let settings = self.session_report_settings.clone();
+        let now = client.clock().now();
+        self.router.outbox.push(async move {
+            let diff_client = client.clone();
+            let diff = tokio::task::spawn_blocking(move || diff_client.diff_stats())
+                .await
+                .ok()
+                .flatten();
+            let report = builder.build(now, diff.as_ref());
+            let uploaded = if report.transcript.is_empty() {
+                None
+            } else {
+                client
+                    .upload_attachment(
+                        TRANSCRIPT_NAME,
+                        report.transcript.as_bytes(),
+                        "text/markdown",
+                    )
+                    .await
+                    .ok()
+            };
+            let note = report.format_note(&settings, uploaded.as_ref());
+            let _ = client.send_agent_message(&note, false).await;
+        });
+    }
+
+    /// Whether an exec or patch approval prompt asks for a push notification;
+    /// `None` leaves other prompts to the server's defaults.
+    fn push(&self, msg: &EventMsg) -> Option<bool> {
+        let level = match msg {
+            EventMsg::ExecApprovalRequest(ev) => {
+                exec_approval_risk(&ev.command, &ev.cwd, ev.escalation)
+            }
+            EventMsg::ApplyPatchApprovalRequest(ev) => {
+                patch_approval_risk(ev.grant_root.as_deref())
+            }
+            _ => return None,
+        };
+        Some(self.approval_push.notifies(level))
+    }
+
+    /// Send a prompt that requires input (optionally tracking the reply it
+    /// expects and asking for a push notification or not) and poll for the
+    /// reply.
+    fn ask(&self, prompt: String, expects: Option<(String, PendingReply)>, push: Option<bool>) {
+        if let Some((id, expects)) = expects
+            && let Ok(mut pending) = self.router.pending.lock()
+        {
+            if let PendingReply::Approval(_) = expects {
+                self.client.audit_approval(&id, "prompt sent");
+                self.start_escalation(id.clone(), prompt.clone());
+            }
+            pending.push_back((id, expects));
+        }
+        let client = self.client.clone();
+        let router = self.router.clone();
+        self.router.outbox.push(async move {
+            let sent = match push {
+                Some(push) => client.send_approval_request(&prompt, push).await,
+                None => client.send_agent_message(&prompt, true).await,
+            };
+            if let Ok(id) = sent {
+                client.set_last_read_message_id(id);
+            }
+            Router::start_polling(&client, router);
+        });
+    }
+
+    /// Run the escalation chain for approval `id`. Each step first checks
+    /// that the approval is still pending.
+    fn start_escalation(&self, id: String, prompt: String) {
+        let Some(chain) = &self.escalation else {
+            return;
+        };
+        let client = self.client.clone();
+        let router = self.router.clone();
+        chain.start(self.client.clock(), move |step, waited| {
+            let client = client.clone();
+            let queued = router.clone();
+            let id = id.clone();
+            let prompt = prompt.clone();
+            router.outbox.push(async move {
+                queued.escalate(&client, id, &prompt, step, waited).await;
+            });
+        });
+    }
+}
+
+impl Router {
+    fn new(
+        tx_sub: WeakSender<Submission>,
+        tx_event: WeakSender<Event>,
+        approval_parser: ApprovalParser,
+    ) -> Self {
+        Self {
+            tx_sub,
+            tx_event,
+            pending: Mutex::new(VecDeque::new()),
+            patches: Mutex::new(HashMap::new()),
+            preview: None,
+            approval_parser,
+            locale: OmnaraLocale::default(),
+            patch_format: PatchFormat::default(),
+            max_message_bytes: 64 * 1024,
+            outbox: Outbox::new(),
+            last_agent_message: Mutex::new(None),
+            next_sub_id: AtomicU64::new(0),
+        }
+    }
+
+    /// The prompt a session event puts to the remote side, and the reply it
+    /// expects, if any. Patch approvals also remember their changes for
+    /// `/preview` and `/explain`.
+    fn prompt(
+        &self,
+        event: &Event,
+        withheld: &WithheldPaths,
+    ) -> Option<(String, Option<(String, PendingReply)>)> {
+        match &event.msg {
+            EventMsg::TurnAborted(ev) if ev.reason == TurnAbortReason::Interrupted => {
+                Some((strings(self.locale).interrupt_prompt.to_string(), None))
+            }
+            EventMsg::ExecApprovalRequest(ev) => {
+                let (prompt, kind) = match ev.escalation {
+                    Some(escalation) => (
+                        format_escalation_approval_request(
+                            &ev.command,
+                            &ev.cwd,
+                            ev.reason.as_deref(),
+                            escalation,
+                            self.locale,
+                        ),
+                        ApprovalKind::Escalation,
+                    ),
+                    None => (
+                        format_exec_approval_request(
+                            &ev.command,
+                            &ev.cwd,
+                            ev.reason.as_deref(),
+                            ev.closest_approved.as_deref(),
+                            self.locale,
+                        ),
+                        ApprovalKind::Exec,
+                    ),
+                };
+                Some((
+                    prompt,
+                    Some((event.id.clone(), PendingReply::Approval(kind))),
+                ))
+            }
+            EventMsg::ApplyPatchApprovalRequest(ev) => {
+                let (details, added, removed) = format_patch_approval_details(
+                    &ev.changes,
+                    withheld,
+                    self.patch_format,
+                    self.locale,
+                );
+                let prompt = format_patch_approval_request(
+                    ev.changes.len(),
+                    added,
+                    removed,
+                    ev.reason.as_deref(),
+                    ev.grant_root.as_deref(),
+                    Some(&details),
+                    self.locale,
+                );
+                if let Ok(mut patches) = self.patches.lock() {
+                    patches.insert(event.id.clone(), ev.changes.clone());
+                }
+                Some((
+                    prompt,
+                    Some((
+                        event.id.clone(),
+                        PendingReply::Approval(ApprovalKind::Patch),
+                    )),
+                ))
+            }
+            EventMsg::McpToolCallApprovalRequest(ev) => Some((
+                format_mcp_approval_request(&ev.invocation, self.locale),
+                Some((event.id.clone(), PendingReply::Approval(ApprovalKind::Mcp))),
+            )),
+            EventMsg::AskUserRequest(ev) => Some((
+                format_ask_user_request(&ev.question, &ev.options, self.locale),
+                Some((ev.call_id.clone(), PendingReply::Question)),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Stop waiting for a reply to `id`, returning what it was waiting for.
+    fn take_pending(&self, id: &str) -> Option<PendingReply> {
+        self.forget_patch(id);
+        let mut pending = self.pending.lock().ok()?;
+        let index = pending
+            .iter()
+            .position(|(pending_id, _)| pending_id == id)?;
+        pending.remove(index).map(|(_, kind)| kind)
+    }
+
+    async fn escalate(
+        &self,
+        client: &OmnaraClient,
+        id: String,
+        prompt: &str,
+        step: EscalationStep,
+        waited: Duration,
+    ) {
+        let pending = self
+            .pending
+            .lock()
+            .is_ok_and(|pending| pending.iter().any(|(pending_id, _)| *pending_id == id));
+        if !pending {
+            return;
+        }
+        client.audit_approval(&id, &step.describe(waited));
+        match step {
+            EscalationStep::Remind => {
+                let reminder = format_approval_reminder(prompt, waited, self.locale);
+                if let Ok(message_id) = client.send_urgent_agent_message(&reminder).await {
+                    client.set_last_read_message_id(message_id);
+                }
+            }
+            EscalationStep::Webhook { url } => {
+                // Off the outbox, so a slow webhook does not hold up notes.
+                let text = format_webhook_text(&client.session_id().to_string(), prompt, waited);
+                let client = client.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = send_webhook(client.http(), &url, &text).await {
+                        client.audit_approval(&id, &format!("webhook failed: {e}"));
+                    }
+                });
+            }
+            EscalationStep::ApplyDefault(decision) => {
+                if let Some(PendingReply::Approval(kind)) = self.take_pending(&id) {
+                    let note = format_default_decision_note(decision, waited, self.locale);
+                    let _ = client.send_agent_message(&note, false).await;
+                    self.resolve(id, kind, decision);
+                }
+            }
+        }
+    }
+
+    fn start_polling(client: &OmnaraClient, router: Arc<Router>) {
+        let poll_client = client.clone();
+        client.start_polling(move |text| {
+            let text = match sanitize_remote_message(&text, router.max_message_bytes) {
+                Ok(text) => text,
+                Err(rejected) => {
+                    router.refuse(&poll_client, rejected);
+                    return;
+                }
+            };
+            if let Some((id, changes)) = router.patch_command_request(&text, EXPLAIN_COMMAND) {
+                router.explain(&poll_client, id, changes);
+            } else if let Some((id, changes, index)) = router.show_hunk_request(&text) {
+                router.show_hunk(&poll_client, id, changes, index);
+            } else if let Some((id, changes)) = router.preview_request(&text) {
+                router.clone().preview(poll_client.clone(), id, changes);
+            } else if let Some(command) = router.tag_request(&text) {
+                router.tag(&poll_client, command);
+            } else {
+                router.route(text);
+            }
+        });
+    }
+
+    /// Post why a dashboard message was not delivered and keep listening.
+    fn refuse(self: &Arc<Self>, client: &OmnaraClient, rejected: RejectedMessage) {
+        warn!(%rejected, "RemoteFrontend: refusing remote message");
+        let note = format_rejected_message_note(&rejected, self.locale);
+        let client = client.clone();
+        let router = self.clone();
+        self.outbox.push(async move {
+            let _ = client.send_agent_message(&note, false).await;
+            Router::start_polling(&client, router);
+        });
+    }
+
+    /// The `/tag` command `text` is, unless it answers a pending prompt.
+    fn tag_request(&self, text: &str) -> Option<Result<TagCommand, TagError>> {
+        let args = text.trim_start().strip_prefix(TAG_COMMAND)?;
+        if !(args.is_empty() || args.starts_with(char::is_whitespace)) {
+            return None;
+        }
+        if !self.pending.lock().ok()?.is_empty() {
+            return None;
+        }
+        Some(parse_tag_command(args))
+    }
+
+    /// Apply a dashboard `/tag` command, post the resulting tags (or why
+    /// they did not change), and keep listening.
+    fn tag(self: &Arc<Self>, client: &OmnaraClient, command: Result<TagCommand, TagError>) {
+        let client = client.clone();
+        let router = self.clone();
+        self.outbox.push(async move {
+            let note = match command {
+                Ok(command) => match client.apply_tag_command(&command).await {
+                    Ok(tags) => format_tags_note(&tags),
+                    Err(e) => format!("⚠️ Could not change the session's tags: {e}"),
+                },
+                Err(e) => format!("⚠️ {e}"),
+            };
+            let _ = client.send_agent_message(&note, false).await;
+            Router::start_polling(&client, router);
+        });
+    }
+
+    /// The pending patch approval a `/preview` reply asks to dry-run. The
+    /// approval stays pending.
+    fn preview_request(&self, text: &str) -> Option<(String, HashMap<PathBuf, FileChange>)> {
+        self.preview.as_ref()?;
+        self.patch_command_request(text, PREVIEW_COMMAND)
+    }
+
+    /// The pending patch approval and hunk number of a `show N` reply.
+    fn show_hunk_request(
+        &self,
+        text: &str,
+    ) -> Option<(String, HashMap<PathBuf, FileChange>, usize)> {
+        let index = parse_show_hunk(text)?;
+        let (id, changes) = self.pending_patch()?;
+        Some((id, changes, index))
+    }
+
+    /// Post hunk `index` of the pending patch in full, asking for the
+    /// decision again. The approval stays pending.
+    fn show_hunk(
+        self: &Arc<Self>,
+        client: &OmnaraClient,
+        id: String,
+        changes: HashMap<PathBuf, FileChange>,
+        index: usize,
+    ) {
+        let note = format_hunk_expansion(&changes, client.withheld_paths(), index, self.locale);
+        let client = client.clone();
+        let router = self.clone();
+        self.outbox.push(async move {
+            router.post_follow_up(&client, &id, &note).await;
+        });
+    }
+
+    /// The pending patch approval `text` applies `command` to, if it is that
+    /// command and the oldest pending reply is a patch approval.
+    fn patch_command_request(
+        &self,
+        text: &str,
+        command: &str,
+    ) -> Option<(String, HashMap<PathBuf, FileChange>)> {
+        if text.split_whitespace().next() != Some(command) {
+            return None;
+        }
+        self.pending_patch()
+    }
+
+    /// The oldest pending reply, if it is a patch approval, with its changes.
+    fn pending_patch(&self) -> Option<(String, HashMap<PathBuf, FileChange>)> {
+        let pending = self.pending.lock().ok()?;
+        let (id, _) = pending
+            .front()
+            .filter(|(_, kind)| matches!(kind, PendingReply::Approval(ApprovalKind::Patch)))?;
+        let changes = self.patches.lock().ok()?.get(id).cloned()?;
+        Some((id.clone(), changes))
+    }
+
+    /// Apply the patch in a scratch worktree, run the check command, and post
+    /// the result, asking for the decision again if it is still pending.
+    fn preview(
+        self: Arc<Self>,
+        client: OmnaraClient,
+        id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    ) {
+        let Some(settings) = self.preview.clone() else {
+            return;
+        };
+        client.audit_approval(&id, "preview requested");
+        let note = format_patch_preview_started(&settings.command, self.locale);
+        self.outbox.push({
+            let client = client.clone();
+            let router = self.clone();
+            async move {
+                let _ = client.send_agent_message(&note, false).await;
+                Router::start_polling(&client, router);
+            }
+        });
+        // The dry run happens off the outbox; only its report is queued.
+        tokio::spawn(async move {
+            let result =
+                preview_patch(&settings.cwd, &changes, &settings.command, settings.timeout).await;
+            let report = format_patch_preview_result(&result, self.locale);
+            let router = self.clone();
+            self.outbox.push(async move {
+                router.post_follow_up(&client, &id, &report).await;
+            });
+        });
+    }
+
+    /// Ask core for a plain-language summary of the patch; the answer comes
+    /// back as [`EventMsg::PatchExplanation`]. The approval stays pending.
+    fn explain(
+        self: &Arc<Self>,
+        client: &OmnaraClient,
+        id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    ) {
+        client.audit_approval(&id, "explanation requested");
+        self.submit(Op::ExplainPatch { id, changes });
+        Router::start_polling(client, self.clone());
+    }
+
+    /// Post a `/preview` or `/explain` result for approval `id`. While the
+    /// approval is still pending, the note asks for the decision again.
+    async fn post_follow_up(self: Arc<Self>, client: &OmnaraClient, id: &str, note: &str) {
+        let pending = self
+            .pending
+            .lock()
+            .is_ok_and(|pending| pending.iter().any(|(pending_id, _)| pending_id == id));
+        if !pending {
+            let _ = client.send_agent_message(note, false).await;
+            return;
+        }
+        if let Ok(message_id) = client.send_agent_message(note, true).await {
+            client.set_last_read_message_id(message_id);
+        }
+        Router::start_polling(client, self);
+    }
+
+    /// Every pending prompt, oldest first, leaving none.
+    fn take_all_pending(&self) -> Vec<(String, PendingReply)> {
+        let pending: Vec<(String, PendingReply)> = self
+            .pending
+            .lock()
+            .map(|mut pending| pending.drain(..).collect())
+            .unwrap_or_default();
+        for (id, _) in &pending {
+            self.forget_patch(id);
+        }
+        pending
+    }
+
+    fn forget_patch(&self, id: &str) -> Option<HashMap<PathBuf, FileChange>> {
+        self.patches.lock().ok()?.remove(id)
+    }
+
+    fn route(&self, text: String) {
+        let pending = self.pending.lock().ok().and_then(|mut q| q.pop_front());
+        let patch = pending.as_ref().and_then(|(id, _)| self.forget_patch(id));
+        let excluded = patch.and_then(|changes| {
+            self.approval_parser
+                .parse_partial_patch(&text, &patch_files(&changes))
+        });
+        if let (Some((id, _)), Some(excluded)) = (&pending, excluded) {
+            // Core drops the excluded files from the patch once approved.
+            self.submit(Op::ExcludePatchFiles {
+                id: id.clone(),
+                paths: excluded.clone(),
+            });
+            self.resolve(id.clone(), ApprovalKind::Patch, ReviewDecision::Approved);
+            self.forward_user_input(format_partial_approval_feedback(&excluded));
+            return;
+        }
+        match pending {
+            Some((id, PendingReply::Question)) => {
+                self.submit(Op::AskUserAnswer { id, answer: text });
+            }
+            Some((id, PendingReply::Approval(kind))) => {
+                // A reply that does not match a known option is a rejection (Abort).
+                let options = match kind {
+                    ApprovalKind::Exec | ApprovalKind::Mcp => EXEC_APPROVAL_OPTIONS,
+                    ApprovalKind::Escalation => ESCALATION_APPROVAL_OPTIONS,
+                    ApprovalKind::Patch => PATCH_APPROVAL_OPTIONS,
+                };
+                let ApprovalReply { decision, feedback } = self
+                    .approval_parser
+                    .parse(&text, options)
+                    .unwrap_or(ApprovalReply {
+                        decision: ReviewDecision::Abort,
+                        feedback: None,
+                    });
+                self.resolve(id, kind, decision);
+                // The turn keeps running after a denial, so the feedback is
+                // picked up together with the rejected tool call's output.
+                if let Some(feedback) = feedback {
+                    self.forward_user_input(format_denial_feedback(&feedback));
+                }
+            }
+            None => self.forward_user_input(text),
+        }
+    }
+
+    /// Submit the decision for approval `id` and tell the frontend.
+    fn resolve(&self, id: String, kind: ApprovalKind, decision: ReviewDecision) {
+        let op = match kind {
+            ApprovalKind::Exec | ApprovalKind::Escalation => Op::ExecApproval {
+                id: id.clone(),
+                decision,
+            },
+            ApprovalKind::Patch => Op::PatchApproval {
+                id: id.clone(),
+                decision,
+            },
+            ApprovalKind::Mcp => Op::McpToolCallApproval {
+                id: id.clone(),
+                decision,
+            },
+        };
+        self.submit(op);
+        self.emit(EventMsg::RemoteApprovalResolved(
+            RemoteApprovalResolvedEvent { id, decision },
+        ));
+    }
+
+    fn forward_user_input(&self, text: String) {
+        self.emit(EventMsg::RemoteInputReceived(RemoteInputReceivedEvent {
+            text: text.clone(),
+        }));
+        self.submit(Op::UserInput {
+            items: vec![InputItem::Text { text }],
+        });
+    }
+
+    fn submit(&self, op: Op) {
+        let Some(tx_sub) = self.tx_sub.upgrade() else {
+            return;
+        };
+        let id = format!("remote-{}", self.next_sub_id.fetch_add(1, Ordering::SeqCst));
+        if let Err(e) = tx_sub.try_send(Submission { id, op }) {
+            warn!("RemoteFrontend: failed to submit remote op: {e}");
+        }
+    }
+
+    fn emit(&self, msg: EventMsg) {
+        if let Some(tx_event) = self.tx_event.upgrade() {
+            let _ = tx_event.try_send(Event {
+                id: String::new(),
+                msg,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn router(
+        tx_sub: &async_channel::Sender<Submission>,
+        tx_event: &async_channel::Sender<Event>,
+    ) -> Router {
+        Router {
+            tx_sub: tx_sub.downgrade(),
+            tx_event: tx_event.downgrade(),
+            pending: Mutex::new(VecDeque::new()),
+            patches: Mutex::new(HashMap::new()),
+            preview: None,
+            approval_parser: ApprovalParser::default(),
+            locale: OmnaraLocale::default(),
+            patch_format: PatchFormat::default(),
+            max_message_bytes: 64 * 1024,
+            outbox: Outbox::new(),
+            last_agent_message: Mutex::new(None),
+            next_sub_id: AtomicU64::new(0),
+        }
+    }
+
+    #[tokio::test]
+    async fn outbox_runs_sends_in_queue_order() {
+        let outbox = Outbox::new();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        for (note, delay) in [("exec", 30), ("patch", 0), ("done", 10)] {
+            let sent = sent.clone();
+            outbox.push(async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                sent.lock().unwrap().push(note);
+            });
+        }
+        outbox.flush(Instant::now() + Duration::from_secs(5)).await;
+        assert_eq!(*sent.lock().unwrap(), vec!["exec", "patch", "done"]);
+    }
+
+    #[test]
+    fn replies_resolve_pending_approvals_before_becoming_input() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        router.pending.lock().unwrap().push_back((
+            "sub-7".to_string(),
+            PendingReply::Approval(ApprovalKind::Patch),
+        ));
+
+        router.route("yes".to_string());
+        router.route("carry on".to_string());
+
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|s| s.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                Op::PatchApproval {
+                    id: "sub-7".to_string(),
+                    decision: ReviewDecision::Approved,
+                },
+                Op::UserInput {
+                    items: vec![InputItem::Text {
+                        text: "carry on".to_string()
+                    }],
+                },
+            ]
+        );
+        let events: Vec<EventMsg> = std::iter::from_fn(|| rx_event.try_recv().ok())
+            .map(|e| e.msg)
+            .collect();
+        assert!(matches!(
+            &events[0],
+            EventMsg::RemoteApprovalResolved(ev)
+                if ev.id == "sub-7" && ev.decision == ReviewDecision::Approved
+        ));
+        assert!(matches!(
+            &events[1],
+            EventMsg::RemoteInputReceived(ev) if ev.text == "carry on"
+        ));
+    }
+
+    #[test]
+    fn withdrawn_prompts_no_longer_take_replies() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        router.pending.lock().unwrap().extend([
+            (
+                "sub-4".to_string(),
+                PendingReply::Approval(ApprovalKind::Exec),
+            ),
+            ("call-5".to_string(), PendingReply::Question),
+        ]);
+        router
+            .patches
+            .lock()
+            .unwrap()
+            .insert("sub-4".to_string(), HashMap::new());
+
+        let withdrawn: Vec<(String, &str)> = router
+            .take_all_pending()
+            .into_iter()
+            .map(|(id, reply)| (id, reply.label()))
+            .collect();
+        assert_eq!(
+            withdrawn,
+            vec![
+                ("sub-4".to_string(), "exec"),
+                ("call-5".to_string(), "question"),
+            ]
+        );
+        assert!(router.patches.lock().unwrap().is_empty());
+
+        router.route("yes".to_string());
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|s| s.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![Op::UserInput {
+                items: vec![InputItem::Text {
+                    text: "yes".to_string()
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn tag_commands_wait_for_pending_prompts() {
+        let (tx_sub, _rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        assert_eq!(
+            router.tag_request(" /tag add ci"),
+            Some(Ok(TagCommand::Add(vec!["ci".to_string()])))
+        );
+        assert_eq!(router.tag_request("/tagline"), None);
+        router.pending.lock().unwrap().push_back((
+            "sub-3".to_string(),
+            PendingReply::Approval(ApprovalKind::Exec),
+        ));
+        assert_eq!(router.tag_request("/tag add ci"), None);
+    }
+
+    #[test]
+    fn inline_feedback_denies_then_forwards_feedback() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        router.pending.lock().unwrap().push_back((
+            "sub-2".to_string(),
+            PendingReply::Approval(ApprovalKind::Exec),
+        ));
+
+        router.route("No: run the unit tests only".to_string());
+
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|s| s.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                Op::ExecApproval {
+                    id: "sub-2".to_string(),
+                    decision: ReviewDecision::Denied,
+                },
+                Op::UserInput {
+                    items: vec![InputItem::Text {
+                        text: format_denial_feedback("run the unit tests only"),
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn question_replies_answer_the_tool_call() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        router
+            .pending
+            .lock()
+            .unwrap()
+            .push_back(("call-3".to_string(), PendingReply::Question));
+
+        router.route("use develop".to_string());
+        router.route("thanks".to_string());
+
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|s| s.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                Op::AskUserAnswer {
+                    id: "call-3".to_string(),
+                    answer: "use develop".to_string(),
+                },
+                Op::UserInput {
+                    items: vec![InputItem::Text {
+                        text: "thanks".to_string()
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mcp_approval_replies_answer_the_tool_call() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        router.pending.lock().unwrap().push_back((
+            "sub-5".to_string(),
+            PendingReply::Approval(ApprovalKind::Mcp),
+        ));
+
+        router.route("always".to_string());
+
+        let op = rx_sub.try_recv().unwrap().op;
+        assert!(matches!(
+            op,
+            Op::McpToolCallApproval { id, decision: ReviewDecision::ApprovedForSession }
+                if id == "sub-5"
+        ));
+    }
+
+    #[test]
+    fn unrecognized_approval_reply_aborts() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        router.pending.lock().unwrap().push_back((
+            "sub-1".to_string(),
+            PendingReply::Approval(ApprovalKind::Exec),
+        ));
+
+        router.route("hmm, not sure".to_string());
+
+        let op = rx_sub.try_recv().unwrap().op;
+        assert!(matches!(
+            op,
+            Op::ExecApproval { id, decision: ReviewDecision::Abort } if id == "sub-1"
+        ));
+    }
+
+    #[test]
+    fn preview_replies_only_target_a_pending_patch() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let mut router = router(&tx_sub, &tx_event);
+        router.preview = Some(PreviewSettings {
+            cwd: PathBuf::from("/work"),
+            command: Vec::new(),
+            timeout: Duration::from_secs(30),
+        });
+        let changes = HashMap::from([(
+            PathBuf::from("/work/a.txt"),
+            FileChange::Add {
+                content: "a\n".to_string(),
+            },
+        )]);
+        router
+            .patches
+            .lock()
+            .unwrap()
+            .insert("sub-4".to_string(), changes.clone());
+        router.pending.lock().unwrap().extend([
+            (
+                "sub-4".to_string(),
+                PendingReply::Approval(ApprovalKind::Patch),
+            ),
+            (
+                "sub-5".to_string(),
+                PendingReply::Approval(ApprovalKind::Exec),
+            ),
+        ]);
+
+        assert_eq!(
+            router.preview_request(" /preview "),
+            Some(("sub-4".to_string(), changes))
+        );
+        assert_eq!(router.preview_request("yes"), None);
+        router.route("yes".to_string());
+        assert!(router.patches.lock().unwrap().is_empty());
+        assert_eq!(router.preview_request("/preview"), None);
+        assert!(matches!(
+            rx_sub.try_recv().unwrap().op,
+            Op::PatchApproval { id, decision: ReviewDecision::Approved } if id == "sub-4"
+        ));
+    }
+
+    #[test]
+    fn partial_patch_approvals_exclude_files_before_approving() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        let add = |content: &str| FileChange::Add {
+            content: content.to_string(),
+        };
+        router.patches.lock().unwrap().insert(
+            "sub-2".to_string(),
+            HashMap::from([
+                (PathBuf::from("/work/src/lib.rs"), add("lib\n")),
+                (PathBuf::from("/work/tests/it.rs"), add("test\n")),
+            ]),
+        );
+        router.pending.lock().unwrap().push_back((
+            "sub-2".to_string(),
+            PendingReply::Approval(ApprovalKind::Patch),
+        ));
+
+        router.route("yes except tests/*".to_string());
+
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|s| s.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                Op::ExcludePatchFiles {
+                    id: "sub-2".to_string(),
+                    paths: vec![PathBuf::from("/work/tests/it.rs")],
+                },
+                Op::PatchApproval {
+                    id: "sub-2".to_string(),
+                    decision: ReviewDecision::Approved,
+                },
+                Op::UserInput {
+                    items: vec![InputItem::Text {
+                        text: format_partial_approval_feedback(&[PathBuf::from(
+                            "/work/tests/it.rs"
+                        )]),
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn explain_works_without_preview_settings() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let router = router(&tx_sub, &tx_event);
+        let changes = HashMap::from([(
+            PathBuf::from("/work/a.txt"),
+            FileChange::Delete {
+                content: "a\n".to_string(),
+            },
+        )]);
+        router
+            .patches
+            .lock()
+            .unwrap()
+            .insert("sub-3".to_string(), changes.clone());
+        router.pending.lock().unwrap().push_back((
+            "sub-3".to_string(),
+            PendingReply::Approval(ApprovalKind::Patch),
+        ));
+
+        assert_eq!(router.preview_request("/preview"), None);
+        assert_eq!(
+            router.patch_command_request("/explain", EXPLAIN_COMMAND),
+            Some(("sub-3".to_string(), changes))
+        );
+        assert!(rx_sub.try_recv().is_err());
+    }
+}
@@ -0,0 +1,607 @@
+//! Token-protected HTTP control API on the local machine
+//! (`[omnara.local_control]`).
+//!
+//! Offers what the Omnara dashboard offers — inject input, answer approvals
+//! and questions, read status and the session diff — without the Omnara
+//! service, e.g. from another machine on the network or through an SSH
+//! tunnel. Replies go through a [`Router`], so frontends see the same
+//! [`EventMsg::RemoteInputReceived`] / [`EventMsg::RemoteApprovalResolved`]
+//! events as for dashboard replies.
+//!
+//! Every request needs `Authorization: Bearer <token>`; without it the body
+//! is never read. Bodies are capped at twice `max_remote_message_kb` (room
+//! for JSON escaping) plus 1 KiB, with 413 beyond that. Bodies and responses
+//! are JSON:
+//!
+//! - `GET /status`: `task_running`, `last_agent_message`, and the `pending`
+//!   approvals and questions (`id`, `kind`, `prompt`), oldest first.
+//! - `GET /diff`: the session diff (`diff`, `files_changed`, `insertions`,
+//!   `deletions`), with withheld paths redacted.
+//! - `GET /diff/stats`: per-file line counts (`files` with `path`,
+//!   `insertions`, `deletions`), their totals, and a `summary` such as
+//!   `+120 −34 across 6 files`, without rendering the diff.
+//! - `POST /input` `{"text": ...}`: new user input.
+//! - `POST /approvals/{id}` `{"decision": "approved" | "approved_for_session"
+//!   | "denied" | "abort"}`.
+//! - `POST /questions/{id}` `{"answer": ...}`.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use async_channel::WeakSender;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tracing::info;
+use tracing::warn;
+
+use super::PendingReply;
+use super::Router;
+use crate::config::Config;
+use crate::git_diff_tracker::DiffLimits;
+use crate::git_diff_tracker::MultiRepoDiffTracker;
+use crate::git_diff_tracker::SubmoduleDiffs;
+use crate::omnara_approval::ApprovalParser;
+use crate::omnara_approval::PatchFormat;
+use crate::omnara_sanitize::sanitize_remote_message;
+use crate::omnara_withhold::WithheldPaths;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::Op;
+use crate::protocol::ReviewDecision;
+use crate::protocol::Submission;
+
+pub(crate) struct LocalControl {
+    server: Arc<tiny_http::Server>,
+    handler: Arc<Handler>,
+}
+
+struct Handler {
+    router: Router,
+    token: String,
+    withheld: WithheldPaths,
+    diff: Mutex<MultiRepoDiffTracker>,
+    status: Mutex<Status>,
+}
+
+#[derive(Default)]
+struct Status {
+    task_running: bool,
+    last_agent_message: Option<String>,
+    /// Prompt text of the pending replies, by id.
+    prompts: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct InputReq {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct ApprovalReq {
+    decision: ReviewDecision,
+}
+
+#[derive(Deserialize)]
+struct AnswerReq {
+    answer: String,
+}
+
+impl LocalControl {
+    /// Environment variable that fixes the bearer token instead of
+    /// generating one.
+    pub(crate) const TOKEN_ENV_VAR: &str = "CODEX_LOCAL_CONTROL_TOKEN";
+
+    /// Bind `omnara.local_control.listen` and serve on a background thread
+    /// until the session ends.
+    pub(crate) fn start(
+        config: &Config,
+        listen: &str,
+        token: String,
+        tx_sub: WeakSender<Submission>,
+        tx_event: WeakSender<Event>,
+    ) -> Result<Self, String> {
+        let server = tiny_http::Server::http(listen).map_err(|e| e.to_string())?;
+        let server = Arc::new(server);
+        let handler = Arc::new(Handler::new(config, token, tx_sub, tx_event));
+        info!(%listen, "LocalControl: serving");
+        std::thread::spawn({
+            let server = server.clone();
+            let handler = handler.clone();
+            move || handler.serve(&server)
+        });
+        Ok(Self { server, handler })
+    }
+
+    /// A random bearer token for when `CODEX_LOCAL_CONTROL_TOKEN` is unset.
+    pub(crate) fn generate_token() -> String {
+        format!("{:032x}", rand::rng().random::<u128>())
+    }
+
+    /// Track the session state served by `/status`.
+    pub(crate) fn observe(&self, event: &Event) {
+        self.handler.observe(event);
+    }
+
+    /// Stop serving and release the address.
+    pub(crate) fn shutdown(&self) {
+        self.server.unblock();
+    }
+}
+
+impl Drop for LocalControl {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Handler {
+    fn new(
+        config: &Config,
+        token: String,
+        tx_sub: WeakSender<Submission>,
+        tx_event: WeakSender<Event>,
+    ) -> Self {
+        let mut router = Router::new(
+            tx_sub,
+            tx_event,
+            ApprovalParser::new(&config.omnara.approval_synonyms),
+        );
+        router.locale = config.omnara.locale;
+        router.patch_format = PatchFormat::from(&config.omnara);
+        router.max_message_bytes = config.omnara.max_remote_message_kb as usize * 1024;
+        let mut diff = MultiRepoDiffTracker::new(Some(config.cwd.clone()));
+        diff.set_exclude_patterns(config.omnara.diff_exclude.clone());
+        diff.set_limits(DiffLimits::from(&config.omnara));
+        diff.set_submodules(SubmoduleDiffs::from(&config.omnara));
+        Self {
+            router,
+            token,
+            withheld: WithheldPaths::new(&config.omnara.withhold_paths),
+            diff: Mutex::new(diff),
+            status: Mutex::new(Status::default()),
+        }
+    }
+
+    fn serve(&self, server: &tiny_http::Server) {
+        #[allow(clippy::expect_used)]
+        let content_type =
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                .expect("valid header");
+        for mut request in server.incoming_requests() {
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Authorization"))
+                .map(|header| header.value.as_str().to_string());
+            let method = request.method().as_str().to_string();
+            let url = request.url().to_string();
+            let (status, reply) =
+                self.handle(&method, &url, authorization.as_deref(), request.as_reader());
+            let response = tiny_http::Response::from_string(reply.to_string())
+                .with_status_code(status)
+                .with_header(content_type.clone());
+            let _ = request.respond(response);
+        }
+    }
+
+    /// Answer one request: the status code and JSON body. The body is only
+    /// read once the token checks out, and never past the size limit, so an
+    /// unauthenticated or oversized upload cannot tie up the server thread.
+    fn handle(
+        &self,
+        method: &str,
+        url: &str,
+        authorization: Option<&str>,
+        body: impl Read,
+    ) -> (u16, Value) {
+        let authorized = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| tokens_match(token, &self.token));
+        if !authorized {
+            warn!(method, url, "LocalControl: unauthorized request");
+            return (401, json!({ "error": "missing or wrong bearer token" }));
+        }
+        // Room for a maximal message after JSON escaping.
+        let limit = self.router.max_message_bytes as u64 * 2 + 1024;
+        let mut body_text = String::new();
+        match body.take(limit + 1).read_to_string(&mut body_text) {
+            Ok(read) if read as u64 > limit => {
+                warn!(method, url, "LocalControl: request body too large");
+                return (
+                    413,
+                    json!({ "error": format!("request body over {limit} bytes") }),
+                );
+            }
+            Ok(_) => {}
+            Err(e) => return (400, json!({ "error": e.to_string() })),
+        }
+        let body = body_text.as_str();
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            ("GET", ["status"]) => (200, self.status()),
+            ("GET", ["diff"]) => self.diff(),
+            ("GET", ["diff", "stats"]) => self.diff_stats(),
+            ("POST", ["input"]) => match serde_json::from_str::<InputReq>(body) {
+                Ok(req) => self.input(req.text),
+                Err(e) => bad_request(e),
+            },
+            ("POST", ["approvals", id]) => match serde_json::from_str::<ApprovalReq>(body) {
+                Ok(req) => self.approve(id, req.decision),
+                Err(e) => bad_request(e),
+            },
+            ("POST", ["questions", id]) => match serde_json::from_str::<AnswerReq>(body) {
+                Ok(req) => self.answer(id, req.answer),
+                Err(e) => bad_request(e),
+            },
+            _ => (404, json!({ "error": "not found" })),
+        }
+    }
+
+    fn status(&self) -> Value {
+        let pending = self
+            .router
+            .pending
+            .lock()
+            .map(|pending| pending.clone())
+            .unwrap_or_default();
+        let Ok(status) = self.status.lock() else {
+            return json!({});
+        };
+        let pending: Vec<Value> = pending
+            .iter()
+            .map(|(id, reply)| {
+                json!({
+                    "id": id,
+                    "kind": reply.label(),
+                    "prompt": status.prompts.get(id),
+                })
+            })
+            .collect();
+        json!({
+            "task_running": status.task_running,
+            "last_agent_message": status.last_agent_message,
+            "pending": pending,
+        })
+    }
+
+    fn diff(&self) -> (u16, Value) {
+        let diff = self
+            .diff
+            .lock()
+            .ok()
+            .and_then(|mut tracker| tracker.get_diff());
+        match diff {
+            Some(diff) => (
+                200,
+                json!({
+                    "diff": self.withheld.redact_diff(&diff.text),
+                    "files_changed": diff.stats.files_changed,
+                    "insertions": diff.stats.insertions,
+                    "deletions": diff.stats.deletions,
+                }),
+            ),
+            None => (404, json!({ "error": "not in a git repository" })),
+        }
+    }
+
+    fn diff_stats(&self) -> (u16, Value) {
+        let stats = self
+            .diff
+            .lock()
+            .ok()
+            .and_then(|tracker| tracker.get_diff_stats());
+        match stats {
+            Some(stats) => {
+                let files: Vec<Value> = stats
+                    .files
+                    .iter()
+                    .map(|file| {
+                        json!({
+                            "path": file.path,
+                            "insertions": file.insertions,
+                            "deletions": file.deletions,
+                        })
+                    })
+                    .collect();
+                (
+                    200,
+                    json!({
+                        "files": files,
+                        "files_changed": stats.files.len(),
+                        "insertions": stats.insertions(),
+                        "deletions": stats.deletions(),
+                        "summary": stats.to_string(),
+                    }),
+                )
+            }
+            None => (404, json!({ "error": "not in a git repository" })),
+        }
+    }
+
+    fn input(&self, text: String) -> (u16, Value) {
+        match sanitize_remote_message(&text, self.router.max_message_bytes) {
+            Ok(text) => {
+                self.router.forward_user_input(text);
+                (202, json!({}))
+            }
+            Err(rejected) => (413, json!({ "error": rejected.to_string() })),
+        }
+    }
+
+    fn approve(&self, id: &str, decision: ReviewDecision) -> (u16, Value) {
+        if !matches!(self.pending_reply(id), Some(PendingReply::Approval(_))) {
+            return (404, json!({ "error": format!("no pending approval {id}") }));
+        }
+        if let Some(PendingReply::Approval(kind)) = self.take(id) {
+            self.router.resolve(id.to_string(), kind, decision);
+        }
+        (200, json!({}))
+    }
+
+    fn answer(&self, id: &str, answer: String) -> (u16, Value) {
+        if !matches!(self.pending_reply(id), Some(PendingReply::Question)) {
+            return (404, json!({ "error": format!("no pending question {id}") }));
+        }
+        let answer = match sanitize_remote_message(&answer, self.router.max_message_bytes) {
+            Ok(answer) => answer,
+            Err(rejected) => return (413, json!({ "error": rejected.to_string() })),
+        };
+        self.take(id);
+        self.router.submit(Op::AskUserAnswer {
+            id: id.to_string(),
+            answer,
+        });
+        (200, json!({}))
+    }
+
+    fn pending_reply(&self, id: &str) -> Option<PendingReply> {
+        let pending = self.router.pending.lock().ok()?;
+        pending
+            .iter()
+            .find(|(pending_id, _)| pending_id == id)
+            .map(|(_, reply)| *reply)
+    }
+
+    fn take(&self, id: &str) -> Option<PendingReply> {
+        if let Ok(mut status) = self.status.lock() {
+            status.prompts.remove(id);
+        }
+        self.router.take_pending(id)
+    }
+
+    fn observe(&self, event: &Event) {
+        match &event.msg {
+            EventMsg::TaskStarted(_) => self.update(|status| status.task_running = true),
+            EventMsg::AgentMessage(ev) => {
+                self.update(|status| status.last_agent_message = Some(ev.message.clone()));
+            }
+            // Approvals and questions do not outlive their turn.
+            EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) => {
+                if let Ok(mut pending) = self.router.pending.lock() {
+                    pending.clear();
+                }
+                if let Ok(mut patches) = self.router.patches.lock() {
+                    patches.clear();
+                }
+                self.update(|status| {
+                    status.task_running = false;
+                    status.prompts.clear();
+                });
+            }
+            _ => {
+                if let Some((prompt, Some((id, reply)))) = self.router.prompt(event, &self.withheld)
+                {
+                    if let Ok(mut pending) = self.router.pending.lock() {
+                        pending.push_back((id.clone(), reply));
+                    }
+                    self.update(|status| {
+                        status.prompts.insert(id, prompt);
+                    });
+                }
+            }
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Status)) {
+        if let Ok(mut status) = self.status.lock() {
+            f(&mut status);
+        }
+    }
+}
+
+fn bad_request(e: serde_json::Error) -> (u16, Value) {
+    (400, json!({ "error": e.to_string() }))
+}
+
+/// Compare without leaking, through timing, how much of a guess was right.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use crate::protocol::ExecApprovalRequestEvent;
+    use crate::protocol::InputItem;
+    use crate::protocol::TaskCompleteEvent;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    const AUTH: Option<&str> = Some("Bearer s3cret");
+
+    fn handler(
+        tx_sub: &async_channel::Sender<Submission>,
+        tx_event: &async_channel::Sender<Event>,
+    ) -> Handler {
+        let codex_home = tempfile::tempdir().expect("create temp dir");
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("load default test config");
+        Handler::new(
+            &config,
+            "s3cret".to_string(),
+            tx_sub.downgrade(),
+            tx_event.downgrade(),
+        )
+    }
+
+    fn exec_approval(id: &str) -> Event {
+        Event {
+            id: id.to_string(),
+            msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+                call_id: "call-1".to_string(),
+                command: vec!["cargo".to_string(), "publish".to_string()],
+                cwd: PathBuf::from("/repo"),
+                reason: None,
+                escalation: None,
+                closest_approved: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn requests_need_the_bearer_token() {
+        let (tx_sub, _rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let handler = handler(&tx_sub, &tx_event);
+
+        assert_eq!(handler.handle("GET", "/status", None, "".as_bytes()).0, 401);
+        assert_eq!(
+            handler
+                .handle("GET", "/status", Some("Bearer wrong!"), "".as_bytes())
+                .0,
+            401
+        );
+        assert_eq!(handler.handle("GET", "/status", AUTH, "".as_bytes()).0, 200);
+        assert_eq!(handler.handle("GET", "/nope", AUTH, "".as_bytes()).0, 404);
+    }
+
+    #[test]
+    fn bodies_are_read_only_when_authorized_and_within_the_limit() {
+        struct Unread;
+        impl Read for Unread {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                panic!("read the body of an unauthorized request");
+            }
+        }
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let handler = handler(&tx_sub, &tx_event);
+
+        assert_eq!(handler.handle("POST", "/input", None, Unread).0, 401);
+        let huge = std::io::repeat(b' ').take(1 << 30);
+        let (status, reply) = handler.handle("POST", "/input", AUTH, huge);
+        assert_eq!(status, 413, "{reply}");
+        assert!(rx_sub.try_recv().is_err());
+    }
+
+    #[test]
+    fn input_is_sanitized_and_forwarded() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let handler = handler(&tx_sub, &tx_event);
+
+        let (status, _) = handler.handle(
+            "POST",
+            "/input",
+            AUTH,
+            r#"{"text": "\u001b[31mrun the tests\u001b[0m"}"#.as_bytes(),
+        );
+
+        assert_eq!(status, 202);
+        assert_eq!(
+            rx_sub.try_recv().map(|s| s.op).ok(),
+            Some(Op::UserInput {
+                items: vec![InputItem::Text {
+                    text: "run the tests".to_string()
+                }],
+            })
+        );
+        assert_eq!(
+            handler.handle("POST", "/input", AUTH, "{}".as_bytes()).0,
+            400
+        );
+    }
+
+    #[test]
+    fn pending_approvals_are_listed_and_resolved_by_id() {
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let handler = handler(&tx_sub, &tx_event);
+        handler.observe(&exec_approval("sub-3"));
+
+        let (_, status) = handler.handle("GET", "/status", AUTH, "".as_bytes());
+        assert_eq!(status["pending"][0]["id"], "sub-3");
+        assert_eq!(status["pending"][0]["kind"], "exec");
+        assert!(
+            status["pending"][0]["prompt"]
+                .as_str()
+                .is_some_and(|prompt| prompt.contains("cargo publish"))
+        );
+
+        let body = r#"{"decision": "approved_for_session"}"#;
+        assert_eq!(
+            handler
+                .handle(
+                    "POST",
+                    "/questions/sub-3",
+                    AUTH,
+                    r#"{"answer": "yes"}"#.as_bytes()
+                )
+                .0,
+            404
+        );
+        assert_eq!(
+            handler
+                .handle("POST", "/approvals/sub-3", AUTH, body.as_bytes())
+                .0,
+            200
+        );
+        assert_eq!(
+            rx_sub.try_recv().map(|s| s.op).ok(),
+            Some(Op::ExecApproval {
+                id: "sub-3".to_string(),
+                decision: ReviewDecision::ApprovedForSession,
+            })
+        );
+        assert_eq!(
+            handler
+                .handle("POST", "/approvals/sub-3", AUTH, body.as_bytes())
+                .0,
+            404
+        );
+    }
+
+    #[test]
+    fn turn_end_clears_pending_replies() {
+        let (tx_sub, _rx_sub) = async_channel::unbounded();
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let handler = handler(&tx_sub, &tx_event);
+        handler.observe(&exec_approval("sub-4"));
+        handler.observe(&Event {
+            id: "sub-4".to_string(),
+            msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: None,
+            }),
+        });
+
+        let (_, status) = handler.handle("GET", "/status", AUTH, "".as_bytes());
+        assert_eq!(status["pending"], json!([]));
+        assert_eq!(status["task_running"], false);
+    }
+}
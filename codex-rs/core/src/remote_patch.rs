@@ -0,0 +1,387 @@
+//! Patches sent from the Omnara dashboard (`/apply-patch`).
+//!
+//! A reviewer pastes a unified diff (`git diff` output, optionally in a
+//! ```` ```diff ```` fence). [`RemotePatch::parse`] translates it into the
+//! `apply_patch` format and verifies it against the working tree the way a
+//! model's patch is verified, so it can be shown and approved locally like
+//! one; [`RemotePatch::apply`] then applies it with the same machinery.
+
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::MaybeApplyPatchVerified;
+use codex_apply_patch::maybe_parse_apply_patch_verified;
+use thiserror::Error;
+
+use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::protocol::FileChange;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RemotePatchError {
+    #[error("the message does not contain a diff")]
+    Empty,
+    #[error("line {line}: {message}")]
+    Malformed { line: usize, message: String },
+    #[error("{path}: {message}")]
+    Unsupported { path: String, message: String },
+    #[error("{path}: paths must be relative and stay inside the workspace")]
+    UnsafePath { path: String },
+    #[error("{0}")]
+    DoesNotApply(String),
+    #[error("the patch was interrupted")]
+    Interrupted,
+}
+
+/// A verified patch from the dashboard, ready for local approval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemotePatch {
+    /// The patch in `apply_patch` format, with absolute paths.
+    patch: String,
+    changes: HashMap<PathBuf, FileChange>,
+}
+
+impl RemotePatch {
+    /// Parse the unified diff in `message` and check that it applies to the
+    /// files under `cwd`. Only the first fenced block is read when the
+    /// message has one.
+    pub fn parse(message: &str, cwd: &Path) -> Result<Self, RemotePatchError> {
+        let patch = to_apply_patch(fenced_block(message), cwd)?;
+        let argv = ["apply_patch".to_string(), patch.clone()];
+        match maybe_parse_apply_patch_verified(&argv, cwd) {
+            MaybeApplyPatchVerified::Body(action) => Ok(Self {
+                patch,
+                changes: convert_apply_patch_to_protocol(&action),
+            }),
+            MaybeApplyPatchVerified::CorrectnessError(e) => {
+                Err(RemotePatchError::DoesNotApply(e.to_string()))
+            }
+            MaybeApplyPatchVerified::ShellParseError(e) => {
+                Err(RemotePatchError::DoesNotApply(format!("{e:?}")))
+            }
+            MaybeApplyPatchVerified::NotApplyPatch => Err(RemotePatchError::Empty),
+        }
+    }
+
+    /// The patch keyed by absolute path, for the approval prompt.
+    pub fn changes(&self) -> &HashMap<PathBuf, FileChange> {
+        &self.changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Write the changes; returns how many files were changed.
+    pub fn apply(&self) -> Result<usize, RemotePatchError> {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        codex_apply_patch::apply_patch(&self.patch, &mut stdout, &mut stderr).map_err(|e| {
+            let stderr = String::from_utf8_lossy(&stderr);
+            let message = match stderr.trim() {
+                "" => e.to_string(),
+                stderr => stderr.to_string(),
+            };
+            RemotePatchError::DoesNotApply(message)
+        })?;
+        Ok(self.changes.len())
+    }
+}
+
+/// The first fenced code block in `message`, or all of it.
+fn fenced_block(message: &str) -> &str {
+    let Some(start) = message.find("```") else {
+        return message;
+    };
+    let Some((_, body)) = message[start..].split_once('\n') else {
+        return "";
+    };
+    match body.find("\n```") {
+        Some(end) => &body[..=end],
+        None if body.starts_with("```") => "",
+        None => body,
+    }
+}
+
+/// Translate a unified diff into an `apply_patch` patch whose paths are
+/// `cwd` joined with the diff's (relative) paths.
+fn to_apply_patch(diff: &str, cwd: &Path) -> Result<String, RemotePatchError> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut patch = String::from("*** Begin Patch\n");
+    let mut files = 0;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(rest) = line.strip_prefix("rename from ") {
+            return Err(unsupported(rest, "renames are not supported"));
+        }
+        if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            return Err(unsupported(line, "binary changes are not supported"));
+        }
+        // Skip `diff --git`, `index`, mode lines, and any prose around them.
+        let Some(old) = line.strip_prefix("--- ") else {
+            i += 1;
+            continue;
+        };
+        let Some(new) = lines.get(i + 1).and_then(|line| line.strip_prefix("+++ ")) else {
+            return Err(malformed(i + 2, "expected a `+++` line after `---`"));
+        };
+        let old = header_path(old, "a/");
+        let new = header_path(new, "b/");
+        i += 2;
+
+        let mut hunks: Vec<Vec<String>> = Vec::new();
+        while let Some(header) = lines.get(i).filter(|line| line.starts_with("@@")) {
+            let Some((mut old_left, mut new_left)) = hunk_counts(header) else {
+                return Err(malformed(i + 1, "malformed hunk header"));
+            };
+            i += 1;
+            let mut hunk = Vec::new();
+            while old_left > 0 || new_left > 0 {
+                // Chat clients drop trailing whitespace, and with it a last
+                // context line that was blank.
+                let line = match lines.get(i) {
+                    Some(line) => *line,
+                    None if old_left == new_left => "",
+                    None => return Err(malformed(i, "the hunk ends early")),
+                };
+                let (old_lines, new_lines) = match line.chars().next() {
+                    None | Some(' ') => (1, 1),
+                    Some('-') => (1, 0),
+                    Some('+') => (0, 1),
+                    Some('\\') => {
+                        i += 1;
+                        continue;
+                    }
+                    Some(_) => return Err(malformed(i + 1, "unexpected line in hunk")),
+                };
+                let (Some(old), Some(new)) = (
+                    old_left.checked_sub(old_lines),
+                    new_left.checked_sub(new_lines),
+                ) else {
+                    return Err(malformed(i + 1, "the hunk is longer than its header says"));
+                };
+                (old_left, new_left) = (old, new);
+                hunk.push(if line.is_empty() {
+                    " ".to_string()
+                } else {
+                    line.to_string()
+                });
+                i += 1;
+            }
+            // `\ No newline at end of file` after the last line.
+            while lines.get(i).is_some_and(|line| line.starts_with('\\')) {
+                i += 1;
+            }
+            hunks.push(hunk);
+        }
+
+        match (old, new) {
+            (None, None) => return Err(malformed(i, "both sides of the diff are /dev/null")),
+            (None, Some(relative)) => {
+                let path = resolve(relative, cwd)?;
+                if path.exists() {
+                    return Err(unsupported(relative, "the file already exists"));
+                }
+                patch.push_str(&format!("*** Add File: {}\n", path.display()));
+                for line in hunks.iter().flatten() {
+                    patch.push_str(line);
+                    patch.push('\n');
+                }
+            }
+            (Some(path), None) => {
+                let path = resolve(path, cwd)?;
+                patch.push_str(&format!("*** Delete File: {}\n", path.display()));
+            }
+            (Some(old), Some(new)) => {
+                if old != new {
+                    return Err(unsupported(old, "renames are not supported"));
+                }
+                if hunks.is_empty() {
+                    return Err(unsupported(old, "the diff has no hunks for this file"));
+                }
+                let path = resolve(old, cwd)?;
+                patch.push_str(&format!("*** Update File: {}\n", path.display()));
+                for hunk in &hunks {
+                    patch.push_str("@@\n");
+                    for line in hunk {
+                        patch.push_str(line);
+                        patch.push('\n');
+                    }
+                }
+            }
+        }
+        files += 1;
+    }
+    if files == 0 {
+        return Err(RemotePatchError::Empty);
+    }
+    patch.push_str("*** End Patch\n");
+    Ok(patch)
+}
+
+/// The path in a `---`/`+++` header without its `a/`/`b/` prefix and
+/// timestamp; `None` for `/dev/null`.
+fn header_path<'a>(header: &'a str, prefix: &str) -> Option<&'a str> {
+    let path = header.split('\t').next().unwrap_or_default().trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path))
+}
+
+/// Old and new line counts of a `@@ -a,b +c,d @@` header.
+fn hunk_counts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@ ")?.split(' ');
+    let old = ranges.next()?.strip_prefix('-')?;
+    let new = ranges.next()?.strip_prefix('+')?;
+    let count = |range: &str| match range.split_once(',') {
+        Some((start, count)) => start.parse::<usize>().ok().and(count.parse().ok()),
+        None => range.parse::<usize>().ok().map(|_| 1),
+    };
+    Some((count(old)?, count(new)?))
+}
+
+fn resolve(path: &str, cwd: &Path) -> Result<PathBuf, RemotePatchError> {
+    let unsafe_path = || RemotePatchError::UnsafePath {
+        path: path.to_string(),
+    };
+    let relative = Path::new(path);
+    let inside = !path.is_empty()
+        && relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(unsafe_path());
+    }
+    let resolved = cwd.join(relative);
+    // A symlink can still lead out of the workspace, so check where the file
+    // or its closest existing ancestor really is.
+    let root = cwd.canonicalize().map_err(|_| unsafe_path())?;
+    let existing = resolved
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(cwd);
+    match existing.canonicalize() {
+        Ok(real) if real.starts_with(&root) => Ok(resolved),
+        _ => Err(unsafe_path()),
+    }
+}
+
+fn malformed(line: usize, message: &str) -> RemotePatchError {
+    RemotePatchError::Malformed {
+        line,
+        message: message.to_string(),
+    }
+}
+
+fn unsupported(path: &str, message: &str) -> RemotePatchError {
+    RemotePatchError::Unsupported {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unified_diff_is_verified_then_applied() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        std::fs::write(cwd.join("lib.rs"), "fn a() {}\n\nfn b() {}\nfn c() {}\n").expect("write");
+        std::fs::write(cwd.join("old.txt"), "bye\n").expect("write");
+        let message = "/apply-patch please take this\n```diff\n\
+            diff --git a/lib.rs b/lib.rs\n\
+            index 1111111..2222222 100644\n\
+            --- a/lib.rs\n\
+            +++ b/lib.rs\n\
+            @@ -1,3 +1,3 @@\n\
+            -fn a() {}\n\
+            +fn a() -> u8 { 1 }\n\
+            \n\
+            \x20fn b() {}\n\
+            --- /dev/null\n\
+            +++ b/new.txt\n\
+            @@ -0,0 +1,2 @@\n\
+            +hello\n\
+            +world\n\
+            \\ No newline at end of file\n\
+            --- a/old.txt\n\
+            +++ /dev/null\n\
+            @@ -1 +0,0 @@\n\
+            -bye\n\
+            ```\n\
+            Thanks!";
+
+        let patch = RemotePatch::parse(message, cwd).expect("valid patch");
+        assert_eq!(patch.len(), 3);
+        assert_eq!(
+            patch.changes().get(&cwd.join("new.txt")),
+            Some(&FileChange::Add {
+                content: "hello\nworld\n".to_string()
+            })
+        );
+        assert_eq!(patch.apply(), Ok(3));
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("lib.rs")).expect("read"),
+            "fn a() -> u8 { 1 }\n\nfn b() {}\nfn c() {}\n"
+        );
+        assert!(!cwd.join("old.txt").exists());
+
+        // The same diff no longer applies to the patched files.
+        assert!(matches!(
+            RemotePatch::parse(message, cwd),
+            Err(RemotePatchError::Unsupported { .. })
+        ));
+        let stale = "--- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@\n-fn a() {}\n+fn z() {}\n";
+        assert!(matches!(
+            RemotePatch::parse(stale, cwd),
+            Err(RemotePatchError::DoesNotApply(_))
+        ));
+        assert_eq!(
+            RemotePatch::parse("--- a/../x\n+++ b/../x\n@@ -1 +1 @@\n-a\n+b\n", cwd),
+            Err(RemotePatchError::UnsafePath {
+                path: "../x".to_string()
+            })
+        );
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().expect("tempdir");
+            std::fs::write(outside.path().join("x"), "a\n").expect("write");
+            std::os::unix::fs::symlink(outside.path(), cwd.join("link")).expect("symlink");
+            std::os::unix::fs::symlink(outside.path().join("x"), cwd.join("x.txt"))
+                .expect("symlink");
+            for (diff, path) in [
+                (
+                    "--- /dev/null\n+++ b/link/new\n@@ -0,0 +1 @@\n+b\n",
+                    "link/new",
+                ),
+                (
+                    "--- a/link/x\n+++ b/link/x\n@@ -1 +1 @@\n-a\n+b\n",
+                    "link/x",
+                ),
+                ("--- a/x.txt\n+++ b/x.txt\n@@ -1 +1 @@\n-a\n+b\n", "x.txt"),
+            ] {
+                assert_eq!(
+                    RemotePatch::parse(diff, cwd),
+                    Err(RemotePatchError::UnsafePath {
+                        path: path.to_string()
+                    })
+                );
+            }
+            assert!(!outside.path().join("new").exists());
+        }
+        assert_eq!(
+            RemotePatch::parse("looks good to me", cwd),
+            Err(RemotePatchError::Empty)
+        );
+    }
+}
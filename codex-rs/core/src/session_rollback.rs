@@ -0,0 +1,97 @@
+//! Undo the session's changes (`/rollback` from the Omnara dashboard).
+//!
+//! A [`RollbackPlan`] restores every file the session diff covers to its
+//! content at the diff baseline: edited and deleted files get their original
+//! content back and files the session created are removed. It is built by
+//! the VCS backend that tracks the baseline (see
+//! [`VcsDiffTracker::rollback_files`](crate::vcs_diff_tracker::VcsDiffTracker::rollback_files))
+//! and presents itself as a patch, so it can be approved like one.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::protocol::FileChange;
+
+#[derive(Debug, Error)]
+pub enum RollbackError {
+    #[error("cannot restore {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("rollback was interrupted")]
+    Interrupted,
+}
+
+/// One file to put back as it was at the baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollbackFile {
+    pub path: PathBuf,
+    /// Content at the baseline; `None` when the session created the file.
+    pub original: Option<Vec<u8>>,
+    /// The rollback as a patch change, for the approval prompt.
+    pub change: FileChange,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RollbackPlan {
+    files: Vec<RollbackFile>,
+}
+
+impl RollbackPlan {
+    pub fn new(mut files: Vec<RollbackFile>) -> Self {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Self { files }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// The rollback as a patch, keyed by absolute path.
+    pub fn changes(&self) -> HashMap<PathBuf, FileChange> {
+        self.files
+            .iter()
+            .map(|file| (file.path.clone(), file.change.clone()))
+            .collect()
+    }
+
+    /// Leave `paths` as they are.
+    pub fn exclude(&mut self, paths: &[PathBuf]) {
+        self.files.retain(|file| !paths.contains(&file.path));
+    }
+
+    /// Restore every file; returns how many were restored. Stops at the first
+    /// file that cannot be written, leaving the ones before it restored.
+    pub fn apply(&self) -> Result<usize, RollbackError> {
+        for file in &self.files {
+            restore(&file.path, file.original.as_deref()).map_err(|source| RollbackError::Io {
+                path: file.path.clone(),
+                source,
+            })?;
+        }
+        Ok(self.files.len())
+    }
+}
+
+fn restore(path: &Path, original: Option<&[u8]>) -> std::io::Result<()> {
+    match original {
+        Some(content) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)
+        }
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
@@ -0,0 +1,398 @@
+//! Version-control backends for the session diff.
+//!
+//! [`GitDiffTracker`] handles plain git repositories. Jujutsu repositories
+//! (including colocated jj/git ones, where git's `HEAD` trails the
+//! working-copy commit) and mercurial repositories get their own backends, so
+//! the baseline is the commit the session actually started from.
+//! [`open_tracker`] picks a backend from the markers above a directory.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::git_diff_tracker::DiffLimits;
+use crate::git_diff_tracker::DiffOutput;
+use crate::git_diff_tracker::DiffStatSummary;
+use crate::git_diff_tracker::FileDiff;
+use crate::git_diff_tracker::GitDiffTracker;
+use crate::git_diff_tracker::SubmoduleDiffs;
+use crate::git_diff_tracker::assemble_diff;
+use crate::git_diff_tracker::changed_since;
+use crate::git_diff_tracker::count_exclusions;
+use crate::git_diff_tracker::load_exclude_patterns;
+use crate::git_diff_tracker::untracked_file_diff;
+use crate::session_rollback::RollbackFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vcs {
+    Git,
+    Jj,
+    Hg,
+}
+
+/// A session diff source for one repository.
+pub trait VcsDiffTracker: std::fmt::Debug + Send {
+    /// False when the directory is not a usable repository for this backend.
+    fn enabled(&self) -> bool;
+
+    /// Top-level directory of the tracked repository.
+    fn repo_root(&self) -> Option<PathBuf>;
+
+    /// Set exclude patterns from config, applied alongside `.codexignore`.
+    fn set_exclude_patterns(&mut self, patterns: Vec<String>);
+
+    fn set_limits(&mut self, limits: DiffLimits);
+
+    /// Choose which submodules are diffed; only git has them.
+    fn set_submodules(&mut self, _submodules: SubmoduleDiffs) {}
+
+    fn limits(&self) -> DiffLimits;
+
+    /// Session diff with the total size capped at `budget`; None when disabled.
+    fn get_diff_within(&mut self, budget: usize) -> Option<DiffOutput>;
+
+    /// Returns the session diff when tracking is enabled; its text may be
+    /// empty if there are no changes.
+    fn get_diff(&mut self) -> Option<DiffOutput> {
+        let budget = self.limits().max_total_bytes;
+        self.get_diff_within(budget)
+    }
+
+    /// Return a diff only if it is non-empty and different from the last one
+    /// returned by this method.
+    fn get_diff_if_changed(&mut self) -> Option<DiffOutput>;
+
+    /// Per-file line counts without the diff text; None when disabled or
+    /// when the backend has no cheap way to compute them.
+    fn get_diff_stats(&self) -> Option<DiffStatSummary> {
+        None
+    }
+
+    /// Use the current state of the working copy as the new baseline.
+    fn checkpoint(&mut self);
+
+    /// Files to restore to undo the changes since the baseline; None when
+    /// disabled or when the backend cannot roll back.
+    fn rollback_files(&self) -> Option<Vec<RollbackFile>> {
+        None
+    }
+}
+
+/// Closest repository enclosing `dir`. A colocated jj repository (`.jj` next
+/// to `.git`) is reported as jj.
+pub fn detect_repo(dir: &Path) -> Option<(Vcs, PathBuf)> {
+    dir.ancestors().find_map(|ancestor| {
+        let vcs = if ancestor.join(".jj").is_dir() {
+            Vcs::Jj
+        } else if ancestor.join(".hg").is_dir() {
+            Vcs::Hg
+        } else if ancestor.join(".git").exists() {
+            Vcs::Git
+        } else {
+            return None;
+        };
+        Some((vcs, ancestor.to_path_buf()))
+    })
+}
+
+/// Tracker for the repository containing `cwd` (or the process cwd when
+/// None). Falls back to git when the `jj`/`hg` command is unavailable.
+pub fn open_tracker(cwd: Option<PathBuf>) -> Box<dyn VcsDiffTracker> {
+    let dir = cwd.clone().or_else(|| std::env::current_dir().ok());
+    match dir.as_deref().and_then(detect_repo) {
+        Some((Vcs::Jj, root)) => {
+            let tracker = JjDiffTracker::new(root);
+            if tracker.enabled() {
+                return Box::new(tracker);
+            }
+        }
+        Some((Vcs::Hg, root)) => {
+            let tracker = HgDiffTracker::new(root);
+            if tracker.enabled() {
+                return Box::new(tracker);
+            }
+        }
+        Some((Vcs::Git, _)) | None => {}
+    }
+    Box::new(GitDiffTracker::new(true, cwd))
+}
+
+/// Jujutsu backend: diffs the working-copy commit against the commit `@`
+/// pointed at when tracking started. jj snapshots new files automatically,
+/// so there is no separate untracked pass.
+#[derive(Debug)]
+pub struct JjDiffTracker {
+    root: PathBuf,
+    initial_commit: Option<String>,
+    last_diff_hash: Option<String>,
+    config_excludes: Vec<String>,
+    limits: DiffLimits,
+}
+
+impl JjDiffTracker {
+    pub fn new(root: PathBuf) -> Self {
+        let mut tracker = Self {
+            root: root.canonicalize().unwrap_or(root),
+            initial_commit: None,
+            last_diff_hash: None,
+            config_excludes: Vec::new(),
+            limits: DiffLimits::default(),
+        };
+        tracker.initial_commit = tracker.current_commit();
+        tracker
+    }
+
+    fn current_commit(&self) -> Option<String> {
+        self.run(&["log", "--no-graph", "-r", "@", "-T", "commit_id"])
+            .map(|out| out.trim().to_string())
+            .filter(|id| !id.is_empty())
+    }
+
+    fn run(&self, args: &[&str]) -> Option<String> {
+        run_vcs(Command::new("jj").arg("--color=never"), &self.root, args)
+    }
+}
+
+impl VcsDiffTracker for JjDiffTracker {
+    fn enabled(&self) -> bool {
+        self.initial_commit.is_some()
+    }
+
+    fn repo_root(&self) -> Option<PathBuf> {
+        Some(self.root.clone())
+    }
+
+    fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        self.config_excludes = patterns;
+    }
+
+    fn set_limits(&mut self, limits: DiffLimits) {
+        self.limits = limits;
+    }
+
+    fn limits(&self) -> DiffLimits {
+        self.limits
+    }
+
+    fn get_diff_within(&mut self, budget: usize) -> Option<DiffOutput> {
+        let base = self.initial_commit.as_deref()?;
+        let out = self
+            .run(&["diff", "--git", "--from", base, "--to", "@"])
+            .unwrap_or_default();
+        let files = FileDiff::parse_all(out.trim());
+        Some(exclude_and_assemble(
+            files,
+            &self.root,
+            &self.config_excludes,
+            self.limits,
+            budget,
+        ))
+    }
+
+    fn get_diff_if_changed(&mut self) -> Option<DiffOutput> {
+        let diff = self.get_diff()?;
+        changed_since(&mut self.last_diff_hash, diff)
+    }
+
+    /// jj snapshots the working copy on every command, so the current
+    /// commit id of `@` already includes uncommitted changes.
+    fn checkpoint(&mut self) {
+        if let Some(id) = self.current_commit() {
+            self.initial_commit = Some(id);
+            self.last_diff_hash = None;
+        }
+    }
+}
+
+/// Mercurial backend: diffs the working directory against the parent
+/// revision at start, plus unknown files that were not present then.
+#[derive(Debug)]
+pub struct HgDiffTracker {
+    root: PathBuf,
+    initial_node: Option<String>,
+    initial_unknown: HashSet<String>,
+    last_diff_hash: Option<String>,
+    config_excludes: Vec<String>,
+    limits: DiffLimits,
+}
+
+impl HgDiffTracker {
+    pub fn new(root: PathBuf) -> Self {
+        let mut tracker = Self {
+            root: root.canonicalize().unwrap_or(root),
+            initial_node: None,
+            initial_unknown: HashSet::new(),
+            last_diff_hash: None,
+            config_excludes: Vec::new(),
+            limits: DiffLimits::default(),
+        };
+        tracker.initial_node = tracker.current_node();
+        if tracker.initial_node.is_some() {
+            tracker.initial_unknown = tracker.unknown_paths().into_iter().collect();
+        }
+        tracker
+    }
+
+    fn current_node(&self) -> Option<String> {
+        self.run(&["log", "-r", ".", "-T", "{node}"])
+            .map(|out| out.trim().to_string())
+            .filter(|node| !node.is_empty())
+    }
+
+    /// Unknown (untracked, non-ignored) files relative to the root.
+    fn unknown_paths(&self) -> Vec<String> {
+        self.run(&["status", "--unknown", "--no-status", "--print0"])
+            .unwrap_or_default()
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn run(&self, args: &[&str]) -> Option<String> {
+        // HGPLAIN disables user config that changes output formats.
+        run_vcs(Command::new("hg").env("HGPLAIN", "1"), &self.root, args)
+    }
+}
+
+impl VcsDiffTracker for HgDiffTracker {
+    fn enabled(&self) -> bool {
+        self.initial_node.is_some()
+    }
+
+    fn repo_root(&self) -> Option<PathBuf> {
+        Some(self.root.clone())
+    }
+
+    fn set_exclude_patterns(&mut self, patterns: Vec<String>) {
+        self.config_excludes = patterns;
+    }
+
+    fn set_limits(&mut self, limits: DiffLimits) {
+        self.limits = limits;
+    }
+
+    fn limits(&self) -> DiffLimits {
+        self.limits
+    }
+
+    fn get_diff_within(&mut self, budget: usize) -> Option<DiffOutput> {
+        let base = self.initial_node.as_deref()?;
+        let out = self.run(&["diff", "--git", "-r", base]).unwrap_or_default();
+        let mut files = FileDiff::parse_all(out.trim());
+        for rel in self.unknown_paths() {
+            if !self.initial_unknown.contains(&rel) {
+                files.push(untracked_file_diff(
+                    &rel,
+                    &self.root.join(&rel),
+                    self.limits.max_file_bytes,
+                ));
+            }
+        }
+        Some(exclude_and_assemble(
+            files,
+            &self.root,
+            &self.config_excludes,
+            self.limits,
+            budget,
+        ))
+    }
+
+    fn get_diff_if_changed(&mut self) -> Option<DiffOutput> {
+        let diff = self.get_diff()?;
+        changed_since(&mut self.last_diff_hash, diff)
+    }
+
+    /// Mercurial has no side-effect-free way to snapshot uncommitted changes,
+    /// so the baseline moves to the current parent revision and unknown
+    /// files; edits that were uncommitted before the checkpoint still show.
+    fn checkpoint(&mut self) {
+        if let Some(node) = self.current_node() {
+            self.initial_node = Some(node);
+            self.initial_unknown = self.unknown_paths().into_iter().collect();
+            self.last_diff_hash = None;
+        }
+    }
+}
+
+/// Drop sections matching the exclude patterns (git applies them as
+/// pathspecs instead), then assemble the diff.
+pub(crate) fn exclude_and_assemble(
+    files: Vec<FileDiff>,
+    root: &Path,
+    config_excludes: &[String],
+    limits: DiffLimits,
+    budget: usize,
+) -> DiffOutput {
+    let excludes = load_exclude_patterns(Some(root), config_excludes);
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    let excluded = count_exclusions(&excludes, &paths);
+    let kept = files
+        .into_iter()
+        .filter(|file| !excludes.iter().any(|pattern| pattern.matches(&file.path)))
+        .collect();
+    assemble_diff(kept, excluded, limits, budget)
+}
+
+fn run_vcs(cmd: &mut Command, root: &Path, args: &[&str]) -> Option<String> {
+    let out = cmd.args(args).current_dir(root).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn closest_marker_wins_and_colocated_jj_beats_git() {
+        let tmp = TempDir::new().expect("tempdir");
+        let outer = tmp.path().join("outer");
+        let inner = outer.join("vendor/lib");
+        std::fs::create_dir_all(outer.join(".git")).expect("mkdir");
+        std::fs::create_dir_all(outer.join(".jj")).expect("mkdir");
+        std::fs::create_dir_all(inner.join(".hg")).expect("mkdir");
+        std::fs::create_dir_all(inner.join("src")).expect("mkdir");
+
+        assert_eq!(
+            detect_repo(&inner.join("src")),
+            Some((Vcs::Hg, inner.clone()))
+        );
+        assert_eq!(
+            detect_repo(&outer.join("vendor")),
+            Some((Vcs::Jj, outer.clone()))
+        );
+        assert_eq!(detect_repo(tmp.path()), None);
+    }
+
+    #[test]
+    fn unusable_backend_falls_back_to_git() {
+        let tmp = TempDir::new().expect("tempdir");
+        let repo = tmp.path().join("repo");
+        std::fs::create_dir_all(repo.join(".jj")).expect("mkdir");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&repo)
+                .status()
+                .expect("run git");
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        std::fs::write(repo.join("README.md"), "hello\n").expect("write");
+        git(&["add", "README.md"]);
+        git(&["commit", "-q", "-m", "init"]);
+
+        // An empty `.jj` is not a usable jj repository, so git takes over.
+        let mut tracker = open_tracker(Some(repo.clone()));
+        std::fs::write(repo.join("README.md"), "changed\n").expect("write");
+        let diff = tracker.get_diff_if_changed().expect("diff");
+        assert!(diff.text.contains("+changed"), "{}", diff.text);
+    }
+}
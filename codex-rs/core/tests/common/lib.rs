@@ -7,6 +7,8 @@ use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::ConfigToml;
 
+pub mod mcp_fixture;
+pub mod omnara;
 pub mod responses;
 pub mod test_codex;
 
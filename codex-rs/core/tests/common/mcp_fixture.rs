@@ -0,0 +1,51 @@
+//! A minimal stdio MCP server for tests that need Codex to call an MCP tool.
+//!
+//! The server is a short `python3` script exposing one tool, `touch`, which
+//! creates the file named by its `path` argument, so a test can tell whether
+//! the call actually ran.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+const SERVER_SCRIPT: &str = r#"
+import json, pathlib, sys
+
+for line in sys.stdin:
+    request = json.loads(line)
+    if "id" not in request:
+        continue
+    method = request["method"]
+    if method == "initialize":
+        result = {
+            "protocolVersion": request["params"]["protocolVersion"],
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "fixture", "version": "0.0.0"},
+        }
+    elif method == "tools/list":
+        result = {"tools": [{
+            "name": "touch",
+            "inputSchema": {"type": "object", "properties": {"path": {"type": "string"}}},
+        }]}
+    elif method == "tools/call":
+        pathlib.Path(request["params"]["arguments"]["path"]).touch()
+        result = {"content": [{"type": "text", "text": "touched"}]}
+    else:
+        result = {}
+    print(json.dumps({"jsonrpc": "2.0", "id": request["id"], "result": result}), flush=True)
+"#;
+
+/// Write the fixture server into `dir` and return a `config.toml` snippet
+/// registering it as MCP server `fixture`, so its tool is `fixture__touch`.
+pub fn fixture_mcp_server_config(dir: &Path, require_approval: bool) -> String {
+    let script: PathBuf = dir.join("fixture_mcp_server.py");
+    std::fs::write(&script, SERVER_SCRIPT).expect("write fixture MCP server");
+    format!(
+        r#"
+[mcp_servers.fixture]
+command = "python3"
+args = [{script:?}]
+require_approval = {require_approval}
+"#,
+        script = script.to_string_lossy()
+    )
+}
@@ -0,0 +1,587 @@
+//! In-process mock of the Omnara HTTP API.
+//!
+//! Lets the Omnara client and the TUI bridge be exercised end-to-end without
+//! live API keys: agent/user messages get sequential ids, remote user replies
+//! are queued with [`MockOmnaraServer::push_user_message`] and handed out
+//! (with `user-N` ids) on the next poll, voice notes are queued with
+//! [`MockOmnaraServer::push_voice_note`] and their audio served under
+//! `/attachments/`, the session history is seeded with
+//! [`MockOmnaraServer::push_history_message`], and every non-poll request is
+//! recorded in arrival order.
+//! The version handshake advertises every capability unless
+//! [`MockOmnaraServer::serve_legacy_api`] is called.
+//! [`MockOmnaraServer::client_with_clock`] puts the client's timers on a
+//! [`ManualClock`], so polling backoff, idle suspension, and escalation can
+//! be stepped through without waiting for them.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::io::Read as _;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use codex_core::omnara_client::OmnaraClient;
+use codex_core::omnara_clock::ManualClock;
+use serde_json::Value;
+use serde_json::json;
+use wiremock::Match;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::Request;
+use wiremock::Respond;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::matchers::path_regex;
+
+const PENDING_PATH: &str = "/api/v1/messages/pending";
+const VERSION_PATH: &str = "/api/v1/version";
+
+/// A request received by the mock, excluding pending-message polls.
+#[derive(Debug, Clone)]
+pub struct OmnaraRequest {
+    pub method: String,
+    pub path: String,
+    /// The JSON body, decompressed if it was sent gzip-encoded.
+    pub body: Value,
+    pub traceparent: Option<String>,
+    /// Whether the body was sent with `Content-Encoding: gzip`.
+    pub compressed: bool,
+}
+
+#[derive(Default)]
+struct State {
+    next_message_id: u64,
+    next_user_message_id: u64,
+    /// Queued replies: id, content, the echoed `client_message_id`, and
+    /// attachments.
+    pending: VecDeque<(String, String, Option<String>, Vec<Value>)>,
+    /// Files served under `/attachments/`, by path.
+    files: HashMap<String, Vec<u8>>,
+    /// Session history served by `GET /api/v1/sessions/{id}/messages`.
+    history: Vec<Value>,
+    /// Agent message ids reported as read on the dashboard with each poll.
+    read_on_dashboard: Vec<String>,
+    /// Reactions handed out on the next poll.
+    reactions: Vec<Value>,
+    next_reaction_id: u64,
+    polls: usize,
+}
+
+pub struct MockOmnaraServer {
+    server: MockServer,
+    state: Arc<Mutex<State>>,
+    /// Set during a [`MockOmnaraServer::fail_requests`] outage.
+    down: Arc<AtomicBool>,
+}
+
+/// Matches every request while the server is down.
+struct ServerDown {
+    down: Arc<AtomicBool>,
+}
+
+impl Match for ServerDown {
+    fn matches(&self, _request: &Request) -> bool {
+        self.down.load(Ordering::SeqCst)
+    }
+}
+
+/// An outage started by [`MockOmnaraServer::fail_requests`]; dropping it
+/// brings the server back.
+pub struct Outage {
+    down: Arc<AtomicBool>,
+}
+
+impl Drop for Outage {
+    fn drop(&mut self) {
+        self.down.store(false, Ordering::SeqCst);
+    }
+}
+
+struct MessageIdResponder {
+    state: Arc<Mutex<State>>,
+}
+
+impl Respond for MessageIdResponder {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let mut state = self.state.lock().expect("state lock");
+        state.next_message_id += 1;
+        ResponseTemplate::new(200)
+            .set_body_json(json!({ "message_id": format!("msg-{}", state.next_message_id) }))
+    }
+}
+
+struct PendingResponder {
+    state: Arc<Mutex<State>>,
+}
+
+impl Respond for PendingResponder {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let mut state = self.state.lock().expect("state lock");
+        state.polls += 1;
+        let messages: Vec<Value> = state
+            .pending
+            .drain(..)
+            .map(|(id, content, client_message_id, attachments)| {
+                json!({
+                    "id": id,
+                    "content": content,
+                    "client_message_id": client_message_id,
+                    "sender_type": "USER",
+                    "created_at": null,
+                    "requires_user_input": false,
+                    "attachments": attachments,
+                })
+            })
+            .collect();
+        let reactions = std::mem::take(&mut state.reactions);
+        ResponseTemplate::new(200).set_body_json(json!({
+            "messages": messages,
+            "status": "ok",
+            "read_message_ids": state.read_on_dashboard,
+            "reactions": reactions,
+        }))
+    }
+}
+
+struct FileResponder {
+    state: Arc<Mutex<State>>,
+}
+
+impl Respond for FileResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let state = self.state.lock().expect("state lock");
+        match state.files.get(request.url.path()) {
+            Some(bytes) => ResponseTemplate::new(200).set_body_bytes(bytes.clone()),
+            None => ResponseTemplate::new(404),
+        }
+    }
+}
+
+struct HistoryResponder {
+    state: Arc<Mutex<State>>,
+}
+
+impl Respond for HistoryResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let since = request
+            .url
+            .query_pairs()
+            .find(|(key, _)| key == "since")
+            .and_then(|(_, since)| chrono::DateTime::parse_from_rfc3339(&since).ok());
+        let state = self.state.lock().expect("state lock");
+        let messages: Vec<&Value> = state
+            .history
+            .iter()
+            .filter(|message| {
+                let created_at = message["created_at"]
+                    .as_str()
+                    .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok());
+                since.is_none_or(|since| created_at.is_some_and(|at| at > since))
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({ "messages": messages }))
+    }
+}
+
+impl MockOmnaraServer {
+    /// Start a mock server with every Omnara endpoint used by the client mounted.
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let state = Arc::new(Mutex::new(State::default()));
+        let down = Arc::new(AtomicBool::new(false));
+
+        // A flag rather than a scoped mock: wiremock reorders mocks by
+        // priority, so dropping a scoped guard can remove the wrong one.
+        Mock::given(ServerDown { down: down.clone() })
+            .and(path_regex(r"^/api/v1/"))
+            .respond_with(ResponseTemplate::new(503))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/api/v1/messages/(agent|user)$"))
+            .respond_with(MessageIdResponder {
+                state: state.clone(),
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/api/v1/messages/[^/]+/read$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path_regex(r"^/api/v1/messages/[^/]+/request-input$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path_regex(r"^/api/v1/messages/[^/]+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(PENDING_PATH))
+            .respond_with(PendingResponder {
+                state: state.clone(),
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/attachments"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "attachment_id": "att-1",
+                "url": format!("{}/attachments/att-1", server.uri()),
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/attachments/[^/]+$"))
+            .respond_with(FileResponder {
+                state: state.clone(),
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path_regex(r"^/api/v1/sessions/[^/]+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/api/v1/sessions/[^/]+/share$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "url": format!("{}/share/share-1", server.uri()),
+                "expires_at": "2030-01-01T00:00:00Z",
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/api/v1/sessions/[^/]+/messages$"))
+            .respond_with(HistoryResponder {
+                state: state.clone(),
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/sessions/end"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(VERSION_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "api_version": "1.1",
+                "capabilities": [
+                    "structured_messages",
+                    "attachments",
+                    "gzip_requests",
+                    "message_updates",
+                    "share_links",
+                    "message_history",
+                    "session_tags",
+                    "read_receipts",
+                ],
+            })))
+            .mount(&server)
+            .await;
+
+        Self {
+            server,
+            state,
+            down,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Build a client pointed at this server with a fresh session id.
+    pub fn client(&self) -> OmnaraClient {
+        OmnaraClient::new("test-api-key".to_string(), self.uri(), uuid::Uuid::new_v4())
+    }
+
+    /// Build a client whose polling and timers run on `clock`.
+    pub fn client_with_clock(&self, clock: &Arc<ManualClock>) -> OmnaraClient {
+        self.client().with_clock(clock.clone())
+    }
+
+    /// Queue a remote user reply; it is delivered on the next poll.
+    pub fn push_user_message(&self, content: &str) {
+        let mut state = self.state.lock().expect("state lock");
+        state.next_user_message_id += 1;
+        let id = format!("user-{}", state.next_user_message_id);
+        state
+            .pending
+            .push_back((id, content.to_string(), None, Vec::new()));
+    }
+
+    /// Queue a message from the mobile app with text `content` (often empty)
+    /// and a voice note, served as `audio/ogg` with the content `audio`.
+    pub fn push_voice_note(&self, content: &str, audio: &[u8]) {
+        let mut state = self.state.lock().expect("state lock");
+        state.next_user_message_id += 1;
+        let id = format!("user-{}", state.next_user_message_id);
+        let file = format!("/attachments/{id}.ogg");
+        state.files.insert(file.clone(), audio.to_vec());
+        state.pending.push_back((
+            id.clone(),
+            content.to_string(),
+            None,
+            vec![json!({
+                "url": file,
+                "mime_type": "audio/ogg",
+                "filename": format!("{id}.ogg"),
+            })],
+        ));
+    }
+
+    /// Queue a remote user message with text `content` and the file
+    /// `filename`, served by the mock server.
+    pub fn push_file(&self, content: &str, filename: &str, mime_type: &str, data: &[u8]) {
+        let mut state = self.state.lock().expect("state lock");
+        state.next_user_message_id += 1;
+        let id = format!("user-{}", state.next_user_message_id);
+        let file = format!("/attachments/{id}");
+        state.files.insert(file.clone(), data.to_vec());
+        state.pending.push_back((
+            id,
+            content.to_string(),
+            None,
+            vec![json!({
+                "url": file,
+                "mime_type": mime_type,
+                "filename": filename,
+            })],
+        ));
+    }
+
+    /// Queue a remote user reply with an explicit id, e.g. to replay one the
+    /// server already handed out.
+    pub fn push_user_message_with_id(&self, id: &str, content: &str) {
+        self.state.lock().expect("state lock").pending.push_back((
+            id.to_string(),
+            content.to_string(),
+            None,
+            Vec::new(),
+        ));
+    }
+
+    /// Queue a message the way the server hands back one a client mirrored
+    /// with `POST /api/v1/messages/user`, echoing its `client_message_id`.
+    pub fn push_echoed_user_message(&self, client_message_id: &str, content: &str) {
+        let mut state = self.state.lock().expect("state lock");
+        state.next_user_message_id += 1;
+        let id = format!("user-{}", state.next_user_message_id);
+        state.pending.push_back((
+            id,
+            content.to_string(),
+            Some(client_message_id.to_string()),
+            Vec::new(),
+        ));
+    }
+
+    /// Add a message to the session history; `sender` is `"USER"` or
+    /// `"AGENT"` and `created_at` an RFC 3339 timestamp.
+    pub fn push_history_message(&self, id: &str, sender: &str, content: &str, created_at: &str) {
+        self.state.lock().expect("state lock").history.push(json!({
+            "id": id,
+            "sender_type": sender,
+            "content": content,
+            "created_at": created_at,
+        }));
+    }
+
+    /// Answer every gzip-encoded request with 415 Unsupported Media Type, like
+    /// a server without request decompression.
+    pub async fn reject_compressed_bodies(&self) {
+        Mock::given(header("content-encoding", "gzip"))
+            .respond_with(ResponseTemplate::new(415))
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answer the next `count` request-input calls with 404, like a server
+    /// that has not persisted the message yet.
+    pub async fn delay_request_input(&self, count: u64) {
+        Mock::given(method("PATCH"))
+            .and(path_regex(r"^/api/v1/messages/[^/]+/request-input$"))
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(count)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answer the version handshake with 404, like a server that predates it.
+    pub async fn serve_legacy_api(&self) {
+        Mock::given(method("GET"))
+            .and(path(VERSION_PATH))
+            .respond_with(ResponseTemplate::new(404))
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answer every request with 401 Unauthorized, like a server given a
+    /// revoked API key.
+    pub async fn reject_api_key(&self) {
+        Mock::given(path_regex(r"^/api/v1/"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid API key"))
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answer every request with 503 Service Unavailable, like a server
+    /// that is down, until the returned guard is dropped.
+    pub fn fail_requests(&self) -> Outage {
+        self.down.store(true, Ordering::SeqCst);
+        Outage {
+            down: self.down.clone(),
+        }
+    }
+
+    /// Number of pending-message polls served so far.
+    pub fn poll_count(&self) -> usize {
+        self.state.lock().expect("state lock").polls
+    }
+
+    /// Wait (up to five seconds) until `count` polls were served.
+    pub async fn wait_for_polls(&self, count: usize) {
+        wait_until(|| self.poll_count() >= count, "polls").await;
+    }
+
+    /// Report agent message `id` as read on the dashboard in later polls.
+    pub fn mark_read_on_dashboard(&self, id: &str) {
+        self.state
+            .lock()
+            .expect("state lock")
+            .read_on_dashboard
+            .push(id.to_string());
+    }
+
+    /// Queue a dashboard reaction on agent message `message_id`; it is
+    /// delivered on the next poll.
+    pub fn push_reaction(&self, message_id: &str, emoji: &str) {
+        let mut state = self.state.lock().expect("state lock");
+        state.next_reaction_id += 1;
+        let id = format!("reaction-{}", state.next_reaction_id);
+        state.reactions.push(json!({
+            "id": id,
+            "message_id": message_id,
+            "emoji": emoji,
+        }));
+    }
+
+    /// Ids of the remote messages the client confirmed with a read receipt,
+    /// in arrival order.
+    pub async fn read_receipts(&self) -> Vec<String> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|r| read_receipt_id(r.url.path()).map(str::to_string))
+            .collect()
+    }
+
+    /// All requests received so far except polls, version handshakes, and
+    /// read receipts, in arrival order.
+    pub async fn requests(&self) -> Vec<OmnaraRequest> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| {
+                let path = r.url.path();
+                path != PENDING_PATH && path != VERSION_PATH && read_receipt_id(path).is_none()
+            })
+            .map(|r| {
+                let compressed = r
+                    .headers
+                    .get("content-encoding")
+                    .is_some_and(|value| value == "gzip");
+                let body = if compressed {
+                    let mut plain = Vec::new();
+                    flate2::read::GzDecoder::new(r.body.as_slice())
+                        .read_to_end(&mut plain)
+                        .map(|_| plain)
+                        .unwrap_or_default()
+                } else {
+                    r.body.clone()
+                };
+                OmnaraRequest {
+                    method: r.method.to_string(),
+                    path: r.url.path().to_string(),
+                    body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+                    traceparent: r
+                        .headers
+                        .get("traceparent")
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string),
+                    compressed,
+                }
+            })
+            .collect()
+    }
+
+    /// Bodies of agent messages received so far, in arrival order.
+    pub async fn agent_messages(&self) -> Vec<Value> {
+        self.requests()
+            .await
+            .into_iter()
+            .filter(|r| r.path == "/api/v1/messages/agent")
+            .map(|r| r.body)
+            .collect()
+    }
+
+    /// Wait (up to five seconds) until `predicate` holds for the recorded
+    /// requests, returning them. Panics on timeout.
+    pub async fn wait_for_requests<F>(&self, predicate: F) -> Vec<OmnaraRequest>
+    where
+        F: Fn(&[OmnaraRequest]) -> bool,
+    {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            let requests = self.requests().await;
+            if predicate(&requests) {
+                return requests;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                panic!("timed out waiting for Omnara requests; got {requests:#?}");
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+/// The message id of a read receipt's path (`/api/v1/messages/{id}/read`).
+fn read_receipt_id(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/v1/messages/")?
+        .strip_suffix("/read")
+}
+
+/// Wait (up to five seconds) until `count` sleeps are armed on `clock`, so
+/// advancing it is sure to reach them.
+pub async fn wait_for_sleeps(clock: &ManualClock, count: usize) {
+    wait_until(|| clock.pending_sleeps() >= count, "armed timers").await;
+}
+
+async fn wait_until(done: impl Fn() -> bool, what: &str) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while !done() {
+        if tokio::time::Instant::now() >= deadline {
+            panic!("timed out waiting for {what}");
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
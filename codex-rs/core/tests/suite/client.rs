@@ -694,6 +694,7 @@ async fn azure_responses_request_includes_store_and_reasoning_ids() {
         status: Some("completed".into()),
         action: WebSearchAction::Search {
             query: "weather".into(),
+            sources: Vec::new(),
         },
     });
     prompt.input.push(ResponseItem::FunctionCall {
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::FileChange;
+use codex_core::protocol::Op;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use responses::ev_assistant_message;
+use responses::ev_completed;
+use responses::sse;
+use responses::start_mock_server;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn explain_patch_answers_with_a_standalone_model_request() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let body = sse(vec![
+        ev_assistant_message("m1", "- Adds a greeting to the README."),
+        ev_completed("r1"),
+    ]);
+    responses::mount_sse_once(&server, wiremock::matchers::any(), body).await;
+
+    let TestCodex { codex, .. } = test_codex().build(&server).await?;
+    let changes = HashMap::from([(
+        PathBuf::from("/work/README.md"),
+        FileChange::Add {
+            content: "Hello!\n".to_string(),
+        },
+    )]);
+    codex
+        .submit(Op::ExplainPatch {
+            id: "patch-1".to_string(),
+            changes,
+        })
+        .await?;
+
+    let EventMsg::PatchExplanation(event) =
+        wait_for_event(&codex, |msg| matches!(msg, EventMsg::PatchExplanation(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(event.id, "patch-1");
+    assert_eq!(
+        event.explanation.as_deref(),
+        Some("- Adds a greeting to the README.")
+    );
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    let request: serde_json::Value = requests[0].body_json()?;
+    assert_eq!(request["tools"], serde_json::json!([]));
+    assert_eq!(request["input"].as_array().map(Vec::len), Some(1));
+    let input = request["input"][0]["content"][0]["text"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(input.contains("+Hello!"), "diff missing from {input}");
+
+    Ok(())
+}
@@ -6,10 +6,12 @@ mod compact;
 mod compact_resume_fork;
 mod exec;
 mod exec_stream_events;
+mod explain_patch;
 mod fork_conversation;
 mod json_result;
 mod live_cli;
 mod model_overrides;
+mod omnara_client;
 mod prompt_caching;
 mod review;
 mod rollout_list_find;
@@ -1,12 +1,23 @@
 use codex_core::CodexAuth;
 use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
 use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol_config_types::ReasoningEffort;
 use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
 use core_test_support::wait_for_event;
 use pretty_assertions::assert_eq;
 use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
 
 const CONFIG_TOML: &str = "config.toml";
 
@@ -36,6 +47,7 @@ async fn override_turn_context_does_not_persist_when_config_exists() {
             approval_policy: None,
             sandbox_policy: None,
             model: Some("o3".to_string()),
+            model_provider: None,
             effort: Some(Some(ReasoningEffort::High)),
             summary: None,
         })
@@ -76,6 +88,7 @@ async fn override_turn_context_does_not_create_config_file() {
             approval_policy: None,
             sandbox_policy: None,
             model: Some("o3".to_string()),
+            model_provider: None,
             effort: Some(Some(ReasoningEffort::Medium)),
             summary: None,
         })
@@ -90,3 +103,65 @@ async fn override_turn_context_does_not_create_config_file() {
         "override should not create config.toml"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn override_turn_context_switches_model_provider() {
+    let first = start_mock_server().await;
+    let second = start_mock_server().await;
+    for server in [&first, &second] {
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(sse(vec![ev_completed("resp")])))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+    let provider = |server: &MockServer| ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = provider(&first);
+    config
+        .model_providers
+        .insert("second".to_string(), provider(&second));
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create conversation")
+        .conversation;
+
+    for (text, model_provider) in [("hello 1", None), ("hello 2", Some("second"))] {
+        if let Some(model_provider) = model_provider {
+            codex
+                .submit(Op::OverrideTurnContext {
+                    cwd: None,
+                    approval_policy: None,
+                    sandbox_policy: None,
+                    model: None,
+                    model_provider: Some(model_provider.to_string()),
+                    effort: None,
+                    summary: None,
+                })
+                .await
+                .expect("submit override");
+        }
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text {
+                    text: text.to_string(),
+                }],
+            })
+            .await
+            .expect("submit input");
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    }
+
+    assert_eq!(first.received_requests().await.unwrap().len(), 1);
+    assert_eq!(second.received_requests().await.unwrap().len(), 1);
+}
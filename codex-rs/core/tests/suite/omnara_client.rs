@@ -0,0 +1,989 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_core::config_types::OmnaraHttpConfig;
+use codex_core::config_types::OmnaraMarkdownDialect;
+use codex_core::config_types::OmnaraMessageKind;
+use codex_core::config_types::OmnaraTranscriber;
+use codex_core::config_types::OmnaraTransformStep;
+use codex_core::config_types::OmnaraTransformer;
+use codex_core::config_types::OmnaraUploads;
+use codex_core::config_types::OmnaraVoice;
+use codex_core::error::CodexErr;
+use codex_core::omnara_capabilities::Capability;
+use codex_core::omnara_capabilities::ServerCapabilities;
+use codex_core::omnara_circuit::CircuitPolicy;
+use codex_core::omnara_client::MessageSender;
+use codex_core::omnara_client::OmnaraClient;
+use codex_core::omnara_client::PollPolicy;
+use codex_core::omnara_client::SessionMetadata;
+use codex_core::omnara_client::SharePermissions;
+use codex_core::omnara_clock::ManualClock;
+use codex_core::omnara_doctor::CheckStatus;
+use codex_core::omnara_tags::TagCommand;
+use core_test_support::omnara::MockOmnaraServer;
+use core_test_support::omnara::wait_for_sleeps;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc::unbounded_channel;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_input_targets_last_agent_message() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    let first = client.send_agent_message("first", false).await.unwrap();
+    let second = client.send_agent_message("second", false).await.unwrap();
+    assert_eq!((first.as_str(), second.as_str()), ("msg-1", "msg-2"));
+
+    client.request_user_input_for_last_message().await.unwrap();
+
+    let requests = server.requests().await;
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/api/v1/messages/agent",
+            "/api/v1/messages/agent",
+            "/api/v1/messages/msg-2/request-input",
+        ]
+    );
+    assert_eq!(requests[1].body["content"], "second");
+    assert_eq!(requests[1].body["agent_type"], "codex");
+    assert_eq!(
+        requests[1].body["agent_instance_id"],
+        client.session_id().to_string()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_input_retries_until_the_message_is_persisted() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+    server.delay_request_input(2).await;
+
+    let id = client.send_agent_message("done", false).await.unwrap();
+    client.request_user_input(&id).await.unwrap();
+
+    let requests = server.requests().await;
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/api/v1/messages/agent",
+            "/api/v1/messages/msg-1/request-input",
+            "/api/v1/messages/msg-1/request-input",
+            "/api/v1/messages/msg-1/request-input",
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn polling_delivers_queued_messages_then_stops() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+    server.push_user_message("hello from the dashboard");
+
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+
+    let received = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("message delivered")
+        .expect("channel open");
+    assert_eq!(received, "hello from the dashboard");
+    // The poller exits after delivering a batch, dropping its sender.
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("poller exits"),
+        None
+    );
+    assert_eq!(server.poll_count(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cancelled_poller_does_not_deliver() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    // Let the first (empty) poll go out, then cancel while the poller sleeps.
+    while server.poll_count() == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    client.cancel_polling();
+    server.push_user_message("too late");
+
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("poller exits"),
+        None
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn restarted_client_injects_each_remote_message_once() {
+    let server = MockOmnaraServer::start().await;
+    let state_dir = tempfile::tempdir().expect("tempdir");
+    let ack_path = state_dir.path().join("session.ack.json");
+    let session_id = uuid::Uuid::new_v4();
+    let restart = || {
+        OmnaraClient::new("test-api-key".to_string(), server.uri(), session_id)
+            .with_ack_path(ack_path.clone())
+            .with_poll_policy(PollPolicy {
+                fast_interval: Duration::from_millis(10),
+                ..PollPolicy::default()
+            })
+    };
+
+    let client = restart();
+    client.send_agent_message("working", false).await.unwrap();
+    server.push_user_message("run the tests");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    let received = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("message delivered")
+        .expect("channel open");
+    assert_eq!(received, "run the tests");
+    drop(client);
+
+    // The process "crashed" before the server saw the read; it serves the
+    // same message again alongside a new one.
+    let client = restart();
+    server.push_user_message_with_id("user-1", "run the tests");
+    server.push_user_message("then commit");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    let mut received = Vec::new();
+    while let Some(text) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("poller exits")
+    {
+        received.push(text);
+    }
+    assert_eq!(received, vec!["then commit".to_string()]);
+
+    // The last-read id survived the restart too.
+    client.request_user_input_for_last_message().await.unwrap();
+    let requests = server.requests().await;
+    assert_eq!(
+        requests.last().map(|r| r.path.as_str()),
+        Some("/api/v1/messages/msg-1/request-input")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn crash_before_recording_redelivers_and_crash_after_does_not() {
+    let server = MockOmnaraServer::start().await;
+    let state_dir = tempfile::tempdir().expect("tempdir");
+    let ack_path = state_dir.path().join("session.ack.json");
+    let session_id = uuid::Uuid::new_v4();
+    let restart = || {
+        OmnaraClient::new("test-api-key".to_string(), server.uri(), session_id)
+            .with_ack_path(ack_path.clone())
+            .with_poll_policy(PollPolicy {
+                fast_interval: Duration::from_millis(10),
+                ..PollPolicy::default()
+            })
+    };
+    async fn drain(rx: &mut tokio::sync::mpsc::UnboundedReceiver<String>) -> Vec<String> {
+        let mut received = Vec::new();
+        while let Some(text) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("poller exits")
+        {
+            received.push(text);
+        }
+        received
+    }
+
+    // Crash during the hand-off: the poller dies before recording the id.
+    let client = restart();
+    client.send_agent_message("working", false).await.unwrap();
+    server.push_user_message("run the tests");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+        panic!("simulated crash before the message is recorded");
+    });
+    assert_eq!(drain(&mut rx).await, vec!["run the tests".to_string()]);
+    drop(client);
+
+    // The restarted client gets the message again and delivers it.
+    let client = restart();
+    server.push_user_message_with_id("user-1", "run the tests");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    assert_eq!(drain(&mut rx).await, vec!["run the tests".to_string()]);
+    drop(client);
+
+    // Crash after recording: the redelivered id is skipped.
+    let client = restart();
+    server.push_user_message_with_id("user-1", "run the tests");
+    server.push_user_message("then commit");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    assert_eq!(drain(&mut rx).await, vec!["then commit".to_string()]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn polling_skips_echoes_of_local_messages() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+    client
+        .send_user_message("typed locally", true)
+        .await
+        .unwrap();
+    let requests = server.requests().await;
+    let origin = requests[0].body["client_message_id"]
+        .as_str()
+        .expect("local message tagged")
+        .to_string();
+
+    server.push_echoed_user_message(&origin, "typed locally");
+    server.push_echoed_user_message("another-client", "typed on a laptop");
+    server.push_user_message("from the dashboard");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    let mut received = Vec::new();
+    while let Some(text) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("poller exits")
+    {
+        received.push(text);
+    }
+    assert_eq!(
+        received,
+        vec![
+            "typed on a laptop".to_string(),
+            "from the dashboard".to_string()
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn voice_notes_are_forwarded_as_transcripts() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let server = MockOmnaraServer::start().await;
+    // A stand-in for whisper.cpp that "transcribes" by printing the audio.
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("whisper-cli");
+    std::fs::write(
+        &binary,
+        "#!/bin/sh\n\
+         if grep -q garbled \"$4\"; then echo 'cannot decode' >&2; exit 1; fi\n\
+         echo; printf '  '; cat \"$4\"; echo\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let client = server.client().with_voice(&OmnaraVoice {
+        transcriber: Some(OmnaraTranscriber::WhisperCpp {
+            binary: binary.clone(),
+            model: dir.path().join("ggml-base.en.bin"),
+            language: None,
+        }),
+        ..OmnaraVoice::default()
+    });
+
+    server.push_voice_note("", b"Run the tests, please.");
+    server.push_voice_note("", b"garbled");
+    server.push_user_message("typed instead");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    let mut received = Vec::new();
+    while let Some(text) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("poller exits")
+    {
+        received.push(text);
+    }
+    assert_eq!(
+        received,
+        vec![
+            "(from voice) Run the tests, please.".to_string(),
+            "typed instead".to_string(),
+        ]
+    );
+    let notes = server.agent_messages().await;
+    assert_eq!(notes.len(), 1);
+    assert_eq!(
+        notes[0]["content"],
+        format!(
+            "🎙️ Could not transcribe the voice note: `{}` failed: cannot decode",
+            binary.display()
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn attached_files_are_saved_into_the_workspace() {
+    let server = MockOmnaraServer::start().await;
+    let workspace = tempfile::tempdir().unwrap();
+    let client = server.client().with_uploads(
+        &OmnaraUploads {
+            enabled: true,
+            max_mb: 1,
+            ..OmnaraUploads::default()
+        },
+        workspace.path(),
+    );
+
+    server.push_file(
+        "Plot this",
+        "sales.csv",
+        "text/csv",
+        b"month,total\njan,3\n",
+    );
+    server.push_file(
+        "",
+        "../../dump.bin",
+        "application/octet-stream",
+        &vec![0; 2 << 20],
+    );
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    let mut received = Vec::new();
+    while let Some(text) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("poller exits")
+    {
+        received.push(text);
+    }
+    assert_eq!(
+        received,
+        vec!["Plot this\n\n(attached file: uploads/sales.csv, 18 B)".to_string()]
+    );
+    assert_eq!(
+        std::fs::read(workspace.path().join("uploads/sales.csv")).unwrap(),
+        b"month,total\njan,3\n"
+    );
+    let notes: Vec<_> = server
+        .agent_messages()
+        .await
+        .into_iter()
+        .map(|note| note["content"].as_str().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(
+        notes,
+        vec![
+            "📎 Saved `uploads/sales.csv` (18 B)".to_string(),
+            "📎 Could not save `dump.bin`: the file is larger than 1 MiB".to_string(),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn read_receipts_confirm_delivered_messages_and_report_seen_notes() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+    let seen = client.subscribe_last_message_seen();
+    let id = client
+        .send_agent_message("waiting for review", false)
+        .await
+        .unwrap();
+    assert!(!*seen.borrow());
+
+    server.mark_read_on_dashboard(&id);
+    server.push_user_message("looks good");
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    while tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("poller exits")
+        .is_some()
+    {}
+    assert!(*seen.borrow());
+    assert_eq!(server.read_receipts().await, vec!["user-1".to_string()]);
+
+    // A newer note has not been seen yet.
+    client.send_agent_message("done", false).await.unwrap();
+    assert!(!*seen.borrow());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn large_bodies_are_gzipped_until_the_server_rejects_them() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+    let diff = "+ added line\n".repeat(200);
+
+    client.send_agent_message("short", false).await.unwrap();
+    client.send_agent_message(&diff, false).await.unwrap();
+    let requests = server.requests().await;
+    let sent: Vec<(bool, &str)> = requests
+        .iter()
+        .map(|r| (r.compressed, r.body["content"].as_str().unwrap_or_default()))
+        .collect();
+    assert_eq!(sent, vec![(false, "short"), (true, diff.as_str())]);
+
+    server.reject_compressed_bodies().await;
+    let id = client.send_agent_message(&diff, false).await.unwrap();
+    client.send_agent_message(&diff, false).await.unwrap();
+    assert_eq!(id, "msg-3");
+    let compressed: Vec<bool> = server.requests().await[2..]
+        .iter()
+        .map(|r| r.compressed)
+        .collect();
+    // Rejected once, retried plain, and not compressed again.
+    assert_eq!(compressed, vec![true, false, false]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn configured_proxy_carries_every_request() {
+    let proxy = MockOmnaraServer::start().await;
+    let client = OmnaraClient::new(
+        "test-api-key".to_string(),
+        "http://omnara.invalid".to_string(),
+        uuid::Uuid::new_v4(),
+    )
+    .with_http_config(&OmnaraHttpConfig {
+        proxy: Some(proxy.uri()),
+        ..OmnaraHttpConfig::default()
+    });
+
+    let id = client
+        .send_agent_message("via the proxy", false)
+        .await
+        .unwrap();
+
+    assert_eq!(id, "msg-1");
+    assert_eq!(proxy.agent_messages().await[0]["content"], "via the proxy");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn upload_attachment_sends_base64_content() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    let attachment = client
+        .upload_attachment("output.txt", b"full output", "text/plain")
+        .await
+        .unwrap();
+    assert_eq!(attachment.attachment_id, "att-1");
+
+    let requests = server.requests().await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body["filename"], "output.txt");
+    assert_eq!(requests[0].body["mime_type"], "text/plain");
+    assert_eq!(requests[0].body["content_base64"], "ZnVsbCBvdXRwdXQ=");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn create_share_link_sends_permissions() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    let link = client
+        .create_share_link(SharePermissions::ApproveOnly)
+        .await
+        .unwrap();
+    assert_eq!(link.url, format!("{}/share/share-1", server.uri()));
+    assert_eq!(link.expires_at.as_deref(), Some("2030-01-01T00:00:00Z"));
+
+    let requests = server.requests().await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].path,
+        format!("/api/v1/sessions/{}/share", client.session_id())
+    );
+    assert_eq!(requests[0].body, json!({ "permissions": "approve_only" }));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn message_history_returns_messages_after_since_oldest_first() {
+    let server = MockOmnaraServer::start().await;
+    server.push_history_message("m-3", "AGENT", "Done.", "2025-03-14T09:05:00Z");
+    server.push_history_message("m-2", "USER", "Run the tests", "2025-03-14T09:00:00Z");
+    server.push_history_message("m-1", "AGENT", "Hello", "2025-03-14T08:00:00Z");
+    let client = server.client();
+
+    let since = chrono::DateTime::parse_from_rfc3339("2025-03-14T08:30:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let history = client.message_history(Some(since)).await.unwrap();
+    let summary: Vec<(&str, MessageSender, &str)> = history
+        .iter()
+        .map(|m| (m.id.as_str(), m.sender, m.content.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("m-2", MessageSender::User, "Run the tests"),
+            ("m-3", MessageSender::Agent, "Done."),
+        ]
+    );
+    assert_eq!(client.message_history(None).await.unwrap().len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_metadata_rides_on_first_agent_message_only() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client().with_session_metadata(SessionMetadata {
+        model: Some("gpt-5".to_string()),
+        git_branch: Some("main".to_string()),
+        ..Default::default()
+    });
+
+    client.send_agent_message("first", false).await.unwrap();
+    client.send_agent_message("second", false).await.unwrap();
+
+    let messages = server.agent_messages().await;
+    assert_eq!(
+        messages[0]["session_metadata"],
+        json!({ "model": "gpt-5", "git_branch": "main" })
+    );
+    assert_eq!(messages[1].get("session_metadata"), None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn messages_are_numbered_and_timestamped_in_send_order() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    client.send_agent_message("first", false).await.unwrap();
+    client.send_user_message("reply", true).await.unwrap();
+    client.send_agent_message("second", true).await.unwrap();
+
+    let requests = server.requests().await;
+    let sequences: Vec<&Value> = requests
+        .iter()
+        .map(|r| &r.body["message_metadata"]["sequence"])
+        .collect();
+    assert_eq!(sequences, vec![&json!(0), &json!(1), &json!(2)]);
+    let timestamps: Vec<i64> = requests
+        .iter()
+        .map(|r| r.body["message_metadata"]["timestamp_ms"].as_i64().unwrap())
+        .collect();
+    assert!(timestamps[0] > 0);
+    assert!(timestamps.is_sorted());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn transformers_rewrite_each_message_kind_before_the_dialect() {
+    let server = MockOmnaraServer::start().await;
+    let client = server
+        .client()
+        .with_markdown_dialect(OmnaraMarkdownDialect::Slack)
+        .with_transformers(&[
+            OmnaraTransformer {
+                step: OmnaraTransformStep::Redact {
+                    patterns: Vec::new(),
+                },
+                applies_to: Vec::new(),
+            },
+            OmnaraTransformer {
+                step: OmnaraTransformStep::StripEmoji,
+                applies_to: vec![OmnaraMessageKind::Update],
+            },
+        ]);
+    let key = "sk-0123456789abcdefghijklmnop";
+
+    let id = client
+        .send_agent_message(&format!("**⏳ Running** `OPENAI_API_KEY={key}`"), false)
+        .await
+        .unwrap();
+    client
+        .update_agent_message(&id, "**✅ Done** `cargo test`")
+        .await
+        .unwrap();
+    client
+        .send_user_message(&format!("use {key}"), true)
+        .await
+        .unwrap();
+
+    let requests = server.requests().await;
+    let contents: Vec<&Value> = requests.iter().map(|r| &r.body["content"]).collect();
+    assert_eq!(
+        contents,
+        vec![
+            &json!("*⏳ Running* `OPENAI_API_KEY=[REDACTED]`"),
+            &json!("*Done* `cargo test`"),
+            &json!("use [REDACTED]"),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn messages_and_edits_are_sent_in_the_configured_dialect() {
+    let server = MockOmnaraServer::start().await;
+    let client = server
+        .client()
+        .with_markdown_dialect(OmnaraMarkdownDialect::Slack);
+
+    let id = client
+        .send_agent_message("**⏳ Running** `cargo test`", false)
+        .await
+        .unwrap();
+    client
+        .update_agent_message(&id, "**✅ Command** `cargo test`")
+        .await
+        .unwrap();
+
+    let requests = server.requests().await;
+    let contents: Vec<&Value> = requests.iter().map(|r| &r.body["content"]).collect();
+    assert_eq!(
+        contents,
+        vec![
+            &json!("*⏳ Running* `cargo test`"),
+            &json!("*✅ Command* `cargo test`"),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn set_title_if_unset_only_names_session_once() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    client.set_title_if_unset("first prompt").await.unwrap();
+    client.set_title_if_unset("second prompt").await.unwrap();
+    client.set_title("renamed").await.unwrap();
+
+    let requests = server.requests().await;
+    let session_path = format!("/api/v1/sessions/{}", client.session_id());
+    assert!(requests.iter().all(|r| r.path == session_path));
+    let titles: Vec<&str> = requests
+        .iter()
+        .map(|r| r.body["title"].as_str().unwrap_or_default())
+        .collect();
+    assert_eq!(titles, vec!["first prompt", "renamed"]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tag_commands_send_the_whole_tag_list() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client().with_session_metadata(SessionMetadata {
+        project: Some("codex".to_string()),
+        tags: vec!["backend".to_string()],
+        ..Default::default()
+    });
+
+    let tags = client
+        .apply_tag_command(&TagCommand::Add(vec!["urgent".to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(tags, vec!["backend".to_string(), "urgent".to_string()]);
+    client
+        .apply_tag_command(&TagCommand::Remove(vec!["backend".to_string()]))
+        .await
+        .unwrap();
+    client.send_agent_message("first", false).await.unwrap();
+
+    let requests = server.requests().await;
+    assert_eq!(requests[0].body, json!({ "tags": ["backend", "urgent"] }));
+    assert_eq!(requests[1].body, json!({ "tags": ["urgent"] }));
+    assert_eq!(
+        requests[2].body["session_metadata"],
+        json!({ "project": "codex", "tags": ["urgent"] })
+    );
+    assert_eq!(client.tags(), vec!["urgent".to_string()]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn legacy_server_gets_plain_messages_only() {
+    let server = MockOmnaraServer::start().await;
+    server.serve_legacy_api().await;
+    let client = server.client().with_session_metadata(SessionMetadata {
+        model: Some("gpt-5".to_string()),
+        ..Default::default()
+    });
+
+    let negotiated = client.negotiate().await;
+    assert_eq!(negotiated, ServerCapabilities::legacy());
+    assert!(!client.child().supports(Capability::Attachments));
+
+    let diff = "+ added line\n".repeat(200);
+    client.send_agent_message(&diff, false).await.unwrap();
+    client.set_title("renamed").await.unwrap();
+    let upload = client
+        .upload_attachment("output.txt", b"full output", "text/plain")
+        .await;
+    assert!(upload.is_err());
+    assert!(
+        client
+            .create_share_link(SharePermissions::ReadOnly)
+            .await
+            .is_err()
+    );
+    assert!(client.message_history(None).await.is_err());
+
+    let requests = server.requests().await;
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, vec!["/api/v1/messages/agent"]);
+    assert!(!requests[0].compressed);
+    assert_eq!(requests[0].body.get("session_metadata"), None);
+    assert_eq!(requests[0].body.get("message_metadata"), None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn current_server_reports_its_version() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    let negotiated = client.negotiate().await;
+    assert_eq!(
+        negotiated,
+        ServerCapabilities {
+            api_version: Some("1.1".to_string()),
+            ..ServerCapabilities::full()
+        }
+    );
+    assert_eq!(client.capabilities(), negotiated);
+}
+
+fn idle_policy(end_session_after: Option<Duration>) -> PollPolicy {
+    PollPolicy {
+        fast_interval: Duration::from_secs(1),
+        idle_interval: Duration::from_secs(1),
+        fast_window: Duration::from_secs(1),
+        suspend_after: Some(Duration::from_secs(10 * 60)),
+        end_session_after,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn polling_backs_off_while_idle_and_snaps_back_on_activity() {
+    let server = MockOmnaraServer::start().await;
+    let clock = Arc::new(ManualClock::new());
+    let client = server
+        .client_with_clock(&clock)
+        .with_poll_policy(PollPolicy {
+            fast_interval: Duration::from_secs(1),
+            idle_interval: Duration::from_secs(30),
+            fast_window: Duration::from_secs(60),
+            suspend_after: None,
+            end_session_after: None,
+        });
+
+    client.start_polling(|_| {});
+    server.wait_for_polls(1).await;
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(1));
+    server.wait_for_polls(2).await;
+
+    // Past the fast window the poller waits the idle interval.
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(60));
+    server.wait_for_polls(3).await;
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(29));
+    assert_eq!(server.poll_count(), 3);
+    clock.advance(Duration::from_secs(1));
+    server.wait_for_polls(4).await;
+
+    // Activity wakes the poller at once and restores the fast interval.
+    wait_for_sleeps(&clock, 1).await;
+    client.note_activity();
+    server.wait_for_polls(5).await;
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(1));
+    server.wait_for_polls(6).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn idle_poller_suspends_and_resumes_on_activity() {
+    let server = MockOmnaraServer::start().await;
+    let clock = Arc::new(ManualClock::new());
+    let client = server
+        .client_with_clock(&clock)
+        .with_poll_policy(idle_policy(None));
+
+    let (tx, mut rx) = unbounded_channel();
+    client.start_polling(move |text| {
+        let _ = tx.send(text);
+    });
+    server.wait_for_polls(1).await;
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(10 * 60));
+    let requests = server
+        .wait_for_requests(|requests| !requests.is_empty())
+        .await;
+    let note = requests[0].body["content"].as_str().unwrap_or_default();
+    assert!(note.starts_with("💤 Session idle for 10 min"), "{note}");
+
+    // No polls go out while suspended, however long it stays idle.
+    let polls = server.poll_count();
+    clock.advance(Duration::from_secs(12 * 60 * 60));
+    assert_eq!(clock.pending_sleeps(), 0);
+    assert_eq!(server.poll_count(), polls);
+
+    server.push_user_message("back again");
+    client.note_activity();
+    let received = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("message delivered")
+        .expect("channel open");
+    assert_eq!(received, "back again");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn idle_session_is_ended_after_limit() {
+    let server = MockOmnaraServer::start().await;
+    let clock = Arc::new(ManualClock::new());
+    let client = server
+        .client_with_clock(&clock)
+        .with_poll_policy(idle_policy(Some(Duration::from_secs(2 * 60 * 60))));
+
+    client.start_polling(|_| {});
+    server.wait_for_polls(1).await;
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(10 * 60));
+    server
+        .wait_for_requests(|requests| !requests.is_empty())
+        .await;
+
+    // The end-of-session timer counts from the last activity.
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(109 * 60));
+    assert_eq!(clock.pending_sleeps(), 1);
+    clock.advance(Duration::from_secs(60));
+    let requests = server
+        .wait_for_requests(|requests| requests.iter().any(|r| r.path == "/api/v1/sessions/end"))
+        .await;
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec!["/api/v1/messages/agent", "/api/v1/sessions/end"]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn doctor_probes_a_throwaway_session_and_flags_a_rejected_key() {
+    let server = MockOmnaraServer::start().await;
+    let client = server.client();
+
+    let report = client.doctor().await;
+    let statuses: Vec<(&str, CheckStatus)> = report
+        .checks
+        .iter()
+        .map(|check| (check.name, check.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("Server", CheckStatus::Pass),
+            ("API key", CheckStatus::Pass),
+            ("Session", CheckStatus::Pass),
+            ("Polling", CheckStatus::Pass),
+            ("Clock", CheckStatus::Pass),
+        ]
+    );
+    assert!(report.passed());
+    // The probe runs in its own session, which it ends; this one is untouched.
+    let requests = server.requests().await;
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec!["/api/v1/messages/agent", "/api/v1/sessions/end"]
+    );
+    let probe_session = requests[0].body["agent_instance_id"].clone();
+    assert_ne!(probe_session, json!(client.session_id().to_string()));
+    assert_eq!(requests[1].body["agent_instance_id"], probe_session);
+    assert_eq!(requests[0].body["send_push"], false);
+    assert_eq!(server.poll_count(), 1);
+
+    server.reject_api_key().await;
+    let report = client.doctor().await;
+    assert!(!report.passed());
+    let key = &report.checks[1];
+    assert_eq!(
+        (key.name, key.status, key.detail.as_str()),
+        ("API key", CheckStatus::Fail, "rejected (401 Unauthorized)")
+    );
+    assert!(
+        report.checks[2..4]
+            .iter()
+            .all(|check| check.status == CheckStatus::Skipped)
+    );
+    assert_eq!(server.requests().await.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn circuit_pauses_requests_after_failures_and_probes_until_the_server_answers() {
+    let server = MockOmnaraServer::start().await;
+    let clock = Arc::new(ManualClock::new());
+    let client = server
+        .client_with_clock(&clock)
+        .with_circuit_breaker(CircuitPolicy {
+            failure_threshold: 2,
+            open_for: Duration::from_secs(60),
+        });
+    let (tx, mut changes) = unbounded_channel();
+    let monitor = client.start_circuit_monitor(move |paused| {
+        let _ = tx.send(paused);
+    });
+
+    let outage = server.fail_requests();
+    for content in ["one", "two"] {
+        assert!(matches!(
+            client.send_agent_message(content, false).await,
+            Err(CodexErr::UnexpectedStatus(..))
+        ));
+    }
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("circuit change within timeout"),
+        Some(true)
+    );
+
+    // While open, nothing reaches the server: messages are queued and other
+    // requests fail right away.
+    assert!(matches!(
+        client.send_agent_message("three", false).await,
+        Err(CodexErr::OmnaraUnavailable)
+    ));
+    assert!(matches!(
+        client.request_user_input("msg-1").await,
+        Err(CodexErr::OmnaraUnavailable)
+    ));
+    assert_eq!(client.queued_message_count(), 1);
+    assert_eq!(server.requests().await.len(), 2);
+
+    // A failed probe keeps the circuit open for another period.
+    wait_for_sleeps(&clock, 1).await;
+    clock.advance(Duration::from_secs(60));
+    wait_for_sleeps(&clock, 1).await;
+    assert!(client.circuit_is_open());
+
+    drop(outage);
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("circuit change within timeout"),
+        Some(false)
+    );
+    assert_eq!(client.send_queued_messages().await, 1);
+    assert_eq!(client.queued_message_count(), 0);
+    let requests = server.requests().await;
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].body["content"], "three");
+    monitor.abort();
+}
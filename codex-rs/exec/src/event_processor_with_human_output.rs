@@ -25,7 +25,6 @@ use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
 use codex_core::protocol::WebSearchBeginEvent;
-use codex_core::protocol::WebSearchEndEvent;
 use codex_protocol::num_format::format_with_separators;
 use owo_colors::OwoColorize;
 use owo_colors::Style;
@@ -371,8 +370,8 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 }
             }
             EventMsg::WebSearchBegin(WebSearchBeginEvent { call_id: _ }) => {}
-            EventMsg::WebSearchEnd(WebSearchEndEvent { call_id: _, query }) => {
-                ts_println!(self, "🌐 Searched: {query}");
+            EventMsg::WebSearchEnd(ev) => {
+                ts_println!(self, "🌐 {}", ev.summary());
             }
             EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
                 call_id,
@@ -501,6 +500,12 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::McpToolCallApprovalRequest(_) => {
+                // Denied by `run_main`.
+            }
+            EventMsg::AskUserRequest(_) => {
+                // Not offered without a frontend that can answer.
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     if !self.reasoning_started {
@@ -596,6 +601,11 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::UserMessage(_) => {}
             EventMsg::EnteredReviewMode(_) => {}
             EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::RemoteInputReceived(_) => {}
+            EventMsg::RemoteApprovalResolved(_) => {}
+            EventMsg::PatchExplanation(_) => {}
+            EventMsg::ContextCompacted(_) => {}
+            EventMsg::ContextSummary(_) => {}
         }
         CodexStatus::Running
     }
@@ -20,6 +20,7 @@ use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
@@ -316,6 +317,15 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
 
     // Run the loop until the task is complete.
     while let Some(event) = rx.recv().await {
+        if let EventMsg::McpToolCallApprovalRequest(_) = &event.msg {
+            // Nobody can approve in a headless run; deny rather than wait.
+            conversation
+                .submit(Op::McpToolCallApproval {
+                    id: event.id.clone(),
+                    decision: ReviewDecision::Denied,
+                })
+                .await?;
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
@@ -0,0 +1,40 @@
+#![cfg(not(target_os = "windows"))]
+
+use core_test_support::mcp_fixture::fixture_mcp_server_config;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use tempfile::tempdir;
+
+use crate::suite::common::run_e2e_exec_test;
+
+/// `codex exec` never asks for approval, so a call to a `require_approval`
+/// MCP server is rejected instead of waiting for an answer.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn mcp_tool_call_requiring_approval_is_rejected() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let tmp_cwd = tempdir()?;
+    std::fs::write(
+        tmp_cwd.path().join("config.toml"),
+        fixture_mcp_server_config(tmp_cwd.path(), true),
+    )?;
+    let marker = tmp_cwd.path().join("touched.txt");
+    let arguments = serde_json::json!({ "path": marker }).to_string();
+    let response_streams = vec![
+        sse(vec![
+            ev_function_call("call_0", "fixture__touch", &arguments),
+            ev_completed("request_0"),
+        ]),
+        sse(vec![
+            ev_assistant_message("m1", "The call was rejected."),
+            ev_completed("request_1"),
+        ]),
+    ];
+    run_e2e_exec_test(tmp_cwd.path(), response_streams).await;
+
+    assert!(!marker.exists(), "the tool must not have run");
+    Ok(())
+}
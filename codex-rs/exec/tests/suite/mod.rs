@@ -1,6 +1,7 @@
 // Aggregates all former standalone integration tests as modules.
 mod apply_patch;
 mod common;
+mod mcp_approval;
 mod output_schema;
 mod resume;
 mod sandbox;
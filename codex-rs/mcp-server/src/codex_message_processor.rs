@@ -1205,6 +1205,8 @@ async fn apply_bespoke_event_handling(
             command,
             cwd,
             reason,
+            escalation: _,
+            closest_approved: _,
         }) => {
             let params = ExecCommandApprovalParams {
                 conversation_id,
@@ -1223,6 +1225,19 @@ async fn apply_bespoke_event_handling(
                 on_exec_approval_response(event_id, rx, conversation).await;
             });
         }
+        EventMsg::McpToolCallApprovalRequest(_) => {
+            // This protocol has no request for MCP tool approvals; deny rather
+            // than leave the call waiting forever.
+            if let Err(err) = conversation
+                .submit(Op::McpToolCallApproval {
+                    id: event_id,
+                    decision: ReviewDecision::Denied,
+                })
+                .await
+            {
+                error!("failed to submit McpToolCallApproval: {err}");
+            }
+        }
         // If this is a TurnAborted, reply to any pending interrupt requests.
         EventMsg::TurnAborted(turn_aborted_event) => {
             let pending = {
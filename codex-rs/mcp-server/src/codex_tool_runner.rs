@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::exec_approval::handle_exec_approval_request;
+use crate::mcp_tool_approval::handle_mcp_tool_approval_request;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotificationMeta;
 use crate::patch_approval::handle_patch_approval_request;
@@ -13,12 +14,14 @@ use codex_core::CodexConversation;
 use codex_core::ConversationManager;
 use codex_core::NewConversation;
 use codex_core::config::Config as CodexConfig;
+use codex_core::omnara_client::OmnaraClient;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem;
+use codex_core::protocol::McpToolCallApprovalRequestEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::Submission;
 use codex_core::protocol::TaskCompleteEvent;
@@ -44,6 +47,7 @@ pub async fn run_codex_tool_session(
     conversation_manager: Arc<ConversationManager>,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, ConversationId>>>,
 ) {
+    let omnara_configured = OmnaraClient::configured(&config.omnara, &config.cwd);
     let NewConversation {
         conversation_id,
         conversation,
@@ -88,6 +92,13 @@ pub async fn run_codex_tool_session(
         .lock()
         .await
         .insert(id.clone(), conversation_id);
+
+    // Let core mirror the session to Omnara; MCP clients have no bridge of
+    // their own.
+    if omnara_configured && let Err(e) = conversation.submit(Op::RegisterRemoteFrontend).await {
+        tracing::warn!("Failed to register Omnara remote frontend: {e}");
+    }
+
     let submission = Submission {
         id: sub_id.clone(),
         op: Op::UserInput {
@@ -178,6 +189,8 @@ async fn run_codex_tool_session_inner(
                         cwd,
                         call_id,
                         reason: _,
+                        escalation: _,
+                        closest_approved: _,
                     }) => {
                         handle_exec_approval_request(
                             command,
@@ -220,6 +233,22 @@ async fn run_codex_tool_session_inner(
                         .await;
                         continue;
                     }
+                    EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                        call_id,
+                        invocation,
+                    }) => {
+                        handle_mcp_tool_approval_request(
+                            call_id,
+                            invocation,
+                            outgoing.clone(),
+                            codex.clone(),
+                            request_id.clone(),
+                            request_id_str.clone(),
+                            event.id.clone(),
+                        )
+                        .await;
+                        continue;
+                    }
                     EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
                         let text = match last_agent_message {
                             Some(msg) => msg,
@@ -257,6 +286,7 @@ async fn run_codex_tool_session_inner(
                     EventMsg::AgentReasoningRawContent(_)
                     | EventMsg::AgentReasoningRawContentDelta(_)
                     | EventMsg::TaskStarted(_)
+                    | EventMsg::AskUserRequest(_)
                     | EventMsg::TokenCount(_)
                     | EventMsg::AgentReasoning(_)
                     | EventMsg::AgentReasoningSectionBreak(_)
@@ -281,7 +311,12 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::UserMessage(_)
                     | EventMsg::ShutdownComplete
                     | EventMsg::EnteredReviewMode(_)
-                    | EventMsg::ExitedReviewMode(_) => {
+                    | EventMsg::ExitedReviewMode(_)
+                    | EventMsg::RemoteInputReceived(_)
+                    | EventMsg::RemoteApprovalResolved(_)
+                    | EventMsg::PatchExplanation(_)
+                    | EventMsg::ContextCompacted(_)
+                    | EventMsg::ContextSummary(_) => {
                         // For now, we do not do anything extra for these
                         // events. Note that
                         // send(codex_event_to_notification(&event)) above has
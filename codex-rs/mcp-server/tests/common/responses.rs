@@ -39,6 +39,37 @@ pub fn create_shell_sse_response(
     Ok(sse)
 }
 
+pub fn create_function_call_sse_response(
+    name: &str,
+    arguments: &serde_json::Value,
+    call_id: &str,
+) -> anyhow::Result<String> {
+    let tool_call = json!({
+        "choices": [
+            {
+                "delta": {
+                    "tool_calls": [
+                        {
+                            "id": call_id,
+                            "function": {
+                                "name": name,
+                                "arguments": serde_json::to_string(arguments)?
+                            }
+                        }
+                    ]
+                },
+                "finish_reason": "tool_calls"
+            }
+        ]
+    });
+
+    let sse = format!(
+        "data: {}\n\ndata: DONE\n\n",
+        serde_json::to_string(&tool_call)?
+    );
+    Ok(sse)
+}
+
 pub fn create_final_assistant_message_sse_response(message: &str) -> anyhow::Result<String> {
     let assistant_message = json!({
         "choices": [
@@ -9,6 +9,8 @@ use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use codex_mcp_server::CodexToolCallParam;
 use codex_mcp_server::ExecApprovalElicitRequestParams;
 use codex_mcp_server::ExecApprovalResponse;
+use codex_mcp_server::McpToolApprovalElicitRequestParams;
+use codex_mcp_server::McpToolApprovalResponse;
 use codex_mcp_server::PatchApprovalElicitRequestParams;
 use codex_mcp_server::PatchApprovalResponse;
 use mcp_types::ElicitRequest;
@@ -24,10 +26,12 @@ use tempfile::TempDir;
 use tokio::time::timeout;
 use wiremock::MockServer;
 
+use core_test_support::mcp_fixture::fixture_mcp_server_config;
 use core_test_support::non_sandbox_test;
 use mcp_test_support::McpProcess;
 use mcp_test_support::create_apply_patch_sse_response;
 use mcp_test_support::create_final_assistant_message_sse_response;
+use mcp_test_support::create_function_call_sse_response;
 use mcp_test_support::create_mock_chat_completions_server;
 use mcp_test_support::create_shell_sse_response;
 
@@ -306,6 +310,111 @@ async fn patch_approval_triggers_elicitation() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Test that a call to an MCP server configured with `require_approval`
+/// triggers an elicitation request and that approving it runs the tool.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_mcp_tool_approval_triggers_elicitation() {
+    non_sandbox_test!();
+
+    if let Err(err) = mcp_tool_approval_triggers_elicitation().await {
+        panic!("failure: {err}");
+    }
+}
+
+async fn mcp_tool_approval_triggers_elicitation() -> anyhow::Result<()> {
+    let workdir = TempDir::new()?;
+    let marker = workdir.path().join("touched.txt");
+    let arguments = json!({ "path": marker });
+
+    let McpHandle {
+        process: mut mcp_process,
+        server: _server,
+        dir: _dir,
+    } = create_mcp_process_with_config(
+        vec![
+            create_function_call_sse_response("fixture__touch", &arguments, "call1234")?,
+            create_final_assistant_message_sse_response("Touched!")?,
+        ],
+        |codex_home| fixture_mcp_server_config(codex_home, true),
+    )
+    .await?;
+
+    let codex_request_id = mcp_process
+        .send_codex_tool_call(CodexToolCallParam {
+            prompt: "touch the marker".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    let elicitation_request = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp_process.read_stream_until_request_message(),
+    )
+    .await??;
+
+    let elicitation_request_id = elicitation_request.id.clone();
+    let params = serde_json::from_value::<McpToolApprovalElicitRequestParams>(
+        elicitation_request
+            .params
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("elicitation_request.params must be set"))?,
+    )?;
+    let expected_elicitation_request = JSONRPCRequest {
+        jsonrpc: JSONRPC_VERSION.into(),
+        id: elicitation_request_id.clone(),
+        method: ElicitRequest::METHOD.to_string(),
+        params: Some(serde_json::to_value(&McpToolApprovalElicitRequestParams {
+            message: "Allow Codex to call the `touch` tool of MCP server `fixture`?".to_string(),
+            requested_schema: ElicitRequestParamsRequestedSchema {
+                r#type: "object".to_string(),
+                properties: json!({}),
+                required: None,
+            },
+            codex_elicitation: "mcp-tool-approval".to_string(),
+            codex_mcp_tool_call_id: codex_request_id.to_string(),
+            codex_event_id: params.codex_event_id.clone(),
+            codex_call_id: "call1234".to_string(),
+            codex_server: "fixture".to_string(),
+            codex_tool: "touch".to_string(),
+            codex_arguments: Some(arguments),
+        })?),
+    };
+    assert_eq!(expected_elicitation_request, elicitation_request);
+    assert!(!marker.exists(), "the tool must wait for approval");
+
+    mcp_process
+        .send_response(
+            elicitation_request_id,
+            serde_json::to_value(McpToolApprovalResponse {
+                decision: ReviewDecision::Approved,
+            })?,
+        )
+        .await?;
+
+    let codex_response = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp_process.read_stream_until_response_message(RequestId::Integer(codex_request_id)),
+    )
+    .await??;
+    assert_eq!(
+        JSONRPCResponse {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id: RequestId::Integer(codex_request_id),
+            result: json!({
+                "content": [
+                    {
+                        "text": "Touched!",
+                        "type": "text"
+                    }
+                ]
+            }),
+        },
+        codex_response
+    );
+    assert!(marker.is_file(), "the approved tool should have run");
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_codex_tool_passes_base_instructions() {
     non_sandbox_test!();
@@ -328,7 +437,7 @@ async fn codex_tool_passes_base_instructions() -> anyhow::Result<()> {
 
     // Run `codex mcp` with a specific config.toml.
     let codex_home = TempDir::new()?;
-    create_config_toml(codex_home.path(), &server.uri())?;
+    create_config_toml(codex_home.path(), &server.uri(), "")?;
     let mut mcp_process = McpProcess::new(codex_home.path()).await?;
     timeout(DEFAULT_READ_TIMEOUT, mcp_process.initialize()).await??;
 
@@ -419,9 +528,19 @@ pub struct McpHandle {
 }
 
 async fn create_mcp_process(responses: Vec<String>) -> anyhow::Result<McpHandle> {
+    create_mcp_process_with_config(responses, |_| String::new()).await
+}
+
+/// Like [`create_mcp_process`], with `extra_config(codex_home)` appended to
+/// the config.
+async fn create_mcp_process_with_config(
+    responses: Vec<String>,
+    extra_config: impl FnOnce(&Path) -> String,
+) -> anyhow::Result<McpHandle> {
     let server = create_mock_chat_completions_server(responses).await;
     let codex_home = TempDir::new()?;
-    create_config_toml(codex_home.path(), &server.uri())?;
+    let extra_config = extra_config(codex_home.path());
+    create_config_toml(codex_home.path(), &server.uri(), &extra_config)?;
     let mut mcp_process = McpProcess::new(codex_home.path()).await?;
     timeout(DEFAULT_READ_TIMEOUT, mcp_process.initialize()).await??;
     Ok(McpHandle {
@@ -434,7 +553,11 @@ async fn create_mcp_process(responses: Vec<String>) -> anyhow::Result<McpHandle>
 /// Create a Codex config that uses the mock server as the model provider.
 /// It also uses `approval_policy = "untrusted"` so that we exercise the
 /// elicitation code path for shell commands.
-fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+fn create_config_toml(
+    codex_home: &Path,
+    server_uri: &str,
+    extra_config: &str,
+) -> std::io::Result<()> {
     let config_toml = codex_home.join("config.toml");
     std::fs::write(
         config_toml,
@@ -452,7 +575,7 @@ base_url = "{server_uri}/v1"
 wire_api = "chat"
 request_max_retries = 0
 stream_max_retries = 0
-"#
+{extra_config}"#
         ),
     )
 }
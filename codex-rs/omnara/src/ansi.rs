@@ -0,0 +1,386 @@
+//! Terminal escape sequences in command output.
+//!
+//! Tools such as cargo, pytest, and eslint color their output, and progress
+//! bars redraw a line with carriage returns. Posted as is, the escape bytes
+//! show up on the dashboard as `[1m[32m` noise. [`strip_ansi`] reduces
+//! output to the text a terminal would show; [`ansi_to_html`] keeps the
+//! foreground colors and bold as HTML spans instead, for servers that render
+//! them (`omnara.exec_output_colors`).
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// The 16 basic colors, normal then bright.
+const PALETTE: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0xcd, 0x31, 0x31],
+    [0x0d, 0xbc, 0x79],
+    [0xe5, 0xe5, 0x10],
+    [0x24, 0x72, 0xc8],
+    [0xbc, 0x3f, 0xbc],
+    [0x11, 0xa8, 0xcd],
+    [0xe5, 0xe5, 0xe5],
+    [0x66, 0x66, 0x66],
+    [0xf1, 0x4c, 0x4c],
+    [0x23, 0xd1, 0x8b],
+    [0xf5, 0xf5, 0x43],
+    [0x3b, 0x8e, 0xea],
+    [0xd6, 0x70, 0xd6],
+    [0x29, 0xb8, 0xdb],
+    [0xe5, 0xe5, 0xe5],
+];
+
+/// How escape sequences in command output reach the dashboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnsiOutput {
+    /// Remove them, leaving plain text.
+    #[default]
+    Strip,
+    /// Render colors and bold as HTML spans in a `<pre>` block.
+    Html,
+}
+
+/// `text` without escape sequences or control characters other than
+/// newlines and tabs. A line redrawn with carriage returns keeps its last
+/// non-blank state.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut style = Style::default();
+    for_each_line(
+        text,
+        |line, out_line| {
+            render_line(line, &mut style, |piece, _| out_line.push_str(piece));
+        },
+        &mut out,
+    );
+    out
+}
+
+/// `text` as HTML-escaped text with its foreground colors and bold as
+/// `<span style="...">` elements; everything else [`strip_ansi`] drops is
+/// dropped too. Spans are closed at the end of each line.
+pub fn ansi_to_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut style = Style::default();
+    for_each_line(
+        text,
+        |line, out_line| {
+            let mut open = Style::default();
+            render_line(line, &mut style, |piece, style| {
+                if style != open {
+                    if open != Style::default() {
+                        out_line.push_str("</span>");
+                    }
+                    if style != Style::default() {
+                        out_line.push_str(&style.span());
+                    }
+                    open = style;
+                }
+                escape_html(piece, out_line);
+            });
+            if open != Style::default() {
+                out_line.push_str("</span>");
+            }
+        },
+        &mut out,
+    );
+    out
+}
+
+/// Whether any text in `text` is colored or bold.
+pub fn has_styles(text: &str) -> bool {
+    let mut style = Style::default();
+    let mut styled = false;
+    for line in text.split('\n') {
+        render_line(line, &mut style, |piece, style| {
+            styled |= style != Style::default() && !piece.trim().is_empty();
+        });
+    }
+    styled
+}
+
+/// Call `render` with each line of `text` (CRLF or LF endings) and `out`,
+/// keeping the newlines between them.
+fn for_each_line(text: &str, mut render: impl FnMut(&str, &mut String), out: &mut String) {
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        render(line.strip_suffix('\r').unwrap_or(line), out);
+    }
+}
+
+/// Feed the visible text of `line` to `emit` with the style it is drawn
+/// in, updating `style` with every SGR sequence, including those in parts
+/// a carriage return overwrote.
+fn render_line(line: &str, style: &mut Style, mut emit: impl FnMut(&str, Style)) {
+    let parts: Vec<&str> = line.split('\r').collect();
+    let shown_from = parts
+        .iter()
+        .rposition(|part| {
+            tokens(part)
+                .iter()
+                .any(|token| matches!(token, Token::Text(text) if !text.trim().is_empty()))
+        })
+        .unwrap_or(0);
+    for (i, part) in parts.iter().enumerate() {
+        for token in tokens(part) {
+            match token {
+                Token::Text(text) if i >= shown_from => emit(text, *style),
+                Token::Text(_) => {}
+                Token::Sgr(params) => style.apply(params),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Text(&'a str),
+    /// The parameters of a Select Graphic Rendition sequence (`ESC [ … m`).
+    Sgr(&'a str),
+}
+
+/// `text` split into runs of printable text and SGR sequences; other
+/// escape sequences and control characters are dropped.
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b != ESC && b != 0x7f && (b >= 0x20 || b == b'\t') {
+            i += 1;
+            continue;
+        }
+        if start < i {
+            out.push(Token::Text(&text[start..i]));
+        }
+        i = if b == ESC {
+            skip_escape(text, i, &mut out)
+        } else {
+            i + 1
+        };
+        start = i;
+    }
+    if start < bytes.len() {
+        out.push(Token::Text(&text[start..]));
+    }
+    out
+}
+
+/// The index after the escape sequence starting at `start`, pushing it to
+/// `out` when it is an SGR sequence. A malformed sequence ends before the
+/// byte that broke it.
+fn skip_escape<'a>(text: &'a str, start: usize, out: &mut Vec<Token<'a>>) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = start + 1;
+    match bytes.get(i) {
+        None => i,
+        Some(b'[') => {
+            i += 1;
+            let params = i;
+            while bytes.get(i).is_some_and(|b| (0x30..=0x3f).contains(b)) {
+                i += 1;
+            }
+            let intermediates = i;
+            while bytes.get(i).is_some_and(|b| (0x20..=0x2f).contains(b)) {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(b'm') if intermediates == i => {
+                    out.push(Token::Sgr(&text[params..i]));
+                    i + 1
+                }
+                Some(0x40..=0x7e) => i + 1,
+                _ => i,
+            }
+        }
+        // OSC, DCS, SOS, PM, and APC strings end at BEL or ST (`ESC \`).
+        Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+            i += 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    BEL => return i + 1,
+                    ESC if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            i
+        }
+        Some(0x20..=0x2f) => {
+            while bytes.get(i).is_some_and(|b| (0x20..=0x2f).contains(b)) {
+                i += 1;
+            }
+            if bytes.get(i).is_some_and(u8::is_ascii) {
+                i += 1;
+            }
+            i
+        }
+        Some(b) if b.is_ascii() => i + 1,
+        Some(_) => i,
+    }
+}
+
+/// The attributes HTML output keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    color: Option<[u8; 3]>,
+    bold: bool,
+}
+
+impl Style {
+    /// Apply SGR `params`; an empty parameter counts as 0 (reset).
+    fn apply(&mut self, params: &str) {
+        let mut codes = params
+            .split(';')
+            .map(|code| code.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.color = Some(PALETTE[usize::from(code - 30)]),
+                90..=97 => self.color = Some(PALETTE[usize::from(code - 90 + 8)]),
+                39 => self.color = None,
+                38 => self.color = extended_color(&mut codes),
+                // Backgrounds are dropped, along with their arguments.
+                48 => {
+                    extended_color(&mut codes);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn span(self) -> String {
+        let mut css = Vec::new();
+        if let Some([r, g, b]) = self.color {
+            css.push(format!("color:#{r:02x}{g:02x}{b:02x}"));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        format!("<span style=\"{}\">", css.join(";"))
+    }
+}
+
+/// The color of a `38;5;N` or `38;2;R;G;B` sequence, consuming its
+/// arguments from `codes`.
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<[u8; 3]> {
+    let channel = |code: Option<u16>| u8::try_from(code.unwrap_or(0)).unwrap_or(u8::MAX);
+    match codes.next()? {
+        5 => {
+            let index = u8::try_from(codes.next()?).ok()?;
+            Some(indexed_color(index))
+        }
+        2 => Some([
+            channel(codes.next()),
+            channel(codes.next()),
+            channel(codes.next()),
+        ]),
+        _ => None,
+    }
+}
+
+/// Color `index` of the 256-color palette.
+fn indexed_color(index: u8) -> [u8; 3] {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match index {
+        0..=15 => PALETTE[usize::from(index)],
+        16..=231 => {
+            let cube = index - 16;
+            [
+                LEVELS[usize::from(cube / 36)],
+                LEVELS[usize::from(cube / 6 % 6)],
+                LEVELS[usize::from(cube % 6)],
+            ]
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            [gray, gray, gray]
+        }
+    }
+}
+
+pub(crate) fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CARGO: &str = "\u{1b}[1m\u{1b}[32m   Compiling\u{1b}[0m demo v0.1.0 (/work)\n\
+        \u{1b}[0m\u{1b}[1m\u{1b}[38;5;9merror[E0425]\u{1b}[0m\u{1b}[0m\u{1b}[1m: cannot find value `x` in this scope\u{1b}[0m\n";
+
+    const PYTEST: &str = "\u{1b}[1m============================= test session starts ==============================\u{1b}[0m\n\
+        tests/test_app.py \u{1b}[32m.\u{1b}[0m\u{1b}[31mF\u{1b}[0m\u{1b}[31m                                       [100%]\u{1b}[0m\n\
+        \u{1b}[31m========================= \u{1b}[31m\u{1b}[1m1 failed\u{1b}[0m, \u{1b}[32m1 passed\u{1b}[0m\u{1b}[31m in 0.12s\u{1b}[0m\u{1b}[31m ==========================\u{1b}[0m\n";
+
+    const ESLINT: &str = "\n\u{1b}[4m/work/src/app.js\u{1b}[24m\n  \u{1b}[2m3:7\u{1b}[22m  \u{1b}[31merror\u{1b}[39m  'x' is assigned a value but never used  \u{1b}[2mno-unused-vars\u{1b}[22m\n\n\
+        \u{1b}[31m\u{1b}[1m✖ 1 problem (1 error, 0 warnings)\u{1b}[22m\u{1b}[39m\n";
+
+    #[test]
+    fn common_tool_output_is_stripped_to_its_text() {
+        assert_eq!(
+            strip_ansi(CARGO),
+            "   Compiling demo v0.1.0 (/work)\nerror[E0425]: cannot find value `x` in this scope\n"
+        );
+        assert_eq!(
+            strip_ansi(PYTEST),
+            "============================= test session starts ==============================\n\
+             tests/test_app.py .F                                       [100%]\n\
+             ========================= 1 failed, 1 passed in 0.12s ==========================\n"
+        );
+        assert_eq!(
+            strip_ansi(ESLINT),
+            "\n/work/src/app.js\n  3:7  error  'x' is assigned a value but never used  no-unused-vars\n\n\
+             ✖ 1 problem (1 error, 0 warnings)\n"
+        );
+        assert!(has_styles(CARGO));
+        assert!(!has_styles("plain\n\u{1b}[0m\u{1b}[K"));
+    }
+
+    #[test]
+    fn progress_redraws_and_other_sequences_are_dropped() {
+        assert_eq!(
+            strip_ansi("Downloading  10%\rDownloading 100%\r\nDone\r"),
+            "Downloading 100%\nDone"
+        );
+        assert_eq!(
+            strip_ansi("\u{1b}]0;title\u{7}\u{1b}[2K\u{1b}[1Gbuilt\u{1b}(B \u{8}ok\u{1b}"),
+            "built ok"
+        );
+        // A sequence cut short by truncation ends where it broke.
+        assert_eq!(strip_ansi("done \u{1b}[3 …"), "done …");
+    }
+
+    #[test]
+    fn colors_become_html_spans() {
+        assert_eq!(
+            ansi_to_html(CARGO),
+            "<span style=\"color:#0dbc79;font-weight:bold\">   Compiling</span> demo v0.1.0 (/work)\n\
+             <span style=\"color:#f14c4c;font-weight:bold\">error[E0425]</span>\
+             <span style=\"font-weight:bold\">: cannot find value `x` in this scope</span>\n"
+        );
+        assert_eq!(
+            ansi_to_html("\u{1b}[38;2;255;136;0m<T> & co\u{1b}[39m\n\u{1b}[48;5;1mbg\u{1b}[0m"),
+            "<span style=\"color:#ff8800\">&lt;T&gt; &amp; co</span>\nbg"
+        );
+        // A color set on one line carries over to the next.
+        assert_eq!(
+            ansi_to_html("\u{1b}[31mfirst\nsecond\u{1b}[0m"),
+            "<span style=\"color:#cd3131\">first</span>\n<span style=\"color:#cd3131\">second</span>"
+        );
+    }
+}
@@ -0,0 +1,1978 @@
+//! Dashboard notes and prompts built from Codex events.
+//!
+//! Every function here returns the Markdown posted to Omnara, or the
+//! attachment uploaded alongside it; nothing here talks to the server.
+
+use crate::ansi::AnsiOutput;
+use crate::ansi::ansi_to_html;
+use crate::ansi::escape_html;
+use crate::ansi::has_styles;
+use crate::ansi::strip_ansi;
+use codex_common::elapsed::format_duration;
+use codex_common::summarize_sandbox_policy;
+use codex_core::command_display::code_span;
+use codex_core::command_display::display_command;
+use codex_core::command_display::longest_backtick_run;
+use codex_core::config_types::OmnaraLocale;
+use codex_core::exec_env::is_secret_name;
+use codex_core::omnara_approval::MAX_DIFF_LINES;
+use codex_core::omnara_approval::PatchFormat;
+use codex_core::omnara_approval::PatchHunk;
+use codex_core::omnara_approval::change_line_counts;
+use codex_core::omnara_approval::format_patch_details;
+use codex_core::omnara_approval::patch_files;
+use codex_core::omnara_approval::patch_hunks;
+use codex_core::omnara_client::AttachmentRef;
+use codex_core::omnara_i18n::fill;
+use codex_core::omnara_i18n::strings;
+use codex_core::omnara_transform::Truncation;
+use codex_core::omnara_withhold::WITHHELD_PLACEHOLDER;
+use codex_core::omnara_withhold::WithheldPaths;
+use codex_core::protocol::{
+    AskForApproval, ContextSummaryEvent, ExecSandbox, FileChange, McpInvocation, ReviewFinding,
+    ReviewOutputEvent, SandboxPolicy, TokenUsage, WebSearchEndEvent,
+};
+use codex_protocol::num_format::format_with_separators;
+use codex_protocol::plan_tool::StepStatus;
+use codex_protocol::plan_tool::UpdatePlanArgs;
+use mcp_types::CallToolResult;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub mod build_output;
+pub mod test_results;
+
+use build_output::BuildReport;
+use build_output::MAX_DIAGNOSTICS;
+use build_output::Severity;
+use build_output::format_build_counts;
+use build_output::parse_build_output;
+use test_results::MAX_FAILURES;
+use test_results::TestReport;
+use test_results::format_test_counts;
+use test_results::parse_test_output;
+
+/// Exec output preview limits: lines, characters per line, and total characters.
+const OUTPUT_PREVIEW: Truncation = Truncation {
+    max_lines: 20,
+    max_line_chars: 200,
+    max_chars: 2000,
+};
+/// Maximum characters in a session title derived from the first prompt.
+const MAX_TITLE_CHARS: usize = 60;
+/// Maximum entries listed per section of a turn summary.
+const MAX_DIGEST_ENTRIES: usize = 15;
+/// Maximum characters of each agent message excerpt in a turn summary.
+const MAX_EXCERPT_CHARS: usize = 160;
+/// Maximum result pages listed in a web search note.
+const MAX_WEB_RESULTS: usize = 5;
+/// Lines of recent output shown in a running command's progress note.
+const MAX_PROGRESS_LINES: usize = 10;
+/// Bytes of recent output kept per running command for its progress note.
+const MAX_TAIL_BYTES: usize = 8 * 1024;
+/// Bytes of diff a patch note shows; larger patches get
+/// [`format_patch_summary`] instead.
+const PATCH_NOTE_BUDGET: usize = 12 * 1024;
+
+/// Full artifact backing a note whose inline preview was truncated. The bridge
+/// uploads it and passes the resulting [`AttachmentRef`] back to the formatter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteAttachment {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub mime: &'static str,
+}
+
+/// Full unified diff for a patch, returned only when the inline preview from
+/// [`format_patch_details`] would be truncated. `withheld` files keep their
+/// headers, with a placeholder in place of their content.
+pub fn patch_attachment(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+) -> Option<NoteAttachment> {
+    if patch_fits(changes, withheld) {
+        return None;
+    }
+
+    use std::fmt::Write as _;
+    let mut full = String::new();
+    for (path, change) in changes {
+        let path_str = path.display();
+        if withheld.matches(path) {
+            let (old, new) = match change {
+                FileChange::Add { .. } => ("/dev/null".to_string(), format!("b/{path_str}")),
+                FileChange::Delete { .. } => (format!("a/{path_str}"), "/dev/null".to_string()),
+                FileChange::Update { .. } => (format!("a/{path_str}"), format!("b/{path_str}")),
+            };
+            let _ = writeln!(full, "--- {old}\n+++ {new}\n{WITHHELD_PLACEHOLDER}");
+            continue;
+        }
+        match change {
+            FileChange::Add { content } => {
+                let _ = writeln!(full, "--- /dev/null\n+++ b/{path_str}");
+                for line in content.lines() {
+                    let _ = writeln!(full, "+{line}");
+                }
+            }
+            FileChange::Update { unified_diff, .. } => {
+                let _ = writeln!(full, "--- a/{path_str}\n+++ b/{path_str}");
+                full.push_str(unified_diff);
+                if !unified_diff.ends_with('\n') {
+                    full.push('\n');
+                }
+            }
+            FileChange::Delete { content } => {
+                let _ = writeln!(full, "--- a/{path_str}\n+++ /dev/null");
+                for line in content.lines() {
+                    let _ = writeln!(full, "-{line}");
+                }
+            }
+        }
+    }
+    Some(NoteAttachment {
+        name: "patch.diff".to_string(),
+        bytes: full.into_bytes(),
+        mime: "text/x-diff",
+    })
+}
+
+/// Full command output, returned only when the preview in [`format_exec_note`]
+/// would be truncated or the note lists build diagnostics in its place.
+pub fn exec_output_attachment(command: &[String], output: &ExecOutput) -> Option<NoteAttachment> {
+    let truncated = output_sections(output)
+        .into_iter()
+        .any(|(_, text)| OUTPUT_PREVIEW.preview(&strip_ansi(text)).1);
+    if !truncated && exec_reports(command, output).1.is_none() {
+        return None;
+    }
+    Some(NoteAttachment {
+        name: "output.txt".to_string(),
+        bytes: strip_ansi(&output.formatted_output).into_bytes(),
+        mime: "text/plain",
+    })
+}
+
+fn format_attachment_reference(label: &str, attachment: &AttachmentRef) -> String {
+    format!("📎 [{label}]({})", attachment.url)
+}
+
+/// Whether a patch note can show every file in full: no file's diff is over
+/// [`MAX_DIFF_LINES`] and all of them together stay within
+/// [`PATCH_NOTE_BUDGET`].
+fn patch_fits(changes: &HashMap<PathBuf, FileChange>, withheld: &WithheldPaths) -> bool {
+    let mut total = 0;
+    for (path, change) in changes {
+        if withheld.matches(path) {
+            continue;
+        }
+        let diff = match change {
+            FileChange::Add { content } => content,
+            FileChange::Update { unified_diff, .. } => unified_diff,
+            FileChange::Delete { .. } => continue,
+        };
+        total += diff.len();
+        if diff.lines().count() > MAX_DIFF_LINES || total > PATCH_NOTE_BUDGET {
+            return false;
+        }
+    }
+    true
+}
+
+/// Tiered summary of a patch too large to show in full, within about
+/// `budget` bytes: every file with its `(+A -D)` counts, then the largest
+/// hunks by changed lines while they fit, grouped by file, then how many
+/// files and hunks were left out.
+pub fn format_patch_summary(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+    budget: usize,
+) -> String {
+    use std::fmt::Write as _;
+    let files = patch_files(changes);
+    let mut msg = String::new();
+    let mut hunks = Vec::new();
+    for (index, path) in files.iter().enumerate() {
+        let Some(change) = changes.get(path) else {
+            continue;
+        };
+        let (added, removed) = change_line_counts(change);
+        let label = match change {
+            FileChange::Add { .. } => " (new)",
+            FileChange::Delete { .. } => " (deleted)",
+            FileChange::Update { .. } => "",
+        };
+        let _ = writeln!(msg, "  └ {}{label} (+{added} -{removed})", path.display());
+        if !withheld.matches(path) {
+            hunks.extend(patch_hunks(index, change));
+        }
+    }
+
+    let total_hunks = hunks.len();
+    let mut hunks: Vec<(usize, PatchHunk)> = hunks.into_iter().enumerate().collect();
+    hunks.sort_by(|(_, a), (_, b)| b.changed.cmp(&a.changed));
+    let mut remaining = budget.saturating_sub(msg.len());
+    let mut shown = Vec::new();
+    for (order, hunk) in hunks {
+        // Fence and file header, paid once per file.
+        let cost = hunk.text.len() + files[hunk.file].as_os_str().len() + 16;
+        if cost <= remaining {
+            remaining -= cost;
+            shown.push((order, hunk));
+        }
+    }
+    // Largest first decided what fits; show them in patch order.
+    shown.sort_by_key(|(order, _)| *order);
+
+    let shown_count = shown.len();
+    let mut files_shown = Vec::new();
+    if !shown.is_empty() {
+        let _ = writeln!(
+            msg,
+            "\n**Largest changes** ({shown_count} of {total_hunks} hunks)"
+        );
+    }
+    let mut current = None;
+    for (_, hunk) in shown {
+        if current != Some(hunk.file) {
+            if current.is_some() {
+                msg.push_str("```\n");
+            }
+            let _ = writeln!(msg, "**{}**\n```diff", files[hunk.file].display());
+            current = Some(hunk.file);
+            files_shown.push(hunk.file);
+        }
+        msg.push_str(&hunk.text);
+    }
+    if current.is_some() {
+        msg.push_str("```\n");
+    }
+
+    let files_with_hunks: Vec<usize> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| {
+            !withheld.matches(path)
+                && changes
+                    .get(*path)
+                    .is_some_and(|change| !matches!(change, FileChange::Delete { .. }))
+        })
+        .map(|(index, _)| index)
+        .collect();
+    let omitted_files = files_with_hunks
+        .iter()
+        .filter(|index| !files_shown.contains(index))
+        .count();
+    if omitted_files > 0 || shown_count < total_hunks {
+        let plural = if omitted_files == 1 { "" } else { "s" };
+        let _ = writeln!(
+            msg,
+            "\n_{omitted_files} file{plural} omitted, {} of {total_hunks} hunks not shown_",
+            total_hunks - shown_count
+        );
+    }
+    msg
+}
+
+/// Build a complete non-approval Omnara note for a patch apply event.
+/// Includes a summary line, a file list, and formatted diff details; a patch
+/// too large to show in full gets [`format_patch_summary`] instead. When the
+/// full diff was uploaded, `attachment` links it below.
+pub fn format_patch_note(
+    changes: &HashMap<PathBuf, FileChange>,
+    withheld: &WithheldPaths,
+    format: PatchFormat,
+    attachment: Option<&AttachmentRef>,
+) -> String {
+    let file_count = changes.len();
+    let (details, added, removed) = format_patch_details(changes, withheld, format);
+
+    let mut msg = String::new();
+    use std::fmt::Write as _;
+    let _ = writeln!(
+        &mut msg,
+        "✏️ Applying patch to {} file{} (+{} -{})",
+        file_count,
+        if file_count == 1 { "" } else { "s" },
+        added,
+        removed
+    );
+    if !patch_fits(changes, withheld) {
+        msg.push_str(&format_patch_summary(changes, withheld, PATCH_NOTE_BUDGET));
+        if let Some(attachment) = attachment {
+            msg.push('\n');
+            msg.push_str(&format_attachment_reference("Full diff", attachment));
+        }
+        return msg;
+    }
+    for path in changes.keys() {
+        let _ = writeln!(&mut msg, "  └ {}", path.display());
+    }
+    if !details.is_empty() {
+        msg.push('\n');
+        msg.push_str(&details);
+    }
+    if let Some(attachment) = attachment {
+        msg.push('\n');
+        msg.push_str(&format_attachment_reference("Full diff", attachment));
+    }
+    msg
+}
+
+/// The `**Exec:**` line naming a command, quoted as it runs. A multi-line
+/// script gets a code block of its own, since a code span would fold its
+/// lines into one.
+fn format_exec_headline(command: &[String]) -> String {
+    let command = display_command(command);
+    if command.text.contains('\n') {
+        format!("**Exec:**\n{}", command.code_block())
+    } else {
+        format!("**Exec:** {}", command.code_span())
+    }
+}
+
+/// Build a concise, styled Omnara note for an executed command, with a trimmed output preview.
+/// A recognized test run leads with its counts and lists its failures; a
+/// recognized build or lint run lists its errors and warnings instead of the
+/// preview. When the full output was uploaded, `attachment` links it below.
+pub fn format_exec_note(
+    command: &[String],
+    output: &ExecOutput,
+    context: &ExecNoteContext,
+    attachment: Option<&AttachmentRef>,
+) -> String {
+    let ok = output.exit_code == 0;
+    let status = if ok {
+        "Success".to_string()
+    } else {
+        format!("Failed (exit {})", output.exit_code)
+    };
+
+    let (report, build) = exec_reports(command, output);
+    let mut msg = String::new();
+    if let Some(report) = &report {
+        let icon = if report.failed > 0 { "❌" } else { "✅" };
+        msg.push_str(&format!("**{icon} {}**\n", format_test_counts(report)));
+    }
+    msg.push_str(&format!(
+        "{}\n**Status:** {status} · {}",
+        format_exec_headline(command),
+        format_duration(context.duration)
+    ));
+    msg.push_str(&format!("\n**Cwd:** `{}`", context.cwd.display()));
+    if let Some(sandbox) = context.sandbox {
+        let sandbox = match sandbox {
+            ExecSandbox::Sandboxed => "🔒 sandboxed",
+            ExecSandbox::Unsandboxed => "🔓 not sandboxed",
+            ExecSandbox::Escalated => "⚠️ escalated (ran outside the sandbox)",
+        };
+        msg.push_str(&format!(" · **Sandbox:** {sandbox}"));
+    }
+    if let Some(report) = report.as_ref().filter(|report| !report.failures.is_empty()) {
+        msg.push_str("\n\n");
+        msg.push_str(&format_test_failures(report));
+    }
+
+    if let Some(build) = &build {
+        msg.push_str("\n\n");
+        msg.push_str(&format_build_diagnostics(build));
+    }
+    let sections = if build.is_some() {
+        Vec::new()
+    } else {
+        output_sections(output)
+    };
+    for (label, text) in sections {
+        msg.push_str("\n\n");
+        if let Some(label) = label {
+            msg.push_str(&format!("**{label}**\n"));
+        }
+        if context.ansi == AnsiOutput::Html && has_styles(text) {
+            // Previewed before conversion, so the limits count text rather
+            // than markup.
+            let (preview, truncated) = OUTPUT_PREVIEW.preview(text);
+            msg.push_str("<pre>");
+            msg.push_str(&ansi_to_html(&preview));
+            if truncated {
+                msg.push_str("… (truncated)\n");
+            }
+            msg.push_str("</pre>");
+            continue;
+        }
+        let (preview, truncated) = OUTPUT_PREVIEW.preview(&strip_ansi(text));
+        msg.push_str("```text\n");
+        msg.push_str(&preview);
+        if truncated {
+            msg.push_str("… (truncated)\n");
+        }
+        msg.push_str("```");
+    }
+    if let Some(attachment) = attachment {
+        msg.push_str("\n\n");
+        msg.push_str(&format_attachment_reference("Full output", attachment));
+    }
+    msg
+}
+
+/// The test results or, failing those, the build diagnostics a recognized
+/// tool printed.
+fn exec_reports(
+    command: &[String],
+    output: &ExecOutput,
+) -> (Option<TestReport>, Option<BuildReport>) {
+    let text = format!(
+        "{}\n{}",
+        strip_ansi(&output.stdout),
+        strip_ansi(&output.stderr)
+    );
+    match parse_test_output(command, &text) {
+        Some(report) => (Some(report), None),
+        None => (None, parse_build_output(command, &text)),
+    }
+}
+
+/// The words of a command line, split on whitespace and shell operators,
+/// for the output parsers to recognize their tool by.
+fn command_words(text: &str) -> Vec<&str> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')'))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Whether `word` runs `program`, by name or path.
+fn is_program(word: &str, program: &str) -> bool {
+    word == program || word.ends_with(&format!("/{program}"))
+}
+
+/// The errors and warnings of `report`, each with its location.
+fn format_build_diagnostics(report: &BuildReport) -> String {
+    let mut msg = format!("**{}**", format_build_counts(report));
+    for diagnostic in report.diagnostics.iter().take(MAX_DIAGNOSTICS) {
+        let icon = match diagnostic.severity {
+            Severity::Error => "❌",
+            Severity::Warning => "⚠️",
+        };
+        msg.push_str(&format!("\n- {icon} "));
+        if let Some(location) = &diagnostic.location {
+            msg.push_str(&format!("{} ", code_span(location)));
+        }
+        escape_html(&diagnostic.message, &mut msg);
+    }
+    if report.diagnostics.len() > MAX_DIAGNOSTICS {
+        msg.push_str(&format!(
+            "\n- … and {} more",
+            report.diagnostics.len() - MAX_DIAGNOSTICS
+        ));
+    }
+    msg
+}
+
+/// The failing tests of `report`, each with its failure excerpt.
+fn format_test_failures(report: &TestReport) -> String {
+    let mut msg = "**Failures**".to_string();
+    for failure in report.failures.iter().take(MAX_FAILURES) {
+        msg.push_str(&format!("\n- {}", code_span(&failure.name)));
+        if let Some(excerpt) = &failure.excerpt {
+            msg.push_str(&format!(": {excerpt}"));
+        }
+    }
+    if report.failures.len() > MAX_FAILURES {
+        msg.push_str(&format!(
+            "\n- … and {} more",
+            report.failures.len() - MAX_FAILURES
+        ));
+    }
+    msg
+}
+
+/// Recent output of a running command, fed from the streamed output chunks
+/// of both streams. Only the last few KiB are kept.
+#[derive(Debug, Default)]
+pub struct OutputTail {
+    text: String,
+}
+
+impl OutputTail {
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.text.push_str(&String::from_utf8_lossy(chunk));
+        if self.text.len() > MAX_TAIL_BYTES {
+            let mut cut = self.text.len() - MAX_TAIL_BYTES;
+            while !self.text.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.text.drain(..cut);
+        }
+    }
+
+    /// The last non-empty lines, clipped like the exec note preview. A line
+    /// redrawn with carriage returns (a progress bar) shows its latest state.
+    fn last_lines(&self) -> Vec<String> {
+        let text = strip_ansi(&self.text);
+        let mut lines: Vec<String> = text
+            .lines()
+            .filter_map(|line| line.rsplit('\r').find(|part| !part.trim().is_empty()))
+            .rev()
+            .take(MAX_PROGRESS_LINES)
+            .map(|line| OUTPUT_PREVIEW.clip_line(line))
+            .collect();
+        lines.reverse();
+        lines
+    }
+}
+
+/// Progress note for a command that is still running: the elapsed time and
+/// its latest output lines. Posted once, then updated in place.
+pub fn format_exec_progress_note(
+    command: &[String],
+    elapsed: Duration,
+    tail: &OutputTail,
+) -> String {
+    let mut msg = format!(
+        "{}\n**Status:** ⏳ Running · {}",
+        format_exec_headline(command),
+        format_duration(elapsed)
+    );
+    let lines = tail.last_lines();
+    if !lines.is_empty() {
+        msg.push_str("\n\n```text\n");
+        for line in lines {
+            msg.push_str(&line);
+            msg.push('\n');
+        }
+        msg.push_str("```");
+    }
+    msg
+}
+
+/// What a finished command printed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecOutput {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    /// The output as shown to the model, used when neither stream has any
+    /// (e.g. a spawn error) and as the uploaded full output.
+    pub formatted_output: String,
+}
+
+/// Where and how a finished command ran, shown in its note's metadata lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecNoteContext {
+    pub cwd: PathBuf,
+    pub duration: Duration,
+    pub sandbox: Option<ExecSandbox>,
+    /// How escape sequences in the output preview are handled.
+    pub ansi: AnsiOutput,
+}
+
+/// The non-empty output streams of a command, labeled when both stdout and
+/// stderr have content. Falls back to the model-facing output (e.g. a spawn
+/// error) when neither stream has any.
+fn output_sections(output: &ExecOutput) -> Vec<(Option<&'static str>, &str)> {
+    let shows_text = |text: &str| !strip_ansi(text).trim().is_empty();
+    let stdout = shows_text(&output.stdout).then_some(output.stdout.as_str());
+    let stderr = shows_text(&output.stderr).then_some(output.stderr.as_str());
+    match (stdout, stderr) {
+        (Some(stdout), Some(stderr)) => vec![(Some("stdout"), stdout), (Some("stderr"), stderr)],
+        (Some(text), None) | (None, Some(text)) => vec![(None, text)],
+        (None, None) if shows_text(&output.formatted_output) => {
+            vec![(None, output.formatted_output.as_str())]
+        }
+        (None, None) => Vec::new(),
+    }
+}
+
+/// The agent's plan as a checklist, refreshed in place as steps progress.
+pub fn format_plan_note(update: &UpdatePlanArgs) -> String {
+    let done = update
+        .plan
+        .iter()
+        .filter(|item| matches!(item.status, StepStatus::Completed))
+        .count();
+    let mut msg = format!("**📝 Plan** ({done}/{} done)", update.plan.len());
+    if let Some(explanation) = update
+        .explanation
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+    {
+        msg.push_str(&format!("\n{explanation}"));
+    }
+    msg.push('\n');
+    for item in &update.plan {
+        let mark = match item.status {
+            StepStatus::Completed => "✅",
+            StepStatus::InProgress => "🔄",
+            StepStatus::Pending => "⬜",
+        };
+        msg.push_str(&format!("\n{mark} {}", item.step));
+    }
+    msg
+}
+
+/// Format an MCP tool call begin note.
+pub fn format_mcp_begin_note(invocation: &McpInvocation) -> String {
+    let inv = format_mcp_invocation(invocation);
+    format!("**Tool:** {inv}\n**Status:** Running")
+}
+
+/// Format an MCP tool call end note.
+pub fn format_mcp_end_note(
+    invocation: &McpInvocation,
+    result: &Result<CallToolResult, String>,
+    _duration: std::time::Duration,
+) -> String {
+    let inv = format_mcp_invocation(invocation);
+    let ok = match result {
+        Ok(r) => !r.is_error.unwrap_or(false),
+        Err(_) => false,
+    };
+    let status = if ok { "Success" } else { "Failed" };
+    format!("**Tool:** {inv}\n**Status:** {status}")
+}
+
+fn format_mcp_invocation(invocation: &McpInvocation) -> String {
+    let args_str = invocation
+        .arguments
+        .as_ref()
+        .map(|v| serde_json::to_string(v).unwrap_or_else(|_| v.to_string()))
+        .unwrap_or_default();
+    if args_str.is_empty() {
+        format!("{}.{}", invocation.server, invocation.tool)
+    } else {
+        format!("{}.{}({})", invocation.server, invocation.tool, args_str)
+    }
+}
+
+/// Build a note for a web search or page visit. With `hide_urls`, every URL
+/// is reduced to its host.
+pub fn format_web_search_note(event: &WebSearchEndEvent, hide_urls: bool) -> String {
+    use std::fmt::Write as _;
+    let mut msg = format!("🌐 {}", web_search_summary(event, hide_urls));
+    for result in event.results.iter().take(MAX_WEB_RESULTS) {
+        let link = match (&result.title, hide_urls) {
+            (Some(title), false) => format!("[{title}]({})", result.url),
+            (Some(title), true) => format!("{title} ({})", url_host(&result.url)),
+            (None, false) => result.url.clone(),
+            (None, true) => url_host(&result.url),
+        };
+        let _ = write!(msg, "\n- {link}");
+    }
+    if event.results.len() > MAX_WEB_RESULTS {
+        let _ = write!(
+            msg,
+            "\n- … and {} more",
+            event.results.len() - MAX_WEB_RESULTS
+        );
+    }
+    msg
+}
+
+/// One-line Markdown description of a web search or page visit.
+fn web_search_summary(event: &WebSearchEndEvent, hide_urls: bool) -> String {
+    let page = |url: &str| {
+        if hide_urls {
+            url_host(url)
+        } else {
+            url.to_string()
+        }
+    };
+    match (&event.url, event.query.is_empty()) {
+        (None, _) => format!("**Web search:** `{}`", event.query),
+        (Some(url), true) => format!("**Opened:** {}", page(url)),
+        (Some(url), false) => format!("**Searched page:** {} for `{}`", page(url), event.query),
+    }
+}
+
+/// Host of `url`, or a placeholder when it does not parse.
+fn url_host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "(hidden URL)".to_string())
+}
+
+/// Format the note sent when a code review starts.
+pub fn format_review_started_note(user_facing_hint: &str, locale: OmnaraLocale) -> String {
+    format!(
+        "🔍 **{}** {user_facing_hint}",
+        strings(locale).review_started
+    )
+}
+
+/// Format the note sent when a code review ends without producing results.
+pub fn format_review_interrupted_note(locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    format!(
+        "🔍 **{}** — {}",
+        text.review_interrupted, text.review_no_findings
+    )
+}
+
+/// Human label for a review finding priority (0 is most severe).
+fn review_severity(priority: i32, locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    match priority {
+        i32::MIN..=0 => format!("P0 · {}", text.severity_critical),
+        1 => format!("P1 · {}", text.severity_high),
+        2 => format!("P2 · {}", text.severity_medium),
+        _ => format!("P3 · {}", text.severity_low),
+    }
+}
+
+/// Format a single review finding as a per-file comment. Paths are shown
+/// relative to `cwd` when they fall inside it.
+pub fn format_review_finding_note(
+    finding: &ReviewFinding,
+    cwd: &Path,
+    locale: OmnaraLocale,
+) -> String {
+    let location = &finding.code_location;
+    let path = location
+        .absolute_file_path
+        .strip_prefix(cwd)
+        .unwrap_or(&location.absolute_file_path);
+    let range = &location.line_range;
+    let lines = if range.start == range.end {
+        format!("{}", range.start)
+    } else {
+        format!("{}-{}", range.start, range.end)
+    };
+    let mut msg = format!(
+        "**[{}] {}**\n`{}:{}` ({} {:.2})",
+        review_severity(finding.priority, locale),
+        finding.title,
+        path.display(),
+        lines,
+        strings(locale).confidence,
+        finding.confidence_score
+    );
+    let body = finding.body.trim();
+    if !body.is_empty() {
+        msg.push_str("\n\n");
+        msg.push_str(body);
+    }
+    msg
+}
+
+/// Format the review verdict with options to accept the findings or request changes.
+pub fn format_review_verdict_request(output: &ReviewOutputEvent, locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    let count = output.findings.len();
+    let findings = if count == 1 {
+        text.review_finished_one
+    } else {
+        text.review_finished_many
+    };
+    let mut msg = format!(
+        "**{}** {}",
+        text.review_finished,
+        fill(findings, &[("count", &count)])
+    );
+    let correctness = output.overall_correctness.trim();
+    if !correctness.is_empty() {
+        msg.push_str(&format!(
+            "\n**{}:** {correctness} ({} {:.2})",
+            text.verdict, text.confidence, output.overall_confidence_score
+        ));
+    }
+    let explanation = output.overall_explanation.trim();
+    if !explanation.is_empty() {
+        msg.push_str(&format!("\n\n{explanation}"));
+    }
+    msg.push_str(&format!(
+        "\n\n[OPTIONS]\n1. {}\n2. {}\n[/OPTIONS]",
+        text.accept_findings, text.request_changes
+    ));
+    msg
+}
+
+/// Format the note acknowledging a remote message queued behind the current turn.
+pub fn format_queued_input_note(waiting: usize, locale: OmnaraLocale) -> String {
+    format!(
+        "⏳ {}",
+        fill(strings(locale).queued_input, &[("waiting", &waiting)])
+    )
+}
+
+/// Format the note counting approval prompts held back by
+/// `max_outstanding_approvals`.
+pub fn format_pending_approvals_note(count: usize, locale: OmnaraLocale) -> String {
+    let text = strings(locale);
+    if count == 1 {
+        format!("⏳ {}", text.approvals_pending_one)
+    } else {
+        format!(
+            "⏳ {}",
+            fill(text.approvals_pending_many, &[("count", &count)])
+        )
+    }
+}
+
+/// Format the note closing an approval prompt that a crashed run left
+/// unanswered; `kind` is the approval kind's label.
+pub fn format_interrupted_approval_note(kind: &str, locale: OmnaraLocale) -> String {
+    format!(
+        "⚠️ {}",
+        fill(strings(locale).approval_interrupted, &[("kind", &kind)])
+    )
+}
+
+/// Format the interrupt prompt, listing queued remote messages that were dropped.
+pub fn format_interrupt_prompt(dropped: &[String], locale: OmnaraLocale) -> String {
+    let mut msg = strings(locale).interrupt_prompt.to_string();
+    push_dropped_messages(&mut msg, dropped, locale);
+    msg
+}
+
+/// Format the divider posted when a remote `/new-task` starts a new task,
+/// listing queued messages from the previous task that were dropped.
+pub fn format_new_task_divider(prompt: &str, dropped: &[String], locale: OmnaraLocale) -> String {
+    let mut msg = format!(
+        "───────────────\n🆕 **{}:** {prompt}",
+        strings(locale).new_task
+    );
+    push_dropped_messages(&mut msg, dropped, locale);
+    msg
+}
+
+/// Format the note posted in a parent session when `label` (a fork, a new
+/// session) continues in the linked session `child`.
+pub fn format_linked_session_note(label: &str, child: &str, locale: OmnaraLocale) -> String {
+    format!(
+        "🔀 {}",
+        fill(
+            strings(locale).continued_in_session,
+            &[("label", &label), ("session", &child)]
+        )
+    )
+}
+
+/// Format the first note of a child session, pointing back at `parent`.
+pub fn format_parent_session_note(parent: &str, locale: OmnaraLocale) -> String {
+    format!(
+        "↩️ {}",
+        fill(strings(locale).opened_from_session, &[("session", &parent)])
+    )
+}
+
+/// Format the note a session forked from the dashboard opens with: the
+/// prompt the fork left out, quoted.
+pub fn format_fork_note(prompt: usize, dropped: &str, locale: OmnaraLocale) -> String {
+    let mut note = format!(
+        "✂️ {}",
+        fill(strings(locale).forked_before, &[("prompt", &prompt)])
+    );
+    note.push_str(&format!("\n> {}", dropped.trim().replace('\n', "\n> ")));
+    note
+}
+
+fn push_dropped_messages(msg: &mut String, dropped: &[String], locale: OmnaraLocale) {
+    if !dropped.is_empty() {
+        msg.push_str("\n\n");
+        msg.push_str(strings(locale).dropped_messages);
+        for text in dropped {
+            msg.push_str(&format!("\n> {}", text.replace('\n', "\n> ")));
+        }
+    }
+}
+
+/// Format the note sent when an `@file` reference in a remote message could not be attached.
+pub fn format_file_context_failure(path: &str, reason: &str) -> String {
+    format!("⚠️ Could not attach `{path}`: {reason}")
+}
+
+/// Format a note the local user added to the session timeline with `/note`.
+pub fn format_local_note(text: &str) -> String {
+    format!("📝 **Note from the terminal:** {text}")
+}
+
+/// Note confirming a dashboard `/model` or `/provider` switch.
+pub fn format_switch_note(setting: &str, old: &str, new: &str) -> String {
+    format!("🔀 Switched {setting}: `{old}` → `{new}` (applies from the next turn)")
+}
+
+/// Note listing the choices for `/model` or `/provider`: the current one
+/// when nothing was asked for, or a rejection of the `unknown` name.
+pub fn format_choices_note(
+    setting: &str,
+    current: &str,
+    available: &[String],
+    unknown: Option<&str>,
+) -> String {
+    let available = available
+        .iter()
+        .map(|choice| format!("`{choice}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match unknown {
+        Some(name) => format!("⚠️ Unknown {setting} `{name}`\n\nAvailable: {available}"),
+        None => format!("Current {setting}: `{current}`\n\nAvailable: {available}"),
+    }
+}
+
+/// A sub-agent's message, note, or approval prompt, marked with its label so
+/// its output reads as a thread of its own within the session.
+pub fn format_sub_agent_message(label: &str, message: &str) -> String {
+    format!("↳ **{label}** · {message}")
+}
+
+/// Note marking where a sub-agent's thread begins.
+pub fn format_sub_agent_started_note(label: &str) -> String {
+    format!("🧵 **{label}** started")
+}
+
+/// Note marking where a sub-agent's thread ends, with its outcome if any.
+pub fn format_sub_agent_finished_note(label: &str, summary: Option<&str>) -> String {
+    let mut msg = format!("🧵 **{label}** finished");
+    if let Some(summary) = summary.map(str::trim).filter(|s| !s.is_empty()) {
+        msg.push_str(&format!("\n\n{summary}"));
+    }
+    msg
+}
+
+/// Note reporting the estimated size of the history around a compaction.
+pub fn format_compaction_note(tokens_before: u64, tokens_after: u64) -> String {
+    format!(
+        "🗜️ Compacted the conversation: ~{} → ~{} tokens",
+        format_with_separators(tokens_before),
+        format_with_separators(tokens_after)
+    )
+}
+
+/// Files listed by the `/context` note; the rest are counted.
+const MAX_CONTEXT_FILES: usize = 10;
+
+/// Note answering a dashboard `/context`: the history's estimated size, what
+/// kinds of items fill it, and the largest files read into it.
+pub fn format_context_summary(summary: &ContextSummaryEvent) -> String {
+    let mut note = format!(
+        "**🧠 Context: ~{} tokens**",
+        format_with_separators(summary.tokens)
+    );
+    if let Some(window) = summary.model_context_window.filter(|window| *window > 0) {
+        let percent = summary.tokens.saturating_mul(100) / window;
+        note.push_str(&format!(
+            " ({percent}% of {})",
+            format_with_separators(window)
+        ));
+    }
+    for section in &summary.sections {
+        let items = match section.items {
+            1 => "1 item".to_string(),
+            n => format!("{n} items"),
+        };
+        note.push_str(&format!(
+            "\n- {}: ~{} tokens ({items})",
+            section.name,
+            format_with_separators(section.tokens)
+        ));
+    }
+    if !summary.files.is_empty() {
+        note.push_str("\n\n**Files**");
+        for file in summary.files.iter().take(MAX_CONTEXT_FILES) {
+            note.push_str(&format!(
+                "\n- `{}` ~{} tokens",
+                file.path,
+                format_with_separators(file.tokens)
+            ));
+        }
+        let more = summary.files.len().saturating_sub(MAX_CONTEXT_FILES);
+        if more > 0 {
+            note.push_str(&format!("\n- …and {more} more"));
+        }
+    }
+    note
+}
+
+/// Note answering a dashboard `/config`: the settings the next turn runs
+/// with.
+pub fn format_config_note(
+    cwd: &Path,
+    model: &str,
+    provider: &str,
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+) -> String {
+    format!(
+        "**⚙️ Configuration**\n\
+         - Model: `{model}`\n\
+         - Provider: `{provider}`\n\
+         - Approval policy: `{approval_policy}`\n\
+         - Sandbox: `{}`\n\
+         - Working directory: `{}`",
+        summarize_sandbox_policy(sandbox_policy),
+        cwd.display()
+    )
+}
+
+/// Variables listed by the `/env` note; the rest are counted.
+const MAX_ENV_VARS: usize = 50;
+/// Characters of a value shown by the `/env` note.
+const MAX_ENV_VALUE_CHARS: usize = 200;
+/// Shown by the `/env` note in place of a credential's value.
+const MASKED_ENV_VALUE: &str = "******** (masked)";
+
+/// Note answering a dashboard `/env <filter>`: the variables among `vars`
+/// whose names contain `filter`, ignoring case, sorted by name. Values of
+/// names that look like credentials are masked.
+pub fn format_env_note(filter: &str, vars: impl IntoIterator<Item = (String, String)>) -> String {
+    let needle = filter.to_lowercase();
+    let mut matching: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.to_lowercase().contains(&needle))
+        .collect();
+    matching.sort();
+    let scope = if filter.is_empty() {
+        String::new()
+    } else {
+        format!(" matching {}", code_span(filter))
+    };
+    if matching.is_empty() {
+        return format!("🔧 No environment variables{scope}");
+    }
+    let count = match matching.len() {
+        1 => "1 variable".to_string(),
+        n => format!("{n} variables"),
+    };
+    let lines: Vec<String> = matching
+        .iter()
+        .take(MAX_ENV_VARS)
+        .map(|(name, value)| {
+            let value = if is_secret_name(name) {
+                MASKED_ENV_VALUE.to_string()
+            } else {
+                let value = redact_url_credentials(value).replace('\n', "\\n");
+                match value.char_indices().nth(MAX_ENV_VALUE_CHARS) {
+                    Some((end, _)) => format!("{}…", &value[..end]),
+                    None => value,
+                }
+            };
+            format!("{name}={value}")
+        })
+        .collect();
+    // A value with a run of backticks must not close the fence early.
+    let longest = lines
+        .iter()
+        .map(|line| longest_backtick_run(line))
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat((longest + 1).max(3));
+    let mut note = format!(
+        "**🔧 Environment: {count}{scope}**\n\n{fence}text\n{}\n{fence}",
+        lines.join("\n")
+    );
+    let more = matching.len().saturating_sub(MAX_ENV_VARS);
+    if more > 0 {
+        note.push_str(&format!(
+            "\n…and {more} more; narrow the filter to see them"
+        ));
+    }
+    note
+}
+
+/// `value` with the `user:password@` part of each URL in it masked, e.g.
+/// `postgres://********@db/app` for `postgres://app:hunter2@db/app`.
+fn redact_url_credentials(value: &str) -> String {
+    let mut redacted = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(scheme_end) = rest.find("://") {
+        let (head, tail) = rest.split_at(scheme_end + 3);
+        redacted.push_str(head);
+        let authority_end = tail
+            .find(|c: char| matches!(c, '/' | '?' | '#') || c.is_whitespace())
+            .unwrap_or(tail.len());
+        match tail[..authority_end].rfind('@') {
+            Some(at) => {
+                redacted.push_str("********");
+                rest = &tail[at..];
+            }
+            None => rest = tail,
+        }
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Why a terminal snapshot is being sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotTrigger {
+    /// The local user ran `/snapshot`.
+    Requested,
+    /// A turn ended in an error and `omnara.snapshot_on_error` is set.
+    Error,
+}
+
+/// The full snapshot text as an uploadable artifact.
+pub fn snapshot_attachment(text: &str) -> NoteAttachment {
+    NoteAttachment {
+        name: "terminal-snapshot.txt".to_string(),
+        bytes: text.as_bytes().to_vec(),
+        mime: "text/plain",
+    }
+}
+
+/// Format a terminal snapshot note: the last lines of the screen inline, with
+/// the uploaded full snapshot linked below when available.
+pub fn format_snapshot_note(
+    text: &str,
+    trigger: SnapshotTrigger,
+    attachment: Option<&AttachmentRef>,
+) -> String {
+    let header = match trigger {
+        SnapshotTrigger::Requested => "🖥️ **Terminal snapshot**",
+        SnapshotTrigger::Error => "🖥️ **Terminal snapshot after error**",
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let tail = lines[lines.len().saturating_sub(OUTPUT_PREVIEW.max_lines)..].join("\n");
+    let (preview, truncated) = OUTPUT_PREVIEW.preview(&tail);
+    let mut msg = header.to_string();
+    if !preview.trim().is_empty() {
+        msg.push_str("\n\n```text\n");
+        if lines.len() > OUTPUT_PREVIEW.max_lines {
+            msg.push_str("… (earlier lines omitted)\n");
+        }
+        msg.push_str(&preview);
+        if truncated {
+            msg.push_str("… (truncated)\n");
+        }
+        msg.push_str("```");
+    }
+    if let Some(attachment) = attachment {
+        msg.push_str("\n\n");
+        msg.push_str(&format_attachment_reference("Full snapshot", attachment));
+    }
+    msg
+}
+
+/// What happened during one turn, collected by the bridge and posted as a
+/// single note by [`TurnSummaryBuilder::build`] when the turn ends: in place
+/// of per-event notes in `digest` mirroring mode, or after them with
+/// `turn_summary` enabled.
+#[derive(Debug, Default)]
+pub struct TurnSummaryBuilder {
+    /// `(command, exit_code)` in completion order.
+    pub commands: Vec<(String, i32)>,
+    /// Lines `(added, removed)` per changed file, summed across patches.
+    pub files: BTreeMap<PathBuf, (usize, usize)>,
+    /// `(server.tool, succeeded)` for MCP tool calls.
+    pub tools: Vec<(String, bool)>,
+    /// Web searches and page visits, as [`web_search_summary`] lines.
+    pub web: Vec<String>,
+    /// Agent messages other than the turn's final answer (`digest` mode only).
+    pub messages: Vec<String>,
+    /// Tokens used by the turn's model requests.
+    pub tokens: Option<TokenUsage>,
+    /// Wall time from the start of the turn to its end.
+    pub duration: Option<Duration>,
+}
+
+impl TurnSummaryBuilder {
+    /// Whether there is nothing to list; usage and duration alone do not
+    /// make a summary worth posting.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+            && self.files.is_empty()
+            && self.tools.is_empty()
+            && self.web.is_empty()
+            && self.messages.is_empty()
+    }
+
+    pub fn record_web_search(&mut self, event: &WebSearchEndEvent, hide_urls: bool) {
+        self.web.push(web_search_summary(event, hide_urls));
+    }
+
+    pub fn record_command(&mut self, command: &[String], exit_code: i32) {
+        self.commands
+            .push((display_command(command).text, exit_code));
+    }
+
+    pub fn record_patch(&mut self, changes: &HashMap<PathBuf, FileChange>) {
+        for (path, change) in changes {
+            let (added, removed) = match change {
+                FileChange::Add { content } => (content.lines().count(), 0),
+                FileChange::Delete { content } => (0, content.lines().count()),
+                FileChange::Update { unified_diff, .. } => (
+                    unified_diff
+                        .lines()
+                        .filter(|l| l.starts_with('+') && !l.starts_with("+++"))
+                        .count(),
+                    unified_diff
+                        .lines()
+                        .filter(|l| l.starts_with('-') && !l.starts_with("---"))
+                        .count(),
+                ),
+            };
+            let entry = self.files.entry(path.clone()).or_default();
+            entry.0 += added;
+            entry.1 += removed;
+        }
+    }
+
+    pub fn record_tool_call(
+        &mut self,
+        invocation: &McpInvocation,
+        result: &Result<CallToolResult, String>,
+    ) {
+        let ok = matches!(result, Ok(r) if !r.is_error.unwrap_or(false));
+        self.tools
+            .push((format!("{}.{}", invocation.server, invocation.tool), ok));
+    }
+
+    /// Format the summary note.
+    pub fn build(&self) -> String {
+        use std::fmt::Write as _;
+        let mut msg = String::from("📋 **Turn summary**");
+        let mut stats = Vec::new();
+        if let Some(duration) = self.duration {
+            stats.push(format!("⏱ {}", format_duration(duration)));
+        }
+        if let Some(tokens) = self.tokens.as_ref().filter(|tokens| !tokens.is_zero()) {
+            stats.push(format!(
+                "🔢 {} tokens ({} in · {} out)",
+                format_with_separators(tokens.total_tokens),
+                format_with_separators(tokens.input_tokens),
+                format_with_separators(tokens.output_tokens)
+            ));
+        }
+        if !stats.is_empty() {
+            let _ = write!(msg, "\n{}", stats.join(" · "));
+        }
+        let overflow = |msg: &mut String, total: usize| {
+            if total > MAX_DIGEST_ENTRIES {
+                let _ = write!(msg, "\n- … and {} more", total - MAX_DIGEST_ENTRIES);
+            }
+        };
+        if !self.commands.is_empty() {
+            let _ = write!(msg, "\n\n**Commands ({}):**", self.commands.len());
+            for (command, exit_code) in self.commands.iter().take(MAX_DIGEST_ENTRIES) {
+                let status = if *exit_code == 0 {
+                    "✓".to_string()
+                } else {
+                    format!("✗ exit {exit_code}")
+                };
+                let _ = write!(msg, "\n- `{command}` {status}");
+            }
+            overflow(&mut msg, self.commands.len());
+        }
+        if !self.files.is_empty() {
+            let _ = write!(msg, "\n\n**Files changed ({}):**", self.files.len());
+            for (path, (added, removed)) in self.files.iter().take(MAX_DIGEST_ENTRIES) {
+                let _ = write!(msg, "\n- {} (+{added} -{removed})", path.display());
+            }
+            overflow(&mut msg, self.files.len());
+        }
+        if !self.tools.is_empty() {
+            let _ = write!(msg, "\n\n**Tool calls ({}):**", self.tools.len());
+            for (tool, ok) in self.tools.iter().take(MAX_DIGEST_ENTRIES) {
+                let _ = write!(msg, "\n- {tool} {}", if *ok { "✓" } else { "✗" });
+            }
+            overflow(&mut msg, self.tools.len());
+        }
+        if !self.web.is_empty() {
+            let _ = write!(msg, "\n\n**Web ({}):**", self.web.len());
+            for entry in self.web.iter().take(MAX_DIGEST_ENTRIES) {
+                let _ = write!(msg, "\n- {entry}");
+            }
+            overflow(&mut msg, self.web.len());
+        }
+        if !self.messages.is_empty() {
+            msg.push_str("\n\n**Updates:**");
+            for message in self.messages.iter().take(MAX_DIGEST_ENTRIES) {
+                let _ = write!(msg, "\n> {}", excerpt(message));
+            }
+            overflow(&mut msg, self.messages.len());
+        }
+        msg
+    }
+}
+
+/// First non-blank line of `text`, shortened to [`MAX_EXCERPT_CHARS`].
+fn excerpt(text: &str) -> String {
+    let line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default()
+        .trim();
+    if line.chars().count() <= MAX_EXCERPT_CHARS && text.trim() == line {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_EXCERPT_CHARS).collect();
+    short.truncate(short.trim_end().len());
+    short.push('…');
+    short
+}
+
+/// Derive a dashboard session title from a prompt: its first non-blank line
+/// with whitespace collapsed, shortened to [`MAX_TITLE_CHARS`].
+pub fn title_from_prompt(prompt: &str) -> Option<String> {
+    let line = prompt.lines().find(|line| !line.trim().is_empty())?;
+    let title = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return Some(title);
+    }
+    let mut short: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+    short.truncate(short.trim_end().len());
+    short.push('…');
+    Some(short)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::ContextFile;
+    use codex_core::protocol::ContextSection;
+    use pretty_assertions::assert_eq;
+
+    fn output_with_lines(n: usize) -> ExecOutput {
+        let formatted_output = (0..n).map(|i| format!("line {i}\n")).collect::<String>();
+        ExecOutput {
+            exit_code: 0,
+            stdout: formatted_output.clone(),
+            stderr: String::new(),
+            formatted_output,
+        }
+    }
+
+    #[test]
+    fn small_outputs_have_no_attachment() {
+        let output = output_with_lines(3);
+        assert_eq!(exec_output_attachment(&["ls".to_string()], &output), None);
+
+        let changes = HashMap::from([(
+            PathBuf::from("a.txt"),
+            FileChange::Add {
+                content: "one\ntwo\n".to_string(),
+            },
+        )]);
+        assert_eq!(patch_attachment(&changes, &WithheldPaths::default()), None);
+    }
+
+    #[test]
+    fn truncated_exec_output_is_attached_in_full() {
+        let output = output_with_lines(OUTPUT_PREVIEW.max_lines + 5);
+        let attachment = exec_output_attachment(&["ls".to_string()], &output).expect("attachment");
+        assert_eq!(attachment.bytes, output.formatted_output.as_bytes());
+
+        let reference = AttachmentRef {
+            attachment_id: "att-1".to_string(),
+            url: "https://example.com/att-1".to_string(),
+        };
+        let context = ExecNoteContext {
+            cwd: PathBuf::from("/work"),
+            duration: Duration::from_millis(250),
+            sandbox: None,
+            ansi: AnsiOutput::Strip,
+        };
+        let note = format_exec_note(&["ls".to_string()], &output, &context, Some(&reference));
+        assert!(note.contains("… (truncated)"));
+        assert!(note.ends_with("📎 [Full output](https://example.com/att-1)"));
+    }
+
+    #[test]
+    fn exec_note_shows_metadata_and_separates_stderr() {
+        let output = ExecOutput {
+            exit_code: 101,
+            stdout: "running 2 tests\n".to_string(),
+            stderr: "error: test failed\n".to_string(),
+            formatted_output: "running 2 tests\nerror: test failed\n".to_string(),
+        };
+        let context = ExecNoteContext {
+            cwd: PathBuf::from("/work/crate"),
+            duration: Duration::from_millis(3400),
+            sandbox: Some(ExecSandbox::Escalated),
+            ansi: AnsiOutput::Strip,
+        };
+        assert_eq!(
+            format_exec_note(
+                &["cargo".to_string(), "test".to_string()],
+                &output,
+                &context,
+                None
+            ),
+            "**Exec:** `cargo test`\n**Status:** Failed (exit 101) · 3.40s\n\
+             **Cwd:** `/work/crate` · **Sandbox:** ⚠️ escalated (ran outside the sandbox)\n\n\
+             **stdout**\n```text\nrunning 2 tests\n```\n\n\
+             **stderr**\n```text\nerror: test failed\n```"
+        );
+    }
+
+    #[test]
+    fn exec_note_strips_or_renders_output_colors() {
+        let colored = "\u{1b}[1m\u{1b}[32m   Compiling\u{1b}[0m demo v0.1.0\n\u{1b}[2K\u{1b}[31merror\u{1b}[0m: <oops>\n";
+        let output = ExecOutput {
+            exit_code: 101,
+            stdout: String::new(),
+            stderr: colored.to_string(),
+            formatted_output: colored.to_string(),
+        };
+        let mut context = ExecNoteContext {
+            cwd: PathBuf::from("/work"),
+            duration: Duration::from_secs(1),
+            sandbox: None,
+            ansi: AnsiOutput::Strip,
+        };
+        let command = ["./build.sh".to_string()];
+        let note = format_exec_note(&command, &output, &context, None);
+        assert!(
+            note.ends_with("```text\n   Compiling demo v0.1.0\nerror: <oops>\n```"),
+            "{note}"
+        );
+
+        context.ansi = AnsiOutput::Html;
+        let note = format_exec_note(&command, &output, &context, None);
+        assert!(
+            note.ends_with(
+                "<pre><span style=\"color:#0dbc79;font-weight:bold\">   Compiling</span> demo v0.1.0\n\
+                 <span style=\"color:#cd3131\">error</span>: &lt;oops&gt;\n</pre>"
+            ),
+            "{note}"
+        );
+        // A recognized build lists its diagnostics, escaped, instead.
+        let cargo = ["cargo".to_string(), "build".to_string()];
+        let note = format_exec_note(&cargo, &output, &context, None);
+        assert!(note.ends_with("\n- ❌ &lt;oops&gt;"), "{note}");
+        // Output without colors keeps the code block.
+        let plain = ExecOutput {
+            stderr: "plain\n".to_string(),
+            ..output
+        };
+        assert!(
+            format_exec_note(&command, &plain, &context, None).ends_with("```text\nplain\n```")
+        );
+    }
+
+    #[test]
+    fn test_runs_lead_with_counts_and_failures() {
+        let stdout = "running 2 tests\ntest a ... ok\ntest b ... FAILED\n\nfailures:\n\n\
+             ---- b stdout ----\nthread 'b' panicked at src/lib.rs:3:5:\nexpected 2\n\n\
+             failures:\n    b\n\ntest result: FAILED. 1 passed; 1 failed; 0 ignored\n";
+        let output = ExecOutput {
+            exit_code: 101,
+            stdout: stdout.to_string(),
+            stderr: "\u{1b}[1m\u{1b}[31merror\u{1b}[0m: test failed, to rerun pass `--lib`\n"
+                .to_string(),
+            formatted_output: stdout.to_string(),
+        };
+        let context = ExecNoteContext {
+            cwd: PathBuf::from("/work"),
+            duration: Duration::from_secs(2),
+            sandbox: None,
+            ansi: AnsiOutput::Strip,
+        };
+        let note = format_exec_note(
+            &["cargo".to_string(), "test".to_string()],
+            &output,
+            &context,
+            None,
+        );
+        assert!(
+            note.starts_with(
+                "**❌ 1 failed, 1 passed**\n**Exec:** `cargo test`\n\
+                 **Status:** Failed (exit 101) · 2.00s\n**Cwd:** `/work`\n\n\
+                 **Failures**\n- `b`: expected 2\n\n**stdout**\n"
+            ),
+            "{note}"
+        );
+
+        // Other commands are not read as test runs.
+        let note = format_exec_note(&["cat".to_string()], &output, &context, None);
+        assert!(note.starts_with("**Exec:** `cat`\n"), "{note}");
+    }
+
+    #[test]
+    fn build_errors_replace_the_output_preview() {
+        let stderr = "   Compiling demo v0.1.0 (/work)\n\
+             error[E0425]: cannot find value `x` in this scope\n --> src/main.rs:2:5\n\n\
+             error: could not compile `demo` (bin \"demo\") due to 1 previous error\n";
+        let output = ExecOutput {
+            exit_code: 101,
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+            formatted_output: stderr.to_string(),
+        };
+        let command = ["cargo".to_string(), "build".to_string()];
+        let attachment = exec_output_attachment(&command, &output).expect("attachment");
+        assert_eq!(attachment.bytes, stderr.as_bytes());
+
+        let context = ExecNoteContext {
+            cwd: PathBuf::from("/work"),
+            duration: Duration::from_secs(4),
+            sandbox: None,
+            ansi: AnsiOutput::Strip,
+        };
+        let reference = AttachmentRef {
+            attachment_id: "att-1".to_string(),
+            url: "https://example.com/att-1".to_string(),
+        };
+        assert_eq!(
+            format_exec_note(&command, &output, &context, Some(&reference)),
+            "**Exec:** `cargo build`\n**Status:** Failed (exit 101) · 4.00s\n**Cwd:** `/work`\n\n\
+             **1 error**\n- ❌ `src/main.rs:2:5` cannot find value `x` in this scope\n\n\
+             📎 [Full output](https://example.com/att-1)"
+        );
+    }
+
+    #[test]
+    fn exec_note_quotes_the_command_as_it_runs() {
+        let output = output_with_lines(0);
+        let context = ExecNoteContext {
+            cwd: PathBuf::from("/work"),
+            duration: Duration::from_millis(10),
+            sandbox: None,
+            ansi: AnsiOutput::Strip,
+        };
+        let script = |script: &str| vec!["bash".to_string(), "-lc".to_string(), script.to_string()];
+        let note = format_exec_note(&script("echo \"hello world\""), &output, &context, None);
+        assert!(
+            note.starts_with("**Exec:** `echo \"hello world\"`\n"),
+            "{note}"
+        );
+
+        let note = format_exec_note(&script("echo `date`"), &output, &context, None);
+        assert!(note.starts_with("**Exec:** `` echo `date` ``\n"), "{note}");
+
+        let note = format_exec_note(&script("cd src\nls"), &output, &context, None);
+        assert!(
+            note.starts_with("**Exec:**\n```bash\ncd src\nls\n```\n**Status:**"),
+            "{note}"
+        );
+    }
+
+    #[test]
+    fn progress_note_shows_elapsed_time_and_the_latest_lines() {
+        let mut tail = OutputTail::default();
+        for i in 0..15 {
+            tail.push(format!("Compiling crate-{i}\n").as_bytes());
+        }
+        tail.push(b"Building [==>   ] 10/20\rBuilding [====> ] 15/20");
+        let note = format_exec_progress_note(
+            &["cargo".to_string(), "build".to_string()],
+            Duration::from_secs(125),
+            &tail,
+        );
+        assert_eq!(
+            note,
+            "**Exec:** `cargo build`\n**Status:** ⏳ Running · 2m 05s\n\n```text\n\
+             Compiling crate-6\nCompiling crate-7\nCompiling crate-8\nCompiling crate-9\n\
+             Compiling crate-10\nCompiling crate-11\nCompiling crate-12\nCompiling crate-13\n\
+             Compiling crate-14\nBuilding [====> ] 15/20\n```"
+        );
+    }
+
+    #[test]
+    fn web_search_note_lists_results_and_can_hide_urls() {
+        let event = WebSearchEndEvent {
+            call_id: "ws_1".to_string(),
+            query: "tokio spawn_blocking".to_string(),
+            url: None,
+            results: vec![
+                codex_protocol::models::WebSearchSource {
+                    url: "https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html"
+                        .to_string(),
+                    title: Some("spawn_blocking in tokio::task".to_string()),
+                },
+                codex_protocol::models::WebSearchSource {
+                    url: "https://github.com/tokio-rs/tokio/issues/1".to_string(),
+                    title: None,
+                },
+            ],
+        };
+        assert_eq!(
+            format_web_search_note(&event, false),
+            "🌐 **Web search:** `tokio spawn_blocking`\n\
+             - [spawn_blocking in tokio::task](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html)\n\
+             - https://github.com/tokio-rs/tokio/issues/1"
+        );
+        assert_eq!(
+            format_web_search_note(&event, true),
+            "🌐 **Web search:** `tokio spawn_blocking`\n\
+             - spawn_blocking in tokio::task (docs.rs)\n\
+             - github.com"
+        );
+
+        let opened = WebSearchEndEvent {
+            call_id: "ws_2".to_string(),
+            query: String::new(),
+            url: Some("https://internal.example.com/wiki/secret-plan".to_string()),
+            results: Vec::new(),
+        };
+        assert_eq!(
+            format_web_search_note(&opened, true),
+            "🌐 **Opened:** internal.example.com"
+        );
+    }
+
+    #[test]
+    fn truncated_patch_is_attached_as_unified_diff() {
+        let content = (0..MAX_DIFF_LINES + 1)
+            .map(|i| format!("{i}\n"))
+            .collect::<String>();
+        let changes = HashMap::from([(PathBuf::from("big.txt"), FileChange::Add { content })]);
+        let attachment = patch_attachment(&changes, &WithheldPaths::default()).expect("attachment");
+        let text = String::from_utf8(attachment.bytes).expect("utf8");
+        assert!(text.starts_with("--- /dev/null\n+++ b/big.txt\n+0\n"));
+        assert_eq!(text.lines().count(), MAX_DIFF_LINES + 3);
+    }
+
+    #[test]
+    fn oversized_patch_shows_the_largest_hunks_within_the_budget() {
+        let small = "@@ -1 +1 @@\n-a\n+b\n";
+        let large = (0..20).fold("@@ -10,20 +10,20 @@\n".to_string(), |diff, i| {
+            diff + &format!("-old {i}\n+new {i}\n")
+        });
+        let changes = HashMap::from([
+            (
+                PathBuf::from("a.rs"),
+                FileChange::Update {
+                    unified_diff: format!("{small}{large}"),
+                    move_path: None,
+                },
+            ),
+            (
+                PathBuf::from("b.rs"),
+                FileChange::Update {
+                    unified_diff: small.to_string(),
+                    move_path: None,
+                },
+            ),
+            (
+                PathBuf::from("gone.rs"),
+                FileChange::Delete {
+                    content: "x\n".to_string(),
+                },
+            ),
+        ]);
+        let summary = format_patch_summary(&changes, &WithheldPaths::default(), 470);
+        assert_eq!(
+            summary,
+            format!(
+                "  └ a.rs (+21 -21)\n  └ b.rs (+1 -1)\n  └ gone.rs (deleted) (+0 -1)\n\
+                 \n**Largest changes** (2 of 3 hunks)\n**a.rs**\n```diff\n{small}{large}```\n\
+                 \n_1 file omitted, 1 of 3 hunks not shown_\n"
+            )
+        );
+
+        // Too small for the large hunk; the smaller ones still fit.
+        let summary = format_patch_summary(&changes, &WithheldPaths::default(), 200);
+        assert!(
+            summary.contains("**a.rs**\n```diff\n@@ -1 +1 @@\n-a\n+b\n```\n**b.rs**\n"),
+            "{summary}"
+        );
+        assert!(!summary.contains("old 0"), "{summary}");
+        assert!(
+            summary.ends_with("_0 files omitted, 1 of 3 hunks not shown_\n"),
+            "{summary}"
+        );
+    }
+
+    #[test]
+    fn patch_notes_over_the_budget_are_summarized() {
+        let content = "x".repeat(200) + "\n";
+        let changes = (0..PATCH_NOTE_BUDGET / 100)
+            .map(|i| {
+                (
+                    PathBuf::from(format!("file{i:03}.txt")),
+                    FileChange::Add {
+                        content: content.clone(),
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let note = format_patch_note(
+            &changes,
+            &WithheldPaths::default(),
+            PatchFormat::default(),
+            None,
+        );
+        assert!(note.contains("  └ file000.txt (new) (+1 -0)\n"), "{note}");
+        assert!(note.contains("**Largest changes**"), "{note}");
+        assert!(note.contains("files omitted"), "{note}");
+        assert!(note.len() <= PATCH_NOTE_BUDGET + 200, "{}", note.len());
+        assert!(patch_attachment(&changes, &WithheldPaths::default()).is_some());
+    }
+
+    #[test]
+    fn withheld_files_are_listed_without_content() {
+        let withheld = WithheldPaths::new(&["secrets/**".to_string()]);
+        let changes = HashMap::from([
+            (
+                PathBuf::from("/repo/secrets/prod.env"),
+                FileChange::Update {
+                    unified_diff: "@@ -1 +1,2 @@\n-TOKEN=old\n+TOKEN=new\n+EXTRA=1\n".to_string(),
+                    move_path: None,
+                },
+            ),
+            (
+                PathBuf::from("/repo/README.md"),
+                FileChange::Add {
+                    content: "hello\n".to_string(),
+                },
+            ),
+        ]);
+        let note = format_patch_note(&changes, &withheld, PatchFormat::default(), None);
+        assert!(
+            note.starts_with("✏️ Applying patch to 2 files (+3 -1)"),
+            "{note}"
+        );
+        assert!(
+            note.contains("**2. /repo/secrets/prod.env** (+2 -1)\n[content withheld by policy]\n"),
+            "{note}"
+        );
+        assert!(!note.contains("TOKEN"), "{note}");
+        assert!(note.contains("+hello"), "{note}");
+    }
+
+    #[test]
+    fn snapshot_note_shows_the_last_lines_and_links_the_rest() {
+        let text = (0..25).map(|i| format!("row {i}\n")).collect::<String>();
+
+        let attachment = AttachmentRef {
+            attachment_id: "att-1".to_string(),
+            url: "https://example.com/snap".to_string(),
+        };
+        let note = format_snapshot_note(&text, SnapshotTrigger::Error, Some(&attachment));
+        let expected_rows = (5..25).map(|i| format!("row {i}\n")).collect::<String>();
+        assert_eq!(
+            note,
+            format!(
+                "🖥️ **Terminal snapshot after error**\n\n```text\n… (earlier lines omitted)\n{expected_rows}```\n\n📎 [Full snapshot](https://example.com/snap)"
+            )
+        );
+    }
+
+    #[test]
+    fn title_uses_first_line_of_prompt() {
+        assert_eq!(
+            title_from_prompt("\n  fix   the flaky\ttest  \nmore detail"),
+            Some("fix the flaky test".to_string())
+        );
+        assert_eq!(title_from_prompt("  \n "), None);
+
+        let long = "word ".repeat(30);
+        let title = title_from_prompt(&long).expect("title");
+        assert_eq!(title.chars().count(), MAX_TITLE_CHARS);
+        assert!(title.ends_with("word…"));
+    }
+
+    #[test]
+    fn review_finding_note_is_relative_to_cwd() {
+        let finding = ReviewFinding {
+            title: "Off-by-one in loop".to_string(),
+            body: "The upper bound skips the last element.".to_string(),
+            confidence_score: 0.8,
+            priority: 1,
+            code_location: codex_core::protocol::ReviewCodeLocation {
+                absolute_file_path: PathBuf::from("/repo/src/lib.rs"),
+                line_range: codex_core::protocol::ReviewLineRange { start: 10, end: 12 },
+            },
+        };
+        assert_eq!(
+            format_review_finding_note(&finding, Path::new("/repo"), OmnaraLocale::En),
+            "**[P1 · high] Off-by-one in loop**\n`src/lib.rs:10-12` (confidence 0.80)\n\nThe upper bound skips the last element."
+        );
+    }
+
+    #[test]
+    fn review_verdict_offers_accept_or_request_changes() {
+        let output = ReviewOutputEvent {
+            overall_correctness: "patch is incorrect".to_string(),
+            overall_explanation: "One blocking issue.".to_string(),
+            overall_confidence_score: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_review_verdict_request(&output, OmnaraLocale::En),
+            "**Code review finished:** 0 findings\n**Verdict:** patch is incorrect (confidence 0.50)\n\nOne blocking issue.\n\n[OPTIONS]\n1. Accept findings\n2. Request changes\n[/OPTIONS]"
+        );
+    }
+
+    #[test]
+    fn lifecycle_notes_follow_the_locale() {
+        assert_eq!(
+            format_interrupt_prompt(&["run the tests".to_string()], OmnaraLocale::Fr),
+            "Dites au modèle ce qu'il doit faire autrement\n\n\
+             Ces messages en file d'attente n'ont pas été envoyés :\n> run the tests"
+        );
+        assert!(format_queued_input_note(2, OmnaraLocale::De).starts_with(
+            "⏳ In der Warteschlange, bis Codex den aktuellen Durchlauf beendet (2 wartend)."
+        ));
+        assert!(
+            format_review_verdict_request(&ReviewOutputEvent::default(), OmnaraLocale::Es)
+                .ends_with("[OPTIONS]\n1. Aceptar hallazgos\n2. Solicitar cambios\n[/OPTIONS]")
+        );
+        let session = "7d4f0c1e";
+        assert_eq!(
+            format_linked_session_note("Fork", session, OmnaraLocale::En),
+            format!("🔀 **Fork** continues in a linked session: `{session}`")
+        );
+        assert_eq!(
+            format_parent_session_note(session, OmnaraLocale::De),
+            format!("↩️ Geöffnet aus Sitzung `{session}`")
+        );
+        assert_eq!(
+            format_fork_note(3, "use sqlite\ninstead", OmnaraLocale::En),
+            "✂️ Forked before prompt 3, which this conversation leaves out:\n> use sqlite\n> instead"
+        );
+    }
+
+    #[test]
+    fn turn_summary_lists_commands_files_and_excerpts() {
+        let mut summary = TurnSummaryBuilder::default();
+        summary.record_command(&["cargo".to_string(), "test".to_string()], 101);
+        summary.record_command(&["ls".to_string()], 0);
+        let changes = HashMap::from([(
+            PathBuf::from("src/lib.rs"),
+            FileChange::Add {
+                content: "a\nb\n".to_string(),
+            },
+        )]);
+        summary.record_patch(&changes);
+        summary.record_patch(&changes);
+        summary.tokens = Some(TokenUsage {
+            input_tokens: 10_000,
+            cached_input_tokens: 0,
+            output_tokens: 2_345,
+            reasoning_output_tokens: 0,
+            total_tokens: 12_345,
+        });
+        summary.duration = Some(Duration::from_secs(125));
+        summary
+            .messages
+            .push("Running the tests now.\nMore detail here.".to_string());
+
+        assert_eq!(
+            summary.build(),
+            "📋 **Turn summary**\n⏱ 2m 05s · 🔢 12,345 tokens (10,000 in · 2,345 out)\n\n**Commands (2):**\n- `cargo test` ✗ exit 101\n- `ls` ✓\n\n**Files changed (1):**\n- src/lib.rs (+4 -0)\n\n**Updates:**\n> Running the tests now.…"
+        );
+    }
+
+    #[test]
+    fn context_summary_lists_sections_and_the_largest_files() {
+        let summary = ContextSummaryEvent {
+            tokens: 13_600,
+            model_context_window: Some(272_000),
+            sections: vec![
+                ContextSection {
+                    name: "Tool output".to_string(),
+                    items: 12,
+                    tokens: 9_000,
+                },
+                ContextSection {
+                    name: "User messages".to_string(),
+                    items: 1,
+                    tokens: 4_600,
+                },
+            ],
+            files: (0..12)
+                .map(|i| ContextFile {
+                    path: format!("src/f{i}.rs"),
+                    tokens: 500 - i,
+                })
+                .collect(),
+        };
+        let note = format_context_summary(&summary);
+        assert!(
+            note.starts_with(
+                "**🧠 Context: ~13,600 tokens** (5% of 272,000)\n\
+                 - Tool output: ~9,000 tokens (12 items)\n\
+                 - User messages: ~4,600 tokens (1 item)\n\n\
+                 **Files**\n\
+                 - `src/f0.rs` ~500 tokens\n"
+            ),
+            "{note}"
+        );
+        assert!(
+            note.ends_with("- `src/f9.rs` ~491 tokens\n- …and 2 more"),
+            "{note}"
+        );
+        assert_eq!(
+            format_compaction_note(48_210, 3_150),
+            "🗜️ Compacted the conversation: ~48,210 → ~3,150 tokens"
+        );
+    }
+
+    #[test]
+    fn config_and_env_notes_are_read_only_reports() {
+        assert_eq!(
+            format_config_note(
+                Path::new("/work"),
+                "gpt-5 medium",
+                "openai",
+                AskForApproval::OnRequest,
+                &SandboxPolicy::ReadOnly,
+            ),
+            "**⚙️ Configuration**\n\
+             - Model: `gpt-5 medium`\n\
+             - Provider: `openai`\n\
+             - Approval policy: `on-request`\n\
+             - Sandbox: `read-only`\n\
+             - Working directory: `/work`"
+        );
+        let vars = [
+            ("PATH", "/usr/bin"),
+            ("GITHUB_TOKEN", "ghp_secret"),
+            ("CARGO_HOME", "/cargo"),
+            ("PYTHONPATH", "/py"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(
+            format_env_note("path", vars.clone()),
+            "**🔧 Environment: 2 variables matching `path`**\n\n```text\n\
+             PATH=/usr/bin\n\
+             PYTHONPATH=/py\n\
+             ```"
+        );
+        let note = format_env_note("", vars.clone());
+        assert!(note.contains("GITHUB_TOKEN=******** (masked)"), "{note}");
+        assert!(!note.contains("ghp_secret"), "{note}");
+        let urls = [
+            ("DATABASE_URL", "postgres://app:hunter2@db:5432/app?ssl=1"),
+            ("MIRRORS", "https://u:p@a.example/x https://b.example/y"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(
+            format_env_note("", urls),
+            "**🔧 Environment: 2 variables**\n\n```text\n\
+             DATABASE_URL=postgres://********@db:5432/app?ssl=1\n\
+             MIRRORS=https://********@a.example/x https://b.example/y\n\
+             ```"
+        );
+        let ticks = [("PS1", "```$ "), ("GREETING", "``hi``")]
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(
+            format_env_note("", ticks),
+            "**🔧 Environment: 2 variables**\n\n````text\n\
+             GREETING=``hi``\n\
+             PS1=```$ \n\
+             ````"
+        );
+        assert_eq!(
+            format_env_note("proxy", vars),
+            "🔧 No environment variables matching `proxy`"
+        );
+    }
+
+    #[test]
+    fn plan_note_is_a_checklist() {
+        use codex_protocol::plan_tool::PlanItemArg;
+        let item = |step: &str, status| PlanItemArg {
+            step: step.to_string(),
+            status,
+        };
+        let update = UpdatePlanArgs {
+            explanation: Some("Fixing the flaky test first.".to_string()),
+            plan: vec![
+                item("Reproduce the failure", StepStatus::Completed),
+                item("Fix the race", StepStatus::InProgress),
+                item("Run the suite", StepStatus::Pending),
+            ],
+        };
+        assert_eq!(
+            format_plan_note(&update),
+            "**📝 Plan** (1/3 done)\nFixing the flaky test first.\n\n\
+             ✅ Reproduce the failure\n🔄 Fix the race\n⬜ Run the suite"
+        );
+    }
+}
@@ -0,0 +1,448 @@
+//! Errors and warnings in the output of recognized compilers and linters.
+//!
+//! A failed build's exec note used to show the first 20 lines of output,
+//! which for cargo are mostly `Compiling` progress. Each
+//! [`BuildOutputParser`] recognizes one tool family's command line and
+//! reads its diagnostics, so the note can list every error and warning as
+//! `file:line` and message, with the full log attached. Support for another
+//! tool is a parser added to [`PARSERS`].
+
+use codex_core::command_display::display_command;
+
+use super::command_words;
+use super::is_program;
+
+/// Diagnostics listed in a note; the rest are counted.
+pub(crate) const MAX_DIAGNOSTICS: usize = 10;
+
+/// Characters of each diagnostic message.
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// The parsers tried, in order, for every finished command.
+pub const PARSERS: &[&dyn BuildOutputParser] = &[&Rustc, &Tsc, &Eslint, &Gcc];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// `file:line` or `file:line:column`, when the tool reported one.
+    pub location: Option<String>,
+    pub message: String,
+}
+
+/// The diagnostics of one build or lint run, errors first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl BuildReport {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+}
+
+/// Reads one tool family's diagnostics.
+pub trait BuildOutputParser: Send + Sync {
+    /// Whether one of `words`, the command line split on whitespace and
+    /// shell operators, starts a tool this parser reads.
+    fn matches(&self, words: &[&str]) -> bool;
+
+    /// The diagnostics in `output` (stdout, then stderr, escape sequences
+    /// removed), in the order printed.
+    fn parse(&self, output: &str) -> Vec<Diagnostic>;
+}
+
+/// The diagnostics of `command` from the first parser that recognizes it
+/// and finds any in `output`; repeats are dropped and errors come first.
+pub fn parse_build_output(command: &[String], output: &str) -> Option<BuildReport> {
+    let text = display_command(command).text;
+    let words = command_words(&text);
+    let found = PARSERS
+        .iter()
+        .filter(|parser| parser.matches(&words))
+        .map(|parser| parser.parse(output))
+        .find(|diagnostics| !diagnostics.is_empty())?;
+    let mut diagnostics: Vec<Diagnostic> = Vec::with_capacity(found.len());
+    for diagnostic in found {
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+    Some(BuildReport { diagnostics })
+}
+
+/// `2 errors, 1 warning`, leaving out a zero count.
+pub fn format_build_counts(report: &BuildReport) -> String {
+    let plural =
+        |count: usize, word: &str| format!("{count} {word}{}", if count == 1 { "" } else { "s" });
+    let mut parts = Vec::new();
+    if report.errors() > 0 {
+        parts.push(plural(report.errors(), "error"));
+    }
+    if report.warnings() > 0 {
+        parts.push(plural(report.warnings(), "warning"));
+    }
+    parts.join(", ")
+}
+
+/// rustc, directly or through cargo: `error[E0425]: message` followed by
+/// `--> file:line:column`. `cargo test` and `cargo bench` are left to the
+/// test results, so a run without a summary keeps its raw output.
+pub struct Rustc;
+
+impl BuildOutputParser for Rustc {
+    fn matches(&self, words: &[&str]) -> bool {
+        const SUBCOMMANDS: &[&str] = &["build", "b", "check", "c", "clippy", "run", "r", "doc"];
+        words.iter().any(|word| is_program(word, "rustc"))
+            || words
+                .windows(2)
+                .any(|pair| is_program(pair[0], "cargo") && SUBCOMMANDS.contains(&pair[1]))
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = output.lines().collect();
+        lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let (severity, rest) = if let Some(rest) = line.strip_prefix("error") {
+                    (Severity::Error, rest)
+                } else {
+                    (Severity::Warning, line.strip_prefix("warning")?)
+                };
+                // An error code, e.g. `[E0425]` or `[clippy::needless_return]`.
+                let rest = match rest.strip_prefix('[') {
+                    Some(code) => code.split_once(']')?.1,
+                    None => rest,
+                };
+                let message = rest.strip_prefix(": ")?;
+                if is_cargo_summary(message) {
+                    return None;
+                }
+                let location = lines
+                    .get(i + 1)
+                    .and_then(|next| next.trim_start().strip_prefix("--> "))
+                    .map(|location| location.trim().to_string());
+                Some(diagnostic(severity, location, message))
+            })
+            .collect()
+    }
+}
+
+/// Lines cargo and rustc print about a build as a whole.
+fn is_cargo_summary(message: &str) -> bool {
+    message.starts_with("could not compile")
+        || message.starts_with("aborting due to")
+        || message.starts_with("build failed")
+        || (message.starts_with('`') && message.contains(") generated "))
+}
+
+/// The TypeScript compiler: `file(line,col): error TS2322: message`, or
+/// `file:line:col - error TS2322: message` with `--pretty`.
+pub struct Tsc;
+
+impl BuildOutputParser for Tsc {
+    fn matches(&self, words: &[&str]) -> bool {
+        words
+            .iter()
+            .any(|word| is_program(word, "tsc") || is_program(word, "vue-tsc"))
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let (location, rest) = match line.split_once(": error TS") {
+                    Some((location, rest)) => {
+                        let (file, position) = location.strip_suffix(')')?.rsplit_once('(')?;
+                        (format!("{file}:{}", position.replace(',', ":")), rest)
+                    }
+                    None => {
+                        let (location, rest) = line.split_once(" - error TS")?;
+                        (location.to_string(), rest)
+                    }
+                };
+                let (_, message) = rest.split_once(": ")?;
+                Some(diagnostic(Severity::Error, Some(location), message))
+            })
+            .collect()
+    }
+}
+
+/// ESLint's default `stylish` output: a file path, then one indented
+/// `line:col  severity  message  rule` line per problem.
+pub struct Eslint;
+
+impl BuildOutputParser for Eslint {
+    fn matches(&self, words: &[&str]) -> bool {
+        words.iter().any(|word| is_program(word, "eslint"))
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        let mut file = None;
+        let mut diagnostics = Vec::new();
+        for line in output.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                file = Some(line.trim());
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(position), Some(severity)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let severity = match severity {
+                "error" => Severity::Error,
+                "warning" => Severity::Warning,
+                _ => continue,
+            };
+            let (Some(file), true) = (file, position.contains(':')) else {
+                continue;
+            };
+            // The message and rule are separated by two or more spaces.
+            let rest = line
+                .trim()
+                .split_once(severity_word(severity))
+                .map_or("", |(_, rest)| rest.trim());
+            let message = match rest.rsplit_once("  ") {
+                Some((message, rule)) => format!("{} ({})", message.trim(), rule.trim()),
+                None => rest.to_string(),
+            };
+            diagnostics.push(diagnostic(
+                severity,
+                Some(format!("{file}:{position}")),
+                &message,
+            ));
+        }
+        diagnostics
+    }
+}
+
+/// The `file:line:col: error: message` lines of gcc and clang, directly or
+/// through make, ninja, or cmake, and of mypy.
+pub struct Gcc;
+
+impl BuildOutputParser for Gcc {
+    fn matches(&self, words: &[&str]) -> bool {
+        const PROGRAMS: &[&str] = &[
+            "gcc", "g++", "cc", "c++", "clang", "clang++", "make", "ninja", "cmake", "mypy",
+        ];
+        words
+            .iter()
+            .any(|word| PROGRAMS.iter().any(|program| is_program(word, program)))
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let (severity, (location, message)) =
+                    if let Some(split) = line.split_once(": error: ") {
+                        (Severity::Error, split)
+                    } else if let Some(split) = line.split_once(": fatal error: ") {
+                        (Severity::Error, split)
+                    } else {
+                        (Severity::Warning, line.split_once(": warning: ")?)
+                    };
+                // `file:line`, optionally with a column.
+                let (file, position) = location.split_once(':')?;
+                let line_number = position.split(':').next()?;
+                if file.is_empty() || line_number.parse::<u32>().is_err() {
+                    return None;
+                }
+                Some(diagnostic(severity, Some(location.to_string()), message))
+            })
+            .collect()
+    }
+}
+
+fn severity_word(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+fn diagnostic(severity: Severity, location: Option<String>, message: &str) -> Diagnostic {
+    let message = message.trim();
+    let message = if message.chars().count() <= MAX_MESSAGE_CHARS {
+        message.to_string()
+    } else {
+        let mut clipped: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+        clipped.push('…');
+        clipped
+    };
+    Diagnostic {
+        severity,
+        location,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bash(command: &str) -> Vec<String> {
+        vec!["bash".to_string(), "-lc".to_string(), command.to_string()]
+    }
+
+    fn error(location: &str, message: &str) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            location: Some(location.to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    fn warning(location: &str, message: &str) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..error(location, message)
+        }
+    }
+
+    #[test]
+    fn cargo_errors_come_first_without_the_build_summary() {
+        let output = "\
+   Compiling demo v0.1.0 (/work)
+warning: unused variable: `y`
+  --> src/lib.rs:10:9
+   |
+10 |     let y = 2;
+   |         ^ help: if this is intentional, prefix it with an underscore: `_y`
+   |
+   = note: `#[warn(unused_variables)]` on by default
+
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+  |
+2 |     x
+  |     ^ not found in this scope
+
+warning: `demo` (lib) generated 1 warning
+error: could not compile `demo` (bin \"demo\") due to 1 previous error
+";
+        let report =
+            parse_build_output(&bash("cargo build --release 2>&1"), output).expect("report");
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                error("src/main.rs:2:5", "cannot find value `x` in this scope"),
+                warning("src/lib.rs:10:9", "unused variable: `y`"),
+            ]
+        );
+        assert_eq!(format_build_counts(&report), "1 error, 1 warning");
+        // A clean build has nothing to report.
+        assert_eq!(
+            parse_build_output(&bash("cargo check"), "    Finished `dev` profile\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn tsc_reads_plain_and_pretty_locations() {
+        let output = "\
+src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/util.ts:12:1 - error TS2304: Cannot find name 'foo'.
+
+Found 2 errors in 2 files.
+";
+        assert_eq!(
+            parse_build_output(&bash("npx tsc --noEmit"), output)
+                .expect("report")
+                .diagnostics,
+            vec![
+                error(
+                    "src/app.ts:3:7",
+                    "Type 'string' is not assignable to type 'number'."
+                ),
+                error("src/util.ts:12:1", "Cannot find name 'foo'."),
+            ]
+        );
+    }
+
+    #[test]
+    fn eslint_reads_stylish_output() {
+        let output = "
+/work/src/app.js
+  3:7  error    'x' is assigned a value but never used  no-unused-vars
+  5:1  warning  Unexpected console statement            no-console
+
+✖ 2 problems (1 error, 1 warning)
+";
+        let report = parse_build_output(&bash("npx eslint src"), output).expect("report");
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                error(
+                    "/work/src/app.js:3:7",
+                    "'x' is assigned a value but never used (no-unused-vars)"
+                ),
+                warning(
+                    "/work/src/app.js:5:1",
+                    "Unexpected console statement (no-console)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn gcc_style_lines_from_make_and_mypy() {
+        let output = "\
+cc -c main.c
+main.c:3:5: error: use of undeclared identifier 'x'
+main.c: In function 'main':
+util.c:8:1: warning: control reaches end of non-void function [-Wreturn-type]
+make: *** [Makefile:4: main.o] Error 1
+";
+        assert_eq!(
+            parse_build_output(&bash("make -j8"), output)
+                .expect("report")
+                .diagnostics,
+            vec![
+                error("main.c:3:5", "use of undeclared identifier 'x'"),
+                warning(
+                    "util.c:8:1",
+                    "control reaches end of non-void function [-Wreturn-type]"
+                ),
+            ]
+        );
+        assert_eq!(
+            parse_build_output(
+                &["mypy".to_string(), "src".to_string()],
+                "src/app.py:3: error: Incompatible return value type  [return-value]\n\
+                 Found 1 error in 1 file (checked 4 source files)\n"
+            )
+            .expect("report")
+            .diagnostics,
+            vec![error(
+                "src/app.py:3",
+                "Incompatible return value type  [return-value]"
+            )]
+        );
+        // Output of an unrecognized command is left alone.
+        assert_eq!(parse_build_output(&bash("./build.sh"), output), None);
+    }
+}
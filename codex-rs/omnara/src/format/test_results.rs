@@ -0,0 +1,385 @@
+//! Test results in the output of recognized test runners.
+//!
+//! A failing `cargo test` run can print hundreds of lines before the one
+//! that matters, and the exec note's preview shows only the first of them.
+//! Each [`TestOutputParser`] recognizes one runner's command line and reads
+//! the counts and failing tests from its output, so the note can lead with
+//! `❌ 3 failed, 121 passed` and a line about each failure. Support for
+//! another runner is a parser added to [`PARSERS`].
+
+use codex_core::command_display::display_command;
+
+use super::command_words;
+use super::is_program;
+
+/// Failures listed in a note; the rest are counted.
+pub(crate) const MAX_FAILURES: usize = 5;
+
+/// Characters of each failure excerpt.
+const MAX_EXCERPT_CHARS: usize = 160;
+
+/// The parsers tried, in order, for every finished command.
+pub const PARSERS: &[&dyn TestOutputParser] = &[&CargoTest, &Pytest, &Jest];
+
+/// What a test run reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// The failing tests in the order reported, each with the first line
+    /// of its failure message when there is one.
+    pub failures: Vec<TestFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFailure {
+    pub name: String,
+    pub excerpt: Option<String>,
+}
+
+/// Reads one test runner's results.
+pub trait TestOutputParser: Send + Sync {
+    /// Whether one of `words`, the command line split on whitespace and
+    /// shell operators, starts this parser's runner.
+    fn matches(&self, words: &[&str]) -> bool;
+
+    /// The results in `output` (stdout, then stderr, escape sequences
+    /// removed); `None` when it has no summary, e.g. when the build failed.
+    fn parse(&self, output: &str) -> Option<TestReport>;
+}
+
+/// The results of `command` from the first parser that recognizes it and
+/// finds a summary in `output`.
+pub fn parse_test_output(command: &[String], output: &str) -> Option<TestReport> {
+    let text = display_command(command).text;
+    let words = command_words(&text);
+    PARSERS
+        .iter()
+        .filter(|parser| parser.matches(&words))
+        .find_map(|parser| parser.parse(output))
+}
+
+/// `3 failed, 121 passed, 2 skipped`, leaving out zero failures and skips.
+pub fn format_test_counts(report: &TestReport) -> String {
+    let mut parts = Vec::new();
+    if report.failed > 0 {
+        parts.push(format!("{} failed", report.failed));
+    }
+    parts.push(format!("{} passed", report.passed));
+    if report.skipped > 0 {
+        parts.push(format!("{} skipped", report.skipped));
+    }
+    parts.join(", ")
+}
+
+/// `cargo test`, summing the `test result:` line of every test binary.
+pub struct CargoTest;
+
+impl TestOutputParser for CargoTest {
+    fn matches(&self, words: &[&str]) -> bool {
+        words
+            .windows(2)
+            .any(|pair| is_program(pair[0], "cargo") && pair[1] == "test")
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let mut report = TestReport::default();
+        let mut summaries = 0;
+        for line in output.lines() {
+            if let Some(counts) = line.strip_prefix("test result: ") {
+                summaries += 1;
+                report.passed += count_before(counts, "passed");
+                report.failed += count_before(counts, "failed");
+                report.skipped += count_before(counts, "ignored");
+            } else if let Some(name) = line
+                .strip_prefix("test ")
+                .and_then(|rest| rest.strip_suffix(" ... FAILED"))
+            {
+                report.failures.push(TestFailure {
+                    name: name.to_string(),
+                    excerpt: cargo_panic_message(output, name),
+                });
+            }
+        }
+        (summaries > 0).then_some(report)
+    }
+}
+
+/// The panic message in the captured output of failed test `name`.
+fn cargo_panic_message(output: &str, name: &str) -> Option<String> {
+    let header = format!("---- {name} stdout ----");
+    let mut lines = output
+        .lines()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with("---- ") && line.trim() != "failures:");
+    let panicked = lines.find(|line| line.starts_with("thread '"))?;
+    // Since Rust 1.73 the message follows on its own line; before, it was
+    // quoted after `panicked at '`.
+    let message = match panicked.split_once("panicked at '") {
+        Some((_, rest)) => rest.rsplit_once("', ").map_or(rest, |(message, _)| message),
+        None => lines.find(|line| !line.trim().is_empty())?,
+    };
+    Some(excerpt(message))
+}
+
+/// `pytest` (also `python -m pytest` and `py.test`).
+pub struct Pytest;
+
+impl TestOutputParser for Pytest {
+    fn matches(&self, words: &[&str]) -> bool {
+        words
+            .iter()
+            .any(|word| is_program(word, "pytest") || is_program(word, "py.test"))
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        // The final line, e.g. `==== 1 failed, 121 passed in 0.12s ====`.
+        let summary = output.lines().rev().find_map(|line| {
+            let line = line.trim().trim_matches('=').trim();
+            let (counts, _) = line.rsplit_once(" in ")?;
+            (counts.contains(" passed") || counts.contains(" failed") || counts.contains(" error"))
+                .then_some(counts)
+        })?;
+        let failures = output
+            .lines()
+            .filter_map(|line| {
+                let rest = line
+                    .strip_prefix("FAILED ")
+                    .or_else(|| line.strip_prefix("ERROR "))?;
+                let (name, message) = match rest.split_once(" - ") {
+                    Some((name, message)) => (name, Some(excerpt(message))),
+                    None => (rest, None),
+                };
+                Some(TestFailure {
+                    name: name.trim().to_string(),
+                    excerpt: message,
+                })
+            })
+            .collect();
+        Some(TestReport {
+            passed: count_before(summary, "passed"),
+            failed: count_before(summary, "failed")
+                + count_before(summary, "error")
+                + count_before(summary, "errors"),
+            skipped: count_before(summary, "skipped"),
+            failures,
+        })
+    }
+}
+
+/// `jest`, directly or through `npx`, or as an npm, yarn, or pnpm `test`
+/// script.
+pub struct Jest;
+
+impl TestOutputParser for Jest {
+    fn matches(&self, words: &[&str]) -> bool {
+        words.iter().any(|word| is_program(word, "jest"))
+            || words.windows(2).any(|pair| {
+                ["npm", "yarn", "pnpm"]
+                    .iter()
+                    .any(|tool| is_program(pair[0], tool))
+                    && pair[1] == "test"
+            })
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        // `Tests:       1 failed, 1 skipped, 121 passed, 123 total`
+        let summary = output
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().strip_prefix("Tests:"))?;
+        let lines: Vec<&str> = output.lines().collect();
+        let failures = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let name = line.trim().strip_prefix("● ")?;
+                if name.starts_with("Console") || name.starts_with("Test suite failed") {
+                    return None;
+                }
+                let message = lines[i + 1..]
+                    .iter()
+                    .map(|line| line.trim())
+                    .find(|line| !line.is_empty())
+                    .filter(|line| !line.starts_with("● "))
+                    .map(excerpt);
+                Some(TestFailure {
+                    name: name.to_string(),
+                    excerpt: message,
+                })
+            })
+            .collect();
+        Some(TestReport {
+            passed: count_before(summary, "passed"),
+            failed: count_before(summary, "failed"),
+            skipped: count_before(summary, "skipped") + count_before(summary, "todo"),
+            failures,
+        })
+    }
+}
+
+/// The number right before `label` in a comma- or semicolon-separated
+/// list such as `1 failed, 121 passed`; 0 when absent.
+fn count_before(counts: &str, label: &str) -> usize {
+    counts
+        .split([',', ';'])
+        .filter_map(|part| {
+            let mut words = part.split_whitespace().rev();
+            let last = words.next()?.trim_end_matches('.');
+            (last == label).then(|| words.next()?.parse::<usize>().ok())?
+        })
+        .sum()
+}
+
+fn excerpt(message: &str) -> String {
+    let message = message.trim();
+    if message.chars().count() <= MAX_EXCERPT_CHARS {
+        return message.to_string();
+    }
+    let mut clipped: String = message.chars().take(MAX_EXCERPT_CHARS).collect();
+    clipped.push('…');
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bash(command: &str) -> Vec<String> {
+        vec!["bash".to_string(), "-lc".to_string(), command.to_string()]
+    }
+
+    #[test]
+    fn cargo_test_counts_every_binary_and_names_failures() {
+        let output = "\
+running 3 tests
+test parse::tests::empty ... ok
+test parse::tests::nested ... FAILED
+test parse::tests::slow ... ignored
+
+failures:
+
+---- parse::tests::nested stdout ----
+
+thread 'parse::tests::nested' panicked at src/parse.rs:88:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    parse::tests::nested
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+running 2 tests
+test it_works ... ok
+test it_still_works ... ok
+
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        assert_eq!(
+            parse_test_output(&bash("cd core && cargo test -p demo"), output),
+            Some(TestReport {
+                passed: 3,
+                failed: 1,
+                skipped: 1,
+                failures: vec![TestFailure {
+                    name: "parse::tests::nested".to_string(),
+                    excerpt: Some("assertion `left == right` failed".to_string()),
+                }],
+            })
+        );
+        // A build error has no summary.
+        assert_eq!(
+            parse_test_output(&bash("cargo test"), "error[E0425]: cannot find value `x`\n"),
+            None
+        );
+        // Nor is other cargo output read as test results.
+        assert_eq!(parse_test_output(&bash("cargo build"), output), None);
+    }
+
+    #[test]
+    fn pytest_reads_the_final_summary_and_short_failures() {
+        let output = "\
+============================= test session starts ==============================
+collected 124 items
+
+tests/test_api.py ....F.....                                             [  8%]
+...
+=========================== short test summary info ============================
+FAILED tests/test_api.py::test_login - AssertionError: assert 401 == 200
+ERROR tests/test_db.py::test_migrate - sqlite3.OperationalError: no such table
+============= 1 failed, 121 passed, 1 skipped, 1 error in 3.52s ==============
+";
+        let report = parse_test_output(&["python".into(), "-m".into(), "pytest".into()], output)
+            .expect("report");
+        assert_eq!(
+            format_test_counts(&report),
+            "2 failed, 121 passed, 1 skipped"
+        );
+        assert_eq!(
+            report.failures,
+            vec![
+                TestFailure {
+                    name: "tests/test_api.py::test_login".to_string(),
+                    excerpt: Some("AssertionError: assert 401 == 200".to_string()),
+                },
+                TestFailure {
+                    name: "tests/test_db.py::test_migrate".to_string(),
+                    excerpt: Some("sqlite3.OperationalError: no such table".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_test_output(&bash("pytest -q"), "3 passed in 0.01s\n"),
+            Some(TestReport {
+                passed: 3,
+                ..TestReport::default()
+            })
+        );
+    }
+
+    #[test]
+    fn jest_reads_the_tests_line_and_failure_headers() {
+        let output = "\
+FAIL src/cart.test.js
+  ● Cart › applies the discount
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 90
+    Received: 100
+
+  ● Console
+
+    console.log
+      debug
+
+PASS src/api.test.js
+
+Test Suites: 1 failed, 1 passed, 2 total
+Tests:       1 failed, 2 skipped, 121 passed, 124 total
+Snapshots:   0 total
+Time:        2.31 s
+";
+        assert_eq!(
+            parse_test_output(&bash("npm test -- --ci"), output),
+            Some(TestReport {
+                passed: 121,
+                failed: 1,
+                skipped: 2,
+                failures: vec![TestFailure {
+                    name: "Cart › applies the discount".to_string(),
+                    excerpt: Some(
+                        "expect(received).toBe(expected) // Object.is equality".to_string()
+                    ),
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Remote control of a Codex session from the Omnara dashboard, for any
+//! Rust frontend.
+//!
+//! The pieces a frontend needs to mirror a session and take instructions and
+//! approvals from the dashboard, without the TUI:
+//!
+//! - [`OmnaraClient`]: the HTTP client. It posts agent messages (with the
+//!   session diff), requests input, polls for replies, and uploads
+//!   attachments.
+//! - [`ansi`]: escape sequences in command output, stripped or turned into
+//!   HTML colors.
+//! - [`format`]: the notes and prompts posted for Codex events (commands,
+//!   patches, MCP calls, web searches, reviews, turn summaries).
+//! - [`approval`]: approval prompts and reply parsing.
+//! - [`guard`]: prompt-injection screening of dashboard messages.
+//! - [`state`]: the bridge state machine that decides what a dashboard
+//!   reply means.
+//! - [`sanitize`]: cleaning of dashboard messages before they are routed.
+//! - [`tags`]: `/tag` commands for the session's dashboard labels.
+//! - [`diagnostics`]: rate-limited notes for stream retries and errors.
+//! - [`journal`]: what a crashed run left pending, for the next one to
+//!   recover.
+//! - [`session_report`]: the report posted when the session ends.
+//! - [`markdown`]: conversion of outgoing messages to the configured
+//!   markdown dialect.
+//!
+//! A frontend's bridge keeps one [`state::BridgeState`] and feeds it every
+//! lifecycle event, posts a prompt from [`approval`] when Codex asks for an
+//! approval, and routes each message delivered by
+//! [`OmnaraClient::start_polling`], once cleaned by
+//! [`sanitize::sanitize_remote_message`], with [`state::BridgeState::route`]. The
+//! TUI's `OmnaraBridge` is the reference implementation.
+//!
+//! # Scope
+//!
+//! This crate owns the formatters ([`ansi`], [`format`]), the prompt-injection
+//! [`guard`] and the bridge [`state`] machine, and depends on `codex-core`
+//! but not on the TUI. The client and the modules re-exported from
+//! `codex-core` (approvals, journal, sanitizing, ...) stay there: core's own
+//! headless `RemoteFrontend` and its approval and `ask_user` paths use them,
+//! and moving them here would make the two crates depend on each other.
+//! Frontends should still import them from this crate, which is the
+//! documented API.
+
+pub mod ansi;
+pub mod format;
+pub mod guard;
+pub mod state;
+
+pub use codex_core::omnara_approval as approval;
+pub use codex_core::omnara_capabilities as capabilities;
+pub use codex_core::omnara_clock as clock;
+pub use codex_core::omnara_diagnostics as diagnostics;
+pub use codex_core::omnara_doctor as doctor;
+pub use codex_core::omnara_escalation as escalation;
+pub use codex_core::omnara_i18n as i18n;
+pub use codex_core::omnara_journal as journal;
+pub use codex_core::omnara_markdown as markdown;
+pub use codex_core::omnara_sanitize as sanitize;
+pub use codex_core::omnara_session_report as session_report;
+pub use codex_core::omnara_tags as tags;
+pub use codex_core::omnara_withhold as withhold;
+
+pub use codex_core::omnara_client::AttachmentRef;
+pub use codex_core::omnara_client::OmnaraClient;
+pub use codex_core::omnara_client::PollPolicy;
+pub use codex_core::omnara_client::SessionMetadata;
@@ -0,0 +1,576 @@
+//! Lifecycle of an Omnara bridge as an explicit state machine.
+//!
+//! A frontend keeps one [`BridgeState`], owned by the single task that talks
+//! to Omnara (the TUI's bridge worker). Every lifecycle event goes through
+//! [`BridgeState::next`], and remote messages are routed by
+//! [`BridgeState::route`], so what a dashboard reply means never depends on
+//! the timing of spawned tasks.
+
+use codex_core::omnara_approval::APPLY_PATCH_COMMAND;
+use codex_core::omnara_approval::EXPLAIN_COMMAND;
+use codex_core::omnara_approval::PREVIEW_COMMAND;
+use codex_core::omnara_approval::ROLLBACK_COMMAND;
+use codex_core::omnara_approval::parse_show_hunk;
+use codex_core::omnara_tags::TAG_COMMAND;
+
+/// Prefix of a remote message that interrupts the current turn.
+pub const NOW_COMMAND: &str = "/now";
+/// Prefix of a remote message that ends the current task and starts another.
+pub const NEW_TASK_COMMAND: &str = "/new-task";
+/// Prefix of a remote message that switches the model for later turns.
+pub const MODEL_COMMAND: &str = "/model";
+/// Prefix of a remote message that switches the model provider.
+pub const PROVIDER_COMMAND: &str = "/provider";
+/// A remote message that compacts the conversation history.
+pub const COMPACT_COMMAND: &str = "/compact";
+/// A remote message that asks what the conversation history holds.
+pub const CONTEXT_COMMAND: &str = "/context";
+/// A remote message that asks for the session's effective configuration.
+pub const CONFIG_COMMAND: &str = "/config";
+/// Prefix of a remote message that lists environment variables by name.
+pub const ENV_COMMAND: &str = "/env";
+/// Prefix of a remote message that branches the conversation before a
+/// prompt.
+pub const FORK_COMMAND: &str = "/fork";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalKind {
+    Exec,
+    /// Several exec approvals posted as one numbered prompt.
+    ExecBatch,
+    /// An exec approval that would run the command outside the sandbox.
+    Escalation,
+    Patch,
+    /// A call to a tool of an MCP server configured with `require_approval`.
+    Mcp,
+    /// "Accept findings / request changes" prompt sent after a code review.
+    Review,
+    /// A question from the model's `ask_user` tool; the reply is the answer.
+    Question,
+    /// Confirmation of a dashboard `/rollback`, between turns.
+    Rollback,
+}
+
+impl ApprovalKind {
+    /// Name used in logs and the approval audit trail.
+    pub fn label(self) -> &'static str {
+        match self {
+            ApprovalKind::Exec => "exec",
+            ApprovalKind::ExecBatch => "exec batch",
+            ApprovalKind::Escalation => "escalation",
+            ApprovalKind::Patch => "patch",
+            ApprovalKind::Mcp => "mcp",
+            ApprovalKind::Review => "review",
+            ApprovalKind::Question => "question",
+            ApprovalKind::Rollback => "rollback",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeState {
+    /// Nothing has been requested from the dashboard: before the session
+    /// starts, after a turn ends and before input is requested, or after the
+    /// local user took over.
+    Idle,
+    /// The dashboard was asked for the next instruction.
+    AwaitingRemoteInput,
+    /// An approval (or review verdict) prompt is out; the next remote message
+    /// answers it.
+    AwaitingApproval { id: String, kind: ApprovalKind },
+    /// The agent is working on a turn.
+    TurnActive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeEvent {
+    SessionStarted,
+    /// A turn started, locally or from forwarded remote input.
+    TurnStarted,
+    /// The turn completed, failed, or was interrupted.
+    TurnEnded,
+    /// An agent message requiring user input was posted.
+    InputRequested,
+    ApprovalRequested {
+        id: String,
+        kind: ApprovalKind,
+    },
+    /// The pending approval prompt was answered from the dashboard.
+    ApprovalAnswered,
+    /// Local input was submitted and remote polling cancelled.
+    LocalInput,
+}
+
+/// What to do with a message from the dashboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route<'a> {
+    /// Reply to the pending prompt of this kind.
+    ApprovalReply(ApprovalKind),
+    /// `/preview` for a pending patch (or rollback): dry-run it; the approval
+    /// stays open.
+    Preview,
+    /// `/explain` for a pending patch (or rollback): summarize it in plain
+    /// language; the approval stays open.
+    Explain,
+    /// `show N` for a pending patch (or rollback): post hunk N in full; the
+    /// approval stays open.
+    ShowHunk(usize),
+    /// `/rollback`: offer to undo the session's changes.
+    Rollback,
+    /// `/apply-patch <diff>`: offer the diff for local approval; the text
+    /// after the command.
+    ApplyPatch(&'a str),
+    /// `/now <message>`: interrupt a running turn, then send `message`.
+    Now(&'a str),
+    /// `/new-task <prompt>`: close out the current task and start another.
+    NewTask(&'a str),
+    /// `/tag …`: change or list the session's tags; the text after `/tag`.
+    Tag(&'a str),
+    /// `/model <name>`: switch the model for later turns; empty lists the
+    /// choices.
+    Model(&'a str),
+    /// `/provider <id>`: switch the model provider; empty lists the choices.
+    Provider(&'a str),
+    /// `/compact`: summarize the conversation to free up context.
+    Compact,
+    /// `/context`: report what the conversation history holds.
+    Context,
+    /// `/config`: report the model, policies, and working directory.
+    Config,
+    /// `/env <filter>`: list the environment variables whose names contain
+    /// `filter`; empty lists them all.
+    Env(&'a str),
+    /// `/fork [N]`: continue in a new conversation forked before prompt N,
+    /// the latest when empty; the text after `/fork`.
+    Fork(&'a str),
+    /// Hold until the running turn completes.
+    Queue,
+    /// Send to the agent as user input.
+    Forward,
+}
+
+impl BridgeState {
+    /// State after `event`. Total: an event the current state does not
+    /// expect (see [`Self::expects`]) still yields a well-defined state, so
+    /// a missed or reordered event cannot wedge the bridge.
+    pub fn next(&self, event: &BridgeEvent) -> BridgeState {
+        use BridgeEvent::*;
+        use BridgeState::*;
+        match (self, event) {
+            (Idle, SessionStarted) => AwaitingRemoteInput,
+            (_, SessionStarted) => self.clone(),
+            (_, TurnStarted) => TurnActive,
+            // A review verdict is requested as the review turn ends; it stays
+            // pending across the end of that turn.
+            (
+                AwaitingApproval {
+                    kind: ApprovalKind::Review,
+                    ..
+                },
+                TurnEnded | InputRequested,
+            ) => self.clone(),
+            // Approvals belong to their turn; once it ends they are moot.
+            (_, TurnEnded) => Idle,
+            (_, InputRequested) => AwaitingRemoteInput,
+            (_, ApprovalRequested { id, kind }) => AwaitingApproval {
+                id: id.clone(),
+                kind: *kind,
+            },
+            (
+                AwaitingApproval {
+                    kind: ApprovalKind::Review | ApprovalKind::Rollback,
+                    ..
+                },
+                ApprovalAnswered,
+            ) => AwaitingRemoteInput,
+            (AwaitingApproval { .. }, ApprovalAnswered) => TurnActive,
+            (_, ApprovalAnswered) => self.clone(),
+            (_, LocalInput) => Idle,
+        }
+    }
+
+    /// Whether `event` is a legal transition from this state. Unexpected
+    /// events are logged by the worker but still applied via [`Self::next`].
+    pub fn expects(&self, event: &BridgeEvent) -> bool {
+        use BridgeEvent::*;
+        use BridgeState::*;
+        match (self, event) {
+            (Idle, SessionStarted) => true,
+            (_, SessionStarted) => false,
+            (
+                Idle
+                | AwaitingRemoteInput
+                | TurnActive
+                | AwaitingApproval {
+                    kind: ApprovalKind::Review | ApprovalKind::Rollback,
+                    ..
+                },
+                TurnStarted,
+            ) => true,
+            (AwaitingApproval { .. }, TurnStarted) => false,
+            (
+                TurnActive
+                | AwaitingApproval {
+                    kind: ApprovalKind::Review,
+                    ..
+                },
+                TurnEnded,
+            ) => true,
+            (_, TurnEnded) => false,
+            (AwaitingApproval { .. }, InputRequested) => false,
+            (_, InputRequested) => true,
+            (
+                TurnActive,
+                ApprovalRequested {
+                    kind:
+                        ApprovalKind::Exec
+                        | ApprovalKind::ExecBatch
+                        | ApprovalKind::Escalation
+                        | ApprovalKind::Patch
+                        | ApprovalKind::Mcp
+                        | ApprovalKind::Question,
+                    ..
+                },
+            ) => true,
+            (
+                Idle | AwaitingRemoteInput | TurnActive,
+                ApprovalRequested {
+                    kind: ApprovalKind::Review,
+                    ..
+                },
+            ) => true,
+            (
+                Idle | AwaitingRemoteInput,
+                ApprovalRequested {
+                    kind: ApprovalKind::Rollback,
+                    ..
+                },
+            ) => true,
+            (_, ApprovalRequested { .. }) => false,
+            (AwaitingApproval { .. }, ApprovalAnswered) => true,
+            (_, ApprovalAnswered) => false,
+            (_, LocalInput) => true,
+        }
+    }
+
+    /// How to handle `text` from the dashboard in this state.
+    pub fn route<'a>(&self, text: &'a str, queue_remote_input: bool) -> Route<'a> {
+        if let BridgeState::AwaitingApproval { kind, .. } = self {
+            let patch = matches!(kind, ApprovalKind::Patch | ApprovalKind::Rollback);
+            if patch && strip_command(text, PREVIEW_COMMAND).is_some() {
+                return Route::Preview;
+            }
+            if patch && strip_command(text, EXPLAIN_COMMAND).is_some() {
+                return Route::Explain;
+            }
+            if patch && let Some(index) = parse_show_hunk(text) {
+                return Route::ShowHunk(index);
+            }
+            return Route::ApprovalReply(*kind);
+        }
+        if strip_command(text, ROLLBACK_COMMAND).is_some() {
+            Route::Rollback
+        } else if let Some(diff) = strip_command_body(text, APPLY_PATCH_COMMAND) {
+            Route::ApplyPatch(diff)
+        } else if let Some(message) = strip_command(text, NOW_COMMAND) {
+            Route::Now(message)
+        } else if let Some(prompt) = strip_command(text, NEW_TASK_COMMAND) {
+            Route::NewTask(prompt)
+        } else if let Some(args) = strip_command(text, TAG_COMMAND) {
+            Route::Tag(args)
+        } else if let Some(model) = strip_command(text, MODEL_COMMAND) {
+            Route::Model(model)
+        } else if let Some(provider) = strip_command(text, PROVIDER_COMMAND) {
+            Route::Provider(provider)
+        } else if strip_command(text, COMPACT_COMMAND).is_some() {
+            Route::Compact
+        } else if strip_command(text, CONTEXT_COMMAND).is_some() {
+            Route::Context
+        } else if strip_command(text, CONFIG_COMMAND).is_some() {
+            Route::Config
+        } else if let Some(filter) = strip_command(text, ENV_COMMAND) {
+            Route::Env(filter)
+        } else if let Some(prompt) = strip_command(text, FORK_COMMAND) {
+            Route::Fork(prompt)
+        } else if queue_remote_input && *self == BridgeState::TurnActive {
+            Route::Queue
+        } else {
+            Route::Forward
+        }
+    }
+}
+
+/// The text following a leading `command` (e.g. `/now`), if `text` is that
+/// command.
+pub fn strip_command<'a>(text: &'a str, command: &str) -> Option<&'a str> {
+    let rest = text.trim_start().strip_prefix(command)?;
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+/// Like [`strip_command`], but only the separator after `command` (spaces
+/// and one line break) is removed: a diff's trailing blank context lines
+/// and final newline are part of it.
+pub fn strip_command_body<'a>(text: &'a str, command: &str) -> Option<&'a str> {
+    let rest = text.trim_start().strip_prefix(command)?;
+    let body = rest.trim_start_matches([' ', '\t']);
+    let body = body
+        .strip_prefix("\r\n")
+        .or_else(|| body.strip_prefix('\n'))
+        .unwrap_or(body);
+    if body.len() == rest.len() && !rest.is_empty() {
+        // Another command that starts with this one, e.g. `/apply-patches`.
+        return None;
+    }
+    Some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const KINDS: [ApprovalKind; 8] = [
+        ApprovalKind::Exec,
+        ApprovalKind::ExecBatch,
+        ApprovalKind::Escalation,
+        ApprovalKind::Patch,
+        ApprovalKind::Mcp,
+        ApprovalKind::Review,
+        ApprovalKind::Question,
+        ApprovalKind::Rollback,
+    ];
+
+    fn random_event(rng: &mut StdRng) -> BridgeEvent {
+        match rng.random_range(0..7) {
+            0 => BridgeEvent::SessionStarted,
+            1 => BridgeEvent::TurnStarted,
+            2 => BridgeEvent::TurnEnded,
+            3 => BridgeEvent::InputRequested,
+            4 => BridgeEvent::ApprovalRequested {
+                id: format!("req-{}", rng.random_range(0..100)),
+                kind: KINDS[rng.random_range(0..KINDS.len())],
+            },
+            5 => BridgeEvent::ApprovalAnswered,
+            _ => BridgeEvent::LocalInput,
+        }
+    }
+
+    #[test]
+    fn typical_session_follows_legal_transitions() {
+        let approval = BridgeEvent::ApprovalRequested {
+            id: "call-1".to_string(),
+            kind: ApprovalKind::Exec,
+        };
+        let review = BridgeEvent::ApprovalRequested {
+            id: "msg-9".to_string(),
+            kind: ApprovalKind::Review,
+        };
+        let mut state = BridgeState::Idle;
+        let mut visited = Vec::new();
+        for event in [
+            BridgeEvent::SessionStarted,
+            BridgeEvent::TurnStarted,
+            approval,
+            BridgeEvent::ApprovalAnswered,
+            BridgeEvent::TurnEnded,
+            BridgeEvent::InputRequested,
+            BridgeEvent::TurnStarted,
+            review,
+            BridgeEvent::TurnEnded,
+            BridgeEvent::ApprovalAnswered,
+            BridgeEvent::LocalInput,
+        ] {
+            assert!(state.expects(&event), "{state:?} should accept {event:?}");
+            state = state.next(&event);
+            visited.push(state.clone());
+        }
+        assert_eq!(
+            visited,
+            vec![
+                BridgeState::AwaitingRemoteInput,
+                BridgeState::TurnActive,
+                BridgeState::AwaitingApproval {
+                    id: "call-1".to_string(),
+                    kind: ApprovalKind::Exec
+                },
+                BridgeState::TurnActive,
+                BridgeState::Idle,
+                BridgeState::AwaitingRemoteInput,
+                BridgeState::TurnActive,
+                BridgeState::AwaitingApproval {
+                    id: "msg-9".to_string(),
+                    kind: ApprovalKind::Review
+                },
+                BridgeState::AwaitingApproval {
+                    id: "msg-9".to_string(),
+                    kind: ApprovalKind::Review
+                },
+                BridgeState::AwaitingRemoteInput,
+                BridgeState::Idle,
+            ]
+        );
+    }
+
+    #[test]
+    fn random_event_sequences_preserve_invariants() {
+        let mut rng = StdRng::seed_from_u64(0x0e4a);
+        for _ in 0..500 {
+            let mut state = BridgeState::Idle;
+            for _ in 0..40 {
+                let event = random_event(&mut rng);
+                let next = state.next(&event);
+
+                // Unexpected events never change a state they do not apply to.
+                if matches!(
+                    event,
+                    BridgeEvent::SessionStarted | BridgeEvent::ApprovalAnswered
+                ) && !state.expects(&event)
+                {
+                    assert_eq!(next, state, "{event:?}");
+                }
+                // A remote message is treated as an approval reply exactly
+                // when a prompt is pending.
+                let pending = matches!(next, BridgeState::AwaitingApproval { .. });
+                assert_eq!(
+                    matches!(next.route("yes", true), Route::ApprovalReply(_)),
+                    pending
+                );
+                // Only messages arriving mid-turn are queued.
+                assert_eq!(
+                    next.route("more", true) == Route::Queue,
+                    next == BridgeState::TurnActive
+                );
+                assert_eq!(next.route("more", false), {
+                    if pending {
+                        next.route("more", true)
+                    } else {
+                        Route::Forward
+                    }
+                });
+                match &event {
+                    // Tool approvals never outlive their turn.
+                    BridgeEvent::TurnEnded => assert!(matches!(
+                        next,
+                        BridgeState::Idle
+                            | BridgeState::AwaitingApproval {
+                                kind: ApprovalKind::Review,
+                                ..
+                            }
+                    )),
+                    BridgeEvent::ApprovalRequested { id, kind } => assert_eq!(
+                        next,
+                        BridgeState::AwaitingApproval {
+                            id: id.clone(),
+                            kind: *kind
+                        }
+                    ),
+                    BridgeEvent::LocalInput => assert_eq!(next, BridgeState::Idle),
+                    BridgeEvent::TurnStarted => assert_eq!(next, BridgeState::TurnActive),
+                    _ => {}
+                }
+                state = next;
+            }
+        }
+    }
+
+    #[test]
+    fn now_command_requires_word_boundary() {
+        assert_eq!(strip_command("/now fix it", NOW_COMMAND), Some("fix it"));
+        assert_eq!(strip_command("  /now", NOW_COMMAND), Some(""));
+        assert_eq!(strip_command("/nowhere", NOW_COMMAND), None);
+        assert_eq!(strip_command("do it /now", NOW_COMMAND), None);
+        assert_eq!(
+            strip_command("/new-task add docs", NEW_TASK_COMMAND),
+            Some("add docs")
+        );
+    }
+
+    #[test]
+    fn commands_are_routed_unless_an_approval_is_pending() {
+        let active = BridgeState::TurnActive;
+        assert_eq!(active.route("/now stop", true), Route::Now("stop"));
+        assert_eq!(
+            BridgeState::AwaitingRemoteInput.route("/new-task docs", true),
+            Route::NewTask("docs")
+        );
+        assert_eq!(active.route("/tag add ci", true), Route::Tag("add ci"));
+        assert_eq!(
+            active.route("/model gpt-5-high", true),
+            Route::Model("gpt-5-high")
+        );
+        assert_eq!(
+            BridgeState::AwaitingRemoteInput.route("/provider", true),
+            Route::Provider("")
+        );
+        assert_eq!(
+            BridgeState::AwaitingRemoteInput.route("/compact", true),
+            Route::Compact
+        );
+        assert_eq!(active.route(" /context ", true), Route::Context);
+        assert_eq!(active.route("/config", true), Route::Config);
+        assert_eq!(
+            BridgeState::AwaitingRemoteInput.route("/env path", true),
+            Route::Env("path")
+        );
+        assert_eq!(active.route("/environment", false), Route::Forward);
+        let pending = BridgeState::AwaitingApproval {
+            id: "call-1".to_string(),
+            kind: ApprovalKind::Patch,
+        };
+        assert_eq!(
+            pending.route("/now stop", true),
+            Route::ApprovalReply(ApprovalKind::Patch)
+        );
+        assert_eq!(
+            pending.route("/tag add ci", true),
+            Route::ApprovalReply(ApprovalKind::Patch)
+        );
+        assert_eq!(pending.route(" /preview ", true), Route::Preview);
+        assert_eq!(
+            BridgeState::AwaitingRemoteInput.route("/rollback", true),
+            Route::Rollback
+        );
+        assert_eq!(
+            pending.route("/rollback", true),
+            Route::ApprovalReply(ApprovalKind::Patch)
+        );
+        assert_eq!(
+            BridgeState::AwaitingRemoteInput.route("/fork 2", true),
+            Route::Fork("2")
+        );
+        assert_eq!(active.route("/forks", true), Route::Queue);
+        assert_eq!(
+            active.route("/apply-patch\n--- a/x\n+++ b/x", true),
+            Route::ApplyPatch("--- a/x\n+++ b/x")
+        );
+        // A trailing blank context line and the final newline are kept.
+        let diff = "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-one\n+two\n \n";
+        assert_eq!(
+            active.route(&format!("/apply-patch  \n{diff}"), true),
+            Route::ApplyPatch(diff)
+        );
+        assert_eq!(active.route("/apply-patch", true), Route::ApplyPatch(""));
+        assert_eq!(active.route("/apply-patches", true), Route::Queue);
+        assert_eq!(pending.route("/explain", true), Route::Explain);
+        assert_eq!(pending.route("Show #3", true), Route::ShowHunk(3));
+        assert_eq!(
+            pending.route("show me the tests", true),
+            Route::ApprovalReply(ApprovalKind::Patch)
+        );
+        let exec = BridgeState::AwaitingApproval {
+            id: "call-2".to_string(),
+            kind: ApprovalKind::Exec,
+        };
+        assert_eq!(
+            exec.route("/preview", true),
+            Route::ApprovalReply(ApprovalKind::Exec)
+        );
+    }
+}
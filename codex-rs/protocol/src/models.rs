@@ -187,11 +187,33 @@ pub struct LocalShellExecAction {
 pub enum WebSearchAction {
     Search {
         query: String,
+        /// Result pages, when the provider returns them.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        sources: Vec<WebSearchSource>,
+    },
+    /// The model opened a page.
+    OpenPage {
+        #[serde(default)]
+        url: Option<String>,
+    },
+    /// The model searched for `pattern` within a page.
+    Find {
+        #[serde(default)]
+        url: Option<String>,
+        #[serde(default)]
+        pattern: Option<String>,
     },
     #[serde(other)]
     Other,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
+pub struct WebSearchSource {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ReasoningItemReasoningSummary {
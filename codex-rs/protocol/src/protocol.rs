@@ -17,6 +17,7 @@ use crate::mcp_protocol::ConversationId;
 use crate::message_history::HistoryEntry;
 use crate::models::ContentItem;
 use crate::models::ResponseItem;
+use crate::models::WebSearchSource;
 use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
 use crate::plan_tool::UpdatePlanArgs;
@@ -115,6 +116,11 @@ pub enum Op {
         #[serde(skip_serializing_if = "Option::is_none")]
         model: Option<String>,
 
+        /// Updated model provider, by its id in `model_providers`. Unknown
+        /// ids are ignored.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model_provider: Option<String>,
+
         /// Updated reasoning effort (honored only for reasoning-capable models).
         ///
         /// Use `Some(Some(_))` to set a specific effort, `Some(None)` to clear
@@ -135,6 +141,15 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve an MCP tool call from a server configured with
+    /// `require_approval`
+    McpToolCallApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
     /// Approve a code patch
     PatchApproval {
         /// The id of the submission we are approving
@@ -143,6 +158,43 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Leave some files out of a pending patch. Sent before the
+    /// `PatchApproval` it applies to; the approved patch is applied without
+    /// the changes to `paths` (absolute, as in the approval request).
+    ExcludePatchFiles {
+        /// The id of the submission the patch approval belongs to
+        id: String,
+        /// Files whose changes should not be applied.
+        paths: Vec<PathBuf>,
+    },
+
+    /// Summarize a pending patch in plain language with a separate,
+    /// lightweight model request. Answered by
+    /// [`EventMsg::PatchExplanation`]; the approval stays pending.
+    ExplainPatch {
+        /// Id of the `ApplyPatchApprovalRequest` event, echoed in the answer.
+        id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    },
+
+    /// Summarize what the conversation history holds. Answered by
+    /// [`EventMsg::ContextSummary`].
+    GetContextSummary,
+
+    /// Sent by a frontend that shows [`EventMsg::AskUserRequest`] questions
+    /// to someone who can answer them with `Op::AskUserAnswer`. The
+    /// `ask_user` tool is only offered once such a frontend (or the
+    /// connection started by `Op::RegisterRemoteFrontend`) is attached.
+    RegisterQuestionFrontend,
+
+    /// Answer an `AskUserRequest` from the model's `ask_user` tool.
+    AskUserAnswer {
+        /// The `call_id` of the `AskUserRequest` being answered.
+        id: String,
+        /// The user's reply; an option number ("2") selects that option.
+        answer: String,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -174,6 +226,14 @@ pub enum Op {
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
+    /// Connect this session to the Omnara dashboard (configured via
+    /// `OMNARA_API_KEY`). Core then mirrors agent messages and approval
+    /// requests remotely and routes dashboard replies back into the session,
+    /// reporting them with [`EventMsg::RemoteInputReceived`] and
+    /// [`EventMsg::RemoteApprovalResolved`]. Frontends that register must not
+    /// mirror to Omnara themselves.
+    RegisterRemoteFrontend,
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -481,6 +541,14 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// An MCP tool call waits for approval; answer with
+    /// `Op::McpToolCallApproval`.
+    McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent),
+
+    /// The model asked the user a question via the `ask_user` tool; the tool
+    /// call blocks until `Op::AskUserAnswer` arrives or it times out.
+    AskUserRequest(AskUserRequestEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that a model stream experienced an error or disconnect
@@ -519,6 +587,24 @@ pub enum EventMsg {
 
     /// Exited review mode with an optional final result to apply.
     ExitedReviewMode(ExitedReviewModeEvent),
+
+    /// User input arrived from the remote frontend and was submitted to the
+    /// agent. Frontends should show it like locally typed input.
+    RemoteInputReceived(RemoteInputReceivedEvent),
+
+    /// A pending approval was decided from the remote frontend and the
+    /// decision already applied. Frontends should dismiss any local prompt
+    /// for the same request.
+    RemoteApprovalResolved(RemoteApprovalResolvedEvent),
+
+    /// Answer to `Op::ExplainPatch`.
+    PatchExplanation(PatchExplanationEvent),
+
+    /// The conversation history was compacted.
+    ContextCompacted(ContextCompactedEvent),
+
+    /// Answer to `Op::GetContextSummary`.
+    ContextSummary(ContextSummaryEvent),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -526,6 +612,57 @@ pub struct ExitedReviewModeEvent {
     pub review_output: Option<ReviewOutputEvent>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct RemoteInputReceivedEvent {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct RemoteApprovalResolvedEvent {
+    /// Id of the `ExecApprovalRequest`/`ApplyPatchApprovalRequest` event.
+    pub id: String,
+    pub decision: ReviewDecision,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct PatchExplanationEvent {
+    /// Id of the `ApplyPatchApprovalRequest` event that was explained.
+    pub id: String,
+    /// The summary; `None` when the model request failed.
+    pub explanation: Option<String>,
+}
+
+/// Sizes are estimated from the history's text, about four bytes a token.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ContextCompactedEvent {
+    pub tokens_before: u64,
+    pub tokens_after: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ContextSummaryEvent {
+    /// Estimated tokens of the whole history.
+    pub tokens: u64,
+    pub model_context_window: Option<u64>,
+    /// The history by kind of item, largest first.
+    pub sections: Vec<ContextSection>,
+    /// Files read by commands or attached to messages, largest first.
+    pub files: Vec<ContextFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct ContextSection {
+    pub name: String,
+    pub items: usize,
+    pub tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct ContextFile {
+    pub path: String,
+    pub tokens: u64,
+}
+
 // Individual event payload types matching each `EventMsg` variant.
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -541,6 +678,10 @@ pub struct TaskCompleteEvent {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TaskStartedEvent {
     pub model_context_window: Option<u64>,
+    /// W3C `traceparent` of the turn's span, so frontends can link their
+    /// own requests into the turn's trace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default, TS)]
@@ -826,6 +967,14 @@ pub struct McpToolCallBeginEvent {
     pub invocation: McpInvocation,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpToolCallApprovalRequestEvent {
+    /// Identifier of the tool call; the McpToolCallBegin event that follows
+    /// an approval carries the same id.
+    pub call_id: String,
+    pub invocation: McpInvocation,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct McpToolCallEndEvent {
     /// Identifier for the corresponding McpToolCallBegin that finished.
@@ -854,7 +1003,26 @@ pub struct WebSearchBeginEvent {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct WebSearchEndEvent {
     pub call_id: String,
+    /// Search query, or the text looked for on `url`; empty when a page was
+    /// only opened.
     pub query: String,
+    /// Page opened or searched within, for browsing actions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Result pages of a search, when the provider returns them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<WebSearchSource>,
+}
+
+impl WebSearchEndEvent {
+    /// One-line description, e.g. `Searched: rust async traits`.
+    pub fn summary(&self) -> String {
+        match (&self.url, self.query.is_empty()) {
+            (None, _) => format!("Searched: {}", self.query),
+            (Some(url), true) => format!("Opened: {url}"),
+            (Some(url), false) => format!("Searched {url} for: {}", self.query),
+        }
+    }
 }
 
 /// Response payload for `Op::GetHistory` containing the current session's
@@ -1070,6 +1238,23 @@ pub struct ExecCommandEndEvent {
     pub duration: Duration,
     /// Formatted output from the command, as seen by the model.
     pub formatted_output: String,
+    /// Whether the command ran inside the sandbox. `None` when unknown.
+    #[serde(default)]
+    pub sandbox: Option<ExecSandbox>,
+}
+
+/// How a command was isolated when it ran.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecSandbox {
+    /// Inside the platform sandbox.
+    Sandboxed,
+    /// Without a sandbox because the sandbox policy allows it, or the command
+    /// was already approved for the session.
+    Unsandboxed,
+    /// Outside the sandbox because the user approved this call, or a retry
+    /// after the sandbox blocked it.
+    Escalated,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
@@ -1103,6 +1288,33 @@ pub struct ExecApprovalRequestEvent {
     /// Optional human-readable reason for the approval (e.g. retry without sandbox).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Set when approving would run the command without the sandbox.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<PermissionEscalation>,
+    /// The command approved for the session that this one nearly matches,
+    /// so approvers can judge just the difference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closest_approved: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct AskUserRequestEvent {
+    /// Identifier of the `ask_user` call; answer with `Op::AskUserAnswer`.
+    pub call_id: String,
+    pub question: String,
+    /// Suggested replies; free-form answers are accepted too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// Why an exec approval request would lift the sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionEscalation {
+    /// The model asked to run the command outside the sandbox.
+    Requested,
+    /// The command failed inside the sandbox and would be retried without it.
+    RetryWithoutSandbox,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
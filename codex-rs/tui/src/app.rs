@@ -4,6 +4,7 @@ use crate::app_event_sender::AppEventSender;
 use crate::chatwidget::ChatWidget;
 use crate::file_search::FileSearchManager;
 use crate::history_cell::HistoryCell;
+use crate::omnara_integration::BridgeManager;
 use crate::pager_overlay::Overlay;
 use crate::resume_picker::ResumeSelection;
 use crate::tui;
@@ -45,6 +46,8 @@ pub(crate) struct App {
     pub(crate) app_event_tx: AppEventSender,
     pub(crate) chat_widget: ChatWidget,
     pub(crate) auth_manager: Arc<AuthManager>,
+    /// Opens one Omnara session per conversation (`None` without an API key).
+    pub(crate) omnara: Option<Arc<BridgeManager>>,
 
     /// Config is stored here so we can recreate ChatWidgets as needed.
     pub(crate) config: Config,
@@ -85,6 +88,7 @@ impl App {
         let conversation_manager = Arc::new(ConversationManager::new(auth_manager.clone()));
 
         let enhanced_keys_supported = tui.enhanced_keys_supported();
+        let omnara = BridgeManager::from_env(&config).map(Arc::new);
 
         let chat_widget = match resume_selection {
             ResumeSelection::StartFresh | ResumeSelection::Exit => {
@@ -96,10 +100,12 @@ impl App {
                     initial_images: initial_images.clone(),
                     enhanced_keys_supported,
                     auth_manager: auth_manager.clone(),
+                    omnara: omnara.clone(),
                 };
                 ChatWidget::new(init, conversation_manager.clone())
             }
             ResumeSelection::Resume(path) => {
+                let last_seen = crate::omnara_integration::last_rollout_timestamp(&path);
                 let resumed = conversation_manager
                     .resume_conversation_from_rollout(
                         config.clone(),
@@ -118,12 +124,15 @@ impl App {
                     initial_images: initial_images.clone(),
                     enhanced_keys_supported,
                     auth_manager: auth_manager.clone(),
+                    omnara: omnara.clone(),
                 };
-                ChatWidget::new_from_existing(
+                let chat_widget = ChatWidget::new_from_existing(
                     init,
                     resumed.conversation,
                     resumed.session_configured,
-                )
+                );
+                chat_widget.replay_omnara_history(last_seen);
+                chat_widget
             }
         };
 
@@ -134,6 +143,7 @@ impl App {
             app_event_tx,
             chat_widget,
             auth_manager: auth_manager.clone(),
+            omnara,
             config,
             active_profile,
             file_search,
@@ -220,6 +230,7 @@ impl App {
                     initial_images: Vec::new(),
                     enhanced_keys_supported: self.enhanced_keys_supported,
                     auth_manager: self.auth_manager.clone(),
+                    omnara: self.omnara.clone(),
                 };
                 self.chat_widget = ChatWidget::new(init, self.server.clone());
                 tui.frame_requester().schedule_frame();
@@ -281,10 +292,29 @@ impl App {
             AppEvent::ResolveApproval { decision } => {
                 let _ = self.chat_widget.apply_external_approval(decision);
             }
+            AppEvent::AnswerQuestion { call_id, answer } => {
+                self.chat_widget.answer_question(call_id, answer, true);
+            }
+            AppEvent::ConfirmRemoteInput { text, reasons } => {
+                self.chat_widget.confirm_remote_input(text, reasons);
+            }
+            AppEvent::ResolveRemoteInput { text, forward } => {
+                self.chat_widget.resolve_remote_input(text, forward);
+            }
+            AppEvent::ConfirmOmnaraRecovery { approvals, unsent } => {
+                self.chat_widget.confirm_omnara_recovery(approvals, unsent);
+            }
+            AppEvent::ResolveOmnaraRecovery { recover } => {
+                self.chat_widget.resolve_omnara_recovery(recover);
+            }
+            AppEvent::ConfirmRemotePatch { id, changes } => {
+                self.chat_widget.confirm_remote_patch(id, changes);
+            }
             AppEvent::ExitRequest => {
-                // End Omnara session and wait up to 2 seconds before exiting.
+                // Flush pending Omnara sends and end the session, waiting up to
+                // 2 seconds before exiting.
                 self.chat_widget
-                    .end_omnara_session_with_timeout(std::time::Duration::from_secs(2))
+                    .shutdown_omnara_with_timeout(std::time::Duration::from_secs(2))
                     .await;
                 return Ok(false);
             }
@@ -323,6 +353,14 @@ impl App {
                     self.config.model_family = family;
                 }
             }
+            AppEvent::UpdateModelProvider(id) => {
+                if let Some(provider) = self.config.model_providers.get(&id) {
+                    self.config.model_provider = provider.clone();
+                    self.config.model_provider_id = id;
+                    self.chat_widget
+                        .set_model_provider(&self.config.model_provider_id, provider.clone());
+                }
+            }
             AppEvent::PersistModelSelection { model, effort } => {
                 let profile = self.active_profile.as_deref();
                 match persist_model_selection(&self.config.codex_home, profile, &model, effort)
@@ -370,6 +408,32 @@ impl App {
             AppEvent::OpenReviewCustomPrompt => {
                 self.chat_widget.show_review_custom_prompt();
             }
+            AppEvent::OmnaraSnapshot(trigger) => {
+                let size = tui.terminal.last_known_screen_size;
+                let lines = self.snapshot_lines(size.width, size.height);
+                self.chat_widget.send_omnara_snapshot(lines, trigger);
+            }
+            AppEvent::OmnaraShareLink {
+                permissions,
+                result,
+            } => {
+                self.chat_widget.show_omnara_share_link(permissions, result);
+            }
+            AppEvent::OmnaraTags(result) => {
+                self.chat_widget.show_omnara_tags(result);
+            }
+            AppEvent::OmnaraHistoryReplay(messages) => {
+                self.chat_widget.show_omnara_history(messages);
+            }
+            AppEvent::OmnaraMessageSeen(seen) => {
+                self.chat_widget.set_omnara_message_seen(seen);
+            }
+            AppEvent::OmnaraOffline(offline) => {
+                self.chat_widget.set_omnara_offline(offline);
+            }
+            AppEvent::OmnaraFork { prompt } => {
+                self.fork_from_omnara(prompt);
+            }
         }
         Ok(true)
     }
@@ -378,6 +442,25 @@ impl App {
         self.chat_widget.token_usage()
     }
 
+    /// History lines as the terminal currently shows them: the last `height`
+    /// rows of the transcript at `width`, including any command still running.
+    fn snapshot_lines(&self, width: u16, height: u16) -> Vec<Line<'static>> {
+        let mut lines: Vec<Line<'static>> = Vec::new();
+        for cell in &self.transcript_cells {
+            if !lines.is_empty() && !cell.is_stream_continuation() {
+                lines.push(Line::from(""));
+            }
+            lines.extend(cell.display_lines(width));
+        }
+        let active = self.chat_widget.active_cell_lines(width);
+        if !active.is_empty() {
+            lines.push(Line::from(""));
+            lines.extend(active);
+        }
+        let skip = lines.len().saturating_sub(usize::from(height));
+        lines.split_off(skip)
+    }
+
     fn on_update_reasoning_effort(&mut self, effort: Option<ReasoningEffortConfig>) {
         self.chat_widget.set_reasoning_effort(effort);
         self.config.model_reasoning_effort = effort;
@@ -480,6 +563,7 @@ mod tests {
             app_event_tx,
             chat_widget,
             auth_manager,
+            omnara: None,
             config,
             active_profile: None,
             file_search,
@@ -573,4 +657,23 @@ mod tests {
         assert_eq!(nth, 1);
         assert_eq!(prefill, "follow-up (edited)");
     }
+
+    #[test]
+    fn snapshot_keeps_only_the_visible_rows() {
+        let mut app = make_test_app();
+        app.transcript_cells = ["one", "two", "three"]
+            .into_iter()
+            .map(|text| {
+                Arc::new(AgentMessageCell::new(vec![Line::from(text)], true))
+                    as Arc<dyn HistoryCell>
+            })
+            .collect();
+
+        let lines = app.snapshot_lines(80, 3);
+
+        assert_eq!(
+            crate::omnara_integration::snapshot_text(&lines),
+            "> two\n\n> three\n"
+        );
+    }
 }
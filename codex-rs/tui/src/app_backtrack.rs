@@ -28,6 +28,9 @@ pub(crate) struct BacktrackState {
     pub(crate) overlay_preview_active: bool,
     /// Pending fork request: (base_id, nth_user_message, prefill).
     pub(crate) pending: Option<(ConversationId, usize, String)>,
+    /// The pending fork came from a dashboard `/fork`: its session is told
+    /// which prompt it left out instead of the composer being prefilled.
+    pub(crate) pending_from_omnara: bool,
 }
 
 impl App {
@@ -102,11 +105,35 @@ impl App {
         nth_user_message: usize,
     ) {
         self.backtrack.pending = Some((base_id, nth_user_message, prefill));
+        self.backtrack.pending_from_omnara = false;
         self.app_event_tx.send(crate::app_event::AppEvent::CodexOp(
             codex_core::protocol::Op::GetPath,
         ));
     }
 
+    /// Fork for a dashboard `/fork` before user prompt `prompt` (1-based),
+    /// the latest when `None`; an out-of-range prompt is refused there.
+    pub(crate) fn fork_from_omnara(&mut self, prompt: Option<usize>) {
+        let count = user_count(&self.transcript_cells);
+        let prompt = prompt.unwrap_or(count);
+        let Some(base_id) = self
+            .chat_widget
+            .conversation_id()
+            .filter(|_| (1..=count).contains(&prompt))
+        else {
+            self.chat_widget.refuse_omnara_fork(prompt, count);
+            return;
+        };
+        let nth_user_message = prompt - 1;
+        let dropped = nth_user_position(&self.transcript_cells, nth_user_message)
+            .and_then(|idx| self.transcript_cells.get(idx))
+            .and_then(|cell| cell.as_any().downcast_ref::<UserHistoryCell>())
+            .map(|c| c.message.clone())
+            .unwrap_or_default();
+        self.request_backtrack(dropped, base_id, nth_user_message);
+        self.backtrack.pending_from_omnara = true;
+    }
+
     /// Open transcript overlay (enters alternate screen and shows full transcript).
     pub(crate) fn open_transcript_overlay(&mut self, tui: &mut tui::Tui) {
         let _ = tui.enter_alt_screen();
@@ -294,15 +321,26 @@ impl App {
         prefill: String,
     ) {
         let cfg = self.chat_widget.config_ref().clone();
+        let from_omnara = std::mem::take(&mut self.backtrack.pending_from_omnara);
         // Perform the fork via a thin wrapper for clarity/testability.
         let result = self
             .perform_fork(ev.path.clone(), nth_user_message, cfg.clone())
             .await;
         match result {
+            Ok(new_conv) if from_omnara => {
+                self.install_forked_conversation(tui, cfg, new_conv, nth_user_message, "");
+                self.chat_widget
+                    .announce_omnara_fork(nth_user_message + 1, &prefill);
+            }
             Ok(new_conv) => {
                 self.install_forked_conversation(tui, cfg, new_conv, nth_user_message, &prefill)
             }
-            Err(e) => tracing::error!("error forking conversation: {e:#}"),
+            Err(e) => {
+                tracing::error!("error forking conversation: {e:#}");
+                if from_omnara {
+                    self.chat_widget.report_omnara_fork_failure(&e.to_string());
+                }
+            }
         }
     }
 
@@ -337,6 +375,7 @@ impl App {
             initial_images: Vec::new(),
             enhanced_keys_supported: self.enhanced_keys_supported,
             auth_manager: self.auth_manager.clone(),
+            omnara: self.omnara.clone(),
         };
         self.chat_widget =
             crate::chatwidget::ChatWidget::new_from_existing(init, conv, session_configured);
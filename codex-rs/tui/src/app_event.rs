@@ -1,10 +1,16 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use codex_core::omnara_client::HistoryMessage;
+use codex_core::omnara_client::ShareLink;
+use codex_core::omnara_client::SharePermissions;
 use codex_core::protocol::ConversationPathResponseEvent;
 use codex_core::protocol::Event;
+use codex_core::protocol::FileChange;
 use codex_file_search::FileMatch;
 
 use crate::history_cell::HistoryCell;
+use codex_omnara::format::SnapshotTrigger;
 
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
@@ -53,6 +59,9 @@ pub(crate) enum AppEvent {
     /// Update the current model slug in the running app and widget.
     UpdateModel(String),
 
+    /// Switch to the model provider with this id in `model_providers`.
+    UpdateModelProvider(String),
+
     /// Persist the selected model and reasoning effort to the appropriate config.
     PersistModelSelection {
         model: String,
@@ -76,7 +85,76 @@ pub(crate) enum AppEvent {
 
     /// Open the custom prompt option from the review popup.
     OpenReviewCustomPrompt,
+
+    /// Capture the visible terminal history and send it to Omnara.
+    OmnaraSnapshot(SnapshotTrigger),
+
+    /// A `/share` link was created, or the reason it could not be.
+    OmnaraShareLink {
+        permissions: SharePermissions,
+        result: Result<ShareLink, String>,
+    },
+
+    /// The session's tags after a `/tag` command, or why it failed.
+    OmnaraTags(Result<Vec<String>, String>),
+
+    /// Dashboard messages exchanged since the resumed session's last local
+    /// event, oldest first.
+    OmnaraHistoryReplay(Vec<HistoryMessage>),
+
+    /// Whether the dashboard has read the last agent message, e.g. a
+    /// pending approval prompt.
+    OmnaraMessageSeen(bool),
+
+    /// Whether Omnara is unreachable, so remote input cannot arrive and
+    /// approvals are local-only.
+    OmnaraOffline(bool),
+
+    /// A dashboard `/fork`: continue in a new conversation forked before
+    /// user prompt `prompt` (1-based), the latest when `None`.
+    OmnaraFork {
+        prompt: Option<usize>,
+    },
+
     ResolveApproval {
         decision: codex_core::protocol::ReviewDecision,
     },
+
+    /// A dashboard reply answered the pending `ask_user` question.
+    AnswerQuestion {
+        call_id: String,
+        answer: String,
+    },
+
+    /// `omnara.workspace_trust` held a dashboard message; ask whether to
+    /// forward it.
+    ConfirmRemoteInput {
+        text: String,
+        reasons: Vec<String>,
+    },
+
+    /// The local user forwarded or discarded a held dashboard message.
+    ResolveRemoteInput {
+        text: String,
+        forward: bool,
+    },
+
+    /// A crashed run of this Omnara session left approvals or notes pending;
+    /// ask whether to recover them.
+    ConfirmOmnaraRecovery {
+        approvals: usize,
+        unsent: usize,
+    },
+
+    /// The local user recovered or discarded what the crashed run left.
+    ResolveOmnaraRecovery {
+        recover: bool,
+    },
+
+    /// A dashboard `/apply-patch` passed its checks; show it for approval.
+    /// The decision is submitted as `Op::PatchApproval` with `id`.
+    ConfirmRemotePatch {
+        id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    },
 }
@@ -56,6 +56,9 @@ const LARGE_PASTE_CHAR_THRESHOLD: usize = 1000;
 pub enum InputResult {
     Submitted(String),
     Command(SlashCommand),
+    /// A command that [takes arguments](SlashCommand::takes_args), with the
+    /// text typed after it.
+    CommandWithArgs(SlashCommand, String),
     None,
 }
 
@@ -416,6 +419,7 @@ impl ChatComposer {
                 ..
             } => {
                 if let Some(sel) = popup.selected_item() {
+                    let args = slash_command_args(self.textarea.text());
                     // Clear textarea so no residual text remains.
                     self.textarea.set_text("");
                     // Capture any needed data from popup before clearing it.
@@ -429,6 +433,9 @@ impl ChatComposer {
                     self.active_popup = ActivePopup::None;
 
                     match sel {
+                        CommandItem::Builtin(cmd) if cmd.takes_args() => {
+                            return (InputResult::CommandWithArgs(cmd, args), true);
+                        }
                         CommandItem::Builtin(cmd) => {
                             return (InputResult::Command(cmd), true);
                         }
@@ -1364,6 +1371,18 @@ impl WidgetRef for ChatComposer {
     }
 }
 
+/// Text following the command token in `/cmd args...`, trimmed.
+fn slash_command_args(text: &str) -> String {
+    let rest = text
+        .trim_start()
+        .strip_prefix('/')
+        .unwrap_or(text)
+        .trim_start();
+    rest.split_once(char::is_whitespace)
+        .map(|(_, args)| args.trim().to_string())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1863,6 +1882,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
+            InputResult::CommandWithArgs(cmd, _) => {
+                panic!("expected plain command dispatch, got /{}", cmd.command())
+            }
             InputResult::None => panic!("expected Command result for '/init'"),
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
@@ -1921,6 +1943,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
+            InputResult::CommandWithArgs(cmd, _) => {
+                panic!("expected plain command dispatch, got /{}", cmd.command())
+            }
             InputResult::None => panic!("expected Command result for '/mention'"),
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
@@ -1928,6 +1953,35 @@ mod tests {
         assert_eq!(composer.textarea.text(), "@");
     }
 
+    #[test]
+    fn slash_note_dispatches_command_with_trailing_text() {
+        use crossterm::event::KeyCode;
+        use crossterm::event::KeyEvent;
+        use crossterm::event::KeyModifiers;
+
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+
+        let chars: Vec<char> = "/note taking over ".chars().collect();
+        type_chars_humanlike(&mut composer, &chars);
+
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(
+            result,
+            InputResult::CommandWithArgs(SlashCommand::Note, "taking over".to_string())
+        );
+        assert!(composer.textarea.is_empty(), "composer should be cleared");
+    }
+
     #[test]
     fn test_multiple_pastes_submission() {
         use crossterm::event::KeyCode;
@@ -13,6 +13,8 @@ use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
 use ratatui::widgets::WidgetRef;
 use std::time::Duration;
 
@@ -67,6 +69,12 @@ pub(crate) struct BottomPane {
     status: Option<StatusIndicatorWidget>,
     /// Queued user messages to show under the status indicator.
     queued_user_messages: Vec<String>,
+    /// Whether the Omnara dashboard has read the last agent message; shown
+    /// in the bottom padding row, even under a modal.
+    remote_seen: bool,
+    /// Whether Omnara is unreachable, so remote control is unavailable;
+    /// shown in the bottom padding row in place of the seen mark.
+    remote_offline: bool,
 }
 
 pub(crate) struct BottomPaneParams {
@@ -99,6 +107,8 @@ impl BottomPane {
             status: None,
             queued_user_messages: Vec::new(),
             esc_backtrack_hint: false,
+            remote_seen: false,
+            remote_offline: false,
         }
     }
 
@@ -350,6 +360,22 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Show or clear the "seen on Omnara" mark.
+    pub(crate) fn set_remote_seen(&mut self, seen: bool) {
+        if self.remote_seen != seen {
+            self.remote_seen = seen;
+            self.request_redraw();
+        }
+    }
+
+    /// Show or clear the "Omnara offline" banner.
+    pub(crate) fn set_remote_offline(&mut self, offline: bool) {
+        if self.remote_offline != offline {
+            self.remote_offline = offline;
+            self.request_redraw();
+        }
+    }
+
     /// Update custom prompts available for the slash popup.
     pub(crate) fn set_custom_prompts(&mut self, prompts: Vec<CustomPrompt>) {
         self.composer.set_custom_prompts(prompts);
@@ -515,6 +541,26 @@ impl WidgetRef for &BottomPane {
             // Render the composer in the remaining area.
             self.composer.render_ref(content, buf);
         }
+
+        let remote_line = if self.remote_offline {
+            Some(Line::from(
+                "Omnara offline — remote control unavailable".red(),
+            ))
+        } else if self.remote_seen {
+            Some(Line::from("✓ seen on Omnara".dim()))
+        } else {
+            None
+        };
+        if let Some(line) = remote_line
+            && area.height > BottomPane::BOTTOM_PAD_LINES + 1
+        {
+            let row = Rect {
+                y: area.bottom() - 1,
+                height: 1,
+                ..area
+            };
+            line.right_aligned().render_ref(row, buf);
+        }
     }
 }
 
@@ -585,6 +631,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn remote_seen_mark_shows_under_approval_modal() {
+        let (tx_raw, _rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let mut pane = BottomPane::new(BottomPaneParams {
+            app_event_tx: tx,
+            frame_requester: FrameRequester::test_dummy(),
+            has_input_focus: true,
+            enhanced_keys_supported: false,
+            placeholder_text: "Ask Codex to do anything".to_string(),
+            disable_paste_burst: false,
+        });
+        pane.push_approval_request(exec_request());
+        pane.set_remote_seen(true);
+
+        let area = Rect::new(0, 0, 60, 12);
+        let mut buf = Buffer::empty(area);
+        (&pane).render_ref(area, &mut buf);
+        let mut last = String::new();
+        for x in 0..area.width {
+            last.push_str(buf[(x, area.height - 1)].symbol());
+        }
+        assert!(
+            last.trim_end().ends_with("✓ seen on Omnara"),
+            "expected seen mark in the padding row: {last:?}"
+        );
+    }
+
+    #[test]
+    fn offline_banner_replaces_the_seen_mark() {
+        let (tx_raw, _rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let mut pane = BottomPane::new(BottomPaneParams {
+            app_event_tx: tx,
+            frame_requester: FrameRequester::test_dummy(),
+            has_input_focus: true,
+            enhanced_keys_supported: false,
+            placeholder_text: "Ask Codex to do anything".to_string(),
+            disable_paste_burst: false,
+        });
+        pane.push_approval_request(exec_request());
+        pane.set_remote_seen(true);
+        pane.set_remote_offline(true);
+
+        let area = Rect::new(0, 0, 60, 12);
+        let last_row = |pane: &BottomPane| {
+            let mut buf = Buffer::empty(area);
+            (&pane).render_ref(area, &mut buf);
+            let mut last = String::new();
+            for x in 0..area.width {
+                last.push_str(buf[(x, area.height - 1)].symbol());
+            }
+            last.trim_end().to_string()
+        };
+        assert!(
+            last_row(&pane).ends_with("Omnara offline — remote control unavailable"),
+            "expected the offline banner in the padding row"
+        );
+        pane.set_remote_offline(false);
+        assert!(last_row(&pane).ends_with("✓ seen on Omnara"));
+    }
+
     #[test]
     fn composer_shown_after_denied_while_task_running() {
         let (tx_raw, rx) = unbounded_channel::<AppEvent>();
@@ -3,10 +3,19 @@ use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use chrono::DateTime;
+use chrono::Utc;
+use codex_core::ModelProviderInfo;
 use codex_core::config::Config;
 use codex_core::config_types::Notifications;
 use codex_core::git_info::current_branch_name;
 use codex_core::git_info::local_git_branches;
+use codex_core::omnara_client::HistoryMessage;
+use codex_core::omnara_client::MessageSender;
+use codex_core::omnara_client::ShareLink;
+use codex_core::omnara_client::SharePermissions;
+use codex_core::omnara_diagnostics::diagnostic_for_event;
+use codex_core::omnara_tags::parse_tag_command;
 use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
@@ -14,6 +23,7 @@ use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::AskUserRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
@@ -22,10 +32,12 @@ use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExitedReviewModeEvent;
+use codex_core::protocol::FileChange;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::McpToolCallApprovalRequestEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
@@ -52,6 +64,7 @@ use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
+use ratatui::text::Line;
 use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
 use tokio::sync::mpsc::UnboundedSender;
@@ -69,6 +82,7 @@ use crate::bottom_pane::custom_prompt_view::CustomPromptView;
 use crate::bottom_pane::popup_consts::STANDARD_POPUP_HINT_LINE;
 use crate::clipboard_paste::paste_image_to_temp_png;
 use crate::diff_render::display_path_for;
+use crate::exec_command::strip_bash_lc_and_escape;
 use crate::get_git_diff::get_git_diff;
 use crate::history_cell;
 use crate::history_cell::AgentMessageCell;
@@ -89,7 +103,10 @@ use self::agent::spawn_agent;
 use self::agent::spawn_agent_from_existing;
 mod session_header;
 use self::session_header::SessionHeader;
+use crate::omnara_integration::BridgeManager;
+use crate::omnara_integration::LinkedSession;
 use crate::omnara_integration::OmnaraBridge;
+use crate::omnara_integration::REMOTE_PATCH_ID_PREFIX;
 use crate::streaming::controller::AppEventHistorySink;
 use crate::streaming::controller::StreamController;
 use std::path::Path;
@@ -100,6 +117,8 @@ use codex_common::model_presets::ModelPreset;
 use codex_common::model_presets::builtin_model_presets;
 use codex_core::AuthManager;
 use codex_core::ConversationManager;
+use codex_core::omnara_i18n::fill;
+use codex_core::omnara_i18n::strings;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -109,6 +128,8 @@ use codex_git_tooling::GhostCommit;
 use codex_git_tooling::GitToolingError;
 use codex_git_tooling::create_ghost_commit;
 use codex_git_tooling::restore_ghost_commit;
+use codex_omnara::format::ExecNoteContext;
+use codex_omnara::format::SnapshotTrigger;
 
 const MAX_TRACKED_GHOST_COMMITS: usize = 20;
 use tracing::{debug, info};
@@ -118,6 +139,7 @@ use tracing::{debug, info};
 struct RunningCommand {
     command: Vec<String>,
     parsed_cmd: Vec<ParsedCommand>,
+    cwd: PathBuf,
 }
 
 const RATE_LIMIT_WARNING_THRESHOLDS: [f64; 3] = [75.0, 90.0, 95.0];
@@ -179,6 +201,8 @@ pub(crate) struct ChatWidgetInit {
     pub(crate) initial_images: Vec<PathBuf>,
     pub(crate) enhanced_keys_supported: bool,
     pub(crate) auth_manager: Arc<AuthManager>,
+    /// Opens this conversation's Omnara session, when Omnara is enabled.
+    pub(crate) omnara: Option<Arc<BridgeManager>>,
 }
 
 pub(crate) struct ChatWidget {
@@ -221,6 +245,11 @@ pub(crate) struct ChatWidget {
     ghost_snapshots_disabled: bool,
     // Optional Omnara bridge (enabled when OMNARA_* env vars are present)
     omnara: Option<OmnaraBridge>,
+    // Opens the Omnara sessions of this and later conversations
+    omnara_sessions: Option<Arc<BridgeManager>>,
+    // Call id of an `ask_user` question awaiting an answer; the next
+    // submission answers it instead of being queued.
+    pending_question: Option<String>,
 }
 
 struct UserMessage {
@@ -253,12 +282,18 @@ impl ChatWidget {
         }
     }
 
-    /// End Omnara session with a timeout; best-effort to wait up to `dur`.
-    pub(crate) async fn end_omnara_session_with_timeout(&self, dur: std::time::Duration) {
-        if let Some(omnara) = self.omnara.as_ref() {
-            tracing::info!("ChatWidget.end_omnara_session_with_timeout: ending Omnara session");
-            let handle = omnara.on_session_end();
-            let _ = tokio::time::timeout(dur, handle).await;
+    /// Flush in-flight Omnara sends and end the session, along with the
+    /// sessions of earlier conversations; best-effort to wait up to `dur`.
+    pub(crate) async fn shutdown_omnara_with_timeout(&mut self, dur: std::time::Duration) {
+        let deadline = std::time::Instant::now() + dur;
+        if let Some(omnara) = self.omnara.as_mut() {
+            tracing::info!("ChatWidget.shutdown_omnara_with_timeout: ending Omnara session");
+            omnara.shutdown(deadline).await;
+        }
+        if let Some(sessions) = self.omnara_sessions.as_ref() {
+            sessions
+                .end_other_sessions(self.omnara.as_ref(), deadline)
+                .await;
         }
     }
     // --- Small event handlers ---
@@ -285,6 +320,14 @@ impl ChatWidget {
         // Announce session to Omnara and start polling immediately.
         if let Some(omnara) = self.omnara.as_mut() {
             omnara.on_session_start();
+            if let Some(workspace) = omnara.workspace() {
+                let message = format!("Omnara workspace: {}", workspace.name);
+                let hint = Some(format!("({})", workspace.source));
+                self.add_to_history(history_cell::new_info_event(message, hint));
+            }
+            // Questions reach the dashboard through the bridge, so the model
+            // may ask them.
+            self.submit_op(Op::RegisterQuestionFrontend);
         }
         if let Some(user_message) = self.initial_user_message.take() {
             self.submit_user_message(user_message);
@@ -360,12 +403,15 @@ impl ChatWidget {
 
     // Raw reasoning uses the same flow as summarized reasoning
 
-    fn on_task_started(&mut self) {
+    fn on_task_started(&mut self, traceparent: Option<&str>) {
         self.bottom_pane.clear_ctrl_c_quit_hint();
         self.bottom_pane.set_task_running(true);
         self.full_reasoning_buffer.clear();
         self.reasoning_buffer.clear();
         self.request_redraw();
+        if let Some(omnara) = self.omnara.as_mut() {
+            omnara.on_task_started(traceparent);
+        }
     }
 
     fn on_task_complete(&mut self, last_agent_message: Option<String>) {
@@ -379,6 +425,7 @@ impl ChatWidget {
         // Mark task stopped and request redraw now that all content is in history.
         self.bottom_pane.set_task_running(false);
         self.running_commands.clear();
+        self.pending_question = None;
         self.request_redraw();
 
         // If there is a queued user message, send exactly one now to begin the next turn.
@@ -424,12 +471,17 @@ impl ChatWidget {
         self.bottom_pane.set_task_running(false);
         self.running_commands.clear();
         self.stream_controller = None;
+        self.pending_question = None;
     }
 
     fn on_error(&mut self, message: String) {
         self.finalize_turn();
         self.add_to_history(history_cell::new_error_event(message));
         self.request_redraw();
+        if self.omnara.is_some() && self.config.omnara.snapshot_on_error {
+            self.app_event_tx
+                .send(AppEvent::OmnaraSnapshot(SnapshotTrigger::Error));
+        }
 
         // After an error ends the turn, try sending the next queued input.
         self.maybe_send_next_queued_input();
@@ -471,6 +523,9 @@ impl ChatWidget {
     }
 
     fn on_plan_update(&mut self, update: codex_core::plan_tool::UpdatePlanArgs) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_plan_update(&update);
+        }
         self.add_to_history(history_cell::new_plan_update(update));
     }
 
@@ -492,6 +547,15 @@ impl ChatWidget {
         );
     }
 
+    fn on_mcp_approval_request(&mut self, id: String, ev: McpToolCallApprovalRequestEvent) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_mcp_approval(id, ev),
+            |s| s.handle_mcp_approval_now(id2, ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -500,14 +564,20 @@ impl ChatWidget {
 
     fn on_exec_command_output_delta(
         &mut self,
-        _ev: codex_core::protocol::ExecCommandOutputDeltaEvent,
+        ev: codex_core::protocol::ExecCommandOutputDeltaEvent,
     ) {
-        // TODO: Handle streaming exec output if/when implemented
+        // TODO: Render streaming exec output in the TUI if/when implemented
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_exec_output(&ev.call_id, &ev.chunk);
+        }
     }
 
     fn on_patch_apply_begin(&mut self, event: PatchApplyBeginEvent) {
-        // Prepare details for Omnara before moving changes into the history cell.
-        let changes_for_omnara = event.changes.clone();
+        // Mirror the patch to Omnara before moving changes into the history cell.
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_paths_touched(event.changes.keys().cloned().collect());
+            omnara.on_patch_apply(event.changes.clone());
+        }
 
         self.add_to_history(history_cell::new_patch_event(
             PatchEventType::ApplyBegin {
@@ -516,12 +586,6 @@ impl ChatWidget {
             event.changes,
             &self.config.cwd,
         ));
-
-        // Mirror a patch summary to Omnara (non-approval note), using centralized formatting.
-        if let Some(omnara) = self.omnara.as_ref() {
-            let msg = crate::omnara_format::format_patch_note(&changes_for_omnara);
-            omnara.send_note(msg);
-        }
     }
 
     fn on_patch_apply_end(&mut self, event: codex_core::protocol::PatchApplyEndEvent) {
@@ -553,10 +617,10 @@ impl ChatWidget {
 
     fn on_web_search_end(&mut self, ev: WebSearchEndEvent) {
         self.flush_answer_stream_with_separator();
-        self.add_to_history(history_cell::new_web_search_call(format!(
-            "Searched: {}",
-            ev.query
-        )));
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_web_search_end(&ev);
+        }
+        self.add_to_history(history_cell::new_web_search_call(ev.summary()));
     }
 
     fn on_get_history_entry_response(
@@ -648,10 +712,17 @@ impl ChatWidget {
     }
 
     pub(crate) fn handle_exec_end_now(&mut self, ev: ExecCommandEndEvent) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_exec_finished(&ev.call_id);
+        }
         let running = self.running_commands.remove(&ev.call_id);
-        let (command, parsed) = match running {
-            Some(rc) => (rc.command, rc.parsed_cmd),
-            None => (vec![ev.call_id.clone()], Vec::new()),
+        let (command, parsed, cwd) = match running {
+            Some(rc) => (rc.command, rc.parsed_cmd, rc.cwd),
+            None => (
+                vec![ev.call_id.clone()],
+                Vec::new(),
+                self.config.cwd.clone(),
+            ),
         };
         let cmd_for_note = command.clone();
 
@@ -672,12 +743,21 @@ impl ChatWidget {
                 formatted_output: ev.formatted_output.clone(),
             };
             cell.complete_call(&ev.call_id, output.clone(), ev.duration);
-            // When the last running command finishes, mirror a concise note to Omnara.
+            // When the last running command finishes, mirror it to Omnara.
             if self.running_commands.is_empty()
                 && let Some(omnara) = self.omnara.as_ref()
             {
-                let msg = crate::omnara_format::format_exec_note(&cmd_for_note, &output);
-                omnara.send_note(msg);
+                omnara.on_exec_end(
+                    &ev.call_id,
+                    cmd_for_note,
+                    output.into(),
+                    ExecNoteContext {
+                        cwd,
+                        duration: ev.duration,
+                        sandbox: ev.sandbox,
+                        ansi: omnara.exec_output_ansi(),
+                    },
+                );
             }
             if cell.should_flush() {
                 self.flush_active_exec_cell();
@@ -700,8 +780,7 @@ impl ChatWidget {
         self.flush_answer_stream_with_separator();
         // Emit the proposed command into history (like proposed patches)
         self.add_to_history(history_cell::new_proposed_command(&ev.command));
-        let command = shlex::try_join(ev.command.iter().map(String::as_str))
-            .unwrap_or_else(|_| ev.command.join(" "));
+        let command = strip_bash_lc_and_escape(&ev.command);
         self.notify(Notification::ExecApprovalRequested { command });
 
         // Always show local modal
@@ -713,7 +792,22 @@ impl ChatWidget {
         self.bottom_pane.push_approval_request(request);
         // Also send remote approval request if Omnara is configured.
         if let Some(omnara) = self.omnara.as_mut() {
-            omnara.send_exec_approval_request(id, ev.command.clone(), ev.reason);
+            match ev.escalation {
+                Some(escalation) => omnara.send_escalation_approval_request(
+                    id,
+                    ev.command.clone(),
+                    ev.cwd.clone(),
+                    ev.reason,
+                    escalation,
+                ),
+                None => omnara.send_exec_approval_request(
+                    id,
+                    ev.command.clone(),
+                    ev.cwd.clone(),
+                    ev.reason,
+                    ev.closest_approved,
+                ),
+            }
         }
         self.request_redraw();
     }
@@ -739,20 +833,11 @@ impl ChatWidget {
         self.bottom_pane.push_approval_request(request);
         // Also send to Omnara if configured
         if let Some(omnara) = self.omnara.as_mut() {
-            let file_count = ev.changes.len();
-            let (details, added, removed) = crate::omnara_format::format_patch_details(&ev.changes);
             omnara.send_patch_approval_request(
                 id,
-                file_count,
-                added,
-                removed,
+                ev.changes.clone(),
                 ev.reason.clone(),
                 ev.grant_root.clone(),
-                if details.is_empty() {
-                    None
-                } else {
-                    Some(details)
-                },
             );
         }
         self.request_redraw();
@@ -762,13 +847,40 @@ impl ChatWidget {
         });
     }
 
+    pub(crate) fn handle_mcp_approval_now(
+        &mut self,
+        id: String,
+        ev: McpToolCallApprovalRequestEvent,
+    ) {
+        self.flush_answer_stream_with_separator();
+        let command = format!("{}.{}", ev.invocation.server, ev.invocation.tool);
+        self.notify(Notification::ExecApprovalRequested { command });
+
+        // Always show local modal
+        let request = ApprovalRequest::McpToolCall {
+            id: id.clone(),
+            invocation: ev.invocation.clone(),
+        };
+        self.bottom_pane.push_approval_request(request);
+        // Also send remote approval request if Omnara is configured.
+        if let Some(omnara) = self.omnara.as_mut() {
+            omnara.send_mcp_approval_request(id, &ev.invocation);
+        }
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_exec_begin_now(&mut self, ev: ExecCommandBeginEvent) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_paths_touched(vec![ev.cwd.clone()]);
+            omnara.on_exec_begin(ev.call_id.clone(), ev.command.clone());
+        }
         // Ensure the status indicator is visible while the command runs.
         self.running_commands.insert(
             ev.call_id.clone(),
             RunningCommand {
                 command: ev.command.clone(),
                 parsed_cmd: ev.parsed_cmd.clone(),
+                cwd: ev.cwd.clone(),
             },
         );
         if let Some(exec) = &self.active_exec_cell {
@@ -803,8 +915,7 @@ impl ChatWidget {
         self.flush_answer_stream_with_separator();
         let cell = history_cell::new_active_mcp_tool_call(ev.invocation.clone());
         if let Some(omnara) = self.omnara.as_ref() {
-            let msg = crate::omnara_format::format_mcp_begin_note(&ev.invocation);
-            omnara.send_note(msg);
+            omnara.on_mcp_begin(&ev.call_id, &ev.invocation);
         }
         self.add_to_history(cell);
     }
@@ -812,9 +923,7 @@ impl ChatWidget {
         self.flush_answer_stream_with_separator();
         let ok = ev.is_success();
         if let Some(omnara) = self.omnara.as_ref() {
-            let msg =
-                crate::omnara_format::format_mcp_end_note(&ev.invocation, &ev.result, ev.duration);
-            omnara.send_note(msg);
+            omnara.on_mcp_end(&ev.call_id, &ev.invocation, &ev.result, ev.duration);
         }
         let cell = history_cell::new_completed_mcp_tool_call(
             80,
@@ -859,14 +968,19 @@ impl ChatWidget {
             initial_images,
             enhanced_keys_supported,
             auth_manager,
+            omnara: omnara_sessions,
         } = common;
         let mut rng = rand::rng();
         let placeholder = EXAMPLE_PROMPTS[rng.random_range(0..EXAMPLE_PROMPTS.len())].to_string();
         let codex_op_tx = spawn_agent(config.clone(), app_event_tx.clone(), conversation_manager);
-        let omnara = crate::omnara_integration::OmnaraBridge::from_env(
-            app_event_tx.clone(),
-            codex_op_tx.clone(),
-        );
+        let omnara = omnara_sessions.as_ref().map(|sessions| {
+            sessions.open(
+                LinkedSession::NewSession,
+                &config,
+                app_event_tx.clone(),
+                codex_op_tx.clone(),
+            )
+        });
 
         Self {
             app_event_tx: app_event_tx.clone(),
@@ -905,7 +1019,9 @@ impl ChatWidget {
             is_review_mode: false,
             ghost_snapshots: Vec::new(),
             ghost_snapshots_disabled: true,
+            pending_question: None,
             omnara,
+            omnara_sessions,
         }
     }
 
@@ -923,16 +1039,21 @@ impl ChatWidget {
             initial_images,
             enhanced_keys_supported,
             auth_manager,
+            omnara: omnara_sessions,
         } = common;
         let mut rng = rand::rng();
         let placeholder = EXAMPLE_PROMPTS[rng.random_range(0..EXAMPLE_PROMPTS.len())].to_string();
 
         let codex_op_tx =
             spawn_agent_from_existing(conversation, session_configured, app_event_tx.clone());
-        let omnara = crate::omnara_integration::OmnaraBridge::from_env(
-            app_event_tx.clone(),
-            codex_op_tx.clone(),
-        );
+        let omnara = omnara_sessions.as_ref().map(|sessions| {
+            sessions.open(
+                LinkedSession::Fork,
+                &config,
+                app_event_tx.clone(),
+                codex_op_tx.clone(),
+            )
+        });
 
         Self {
             app_event_tx: app_event_tx.clone(),
@@ -971,7 +1092,9 @@ impl ChatWidget {
             is_review_mode: false,
             ghost_snapshots: Vec::new(),
             ghost_snapshots_disabled: true,
+            pending_question: None,
             omnara,
+            omnara_sessions,
         }
     }
 
@@ -1007,6 +1130,9 @@ impl ChatWidget {
             }
             other if other.kind == KeyEventKind::Press => {
                 self.bottom_pane.clear_ctrl_c_quit_hint();
+                if let Some(omnara) = self.omnara.as_ref() {
+                    omnara.note_local_activity();
+                }
             }
             _ => {}
         }
@@ -1033,7 +1159,9 @@ impl ChatWidget {
                             text,
                             image_paths: self.bottom_pane.take_recent_submission_images(),
                         };
-                        if self.bottom_pane.is_task_running() {
+                        if let Some(call_id) = self.pending_question.take() {
+                            self.answer_question(call_id, user_message.text, false);
+                        } else if self.bottom_pane.is_task_running() {
                             self.queued_user_messages.push_back(user_message);
                             self.refresh_queued_user_messages();
                         } else {
@@ -1043,6 +1171,9 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::CommandWithArgs(cmd, args) => {
+                        self.dispatch_command_with_args(cmd, args);
+                    }
                     InputResult::None => {}
                 }
             }
@@ -1064,6 +1195,235 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    /// Lines of the in-progress exec cell, which is not yet part of the
+    /// transcript.
+    pub(crate) fn active_cell_lines(&self, width: u16) -> Vec<Line<'static>> {
+        self.active_exec_cell
+            .as_ref()
+            .map(|cell| cell.display_lines(width))
+            .unwrap_or_default()
+    }
+
+    /// Upload a terminal snapshot to the Omnara session.
+    pub(crate) fn send_omnara_snapshot(
+        &mut self,
+        lines: Vec<Line<'static>>,
+        trigger: SnapshotTrigger,
+    ) {
+        let Some(omnara) = self.omnara.as_ref() else {
+            return;
+        };
+        let text = crate::omnara_integration::snapshot_text(&lines);
+        let attachment = codex_omnara::format::snapshot_attachment(&text);
+        omnara.send_note_with_attachment(Some(attachment), move |uploaded| {
+            codex_omnara::format::format_snapshot_note(&text, trigger, uploaded)
+        });
+        if trigger == SnapshotTrigger::Requested {
+            self.add_to_history(history_cell::new_info_event(
+                format!("Snapshot sent to Omnara ({} lines)", lines.len()),
+                None,
+            ));
+            self.request_redraw();
+        }
+    }
+
+    fn dispatch_command_with_args(&mut self, cmd: SlashCommand, args: String) {
+        match cmd {
+            SlashCommand::Note => self.add_omnara_note(args),
+            SlashCommand::Share => self.share_omnara_session(&args),
+            SlashCommand::Tag => self.tag_omnara_session(&args),
+            _ => self.dispatch_command(cmd),
+        }
+    }
+
+    /// Ask Omnara for a `/share [read-only|approve-only]` link; it is shown
+    /// by [`Self::show_omnara_share_link`] once created.
+    fn share_omnara_session(&mut self, args: &str) {
+        let permissions = match args.trim() {
+            "" | "read-only" => SharePermissions::ReadOnly,
+            "approve-only" => SharePermissions::ApproveOnly,
+            _ => {
+                self.add_to_history(history_cell::new_error_event(
+                    "Usage: /share [read-only|approve-only]".to_string(),
+                ));
+                self.request_redraw();
+                return;
+            }
+        };
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.create_share_link(permissions);
+        } else {
+            self.add_to_history(history_cell::new_error_event(
+                "'/share' requires an Omnara session (run `codex omnara login` or set OMNARA_API_KEY).".to_string(),
+            ));
+            self.request_redraw();
+        }
+    }
+
+    /// Change or list the Omnara session's tags with `/tag`; the result is
+    /// shown by [`Self::show_omnara_tags`].
+    fn tag_omnara_session(&mut self, args: &str) {
+        let command = match parse_tag_command(args) {
+            Ok(command) => command,
+            Err(e) => {
+                self.add_to_history(history_cell::new_error_event(e.to_string()));
+                self.request_redraw();
+                return;
+            }
+        };
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.tag(command);
+        } else {
+            self.add_to_history(history_cell::new_error_event(
+                "'/tag' requires an Omnara session (run `codex omnara login` or set OMNARA_API_KEY).".to_string(),
+            ));
+            self.request_redraw();
+        }
+    }
+
+    /// Show the session's tags after a `/tag` command.
+    /// Show or clear the "seen on Omnara" mark under the bottom pane.
+    pub(crate) fn set_omnara_message_seen(&mut self, seen: bool) {
+        self.bottom_pane.set_remote_seen(seen);
+    }
+
+    /// Show or clear the "Omnara offline" banner under the bottom pane.
+    pub(crate) fn set_omnara_offline(&mut self, offline: bool) {
+        self.bottom_pane.set_remote_offline(offline);
+    }
+
+    pub(crate) fn show_omnara_tags(&mut self, result: Result<Vec<String>, String>) {
+        let cell = match result {
+            Ok(tags) if tags.is_empty() => {
+                history_cell::new_info_event("The Omnara session has no tags".to_string(), None)
+            }
+            Ok(tags) => history_cell::new_info_event(
+                format!("Omnara session tags: {}", tags.join(", ")),
+                None,
+            ),
+            Err(err) => history_cell::new_error_event(format!(
+                "Could not change the Omnara session's tags: {err}"
+            )),
+        };
+        self.add_to_history(cell);
+        self.request_redraw();
+    }
+
+    /// Show a created `/share` link in history so it can be copied.
+    pub(crate) fn show_omnara_share_link(
+        &mut self,
+        permissions: SharePermissions,
+        result: Result<ShareLink, String>,
+    ) {
+        let cell = match result {
+            Ok(link) => {
+                let access = match permissions {
+                    SharePermissions::ReadOnly => "Read-only",
+                    SharePermissions::ApproveOnly => "Approve-only",
+                };
+                history_cell::new_info_event(
+                    format!("{access} Omnara link: {}", link.url),
+                    link.expires_at.map(|at| format!("(expires {at})")),
+                )
+            }
+            Err(err) => history_cell::new_error_event(format!(
+                "Could not create an Omnara share link: {err}"
+            )),
+        };
+        self.add_to_history(cell);
+        self.request_redraw();
+    }
+
+    /// Tell the dashboard why its `/fork` before prompt `prompt` was
+    /// refused: the conversation has `count` prompts.
+    pub(crate) fn refuse_omnara_fork(&self, prompt: usize, count: usize) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            let text = fill(
+                strings(self.config.omnara.locale).fork_out_of_range,
+                &[("prompt", &prompt), ("count", &count)],
+            );
+            omnara.send_note(format!("⚠️ {text}"));
+        }
+    }
+
+    /// Tell the dashboard its `/fork` failed with `error`.
+    pub(crate) fn report_omnara_fork_failure(&self, error: &str) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            let text = fill(
+                strings(self.config.omnara.locale).fork_failed,
+                &[("error", &error)],
+            );
+            omnara.send_note(format!("⚠️ {text}"));
+        }
+    }
+
+    /// Open the session of a conversation forked from the dashboard with
+    /// the prompt it left out, `dropped`, the `prompt`th of the original.
+    pub(crate) fn announce_omnara_fork(&mut self, prompt: usize, dropped: &str) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.send_note(codex_omnara::format::format_fork_note(
+                prompt,
+                dropped,
+                self.config.omnara.locale,
+            ));
+        }
+        self.add_to_history(history_cell::new_info_event(
+            format!("Forked from Omnara before prompt {prompt}"),
+            None,
+        ));
+        self.request_redraw();
+    }
+
+    /// Ask Omnara for what was exchanged on the dashboard after `since`, the
+    /// last event of the resumed rollout.
+    pub(crate) fn replay_omnara_history(&self, since: Option<DateTime<Utc>>) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.replay_history(since);
+        }
+    }
+
+    /// Render dashboard exchanges from while this terminal was away: user
+    /// messages as prompts, agent messages as agent output.
+    pub(crate) fn show_omnara_history(&mut self, messages: Vec<HistoryMessage>) {
+        self.add_to_history(history_cell::new_info_event(
+            format!(
+                "Replaying {} message(s) exchanged on Omnara while this terminal was away",
+                messages.len()
+            ),
+            None,
+        ));
+        for message in messages {
+            match message.sender {
+                MessageSender::User => {
+                    self.add_to_history(history_cell::new_user_prompt(message.content));
+                }
+                MessageSender::Agent => {
+                    let mut lines: Vec<Line<'static>> = Vec::new();
+                    append_markdown(&message.content, &mut lines, &self.config);
+                    self.add_to_history(AgentMessageCell::new(lines, true));
+                }
+            }
+        }
+        self.request_redraw();
+    }
+
+    /// Post `/note` text to the Omnara session timeline without involving
+    /// the model.
+    fn add_omnara_note(&mut self, text: String) {
+        let cell = if text.is_empty() {
+            history_cell::new_error_event("Usage: /note <text>".to_string())
+        } else if let Some(omnara) = self.omnara.as_ref() {
+            omnara.send_note(codex_omnara::format::format_local_note(&text));
+            history_cell::new_info_event(format!("Note sent to Omnara: {text}"), None)
+        } else {
+            history_cell::new_error_event(
+                "'/note' requires an Omnara session (run `codex omnara login` or set OMNARA_API_KEY).".to_string(),
+            )
+        };
+        self.add_to_history(cell);
+        self.request_redraw();
+    }
+
     fn dispatch_command(&mut self, cmd: SlashCommand) {
         if !cmd.available_during_task() && self.bottom_pane.is_task_running() {
             let message = format!(
@@ -1133,6 +1493,26 @@ impl ChatWidget {
             SlashCommand::Mcp => {
                 self.add_mcp_output();
             }
+            SlashCommand::Note => {
+                self.add_omnara_note(String::new());
+            }
+            SlashCommand::Share => {
+                self.share_omnara_session("");
+            }
+            SlashCommand::Tag => {
+                self.tag_omnara_session("");
+            }
+            SlashCommand::Snapshot => {
+                if self.omnara.is_some() {
+                    self.app_event_tx
+                        .send(AppEvent::OmnaraSnapshot(SnapshotTrigger::Requested));
+                } else {
+                    self.add_to_history(history_cell::new_error_event(
+                        "'/snapshot' requires an Omnara session (run `codex omnara login` or set OMNARA_API_KEY).".to_string(),
+                    ));
+                    self.request_redraw();
+                }
+            }
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => {
                 use codex_core::protocol::EventMsg;
@@ -1268,6 +1648,184 @@ impl ChatWidget {
         }
     }
 
+    /// Show an `ask_user` question and mirror it to Omnara; the next
+    /// submission (or a dashboard reply) answers it.
+    fn on_ask_user_request(&mut self, ev: AskUserRequestEvent) {
+        self.flush_answer_stream_with_separator();
+        let hint = (!ev.options.is_empty()).then(|| {
+            let options: Vec<String> = ev
+                .options
+                .iter()
+                .enumerate()
+                .map(|(i, option)| format!("{}. {option}", i + 1))
+                .collect();
+            format!("({}; or type an answer)", options.join(", "))
+        });
+        self.add_to_history(history_cell::new_info_event(
+            format!("Question from the agent: {}", ev.question),
+            hint,
+        ));
+        self.pending_question = Some(ev.call_id.clone());
+        if let Some(omnara) = self.omnara.as_mut() {
+            omnara.send_question(ev.call_id, &ev.question, &ev.options);
+        }
+        self.request_redraw();
+    }
+
+    /// Answer the pending `ask_user` question, typed locally or relayed from
+    /// the dashboard.
+    pub(crate) fn answer_question(&mut self, call_id: String, answer: String, remote: bool) {
+        if self.pending_question.as_deref() == Some(call_id.as_str()) {
+            self.pending_question = None;
+        }
+        self.codex_op_tx
+            .send(Op::AskUserAnswer {
+                id: call_id,
+                answer: answer.clone(),
+            })
+            .unwrap_or_else(|e| {
+                tracing::error!("failed to send AskUserAnswer op: {e}");
+            });
+        self.add_to_history(history_cell::new_user_prompt(answer.clone()));
+        if !remote && let Some(omnara) = self.omnara.as_ref() {
+            omnara.cancel_polling();
+            omnara.on_local_user_message(answer);
+        }
+        self.request_redraw();
+    }
+
+    /// Ask whether to forward a dashboard message held by
+    /// `omnara.workspace_trust`. The message is shown in full in history; a
+    /// popup offers to forward or discard it.
+    pub(crate) fn confirm_remote_input(&mut self, text: String, reasons: Vec<String>) {
+        self.add_to_history(history_cell::new_held_remote_message(&text, &reasons));
+        let resolve = |forward: bool| -> SelectionAction {
+            let text = text.clone();
+            Box::new(move |tx: &AppEventSender| {
+                tx.send(AppEvent::ResolveRemoteInput {
+                    text: text.clone(),
+                    forward,
+                });
+            })
+        };
+        let items = vec![
+            SelectionItem {
+                name: "Forward to Codex".to_string(),
+                description: Some("Send the message as if it was typed here".to_string()),
+                is_current: false,
+                actions: vec![resolve(true)],
+                dismiss_on_select: true,
+                search_value: None,
+            },
+            SelectionItem {
+                name: "Discard".to_string(),
+                description: Some("Drop the message and tell the dashboard".to_string()),
+                is_current: false,
+                actions: vec![resolve(false)],
+                dismiss_on_select: true,
+                search_value: None,
+            },
+        ];
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: "Forward message from Omnara?".to_string(),
+            subtitle: Some(reasons.join("; ")),
+            footer_hint: Some(STANDARD_POPUP_HINT_LINE.to_string()),
+            items,
+            ..Default::default()
+        });
+        self.request_redraw();
+    }
+
+    /// Hand the local decision on a held dashboard message to the bridge.
+    pub(crate) fn resolve_remote_input(&mut self, text: String, forward: bool) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.resolve_held_input(text, forward);
+        }
+    }
+
+    /// Ask whether to recover what a crashed run of the Omnara session left
+    /// pending.
+    pub(crate) fn confirm_omnara_recovery(&mut self, approvals: usize, unsent: usize) {
+        let plural = |count: usize, noun: &str| match count {
+            1 => format!("1 {noun}"),
+            n => format!("{n} {noun}s"),
+        };
+        let mut left = Vec::new();
+        if approvals > 0 {
+            left.push(plural(approvals, "unanswered approval"));
+        }
+        if unsent > 0 {
+            left.push(plural(unsent, "unsent note"));
+        }
+        let resolve = |recover: bool| -> SelectionAction {
+            Box::new(move |tx: &AppEventSender| {
+                tx.send(AppEvent::ResolveOmnaraRecovery { recover });
+            })
+        };
+        let items = vec![
+            SelectionItem {
+                name: "Recover".to_string(),
+                description: Some(
+                    "Send the notes again and close the approvals on the dashboard".to_string(),
+                ),
+                is_current: false,
+                actions: vec![resolve(true)],
+                dismiss_on_select: true,
+                search_value: None,
+            },
+            SelectionItem {
+                name: "Discard".to_string(),
+                description: Some("Leave the dashboard as it is".to_string()),
+                is_current: false,
+                actions: vec![resolve(false)],
+                dismiss_on_select: true,
+                search_value: None,
+            },
+        ];
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: "Recover the interrupted Omnara session?".to_string(),
+            subtitle: Some(format!("The last run left {}", left.join(" and "))),
+            footer_hint: Some(STANDARD_POPUP_HINT_LINE.to_string()),
+            items,
+            ..Default::default()
+        });
+        self.request_redraw();
+    }
+
+    /// Hand the local decision on the Omnara recovery to the bridge.
+    pub(crate) fn resolve_omnara_recovery(&mut self, recover: bool) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.resolve_recovery(recover);
+        }
+    }
+
+    /// Show a patch sent from the dashboard with `/apply-patch` in the usual
+    /// patch approval modal. The decision goes back to the bridge, which
+    /// applies the patch (see [`Self::submit_op`]).
+    pub(crate) fn confirm_remote_patch(
+        &mut self,
+        id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    ) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(history_cell::new_patch_event(
+            PatchEventType::ApprovalRequest,
+            changes.clone(),
+            &self.config.cwd,
+        ));
+        self.bottom_pane
+            .push_approval_request(ApprovalRequest::ApplyPatch {
+                id,
+                reason: Some("Patch sent from the Omnara dashboard".to_string()),
+                grant_root: None,
+            });
+        self.request_redraw();
+        self.notify(Notification::EditApprovalRequested {
+            cwd: self.config.cwd.clone(),
+            changes: changes.into_keys().collect(),
+        });
+    }
+
     fn capture_ghost_snapshot(&mut self) {
         if self.ghost_snapshots_disabled {
             return;
@@ -1354,6 +1912,13 @@ impl ChatWidget {
             }
         }
 
+        if !from_replay && let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_session_event(&msg);
+            if let Some(diagnostic) = diagnostic_for_event(&msg) {
+                omnara.on_diagnostic(diagnostic);
+            }
+        }
+
         match msg {
             EventMsg::SessionConfigured(e) => self.on_session_configured(e),
             EventMsg::AgentMessage(AgentMessageEvent { message }) => self.on_agent_message(message),
@@ -1370,11 +1935,14 @@ impl ChatWidget {
                 self.on_agent_reasoning_final()
             }
             EventMsg::AgentReasoningSectionBreak(_) => self.on_reasoning_section_break(),
-            EventMsg::TaskStarted(_) => self.on_task_started(),
+            EventMsg::TaskStarted(ev) => self.on_task_started(ev.traceparent.as_deref()),
             EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
                 self.on_task_complete(last_agent_message)
             }
             EventMsg::TokenCount(ev) => {
+                if let (Some(omnara), Some(info)) = (self.omnara.as_ref(), ev.info.as_ref()) {
+                    omnara.on_token_usage(&info.total_token_usage);
+                }
                 self.set_token_info(ev.info);
                 self.on_rate_limit_snapshot(ev.rate_limits);
             }
@@ -1398,6 +1966,9 @@ impl ChatWidget {
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::McpToolCallApprovalRequest(ev) => {
+                self.on_mcp_approval_request(id.unwrap_or_default(), ev)
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
             EventMsg::PatchApplyBegin(ev) => self.on_patch_apply_begin(ev),
@@ -1429,6 +2000,29 @@ impl ChatWidget {
                 self.on_entered_review_mode(review_request)
             }
             EventMsg::ExitedReviewMode(review) => self.on_exited_review_mode(review),
+            EventMsg::RemoteInputReceived(ev) => {
+                self.add_to_history(history_cell::new_user_prompt(ev.text));
+            }
+            EventMsg::AskUserRequest(ev) => self.on_ask_user_request(ev),
+            EventMsg::RemoteApprovalResolved(ev) => {
+                // Core already submitted the decision; this just dismisses the modal.
+                self.apply_external_approval(ev.decision);
+            }
+            EventMsg::PatchExplanation(ev) => {
+                if let Some(omnara) = self.omnara.as_ref() {
+                    omnara.on_patch_explanation(ev.id, ev.explanation);
+                }
+            }
+            EventMsg::ContextCompacted(ev) => {
+                if let Some(omnara) = self.omnara.as_ref() {
+                    omnara.on_context_compacted(ev.tokens_before, ev.tokens_after);
+                }
+            }
+            EventMsg::ContextSummary(ev) => {
+                if let Some(omnara) = self.omnara.as_ref() {
+                    omnara.on_context_summary(ev);
+                }
+            }
         }
     }
 
@@ -1437,10 +2031,16 @@ impl ChatWidget {
         self.is_review_mode = true;
         let banner = format!(">> Code review started: {} <<", review.user_facing_hint);
         self.add_to_history(history_cell::new_review_status_line(banner));
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_review_started(&review.user_facing_hint);
+        }
         self.request_redraw();
     }
 
     fn on_exited_review_mode(&mut self, review: ExitedReviewModeEvent) {
+        if let Some(omnara) = self.omnara.as_mut() {
+            omnara.on_review_finished(review.review_output.clone());
+        }
         // Leave review mode; if output is present, flush pending stream + show results.
         if let Some(output) = review.review_output {
             self.flush_answer_stream_with_separator();
@@ -1566,6 +2166,7 @@ impl ChatWidget {
             usage_ref,
             &self.conversation_id,
             self.rate_limit_snapshot.as_ref(),
+            self.omnara.as_ref().map(OmnaraBridge::stats).as_ref(),
         ));
     }
 
@@ -1590,6 +2191,7 @@ impl ChatWidget {
                     approval_policy: None,
                     sandbox_policy: None,
                     model: Some(model_slug.clone()),
+                    model_provider: None,
                     effort: Some(effort),
                     summary: None,
                 }));
@@ -1651,6 +2253,7 @@ impl ChatWidget {
                     approval_policy: Some(approval),
                     sandbox_policy: Some(sandbox.clone()),
                     model: None,
+                    model_provider: None,
                     effort: None,
                     summary: None,
                 }));
@@ -1678,22 +2281,50 @@ impl ChatWidget {
     /// Set the approval policy in the widget's config copy.
     pub(crate) fn set_approval_policy(&mut self, policy: AskForApproval) {
         self.config.approval_policy = policy;
+        self.sync_omnara_policies();
     }
 
     /// Set the sandbox policy in the widget's config copy.
     pub(crate) fn set_sandbox_policy(&mut self, policy: SandboxPolicy) {
         self.config.sandbox_policy = policy;
+        self.sync_omnara_policies();
     }
 
     /// Set the reasoning effort in the widget's config copy.
     pub(crate) fn set_reasoning_effort(&mut self, effort: Option<ReasoningEffortConfig>) {
         self.config.model_reasoning_effort = effort;
+        self.sync_omnara_model();
     }
 
     /// Set the model in the widget's config copy.
     pub(crate) fn set_model(&mut self, model: &str) {
         self.session_header.set_model(model);
         self.config.model = model.to_string();
+        self.sync_omnara_model();
+    }
+
+    /// Set the model provider in the widget's config copy.
+    pub(crate) fn set_model_provider(&mut self, id: &str, provider: ModelProviderInfo) {
+        self.config.model_provider_id = id.to_string();
+        self.config.model_provider = provider;
+        self.sync_omnara_model();
+    }
+
+    /// Tell the Omnara bridge which model, effort, and provider are in use.
+    fn sync_omnara_model(&self) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_model_changed(
+                &self.config.model,
+                self.config.model_reasoning_effort,
+                &self.config.model_provider_id,
+            );
+        }
+    }
+
+    fn sync_omnara_policies(&self) {
+        if let Some(omnara) = self.omnara.as_ref() {
+            omnara.on_policies_changed(self.config.approval_policy, &self.config.sandbox_policy);
+        }
     }
 
     pub(crate) fn add_info_message(&mut self, message: String, hint: Option<String>) {
@@ -1763,9 +2394,18 @@ impl ChatWidget {
     }
     /// Forward an `Op` directly to codex.
     pub(crate) fn submit_op(&self, op: Op) {
+        // Decisions on `/apply-patch` patches belong to the bridge, not core.
+        if let Op::PatchApproval { id, decision } = &op
+            && id.starts_with(REMOTE_PATCH_ID_PREFIX)
+        {
+            if let Some(omnara) = self.omnara.as_ref() {
+                omnara.resolve_remote_patch(id.clone(), *decision);
+            }
+            return;
+        }
         // Cancel Omnara polling when user resolves approvals locally to avoid double resolution.
         match op {
-            Op::ExecApproval { .. } | Op::PatchApproval { .. } => {
+            Op::ExecApproval { .. } | Op::PatchApproval { .. } | Op::McpToolCallApproval { .. } => {
                 if let Some(omnara) = self.omnara.as_ref() {
                     omnara.cancel_polling();
                 }
@@ -14,6 +14,7 @@ use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::AskUserRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
@@ -285,6 +286,7 @@ async fn helpers_are_available_and_do_not_panic() {
         initial_images: Vec::new(),
         enhanced_keys_supported: false,
         auth_manager,
+        omnara: None,
     };
     let mut w = ChatWidget::new(init, conversation_manager);
     // Basic construction sanity.
@@ -338,6 +340,8 @@ fn make_chatwidget_manual() -> (
         ghost_snapshots: Vec::new(),
         ghost_snapshots_disabled: false,
         omnara: None,
+        omnara_sessions: None,
+        pending_question: None,
     };
     (widget, rx, op_rx)
 }
@@ -426,6 +430,8 @@ fn exec_approval_emits_proposed_command_and_decision_history() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        escalation: None,
+        closest_approved: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-short".into(),
@@ -464,6 +470,8 @@ fn exec_approval_decision_truncates_multiline_and_long_commands() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        escalation: None,
+        closest_approved: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-multi".into(),
@@ -494,6 +502,8 @@ fn exec_approval_decision_truncates_multiline_and_long_commands() {
         command: vec!["bash".into(), "-lc".into(), long],
         cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
         reason: None,
+        escalation: None,
+        closest_approved: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-long".into(),
@@ -546,6 +556,7 @@ fn end_exec(chat: &mut ChatWidget, call_id: &str, stdout: &str, stderr: &str, ex
             exit_code,
             duration: std::time::Duration::from_millis(5),
             formatted_output: aggregated,
+            sandbox: None,
         }),
     });
 }
@@ -597,6 +608,37 @@ fn empty_enter_during_task_does_not_queue() {
     assert!(chat.queued_user_messages.is_empty());
 }
 
+#[test]
+fn submission_during_ask_user_question_answers_it() {
+    let (mut chat, _rx, mut op_rx) = make_chatwidget_manual();
+    chat.bottom_pane.set_task_running(true);
+    chat.handle_codex_event(Event {
+        id: "t1".into(),
+        msg: EventMsg::AskUserRequest(AskUserRequestEvent {
+            call_id: "call-1".into(),
+            question: "Which branch?".into(),
+            options: vec!["main".into()],
+        }),
+    });
+
+    chat.bottom_pane.set_composer_text("main".to_string());
+    chat.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    assert!(chat.queued_user_messages.is_empty());
+    assert_eq!(
+        op_rx.try_recv().unwrap(),
+        Op::AskUserAnswer {
+            id: "call-1".into(),
+            answer: "main".into(),
+        }
+    );
+
+    // Once answered, submissions queue as usual.
+    chat.bottom_pane.set_composer_text("next".to_string());
+    chat.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(chat.queued_user_messages.len(), 1);
+}
+
 #[test]
 fn alt_up_edits_most_recent_queued_message() {
     let (mut chat, _rx, _op_rx) = make_chatwidget_manual();
@@ -1183,6 +1225,8 @@ fn approval_modal_exec_snapshot() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        escalation: None,
+        closest_approved: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve".into(),
@@ -1211,6 +1255,8 @@ fn approval_modal_exec_without_reason_snapshot() {
         command: vec!["bash".into(), "-lc".into(), "echo hello world".into()],
         cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
         reason: None,
+        escalation: None,
+        closest_approved: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-noreason".into(),
@@ -1329,6 +1375,7 @@ fn ui_snapshots_small_heights_task_running() {
         id: "task-1".into(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: None,
+            traceparent: None,
         }),
     });
     chat.handle_codex_event(Event {
@@ -1360,6 +1407,7 @@ fn status_widget_and_approval_modal_snapshot() {
         id: "task-1".into(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: None,
+            traceparent: None,
         }),
     });
     // Provide a deterministic header for the status line.
@@ -1378,6 +1426,8 @@ fn status_widget_and_approval_modal_snapshot() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        escalation: None,
+        closest_approved: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-exec".into(),
@@ -1404,6 +1454,7 @@ fn status_widget_active_snapshot() {
         id: "task-1".into(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: None,
+            traceparent: None,
         }),
     });
     // Provide a deterministic header via a bold reasoning chunk.
@@ -1864,6 +1915,7 @@ fn multiple_agent_messages_in_single_turn_emit_multiple_headers() {
         id: "s1".into(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: None,
+            traceparent: None,
         }),
     });
 
@@ -2039,12 +2091,14 @@ fn chatwidget_exec_and_status_layout_vt100_snapshot() {
             exit_code: 0,
             duration: std::time::Duration::from_millis(16000),
             formatted_output: String::new(),
+            sandbox: None,
         }),
     });
     chat.handle_codex_event(Event {
         id: "t1".into(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: None,
+            traceparent: None,
         }),
     });
     chat.handle_codex_event(Event {
@@ -2129,6 +2183,7 @@ fn chatwidget_markdown_code_blocks_vt100_snapshot() {
         id: "t1".into(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
             model_context_window: None,
+            traceparent: None,
         }),
     });
     // Build a vt100 visual from the history insertions only (no UI overlay)
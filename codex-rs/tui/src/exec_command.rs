@@ -1,18 +1,13 @@
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::command_display::display_command;
 use dirs::home_dir;
-use shlex::try_join;
-
-pub(crate) fn escape_command(command: &[String]) -> String {
-    try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
-}
 
+/// `command` as the user would type it: a shell's script unwrapped, or the
+/// argv quoted for the platform shell. Omnara approval prompts show the same.
 pub(crate) fn strip_bash_lc_and_escape(command: &[String]) -> String {
-    match command {
-        [first, second, third] if first == "bash" && second == "-lc" => third.clone(),
-        _ => escape_command(command),
-    }
+    display_command(command).text
 }
 
 /// If `path` is absolute and inside $HOME, return the part *after* the home
@@ -40,7 +35,7 @@ mod tests {
     #[test]
     fn test_escape_command() {
         let args = vec!["foo".into(), "bar baz".into(), "weird&stuff".into()];
-        let cmdline = escape_command(&args);
+        let cmdline = strip_bash_lc_and_escape(&args);
         assert_eq!(cmdline, "foo 'bar baz' 'weird&stuff'");
     }
 
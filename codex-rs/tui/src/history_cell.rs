@@ -18,6 +18,7 @@ use codex_core::auth::get_auth_file;
 use codex_core::auth::try_read_auth_json;
 use codex_core::config::Config;
 use codex_core::config_types::ReasoningSummaryFormat;
+use codex_core::omnara_metrics::OmnaraStats;
 use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
 use codex_core::plan_tool::UpdatePlanArgs;
@@ -1078,11 +1079,25 @@ pub(crate) fn new_warning_event(message: String) -> PlainHistoryCell {
     }
 }
 
+/// A dashboard message held by `omnara.workspace_trust`, shown in full so it
+/// can be read before it is forwarded or discarded.
+#[allow(clippy::disallowed_methods)]
+pub(crate) fn new_held_remote_message(text: &str, reasons: &[String]) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> =
+        vec![vec![format!("⚠ Message from Omnara held: {}", reasons.join("; ")).yellow()].into()];
+    lines.extend(
+        text.lines()
+            .map(|line| Line::from(format!("  {line}").dim())),
+    );
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_status_output(
     config: &Config,
     usage: &TokenUsage,
     session_id: &Option<ConversationId>,
     rate_limits: Option<&RateLimitSnapshot>,
+    omnara: Option<&OmnaraStats>,
 ) -> PlainHistoryCell {
     let mut lines: Vec<Line<'static>> = Vec::new();
     lines.push("/status".magenta().into());
@@ -1223,6 +1238,12 @@ pub(crate) fn new_status_output(
     lines.push(vec!["  • CLI Version: ".into(), cli_version.into()].into());
     lines.push("".into());
 
+    // 📡 Omnara (only while the bridge is enabled)
+    if let Some(stats) = omnara {
+        lines.extend(build_status_omnara_lines(stats));
+        lines.push("".into());
+    }
+
     // 📊 Token Usage
     lines.push(vec!["📊 ".into(), "Token Usage".bold()].into());
     if let Some(session_id) = session_id {
@@ -1611,6 +1632,41 @@ fn format_mcp_invocation<'a>(invocation: McpInvocation) -> Line<'a> {
     invocation_spans.into()
 }
 
+fn build_status_omnara_lines(stats: &OmnaraStats) -> Vec<Line<'static>> {
+    let latency = stats
+        .average_approval_latency
+        .map(format_duration)
+        .unwrap_or_else(|| "—".to_string());
+    vec![
+        vec![padded_emoji("📡").into(), "Omnara".bold()].into(),
+        vec![
+            "  • Messages Mirrored: ".into(),
+            format!(
+                "{} agent, {} user",
+                format_with_separators(stats.agent_messages_sent),
+                format_with_separators(stats.user_messages_sent)
+            )
+            .into(),
+        ]
+        .into(),
+        vec![
+            "  • Remote Inputs: ".into(),
+            format_with_separators(stats.remote_inputs_received).into(),
+        ]
+        .into(),
+        vec![
+            "  • Approvals: ".into(),
+            format!(
+                "{} remote, {} local",
+                stats.approvals_resolved_remotely, stats.approvals_resolved_locally
+            )
+            .into(),
+        ]
+        .into(),
+        vec!["  • Avg Approval Latency: ".into(), latency.into()].into(),
+    ]
+}
+
 fn build_status_limit_lines(snapshot: Option<&RateLimitSnapshot>) -> Vec<Line<'static>> {
     let mut lines: Vec<Line<'static>> =
         vec![vec![padded_emoji("⏱️").into(), "Usage Limits".bold()].into()];
@@ -2289,4 +2345,33 @@ mod tests {
             vec!["thinking", "We should fix the bug next."]
         );
     }
+
+    #[test]
+    fn status_output_summarizes_the_omnara_bridge() {
+        let stats = OmnaraStats {
+            agent_messages_sent: 204,
+            user_messages_sent: 12,
+            remote_inputs_received: 7,
+            approvals_resolved_remotely: 5,
+            approvals_resolved_locally: 2,
+            average_approval_latency: Some(Duration::from_secs(75)),
+        };
+        assert_eq!(
+            render_lines(&build_status_omnara_lines(&stats)),
+            vec![
+                "📡\u{200A}Omnara",
+                "  • Messages Mirrored: 204 agent, 12 user",
+                "  • Remote Inputs: 7",
+                "  • Approvals: 5 remote, 2 local",
+                "  • Avg Approval Latency: 1m 15s",
+            ]
+        );
+
+        let cell = new_status_output(&test_config(), &TokenUsage::default(), &None, None, None);
+        assert!(
+            !render_lines(&cell.display_lines(80))
+                .iter()
+                .any(|line| line.contains("Omnara"))
+        );
+    }
 }
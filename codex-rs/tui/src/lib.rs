@@ -53,7 +53,7 @@ mod markdown;
 mod markdown_render;
 mod markdown_stream;
 mod new_model_popup;
-mod omnara_format;
+mod omnara_context;
 mod omnara_integration;
 pub mod onboarding;
 mod pager_overlay;
@@ -0,0 +1,144 @@
+//! `@file <path>` directives in remote (dashboard) messages.
+//!
+//! Locally, @-mentions insert a path the model can open itself. Someone
+//! driving the session from Omnara has no file picker, so the bridge reads the
+//! referenced files up front and forwards them as extra context blocks.
+
+use codex_core::protocol::InputItem;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Directive that introduces a file reference in a remote message.
+const FILE_DIRECTIVE: &str = "@file";
+/// Maximum bytes forwarded per referenced file.
+const MAX_FILE_BYTES: usize = 64 * 1024;
+/// Maximum number of files attached from a single message.
+const MAX_FILES: usize = 8;
+
+/// Context gathered for the `@file` directives of one message.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FileContext {
+    /// One text block per file that could be read.
+    pub items: Vec<InputItem>,
+    /// `(path, reason)` for references that were skipped.
+    pub failures: Vec<(String, String)>,
+}
+
+/// Paths referenced as `@file <path>` in `text`, in order, without duplicates.
+pub(crate) fn file_references(text: &str) -> Vec<&str> {
+    let mut refs = Vec::new();
+    let mut tokens = text.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == FILE_DIRECTIVE
+            && let Some(path) = tokens.next().map(|p| p.trim_end_matches([',', ';', ')']))
+            && !path.is_empty()
+            && !refs.contains(&path)
+        {
+            refs.push(path);
+        }
+    }
+    refs
+}
+
+/// Read every file referenced in `text` relative to `cwd`. Files must resolve
+/// inside `cwd`; each is capped at [`MAX_FILE_BYTES`].
+pub(crate) fn collect_file_context(text: &str, cwd: &Path) -> FileContext {
+    let mut context = FileContext::default();
+    for (i, path) in file_references(text).into_iter().enumerate() {
+        if i >= MAX_FILES {
+            context.failures.push((
+                path.to_string(),
+                format!("at most {MAX_FILES} files can be attached per message"),
+            ));
+            continue;
+        }
+        match read_file_block(cwd, path) {
+            Ok(text) => context.items.push(InputItem::Text { text }),
+            Err(reason) => context.failures.push((path.to_string(), reason)),
+        }
+    }
+    context
+}
+
+fn read_file_block(cwd: &Path, path: &str) -> Result<String, String> {
+    let root = cwd.canonicalize().map_err(|e| e.to_string())?;
+    let resolved = root.join(path).canonicalize().map_err(|e| e.to_string())?;
+    if !resolved.starts_with(&root) {
+        return Err("path is outside the working directory".to_string());
+    }
+    // Read one byte past the cap to tell a truncated file from one that
+    // fits exactly, without loading a huge file into memory.
+    let mut bytes = Vec::new();
+    File::open(&resolved)
+        .and_then(|file| file.take(MAX_FILE_BYTES as u64 + 1).read_to_end(&mut bytes))
+        .map_err(|e| e.to_string())?;
+    if bytes.contains(&0) {
+        return Err("binary files are not supported".to_string());
+    }
+    let truncated = bytes.len() > MAX_FILE_BYTES;
+    let mut contents = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_FILE_BYTES)])
+        .trim_end_matches('\n')
+        .to_string();
+    if truncated {
+        contents.push_str(&format!("\n… (truncated at {} KiB)", MAX_FILE_BYTES / 1024));
+    }
+    Ok(format!("<file path=\"{path}\">\n{contents}\n</file>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn references_are_parsed_in_order_without_duplicates() {
+        assert_eq!(
+            file_references("look at @file src/a.rs and @file b.rs, then @file src/a.rs"),
+            vec!["src/a.rs", "b.rs"]
+        );
+        assert_eq!(
+            file_references("email me@file.com @file"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn files_are_read_relative_to_cwd_and_capped() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::create_dir(dir.path().join("src")).expect("mkdir");
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").expect("write");
+        std::fs::write(dir.path().join("big.txt"), "x".repeat(MAX_FILE_BYTES + 1)).expect("write");
+
+        let context = collect_file_context("@file src/main.rs @file big.txt", dir.path());
+        assert_eq!(context.failures, Vec::new());
+        assert_eq!(
+            context.items[0],
+            InputItem::Text {
+                text: "<file path=\"src/main.rs\">\nfn main() {}\n</file>".to_string()
+            }
+        );
+        let InputItem::Text { text } = &context.items[1] else {
+            panic!("expected text item");
+        };
+        assert!(text.ends_with("… (truncated at 64 KiB)\n</file>"));
+    }
+
+    #[test]
+    fn missing_and_escaping_paths_are_reported() {
+        let dir = TempDir::new().expect("tempdir");
+        let inner = dir.path().join("repo");
+        std::fs::create_dir(&inner).expect("mkdir");
+        std::fs::write(dir.path().join("secret.txt"), "nope").expect("write");
+
+        let context = collect_file_context("@file missing.rs @file ../secret.txt", &inner);
+        assert_eq!(context.items, Vec::new());
+        let paths: Vec<&str> = context.failures.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["missing.rs", "../secret.txt"]);
+        assert_eq!(
+            context.failures[1].1,
+            "path is outside the working directory"
+        );
+    }
+}
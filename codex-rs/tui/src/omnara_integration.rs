@@ -1,57 +1,960 @@
+use chrono::DateTime;
+use chrono::Utc;
+use codex_core::command_risk::RiskLevel;
+use codex_core::config::Config;
+use codex_core::config_types::ApprovalPush;
+use codex_core::config_types::ApprovalSynonyms;
+use codex_core::config_types::OmnaraLocale;
+use codex_core::config_types::OmnaraMarkdownDialect;
+use codex_core::config_types::OmnaraMirroring;
+use codex_core::config_types::OmnaraSessionReport;
+use codex_core::config_types::OmnaraWebActivity;
+use codex_core::config_types::OmnaraWorkspaceTrust;
+use codex_core::git_diff_tracker::DiffLimits;
+use codex_core::git_diff_tracker::SubmoduleDiffs;
+use codex_core::omnara_alerts::AlertKind;
+use codex_core::omnara_alerts::Alerts;
+use codex_core::omnara_approval::ApprovalParser;
+use codex_core::omnara_approval::ApprovalReaction;
+use codex_core::omnara_approval::ApprovalReply;
+use codex_core::omnara_approval::BatchReply;
+use codex_core::omnara_approval::ESCALATION_APPROVAL_OPTIONS;
+use codex_core::omnara_approval::EXEC_APPROVAL_OPTIONS;
+use codex_core::omnara_approval::ExecRequest;
+use codex_core::omnara_approval::PATCH_APPROVAL_OPTIONS;
+use codex_core::omnara_approval::PatchFormat;
+use codex_core::omnara_approval::format_auto_approve_ended;
+use codex_core::omnara_approval::format_auto_approve_started;
+use codex_core::omnara_approval::format_denial_feedback;
+use codex_core::omnara_approval::format_exec_batch_approval_request;
+use codex_core::omnara_approval::format_hunk_expansion;
+use codex_core::omnara_approval::format_partial_approval_feedback;
+use codex_core::omnara_approval::format_patch_explanation;
+use codex_core::omnara_approval::format_patch_preview_result;
+use codex_core::omnara_approval::format_patch_preview_started;
+use codex_core::omnara_approval::format_prompt_with_details;
+use codex_core::omnara_approval::format_remote_patch_received;
+use codex_core::omnara_approval::format_remote_patch_refused;
+use codex_core::omnara_approval::format_remote_patch_result;
+use codex_core::omnara_approval::format_rollback_request;
+use codex_core::omnara_approval::format_rollback_result;
+use codex_core::omnara_approval::format_withdrawn_approval_note;
+use codex_core::omnara_approval::normalize_reply;
+use codex_core::omnara_approval::patch_files;
+use codex_core::omnara_approval::resolve_ask_user_reply;
+use codex_core::omnara_capabilities::Capability;
+use codex_core::omnara_circuit::CircuitPolicy;
+use codex_core::omnara_client::AttachmentRef;
+use codex_core::omnara_client::MessageSender;
 use codex_core::omnara_client::OmnaraClient;
+use codex_core::omnara_client::PollPolicy;
+use codex_core::omnara_client::SessionMetadata;
+use codex_core::omnara_client::SharePermissions;
+use codex_core::omnara_diagnostics::Diagnostic;
+use codex_core::omnara_diagnostics::DiagnosticLimiter;
+use codex_core::omnara_diagnostics::format_diagnostic_note;
+use codex_core::omnara_escalation::EscalationChain;
+use codex_core::omnara_escalation::EscalationStep;
+use codex_core::omnara_i18n::strings;
+use codex_core::omnara_metrics::ApprovalResolver;
+use codex_core::omnara_metrics::OmnaraStats;
+use codex_core::omnara_question::ClarifyingQuestion;
+use codex_core::omnara_question::detect_question;
+use codex_core::omnara_question::format_clarifying_question;
+use codex_core::omnara_quiet_hours::QuietApproval;
+use codex_core::omnara_quiet_hours::QuietHours;
+use codex_core::omnara_quiet_hours::format_quiet_hours_decision_note;
+use codex_core::omnara_quiet_hours::format_quiet_hours_digest;
+use codex_core::omnara_sanitize::format_rejected_message_note;
+use codex_core::omnara_sanitize::sanitize_remote_message;
+use codex_core::omnara_schedule::Schedule;
+use codex_core::omnara_schedule::format_schedule_finished;
+use codex_core::omnara_schedule::format_schedule_started;
+use codex_core::omnara_schedule::schedules_from_config;
+use codex_core::omnara_session_report::SessionReportBuilder;
+use codex_core::omnara_session_report::TRANSCRIPT_NAME;
+use codex_core::omnara_tags::TagCommand;
+use codex_core::omnara_tags::format_tags_note;
+use codex_core::omnara_tags::parse_tag_command;
+use codex_core::omnara_withhold::WITHHELD_PLACEHOLDER;
+use codex_core::omnara_workspace::SelectedWorkspace;
+use codex_core::patch_preview::preview_patch;
+use codex_core::plan_tool::UpdatePlanArgs;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::ContextSummaryEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::FileChange;
 use codex_core::protocol::InputItem;
+use codex_core::protocol::McpInvocation;
 use codex_core::protocol::Op;
+use codex_core::protocol::PermissionEscalation;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::ReviewOutputEvent;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::TokenUsage;
+use codex_core::protocol::WebSearchEndEvent;
+use codex_core::protocol_config_types::ReasoningEffort;
+use codex_core::remote_patch::RemotePatch;
+use codex_core::remote_patch::RemotePatchError;
+use codex_core::session_rollback::RollbackError;
+use codex_core::session_rollback::RollbackPlan;
+use codex_core::trace_context::TraceContext;
+use mcp_types::CallToolResult;
+use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::unbounded_channel;
 use tokio::task::JoinHandle;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::history_cell;
-use tracing::{debug, info};
+use crate::history_cell::CommandOutput;
+use codex_omnara::ansi::AnsiOutput;
+use codex_omnara::format::ExecOutput;
+use codex_omnara::format::NoteAttachment;
+use codex_omnara::format::OutputTail;
+use codex_omnara::format::TurnSummaryBuilder;
+use codex_omnara::format::format_choices_note;
+use codex_omnara::format::format_compaction_note;
+use codex_omnara::format::format_config_note;
+use codex_omnara::format::format_context_summary;
+use codex_omnara::format::format_env_note;
+use codex_omnara::format::format_interrupted_approval_note;
+use codex_omnara::format::format_pending_approvals_note;
+use codex_omnara::format::format_sub_agent_finished_note;
+use codex_omnara::format::format_sub_agent_message;
+use codex_omnara::format::format_sub_agent_started_note;
+use codex_omnara::format::format_switch_note;
+use codex_omnara::guard::Suspicion;
+use codex_omnara::state::ApprovalKind;
+use codex_omnara::state::BridgeEvent;
+use codex_omnara::state::BridgeState;
+use codex_omnara::state::Route;
+use ratatui::text::Line;
+use tracing::{debug, info, warn};
+
+pub(crate) use manager::BridgeManager;
+pub(crate) use manager::LinkedSession;
+use model_choices::ModelChoices;
+
+mod manager;
+mod model_choices;
 
 /// Thin TUI-side bridge over the core Omnara client.
-/// - Tracks last agent send handle so we can request input deterministically.
-/// - Starts polling and forwards remote user messages into the UI and agent.
+/// - Hands every send and lifecycle event to a single worker task as an
+///   ordered [`BridgeCommand`], so the dashboard sees notes in the order they
+///   happened and input is requested only after the turn's last message.
+/// - The worker owns the [`BridgeState`], starts polling, and routes remote
+///   user messages into the UI and agent.
+/// - Shutdown drains the worker before ending the session.
 pub(crate) struct OmnaraBridge {
     client: OmnaraClient,
-    last_agent_send_handle: Option<JoinHandle<()>>,
+    commands: UnboundedSender<BridgeCommand>,
+    /// The worker until the first command spawns it, so the `with_*`
+    /// builders can still configure it.
+    worker: Mutex<Option<Worker>>,
+    worker_handle: Mutex<Option<JoinHandle<()>>>,
+    /// Root for `@file` references, touched paths, and review findings.
+    cwd: PathBuf,
+    /// `digest` posts the turn summary in place of per-event notes.
+    mirroring: OmnaraMirroring,
+    /// In `full` mode, also post the turn summary when a turn ends.
+    turn_summary: bool,
+    /// The current turn's events, summarized when it ends.
+    turn: Mutex<TurnState>,
+    /// Language of the prompts and notes posted to the dashboard.
+    locale: OmnaraLocale,
+    /// How patch notes and approvals render their changes.
+    patch_format: PatchFormat,
+    /// How long a command runs before its progress note is posted, and how
+    /// often it is refreshed; `None` disables progress notes.
+    exec_progress: Option<Duration>,
+    /// Commands with a progress ticker, by call id.
+    running_execs: Mutex<HashMap<String, RunningExec>>,
+    /// How web searches and page visits are mirrored.
+    web_activity: OmnaraWebActivity,
+    /// Render output colors in exec notes where the server allows it.
+    exec_output_colors: bool,
+    /// Rate limit of error and diagnostics notes; `None` disables them.
+    diagnostics: Option<Mutex<DiagnosticLimiter>>,
+    /// What the session did, for the report posted at shutdown; `None`
+    /// unless `omnara.session_report.enabled`.
+    session_report: Option<Mutex<SessionReportBuilder>>,
+    session_report_settings: OmnaraSessionReport,
+}
+
+/// A running command's recent output and the task that periodically posts
+/// its progress note.
+struct RunningExec {
+    tail: Arc<Mutex<OutputTail>>,
+    ticker: JoinHandle<()>,
+    /// The command names a withheld path, so its output is not recorded.
+    withheld: bool,
+}
+
+#[derive(Default)]
+struct TurnState {
+    summary: TurnSummaryBuilder,
+    /// Latest agent message in `digest` mode; it becomes an excerpt once a
+    /// newer one arrives, and is posted in full after the summary if it was
+    /// the turn's last.
+    last_message: Option<String>,
+    /// Agent message that reads as a question, in `full` mode, held until
+    /// the next event: posted as a question if the turn ends there, as is
+    /// otherwise.
+    question: Option<String>,
+    started: Option<tokio::time::Instant>,
+    /// Session token totals when the turn started and as last reported.
+    tokens_at_start: TokenUsage,
+    tokens: TokenUsage,
+}
+
+/// Formats a note once its attachment upload has finished (or failed).
+type NoteBuilder = Box<dyn FnOnce(Option<&AttachmentRef>) -> String + Send>;
+
+/// Input to the worker task: sends and lifecycle events from the TUI and
+/// messages delivered by the poller, handled strictly in arrival order.
+enum BridgeCommand {
+    /// Post an agent message; with `request_input`, then ask the dashboard
+    /// for the next instruction and start polling.
+    AgentMessage {
+        message: String,
+        request_input: bool,
+    },
+    /// Post the question a turn ended with, offering its suggested answers.
+    Question(ClarifyingQuestion),
+    /// Upload `attachment` (if any), then post the note `build` formats.
+    Note {
+        attachment: Option<NoteAttachment>,
+        build: NoteBuilder,
+    },
+    /// Post or refresh the progress note of running command `call_id`.
+    ExecProgress {
+        call_id: String,
+        note: String,
+    },
+    /// Upload `attachment` (if any), then post the note `build` formats as
+    /// status `key`, editing the note already posted for `key` in place.
+    /// After the `last` status of a key, its next one is a new note.
+    Status {
+        key: String,
+        attachment: Option<NoteAttachment>,
+        build: NoteBuilder,
+        last: bool,
+    },
+    /// Sub-agent `id` started; until it finishes, messages, notes, and
+    /// approval prompts carry its label. `start_note` marks its start.
+    SubAgentStarted {
+        id: String,
+        label: String,
+        codex_op_tx: Option<tokio::sync::mpsc::UnboundedSender<Op>>,
+        start_note: Option<String>,
+    },
+    /// Sub-agent `id` finished, with its outcome if any. With `announce`,
+    /// a note marks its end.
+    SubAgentFinished {
+        id: String,
+        summary: Option<String>,
+        announce: bool,
+    },
+    /// Mirror a local prompt as a read user message.
+    LocalUserMessage(String),
+    /// Include the repositories containing these paths in the session diff.
+    TrackPaths(Vec<PathBuf>),
+    /// Record patched files so those outside version control are diffed.
+    RecordPatch(HashMap<PathBuf, FileChange>),
+    /// Create a `/share` link and report it back as `OmnaraShareLink`.
+    ShareLink(SharePermissions),
+    /// Apply a local `/tag` command and report the tags back as
+    /// `OmnaraTags`.
+    Tag(TagCommand),
+    /// The model, effort, or provider was changed locally.
+    CurrentModel {
+        model: String,
+        effort: Option<ReasoningEffort>,
+        provider: String,
+    },
+    /// The approval or sandbox policy was changed locally.
+    CurrentPolicies {
+        approval_policy: AskForApproval,
+        sandbox_policy: SandboxPolicy,
+    },
+    /// Fetch the dashboard exchanges after `since` (all of them when `None`)
+    /// and report them as `OmnaraHistoryReplay`.
+    ReplayHistory {
+        since: Option<DateTime<Utc>>,
+    },
+    SessionStart,
+    /// A turn started; `trace` is its trace context, if core reported one.
+    TaskStarted {
+        trace: Option<TraceContext>,
+    },
+    /// The turn completed; its final agent message was queued before this.
+    TaskComplete,
+    /// The turn was interrupted (Esc, Ctrl-C, or a remote `/now`).
+    UserInterrupt,
+    Approval {
+        id: String,
+        kind: ApprovalKind,
+        prompt: String,
+        /// Risk level of an exec or patch approval, which decides whether it
+        /// asks for a push notification.
+        risk: Option<RiskLevel>,
+        /// The proposed changes of a patch approval, for `/preview`.
+        changes: Option<HashMap<PathBuf, FileChange>>,
+        /// The request behind an exec approval, for batching.
+        exec: Option<ExecRequest>,
+    },
+    /// The exec approval batch window closed; post what it gathered.
+    FlushExecBatch,
+    /// The auto-approval window opened by a "yes for <duration>" reply
+    /// closed.
+    AutoApprovalEnded,
+    /// The dashboard reacted to the last agent message.
+    Reaction(ApprovalReaction),
+    /// A `/preview` dry run of approval `id` finished.
+    PreviewFinished {
+        id: String,
+        report: String,
+    },
+    /// Core answered the `/explain` request for approval `id`.
+    ExplainFinished {
+        id: String,
+        explanation: Option<String>,
+    },
+    ReviewFinished(ReviewOutputEvent),
+    /// Core compacted the conversation history.
+    ContextCompacted {
+        tokens_before: u64,
+        tokens_after: u64,
+    },
+    /// Core answered a dashboard `/context`.
+    ContextSummary(ContextSummaryEvent),
+    /// Local input was submitted; polling was already cancelled.
+    LocalInput,
+    Remote(String),
+    /// The local user decided on a message held by `workspace_trust`.
+    ResolveHeldInput {
+        text: String,
+        forward: bool,
+    },
+    /// The local user decided whether to recover what a crashed run left.
+    ResolveRecovery {
+        recover: bool,
+    },
+    /// The local user decided on a patch sent with `/apply-patch`.
+    ResolveRemotePatch {
+        id: String,
+        decision: ReviewDecision,
+    },
+    /// The quiet hours range that held notes has run out.
+    QuietHoursEnded,
+    /// Schedule `index` came due for its run at `at`.
+    ScheduleDue {
+        index: usize,
+        at: DateTime<Utc>,
+    },
+    /// Omnara requests paused after repeated failures (`paused`), or
+    /// resumed once the server answered again.
+    CircuitChanged {
+        paused: bool,
+    },
+    /// A step of the escalation chain for approval `id` came due.
+    Escalate {
+        id: String,
+        step: EscalationStep,
+        waited: Duration,
+    },
+    /// The running turn reached the `[omnara.alerts]` threshold.
+    TurnAlert {
+        elapsed: Duration,
+    },
+    /// Approval `id` reached the `[omnara.alerts]` threshold unanswered.
+    ApprovalAlert {
+        id: String,
+        waited: Duration,
+    },
+    /// Upload the session's transcript and post the report linking it.
+    SessionReport {
+        builder: Box<SessionReportBuilder>,
+        settings: OmnaraSessionReport,
+    },
+    Shutdown,
+}
+
+/// Owner of the bridge state and the only task that talks to Omnara, so
+/// sends land in order and routing decisions never race with them.
+struct Worker {
+    client: OmnaraClient,
     app_event_tx: AppEventSender,
     codex_op_tx: tokio::sync::mpsc::UnboundedSender<Op>,
-    pending: Arc<Mutex<VecDeque<(String, ApprovalKind)>>>,
+    approval_parser: ApprovalParser,
+    locale: OmnaraLocale,
+    patch_format: PatchFormat,
+    cwd: PathBuf,
+    /// Hold remote messages during an active turn instead of forwarding them.
+    queue_remote_input: bool,
+    /// Larger remote messages are refused.
+    max_remote_message_bytes: usize,
+    /// Which remote messages need local confirmation before they reach
+    /// the agent.
+    workspace_trust: OmnaraWorkspaceTrust,
+    /// Which exec and patch approval prompts ask for a push notification.
+    approval_push: ApprovalPush,
+    state: BridgeState,
+    /// Remote messages held until the running turn completes.
+    queued: VecDeque<String>,
+    /// When the pending approval prompt was posted, for the latency metric.
+    approval_sent_at: Option<tokio::time::Instant>,
+    /// Approval prompts posted at once; `0` means no limit.
+    max_outstanding_approvals: usize,
+    /// Prompts posted while an earlier one was waiting, answered in order.
+    posted_approvals: VecDeque<(QueuedApproval, QuietApproval)>,
+    /// Prompts not posted yet for lack of room.
+    held_approvals: VecDeque<QueuedApproval>,
+    /// How long exec approvals are gathered into one prompt; zero posts
+    /// each on its own.
+    exec_batch_window: Duration,
+    /// Exec approvals gathered in the current window, and its timer.
+    exec_batch: Vec<QueuedApproval>,
+    exec_batch_timer: Option<JoinHandle<()>>,
+    /// The exec approvals each batch prompt answers, in prompt order, by
+    /// the batch prompt's id.
+    exec_batches: HashMap<String, Vec<String>>,
+    /// The window a "yes for <duration>" reply opened, if still open.
+    auto_approval: Option<AutoApproval>,
+    /// What to do when an approval prompt goes unanswered.
+    escalation: Option<EscalationChain>,
+    /// The pending approval's prompt and escalation timer.
+    escalation_timer: Option<(String, JoinHandle<()>)>,
+    /// Urgent notes for long turns and unanswered approvals.
+    alerts: Option<Alerts>,
+    /// Fires `TurnAlert` for the running turn.
+    turn_alert_timer: Option<JoinHandle<()>>,
+    /// Fires `ApprovalAlert` for the pending approval prompt.
+    approval_alert_timer: Option<JoinHandle<()>>,
+    /// Do-not-disturb schedule for notes and approval prompts.
+    quiet_hours: Option<QuietHours>,
+    /// Notes held for the digest posted when quiet hours end.
+    quiet_notes: Vec<String>,
+    /// Fires `QuietHoursEnded` once the current quiet range is over.
+    quiet_timer: Option<JoinHandle<()>>,
+    /// Prompts submitted on a schedule.
+    schedules: Vec<Schedule>,
+    /// Each schedule's next run and the timer firing `ScheduleDue` for it,
+    /// by index; `None` for a schedule that never runs.
+    schedule_timers: Vec<Option<(DateTime<Utc>, JoinHandle<()>)>>,
+    /// Scheduled runs that came due while the agent was busy, by index;
+    /// they start once the turn ends.
+    due_schedules: VecDeque<usize>,
+    /// The schedule whose prompt started the running turn.
+    scheduled_turn: Option<usize>,
+    /// Suggested answers to the question the last turn ended with; a
+    /// numeric reply is forwarded as the option's text.
+    question_options: Vec<String>,
+    /// Reports circuit changes as `CircuitChanged` and probes the server
+    /// while requests are paused.
+    circuit_monitor: Option<JoinHandle<()>>,
+    /// Set while the circuit is open: remote input cannot arrive, so
+    /// approval prompts are left to the terminal.
+    offline: bool,
+    /// Forwards read receipts for the last agent message as
+    /// `OmnaraMessageSeen`.
+    seen_monitor: Option<JoinHandle<()>>,
+    /// Forwards reactions on the last agent message as `Reaction`.
+    reaction_monitor: Option<JoinHandle<()>>,
+    /// What dashboard `/model` and `/provider` commands may switch to.
+    model_choices: ModelChoices,
+    /// The policies in use, reported by `/config`.
+    approval_policy: AskForApproval,
+    sandbox_policy: SandboxPolicy,
+    /// Check command and timeout for `/preview` dry runs.
+    preview_command: Vec<String>,
+    preview_timeout: Duration,
+    /// The pending approval's risk level, for a "yes for <duration>" reply.
+    pending_risk: Option<RiskLevel>,
+    /// The pending approval's prompt, posted again on a 🔁 reaction.
+    pending_prompt: Option<String>,
+    /// The pending exec approval's command, for the detail a 🔁 reaction
+    /// asks for.
+    pending_exec: Option<ExecRequest>,
+    /// The pending patch approval's changes.
+    pending_patch: Option<HashMap<PathBuf, FileChange>>,
+    /// The pending `/rollback` approval's plan.
+    pending_rollback: Option<RollbackPlan>,
+    /// `/apply-patch` patches awaiting approval in the terminal, by id.
+    remote_patches: HashMap<String, RemotePatch>,
+    remote_patch_seq: u64,
+    /// Message ids of the turn's status notes (command progress, tool
+    /// calls, the plan), by status key.
+    status_messages: HashMap<String, String>,
+    /// Running sub-agents, by id.
+    sub_agents: HashMap<String, SubAgent>,
+    /// Ids of the running sub-agents, latest last; the latest one's label
+    /// marks messages, notes, and approval prompts.
+    active_sub_agents: Vec<String>,
+    /// The sub-agent each approval prompt was posted for, by approval id.
+    approval_agents: HashMap<String, String>,
+    /// Set when a remote `/now` or `/new-task` interrupted the turn, so the
+    /// interrupt prompt is not echoed back to the dashboard that caused it.
+    interrupted_remotely: bool,
+    /// Handed to the poller so delivered messages join the command stream.
+    commands: UnboundedSender<BridgeCommand>,
+    rx: UnboundedReceiver<BridgeCommand>,
+}
+
+/// An agent Codex runs alongside the conversation's own, e.g. the reviewer.
+struct SubAgent {
+    label: String,
+    /// Where its approval decisions go; `None` when its approvals show in
+    /// the conversation's approval modal, like the main agent's.
+    codex_op_tx: Option<tokio::sync::mpsc::UnboundedSender<Op>>,
+}
+
+/// An approval prompt and what answering it needs.
+struct QueuedApproval {
+    id: String,
+    kind: ApprovalKind,
+    prompt: String,
+    risk: Option<RiskLevel>,
+    changes: Option<HashMap<PathBuf, FileChange>>,
+    exec: Option<ExecRequest>,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum ApprovalKind {
-    Exec,
-    Patch,
+/// Requests approved without asking until the window's timer fires.
+struct AutoApproval {
+    /// Kind and highest risk level of the requests it approves.
+    kind: ApprovalKind,
+    risk: Option<RiskLevel>,
+    /// The approval whose reply opened the window, for the audit trail.
+    opened_by: String,
+    timer: JoinHandle<()>,
 }
 
+impl AutoApproval {
+    fn covers(&self, kind: ApprovalKind, risk: Option<RiskLevel>) -> bool {
+        window_kind(kind) == self.kind && risk <= self.risk
+    }
+}
+
+/// Batches share a window with single exec approvals.
+fn window_kind(kind: ApprovalKind) -> ApprovalKind {
+    match kind {
+        ApprovalKind::ExecBatch => ApprovalKind::Exec,
+        kind => kind,
+    }
+}
+
+/// A dashboard reply to the post-review prompt.
+#[derive(Debug, PartialEq, Eq)]
+enum ReviewReply {
+    Accept,
+    RequestChanges,
+    /// Free-form text, treated as the requested changes.
+    Feedback(String),
+}
+
+/// Prompt forwarded to the agent when the dashboard accepts review findings.
+const ACCEPT_FINDINGS_PROMPT: &str = "Please fix the issues raised in the review findings above.";
+
+/// Prefix of the approval ids given to `/apply-patch` patches; the chat
+/// widget hands decisions on them back to the bridge instead of core.
+pub(crate) const REMOTE_PATCH_ID_PREFIX: &str = "omnara-patch-";
+
 impl OmnaraBridge {
     pub fn new(
         client: OmnaraClient,
         app_event_tx: AppEventSender,
         codex_op_tx: tokio::sync::mpsc::UnboundedSender<Op>,
+        cwd: PathBuf,
     ) -> Self {
         info!(session_id = %client.session_id(), "OmnaraBridge: enabled");
+        let (commands, rx) = unbounded_channel();
         Self {
-            client,
-            last_agent_send_handle: None,
-            app_event_tx,
-            codex_op_tx,
-            pending: Arc::new(Mutex::new(VecDeque::new())),
+            client: client.clone(),
+            commands: commands.clone(),
+            worker: Mutex::new(Some(Worker {
+                client,
+                app_event_tx,
+                codex_op_tx,
+                approval_parser: ApprovalParser::default(),
+                locale: OmnaraLocale::default(),
+                patch_format: PatchFormat::default(),
+                cwd: cwd.clone(),
+                queue_remote_input: true,
+                max_remote_message_bytes: 64 * 1024,
+                workspace_trust: OmnaraWorkspaceTrust::default(),
+                approval_push: ApprovalPush::default(),
+                state: BridgeState::Idle,
+                queued: VecDeque::new(),
+                approval_sent_at: None,
+                max_outstanding_approvals: 0,
+                posted_approvals: VecDeque::new(),
+                held_approvals: VecDeque::new(),
+                exec_batch_window: Duration::ZERO,
+                exec_batch: Vec::new(),
+                exec_batch_timer: None,
+                exec_batches: HashMap::new(),
+                auto_approval: None,
+                escalation: None,
+                escalation_timer: None,
+                alerts: None,
+                turn_alert_timer: None,
+                approval_alert_timer: None,
+                quiet_hours: None,
+                quiet_notes: Vec::new(),
+                quiet_timer: None,
+                schedules: Vec::new(),
+                schedule_timers: Vec::new(),
+                due_schedules: VecDeque::new(),
+                scheduled_turn: None,
+                question_options: Vec::new(),
+                circuit_monitor: None,
+                seen_monitor: None,
+                reaction_monitor: None,
+                model_choices: ModelChoices::default(),
+                approval_policy: AskForApproval::default(),
+                sandbox_policy: SandboxPolicy::new_read_only_policy(),
+                preview_command: Vec::new(),
+                preview_timeout: Duration::from_secs(300),
+                pending_risk: None,
+                pending_prompt: None,
+                pending_exec: None,
+                pending_patch: None,
+                pending_rollback: None,
+                remote_patches: HashMap::new(),
+                remote_patch_seq: 0,
+                status_messages: HashMap::new(),
+                sub_agents: HashMap::new(),
+                active_sub_agents: Vec::new(),
+                approval_agents: HashMap::new(),
+                interrupted_remotely: false,
+                offline: false,
+                commands,
+                rx,
+            })),
+            worker_handle: Mutex::new(None),
+            cwd,
+            mirroring: OmnaraMirroring::default(),
+            turn_summary: false,
+            turn: Mutex::new(TurnState::default()),
+            locale: OmnaraLocale::default(),
+            patch_format: PatchFormat::default(),
+            exec_progress: None,
+            running_execs: Mutex::new(HashMap::new()),
+            web_activity: OmnaraWebActivity::default(),
+            exec_output_colors: false,
+            diagnostics: Some(Mutex::new(DiagnosticLimiter::default())),
+            session_report: None,
+            session_report_settings: OmnaraSessionReport::default(),
+        }
+    }
+
+    /// Choose between mirroring every event and one summary note per turn.
+    pub fn with_mirroring(mut self, mirroring: OmnaraMirroring) -> Self {
+        self.mirroring = mirroring;
+        self
+    }
+
+    /// In `full` mode, also post a summary note when each turn ends.
+    pub fn with_turn_summary(mut self, enabled: bool) -> Self {
+        self.turn_summary = enabled;
+        self
+    }
+
+    /// Post a report of the session when it shuts down, if `settings`
+    /// enables it.
+    pub fn with_session_report(mut self, settings: &OmnaraSessionReport) -> Self {
+        self.session_report = settings
+            .enabled
+            .then(|| Mutex::new(SessionReportBuilder::new(self.client.clock().now())));
+        self.session_report_settings = settings.clone();
+        self
+    }
+
+    /// Enable or disable holding remote messages while a turn is running.
+    pub fn with_queued_remote_input(mut self, enabled: bool) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.queue_remote_input = enabled;
+        }
+        self
+    }
+
+    /// Post at most `max` approval prompts at once (`0`: no limit); later
+    /// ones wait until an earlier one is answered.
+    pub fn with_max_outstanding_approvals(mut self, max: usize) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.max_outstanding_approvals = max;
+        }
+        self
+    }
+
+    /// Gather exec approvals arriving within `window` of the first into one
+    /// prompt (`Duration::ZERO`: post each on its own).
+    pub fn with_exec_approval_batch(mut self, window: Duration) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.exec_batch_window = window;
+        }
+        self
+    }
+
+    /// Refuse remote messages larger than `bytes`.
+    pub fn with_max_remote_message_bytes(mut self, bytes: usize) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.max_remote_message_bytes = bytes;
+        }
+        self
+    }
+
+    /// Hold remote messages for local confirmation as `trust` requires.
+    pub fn with_workspace_trust(mut self, trust: OmnaraWorkspaceTrust) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.workspace_trust = trust;
+        }
+        self
+    }
+
+    /// Ask for push notifications on approval prompts per risk level.
+    pub fn with_approval_push(mut self, approval_push: ApprovalPush) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.approval_push = approval_push;
+        }
+        self
+    }
+
+    /// Hold notes and quiet approval prompts as `quiet_hours` requires.
+    pub fn with_quiet_hours(mut self, quiet_hours: Option<QuietHours>) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.quiet_hours = quiet_hours;
+        }
+        self
+    }
+
+    /// Submit each schedule's prompt at its runs, once the session is idle.
+    pub fn with_schedules(mut self, schedules: Vec<Schedule>) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.schedules = schedules;
+        }
+        self
+    }
+
+    /// Escalate approval prompts that go unanswered.
+    pub fn with_approval_escalation(mut self, chain: Option<EscalationChain>) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.escalation = chain;
+        }
+        self
+    }
+
+    /// Alert the dashboard about long turns and unanswered approvals.
+    pub fn with_alerts(mut self, alerts: Option<Alerts>) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.alerts = alerts;
         }
+        self
     }
 
-    pub fn from_env(
+    /// Check command run by `/preview` on a pending patch (empty: only
+    /// check that it applies).
+    pub fn with_patch_preview(mut self, command: Vec<String>, timeout: Duration) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.preview_command = command;
+            worker.preview_timeout = timeout;
+        }
+        self
+    }
+
+    /// Accept dashboard `/model` and `/provider` switches to `choices`.
+    pub(crate) fn with_model_choices(mut self, choices: ModelChoices) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.model_choices = choices;
+        }
+        self
+    }
+
+    /// The approval and sandbox policies a dashboard `/config` reports.
+    pub fn with_policies(
+        mut self,
+        approval_policy: AskForApproval,
+        sandbox_policy: SandboxPolicy,
+    ) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.approval_policy = approval_policy;
+            worker.sandbox_policy = sandbox_policy;
+        }
+        self
+    }
+
+    /// Post a progress note for commands running longer than `interval`,
+    /// refreshed in place every `interval` (`None` disables).
+    pub fn with_exec_progress(mut self, interval: Option<Duration>) -> Self {
+        self.exec_progress = interval;
+        self
+    }
+
+    /// Post notes for stream retries, turn errors, and sandbox failures.
+    pub fn with_error_notes(mut self, enabled: bool) -> Self {
+        self.diagnostics = enabled.then(|| Mutex::new(DiagnosticLimiter::default()));
+        self
+    }
+
+    /// Choose how web searches and page visits are mirrored.
+    pub fn with_web_activity(mut self, web_activity: OmnaraWebActivity) -> Self {
+        self.web_activity = web_activity;
+        self
+    }
+
+    /// Render command output colors as HTML in exec notes, for servers
+    /// that support structured messages.
+    pub fn with_exec_output_colors(mut self, enabled: bool) -> Self {
+        self.exec_output_colors = enabled;
+        self
+    }
+
+    /// Accept extra (e.g., localized) replies to approval prompts.
+    pub fn with_approval_synonyms(mut self, synonyms: &ApprovalSynonyms) -> Self {
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.approval_parser = ApprovalParser::new(synonyms).with_locale(worker.locale);
+        }
+        self
+    }
+
+    /// Post prompts and notes in `locale` and accept its approval replies.
+    pub fn with_locale(mut self, locale: OmnaraLocale) -> Self {
+        self.locale = locale;
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.locale = locale;
+            worker.approval_parser =
+                std::mem::take(&mut worker.approval_parser).with_locale(locale);
+        }
+        self
+    }
+
+    /// Render patch notes and approvals with `format`.
+    pub fn with_patch_format(mut self, format: PatchFormat) -> Self {
+        self.patch_format = format;
+        if let Ok(Some(worker)) = self.worker.get_mut() {
+            worker.patch_format = format;
+        }
+        self
+    }
+
+    /// Bridge `client`'s session, configured from the `[omnara]` section.
+    /// `parent` is the session this one was opened from, if any.
+    pub fn from_config(
+        client: OmnaraClient,
+        parent: Option<String>,
+        config: &Config,
         app_event_tx: AppEventSender,
         codex_op_tx: tokio::sync::mpsc::UnboundedSender<Op>,
-    ) -> Option<Self> {
-        match OmnaraClient::from_env() {
-            Some(client) => Some(Self::new(client, app_event_tx, codex_op_tx)),
-            None => {
-                debug!("OmnaraBridge: disabled (no API key)");
+    ) -> Self {
+        Self::new(
+            client
+                .with_poll_policy(PollPolicy::from(&config.omnara))
+                .with_diff_excludes(config.omnara.diff_exclude.clone())
+                .with_withheld_paths(&config.omnara.withhold_paths)
+                .with_diff_limits(DiffLimits::from(&config.omnara))
+                .with_diff_submodules(SubmoduleDiffs::from(&config.omnara))
+                .with_trace_propagation(config.omnara.trace_context)
+                .with_request_compression(config.omnara.compress_requests)
+                .with_markdown_dialect(config.omnara.markdown_dialect)
+                .with_transformers(&config.omnara.transformers)
+                .with_voice(&config.omnara.voice)
+                .with_uploads(&config.omnara.uploads, &config.cwd)
+                .with_circuit_breaker(CircuitPolicy::from(&config.omnara.circuit_breaker))
+                .with_session_metadata(SessionMetadata {
+                    parent_session_id: parent,
+                    ..session_metadata(config)
+                }),
+            app_event_tx,
+            codex_op_tx,
+            config.cwd.clone(),
+        )
+        .with_queued_remote_input(config.omnara.queue_remote_input)
+        .with_max_remote_message_bytes(config.omnara.max_remote_message_kb as usize * 1024)
+        .with_max_outstanding_approvals(config.omnara.max_outstanding_approvals)
+        .with_exec_approval_batch(Duration::from_millis(config.omnara.exec_approval_batch_ms))
+        .with_workspace_trust(config.omnara.workspace_trust)
+        .with_locale(config.omnara.locale)
+        .with_patch_format(PatchFormat::from(&config.omnara))
+        .with_approval_synonyms(&config.omnara.approval_synonyms)
+        .with_approval_push(config.omnara.approval_push)
+        .with_quiet_hours(
+            QuietHours::from_config(&config.omnara.quiet_hours).unwrap_or_else(|e| {
+                warn!("{e}; quiet hours are off");
                 None
+            }),
+        )
+        .with_schedules(
+            schedules_from_config(&config.omnara.schedules).unwrap_or_else(|e| {
+                warn!("{e}; scheduled prompts are off");
+                Vec::new()
+            }),
+        )
+        .with_approval_escalation(EscalationChain::from_config(
+            &config.omnara.approval_escalation,
+        ))
+        .with_alerts(Alerts::from_config(&config.omnara.alerts))
+        .with_patch_preview(
+            config.omnara.preview_command.clone(),
+            Duration::from_secs(config.omnara.preview_timeout_secs),
+        )
+        .with_exec_progress(
+            (config.omnara.exec_progress_secs > 0)
+                .then(|| Duration::from_secs(config.omnara.exec_progress_secs)),
+        )
+        .with_web_activity(config.omnara.web_activity)
+        .with_exec_output_colors(
+            config.omnara.exec_output_colors
+                && matches!(
+                    config.omnara.markdown_dialect,
+                    OmnaraMarkdownDialect::CommonMark | OmnaraMarkdownDialect::Gfm
+                ),
+        )
+        .with_error_notes(config.omnara.error_notes)
+        .with_model_choices(ModelChoices::from_config(config))
+        .with_policies(config.approval_policy, config.sandbox_policy.clone())
+        .with_mirroring(config.omnara.mirroring)
+        .with_turn_summary(config.omnara.turn_summary)
+        .with_session_report(&config.omnara.session_report)
+    }
+
+    pub fn session_id(&self) -> String {
+        self.client.session_id().to_string()
+    }
+
+    /// The named Omnara workspace this session reports to; None for the
+    /// default key.
+    pub fn workspace(&self) -> Option<&SelectedWorkspace> {
+        self.client.workspace()
+    }
+
+    /// What the bridge has done so far this session, for `/status`.
+    pub fn stats(&self) -> OmnaraStats {
+        self.client.metrics().stats()
+    }
+
+    /// Queue `command` for the worker, starting it on first use.
+    fn command(&self, command: BridgeCommand) {
+        self.start_worker();
+        let _ = self.commands.send(command);
+    }
+
+    /// Spawn the worker unless it is already running.
+    fn start_worker(&self) {
+        let worker = self.worker.lock().ok().and_then(|mut worker| worker.take());
+        if let Some(worker) = worker {
+            let handle = tokio::spawn(worker.run());
+            if let Ok(mut slot) = self.worker_handle.lock() {
+                *slot = Some(handle);
             }
         }
     }
@@ -63,259 +966,2794 @@ impl OmnaraBridge {
         self.client.append_log(&format!(
             "[Bridge] on_agent_message(request_after={request_after})\n"
         ));
-        let client = self.client.clone();
-        let app_event_tx = self.app_event_tx.clone();
-        let codex_op_tx = self.codex_op_tx.clone();
-        let pending = self.pending.clone();
-
-        let handle = tokio::spawn(async move {
-            info!("OmnaraBridge: sending agent message");
-            client.append_log("[Bridge] sending agent message via client\n");
-            let _ = client.send_agent_message(&message, false).await;
-            if request_after {
-                // Deterministically request input on the last sent message and begin polling.
-                info!("OmnaraBridge: requesting user input after agent message");
-                client.append_log("[Bridge] request_user_input_for_last_message\n");
-                let _ = client.request_user_input_for_last_message().await;
-                Self::start_polling_impl(client, app_event_tx, codex_op_tx, pending);
+        if !request_after
+            && self.is_digest()
+            && let Ok(mut state) = self.turn.lock()
+        {
+            if let Some(previous) = state.last_message.replace(message) {
+                state.summary.messages.push(previous);
             }
+            return;
+        }
+        if !request_after
+            && detect_question(&message, self.locale).is_some()
+            && let Ok(mut state) = self.turn.lock()
+        {
+            state.question = Some(message);
+            return;
+        }
+        self.command(BridgeCommand::AgentMessage {
+            message,
+            request_input: request_after,
         });
+    }
+
+    /// Post the held question message as is: the turn went on after it.
+    fn release_question(&self) {
+        let question = self
+            .turn
+            .lock()
+            .ok()
+            .and_then(|mut state| state.question.take());
+        if let Some(message) = question {
+            self.command(BridgeCommand::AgentMessage {
+                message,
+                request_input: false,
+            });
+        }
+    }
+
+    /// Post a turn's last message, as a question when it reads as one.
+    fn post_final_message(&self, message: String) {
+        match detect_question(&message, self.locale) {
+            Some(question) => self.command(BridgeCommand::Question(question)),
+            None => self.send_note(message),
+        }
+    }
 
-        self.last_agent_send_handle = Some(handle);
+    /// Called when Codex starts a turn. In queue mode, keep polling so remote
+    /// messages can be queued (or `/now` can interrupt) while the agent works.
+    /// Requests made during the turn join its trace (`traceparent`).
+    pub fn on_task_started(&mut self, traceparent: Option<&str>) {
+        info!("OmnaraBridge.on_task_started");
+        let trace = traceparent.and_then(TraceContext::parse);
+        if let Ok(mut state) = self.turn.lock() {
+            state.started = Some(self.client.clock().now());
+            state.tokens_at_start = state.tokens.clone();
+        }
+        self.command(BridgeCommand::TaskStarted { trace });
     }
 
-    /// Called when Codex signals a task completed. Await the last send (if any),
-    /// then request user input and start polling.
+    /// Called when Codex signals a task completed. Once the turn's last
+    /// message is posted, request user input and start polling.
     pub fn on_task_complete(&mut self) {
         info!("OmnaraBridge.on_task_complete");
         self.client.append_log("[Bridge] on_task_complete\n");
-        if let Some(handle) = self.last_agent_send_handle.take() {
-            let client = self.client.clone();
-            let app_event_tx = self.app_event_tx.clone();
-            let codex_op_tx = self.codex_op_tx.clone();
-            let pending = self.pending.clone();
-            tokio::spawn(async move {
-                let _ = handle.await;
-                info!("OmnaraBridge: last agent send completed; requesting user input");
-                client.append_log("[Bridge] awaiting last send complete\n");
-                let _ = client.request_user_input_for_last_message().await;
-                Self::start_polling_impl(client, app_event_tx, codex_op_tx, pending);
-            });
-        } else {
-            let client = self.client.clone();
-            let app_event_tx = self.app_event_tx.clone();
-            let codex_op_tx = self.codex_op_tx.clone();
-            let pending = self.pending.clone();
-            tokio::spawn(async move {
-                info!("OmnaraBridge: no last send; requesting user input now");
-                client.append_log("[Bridge] no last send; request input\n");
-                let _ = client.request_user_input_for_last_message().await;
-                Self::start_polling_impl(client, app_event_tx, codex_op_tx, pending);
-            });
+        self.stop_exec_progress();
+        self.flush_turn_summary();
+        let question = self
+            .turn
+            .lock()
+            .ok()
+            .and_then(|mut state| state.question.take());
+        if let Some(message) = question {
+            self.post_final_message(message);
         }
+        self.command(BridgeCommand::TaskComplete);
     }
 
-    /// Send the standard interrupt message (requires input) and start polling immediately.
-    /// Send a plain agent note to Omnara (no user input required).
-    pub fn send_note(&self, message: String) {
-        let client = self.client.clone();
-        tokio::spawn(async move {
-            let _ = client.send_agent_message(&message, false).await;
-        });
+    /// Record a session event for the report posted at shutdown. Any event
+    /// but the turn's end (or a token count) releases a held question.
+    pub fn on_session_event(&self, msg: &EventMsg) {
+        if !matches!(msg, EventMsg::TaskComplete(_) | EventMsg::TokenCount(_)) {
+            self.release_question();
+        }
+        if let Some(report) = &self.session_report
+            && let Ok(mut report) = report.lock()
+        {
+            report.observe(msg);
+        }
     }
-    pub fn on_user_interrupt(&mut self) {
-        info!("OmnaraBridge.on_user_interrupt");
-        self.client.append_log("[Bridge] on_user_interrupt\n");
-        let client = self.client.clone();
-        let app_event_tx = self.app_event_tx.clone();
-        let codex_op_tx = self.codex_op_tx.clone();
-        let pending = self.pending.clone();
-        tokio::spawn(async move {
-            if let Ok(id) = client
-                .send_agent_message("Tell the model what to do differently", true)
-                .await
-            {
-                client.set_last_read_message_id(id);
-            }
-            // No need to request input again; the send above already did requires_user_input.
-            info!("OmnaraBridge: interrupt sent; starting polling");
-            client.append_log("[Bridge] interrupt sent; start polling\n");
-            Self::start_polling_impl(client, app_event_tx, codex_op_tx, pending);
-        });
+
+    /// Record the session's token usage so far, as reported after each
+    /// model request.
+    pub fn on_token_usage(&self, total: &TokenUsage) {
+        if let Ok(mut state) = self.turn.lock() {
+            state.tokens = total.clone();
+        }
     }
 
-    /// Cancel any active poll (called when local user submits input).
-    pub fn cancel_polling(&self) {
-        debug!("OmnaraBridge.cancel_polling");
-        self.client.append_log("[Bridge] cancel_polling\n");
-        self.client.cancel_polling();
+    fn is_digest(&self) -> bool {
+        self.mirroring == OmnaraMirroring::Digest
     }
 
-    /// Mirror a local user message to Omnara as a USER message, marking it as read.
-    pub fn on_local_user_message(&self, text: String) {
-        info!(len = text.len(), "OmnaraBridge.on_local_user_message");
-        self.client.append_log("[Bridge] on_local_user_message\n");
-        let client = self.client.clone();
-        tokio::spawn(async move {
-            let _ = client.send_user_message(&text, true).await;
+    /// Add to the turn summary, if one will be posted.
+    fn record(&self, record: impl FnOnce(&mut TurnSummaryBuilder)) {
+        if (self.is_digest() || self.turn_summary)
+            && let Ok(mut state) = self.turn.lock()
+        {
+            record(&mut state.summary);
+        }
+    }
+
+    /// Post the summary of the turn so far and, in `digest` mode, its last
+    /// agent message.
+    fn flush_turn_summary(&mut self) {
+        let Some((mut summary, last_message, started, tokens)) =
+            self.turn.lock().ok().map(|mut state| {
+                let tokens = token_usage_since(&state.tokens, &state.tokens_at_start);
+                (
+                    std::mem::take(&mut state.summary),
+                    state.last_message.take(),
+                    state.started.take(),
+                    tokens,
+                )
+            })
+        else {
+            return;
+        };
+        let note = (!summary.is_empty()).then(|| {
+            summary.duration = started.map(|started| self.client.clock().elapsed(started));
+            summary.tokens = Some(tokens);
+            summary.build()
         });
+        if note.is_none() && last_message.is_none() {
+            return;
+        }
+        debug!("OmnaraBridge: posting turn summary");
+        if let Some(note) = note {
+            self.send_note(note);
+        }
+        if let Some(message) = last_message {
+            self.post_final_message(message);
+        }
     }
 
-    fn start_polling_impl(
-        client: OmnaraClient,
-        app_event_tx: AppEventSender,
-        codex_op_tx: tokio::sync::mpsc::UnboundedSender<Op>,
-        pending: Arc<Mutex<VecDeque<(String, ApprovalKind)>>>,
-    ) {
-        info!("OmnaraBridge: starting polling loop");
-        client.start_polling(move |text: String| {
-            if let Some(decision) = parse_approval_response(&text)
-                && let Ok(mut q) = pending.lock()
-                && let Some((_id, _kind)) = q.pop_front()
-            {
-                // Resolve the modal in UI; this will also send the op.
-                app_event_tx.send(AppEvent::ResolveApproval { decision });
-                return;
-            } else {
-                // Fallback: if an approval is pending but response text does not match
-                // a known option, treat it as a rejection (Abort).
-                if let Ok(mut q) = pending.lock()
-                    && let Some((_id, _kind)) = q.pop_front()
-                {
-                    app_event_tx.send(AppEvent::ResolveApproval {
-                        decision: codex_core::protocol::ReviewDecision::Abort,
-                    });
-                    return;
+    /// Start the progress ticker of a command (full mode only). If it is
+    /// still running after the configured interval, a progress note is
+    /// posted and then refreshed in place until it finishes.
+    pub fn on_exec_begin(&self, call_id: String, command: Vec<String>) {
+        let Some(interval) = self.exec_progress.filter(|_| !self.is_digest()) else {
+            return;
+        };
+        // The ticker queues commands directly.
+        self.start_worker();
+        let withheld = self.client.withheld_paths().touches_command(&command);
+        let mut tail = OutputTail::default();
+        if withheld {
+            tail.push(WITHHELD_PLACEHOLDER.as_bytes());
+        }
+        let tail = Arc::new(Mutex::new(tail));
+        let ticker = tokio::spawn({
+            let tail = tail.clone();
+            let call_id = call_id.clone();
+            let commands = self.commands.clone();
+            let clock = self.client.clock();
+            let started = clock.now();
+            async move {
+                loop {
+                    clock.sleep(interval).await;
+                    let elapsed = Duration::from_secs(clock.elapsed(started).as_secs());
+                    let Ok(note) = tail.lock().map(|tail| {
+                        codex_omnara::format::format_exec_progress_note(&command, elapsed, &tail)
+                    }) else {
+                        return;
+                    };
+                    let progress = BridgeCommand::ExecProgress {
+                        call_id: call_id.clone(),
+                        note,
+                    };
+                    if commands.send(progress).is_err() {
+                        return;
+                    }
                 }
             }
-            // 1) Show in TUI history like a user-typed message.
-            app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
-                history_cell::new_user_prompt(text.clone()),
-            )));
-
-            // 2) Send to the agent as user input.
-            let _ = codex_op_tx.send(Op::UserInput {
-                items: vec![InputItem::Text { text: text.clone() }],
-            });
-            let _ = codex_op_tx.send(Op::AddToHistory { text });
         });
+        if let Ok(mut running) = self.running_execs.lock()
+            && let Some(previous) = running.insert(
+                call_id,
+                RunningExec {
+                    tail,
+                    ticker,
+                    withheld,
+                },
+            )
+        {
+            previous.ticker.abort();
+        }
     }
 
-    /// On startup, publish a session start notice (requires input) and begin polling.
-    pub fn on_session_start(&mut self) {
-        info!("OmnaraBridge.on_session_start");
-        self.client.append_log("[Bridge] on_session_start\n");
-        let client = self.client.clone();
-        let app_event_tx = self.app_event_tx.clone();
-        let codex_op_tx = self.codex_op_tx.clone();
-        let pending = self.pending.clone();
-        tokio::spawn(async move {
-            if let Ok(id) = client
-                .send_agent_message("Codex session started - waiting for your input...", true)
-                .await
-            {
-                client.set_last_read_message_id(id);
-            }
-            Self::start_polling_impl(client, app_event_tx, codex_op_tx, pending);
-        });
+    /// Record streamed output of a command for its progress note.
+    pub fn on_exec_output(&self, call_id: &str, chunk: &[u8]) {
+        if let Ok(running) = self.running_execs.lock()
+            && let Some(exec) = running.get(call_id)
+            && !exec.withheld
+            && let Ok(mut tail) = exec.tail.lock()
+        {
+            tail.push(chunk);
+        }
     }
 
-    /// On shutdown, end the Omnara session and return a JoinHandle to await.
-    pub fn on_session_end(&self) -> tokio::task::JoinHandle<()> {
-        info!("OmnaraBridge.on_session_end");
-        self.client.append_log("[Bridge] on_session_end\n");
-        let client = self.client.clone();
-        tokio::spawn(async move {
-            let _ = client.end_session().await;
-        })
+    /// Stop the progress ticker of a finished command. Its progress note
+    /// becomes the command's end note, if one is posted.
+    pub fn on_exec_finished(&self, call_id: &str) {
+        let finished = self
+            .running_execs
+            .lock()
+            .ok()
+            .and_then(|mut running| running.remove(call_id));
+        if let Some(exec) = finished {
+            exec.ticker.abort();
+        }
     }
 
-    /// Send an approval request to Omnara (exec) and start polling.
-    pub fn send_exec_approval_request(
-        &mut self,
-        request_id: String,
+    /// Stop every progress ticker, e.g. when the turn ends without the
+    /// commands reporting their end.
+    fn stop_exec_progress(&self) {
+        let finished: Vec<(String, RunningExec)> = self
+            .running_execs
+            .lock()
+            .map(|mut running| running.drain().collect())
+            .unwrap_or_default();
+        for (_, exec) in finished {
+            exec.ticker.abort();
+        }
+    }
+
+    /// How exec notes render escape sequences in command output.
+    pub fn exec_output_ansi(&self) -> AnsiOutput {
+        if self.exec_output_colors && self.client.supports(Capability::StructuredMessages) {
+            AnsiOutput::Html
+        } else {
+            AnsiOutput::Strip
+        }
+    }
+
+    /// Mirror a finished command: a note with its metadata and an output
+    /// preview, replacing its progress note, or a turn summary entry.
+    pub fn on_exec_end(
+        &self,
+        call_id: &str,
         command: Vec<String>,
-        reason: Option<String>,
+        output: ExecOutput,
+        context: codex_omnara::format::ExecNoteContext,
     ) {
-        let approval_msg =
-            crate::omnara_format::format_exec_approval_request(&command, reason.as_deref());
-        let client = self.client.clone();
-        let app_event_tx = self.app_event_tx.clone();
-        let codex_op_tx = self.codex_op_tx.clone();
-        let pending = self.pending.clone();
-        tokio::spawn(async move {
-            if let Ok(id) = client.send_agent_message(&approval_msg, true).await {
-                client.set_last_read_message_id(id);
-                client.append_log(&format!(
-                    "Sent exec approval request - Request ID: {request_id}\n"
-                ));
-                if let Ok(mut q) = pending.lock() {
-                    q.push_back((request_id, ApprovalKind::Exec));
-                }
-                OmnaraBridge::start_polling_impl(
-                    client,
-                    app_event_tx,
-                    codex_op_tx,
-                    pending.clone(),
-                );
+        self.record(|summary| summary.record_command(&command, output.exit_code));
+        if self.is_digest() {
+            return;
+        }
+        let output = if self.client.withheld_paths().touches_command(&command) {
+            ExecOutput {
+                exit_code: output.exit_code,
+                stdout: WITHHELD_PLACEHOLDER.to_string(),
+                stderr: String::new(),
+                formatted_output: WITHHELD_PLACEHOLDER.to_string(),
             }
+        } else {
+            output
+        };
+        let attachment = codex_omnara::format::exec_output_attachment(&command, &output);
+        self.command(BridgeCommand::Status {
+            key: exec_status_key(call_id),
+            attachment,
+            build: Box::new(move |attachment| {
+                codex_omnara::format::format_exec_note(&command, &output, &context, attachment)
+            }),
+            last: true,
         });
     }
 
-    /// Send an approval request to Omnara (patch) and start polling.
-    #[allow(clippy::too_many_arguments)]
-    pub fn send_patch_approval_request(
-        &mut self,
-        request_id: String,
-        file_count: usize,
-        added_lines: usize,
-        removed_lines: usize,
-        reason: Option<String>,
-        grant_root: Option<std::path::PathBuf>,
-        patch_details: Option<String>,
+    /// Mirror a patch being applied: a summary note with diff details, or a
+    /// turn summary entry.
+    pub fn on_patch_apply(&self, changes: HashMap<PathBuf, FileChange>) {
+        self.record(|summary| summary.record_patch(&changes));
+        self.command(BridgeCommand::RecordPatch(changes.clone()));
+        if self.is_digest() {
+            return;
+        }
+        let withheld = self.client.withheld_paths().clone();
+        let format = self.patch_format;
+        let attachment = codex_omnara::format::patch_attachment(&changes, &withheld);
+        self.send_note_with_attachment(attachment, move |attachment| {
+            codex_omnara::format::format_patch_note(&changes, &withheld, format, attachment)
+        });
+    }
+
+    /// Mirror the start of an MCP tool call (full mode only; summaries record the end).
+    pub fn on_mcp_begin(&self, call_id: &str, invocation: &McpInvocation) {
+        if !self.is_digest() {
+            self.send_status(
+                format!("mcp:{call_id}"),
+                codex_omnara::format::format_mcp_begin_note(invocation),
+                false,
+            );
+        }
+    }
+
+    /// Mirror the result of an MCP tool call, replacing its begin note.
+    pub fn on_mcp_end(
+        &self,
+        call_id: &str,
+        invocation: &McpInvocation,
+        result: &Result<CallToolResult, String>,
+        duration: std::time::Duration,
     ) {
-        let approval_msg = crate::omnara_format::format_patch_approval_request(
-            file_count,
-            added_lines,
-            removed_lines,
-            reason.as_deref(),
-            grant_root.as_deref(),
-            patch_details.as_deref(),
+        self.record(|summary| summary.record_tool_call(invocation, result));
+        if self.is_digest() {
+            return;
+        }
+        self.send_status(
+            format!("mcp:{call_id}"),
+            codex_omnara::format::format_mcp_end_note(invocation, result, duration),
+            true,
         );
+    }
 
-        let client = self.client.clone();
-        let app_event_tx = self.app_event_tx.clone();
-        let codex_op_tx = self.codex_op_tx.clone();
-        let pending = self.pending.clone();
-        tokio::spawn(async move {
-            if let Ok(id) = client.send_agent_message(&approval_msg, true).await {
-                client.set_last_read_message_id(id);
-                client.append_log(&format!(
-                    "Sent patch approval request - Request ID: {request_id}\n"
-                ));
-                if let Ok(mut q) = pending.lock() {
-                    q.push_back((request_id, ApprovalKind::Patch));
-                }
-                OmnaraBridge::start_polling_impl(
-                    client,
-                    app_event_tx,
-                    codex_op_tx,
-                    pending.clone(),
-                );
-            }
+    /// Mirror the agent's plan as a checklist (full mode only), refreshed in
+    /// place as it changes during the turn.
+    pub fn on_plan_update(&self, update: &UpdatePlanArgs) {
+        if !self.is_digest() {
+            self.send_status(
+                "plan".to_string(),
+                codex_omnara::format::format_plan_note(update),
+                false,
+            );
+        }
+    }
+
+    /// Mirror a web search or page visit: a note, or a turn summary entry.
+    pub fn on_web_search_end(&self, event: &WebSearchEndEvent) {
+        let hide_urls = match self.web_activity {
+            OmnaraWebActivity::Off => return,
+            OmnaraWebActivity::Full => false,
+            OmnaraWebActivity::HideUrls => true,
+        };
+        self.record(|summary| summary.record_web_search(event, hide_urls));
+        if self.is_digest() {
+            return;
+        }
+        self.send_note(codex_omnara::format::format_web_search_note(
+            event, hide_urls,
+        ));
+    }
+
+    /// Post a note for `diagnostic` unless too many of its kind were posted
+    /// recently. Posted in both mirroring modes.
+    pub fn on_diagnostic(&self, diagnostic: Diagnostic) {
+        let Some(limiter) = self.diagnostics.as_ref() else {
+            return;
+        };
+        let now = self.client.clock().now();
+        let admitted = limiter
+            .lock()
+            .ok()
+            .and_then(|mut limiter| limiter.admit(diagnostic.kind(), now));
+        if let Some(suppressed) = admitted {
+            self.send_note(format_diagnostic_note(&diagnostic, suppressed));
+        }
+    }
+
+    /// Send a plain agent note to Omnara (no user input required). During
+    /// quiet hours it is held for the digest.
+    pub fn send_note(&self, message: String) {
+        self.command(BridgeCommand::Note {
+            attachment: None,
+            build: Box::new(move |_| message),
         });
     }
-}
 
-fn parse_approval_response(message: &str) -> Option<codex_core::protocol::ReviewDecision> {
-    let normalized = message.trim().to_lowercase();
-    if normalized == "yes" {
-        Some(codex_core::protocol::ReviewDecision::Approved)
-    } else if normalized == "always" {
-        Some(codex_core::protocol::ReviewDecision::ApprovedForSession)
-    } else if normalized == "no, provide feedback" || normalized == "no" {
-        Some(codex_core::protocol::ReviewDecision::Abort)
-    } else {
-        None
+    /// Post `message` as status `key`, editing the note already posted for
+    /// `key` where the server supports it; see [`BridgeCommand::Status`].
+    fn send_status(&self, key: String, message: String, last: bool) {
+        self.command(BridgeCommand::Status {
+            key,
+            attachment: None,
+            build: Box::new(move |_| message),
+            last,
+        });
     }
-}
+
+    /// Send a note whose inline preview is backed by a full artifact. The
+    /// attachment (if any) is uploaded first and `build` formats the note with
+    /// the resulting reference, or without one if the upload failed or the
+    /// server does not accept attachments.
+    pub fn send_note_with_attachment<F>(&self, attachment: Option<NoteAttachment>, build: F)
+    where
+        F: FnOnce(Option<&AttachmentRef>) -> String + Send + 'static,
+    {
+        self.command(BridgeCommand::Note {
+            attachment,
+            build: Box::new(build),
+        });
+    }
+
+    /// Send the standard interrupt message (requires input) and start polling,
+    /// unless the interrupt came from the dashboard.
+    pub fn on_user_interrupt(&mut self) {
+        info!("OmnaraBridge.on_user_interrupt");
+        self.client.append_log("[Bridge] on_user_interrupt\n");
+        self.stop_exec_progress();
+        self.flush_turn_summary();
+        self.command(BridgeCommand::UserInterrupt);
+    }
+
+    /// Record local activity (e.g., a key press) so an idle-suspended poller
+    /// resumes and polling returns to the fast interval.
+    pub fn note_local_activity(&self) {
+        self.client.note_activity();
+    }
+
+    /// Cancel any active poll (called when local user submits input).
+    pub fn cancel_polling(&self) {
+        debug!("OmnaraBridge.cancel_polling");
+        self.client.append_log("[Bridge] cancel_polling\n");
+        self.client.cancel_polling();
+        self.command(BridgeCommand::LocalInput);
+    }
+
+    /// Create a dashboard link for someone else to follow the session with
+    /// `permissions`; the result arrives as `AppEvent::OmnaraShareLink`.
+    pub fn create_share_link(&self, permissions: SharePermissions) {
+        info!(?permissions, "OmnaraBridge.create_share_link");
+        self.command(BridgeCommand::ShareLink(permissions));
+    }
+
+    /// Add, remove, or list the session's tags; the tags after the command
+    /// arrive as `AppEvent::OmnaraTags`.
+    pub fn tag(&self, command: TagCommand) {
+        info!(?command, "OmnaraBridge.tag");
+        self.command(BridgeCommand::Tag(command));
+    }
+
+    /// Keep dashboard `/model` and `/provider` notes in step with a local
+    /// switch.
+    pub(crate) fn on_model_changed(
+        &self,
+        model: &str,
+        effort: Option<ReasoningEffort>,
+        provider: &str,
+    ) {
+        self.command(BridgeCommand::CurrentModel {
+            model: model.to_string(),
+            effort,
+            provider: provider.to_string(),
+        });
+    }
+
+    /// Keep dashboard `/config` notes in step with a local `/approvals`
+    /// switch.
+    pub(crate) fn on_policies_changed(
+        &self,
+        approval_policy: AskForApproval,
+        sandbox_policy: &SandboxPolicy,
+    ) {
+        self.command(BridgeCommand::CurrentPolicies {
+            approval_policy,
+            sandbox_policy: sandbox_policy.clone(),
+        });
+    }
+
+    /// Fetch the dashboard exchanges after `since` (all of them when `None`);
+    /// they arrive as `AppEvent::OmnaraHistoryReplay`.
+    pub fn replay_history(&self, since: Option<DateTime<Utc>>) {
+        info!(?since, "OmnaraBridge.replay_history");
+        self.command(BridgeCommand::ReplayHistory { since });
+    }
+
+    /// Forward or discard a remote message held for local confirmation.
+    pub fn resolve_held_input(&self, text: String, forward: bool) {
+        info!(forward, "OmnaraBridge.resolve_held_input");
+        self.command(BridgeCommand::ResolveHeldInput { text, forward });
+    }
+
+    /// Recover or discard what a crashed run of the session left pending.
+    pub fn resolve_recovery(&self, recover: bool) {
+        info!(recover, "OmnaraBridge.resolve_recovery");
+        self.command(BridgeCommand::ResolveRecovery { recover });
+    }
+
+    /// Apply or drop the `/apply-patch` patch approved as `id` in the
+    /// terminal.
+    pub fn resolve_remote_patch(&self, id: String, decision: ReviewDecision) {
+        info!(id, ?decision, "OmnaraBridge.resolve_remote_patch");
+        self.command(BridgeCommand::ResolveRemotePatch { id, decision });
+    }
+
+    /// Mirror a local user message to Omnara as a USER message, marking it as read.
+    pub fn on_local_user_message(&self, text: String) {
+        info!(len = text.len(), "OmnaraBridge.on_local_user_message");
+        self.client.append_log("[Bridge] on_local_user_message\n");
+        self.command(BridgeCommand::LocalUserMessage(text));
+    }
+
+    /// Name the session after the first prompt unless it already has a title.
+    async fn title_from_first_prompt(client: &OmnaraClient, prompt: &str) {
+        if let Some(title) = codex_omnara::format::title_from_prompt(prompt) {
+            let _ = client.set_title_if_unset(&title).await;
+        }
+    }
+
+    /// On startup, publish a session start notice (requires input) and begin polling.
+    pub fn on_session_start(&mut self) {
+        info!("OmnaraBridge.on_session_start");
+        self.client.append_log("[Bridge] on_session_start\n");
+        self.command(BridgeCommand::SessionStart);
+    }
+
+    /// On shutdown, let the worker finish every queued send (notes,
+    /// approvals, the last agent message), then flush the client and end the
+    /// session. Gives up on the worker at `deadline`.
+    pub async fn shutdown(&mut self, deadline: Instant) {
+        info!("OmnaraBridge.shutdown");
+        self.client.append_log("[Bridge] shutdown\n");
+        self.release_question();
+        self.flush_turn_summary();
+        if let Some(builder) = self.session_report.take()
+            && let Ok(builder) = builder.into_inner()
+        {
+            self.command(BridgeCommand::SessionReport {
+                builder: Box::new(builder),
+                settings: self.session_report_settings.clone(),
+            });
+        }
+        self.command(BridgeCommand::Shutdown);
+        let worker = self
+            .worker_handle
+            .lock()
+            .ok()
+            .and_then(|mut handle| handle.take());
+        if let Some(worker) = worker
+            && tokio::time::timeout_at(deadline.into(), worker)
+                .await
+                .is_err()
+        {
+            warn!("OmnaraBridge: shutdown deadline reached before sends flushed");
+        }
+        let _ = self.client.shutdown(deadline).await;
+    }
+
+    /// Report files and directories the agent touched so repositories outside
+    /// the initial cwd are included in the session diff.
+    pub fn on_paths_touched(&self, paths: Vec<PathBuf>) {
+        let paths: Vec<PathBuf> = paths.iter().map(|p| self.cwd.join(p)).collect();
+        self.command(BridgeCommand::TrackPaths(paths));
+    }
+
+    /// Mirror the start of a code review.
+    pub fn on_review_started(&self, user_facing_hint: &str) {
+        info!("OmnaraBridge.on_review_started");
+        // The reviewer runs in this conversation, so its approvals show in
+        // the same modal.
+        self.on_sub_agent_started(
+            REVIEWER_ID.to_string(),
+            REVIEWER_ID.to_string(),
+            None,
+            Some(codex_omnara::format::format_review_started_note(
+                user_facing_hint,
+                self.locale,
+            )),
+        );
+    }
+
+    /// Mirror review results: one note per finding, then the verdict as a
+    /// prompt to accept the findings or request changes.
+    pub fn on_review_finished(&mut self, output: Option<ReviewOutputEvent>) {
+        info!("OmnaraBridge.on_review_finished");
+        self.on_sub_agent_finished(REVIEWER_ID.to_string(), None);
+        let Some(output) = output else {
+            self.send_note(codex_omnara::format::format_review_interrupted_note(
+                self.locale,
+            ));
+            return;
+        };
+        self.command(BridgeCommand::ReviewFinished(output));
+    }
+
+    /// Mirror sub-agent `id` (a reviewer, a parallel worker) as a thread of
+    /// the session: `start_note`, or else a `🧵` note, marks its start, and
+    /// until [`Self::on_sub_agent_finished`] messages, notes, and approval
+    /// prompts carry `label`. Approvals answered from the dashboard go to
+    /// `codex_op_tx` when given, instead of the local approval modal.
+    pub fn on_sub_agent_started(
+        &self,
+        id: String,
+        label: String,
+        codex_op_tx: Option<tokio::sync::mpsc::UnboundedSender<Op>>,
+        start_note: Option<String>,
+    ) {
+        info!(%id, %label, "OmnaraBridge.on_sub_agent_started");
+        let start_note = start_note
+            .or_else(|| (!self.is_digest()).then(|| format_sub_agent_started_note(&label)));
+        self.command(BridgeCommand::SubAgentStarted {
+            id,
+            label,
+            codex_op_tx,
+            start_note,
+        });
+    }
+
+    /// Close sub-agent `id`'s thread with a `🧵` note carrying `summary`.
+    pub fn on_sub_agent_finished(&self, id: String, summary: Option<String>) {
+        info!(%id, "OmnaraBridge.on_sub_agent_finished");
+        self.command(BridgeCommand::SubAgentFinished {
+            id,
+            summary,
+            announce: !self.is_digest(),
+        });
+    }
+
+    /// Send an approval request to Omnara (exec) and start polling.
+    pub fn send_exec_approval_request(
+        &mut self,
+        request_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: Option<String>,
+        closest_approved: Option<Vec<String>>,
+    ) {
+        let prompt = codex_core::omnara_approval::format_exec_approval_request(
+            &command,
+            &cwd,
+            reason.as_deref(),
+            closest_approved.as_deref(),
+            self.locale,
+        );
+        let risk = codex_core::omnara_approval::exec_approval_risk(&command, &cwd, None);
+        self.command(BridgeCommand::Approval {
+            id: request_id,
+            kind: ApprovalKind::Exec,
+            prompt,
+            risk: Some(risk),
+            changes: None,
+            exec: Some(ExecRequest {
+                command,
+                cwd,
+                reason,
+                closest_approved,
+            }),
+        });
+    }
+
+    /// Send a sandbox escalation approval request to Omnara and start polling.
+    pub fn send_escalation_approval_request(
+        &mut self,
+        request_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: Option<String>,
+        escalation: PermissionEscalation,
+    ) {
+        let prompt = codex_core::omnara_approval::format_escalation_approval_request(
+            &command,
+            &cwd,
+            reason.as_deref(),
+            escalation,
+            self.locale,
+        );
+        self.command(BridgeCommand::Approval {
+            id: request_id,
+            kind: ApprovalKind::Escalation,
+            prompt,
+            risk: Some(RiskLevel::High),
+            changes: None,
+            exec: None,
+        });
+    }
+
+    /// Send an MCP tool call approval request to Omnara and start polling.
+    pub fn send_mcp_approval_request(&mut self, request_id: String, invocation: &McpInvocation) {
+        let prompt =
+            codex_core::omnara_approval::format_mcp_approval_request(invocation, self.locale);
+        self.command(BridgeCommand::Approval {
+            id: request_id,
+            kind: ApprovalKind::Mcp,
+            prompt,
+            risk: None,
+            changes: None,
+            exec: None,
+        });
+    }
+
+    /// Send an approval request to Omnara (patch) and start polling.
+    pub fn send_patch_approval_request(
+        &mut self,
+        request_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+        reason: Option<String>,
+        grant_root: Option<PathBuf>,
+    ) {
+        let (details, added, removed) = codex_core::omnara_approval::format_patch_approval_details(
+            &changes,
+            self.client.withheld_paths(),
+            self.patch_format,
+            self.locale,
+        );
+        let prompt = codex_core::omnara_approval::format_patch_approval_request(
+            changes.len(),
+            added,
+            removed,
+            reason.as_deref(),
+            grant_root.as_deref(),
+            Some(details.as_str()),
+            self.locale,
+        );
+        let risk = codex_core::omnara_approval::patch_approval_risk(grant_root.as_deref());
+        self.command(BridgeCommand::Approval {
+            id: request_id,
+            kind: ApprovalKind::Patch,
+            prompt,
+            risk: Some(risk),
+            changes: Some(changes),
+            exec: None,
+        });
+    }
+
+    /// Post core's answer to a dashboard `/explain` request.
+    pub fn on_patch_explanation(&self, id: String, explanation: Option<String>) {
+        self.command(BridgeCommand::ExplainFinished { id, explanation });
+    }
+
+    /// Report the history's estimated size before and after a compaction.
+    pub fn on_context_compacted(&self, tokens_before: u64, tokens_after: u64) {
+        self.command(BridgeCommand::ContextCompacted {
+            tokens_before,
+            tokens_after,
+        });
+    }
+
+    /// Post core's answer to a dashboard `/context` request.
+    pub fn on_context_summary(&self, summary: ContextSummaryEvent) {
+        self.command(BridgeCommand::ContextSummary(summary));
+    }
+
+    /// Post an `ask_user` question as an input request and start polling.
+    pub fn send_question(&mut self, call_id: String, question: &str, options: &[String]) {
+        let prompt =
+            codex_core::omnara_approval::format_ask_user_request(question, options, self.locale);
+        self.command(BridgeCommand::Approval {
+            id: call_id,
+            kind: ApprovalKind::Question,
+            prompt,
+            risk: None,
+            changes: None,
+            exec: None,
+        });
+    }
+}
+
+impl Drop for OmnaraBridge {
+    /// Stop the worker (after the commands already queued) when the
+    /// conversation goes away, so replies to its session are not routed into
+    /// whichever conversation replaced it.
+    fn drop(&mut self) {
+        let _ = self.commands.send(BridgeCommand::Shutdown);
+    }
+}
+
+impl Worker {
+    async fn run(mut self) {
+        // Before the first send, so nothing reaches an older server in a
+        // shape it does not understand.
+        self.client.negotiate().await;
+        let commands = self.commands.clone();
+        self.circuit_monitor = Some(self.client.start_circuit_monitor(move |paused| {
+            let _ = commands.send(BridgeCommand::CircuitChanged { paused });
+        }));
+        let mut seen = self.client.subscribe_last_message_seen();
+        let app_event_tx = self.app_event_tx.clone();
+        self.seen_monitor = Some(tokio::spawn(async move {
+            while seen.changed().await.is_ok() {
+                let now = *seen.borrow_and_update();
+                app_event_tx.send(AppEvent::OmnaraMessageSeen(now));
+            }
+        }));
+        let mut reactions = self.client.subscribe_reactions();
+        let commands = self.commands.clone();
+        self.reaction_monitor = Some(tokio::spawn(async move {
+            while reactions.changed().await.is_ok() {
+                let reaction = reactions
+                    .borrow_and_update()
+                    .as_ref()
+                    .and_then(|reaction| ApprovalReaction::from_emoji(&reaction.emoji));
+                if let Some(reaction) = reaction {
+                    let _ = commands.send(BridgeCommand::Reaction(reaction));
+                }
+            }
+        }));
+        let now = Utc::now();
+        self.schedule_timers = std::iter::repeat_with(|| None)
+            .take(self.schedules.len())
+            .collect();
+        for index in 0..self.schedules.len() {
+            self.arm_schedule(index, now);
+        }
+        while let Some(command) = self.rx.recv().await {
+            if let BridgeCommand::Shutdown = command {
+                if let Some(timer) = self.quiet_timer.take() {
+                    timer.abort();
+                }
+                for (_, timer) in self.schedule_timers.drain(..).flatten() {
+                    timer.abort();
+                }
+                if let Some(monitor) = self.circuit_monitor.take() {
+                    monitor.abort();
+                }
+                if let Some(monitor) = self.seen_monitor.take() {
+                    monitor.abort();
+                }
+                if let Some(monitor) = self.reaction_monitor.take() {
+                    monitor.abort();
+                }
+                if let Some(window) = self.auto_approval.take() {
+                    window.timer.abort();
+                }
+                self.flush_quiet_notes().await;
+                self.client.cancel_polling();
+                break;
+            }
+            self.handle(command).await;
+            self.advance_approvals().await;
+            self.client
+                .metrics()
+                .set_remote_queue_depth(self.queued.len());
+        }
+    }
+
+    async fn handle(&mut self, command: BridgeCommand) {
+        match command {
+            BridgeCommand::AgentMessage {
+                message,
+                request_input,
+            } => {
+                info!("OmnaraBridge: sending agent message");
+                self.client
+                    .append_log("[Bridge] sending agent message via client\n");
+                let message = self.labeled(message);
+                let sent = self.client.send_agent_message(&message, false).await;
+                if request_input {
+                    // Request input on the message just sent and begin polling.
+                    info!("OmnaraBridge: requesting user input after agent message");
+                    self.client
+                        .append_log("[Bridge] request_user_input for the message just sent\n");
+                    let _ = match sent {
+                        Ok(message_id) => self.client.request_user_input(&message_id).await,
+                        Err(_) => self.client.request_user_input_for_last_message().await,
+                    };
+                    self.transition(BridgeEvent::InputRequested);
+                    self.start_polling();
+                }
+            }
+            BridgeCommand::Question(question) => {
+                info!("OmnaraBridge: sending the question the turn ended with");
+                let message = self.labeled(format_clarifying_question(&question, self.locale));
+                let _ = self.client.send_agent_message(&message, false).await;
+                self.question_options = question.options;
+            }
+            BridgeCommand::Note { attachment, build } => {
+                let uploaded = match attachment {
+                    Some(a) if self.client.supports(Capability::Attachments) => self
+                        .client
+                        .upload_attachment(&a.name, &a.bytes, a.mime)
+                        .await
+                        .ok(),
+                    _ => None,
+                };
+                let message = self.labeled(build(uploaded.as_ref()));
+                if self.is_quiet() {
+                    self.hold_note(message);
+                    return;
+                }
+                let _ = self.client.send_agent_message(&message, false).await;
+            }
+            BridgeCommand::ExecProgress { call_id, note } => {
+                // Without in-place updates each refresh would be a new note;
+                // progress is stale by the time a digest would carry it.
+                if !self.client.supports(Capability::MessageUpdates) || self.is_quiet() {
+                    return;
+                }
+                let note = self.labeled(note);
+                self.post_status(exec_status_key(&call_id), note, false)
+                    .await;
+            }
+            BridgeCommand::Status {
+                key,
+                attachment,
+                build,
+                last,
+            } => {
+                let uploaded = match attachment {
+                    Some(a) if self.client.supports(Capability::Attachments) => self
+                        .client
+                        .upload_attachment(&a.name, &a.bytes, a.mime)
+                        .await
+                        .ok(),
+                    _ => None,
+                };
+                let message = self.labeled(build(uploaded.as_ref()));
+                if self.is_quiet() {
+                    // Only a final status is still news when quiet hours end.
+                    if last {
+                        self.status_messages.remove(&key);
+                        self.hold_note(message);
+                    }
+                    return;
+                }
+                self.post_status(key, message, last).await;
+            }
+            BridgeCommand::SubAgentStarted {
+                id,
+                label,
+                codex_op_tx,
+                start_note,
+            } => {
+                if let Some(note) = start_note {
+                    if self.is_quiet() {
+                        self.hold_note(note);
+                    } else {
+                        let _ = self.client.send_agent_message(&note, false).await;
+                    }
+                }
+                self.active_sub_agents.retain(|active| *active != id);
+                self.active_sub_agents.push(id.clone());
+                self.sub_agents.insert(id, SubAgent { label, codex_op_tx });
+            }
+            BridgeCommand::SubAgentFinished {
+                id,
+                summary,
+                announce,
+            } => {
+                self.active_sub_agents.retain(|active| *active != id);
+                // Approvals still pending fall back to the local modal.
+                self.approval_agents.retain(|_, agent| *agent != id);
+                let Some(agent) = self.sub_agents.remove(&id) else {
+                    return;
+                };
+                if announce {
+                    let note = format_sub_agent_finished_note(&agent.label, summary.as_deref());
+                    let _ = self.client.send_agent_message(&note, false).await;
+                }
+            }
+            BridgeCommand::LocalUserMessage(text) => {
+                let _ = self.client.send_user_message(&text, true).await;
+                OmnaraBridge::title_from_first_prompt(&self.client, &text).await;
+            }
+            BridgeCommand::ReplayHistory { since } => {
+                let messages = match self.client.message_history(since).await {
+                    Ok(messages) => messages,
+                    Err(e) => {
+                        debug!("OmnaraBridge: no history to replay: {e}");
+                        return;
+                    }
+                };
+                // Messages after the last agent reply were not answered;
+                // polling hands them over as input instead.
+                let Some(last_reply) = messages
+                    .iter()
+                    .rposition(|message| message.sender == MessageSender::Agent)
+                else {
+                    return;
+                };
+                let mut messages = messages;
+                messages.truncate(last_reply + 1);
+                self.app_event_tx
+                    .send(AppEvent::OmnaraHistoryReplay(messages));
+            }
+            BridgeCommand::ShareLink(permissions) => {
+                let result = self
+                    .client
+                    .create_share_link(permissions)
+                    .await
+                    .map_err(|e| e.to_string());
+                self.app_event_tx.send(AppEvent::OmnaraShareLink {
+                    permissions,
+                    result,
+                });
+            }
+            BridgeCommand::Tag(command) => {
+                let result = self
+                    .client
+                    .apply_tag_command(&command)
+                    .await
+                    .map_err(|e| e.to_string());
+                self.app_event_tx.send(AppEvent::OmnaraTags(result));
+            }
+            BridgeCommand::CurrentModel {
+                model,
+                effort,
+                provider,
+            } => {
+                self.model_choices.set_model(model, effort);
+                self.model_choices.set_provider(provider);
+            }
+            BridgeCommand::CurrentPolicies {
+                approval_policy,
+                sandbox_policy,
+            } => {
+                self.approval_policy = approval_policy;
+                self.sandbox_policy = sandbox_policy;
+            }
+            BridgeCommand::TrackPaths(paths) => {
+                let client = self.client.clone();
+                let _ = tokio::task::spawn_blocking(move || {
+                    for path in &paths {
+                        client.track_repo_path(path);
+                    }
+                })
+                .await;
+            }
+            BridgeCommand::RecordPatch(changes) => {
+                let client = self.client.clone();
+                let _ = tokio::task::spawn_blocking(move || client.record_patch(&changes)).await;
+            }
+            BridgeCommand::SessionStart => {
+                if let Some(recovery) = self.client.recovery() {
+                    self.app_event_tx.send(AppEvent::ConfirmOmnaraRecovery {
+                        approvals: recovery.approvals.len(),
+                        unsent: recovery.unsent.len(),
+                    });
+                }
+                self.request_input(strings(self.locale).session_started)
+                    .await;
+                self.transition(BridgeEvent::SessionStarted);
+                self.start_polling();
+            }
+            BridgeCommand::TaskStarted { trace } => {
+                self.client.set_turn_trace(trace);
+                self.question_options.clear();
+                // A new turn's statuses get new notes rather than editing
+                // ones that have scrolled out of view.
+                self.status_messages.clear();
+                self.transition(BridgeEvent::TurnStarted);
+                if self.queue_remote_input {
+                    self.start_polling();
+                }
+            }
+            BridgeCommand::TaskComplete => {
+                self.forget_approvals();
+                self.transition(BridgeEvent::TurnEnded);
+                if let Some(index) = self.scheduled_turn.take() {
+                    let schedule = &self.schedules[index];
+                    let note =
+                        format_schedule_finished(schedule, self.next_run(index), self.locale);
+                    let _ = self.client.send_agent_message(&note, false).await;
+                }
+                if let Some(text) = self.queued.pop_front() {
+                    // Like local queued input: submit exactly one to begin the next turn.
+                    info!("OmnaraBridge: submitting queued remote message");
+                    self.client
+                        .append_log("[Bridge] submitting queued remote message\n");
+                    self.forward_user_input(text).await;
+                    return;
+                }
+                if let Some(index) = self.due_schedules.pop_front() {
+                    self.run_schedule(index).await;
+                    return;
+                }
+                info!("OmnaraBridge: turn complete; requesting user input");
+                self.client.append_log("[Bridge] request input\n");
+                let _ = self.client.request_user_input_for_last_message().await;
+                self.transition(BridgeEvent::InputRequested);
+                self.start_polling();
+            }
+            BridgeCommand::UserInterrupt => {
+                self.scheduled_turn = None;
+                self.withdraw_approvals().await;
+                if std::mem::take(&mut self.interrupted_remotely) {
+                    // The dashboard already sent what to do next.
+                    debug!("OmnaraBridge: interrupt came from the dashboard; skipping prompt");
+                    return;
+                }
+                self.transition(BridgeEvent::TurnEnded);
+                let dropped: Vec<String> = self.queued.drain(..).collect();
+                let prompt = codex_omnara::format::format_interrupt_prompt(&dropped, self.locale);
+                self.request_input(&prompt).await;
+                info!("OmnaraBridge: interrupt sent; starting polling");
+                self.client
+                    .append_log("[Bridge] interrupt sent; start polling\n");
+                self.transition(BridgeEvent::InputRequested);
+                self.start_polling();
+            }
+            BridgeCommand::Approval {
+                id,
+                kind,
+                prompt,
+                risk,
+                changes,
+                exec,
+            } => {
+                let prompt = match self.active_sub_agents.last() {
+                    Some(agent) => {
+                        self.approval_agents.insert(id.clone(), agent.clone());
+                        self.labeled(prompt)
+                    }
+                    None => prompt,
+                };
+                let approval = QueuedApproval {
+                    id,
+                    kind,
+                    prompt,
+                    risk,
+                    changes,
+                    exec,
+                };
+                if kind == ApprovalKind::Exec
+                    && approval.exec.is_some()
+                    && !self.exec_batch_window.is_zero()
+                {
+                    self.gather_exec_approval(approval);
+                } else {
+                    // Keep the prompts in the order core asked.
+                    self.flush_exec_batch().await;
+                    self.queue_approval(approval).await;
+                }
+            }
+            BridgeCommand::FlushExecBatch => self.flush_exec_batch().await,
+            BridgeCommand::Reaction(reaction) => self.react(reaction).await,
+            BridgeCommand::AutoApprovalEnded => {
+                if self.auto_approval.take().is_some() {
+                    let note = format_auto_approve_ended(self.locale);
+                    if self.is_quiet() {
+                        self.hold_note(note);
+                    } else {
+                        let _ = self.client.send_agent_message(&note, false).await;
+                    }
+                }
+            }
+            BridgeCommand::PreviewFinished { id, report } => {
+                self.post_approval_follow_up(&id, &report).await;
+            }
+            BridgeCommand::ExplainFinished { id, explanation } => {
+                let note = format_patch_explanation(explanation.as_deref(), self.locale);
+                self.post_approval_follow_up(&id, &note).await;
+            }
+            BridgeCommand::ContextCompacted {
+                tokens_before,
+                tokens_after,
+            } => {
+                let note = format_compaction_note(tokens_before, tokens_after);
+                let _ = self.client.send_agent_message(&note, false).await;
+            }
+            BridgeCommand::ContextSummary(summary) => {
+                let note = format_context_summary(&summary);
+                let _ = self.client.send_agent_message(&note, false).await;
+            }
+            BridgeCommand::ReviewFinished(output) => {
+                for finding in &output.findings {
+                    let note = codex_omnara::format::format_review_finding_note(
+                        finding,
+                        &self.cwd,
+                        self.locale,
+                    );
+                    let _ = self.client.send_agent_message(&note, false).await;
+                }
+                let verdict =
+                    codex_omnara::format::format_review_verdict_request(&output, self.locale);
+                if let Ok(id) = self.client.send_agent_message(&verdict, true).await {
+                    self.client.set_last_read_message_id(id.clone());
+                    self.transition(BridgeEvent::ApprovalRequested {
+                        id,
+                        kind: ApprovalKind::Review,
+                    });
+                    self.start_polling();
+                }
+            }
+            BridgeCommand::LocalInput => self.transition(BridgeEvent::LocalInput),
+            BridgeCommand::Remote(text) => {
+                self.client.metrics().record_remote_input();
+                self.receive(text).await;
+            }
+            BridgeCommand::ResolveHeldInput { text, forward } => {
+                if forward {
+                    self.send_to_agent(text).await;
+                    self.transition(BridgeEvent::TurnStarted);
+                } else {
+                    let note = format!("🛡️ {}", strings(self.locale).input_discarded);
+                    let _ = self.client.send_agent_message(&note, false).await;
+                }
+            }
+            BridgeCommand::QuietHoursEnded => {
+                self.quiet_timer = None;
+                if self.is_quiet() {
+                    // Another range follows straight on.
+                    self.schedule_quiet_hours_end();
+                } else {
+                    self.flush_quiet_notes().await;
+                }
+            }
+            BridgeCommand::ScheduleDue { index, at } => {
+                self.arm_schedule(index, at);
+                match self.state {
+                    BridgeState::Idle | BridgeState::AwaitingRemoteInput => {
+                        self.run_schedule(index).await;
+                    }
+                    // A run that is already waiting covers this one too.
+                    _ if self.due_schedules.contains(&index) => {}
+                    _ => self.due_schedules.push_back(index),
+                }
+            }
+            BridgeCommand::CircuitChanged { paused } => self.circuit_changed(paused).await,
+            BridgeCommand::ResolveRecovery { recover } => self.recover(recover).await,
+            BridgeCommand::ResolveRemotePatch { id, decision } => {
+                self.resolve_remote_patch(&id, decision).await
+            }
+            BridgeCommand::Escalate { id, step, waited } => self.escalate(id, step, waited).await,
+            BridgeCommand::TurnAlert { elapsed } => self.turn_alert(elapsed).await,
+            BridgeCommand::ApprovalAlert { id, waited } => self.approval_alert(id, waited).await,
+            BridgeCommand::SessionReport { builder, settings } => {
+                let client = self.client.clone();
+                let diff = tokio::task::spawn_blocking(move || client.diff_stats())
+                    .await
+                    .ok()
+                    .flatten();
+                let report = builder.build(self.client.clock().now(), diff.as_ref());
+                let uploaded = if !report.transcript.is_empty()
+                    && self.client.supports(Capability::Attachments)
+                {
+                    self.client
+                        .upload_attachment(
+                            TRANSCRIPT_NAME,
+                            report.transcript.as_bytes(),
+                            "text/markdown",
+                        )
+                        .await
+                        .ok()
+                } else {
+                    None
+                };
+                let note = report.format_note(&settings, uploaded.as_ref());
+                let _ = self.client.send_agent_message(&note, false).await;
+            }
+            BridgeCommand::Shutdown => {}
+        }
+    }
+
+    /// Post an approval prompt when there is room for it, otherwise hold it
+    /// with a note saying how many are waiting.
+    async fn queue_approval(&mut self, approval: QueuedApproval) {
+        if self.has_room_for_approval() {
+            self.post_approval(approval).await;
+        } else {
+            self.held_approvals.push_back(approval);
+            let note = format_pending_approvals_note(self.held_approvals.len(), self.locale);
+            let _ = self.client.send_agent_message(&note, false).await;
+        }
+    }
+
+    /// Add an exec approval to the current batch, opening the batch window
+    /// if it is the first.
+    fn gather_exec_approval(&mut self, approval: QueuedApproval) {
+        self.exec_batch.push(approval);
+        if self.exec_batch_timer.is_some() {
+            return;
+        }
+        let clock = self.client.clock();
+        let commands = self.commands.clone();
+        let window = self.exec_batch_window;
+        self.exec_batch_timer = Some(tokio::spawn(async move {
+            clock.sleep(window).await;
+            let _ = commands.send(BridgeCommand::FlushExecBatch);
+        }));
+    }
+
+    /// Queue the gathered exec approvals: a lone one as it is, several as
+    /// one numbered prompt answering all of them.
+    async fn flush_exec_batch(&mut self) {
+        if let Some(timer) = self.exec_batch_timer.take() {
+            timer.abort();
+        }
+        let mut batch = std::mem::take(&mut self.exec_batch);
+        if batch.len() <= 1 {
+            if let Some(approval) = batch.pop() {
+                self.queue_approval(approval).await;
+            }
+            return;
+        }
+        let requests: Vec<ExecRequest> = batch
+            .iter()
+            .filter_map(|approval| approval.exec.clone())
+            .collect();
+        let prompt = self.labeled(format_exec_batch_approval_request(&requests, self.locale));
+        let id = format!("batch-{}", batch[0].id);
+        let risk = batch.iter().filter_map(|approval| approval.risk).max();
+        self.exec_batches.insert(
+            id.clone(),
+            batch.into_iter().map(|approval| approval.id).collect(),
+        );
+        self.queue_approval(QueuedApproval {
+            id,
+            kind: ApprovalKind::ExecBatch,
+            prompt,
+            risk,
+            changes: None,
+            exec: None,
+        })
+        .await;
+    }
+
+    /// Post an approval prompt. It becomes the one replies answer unless an
+    /// earlier prompt is still waiting, in which case it is answered next.
+    async fn post_approval(&mut self, approval: QueuedApproval) {
+        let QueuedApproval {
+            id,
+            kind,
+            mut prompt,
+            risk,
+            changes,
+            exec,
+        } = approval;
+        if let Some(window) = &self.auto_approval
+            && window.covers(kind, risk)
+        {
+            let step = format!("auto-approved; window opened by {}", window.opened_by);
+            self.client.audit_approval(&id, &step);
+            self.resolve_approval(&id, kind, ReviewDecision::Approved);
+            return;
+        }
+        // A sub-agent whose approvals skip the terminal still tries the
+        // dashboard, the only place they can be answered.
+        if self.offline && self.sub_agent_op_tx(&id).is_none() {
+            self.client
+                .audit_approval(&id, "Omnara offline; left to the terminal");
+            return;
+        }
+        if !self.held_approvals.is_empty() {
+            let pending = format_pending_approvals_note(self.held_approvals.len(), self.locale);
+            prompt = format!("{pending}\n\n{prompt}");
+        }
+        let quiet = match (&self.quiet_hours, kind) {
+            (None, _) => QuietApproval::Notify,
+            // A question has no default answer.
+            (Some(_), ApprovalKind::Question) if self.is_quiet() => QuietApproval::Silent,
+            (Some(quiet_hours), _) => quiet_hours.approval(Utc::now(), risk),
+        };
+        if let QuietApproval::Decide(decision) = quiet {
+            self.client.audit_approval(
+                &id,
+                &format!("quiet hours; applied default decision {decision:?}"),
+            );
+            self.hold_note(format_quiet_hours_decision_note(
+                &prompt,
+                decision,
+                self.locale,
+            ));
+            self.resolve_approval(&id, kind, decision);
+            return;
+        }
+        let sent = match (quiet, risk) {
+            (QuietApproval::Silent, _) => self.client.send_approval_request(&prompt, false).await,
+            (_, Some(level)) => {
+                let push = self.approval_push.notifies(level);
+                self.client.send_approval_request(&prompt, push).await
+            }
+            (_, None) => self.client.send_agent_message(&prompt, true).await,
+        };
+        if let Ok(message_id) = sent {
+            self.client.set_last_read_message_id(message_id);
+            self.client.append_log(&format!(
+                "Sent {} approval request - Request ID: {id}\n",
+                kind.label()
+            ));
+            self.client
+                .audit_approval(&id, &format!("{} prompt sent", kind.label()));
+            let approval = QueuedApproval {
+                id,
+                kind,
+                prompt,
+                risk,
+                changes,
+                exec,
+            };
+            if matches!(self.state, BridgeState::AwaitingApproval { .. }) {
+                self.client.journal_approval(&approval.id, kind.label());
+                self.posted_approvals.push_back((approval, quiet));
+            } else {
+                self.await_approval(approval, quiet);
+            }
+        }
+    }
+
+    /// Make `approval`, already posted, the prompt replies answer.
+    fn await_approval(&mut self, approval: QueuedApproval, quiet: QuietApproval) {
+        let QueuedApproval {
+            id,
+            kind,
+            prompt,
+            risk,
+            changes,
+            exec,
+        } = approval;
+        self.transition(BridgeEvent::ApprovalRequested {
+            id: id.clone(),
+            kind,
+        });
+        self.pending_risk = risk;
+        self.pending_prompt = Some(prompt.clone());
+        self.pending_exec = exec;
+        self.pending_patch = changes;
+        // Quiet prompts wait silently instead of escalating.
+        if quiet == QuietApproval::Notify {
+            self.arm_approval_alert(id.clone());
+            self.start_escalation(id, kind, prompt);
+        }
+        self.start_polling();
+    }
+
+    /// Whether another approval prompt may be posted now under
+    /// `max_outstanding_approvals`.
+    fn has_room_for_approval(&self) -> bool {
+        let awaiting = matches!(self.state, BridgeState::AwaitingApproval { .. });
+        self.max_outstanding_approvals == 0
+            || usize::from(awaiting) + self.posted_approvals.len() < self.max_outstanding_approvals
+    }
+
+    /// Once the current approval is answered, move on to the next posted
+    /// prompt and post held ones while there is room.
+    async fn advance_approvals(&mut self) {
+        if matches!(self.state, BridgeState::AwaitingApproval { .. }) {
+            return;
+        }
+        if let Some((approval, quiet)) = self.posted_approvals.pop_front() {
+            self.await_approval(approval, quiet);
+        }
+        while self.has_room_for_approval()
+            && let Some(approval) = self.held_approvals.pop_front()
+        {
+            self.post_approval(approval).await;
+        }
+    }
+
+    /// Drop the approvals of a turn that ended; core cancels them.
+    fn forget_approvals(&mut self) {
+        for (approval, _) in self.posted_approvals.drain(..) {
+            self.client.settle_journaled_approval(&approval.id);
+        }
+        self.held_approvals.clear();
+        if let Some(timer) = self.exec_batch_timer.take() {
+            timer.abort();
+        }
+        self.exec_batch.clear();
+        self.exec_batches.clear();
+    }
+
+    /// Drop the approvals of an interrupted turn. Nothing can answer the
+    /// posted prompts any more, so each gets a note withdrawing it; a review
+    /// verdict prompt outlives the turn and stays.
+    async fn withdraw_approvals(&mut self) {
+        let mut withdrawn = Vec::new();
+        if let BridgeState::AwaitingApproval { id, kind } = &self.state
+            && *kind != ApprovalKind::Review
+        {
+            withdrawn.push((id.clone(), *kind));
+        }
+        withdrawn.extend(
+            self.posted_approvals
+                .iter()
+                .map(|(approval, _)| (approval.id.clone(), approval.kind)),
+        );
+        self.forget_approvals();
+        for (id, kind) in withdrawn {
+            self.client.settle_journaled_approval(&id);
+            self.client
+                .audit_approval(&id, "withdrawn; the turn was interrupted");
+            self.approval_agents.remove(&id);
+            let note = format_withdrawn_approval_note(kind.label(), self.locale);
+            if self.is_quiet() {
+                self.hold_note(note);
+            } else {
+                let _ = self.client.send_agent_message(&note, false).await;
+            }
+        }
+    }
+
+    /// Send the notes a crashed run left unsent and close its unanswered
+    /// approval prompts, or only forget them.
+    async fn recover(&mut self, recover: bool) {
+        let Some(recovery) = self.client.take_recovery() else {
+            return;
+        };
+        if !recover {
+            return;
+        }
+        for note in &recovery.unsent {
+            let _ = self.client.send_agent_message(note, false).await;
+        }
+        for approval in &recovery.approvals {
+            self.client
+                .audit_approval(&approval.id, "closed after a restart");
+            let note = format_interrupted_approval_note(&approval.kind, self.locale);
+            let _ = self.client.send_agent_message(&note, false).await;
+        }
+    }
+
+    fn transition(&mut self, event: BridgeEvent) {
+        if !self.state.expects(&event) {
+            debug!(state = ?self.state, ?event, "OmnaraBridge: unexpected event");
+        }
+        match &event {
+            BridgeEvent::ApprovalRequested { id, kind } => {
+                self.client.journal_approval(id, kind.label());
+                self.approval_sent_at = Some(self.client.clock().now())
+            }
+            BridgeEvent::ApprovalAnswered => {
+                if let Some(sent_at) = self.approval_sent_at.take() {
+                    let latency = self.client.clock().elapsed(sent_at);
+                    self.client.metrics().observe_approval_latency(latency);
+                    let (span, _) = self.client.span("approval_answered");
+                    span.in_scope(|| {
+                        info!(
+                            latency_ms = latency.as_millis() as u64,
+                            "OmnaraBridge: remote user answered approval"
+                        );
+                    });
+                }
+            }
+            BridgeEvent::TurnStarted => self.arm_turn_alert(),
+            BridgeEvent::TurnEnded => {
+                if let Some(timer) = self.turn_alert_timer.take() {
+                    timer.abort();
+                }
+            }
+            _ => {}
+        }
+        let next = self.state.next(&event);
+        if let BridgeState::AwaitingApproval { id, .. } = &self.state
+            && next != self.state
+        {
+            match &event {
+                BridgeEvent::LocalInput => {
+                    self.client.audit_approval(id, "resolved locally");
+                    self.client
+                        .metrics()
+                        .record_approval_resolved(ApprovalResolver::Local);
+                }
+                BridgeEvent::ApprovalAnswered => {
+                    self.client
+                        .metrics()
+                        .record_approval_resolved(ApprovalResolver::Remote);
+                }
+                _ => {}
+            }
+            self.client.settle_journaled_approval(id);
+            if let Some((_, timer)) = self.escalation_timer.take() {
+                timer.abort();
+            }
+            if let Some(timer) = self.approval_alert_timer.take() {
+                timer.abort();
+            }
+            self.pending_risk = None;
+            self.pending_prompt = None;
+            self.pending_exec = None;
+            self.pending_patch = None;
+            self.pending_rollback = None;
+        }
+        self.state = next;
+    }
+
+    /// Start the escalation chain for a tool approval prompt.
+    fn start_escalation(&mut self, id: String, kind: ApprovalKind, prompt: String) {
+        let Some(chain) = &self.escalation else {
+            return;
+        };
+        if matches!(
+            kind,
+            ApprovalKind::Review | ApprovalKind::Question | ApprovalKind::Rollback
+        ) {
+            return;
+        }
+        let commands = self.commands.clone();
+        let timer = chain.start(self.client.clock(), move |step, waited| {
+            let _ = commands.send(BridgeCommand::Escalate {
+                id: id.clone(),
+                step,
+                waited,
+            });
+        });
+        if let Some((_, previous)) = self.escalation_timer.replace((prompt, timer)) {
+            previous.abort();
+        }
+    }
+
+    /// Carry out an escalation step if its approval is still pending.
+    async fn escalate(&mut self, id: String, step: EscalationStep, waited: Duration) {
+        let kind = match &self.state {
+            BridgeState::AwaitingApproval { id: pending, kind } if *pending == id => *kind,
+            _ => return,
+        };
+        let Some((prompt, _)) = self.escalation_timer.as_ref() else {
+            return;
+        };
+        self.client.audit_approval(&id, &step.describe(waited));
+        match step {
+            EscalationStep::Remind => {
+                let reminder = codex_core::omnara_escalation::format_approval_reminder(
+                    prompt,
+                    waited,
+                    self.locale,
+                );
+                if let Ok(message_id) = self.client.send_urgent_agent_message(&reminder).await {
+                    self.client.set_last_read_message_id(message_id);
+                }
+            }
+            EscalationStep::Webhook { url } => {
+                let text = codex_core::omnara_escalation::format_webhook_text(
+                    &self.client.session_id().to_string(),
+                    prompt,
+                    waited,
+                );
+                let client = self.client.clone();
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        codex_core::omnara_escalation::send_webhook(client.http(), &url, &text)
+                            .await
+                    {
+                        client.audit_approval(&id, &format!("webhook failed: {e}"));
+                    }
+                });
+            }
+            EscalationStep::ApplyDefault(decision) => {
+                // Not an answer, so it stays out of the latency metric.
+                self.approval_sent_at = None;
+                self.transition(BridgeEvent::ApprovalAnswered);
+                let note = codex_core::omnara_escalation::format_default_decision_note(
+                    decision,
+                    waited,
+                    self.locale,
+                );
+                let _ = self.client.send_agent_message(&note, false).await;
+                self.resolve_approval(&id, kind, decision);
+            }
+        }
+    }
+
+    /// Start the turn alert's timer unless one is already running.
+    fn arm_turn_alert(&mut self) {
+        if self.turn_alert_timer.is_some() {
+            return;
+        }
+        let Some(alerts) = &self.alerts else {
+            return;
+        };
+        let commands = self.commands.clone();
+        self.turn_alert_timer = alerts.arm(AlertKind::Turn, self.client.clock(), move |elapsed| {
+            let _ = commands.send(BridgeCommand::TurnAlert { elapsed });
+        });
+    }
+
+    /// Start the approval alert's timer for prompt `id`.
+    fn arm_approval_alert(&mut self, id: String) {
+        let Some(alerts) = &self.alerts else {
+            return;
+        };
+        let commands = self.commands.clone();
+        let timer = alerts.arm(AlertKind::Approval, self.client.clock(), move |waited| {
+            let _ = commands.send(BridgeCommand::ApprovalAlert { id, waited });
+        });
+        if let Some(previous) = std::mem::replace(&mut self.approval_alert_timer, timer) {
+            previous.abort();
+        }
+    }
+
+    /// Post the turn alert if its turn is still running.
+    async fn turn_alert(&mut self, elapsed: Duration) {
+        if self.turn_alert_timer.is_none() {
+            return;
+        }
+        let note = codex_core::omnara_alerts::format_turn_alert(elapsed, self.locale);
+        if self.is_quiet() {
+            self.hold_note(note);
+            return;
+        }
+        info!(
+            minutes = elapsed.as_secs() / 60,
+            "OmnaraBridge: turn alert sent"
+        );
+        let _ = self.client.send_urgent_note(&note).await;
+        if let Some(url) = self.alerts.as_ref().and_then(Alerts::webhook_url) {
+            let text = codex_core::omnara_alerts::format_turn_alert_webhook_text(
+                &self.client.session_id().to_string(),
+                elapsed,
+            );
+            let url = url.to_string();
+            let client = self.client.clone();
+            tokio::spawn(async move {
+                if let Err(e) =
+                    codex_core::omnara_escalation::send_webhook(client.http(), &url, &text).await
+                {
+                    warn!("OmnaraBridge: turn alert webhook failed: {e}");
+                }
+            });
+        }
+    }
+
+    /// Re-send approval `id` as an alert if it is still pending.
+    async fn approval_alert(&mut self, id: String, waited: Duration) {
+        if !matches!(&self.state, BridgeState::AwaitingApproval { id: pending, .. } if *pending == id)
+        {
+            return;
+        }
+        let Some(prompt) = self.pending_prompt.clone() else {
+            return;
+        };
+        self.client.audit_approval(
+            &id,
+            &format!("no reply after {}m; alert sent", waited.as_secs() / 60),
+        );
+        let alert = codex_core::omnara_alerts::format_approval_alert(&prompt, waited, self.locale);
+        if let Ok(message_id) = self.client.send_urgent_agent_message(&alert).await {
+            self.client.set_last_read_message_id(message_id);
+        }
+        if let Some(url) = self.alerts.as_ref().and_then(Alerts::webhook_url) {
+            let text = codex_core::omnara_escalation::format_webhook_text(
+                &self.client.session_id().to_string(),
+                &prompt,
+                waited,
+            );
+            let url = url.to_string();
+            let client = self.client.clone();
+            tokio::spawn(async move {
+                if let Err(e) =
+                    codex_core::omnara_escalation::send_webhook(client.http(), &url, &text).await
+                {
+                    client.audit_approval(&id, &format!("alert webhook failed: {e}"));
+                }
+            });
+        }
+    }
+
+    /// `message` marked with the latest running sub-agent's label, if any.
+    fn labeled(&self, message: String) -> String {
+        match self
+            .active_sub_agents
+            .last()
+            .and_then(|id| self.sub_agents.get(id))
+        {
+            Some(agent) => format_sub_agent_message(&agent.label, &message),
+            None => message,
+        }
+    }
+
+    /// The op channel of the sub-agent approval `id` was posted for, if it
+    /// has its own.
+    fn sub_agent_op_tx(&self, id: &str) -> Option<tokio::sync::mpsc::UnboundedSender<Op>> {
+        let agent = self.approval_agents.get(id)?;
+        self.sub_agents.get(agent)?.codex_op_tx.clone()
+    }
+
+    /// Submit `decision` for approval `id`: to its sub-agent when it has its
+    /// own op channel, otherwise through the local approval modal, which
+    /// sends the op. For a batch prompt, every approval it batched gets it.
+    fn resolve_approval(&mut self, id: &str, kind: ApprovalKind, decision: ReviewDecision) {
+        if let Some(members) = self.exec_batches.remove(id) {
+            for member in members {
+                self.resolve_approval(&member, ApprovalKind::Exec, decision);
+            }
+            return;
+        }
+        let tx = self.sub_agent_op_tx(id);
+        self.approval_agents.remove(id);
+        let Some(tx) = tx else {
+            self.app_event_tx
+                .send(AppEvent::ResolveApproval { decision });
+            return;
+        };
+        let id = id.to_string();
+        let op = match kind {
+            ApprovalKind::Patch => Op::PatchApproval { id, decision },
+            ApprovalKind::Mcp => Op::McpToolCallApproval { id, decision },
+            _ => Op::ExecApproval { id, decision },
+        };
+        let _ = tx.send(op);
+    }
+
+    /// Edit the note posted for status `key`, or post a new one when there
+    /// is none, the server cannot edit notes, or the edit fails.
+    async fn post_status(&mut self, key: String, message: String, last: bool) {
+        let posted = if last {
+            self.status_messages.remove(&key)
+        } else {
+            self.status_messages.get(&key).cloned()
+        };
+        if let Some(message_id) = posted
+            && self.client.supports(Capability::MessageUpdates)
+            && self
+                .client
+                .update_agent_message(&message_id, &message)
+                .await
+                .is_ok()
+        {
+            return;
+        }
+        if let Ok(message_id) = self.client.send_agent_message(&message, false).await
+            && !last
+            && self.client.supports(Capability::MessageUpdates)
+        {
+            self.status_messages.insert(key, message_id);
+        }
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.quiet_hours
+            .as_ref()
+            .is_some_and(|quiet_hours| quiet_hours.is_quiet(Utc::now()))
+    }
+
+    /// Keep `note` for the digest posted when quiet hours end.
+    fn hold_note(&mut self, note: String) {
+        self.quiet_notes.push(note);
+        self.schedule_quiet_hours_end();
+    }
+
+    fn schedule_quiet_hours_end(&mut self) {
+        if self.quiet_timer.is_some() {
+            return;
+        }
+        let Some(remaining) = self
+            .quiet_hours
+            .as_ref()
+            .and_then(|quiet_hours| quiet_hours.remaining(Utc::now()))
+        else {
+            return;
+        };
+        let clock = self.client.clock();
+        let commands = self.commands.clone();
+        self.quiet_timer = Some(tokio::spawn(async move {
+            clock.sleep(remaining).await;
+            let _ = commands.send(BridgeCommand::QuietHoursEnded);
+        }));
+    }
+
+    /// Fire `ScheduleDue` at schedule `index`'s first run after `after`.
+    fn arm_schedule(&mut self, index: usize, after: DateTime<Utc>) {
+        let clock = self.client.clock();
+        let commands = self.commands.clone();
+        self.schedule_timers[index] = self.schedules[index].next_after(after).map(|at| {
+            let wait = (at - Utc::now()).to_std().unwrap_or_default();
+            let timer = tokio::spawn(async move {
+                clock.sleep(wait).await;
+                let _ = commands.send(BridgeCommand::ScheduleDue { index, at });
+            });
+            (at, timer)
+        });
+    }
+
+    fn next_run(&self, index: usize) -> Option<DateTime<Utc>> {
+        self.schedule_timers.get(index)?.as_ref().map(|(at, _)| *at)
+    }
+
+    /// Announce schedule `index`'s run on the dashboard and submit its
+    /// prompt. The prompt comes from the local config, so `workspace_trust`
+    /// does not hold it.
+    async fn run_schedule(&mut self, index: usize) {
+        let schedule = &self.schedules[index];
+        info!(schedule = %schedule.name, "OmnaraBridge: running scheduled prompt");
+        self.client
+            .append_log(&format!("[Bridge] scheduled prompt {}\n", schedule.name));
+        let note = format_schedule_started(schedule, self.next_run(index), self.locale);
+        let prompt = schedule.prompt.clone();
+        let _ = self.client.send_agent_message(&note, false).await;
+        // Stop waiting for a dashboard reply; the turn restarts polling.
+        self.client.cancel_polling();
+        self.client.note_activity();
+        self.scheduled_turn = Some(index);
+        self.send_to_agent(prompt).await;
+        self.transition(BridgeEvent::TurnStarted);
+    }
+
+    /// Post the notes held during quiet hours as one digest.
+    async fn flush_quiet_notes(&mut self) {
+        if self.quiet_notes.is_empty() {
+            return;
+        }
+        let notes = std::mem::take(&mut self.quiet_notes);
+        let digest = format_quiet_hours_digest(&notes, self.locale);
+        let _ = self.client.send_agent_message(&digest, false).await;
+    }
+
+    /// Warn in the terminal when Omnara requests pause and keep approvals
+    /// local until they resume; then send the messages queued meanwhile,
+    /// ask for input again if the dashboard was asked before, and let the
+    /// pending approval escalate again.
+    async fn circuit_changed(&mut self, paused: bool) {
+        self.offline = paused;
+        self.app_event_tx.send(AppEvent::OmnaraOffline(paused));
+        if paused {
+            // Nobody on the dashboard can answer in time, so no default
+            // decision or alert may fire for the pending prompt.
+            if let Some((_, timer)) = self.escalation_timer.take() {
+                timer.abort();
+            }
+            if let Some(timer) = self.approval_alert_timer.take() {
+                timer.abort();
+            }
+            let minutes = self.client.circuit_policy().open_for.as_secs().div_ceil(60);
+            self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                history_cell::new_warning_event(format!(
+                    "Omnara is unreachable; dashboard updates are queued and the server is retried every {minutes} min"
+                )),
+            )));
+            return;
+        }
+        let sent = self.client.send_queued_messages().await;
+        if sent > 0 && self.state == BridgeState::AwaitingRemoteInput {
+            let _ = self.client.request_user_input_for_last_message().await;
+        }
+        if let BridgeState::AwaitingApproval { id, kind } = &self.state
+            && let Some(prompt) = self.pending_prompt.clone()
+            && !self.is_quiet()
+        {
+            let (id, kind) = (id.clone(), *kind);
+            self.arm_approval_alert(id.clone());
+            self.start_escalation(id, kind, prompt);
+        }
+        let hint = (sent > 0).then(|| format!("sent {sent} queued message(s)"));
+        self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+            history_cell::new_info_event("Omnara is reachable again".to_string(), hint),
+        )));
+    }
+
+    /// Post `message` as requiring input and mark it as read, so polling
+    /// returns only replies to it.
+    async fn request_input(&self, message: &str) {
+        if let Ok(id) = self.client.send_agent_message(message, true).await {
+            self.client.set_last_read_message_id(id);
+        }
+    }
+
+    fn start_polling(&self) {
+        info!("OmnaraBridge: starting polling loop");
+        let commands = self.commands.clone();
+        self.client.start_polling(move |text: String| {
+            let _ = commands.send(BridgeCommand::Remote(text));
+        });
+    }
+
+    /// Strip escape sequences and control characters from a remote message,
+    /// then route it. An oversized message is refused with a note and
+    /// nothing changes; polling resumes.
+    async fn receive(&mut self, text: String) {
+        match sanitize_remote_message(&text, self.max_remote_message_bytes) {
+            Ok(text) => self.route(text).await,
+            Err(rejected) => {
+                warn!(%rejected, "OmnaraBridge: refusing remote message");
+                let note = format_rejected_message_note(&rejected, self.locale);
+                let _ = self.client.send_agent_message(&note, false).await;
+                self.start_polling();
+            }
+        }
+    }
+
+    /// Approval replies resolve the pending modal, commands act on the turn,
+    /// and anything else becomes user input (or is queued mid-turn).
+    async fn route(&mut self, text: String) {
+        match self.state.route(&text, self.queue_remote_input) {
+            Route::ApprovalReply(ApprovalKind::Review) => {
+                self.transition(BridgeEvent::ApprovalAnswered);
+                match parse_review_reply(&text, self.locale) {
+                    ReviewReply::Accept => {
+                        self.forward_user_input(ACCEPT_FINDINGS_PROMPT.to_string())
+                            .await;
+                    }
+                    ReviewReply::RequestChanges => {
+                        // Nothing to forward yet; ask for the changes and keep listening.
+                        self.request_input(strings(self.locale).what_changes).await;
+                        self.transition(BridgeEvent::InputRequested);
+                        self.start_polling();
+                    }
+                    ReviewReply::Feedback(feedback) => {
+                        self.forward_user_input(feedback).await;
+                    }
+                }
+            }
+            Route::ApprovalReply(ApprovalKind::Question) => {
+                if let BridgeState::AwaitingApproval { id, .. } = &self.state {
+                    let id = id.clone();
+                    match self.sub_agent_op_tx(&id) {
+                        Some(tx) => {
+                            let _ = tx.send(Op::AskUserAnswer { id, answer: text });
+                        }
+                        None => self.app_event_tx.send(AppEvent::AnswerQuestion {
+                            call_id: id,
+                            answer: text,
+                        }),
+                    }
+                }
+                self.transition(BridgeEvent::ApprovalAnswered);
+            }
+            Route::ApprovalReply(ApprovalKind::Rollback) => self.answer_rollback(&text).await,
+            Route::ApprovalReply(ApprovalKind::ExecBatch) => self.answer_exec_batch(&text).await,
+            Route::ApprovalReply(kind) => {
+                let answered = match &self.state {
+                    BridgeState::AwaitingApproval { id, .. } => Some(id.clone()),
+                    _ => None,
+                };
+                if let Some(id) = &answered
+                    && let Some(window) = self.approval_parser.parse_window(&text)
+                {
+                    let id = id.clone();
+                    let risk = self.pending_risk;
+                    self.transition(BridgeEvent::ApprovalAnswered);
+                    self.resolve_approval(&id, kind, ReviewDecision::Approved);
+                    self.open_auto_approval(id, kind, risk, window).await;
+                    return;
+                }
+                let excluded = match (&answered, &self.pending_patch) {
+                    (Some(_), Some(changes)) if kind == ApprovalKind::Patch => self
+                        .approval_parser
+                        .parse_partial_patch(&text, &patch_files(changes)),
+                    _ => None,
+                };
+                self.transition(BridgeEvent::ApprovalAnswered);
+                if let (Some(id), Some(excluded)) = (&answered, excluded) {
+                    self.client.audit_approval(
+                        id,
+                        &format!("approved from the dashboard except {excluded:?}"),
+                    );
+                    // Sent ahead of the approval, so core applies the patch
+                    // without these files.
+                    let tx = self
+                        .sub_agent_op_tx(id)
+                        .unwrap_or_else(|| self.codex_op_tx.clone());
+                    let _ = tx.send(Op::ExcludePatchFiles {
+                        id: id.clone(),
+                        paths: excluded.clone(),
+                    });
+                    self.resolve_approval(id, kind, ReviewDecision::Approved);
+                    self.send_to_agent(format_partial_approval_feedback(&excluded))
+                        .await;
+                    return;
+                }
+                // Resolve the modal in UI; this will also send the op. A reply that
+                // does not match a known option is treated as a rejection (Abort).
+                let options = match kind {
+                    ApprovalKind::Exec | ApprovalKind::Mcp => EXEC_APPROVAL_OPTIONS,
+                    ApprovalKind::Escalation => ESCALATION_APPROVAL_OPTIONS,
+                    _ => PATCH_APPROVAL_OPTIONS,
+                };
+                let ApprovalReply { decision, feedback } = self
+                    .approval_parser
+                    .parse(&text, options)
+                    .unwrap_or(ApprovalReply {
+                        decision: ReviewDecision::Abort,
+                        feedback: None,
+                    });
+                match &answered {
+                    Some(id) => {
+                        self.client.audit_approval(
+                            id,
+                            &format!("answered from the dashboard: {decision:?}"),
+                        );
+                        self.resolve_approval(id, kind, decision);
+                    }
+                    None => self
+                        .app_event_tx
+                        .send(AppEvent::ResolveApproval { decision }),
+                }
+                // A denial keeps the turn running, so the feedback reaches the
+                // model together with the rejected tool call's output.
+                if let Some(feedback) = feedback {
+                    self.send_to_agent(format_denial_feedback(&feedback)).await;
+                }
+            }
+            Route::Preview => self.preview().await,
+            Route::Explain => self.explain(),
+            Route::ShowHunk(index) => self.show_hunk(index).await,
+            Route::Rollback => self.rollback().await,
+            Route::ApplyPatch(diff) => {
+                let diff = diff.to_string();
+                self.receive_patch(diff).await;
+            }
+            Route::Now(message) => {
+                let message = message.to_string();
+                self.interrupt_with(message).await;
+            }
+            Route::NewTask(prompt) => {
+                let prompt = prompt.to_string();
+                self.start_new_task(prompt).await;
+            }
+            Route::Tag(args) => {
+                let args = args.to_string();
+                self.tag_remotely(&args).await;
+            }
+            Route::Model(name) => {
+                let name = name.to_string();
+                self.switch_model(&name).await;
+            }
+            Route::Provider(id) => {
+                let id = id.to_string();
+                self.switch_provider(&id).await;
+            }
+            Route::Compact => self.compact().await,
+            Route::Context => {
+                let _ = self.codex_op_tx.send(Op::GetContextSummary);
+                self.start_polling();
+            }
+            Route::Config => {
+                let note = format_config_note(
+                    &self.cwd,
+                    &self.model_choices.model_label(),
+                    self.model_choices.provider(),
+                    self.approval_policy,
+                    &self.sandbox_policy,
+                );
+                let _ = self.client.send_agent_message(&note, false).await;
+                self.start_polling();
+            }
+            Route::Env(filter) => {
+                let note = format_env_note(filter, std::env::vars());
+                let _ = self.client.send_agent_message(&note, false).await;
+                self.start_polling();
+            }
+            Route::Fork(prompt) => {
+                let prompt = prompt.to_string();
+                self.fork(&prompt).await;
+            }
+            Route::Queue => self.queue(text).await,
+            Route::Forward => {
+                let text = match std::mem::take(&mut self.question_options) {
+                    options if options.is_empty() => text,
+                    options => resolve_ask_user_reply(&text, &options),
+                };
+                self.forward_user_input(text).await;
+            }
+        }
+    }
+
+    /// Handle a dashboard `/tag` command: post the resulting tags, or why
+    /// they did not change, and keep listening.
+    async fn tag_remotely(&mut self, args: &str) {
+        let note = match parse_tag_command(args) {
+            Ok(command) => match self.client.apply_tag_command(&command).await {
+                Ok(tags) => format_tags_note(&tags),
+                Err(e) => format!("⚠️ Could not change the session's tags: {e}"),
+            },
+            Err(e) => format!("⚠️ {e}"),
+        };
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+    }
+
+    /// Handle a dashboard `/model` command: switch to the named model for
+    /// the next turn, or list the choices when none (or an unknown one) was
+    /// named.
+    async fn switch_model(&mut self, name: &str) {
+        let current = self.model_choices.model_label();
+        let note = match self.model_choices.resolve_model(name) {
+            Some((model, effort)) if !name.is_empty() => {
+                let _ = self.codex_op_tx.send(Op::OverrideTurnContext {
+                    cwd: None,
+                    approval_policy: None,
+                    sandbox_policy: None,
+                    model: Some(model.clone()),
+                    model_provider: None,
+                    effort: Some(effort),
+                    summary: None,
+                });
+                self.app_event_tx.send(AppEvent::UpdateModel(model.clone()));
+                self.app_event_tx
+                    .send(AppEvent::UpdateReasoningEffort(effort));
+                self.model_choices.set_model(model, effort);
+                format_switch_note("model", &current, &self.model_choices.model_label())
+            }
+            _ => format_choices_note(
+                "model",
+                &current,
+                &self.model_choices.models(),
+                (!name.is_empty()).then_some(name),
+            ),
+        };
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+    }
+
+    /// Handle a dashboard `/provider` command, as [`Self::switch_model`]
+    /// does for models.
+    async fn switch_provider(&mut self, id: &str) {
+        let current = self.model_choices.provider().to_string();
+        let note = if !id.is_empty() && self.model_choices.has_provider(id) {
+            let _ = self.codex_op_tx.send(Op::OverrideTurnContext {
+                cwd: None,
+                approval_policy: None,
+                sandbox_policy: None,
+                model: None,
+                model_provider: Some(id.to_string()),
+                effort: None,
+                summary: None,
+            });
+            self.app_event_tx
+                .send(AppEvent::UpdateModelProvider(id.to_string()));
+            self.model_choices.set_provider(id.to_string());
+            format_switch_note("provider", &current, id)
+        } else {
+            format_choices_note(
+                "provider",
+                &current,
+                self.model_choices.providers(),
+                (!id.is_empty()).then_some(id),
+            )
+        };
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+    }
+
+    /// Handle a dashboard `/compact` between turns; the sizes are posted when
+    /// core reports the compaction.
+    async fn compact(&mut self) {
+        if self.state == BridgeState::TurnActive {
+            let note = "⚠️ `/compact` is not available while Codex is working; send it again \
+                        when the turn ends.";
+            let _ = self.client.send_agent_message(note, false).await;
+            self.start_polling();
+            return;
+        }
+        let _ = self.codex_op_tx.send(Op::Compact);
+        self.transition(BridgeEvent::TurnStarted);
+    }
+
+    /// Handle `/preview` on a pending patch approval: apply it in a scratch
+    /// worktree and run the check command in the background, then post the
+    /// result. The approval stays pending meanwhile.
+    async fn preview(&mut self) {
+        let (BridgeState::AwaitingApproval { id, .. }, Some(changes)) =
+            (&self.state, self.pending_patch.clone())
+        else {
+            return;
+        };
+        let id = id.clone();
+        self.client.audit_approval(&id, "preview requested");
+        let note = format_patch_preview_started(&self.preview_command, self.locale);
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+        let cwd = self.cwd.clone();
+        let command = self.preview_command.clone();
+        let timeout = self.preview_timeout;
+        let commands = self.commands.clone();
+        let locale = self.locale;
+        tokio::spawn(async move {
+            let result = preview_patch(&cwd, &changes, &command, timeout).await;
+            let report = format_patch_preview_result(&result, locale);
+            let _ = commands.send(BridgeCommand::PreviewFinished { id, report });
+        });
+    }
+
+    /// Post a `/preview` or `/explain` result for approval `id`. While the
+    /// approval is still pending, the note asks for the decision again.
+    async fn post_approval_follow_up(&mut self, id: &str, note: &str) {
+        let pending = matches!(&self.state, BridgeState::AwaitingApproval { id: pending, .. } if pending == id);
+        if pending {
+            self.request_input(note).await;
+            self.start_polling();
+        } else {
+            let _ = self.client.send_agent_message(note, false).await;
+        }
+    }
+
+    /// Handle `/explain` on a pending patch approval: ask core for a
+    /// plain-language summary. The answer arrives as `ExplainFinished`; the
+    /// approval stays pending meanwhile.
+    fn explain(&mut self) {
+        let (BridgeState::AwaitingApproval { id, .. }, Some(changes)) =
+            (&self.state, self.pending_patch.clone())
+        else {
+            return;
+        };
+        self.client.audit_approval(id, "explanation requested");
+        let _ = self.codex_op_tx.send(Op::ExplainPatch {
+            id: id.clone(),
+            changes,
+        });
+        self.start_polling();
+    }
+
+    /// Answer a reaction on the pending approval prompt: 👍 and 👎 like the
+    /// same text replies, 🔁 by posting the prompt again with more detail.
+    /// Reactions on other messages are not answers.
+    async fn react(&mut self, reaction: ApprovalReaction) {
+        let BridgeState::AwaitingApproval { id, kind } = &self.state else {
+            self.start_polling();
+            return;
+        };
+        let (id, kind) = (id.clone(), *kind);
+        self.client
+            .audit_approval(&id, &format!("reaction {reaction:?} received"));
+        if let Some(reply) = reaction.reply() {
+            self.receive(reply.to_string()).await;
+            return;
+        }
+        let Some(prompt) = &self.pending_prompt else {
+            self.start_polling();
+            return;
+        };
+        let prompt = format_prompt_with_details(
+            prompt,
+            self.pending_exec.as_ref(),
+            self.pending_patch.as_ref(),
+            self.client.withheld_paths(),
+            self.locale,
+        );
+        self.client.append_log(&format!(
+            "Sent {} approval request again - Request ID: {id}\n",
+            kind.label()
+        ));
+        self.post_approval_follow_up(&id, &prompt).await;
+    }
+
+    /// Handle `show N` on a pending patch approval: post hunk N in full and
+    /// ask for the decision again.
+    async fn show_hunk(&mut self, index: usize) {
+        let (BridgeState::AwaitingApproval { id, .. }, Some(changes)) =
+            (&self.state, self.pending_patch.as_ref())
+        else {
+            return;
+        };
+        let id = id.clone();
+        let note = format_hunk_expansion(changes, self.client.withheld_paths(), index, self.locale);
+        self.post_approval_follow_up(&id, &note).await;
+    }
+
+    /// Handle `/fork [N]`: ask the app to fork the conversation before
+    /// prompt N. The fork opens its own linked session; until it does, and
+    /// for the note if it cannot, polling continues here. Refused mid-turn,
+    /// like `/rollback`.
+    async fn fork(&mut self, prompt: &str) {
+        let text = strings(self.locale);
+        if self.state == BridgeState::TurnActive {
+            let _ = self.client.send_agent_message(text.fork_busy, false).await;
+            self.start_polling();
+            return;
+        }
+        let prompt = match prompt {
+            "" => None,
+            prompt => match prompt.parse::<usize>() {
+                Ok(prompt) if prompt > 0 => Some(prompt),
+                _ => {
+                    self.request_input(text.fork_usage).await;
+                    self.start_polling();
+                    return;
+                }
+            },
+        };
+        info!(?prompt, "OmnaraBridge: /fork requested");
+        self.app_event_tx.send(AppEvent::OmnaraFork { prompt });
+        self.start_polling();
+    }
+
+    /// Handle `/rollback`: post the patch that undoes the session's changes
+    /// as an approval prompt. Refused mid-turn, while the agent may still be
+    /// editing the files.
+    async fn rollback(&mut self) {
+        let text = strings(self.locale);
+        if self.state == BridgeState::TurnActive {
+            let _ = self
+                .client
+                .send_agent_message(text.rollback_busy, false)
+                .await;
+            self.start_polling();
+            return;
+        }
+        let client = self.client.clone();
+        let plan = tokio::task::spawn_blocking(move || client.rollback_plan())
+            .await
+            .ok()
+            .flatten();
+        let plan = match plan {
+            Some(plan) if !plan.is_empty() => plan,
+            plan => {
+                let message = if plan.is_some() {
+                    text.rollback_nothing
+                } else {
+                    text.rollback_unavailable
+                };
+                self.request_input(message).await;
+                self.transition(BridgeEvent::InputRequested);
+                self.start_polling();
+                return;
+            }
+        };
+        let changes = plan.changes();
+        let prompt = format_rollback_request(
+            &changes,
+            self.client.withheld_paths(),
+            self.patch_format,
+            self.locale,
+        );
+        if let Ok(message_id) = self.client.send_agent_message(&prompt, true).await {
+            self.client.set_last_read_message_id(message_id.clone());
+            self.client
+                .audit_approval(&message_id, "rollback prompt sent");
+            self.transition(BridgeEvent::ApprovalRequested {
+                id: message_id,
+                kind: ApprovalKind::Rollback,
+            });
+            self.pending_patch = Some(changes);
+            self.pending_rollback = Some(plan);
+        }
+        self.start_polling();
+    }
+
+    /// Open an auto-approval window after approval `id` of `kind` and
+    /// `risk` was answered "yes for <duration>", replacing any open one.
+    async fn open_auto_approval(
+        &mut self,
+        id: String,
+        kind: ApprovalKind,
+        risk: Option<RiskLevel>,
+        window: Duration,
+    ) {
+        let kind = window_kind(kind);
+        self.client.audit_approval(
+            &id,
+            &format!(
+                "approved from the dashboard; auto-approving {} requests up to {risk:?} for {}s",
+                kind.label(),
+                window.as_secs()
+            ),
+        );
+        let clock = self.client.clock();
+        let commands = self.commands.clone();
+        let timer = tokio::spawn(async move {
+            clock.sleep(window).await;
+            let _ = commands.send(BridgeCommand::AutoApprovalEnded);
+        });
+        let previous = self.auto_approval.replace(AutoApproval {
+            kind,
+            risk,
+            opened_by: id,
+            timer,
+        });
+        if let Some(previous) = previous {
+            previous.timer.abort();
+        }
+        let note = format_auto_approve_started(kind.label(), window, self.locale);
+        let _ = self.client.send_agent_message(&note, false).await;
+    }
+
+    /// Resolve each approval of the pending batch prompt as the reply
+    /// decides. A reply that is not understood aborts them all, like one
+    /// to a single prompt.
+    async fn answer_exec_batch(&mut self, reply: &str) {
+        let BridgeState::AwaitingApproval { id, .. } = &self.state else {
+            return;
+        };
+        let id = id.clone();
+        if let Some(window) = self.approval_parser.parse_window(reply) {
+            let risk = self.pending_risk;
+            self.transition(BridgeEvent::ApprovalAnswered);
+            self.resolve_approval(&id, ApprovalKind::ExecBatch, ReviewDecision::Approved);
+            self.open_auto_approval(id, ApprovalKind::ExecBatch, risk, window)
+                .await;
+            return;
+        }
+        let members = self.exec_batches.remove(&id).unwrap_or_default();
+        let BatchReply {
+            decisions,
+            feedback,
+        } = self
+            .approval_parser
+            .parse_batch(reply, members.len())
+            .unwrap_or(BatchReply {
+                decisions: vec![ReviewDecision::Abort; members.len()],
+                feedback: None,
+            });
+        self.transition(BridgeEvent::ApprovalAnswered);
+        self.client
+            .audit_approval(&id, &format!("answered from the dashboard: {decisions:?}"));
+        for (member, decision) in members.iter().zip(decisions) {
+            self.resolve_approval(member, ApprovalKind::Exec, decision);
+        }
+        if let Some(feedback) = feedback {
+            self.send_to_agent(format_denial_feedback(&feedback)).await;
+        }
+    }
+
+    /// Apply the pending rollback if the reply approves it (optionally
+    /// leaving some files alone), then ask for the next instruction.
+    async fn answer_rollback(&mut self, reply: &str) {
+        let answered = match &self.state {
+            BridgeState::AwaitingApproval { id, .. } => id.clone(),
+            _ => return,
+        };
+        let plan = self.pending_rollback.take();
+        let excluded = self.pending_patch.as_ref().and_then(|changes| {
+            self.approval_parser
+                .parse_partial_patch(reply, &patch_files(changes))
+        });
+        self.transition(BridgeEvent::ApprovalAnswered);
+        let approved = excluded.is_some()
+            || self
+                .approval_parser
+                .parse(reply, PATCH_APPROVAL_OPTIONS)
+                .is_some_and(|reply| reply.decision == ReviewDecision::Approved);
+        let note = match plan {
+            Some(mut plan) if approved => {
+                plan.exclude(&excluded.unwrap_or_default());
+                self.client
+                    .audit_approval(&answered, "rollback approved from the dashboard");
+                let result = tokio::task::spawn_blocking(move || plan.apply())
+                    .await
+                    .unwrap_or(Err(RollbackError::Interrupted));
+                let note = format_rollback_result(&result, self.locale);
+                if let Ok(count) = result {
+                    self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                        history_cell::new_info_event(
+                            format!("Rolled back {count} file(s) from Omnara"),
+                            None,
+                        ),
+                    )));
+                }
+                note
+            }
+            _ => {
+                self.client
+                    .audit_approval(&answered, "rollback declined from the dashboard");
+                format!("↩️ {}", strings(self.locale).rollback_cancelled)
+            }
+        };
+        self.request_input(&note).await;
+        self.transition(BridgeEvent::InputRequested);
+        self.start_polling();
+    }
+
+    /// Handle `/apply-patch`: check the diff against the working tree and
+    /// show it in the terminal for approval (answered as
+    /// `ResolveRemotePatch`). Refused mid-turn, like `/rollback`.
+    async fn receive_patch(&mut self, diff: String) {
+        if self.state == BridgeState::TurnActive {
+            let _ = self
+                .client
+                .send_agent_message(strings(self.locale).apply_patch_busy, false)
+                .await;
+            self.start_polling();
+            return;
+        }
+        let cwd = self.cwd.clone();
+        let parsed = tokio::task::spawn_blocking(move || RemotePatch::parse(&diff, &cwd))
+            .await
+            .unwrap_or(Err(RemotePatchError::Interrupted));
+        let patch = match parsed {
+            Ok(patch) => patch,
+            Err(err) => {
+                info!("OmnaraBridge: refused /apply-patch: {err}");
+                self.request_input(&format_remote_patch_refused(&err, self.locale))
+                    .await;
+                self.transition(BridgeEvent::InputRequested);
+                self.start_polling();
+                return;
+            }
+        };
+        self.remote_patch_seq += 1;
+        let id = format!("{REMOTE_PATCH_ID_PREFIX}{}", self.remote_patch_seq);
+        self.app_event_tx.send(AppEvent::ConfirmRemotePatch {
+            id: id.clone(),
+            changes: patch.changes().clone(),
+        });
+        let note = format_remote_patch_received(patch.len(), self.locale);
+        self.remote_patches.insert(id, patch);
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+    }
+
+    /// Apply the `/apply-patch` patch `id` if the terminal approved it, then
+    /// report the outcome on the dashboard.
+    async fn resolve_remote_patch(&mut self, id: &str, decision: ReviewDecision) {
+        let Some(patch) = self.remote_patches.remove(id) else {
+            return;
+        };
+        let note = match decision {
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+                let result = tokio::task::spawn_blocking(move || patch.apply())
+                    .await
+                    .unwrap_or(Err(RemotePatchError::Interrupted));
+                let message = match &result {
+                    Ok(count) => format!("Applied the patch from Omnara to {count} file(s)"),
+                    Err(err) => format!("The patch from Omnara failed: {err}"),
+                };
+                self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                    history_cell::new_info_event(message, None),
+                )));
+                format_remote_patch_result(&result, self.locale)
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                format!("📥 {}", strings(self.locale).apply_patch_declined)
+            }
+        };
+        if self.state == BridgeState::TurnActive {
+            let _ = self.client.send_agent_message(&note, false).await;
+        } else {
+            self.request_input(&note).await;
+            self.transition(BridgeEvent::InputRequested);
+            self.start_polling();
+        }
+    }
+
+    /// Hold a message until the turn finishes, acknowledge it on the dashboard,
+    /// and keep listening.
+    async fn queue(&mut self, text: String) {
+        self.queued.push_back(text);
+        let waiting = self.queued.len();
+        info!(waiting, "OmnaraBridge: queued remote message during turn");
+        let note = codex_omnara::format::format_queued_input_note(waiting, self.locale);
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+    }
+
+    /// Handle `/now <message>`: interrupt a running turn and send `message`
+    /// right away. With no message, this is a plain interrupt.
+    async fn interrupt_with(&mut self, message: String) {
+        if self.state == BridgeState::TurnActive {
+            info!("OmnaraBridge: /now interrupting current turn");
+            self.interrupted_remotely = !message.is_empty();
+            let _ = self.codex_op_tx.send(Op::Interrupt);
+            self.transition(BridgeEvent::TurnEnded);
+        }
+        if !message.is_empty() {
+            self.forward_user_input(message).await;
+        }
+    }
+
+    /// Handle `/new-task <prompt>`: end the running turn, post a divider that
+    /// closes out the previous task (with its final diff), move the diff
+    /// baseline, then start the new task. Queued messages belonged to the old
+    /// task and are dropped.
+    async fn start_new_task(&mut self, prompt: String) {
+        if prompt.is_empty() {
+            self.request_input(strings(self.locale).new_task_usage)
+                .await;
+            self.start_polling();
+            return;
+        }
+        if self.state == BridgeState::TurnActive {
+            info!("OmnaraBridge: /new-task interrupting current turn");
+            self.interrupted_remotely = true;
+            let _ = self.codex_op_tx.send(Op::Interrupt);
+            self.transition(BridgeEvent::TurnEnded);
+        }
+        let dropped: Vec<String> = self.queued.drain(..).collect();
+        let divider = codex_omnara::format::format_new_task_divider(&prompt, &dropped, self.locale);
+        let _ = self.client.send_agent_message(&divider, false).await;
+        let client = self.client.clone();
+        let _ = tokio::task::spawn_blocking(move || client.checkpoint_diff()).await;
+        self.forward_user_input(prompt).await;
+    }
+
+    /// Start a turn with `text` as if the local user had typed it, unless
+    /// `workspace_trust` holds it for confirmation in the terminal.
+    async fn forward_user_input(&mut self, text: String) {
+        if let Some(suspicions) = codex_omnara::guard::screen(&text, self.workspace_trust) {
+            self.hold(text, suspicions).await;
+            return;
+        }
+        self.send_to_agent(text).await;
+        self.transition(BridgeEvent::TurnStarted);
+    }
+
+    /// Ask the local user whether to forward `text`; the answer comes back
+    /// as `ResolveHeldInput`. Polling resumes meanwhile.
+    async fn hold(&mut self, text: String, suspicions: Vec<Suspicion>) {
+        info!(
+            ?suspicions,
+            "OmnaraBridge: holding remote message for confirmation"
+        );
+        let reasons = suspicions.iter().map(ToString::to_string).collect();
+        self.app_event_tx
+            .send(AppEvent::ConfirmRemoteInput { text, reasons });
+        let note = format!("🛡️ {}", strings(self.locale).input_held);
+        let _ = self.client.send_agent_message(&note, false).await;
+        self.start_polling();
+    }
+
+    /// Pass remote text to the agent without changing the bridge state (e.g.
+    /// denial feedback for the running turn).
+    async fn send_to_agent(&self, text: String) {
+        // Show in TUI history like a user-typed message.
+        self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+            history_cell::new_user_prompt(text.clone()),
+        )));
+
+        // Send to the agent as user input, followed by any `@file` context.
+        let context = crate::omnara_context::collect_file_context(&text, &self.cwd);
+        let mut items = vec![InputItem::Text { text: text.clone() }];
+        items.extend(context.items);
+        let _ = self.codex_op_tx.send(Op::UserInput { items });
+        let _ = self
+            .codex_op_tx
+            .send(Op::AddToHistory { text: text.clone() });
+
+        // Remote prompts can be the first of the session, so they name it too.
+        // Skipped `@file` references are reported back to the dashboard.
+        OmnaraBridge::title_from_first_prompt(&self.client, &text).await;
+        for (path, reason) in context.failures {
+            let note = codex_omnara::format::format_file_context_failure(&path, &reason);
+            let _ = self.client.send_agent_message(&note, false).await;
+        }
+    }
+}
+
+/// Sub-agent id and label of the reviewer `/review` runs.
+const REVIEWER_ID: &str = "reviewer";
+
+/// Status key of command `call_id`'s progress and end notes.
+fn exec_status_key(call_id: &str) -> String {
+    format!("exec:{call_id}")
+}
+
+fn session_metadata(config: &Config) -> SessionMetadata {
+    SessionMetadata {
+        model: Some(config.model.clone()),
+        model_provider: Some(config.model_provider_id.clone()),
+        codex_version: Some(crate::version::CODEX_CLI_VERSION.to_string()),
+        ..SessionMetadata::detect(&config.cwd).with_labels(&config.omnara)
+    }
+}
+
+/// Time of the last line recorded in the rollout at `path`: when this
+/// terminal last saw the conversation. Read it before resuming, while nothing
+/// new has been appended.
+pub(crate) fn last_rollout_timestamp(path: &Path) -> Option<DateTime<Utc>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    contents.lines().rev().find_map(|line| {
+        let line: serde_json::Value = serde_json::from_str(line).ok()?;
+        let timestamp = DateTime::parse_from_rfc3339(line.get("timestamp")?.as_str()?).ok()?;
+        Some(timestamp.with_timezone(&Utc))
+    })
+}
+
+/// Accepts the English replies and `locale`'s option labels and yes/no words.
+fn parse_review_reply(message: &str, locale: OmnaraLocale) -> ReviewReply {
+    let text = strings(locale);
+    let reply = normalize_reply(message);
+    let matches = |label: &str, words: &[&str]| {
+        reply == normalize_reply(label) || words.contains(&reply.as_str())
+    };
+    match reply.as_str() {
+        "accept findings" | "accept" | "yes" => ReviewReply::Accept,
+        "request changes" | "no" => ReviewReply::RequestChanges,
+        _ if matches(text.accept_findings, text.yes_words) => ReviewReply::Accept,
+        _ if matches(text.request_changes, text.no_words) => ReviewReply::RequestChanges,
+        _ => ReviewReply::Feedback(message.trim().to_string()),
+    }
+}
+
+/// Tokens used between two session totals.
+fn token_usage_since(total: &TokenUsage, start: &TokenUsage) -> TokenUsage {
+    TokenUsage {
+        input_tokens: total.input_tokens.saturating_sub(start.input_tokens),
+        cached_input_tokens: total
+            .cached_input_tokens
+            .saturating_sub(start.cached_input_tokens),
+        output_tokens: total.output_tokens.saturating_sub(start.output_tokens),
+        reasoning_output_tokens: total
+            .reasoning_output_tokens
+            .saturating_sub(start.reasoning_output_tokens),
+        total_tokens: total.total_tokens.saturating_sub(start.total_tokens),
+    }
+}
+
+impl From<CommandOutput> for ExecOutput {
+    fn from(output: CommandOutput) -> Self {
+        Self {
+            exit_code: output.exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            formatted_output: output.formatted_output,
+        }
+    }
+}
+
+/// Plain-text rendering of terminal lines, with styling dropped, for a
+/// snapshot note.
+pub(crate) fn snapshot_text(lines: &[Line<'_>]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let row: String = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,135 @@
+//! One Omnara session per conversation.
+//!
+//! A Codex process can drive several conversations (a fork, a new session,
+//! other agents). [`BridgeManager`] gives each its own Omnara session so its
+//! events and approval prompts land there: the first bridge opened uses the
+//! process session (`OMNARA_SESSION_ID`), every later one a child session
+//! linked to its parent by notes on both sides and `parent_session_id`
+//! metadata. A fork's parent is the session it was forked from; any other
+//! conversation's is the process session.
+
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Instant;
+
+use codex_core::config::Config;
+use codex_core::omnara_client::OmnaraClient;
+use codex_core::omnara_i18n::strings;
+use codex_core::protocol::Op;
+use tracing::debug;
+use tracing::info;
+
+use super::OmnaraBridge;
+use crate::app_event_sender::AppEventSender;
+use codex_omnara::format::format_linked_session_note;
+use codex_omnara::format::format_parent_session_note;
+
+/// Why a conversation after the first was opened; names it in the parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LinkedSession {
+    NewSession,
+    Fork,
+}
+
+pub(crate) struct BridgeManager {
+    root: OmnaraClient,
+    /// Clients of every session opened so far; the first is `root`.
+    sessions: Mutex<Vec<OmnaraClient>>,
+}
+
+impl BridgeManager {
+    pub fn new(root: OmnaraClient) -> Self {
+        Self {
+            root,
+            sessions: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn from_env(config: &Config) -> Option<Self> {
+        let Some(client) = OmnaraClient::from_config(&config.omnara, &config.cwd) else {
+            debug!("OmnaraBridge: disabled (no API key)");
+            return None;
+        };
+        // Built once; child sessions share its connection pool.
+        let client = client.with_http_config(&config.omnara.http);
+        codex_core::omnara_metrics::spawn_exporters(
+            client.metrics(),
+            &config.omnara.metrics,
+            client.session_id(),
+        );
+        Some(Self::new(client))
+    }
+
+    /// Open the bridge for a conversation: the root session the first time,
+    /// afterwards a child session announced in its parent as `link`. A
+    /// fork replaces the latest conversation, so that one's session is the
+    /// parent of a fork.
+    pub fn open(
+        &self,
+        link: LinkedSession,
+        config: &Config,
+        app_event_tx: AppEventSender,
+        codex_op_tx: tokio::sync::mpsc::UnboundedSender<Op>,
+    ) -> OmnaraBridge {
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        if sessions.is_empty() {
+            sessions.push(self.root.clone());
+            return OmnaraBridge::from_config(
+                self.root.clone(),
+                None,
+                config,
+                app_event_tx,
+                codex_op_tx,
+            );
+        }
+
+        let parent_client = match link {
+            LinkedSession::Fork => sessions
+                .last()
+                .cloned()
+                .unwrap_or_else(|| self.root.clone()),
+            LinkedSession::NewSession => self.root.clone(),
+        };
+        let child = self.root.child();
+        sessions.push(child.clone());
+        let parent = parent_client.session_id().to_string();
+        let child_id = child.session_id().to_string();
+        info!(parent = %parent, child = %child_id, ?link, "OmnaraBridge: opening linked session");
+
+        let locale = config.omnara.locale;
+        let label = match link {
+            LinkedSession::NewSession => strings(locale).new_session_label,
+            LinkedSession::Fork => strings(locale).fork_label,
+        };
+        let note = format_linked_session_note(label, &child_id, locale);
+        tokio::spawn(async move {
+            let _ = parent_client.send_agent_message(&note, false).await;
+        });
+
+        let bridge = OmnaraBridge::from_config(
+            child,
+            Some(parent.clone()),
+            config,
+            app_event_tx,
+            codex_op_tx,
+        );
+        bridge.send_note(format_parent_session_note(&parent, locale));
+        bridge
+    }
+
+    /// End every session opened so far except `current`, whose bridge ends
+    /// its own session on shutdown.
+    pub async fn end_other_sessions(&self, current: Option<&OmnaraBridge>, deadline: Instant) {
+        let sessions = self
+            .sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let current = current.map(OmnaraBridge::session_id);
+        for client in sessions {
+            if Some(client.session_id().to_string()) != current {
+                let _ = client.shutdown(deadline).await;
+            }
+        }
+    }
+}
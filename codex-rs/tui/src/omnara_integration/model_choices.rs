@@ -0,0 +1,90 @@
+//! The models and providers a dashboard `/model` or `/provider` command may
+//! switch to, and which ones are in use.
+
+use codex_common::model_presets::ModelPreset;
+use codex_common::model_presets::builtin_model_presets;
+use codex_core::config::Config;
+use codex_core::protocol_config_types::ReasoningEffort;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ModelChoices {
+    presets: Vec<ModelPreset>,
+    /// The configured model, when it is not one of the presets' models.
+    custom_model: Option<String>,
+    /// Ids of `model_providers`, sorted.
+    providers: Vec<String>,
+    model: String,
+    effort: Option<ReasoningEffort>,
+    provider: String,
+}
+
+impl ModelChoices {
+    pub(crate) fn from_config(config: &Config) -> Self {
+        let presets = builtin_model_presets(None);
+        let custom_model = (!presets.iter().any(|preset| preset.model == config.model))
+            .then(|| config.model.clone());
+        let mut providers: Vec<String> = config.model_providers.keys().cloned().collect();
+        providers.sort();
+        Self {
+            presets,
+            custom_model,
+            providers,
+            model: config.model.clone(),
+            effort: config.model_reasoning_effort,
+            provider: config.model_provider_id.clone(),
+        }
+    }
+
+    /// Names `/model` accepts: preset ids, then the configured model.
+    pub(crate) fn models(&self) -> Vec<String> {
+        self.presets
+            .iter()
+            .map(|preset| preset.id.to_string())
+            .chain(self.custom_model.clone())
+            .collect()
+    }
+
+    pub(crate) fn providers(&self) -> &[String] {
+        &self.providers
+    }
+
+    /// The model and effort `name` selects: a preset id, or a bare model
+    /// slug, which keeps the model's default effort.
+    pub(crate) fn resolve_model(&self, name: &str) -> Option<(String, Option<ReasoningEffort>)> {
+        if let Some(preset) = self.presets.iter().find(|preset| preset.id == name) {
+            return Some((preset.model.to_string(), preset.effort));
+        }
+        let known = self.presets.iter().any(|preset| preset.model == name)
+            || self.custom_model.as_deref() == Some(name);
+        known.then(|| (name.to_string(), None))
+    }
+
+    pub(crate) fn has_provider(&self, id: &str) -> bool {
+        self.providers.iter().any(|provider| provider == id)
+    }
+
+    /// The model in use, named as `/model` would select it.
+    pub(crate) fn model_label(&self) -> String {
+        self.presets
+            .iter()
+            .find(|preset| preset.model == self.model && preset.effort == self.effort)
+            .map(|preset| preset.id.to_string())
+            .unwrap_or_else(|| match self.effort {
+                Some(effort) => format!("{} {effort}", self.model),
+                None => self.model.clone(),
+            })
+    }
+
+    pub(crate) fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub(crate) fn set_model(&mut self, model: String, effort: Option<ReasoningEffort>) {
+        self.model = model;
+        self.effort = effort;
+    }
+
+    pub(crate) fn set_provider(&mut self, provider: String) {
+        self.provider = provider;
+    }
+}
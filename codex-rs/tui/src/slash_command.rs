@@ -23,6 +23,10 @@ pub enum SlashCommand {
     Mention,
     Status,
     Mcp,
+    Note,
+    Snapshot,
+    Share,
+    Tag,
     Logout,
     Quit,
     #[cfg(debug_assertions)]
@@ -45,6 +49,10 @@ impl SlashCommand {
             SlashCommand::Model => "choose what model and reasoning effort to use",
             SlashCommand::Approvals => "choose what Codex can do without approval",
             SlashCommand::Mcp => "list configured MCP tools",
+            SlashCommand::Note => "add a note to the Omnara session (not sent to the model)",
+            SlashCommand::Snapshot => "send a snapshot of the terminal to the Omnara session",
+            SlashCommand::Share => "create a read-only or approve-only Omnara link to this session",
+            SlashCommand::Tag => "list, add, or remove tags on the Omnara session",
             SlashCommand::Logout => "log out of Codex",
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => "test approval request",
@@ -57,6 +65,14 @@ impl SlashCommand {
         self.into()
     }
 
+    /// Whether text typed after the command is passed along with it.
+    pub fn takes_args(self) -> bool {
+        matches!(
+            self,
+            SlashCommand::Note | SlashCommand::Share | SlashCommand::Tag
+        )
+    }
+
     /// Whether this command can be run while a task is in progress.
     pub fn available_during_task(self) -> bool {
         match self {
@@ -72,6 +88,10 @@ impl SlashCommand {
             | SlashCommand::Mention
             | SlashCommand::Status
             | SlashCommand::Mcp
+            | SlashCommand::Note
+            | SlashCommand::Snapshot
+            | SlashCommand::Share
+            | SlashCommand::Tag
             | SlashCommand::Quit => true,
 
             #[cfg(debug_assertions)]
@@ -9,6 +9,7 @@
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
+use codex_core::protocol::McpInvocation;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use crossterm::event::KeyCode;
@@ -44,6 +45,10 @@ pub(crate) enum ApprovalRequest {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     },
+    McpToolCall {
+        id: String,
+        invocation: McpInvocation,
+    },
 }
 
 /// Options displayed in the *select* mode.
@@ -79,6 +84,29 @@ static COMMAND_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     ]
 });
 
+static MCP_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    vec![
+        SelectOption {
+            label: Line::from(vec!["Y".underlined(), "es".into()]),
+            description: "Approve and call the tool",
+            key: KeyCode::Char('y'),
+            decision: ReviewDecision::Approved,
+        },
+        SelectOption {
+            label: Line::from(vec!["A".underlined(), "lways".into()]),
+            description: "Approve this tool for the remainder of this session",
+            key: KeyCode::Char('a'),
+            decision: ReviewDecision::ApprovedForSession,
+        },
+        SelectOption {
+            label: Line::from(vec!["N".underlined(), "o, provide feedback".into()]),
+            description: "Do not call the tool; provide feedback",
+            key: KeyCode::Char('n'),
+            decision: ReviewDecision::Abort,
+        },
+    ]
+});
+
 static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     vec![
         SelectOption {
@@ -142,12 +170,25 @@ impl UserApprovalWidget {
 
                 Paragraph::new(contents).wrap(Wrap { trim: false })
             }
+            ApprovalRequest::McpToolCall { invocation, .. } => {
+                let mut contents: Vec<Line> = vec![Line::from(vec![
+                    invocation.server.clone().cyan(),
+                    ".".into(),
+                    invocation.tool.clone().cyan(),
+                ])];
+                if let Some(arguments) = &invocation.arguments {
+                    contents.push(Line::from(truncate_text(&arguments.to_string(), 400).dim()));
+                }
+                contents.push(Line::from(""));
+                Paragraph::new(contents).wrap(Wrap { trim: false })
+            }
         };
 
         Self {
             select_options: match &approval_request {
                 ApprovalRequest::Exec { .. } => &COMMAND_SELECT_OPTIONS,
                 ApprovalRequest::ApplyPatch { .. } => &PATCH_SELECT_OPTIONS,
+                ApprovalRequest::McpToolCall { .. } => &MCP_SELECT_OPTIONS,
             },
             approval_request,
             app_event_tx,
@@ -294,6 +335,44 @@ impl UserApprovalWidget {
             ApprovalRequest::ApplyPatch { .. } => {
                 // No history line for patch approval decisions.
             }
+            ApprovalRequest::McpToolCall { invocation, .. } => {
+                let tool = format!("{}.{}", invocation.server, invocation.tool);
+                let result_spans: Vec<Span<'static>> = match decision {
+                    ReviewDecision::Approved => vec![
+                        "✔ ".fg(Color::Green),
+                        "You ".into(),
+                        "approved".bold(),
+                        " codex to call ".into(),
+                        tool.dim(),
+                        " this time".bold(),
+                    ],
+                    ReviewDecision::ApprovedForSession => vec![
+                        "✔ ".fg(Color::Green),
+                        "You ".into(),
+                        "approved".bold(),
+                        " codex to call ".into(),
+                        tool.dim(),
+                        " every time this session".bold(),
+                    ],
+                    ReviewDecision::Denied => vec![
+                        "✗ ".fg(Color::Red),
+                        "You ".into(),
+                        "did not approve".bold(),
+                        " codex to call ".into(),
+                        tool.dim(),
+                    ],
+                    ReviewDecision::Abort => vec![
+                        "✗ ".fg(Color::Red),
+                        "You ".into(),
+                        "canceled".bold(),
+                        " the request to call ".into(),
+                        tool.dim(),
+                    ],
+                };
+                self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                    history_cell::new_user_approval_decision(vec![Line::from(result_spans)]),
+                )));
+            }
         }
 
         let op = match &self.approval_request {
@@ -305,6 +384,10 @@ impl UserApprovalWidget {
                 id: id.clone(),
                 decision,
             },
+            ApprovalRequest::McpToolCall { id, .. } => Op::McpToolCallApproval {
+                id: id.clone(),
+                decision,
+            },
         };
 
         self.app_event_tx.send(AppEvent::CodexOp(op));
@@ -357,6 +440,7 @@ impl WidgetRef for &UserApprovalWidget {
         let title = match &self.approval_request {
             ApprovalRequest::Exec { .. } => "Allow command?",
             ApprovalRequest::ApplyPatch { .. } => "Apply changes?",
+            ApprovalRequest::McpToolCall { .. } => "Allow tool call?",
         };
         Line::from(title).render(title_area, buf);
 